@@ -0,0 +1,78 @@
+//! Packaging, hashing, extraction and history indexing on the fixtures that
+//! `get_perf_report` uses. Run with `cargo bench`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use crosssave_cloud_lib::perf::{
+    create_history, create_save_tree, extract_tree, hash_archive, index_history, package_tree,
+    FixtureSpec,
+};
+
+struct Fixture {
+    root: PathBuf,
+    tree: PathBuf,
+    archive: PathBuf,
+    history: PathBuf,
+}
+
+impl Fixture {
+    fn create() -> Self {
+        let spec = FixtureSpec::default();
+        let root = std::env::temp_dir().join(format!("crosssave-bench-{}", std::process::id()));
+        let tree = root.join("tree");
+        let history = root.join("history");
+        create_save_tree(&tree, &spec).expect("create save tree");
+        let packaged = package_tree(&tree, "bench-fixture").expect("package fixture");
+        create_history(&history, &packaged, &spec).expect("create history");
+        Self {
+            root,
+            tree,
+            archive: packaged.archive_path,
+            history,
+        }
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+        let _ = fs::remove_file(&self.archive);
+    }
+}
+
+fn remove_staged(archive: &Path) {
+    let _ = fs::remove_file(archive);
+}
+
+fn benches(c: &mut Criterion) {
+    let fixture = Fixture::create();
+    let mut group = c.benchmark_group("sync_paths");
+    group.sample_size(20);
+
+    group.bench_function("packaging", |b| {
+        b.iter(|| {
+            let packaged = package_tree(&fixture.tree, "bench-packaging").expect("package");
+            remove_staged(&packaged.archive_path);
+        })
+    });
+    group.bench_function("hashing", |b| {
+        b.iter(|| hash_archive(&fixture.archive).expect("hash"))
+    });
+    group.bench_function("extraction", |b| {
+        let dest = fixture.root.join("extract");
+        b.iter(|| {
+            extract_tree(&fixture.archive, &dest).expect("extract");
+            let _ = fs::remove_dir_all(&dest);
+        })
+    });
+    group.bench_function("history_indexing", |b| {
+        b.iter(|| index_history(&fixture.history).expect("index"))
+    });
+
+    group.finish();
+}
+
+criterion_group!(sync_paths, benches);
+criterion_main!(sync_paths);
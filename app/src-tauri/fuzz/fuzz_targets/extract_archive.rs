@@ -0,0 +1,36 @@
+//! Downloaded archives go through `extract_with_limits` before any save is
+//! restored, so arbitrary bytes must come back as an `ExtractError`, never a
+//! panic or a file outside the destination. Run with
+//! `cargo fuzz run extract_archive` from `app/src-tauri`.
+
+#![no_main]
+
+use std::fs;
+
+use crosssave_cloud_lib::extract::{extract_with_limits, ExtractLimits};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let root = std::env::temp_dir().join(format!("crosssave-fuzz-{}", std::process::id()));
+    let archive = root.join("archive.zip");
+    let dest = root.join("out");
+    if fs::create_dir_all(&root).is_err() || fs::write(&archive, data).is_err() {
+        return;
+    }
+
+    // Small limits keep each run fast; the checks are the same at any size
+    let limits = ExtractLimits {
+        max_entries: 256,
+        max_total_bytes: 4 * 1024 * 1024,
+    };
+    if let Ok(files) = extract_with_limits(&archive, &dest, limits) {
+        for file in files {
+            let path = dest.join(&file);
+            assert!(
+                path.starts_with(&dest) && !file.split('/').any(|part| part == ".."),
+                "{file} escaped the destination"
+            );
+        }
+    }
+    let _ = fs::remove_dir_all(&dest);
+});
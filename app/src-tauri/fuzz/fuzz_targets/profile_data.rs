@@ -0,0 +1,15 @@
+//! Imported profiles arrive as JSON or `.crossprofile` bundles from URLs and
+//! shared files. Decoding and validation must reject bad input with a
+//! `ProfileError`. Run with `cargo fuzz run profile_data` from `app/src-tauri`.
+
+#![no_main]
+
+use crosssave_cloud_lib::profile_bundle::decode_profile_data;
+use crosssave_cloud_lib::ProfileManager;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(bundle) = decode_profile_data(data) {
+        let _ = ProfileManager::validate_profile(&bundle.profile);
+    }
+});
@@ -0,0 +1,19 @@
+//! `SaveMetadata` is read from history indexes and cloud listings written by
+//! other devices. Anything that parses must also serialize back to the same
+//! value. Run with `cargo fuzz run save_metadata` from `app/src-tauri`.
+
+#![no_main]
+
+use crosssave_cloud_lib::SaveMetadata;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(metadata) = serde_json::from_slice::<SaveMetadata>(data) else {
+        return;
+    };
+    let json = serde_json::to_vec(&metadata).expect("serialize parsed metadata");
+    let reparsed: SaveMetadata = serde_json::from_slice(&json).expect("reparse metadata");
+    assert_eq!(reparsed.version_id, metadata.version_id);
+    assert_eq!(reparsed.file_list, metadata.file_list);
+    assert_eq!(reparsed.parent_version_id, metadata.parent_version_id);
+});
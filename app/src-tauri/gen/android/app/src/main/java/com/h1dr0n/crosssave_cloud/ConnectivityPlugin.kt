@@ -0,0 +1,36 @@
+package com.h1dr0n.crosssave_cloud
+
+import android.app.Activity
+import android.content.Context
+import android.content.Intent
+import android.content.IntentFilter
+import android.net.ConnectivityManager
+import android.os.BatteryManager
+import android.os.PowerManager
+import app.tauri.annotation.Command
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+
+// Network and power state that decides whether sync may run. Called from
+// src/core/connectivity.rs.
+@TauriPlugin
+class ConnectivityPlugin(private val activity: Activity) : Plugin(activity) {
+  @Command
+  fun conditions(invoke: Invoke) {
+    val connectivity =
+      activity.getSystemService(Context.CONNECTIVITY_SERVICE) as ConnectivityManager
+    val power = activity.getSystemService(Context.POWER_SERVICE) as PowerManager
+    // Sticky broadcast, so no receiver has to be kept around
+    val battery = activity.registerReceiver(null, IntentFilter(Intent.ACTION_BATTERY_CHANGED))
+    val plugged = battery?.getIntExtra(BatteryManager.EXTRA_PLUGGED, 0) ?: 0
+
+    val response = JSObject()
+    response.put("metered", connectivity.isActiveNetworkMetered)
+    response.put("charging", plugged != 0)
+    response.put("battery_saver", power.isPowerSaveMode)
+    response.put("idle", power.isDeviceIdleMode)
+    invoke.resolve(response)
+  }
+}
@@ -0,0 +1,26 @@
+package com.h1dr0n.crosssave_cloud
+
+import android.annotation.SuppressLint
+import android.app.Activity
+import android.provider.Settings
+import app.tauri.annotation.Command
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+
+// ANDROID_ID, which stays the same for this app across reinstalls and
+// clearing its data. Called from src/core/device_id.rs.
+@TauriPlugin
+class DeviceIdPlugin(private val activity: Activity) : Plugin(activity) {
+  @SuppressLint("HardwareIds")
+  @Command
+  fun androidId(invoke: Invoke) {
+    val response = JSObject()
+    val id = Settings.Secure.getString(activity.contentResolver, Settings.Secure.ANDROID_ID)
+    if (id != null) {
+      response.put("id", id)
+    }
+    invoke.resolve(response)
+  }
+}
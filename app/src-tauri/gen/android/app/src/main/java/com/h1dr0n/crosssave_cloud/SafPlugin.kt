@@ -0,0 +1,200 @@
+package com.h1dr0n.crosssave_cloud
+
+import android.app.Activity
+import android.content.Intent
+import android.net.Uri
+import android.provider.DocumentsContract
+import android.provider.DocumentsContract.Document
+import androidx.activity.result.ActivityResult
+import app.tauri.annotation.ActivityCallback
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSArray
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.io.File
+import kotlin.concurrent.thread
+
+@InvokeArg
+class TreeArgs {
+  lateinit var uri: String
+}
+
+@InvokeArg
+class FileArgs {
+  lateinit var uri: String
+  lateinit var relativePath: String
+  lateinit var localPath: String
+}
+
+// Storage Access Framework access for save folders granted through the
+// system picker. Called from src/core/saf.rs.
+@TauriPlugin
+class SafPlugin(private val activity: Activity) : Plugin(activity) {
+  private val resolver get() = activity.contentResolver
+
+  @Command
+  fun pickFolder(invoke: Invoke) {
+    val intent = Intent(Intent.ACTION_OPEN_DOCUMENT_TREE).addFlags(
+      Intent.FLAG_GRANT_READ_URI_PERMISSION or
+        Intent.FLAG_GRANT_WRITE_URI_PERMISSION or
+        Intent.FLAG_GRANT_PERSISTABLE_URI_PERMISSION
+    )
+    startActivityForResult(invoke, intent, "folderPicked")
+  }
+
+  @ActivityCallback
+  private fun folderPicked(invoke: Invoke, result: ActivityResult) {
+    val response = JSObject()
+    val uri = result.data?.data
+    if (result.resultCode == Activity.RESULT_OK && uri != null) {
+      // Keep access after the app restarts
+      resolver.takePersistableUriPermission(
+        uri,
+        Intent.FLAG_GRANT_READ_URI_PERMISSION or Intent.FLAG_GRANT_WRITE_URI_PERMISSION
+      )
+      response.put("uri", uri.toString())
+    }
+    invoke.resolve(response)
+  }
+
+  @Command
+  fun listFiles(invoke: Invoke) {
+    val args = invoke.parseArgs(TreeArgs::class.java)
+    background(invoke) {
+      val tree = Uri.parse(args.uri)
+      val files = JSArray()
+      collect(tree, DocumentsContract.getTreeDocumentId(tree), "", files)
+      JSObject().put("files", files)
+    }
+  }
+
+  @Command
+  fun readFile(invoke: Invoke) {
+    val args = invoke.parseArgs(FileArgs::class.java)
+    background(invoke) {
+      val tree = Uri.parse(args.uri)
+      val documentId = find(tree, args.relativePath.split('/'))
+        ?: throw IllegalArgumentException("${args.relativePath} not found")
+      val document = DocumentsContract.buildDocumentUriUsingTree(tree, documentId)
+      val input = resolver.openInputStream(document)
+        ?: throw IllegalStateException("cannot open ${args.relativePath}")
+      input.use { source ->
+        File(args.localPath).outputStream().use { source.copyTo(it) }
+      }
+      JSObject()
+    }
+  }
+
+  @Command
+  fun writeFile(invoke: Invoke) {
+    val args = invoke.parseArgs(FileArgs::class.java)
+    background(invoke) {
+      val tree = Uri.parse(args.uri)
+      val segments = args.relativePath.split('/').filter { it.isNotEmpty() }
+      var parentId = DocumentsContract.getTreeDocumentId(tree)
+      for (folder in segments.dropLast(1)) {
+        parentId = child(tree, parentId, folder)?.id
+          ?: DocumentsContract.getDocumentId(
+            DocumentsContract.createDocument(
+              resolver,
+              DocumentsContract.buildDocumentUriUsingTree(tree, parentId),
+              Document.MIME_TYPE_DIR,
+              folder
+            ) ?: throw IllegalStateException("cannot create $folder")
+          )
+      }
+
+      val name = segments.last()
+      val existing = child(tree, parentId, name)?.id
+      val document = if (existing != null) {
+        DocumentsContract.buildDocumentUriUsingTree(tree, existing)
+      } else {
+        DocumentsContract.createDocument(
+          resolver,
+          DocumentsContract.buildDocumentUriUsingTree(tree, parentId),
+          "application/octet-stream",
+          name
+        ) ?: throw IllegalStateException("cannot create $name")
+      }
+      val output = resolver.openOutputStream(document, "wt")
+        ?: throw IllegalStateException("cannot write ${args.relativePath}")
+      output.use { target ->
+        File(args.localPath).inputStream().use { it.copyTo(target) }
+      }
+      JSObject()
+    }
+  }
+
+  private fun background(invoke: Invoke, work: () -> JSObject) {
+    thread {
+      try {
+        invoke.resolve(work())
+      } catch (error: Exception) {
+        invoke.reject(error.message ?: error.toString())
+      }
+    }
+  }
+
+  private fun collect(tree: Uri, parentId: String, prefix: String, files: JSArray) {
+    for (entry in children(tree, parentId)) {
+      val path = prefix + entry.name
+      if (entry.mimeType == Document.MIME_TYPE_DIR) {
+        collect(tree, entry.id, "$path/", files)
+      } else {
+        val file = JSObject()
+        file.put("relative_path", path)
+        file.put("size", entry.size)
+        file.put("modified_ms", entry.modified)
+        files.put(file)
+      }
+    }
+  }
+
+  private fun find(tree: Uri, segments: List<String>): String? {
+    var id = DocumentsContract.getTreeDocumentId(tree)
+    for (segment in segments.filter { it.isNotEmpty() }) {
+      id = child(tree, id, segment)?.id ?: return null
+    }
+    return id
+  }
+
+  private fun child(tree: Uri, parentId: String, name: String): Entry? =
+    children(tree, parentId).firstOrNull { it.name == name }
+
+  private data class Entry(
+    val id: String,
+    val name: String,
+    val mimeType: String,
+    val size: Long,
+    val modified: Long
+  )
+
+  private fun children(tree: Uri, parentId: String): List<Entry> {
+    val uri = DocumentsContract.buildChildDocumentsUriUsingTree(tree, parentId)
+    val columns = arrayOf(
+      Document.COLUMN_DOCUMENT_ID,
+      Document.COLUMN_DISPLAY_NAME,
+      Document.COLUMN_MIME_TYPE,
+      Document.COLUMN_SIZE,
+      Document.COLUMN_LAST_MODIFIED
+    )
+    val entries = mutableListOf<Entry>()
+    resolver.query(uri, columns, null, null, null)?.use { cursor ->
+      while (cursor.moveToNext()) {
+        entries.add(
+          Entry(
+            id = cursor.getString(0),
+            name = cursor.getString(1),
+            mimeType = cursor.getString(2) ?: "",
+            size = if (cursor.isNull(3)) 0 else cursor.getLong(3),
+            modified = if (cursor.isNull(4)) 0 else cursor.getLong(4)
+          )
+        )
+      }
+    }
+    return entries
+  }
+}
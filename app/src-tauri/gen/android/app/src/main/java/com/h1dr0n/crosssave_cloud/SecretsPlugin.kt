@@ -0,0 +1,93 @@
+package com.h1dr0n.crosssave_cloud
+
+import android.app.Activity
+import android.content.Context
+import android.security.keystore.KeyGenParameterSpec
+import android.security.keystore.KeyProperties
+import android.util.Base64
+import app.tauri.annotation.Command
+import app.tauri.annotation.InvokeArg
+import app.tauri.annotation.TauriPlugin
+import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSObject
+import app.tauri.plugin.Plugin
+import java.security.KeyStore
+import javax.crypto.Cipher
+import javax.crypto.KeyGenerator
+import javax.crypto.SecretKey
+import javax.crypto.spec.GCMParameterSpec
+
+@InvokeArg
+class SecretArgs {
+  lateinit var name: String
+  var secret: String? = null
+}
+
+// Tokens and access keys, AES-GCM encrypted with a key that never leaves the
+// Android keystore and kept in private preferences. Called from
+// src/core/secrets.rs.
+@TauriPlugin
+class SecretsPlugin(private val activity: Activity) : Plugin(activity) {
+  private val preferences get() =
+    activity.getSharedPreferences("crosssave_secrets", Context.MODE_PRIVATE)
+
+  @Command
+  fun get(invoke: Invoke) {
+    val args = invoke.parseArgs(SecretArgs::class.java)
+    val response = JSObject()
+    val stored = preferences.getString(args.name, null)
+    if (stored != null) {
+      val bytes = Base64.decode(stored, Base64.NO_WRAP)
+      val cipher = Cipher.getInstance(TRANSFORMATION)
+      cipher.init(Cipher.DECRYPT_MODE, key(), GCMParameterSpec(TAG_BITS, bytes, 0, IV_BYTES))
+      val secret = cipher.doFinal(bytes, IV_BYTES, bytes.size - IV_BYTES)
+      response.put("secret", String(secret, Charsets.UTF_8))
+    }
+    invoke.resolve(response)
+  }
+
+  @Command
+  fun set(invoke: Invoke) {
+    val args = invoke.parseArgs(SecretArgs::class.java)
+    val secret = args.secret ?: return invoke.reject("secret is required")
+    val cipher = Cipher.getInstance(TRANSFORMATION)
+    cipher.init(Cipher.ENCRYPT_MODE, key())
+    val encrypted = cipher.iv + cipher.doFinal(secret.toByteArray(Charsets.UTF_8))
+    preferences.edit()
+      .putString(args.name, Base64.encodeToString(encrypted, Base64.NO_WRAP))
+      .apply()
+    invoke.resolve()
+  }
+
+  @Command
+  fun delete(invoke: Invoke) {
+    val args = invoke.parseArgs(SecretArgs::class.java)
+    preferences.edit().remove(args.name).apply()
+    invoke.resolve()
+  }
+
+  private fun key(): SecretKey {
+    val keyStore = KeyStore.getInstance(KEYSTORE).apply { load(null) }
+    (keyStore.getKey(KEY_ALIAS, null) as? SecretKey)?.let { return it }
+
+    val generator = KeyGenerator.getInstance(KeyProperties.KEY_ALGORITHM_AES, KEYSTORE)
+    generator.init(
+      KeyGenParameterSpec.Builder(
+        KEY_ALIAS,
+        KeyProperties.PURPOSE_ENCRYPT or KeyProperties.PURPOSE_DECRYPT
+      )
+        .setBlockModes(KeyProperties.BLOCK_MODE_GCM)
+        .setEncryptionPaddings(KeyProperties.ENCRYPTION_PADDING_NONE)
+        .build()
+    )
+    return generator.generateKey()
+  }
+
+  companion object {
+    private const val KEYSTORE = "AndroidKeyStore"
+    private const val KEY_ALIAS = "crosssave_secrets"
+    private const val TRANSFORMATION = "AES/GCM/NoPadding"
+    private const val IV_BYTES = 12
+    private const val TAG_BITS = 128
+  }
+}
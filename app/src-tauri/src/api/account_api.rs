@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use tauri::State;
+use tracing::error;
+
+use crate::core::account::{
+    apply_account_choice, pending_account_switch, AccountHistoryChoice, AccountSwitch,
+};
+use crate::core::history::HistoryManager;
+use crate::core::settings::SettingsManager;
+use crate::core::sync::SyncManager;
+
+/// The account switch holding sync, if the local history belongs to an
+/// account other than the signed-in one
+#[tauri::command]
+pub async fn get_account_switch(
+    settings: State<'_, Arc<SettingsManager>>,
+    history: State<'_, Arc<HistoryManager>>,
+) -> Result<Option<AccountSwitch>, String> {
+    pending_account_switch(&settings, &history).map_err(|err| err.to_string())
+}
+
+/// Keep the local history apart for the previous account, or hand it to
+/// the new one, then let sync continue
+#[tauri::command]
+pub async fn resolve_account_switch(
+    settings: State<'_, Arc<SettingsManager>>,
+    history: State<'_, Arc<HistoryManager>>,
+    sync: State<'_, SyncManager>,
+    choice: AccountHistoryChoice,
+) -> Result<(), String> {
+    let settings_for_task = settings.inner().clone();
+    let history_for_task = history.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        apply_account_choice(&settings_for_task, &history_for_task, choice)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| {
+        error!("[HISTORY] Failed to apply account switch: {err}");
+        err.to_string()
+    })?;
+
+    if choice == AccountHistoryChoice::Separate {
+        // Queued jobs came from the history that was just set aside
+        sync.queue.clear().await;
+        sync.downloads.clear().await;
+    }
+    sync.queue.signal_online();
+    sync.downloads.signal_online();
+    sync.trigger_sync();
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::sync::Mutex;
@@ -8,14 +8,22 @@ use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tracing::{error, info};
 
+use crate::core::account::{pending_account_switch, AccountSwitch};
 use crate::core::cloud::{
-    ensure_device_identity, log_tag, CloudBackend, CloudDevice, CloudError, CloudVersionSummary,
-    UploadRequest, UploadUrlResponse,
+    ensure_device_identity, log_tag, AccountActivity, CloudBackend, CloudDevice, CloudError,
+    CloudSession, CloudVersionSummary, DeviceMerge, GameShares, ShareAccess,
+    UploadRequest, UploadUrlResponse, MAX_CLOUD_FILE_BYTES,
 };
+use crate::core::conflict::ConflictSide;
+use crate::core::extract::read_entry;
 use crate::core::history::HistoryManager;
+use crate::core::merge::{merge_conflict, MergeOutcome, MergeRegistry};
+use crate::core::packager::decode_download;
 use crate::core::profile::ProfileManager;
-use crate::core::settings::{CloudMode, CloudSettings, SelfHostSettings, SettingsManager};
-use crate::core::sync::{perform_download, SyncManager};
+use crate::core::settings::{
+    CloudMode, CloudSettings, OfficialEndpoint, SelfHostSettings, SettingsManager,
+};
+use crate::core::sync::{perform_download, record_conflict_resolution, SyncManager};
 use crate::switch_cloud_backend;
 
 #[derive(Clone, Debug, Serialize)]
@@ -31,6 +39,22 @@ pub struct LoginResult {
     pub device_id: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_id: Option<String>,
+    /// Set when the local history was synced with another account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_switch: Option<AccountSwitch>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CloudEndpointSummary {
+    pub name: String,
+    pub base_url: String,
+    pub signed_in: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CloudEndpoints {
+    pub active: String,
+    pub endpoints: Vec<CloudEndpointSummary>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -121,6 +145,7 @@ pub async fn login_cloud(
     email: String,
     password: String,
     cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    history: State<'_, Arc<HistoryManager>>,
     settings: State<'_, Arc<SettingsManager>>,
 ) -> Result<LoginResult, String> {
     ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
@@ -152,6 +177,7 @@ pub async fn login_cloud(
         token,
         device_id,
         user_id,
+        account_switch: check_account_switch(&app, &settings, &history),
     })
 }
 
@@ -164,6 +190,7 @@ pub async fn signup_cloud(
     platform: String,
     device_name: String,
     cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    history: State<'_, Arc<HistoryManager>>,
     settings: State<'_, Arc<SettingsManager>>,
 ) -> Result<LoginResult, String> {
     ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
@@ -224,9 +251,69 @@ pub async fn signup_cloud(
         token,
         device_id: settings_snapshot.cloud.device_id,
         user_id,
+        account_switch: check_account_switch(&app, &settings, &history),
+    })
+}
+
+/// Sign in with a token the server handed out directly, such as the one in a
+/// `crosssave://login-callback` link, instead of an email and password
+pub(crate) async fn login_with_token(
+    app: &AppHandle,
+    token: String,
+    user_id: Option<String>,
+) -> Result<LoginResult, String> {
+    let cloud = app.state::<Arc<Mutex<Box<dyn CloudBackend + Send>>>>();
+    let settings = app.state::<Arc<SettingsManager>>();
+    let history = app.state::<Arc<HistoryManager>>();
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let mut app_settings = settings
+        .get_settings()
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+    app_settings.cloud.enabled = true;
+    app_settings.cloud.api_key = token.clone();
+    // Without a user id the account-switch check has nothing to compare
+    app_settings.cloud.user_id = user_id.unwrap_or_default();
+    settings
+        .update_settings(app_settings)
+        .map_err(|e| format!("Failed to persist settings: {e}"))?;
+
+    let device_id = register_device_with_retry(app, &cloud, &settings, token.clone()).await?;
+
+    let user_id = settings
+        .get_settings()
+        .map(|s| Some(s.cloud.user_id).filter(|id| !id.is_empty()))
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+
+    Ok(LoginResult {
+        token,
+        device_id,
+        user_id,
+        account_switch: check_account_switch(app, &settings, &history),
     })
 }
 
+/// Announce a login on top of another account's history. Sync holds
+/// until the user answers with `resolve_account_switch`.
+fn check_account_switch(
+    app: &AppHandle,
+    settings: &SettingsManager,
+    history: &HistoryManager,
+) -> Option<AccountSwitch> {
+    match pending_account_switch(settings, history) {
+        Ok(Some(switch)) => {
+            info!("[CLOUD] Signed in with a different account than the local history's");
+            let _ = app.emit("cloud://account-changed", switch.clone());
+            Some(switch)
+        }
+        Ok(None) => None,
+        Err(err) => {
+            error!("[CLOUD] Failed to check history owner: {err}");
+            None
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn logout_cloud(settings: State<'_, Arc<SettingsManager>>) -> Result<(), String> {
     ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
@@ -236,6 +323,9 @@ pub async fn logout_cloud(settings: State<'_, Arc<SettingsManager>>) -> Result<(
         .map_err(|e| format!("Failed to load settings: {e}"))?;
     app_settings.cloud.enabled = false;
     app_settings.cloud.api_key.clear();
+    // The next login may be a different account
+    app_settings.cloud.user_id.clear();
+    app_settings.clear_device_registration();
     settings
         .update_settings(app_settings)
         .map_err(|e| format!("Failed to persist settings: {e}"))?;
@@ -259,12 +349,13 @@ pub async fn upload_cloud_save(
     settings: State<'_, Arc<SettingsManager>>,
 ) -> Result<CloudVersionSummary, String> {
     ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+    ensure_history_owned_by_account(&settings)?;
 
     let history_entry = history
         .get_history_item(game_id, local_version_id)
         .map_err(|e| format!("Failed to find local version: {e}"))?;
 
-    let archive_path_buf = PathBuf::from(&history_entry.archive_path);
+    let archive_path_buf = history_entry.archive_path.clone();
     let metadata = history_entry.metadata;
 
     let backend = cloud.lock().await;
@@ -295,6 +386,13 @@ pub async fn get_upload_url(
         device_id: Some(device_id),
         emulator_id: None,
         worker_token: None,
+        thumbnail_sha256: None,
+        note: None,
+        tags: Vec::new(),
+        pinned: false,
+        parent_version_id: None,
+        encrypted: false,
+        encoding: None,
     };
 
     backend
@@ -325,6 +423,13 @@ pub async fn notify_upload(
         device_id: Some(device_id),
         emulator_id: None,
         worker_token,
+        thumbnail_sha256: None,
+        note: None,
+        tags: Vec::new(),
+        pinned: false,
+        parent_version_id: None,
+        encrypted: false,
+        encoding: None,
     };
 
     backend
@@ -421,6 +526,173 @@ pub async fn remove_cloud_device(
     }
 }
 
+/// Fold duplicate entries of the same machine into one, keeping this
+/// device. Entries matched only by platform and name come back as
+/// suggestions and are merged once the user passes their ids in `confirm`.
+/// With `dry_run` the list is left alone and only what would be merged is
+/// returned.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn merge_duplicate_devices(
+    app: AppHandle,
+    dry_run: Option<bool>,
+    confirm: Option<Vec<String>>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<DeviceMerge, String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let dry_run = dry_run.unwrap_or(false);
+    let keep = settings
+        .get_settings()
+        .map(|app_settings| app_settings.cloud.device_id)
+        .ok()
+        .filter(|id| !id.trim().is_empty());
+
+    let backend = cloud.lock().await;
+    let merge = backend
+        .merge_devices(keep, confirm.unwrap_or_default(), dry_run)
+        .await
+        .map_err(cloud_error_to_string)?;
+    if !dry_run {
+        let _ = app.emit("cloud://device-updated", merge.devices.clone());
+    }
+    Ok(merge)
+}
+
+/// Recent sign-ins, device changes and version uploads and deletes on the
+/// account, newest first
+#[tauri::command]
+pub async fn get_account_activity(
+    limit: Option<usize>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Vec<AccountActivity>, String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .list_account_activity(limit)
+        .await
+        .map_err(cloud_error_to_string)
+}
+
+/// Logins on the account that can still use the cloud, newest first, with
+/// the one this app uses marked `current`
+#[tauri::command]
+pub async fn list_cloud_sessions(
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Vec<CloudSession>, String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend.list_sessions().await.map_err(cloud_error_to_string)
+}
+
+/// Signs out one session, or every other session when `session_id` is
+/// omitted. Returns how many were signed out.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn revoke_cloud_session(
+    session_id: Option<String>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<usize, String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .revoke_sessions(session_id)
+        .await
+        .map_err(cloud_error_to_string)
+}
+
+/// Shares a game's cloud saves with the account registered to `email`.
+/// With `write` access that account can also upload new versions.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn share_game(
+    game_id: String,
+    email: String,
+    access: ShareAccess,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .share_game(game_id, email, access)
+        .await
+        .map_err(cloud_error_to_string)
+}
+
+/// Games this account shares and games shared with it
+#[tauri::command]
+pub async fn list_shared_games(
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<GameShares, String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .list_shared_games()
+        .await
+        .map_err(cloud_error_to_string)
+}
+
+/// Stops sharing a game with `grantee_user_id`, or leaves a game shared with
+/// this account when it's omitted. Local history of the game is kept.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unshare_game(
+    game_id: String,
+    grantee_user_id: Option<String>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .unshare_game(game_id, grantee_user_id)
+        .await
+        .map_err(cloud_error_to_string)
+}
+
+/// Emails a password reset code for a locked-out account. Works without
+/// being logged in.
+#[tauri::command]
+pub async fn request_password_reset(
+    email: String,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .request_password_reset(email)
+        .await
+        .map_err(cloud_error_to_string)
+}
+
+/// Sets a new password with the code from the reset email. The user logs in
+/// with it afterwards as usual.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn complete_password_reset(
+    token: String,
+    new_password: String,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let backend = cloud.lock().await;
+    backend
+        .complete_password_reset(token, new_password)
+        .await
+        .map_err(cloud_error_to_string)
+}
+
 /// Lists available save versions for a game from the cloud using metadata.
 ///
 /// Returns a list of `CloudVersionSummary` objects sorted by timestamp.
@@ -448,10 +720,11 @@ pub async fn list_cloud_versions(
             settings_snapshot.self_host.api_server.clone(),
             settings_snapshot.self_host.access_key.clone(),
         ),
-        CloudMode::Off => (String::new(), String::new()),
+        CloudMode::Off | CloudMode::Demo => (String::new(), String::new()),
     };
 
-    if base_url.trim().is_empty() || token.trim().is_empty() {
+    let demo = settings_snapshot.cloud_mode == CloudMode::Demo;
+    if !demo && (base_url.trim().is_empty() || token.trim().is_empty()) {
         return Err("cloud_not_configured".into());
     }
 
@@ -580,6 +853,170 @@ pub async fn download_cloud_version(
     Ok(target_path.to_string_lossy().to_string())
 }
 
+/// Downloads a single file of a cloud version without restoring anything.
+///
+/// The file is saved to
+/// `AppData/data/cloud_downloads/files/{game_id}_{version_id}/{path}`.
+/// Returns the absolute path to the downloaded file. Backends without
+/// per-file downloads fall back to fetching the whole archive.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn download_cloud_file(
+    game_id: String,
+    version_id: String,
+    path: String,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    ensure_cloud_mode_enabled(&settings).map_err(cloud_error_to_string)?;
+
+    let token = ensure_api_key(&settings)?;
+    if token.trim().is_empty() {
+        return Err("Not logged in".into());
+    }
+
+    // Ids and the path all end up in the local file name
+    let game_id = game_id.trim().to_string();
+    let version_id = version_id.trim().to_string();
+    if [&game_id, &version_id]
+        .iter()
+        .any(|id| id.is_empty() || id.contains(['/', '\\']) || id.contains(".."))
+    {
+        return Err("Invalid game or version id".into());
+    }
+    let relative = archive_file_path(&path)?;
+
+    ensure_config(&cloud, Some(&app)).await?;
+
+    let downloads_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {e}"))?
+        .join("data")
+        .join("cloud_downloads");
+
+    let result = cloud
+        .lock()
+        .await
+        .download_file(game_id.clone(), version_id.clone(), path.clone())
+        .await;
+    let data = match result {
+        Ok(data) => data,
+        Err(CloudError::NotFound(reason)) => {
+            info!("[CLOUD] No per-file download of {path} ({reason}); fetching the archive");
+            let archive_path = downloads_dir.join(format!("{game_id}_{version_id}.zip"));
+            fetch_version_archive(&cloud, &game_id, &version_id, &archive_path).await?;
+            let entry_path = path.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let file = std::fs::File::open(&archive_path)
+                    .map_err(|e| format!("Failed to open archive: {e}"))?;
+                read_entry(file, &entry_path, MAX_CLOUD_FILE_BYTES).map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())??
+        }
+        Err(err) => {
+            error!("[CLOUD] Failed to download {path} of {game_id}/{version_id}: {err}");
+            return Err(cloud_error_to_string(err));
+        }
+    };
+
+    let target_path = downloads_dir
+        .join("files")
+        .join(format!("{game_id}_{version_id}"))
+        .join(relative);
+    if let Some(parent) = target_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to prepare downloads dir: {e}"))?;
+    }
+    tokio::fs::write(&target_path, &data)
+        .await
+        .map_err(|e| format!("Failed to write {path}: {e}"))?;
+
+    info!(
+        "[CLOUD] Downloaded {path} ({} bytes) from {game_id}/{version_id}",
+        data.len()
+    );
+    Ok(target_path.to_string_lossy().to_string())
+}
+
+/// A path from a version's `file_list`, checked to stay inside the
+/// downloads folder
+fn archive_file_path(path: &str) -> Result<PathBuf, String> {
+    let invalid = || format!("Invalid file path {path}");
+    if path.is_empty() || path.starts_with('/') || path.contains(['\\', ':', '\0']) {
+        return Err(invalid());
+    }
+    let mut relative = PathBuf::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return Err(invalid()),
+            _ => relative.push(segment),
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err(invalid());
+    }
+    Ok(relative)
+}
+
+/// Put the whole archive of a version at `archive_path`, unless an earlier
+/// download already did
+async fn fetch_version_archive(
+    cloud: &State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    game_id: &str,
+    version_id: &str,
+    archive_path: &Path,
+) -> Result<(), String> {
+    if archive_path.is_file() {
+        return Ok(());
+    }
+    if let Some(parent) = archive_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to prepare downloads dir: {e}"))?;
+    }
+
+    let backend = cloud.lock().await;
+    if backend.holds_archives() {
+        return backend
+            .download_version(
+                game_id.to_string(),
+                version_id.to_string(),
+                archive_path.to_path_buf(),
+            )
+            .await
+            .map_err(cloud_error_to_string);
+    }
+    let download = backend
+        .request_download_url(game_id.to_string(), version_id.to_string())
+        .await
+        .map_err(cloud_error_to_string)?;
+    drop(backend);
+
+    let response = Client::new()
+        .get(&download.download_url)
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed: {}", response.status()));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Download failed: {e}"))?;
+    let bytes = tauri::async_runtime::spawn_blocking(move || decode_download(bytes.to_vec()))
+        .await
+        .map_err(|e| format!("Failed to decode archive: {e}"))?
+        .map_err(|e| format!("Failed to decode archive: {e}"))?;
+    tokio::fs::write(archive_path, &bytes)
+        .await
+        .map_err(|e| format!("Failed to write archive: {e}"))
+}
+
 /// Retrieves the current cloud configuration settings.
 #[tauri::command]
 pub async fn get_cloud_config(
@@ -606,13 +1043,162 @@ pub async fn update_cloud_config(
     let mut app_settings = settings
         .get_settings()
         .map_err(|e| format!("Failed to load settings: {e}"))?;
+    // Ownership, reconciliation and endpoints only change through their own commands
+    let history_owner = app_settings.cloud.history_owner.take();
+    let reconciled_for = app_settings.cloud.reconciled_for.take();
+    let endpoints = std::mem::take(&mut app_settings.cloud.endpoints);
+    let active_endpoint = std::mem::take(&mut app_settings.cloud.active_endpoint);
     app_settings.cloud = new_config;
+    app_settings.cloud.history_owner = history_owner;
+    app_settings.cloud.reconciled_for = reconciled_for;
+    app_settings.cloud.endpoints = endpoints;
+    app_settings.cloud.active_endpoint = active_endpoint;
     settings
         .update_settings(app_settings)
         .map(|s| s.cloud)
         .map_err(|e| format!("Failed to save settings: {e}"))
 }
 
+/// Official endpoints without their tokens
+#[tauri::command]
+pub async fn list_cloud_endpoints(
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<CloudEndpoints, String> {
+    let cloud = settings
+        .get_settings()
+        .map(|s| s.cloud)
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+    Ok(endpoint_list(&cloud))
+}
+
+/// Add an official endpoint, or point an existing one at another URL.
+/// A moved endpoint's session belonged to the old server and is dropped.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn save_cloud_endpoint(
+    name: String,
+    base_url: String,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<CloudEndpoints, String> {
+    let name = name.trim().to_string();
+    let base_url = base_url.trim().trim_end_matches('/').to_string();
+    let mut app_settings = settings
+        .get_settings()
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+
+    let cloud = &mut app_settings.cloud;
+    let fresh = OfficialEndpoint::new(&name, &base_url);
+    match cloud.endpoints.iter_mut().find(|e| e.name == name) {
+        Some(endpoint) if endpoint.base_url != base_url => *endpoint = fresh,
+        Some(_) => {}
+        None => cloud.endpoints.push(fresh),
+    }
+    let moved_active = cloud.active_endpoint == name && cloud.base_url != base_url;
+    if moved_active {
+        cloud.base_url = base_url;
+        cloud.api_key.clear();
+        cloud.user_id.clear();
+        app_settings.clear_device_registration();
+    }
+
+    info!("[CLOUD] Saved official endpoint {name}");
+    settings
+        .update_settings(app_settings)
+        .map(|s| endpoint_list(&s.cloud))
+        .map_err(|e| format!("Failed to save settings: {e}"))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_cloud_endpoint(
+    name: String,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<CloudEndpoints, String> {
+    let mut app_settings = settings
+        .get_settings()
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+    if app_settings.cloud.active_endpoint == name {
+        return Err("Select another endpoint before removing this one".to_string());
+    }
+    app_settings.cloud.endpoints.retain(|e| e.name != name);
+    settings
+        .update_settings(app_settings)
+        .map(|s| endpoint_list(&s.cloud))
+        .map_err(|e| format!("Failed to save settings: {e}"))
+}
+
+/// Switch the official backend to another endpoint, restoring the session
+/// last used there. Without one, `cloud://reconnect-required` asks for a login.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn select_cloud_endpoint(
+    name: String,
+    app: AppHandle,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings_manager: State<'_, Arc<SettingsManager>>,
+    sync: State<'_, SyncManager>,
+) -> Result<CloudEndpoints, String> {
+    let mut app_settings = settings_manager
+        .get_settings()
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+    app_settings
+        .cloud
+        .select_endpoint(&name)
+        .map_err(|e| e.to_string())?;
+    let updated_settings = settings_manager
+        .update_settings(app_settings)
+        .map_err(|e| format!("Failed to save settings: {e}"))?;
+    info!("[CLOUD] Official endpoint set to {name}");
+    let _ = app.emit("cloud://endpoint-changed", &name);
+
+    if updated_settings.cloud_mode == CloudMode::Official {
+        sync.pause();
+        let _ = app.emit("cloud://reconnect-started", "reconnect");
+        switch_cloud_backend(
+            &app,
+            &cloud,
+            (*settings_manager).clone(),
+            CloudMode::Official,
+            updated_settings.clone(),
+        )
+        .await
+        .map_err(cloud_error_to_string)?;
+
+        match validate_official_config(&app, &updated_settings.cloud, false).await {
+            Ok(_) => {
+                sync.resume();
+                let _ = app.emit("cloud://online", "online");
+            }
+            Err(err) => {
+                let _ = app.emit("cloud://reconnect-required", err);
+            }
+        }
+    }
+
+    Ok(endpoint_list(&updated_settings.cloud))
+}
+
+fn endpoint_list(cloud: &CloudSettings) -> CloudEndpoints {
+    let endpoints = cloud
+        .endpoints
+        .iter()
+        .map(|endpoint| {
+            // The active endpoint's live session is in the top-level fields
+            let (base_url, api_key) = if endpoint.name == cloud.active_endpoint {
+                (&cloud.base_url, &cloud.api_key)
+            } else {
+                (&endpoint.base_url, &endpoint.api_key)
+            };
+            CloudEndpointSummary {
+                name: endpoint.name.clone(),
+                base_url: base_url.clone(),
+                signed_in: !api_key.trim().is_empty(),
+            }
+        })
+        .collect();
+    CloudEndpoints {
+        active: cloud.active_endpoint.clone(),
+        endpoints,
+    }
+}
+
 #[tauri::command]
 pub async fn update_cloud_mode(
     new_mode: String,
@@ -669,7 +1255,7 @@ pub async fn update_cloud_mode(
         CloudMode::SelfHost => {
             validate_self_host_config(&app, &updated_settings.self_host, false).await
         }
-        CloudMode::Off => Ok(()),
+        CloudMode::Off | CloudMode::Demo => Ok(()),
     };
 
     if let Err(err) = validation_result {
@@ -729,10 +1315,19 @@ pub async fn get_cloud_status(
         .get_settings()
         .map_err(|e| format!("Failed to load settings: {e}"))?;
 
+    if app_settings.cloud_mode == CloudMode::Demo {
+        let backend = cloud.lock().await;
+        return Ok(CloudStatus {
+            enabled: app_settings.cloud.enabled,
+            device_id: backend.ensure_device_id().map_err(cloud_error_to_string)?,
+            connected: backend.check_connection().await.unwrap_or(false),
+        });
+    }
+
     let base_url = match app_settings.cloud_mode {
         CloudMode::Official => app_settings.cloud.base_url.clone(),
         CloudMode::SelfHost => app_settings.self_host.api_server.clone(),
-        CloudMode::Off => String::new(),
+        CloudMode::Off | CloudMode::Demo => String::new(),
     }
     .trim_end_matches('/')
     .to_string();
@@ -853,7 +1448,20 @@ fn cloud_error_to_string(error: CloudError) -> String {
             }
         }
         CloudError::NotFound(msg) => {
-            if msg.contains("version") {
+            if msg.contains("password reset") {
+                "This server can't send password reset emails. Ask its administrator for help"
+                    .to_string()
+            } else if msg == "sessions" {
+                "This server doesn't support signing out other sessions".to_string()
+            } else if msg == "session" {
+                "That session is already signed out".to_string()
+            } else if msg == "sharing" {
+                "This server doesn't support sharing games".to_string()
+            } else if msg == "share account" {
+                "No account uses that email address".to_string()
+            } else if msg == "share" {
+                "That game is no longer shared".to_string()
+            } else if msg.contains("version") {
                 "Save version not found".to_string()
             } else if msg.contains("game") {
                 "Game not found".to_string()
@@ -879,6 +1487,10 @@ fn cloud_error_to_string(error: CloudError) -> String {
         }
         CloudError::Serialization(_) => "Data format error. Please try again".to_string(),
         CloudError::Unauthorized(msg) => msg, // Already user-friendly from cloud.rs
+        CloudError::QuotaExceeded(_) => {
+            "Cloud storage is full. Delete old save versions to free up space".to_string()
+        }
+        CloudError::Conflict(msg) => format!("Request rejected: {msg}"),
     }
 }
 
@@ -895,11 +1507,24 @@ fn ensure_cloud_mode_enabled(settings: &State<'_, Arc<SettingsManager>>) -> Resu
     Ok(())
 }
 
+/// Refuse to push one account's saves into another account's cloud
+fn ensure_history_owned_by_account(settings: &SettingsManager) -> Result<(), String> {
+    let app_settings = settings
+        .get_settings()
+        .map_err(|e| format!("Failed to load settings: {e}"))?;
+    if app_settings.history_belongs_to_account() {
+        Ok(())
+    } else {
+        Err("Local history belongs to another account; choose how to handle it first".to_string())
+    }
+}
+
 fn parse_cloud_mode(new_mode: &str) -> Result<CloudMode, String> {
     match new_mode.to_lowercase().as_str() {
         "official" => Ok(CloudMode::Official),
         "selfhost" | "self_host" | "self-host" => Ok(CloudMode::SelfHost),
         "off" => Ok(CloudMode::Off),
+        "demo" => Ok(CloudMode::Demo),
         other => Err(format!("Unsupported cloud mode: {other}")),
     }
 }
@@ -1135,6 +1760,7 @@ fn mode_to_str(mode: &CloudMode) -> &'static str {
         CloudMode::Official => "official",
         CloudMode::SelfHost => "self_host",
         CloudMode::Off => "off",
+        CloudMode::Demo => "demo",
     }
 }
 
@@ -1182,8 +1808,21 @@ pub async fn get_conflict_details(
 pub async fn resolve_conflict_upload(
     game_id: String,
     sync: State<'_, SyncManager>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    history: State<'_, Arc<HistoryManager>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    app: AppHandle,
 ) -> Result<(), String> {
     info!("[CONFLICT] Resolving by uploading local save for {}", game_id);
+    record_conflict_resolution(
+        &app,
+        &history,
+        &cloud,
+        &settings,
+        &game_id,
+        ConflictSide::Local,
+    )
+    .await;
     sync.trigger_sync();
     Ok(())
 }
@@ -1200,6 +1839,15 @@ pub async fn resolve_conflict_download(
     app: AppHandle,
 ) -> Result<(), String> {
     info!("[CONFLICT] Resolving by downloading cloud save for {}", game_id);
+    record_conflict_resolution(
+        &app,
+        &history,
+        &cloud,
+        &settings,
+        &game_id,
+        ConflictSide::Cloud,
+    )
+    .await;
 
     // Get latest cloud version
     let backend = cloud.lock().await;
@@ -1228,3 +1876,30 @@ pub async fn resolve_conflict_download(
     .await
     .map_err(|e| format!("Download failed: {e}"))
 }
+
+/// Resolve a conflict by merging the files of both versions into a new one.
+/// Fails with the conflicting file names when both sides changed a file.
+#[tauri::command]
+pub async fn resolve_conflict_merge(
+    game_id: String,
+    sync: State<'_, SyncManager>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    history: State<'_, Arc<HistoryManager>>,
+    profiles: State<'_, Arc<RwLock<ProfileManager>>>,
+    registry: State<'_, Arc<MergeRegistry>>,
+    app: AppHandle,
+) -> Result<MergeOutcome, String> {
+    info!("[CONFLICT] Resolving by merging both saves for {}", game_id);
+    let outcome = merge_conflict(
+        &app,
+        &cloud,
+        history.inner().clone(),
+        &profiles,
+        registry.inner().clone(),
+        &game_id,
+    )
+    .await
+    .map_err(|e| format!("Merge failed: {e}"))?;
+    sync.trigger_sync();
+    Ok(outcome)
+}
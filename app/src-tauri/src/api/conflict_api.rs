@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use tauri::State;
+use tracing::error;
+
+use crate::core::conflict::{ConflictManager, ConflictRecord, ConflictRule, RuleSuggestion};
+
+fn sanitize_game_id(game_id: String) -> Result<String, String> {
+    let trimmed = game_id.trim();
+    if trimmed.is_empty() {
+        return Err("game_id cannot be empty".to_string());
+    }
+    Ok(trimmed.to_string())
+}
+
+/// Past conflict resolutions, newest first
+#[tauri::command(rename_all = "snake_case")]
+pub async fn list_conflict_history(
+    conflicts: State<'_, Arc<ConflictManager>>,
+    game_id: Option<String>,
+) -> Result<Vec<ConflictRecord>, String> {
+    conflicts
+        .records(game_id.as_deref())
+        .map_err(|err| err.to_string())
+}
+
+/// Rules proposed from repeated identical resolutions
+#[tauri::command]
+pub async fn list_conflict_suggestions(
+    conflicts: State<'_, Arc<ConflictManager>>,
+) -> Result<Vec<RuleSuggestion>, String> {
+    conflicts.suggestions().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn accept_conflict_suggestion(
+    conflicts: State<'_, Arc<ConflictManager>>,
+    game_id: String,
+) -> Result<ConflictRule, String> {
+    let game_id = sanitize_game_id(game_id)?;
+    conflicts.accept_suggestion(&game_id).map_err(|err| {
+        error!("[SYNC] Failed to accept conflict suggestion: {err}");
+        err.to_string()
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn dismiss_conflict_suggestion(
+    conflicts: State<'_, Arc<ConflictManager>>,
+    game_id: String,
+) -> Result<(), String> {
+    let game_id = sanitize_game_id(game_id)?;
+    conflicts
+        .dismiss_suggestion(&game_id)
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn list_conflict_rules(
+    conflicts: State<'_, Arc<ConflictManager>>,
+) -> Result<Vec<ConflictRule>, String> {
+    conflicts.rules().map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+pub async fn set_conflict_rule(
+    conflicts: State<'_, Arc<ConflictManager>>,
+    rule: ConflictRule,
+) -> Result<(), String> {
+    let mut rule = rule;
+    rule.game_id = sanitize_game_id(rule.game_id)?;
+    conflicts.set_rule(rule).map_err(|err| {
+        error!("[SYNC] Failed to save conflict rule: {err}");
+        err.to_string()
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_conflict_rule(
+    conflicts: State<'_, Arc<ConflictManager>>,
+    game_id: String,
+) -> Result<(), String> {
+    let game_id = sanitize_game_id(game_id)?;
+    conflicts
+        .remove_rule(&game_id)
+        .map_err(|err| err.to_string())
+}
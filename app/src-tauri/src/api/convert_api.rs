@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tauri::State;
+use tracing::error;
+
+use crate::core::convert::{convert_file, ConvertedSave, ConverterRegistry};
+
+/// Convert one save file from `source_format` to `target_format`, e.g.
+/// `retroarch` to `desmume`, writing the result next to it
+#[tauri::command]
+pub async fn convert_save(
+    registry: State<'_, Arc<ConverterRegistry>>,
+    source_path: String,
+    source_format: String,
+    target_format: String,
+) -> Result<ConvertedSave, String> {
+    let source_path = PathBuf::from(source_path.trim());
+    if !source_path.is_file() {
+        return Err(format!("Save file not found: {}", source_path.display()));
+    }
+    let registry = registry.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        convert_file(
+            &registry,
+            &source_path,
+            source_format.trim(),
+            target_format.trim(),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|err| {
+        error!("[CONVERT] Conversion failed: {err}");
+        err.to_string()
+    })
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::core::crash::{CrashReport, CrashReporter};
+use crate::core::settings::SettingsManager;
+
+/// Panic reports stored on this device, newest first
+#[tauri::command]
+pub async fn list_crash_reports(
+    reporter: State<'_, Arc<CrashReporter>>,
+) -> Result<Vec<CrashReport>, String> {
+    reporter.reports().map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn submit_crash_report(
+    reporter: State<'_, Arc<CrashReporter>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    report_id: String,
+) -> Result<(), String> {
+    let app_settings = settings.get_settings().map_err(|err| err.to_string())?;
+    reporter
+        .submit(&app_settings, &report_id)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_crash_report(
+    reporter: State<'_, Arc<CrashReporter>>,
+    report_id: String,
+) -> Result<(), String> {
+    reporter.remove(&report_id).map_err(|err| err.to_string())
+}
@@ -0,0 +1,223 @@
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::api::cloud_api::{download_cloud_save, login_with_token};
+use crate::api::history_api::rollback_version;
+use crate::core::cloud::CloudBackend;
+use crate::core::history::HistoryManager;
+use crate::core::profile::ProfileManager;
+use crate::core::settings::SettingsManager;
+
+/// URL scheme registered with the OS, as in `crosssave://restore?...`
+const SCHEME: &str = "crosssave";
+/// How long a `begin_link_login` state stays valid for the callback
+const LOGIN_STATE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// A `crosssave://` link the app knows how to handle
+#[derive(Debug, PartialEq, Eq)]
+enum DeepLink {
+    /// `crosssave://login-callback?token=...&state=...[&user_id=...]`
+    LoginCallback {
+        token: String,
+        state: String,
+        user_id: Option<String>,
+    },
+    /// `crosssave://restore?game_id=...&version_id=...`
+    Restore { game_id: String, version_id: String },
+}
+
+impl DeepLink {
+    fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("unsupported scheme {}", url.scheme()));
+        }
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let required = |name: &str| param(name).ok_or_else(|| format!("missing {name}"));
+
+        match url.host_str() {
+            Some("login-callback") => Ok(DeepLink::LoginCallback {
+                token: required("token")?,
+                state: required("state")?,
+                user_id: param("user_id"),
+            }),
+            Some("restore") => Ok(DeepLink::Restore {
+                game_id: link_id(required("game_id")?, "game_id")?,
+                version_id: link_id(required("version_id")?, "version_id")?,
+            }),
+            Some(other) => Err(format!("unknown link action {other}")),
+            None => Err("link has no action".to_string()),
+        }
+    }
+}
+
+/// Ids from a link end up in history paths, so only plain names pass
+fn link_id(value: String, field: &str) -> Result<String, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        Err(format!("{field} cannot be empty"))
+    } else if value == ".." || value.contains(['/', '\\']) {
+        Err(format!("{field} is not a valid id"))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// The `state` handed out by `begin_link_login`, so only a login this app
+/// started can complete through a callback link
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending_login: Mutex<Option<(String, Instant)>>,
+}
+
+impl DeepLinkState {
+    fn begin_login(&self) -> String {
+        let state = Uuid::new_v4().to_string();
+        if let Ok(mut pending) = self.pending_login.lock() {
+            *pending = Some((state.clone(), Instant::now()));
+        }
+        state
+    }
+
+    /// Whether `state` is the outstanding one; it can only be used once
+    fn take_login(&self, state: &str) -> bool {
+        let Ok(mut pending) = self.pending_login.lock() else {
+            return false;
+        };
+        match pending.take() {
+            Some((expected, started)) => expected == state && started.elapsed() < LOGIN_STATE_TTL,
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct RestoreRequestedPayload {
+    game_id: String,
+    version_id: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct DeepLinkErrorPayload {
+    message: String,
+}
+
+/// Where `confirm_deep_link_restore` took the version from
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeepLinkRestoreSource {
+    /// The version was already in local history
+    Local,
+    /// The version was downloaded from the cloud first
+    Cloud,
+}
+
+/// Act on links the OS opened the app with. Restores only ask the frontend
+/// for confirmation; nothing is overwritten until `confirm_deep_link_restore`.
+pub fn handle_urls(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        let link = match DeepLink::parse(&url) {
+            Ok(link) => link,
+            Err(err) => {
+                // The query may hold a token, so only the action is logged
+                warn!(
+                    "[CLOUD] Ignoring deep link {}://{}: {err}",
+                    url.scheme(),
+                    url.host_str().unwrap_or_default()
+                );
+                emit_error(app, err);
+                continue;
+            }
+        };
+
+        match link {
+            DeepLink::LoginCallback {
+                token,
+                state,
+                user_id,
+            } => {
+                let expected = app
+                    .try_state::<DeepLinkState>()
+                    .is_some_and(|links| links.take_login(&state));
+                if !expected {
+                    warn!("[CLOUD] Ignoring login callback this app did not start");
+                    emit_error(app, "This sign-in link has expired; try again".to_string());
+                    continue;
+                }
+                info!("[CLOUD] Completing sign-in from login callback");
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    match login_with_token(&app, token, user_id).await {
+                        Ok(result) => {
+                            let _ = app.emit("deeplink://login-complete", result);
+                        }
+                        Err(err) => {
+                            warn!("[CLOUD] Login callback failed: {err}");
+                            emit_error(&app, err);
+                        }
+                    }
+                });
+            }
+            DeepLink::Restore {
+                game_id,
+                version_id,
+            } => {
+                info!("[SYNC] Restore of {game_id}/{version_id} requested by link");
+                let _ = app.emit(
+                    "deeplink://restore-requested",
+                    RestoreRequestedPayload {
+                        game_id,
+                        version_id,
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn emit_error(app: &AppHandle, message: String) {
+    let _ = app.emit("deeplink://error", DeepLinkErrorPayload { message });
+}
+
+/// Start a browser sign-in. The returned `state` goes into the sign-in URL
+/// and must come back in the `crosssave://login-callback` link.
+#[tauri::command]
+pub async fn begin_link_login(links: State<'_, DeepLinkState>) -> Result<String, String> {
+    Ok(links.begin_login())
+}
+
+/// Restore the version a `deeplink://restore-requested` event named, from
+/// local history when it is there and from the cloud otherwise
+#[tauri::command(rename_all = "snake_case")]
+pub async fn confirm_deep_link_restore(
+    app: AppHandle,
+    game_id: String,
+    version_id: String,
+    cloud: State<'_, Arc<tokio::sync::Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    history: State<'_, Arc<HistoryManager>>,
+    profiles: State<'_, Arc<RwLock<ProfileManager>>>,
+) -> Result<DeepLinkRestoreSource, String> {
+    let game_id = link_id(game_id, "game_id")?;
+    let version_id = link_id(version_id, "version_id")?;
+
+    if history
+        .get_history_item(game_id.clone(), version_id.clone())
+        .is_ok()
+    {
+        rollback_version(app, history, profiles, game_id, version_id, Some(false)).await?;
+        Ok(DeepLinkRestoreSource::Local)
+    } else {
+        download_cloud_save(game_id, version_id, cloud, settings, history, profiles, app).await?;
+        Ok(DeepLinkRestoreSource::Cloud)
+    }
+}
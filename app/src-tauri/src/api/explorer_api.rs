@@ -1,10 +1,15 @@
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
-use serde::Serialize;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
+use crate::core::encryption::file_is_encrypted;
 use crate::core::packager::SavePackager;
+use crate::core::path_check::{check_path, PathReport};
+use crate::core::paths::simplified;
 use crate::core::profile::ProfileManager;
 
 #[derive(Debug, Serialize)]
@@ -13,37 +18,84 @@ pub struct ScannedFile {
     pub name: String,
     pub size: u64,
     pub modified: u128,
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ScanOptions {
+    /// Add per-directory totals to the response
+    #[serde(default)]
+    pub summarize: bool,
+}
+
+/// Totals for the files found in one directory, or across the whole scan
+#[derive(Debug, Default, Serialize)]
+pub struct ScanSummary {
+    /// Set for a directory, `None` for the whole scan
+    pub path: Option<String>,
+    pub file_count: usize,
+    pub total_size: u64,
+    /// Newest modification time, in milliseconds since the epoch
+    pub newest_modified: Option<u128>,
+    /// Files matching each of the profile's file patterns
+    pub pattern_matches: BTreeMap<String, usize>,
+}
+
+impl ScanSummary {
+    fn add(&mut self, file: &ScannedFile, matched: &[&str]) {
+        self.file_count += 1;
+        self.total_size += file.size;
+        self.newest_modified = self.newest_modified.max(Some(file.modified));
+        for pattern in matched {
+            *self.pattern_matches.entry(pattern.to_string()).or_default() += 1;
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanResponse {
+    pub files: Vec<ScannedFile>,
+    /// Totals across all files, with `summarize`
+    pub summary: Option<ScanSummary>,
+    /// Totals per directory holding files, with `summarize`
+    pub directories: Vec<ScanSummary>,
 }
 
 #[tauri::command]
 pub async fn scan_save_files(
     profile_state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
     emulator_id: String,
-) -> Result<Vec<ScannedFile>, String> {
+    game_id: Option<String>,
+    scan_options: Option<ScanOptions>,
+) -> Result<ScanResponse, String> {
+    let scan_options = scan_options.unwrap_or_default();
     // Clone data needed for the thread in a separate block to ensure lock is released
-    let (default_save_paths, file_patterns) = {
+    let (save_paths, file_patterns, exclude_patterns, max_file_size_bytes, save_encryption) = {
         let manager = profile_state.read().map_err(|e| e.to_string())?;
         let profile = manager
             .get_profile(&emulator_id)
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("Profile {} not found", emulator_id))?;
 
+        let location = profile.save_location(game_id.as_deref());
         (
-            profile.default_save_paths.clone(),
-            profile.file_patterns.clone(),
+            location.paths,
+            location.file_patterns,
+            profile.exclude_patterns.clone(),
+            profile.max_file_size_bytes,
+            profile.save_encryption,
         )
     };
 
     let emulator_id_clone = emulator_id.clone();
 
     // Offload scanning to a blocking thread to avoid freezing the UI
-    let scanned_files = tauri::async_runtime::spawn_blocking(move || {
-        let packager = SavePackager::new("explorer".to_string(), emulator_id_clone.clone());
-
-        let paths: Vec<PathBuf> = default_save_paths.iter().map(PathBuf::from).collect();
+    let response = tauri::async_runtime::spawn_blocking(move || {
+        let mut packager = SavePackager::new("explorer".to_string(), emulator_id_clone.clone());
+        packager.set_filters(exclude_patterns, max_file_size_bytes);
 
         let files = packager
-            .collect_files(paths, file_patterns)
+            .collect_files(save_paths, file_patterns.clone())
             .map_err(|e| e.to_string())?;
 
         let mut scanned_files = Vec::new();
@@ -63,11 +115,14 @@ pub async fn scan_save_files(
                     .unwrap_or_default()
                     .as_millis();
 
+                let encrypted = file_is_encrypted(save_encryption, &path);
+
                 scanned_files.push(ScannedFile {
-                    path: path.to_string_lossy().to_string(),
+                    path: simplified(&path).to_string_lossy().to_string(),
                     name,
                     size: metadata.len(),
                     modified,
+                    encrypted,
                 });
             }
         }
@@ -77,59 +132,82 @@ pub async fn scan_save_files(
             scanned_files.len(),
             emulator_id_clone
         );
-        Ok::<Vec<ScannedFile>, String>(scanned_files)
+        let (summary, directories) = if scan_options.summarize {
+            let (summary, directories) = summarize(&scanned_files, &file_patterns);
+            (Some(summary), directories)
+        } else {
+            (None, Vec::new())
+        };
+        Ok::<ScanResponse, String>(ScanResponse {
+            files: scanned_files,
+            summary,
+            directories,
+        })
     })
     .await
     .map_err(|e| e.to_string())??;
 
-    Ok(scanned_files)
+    Ok(response)
 }
 
-#[derive(Debug, Serialize)]
-pub struct PathStatus {
-    pub path: String,
-    pub exists: bool,
-    pub is_dir: bool,
-    pub error: Option<String>,
+/// Overall and per-directory totals of `files`, newest directory first
+fn summarize(files: &[ScannedFile], patterns: &[String]) -> (ScanSummary, Vec<ScanSummary>) {
+    let compiled: Vec<(&str, Pattern)> = patterns
+        .iter()
+        .filter_map(|pattern| Some((pattern.as_str(), Pattern::new(pattern).ok()?)))
+        .collect();
+
+    let mut summary = ScanSummary::default();
+    let mut by_directory: BTreeMap<String, ScanSummary> = BTreeMap::new();
+    for file in files {
+        let path = Path::new(&file.path);
+        let matched: Vec<&str> = compiled
+            .iter()
+            .filter(|(_, pattern)| pattern.matches_path(path))
+            .map(|(pattern, _)| *pattern)
+            .collect();
+        summary.add(file, &matched);
+
+        let directory = path
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_default();
+        by_directory
+            .entry(directory.clone())
+            .or_insert_with(|| ScanSummary {
+                path: Some(directory),
+                ..ScanSummary::default()
+            })
+            .add(file, &matched);
+    }
+
+    let mut directories: Vec<ScanSummary> = by_directory.into_values().collect();
+    directories.sort_by(|a, b| b.newest_modified.cmp(&a.newest_modified));
+    (summary, directories)
 }
 
+/// Existence, permissions, free space and case sensitivity of each of
+/// the profile's save paths, with issue codes for anything that would make
+/// packaging or restoring fail
 #[tauri::command]
 pub async fn check_path_status(
     profile_state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
     emulator_id: String,
-) -> Result<Vec<PathStatus>, String> {
-    let manager = profile_state.read().map_err(|e| e.to_string())?;
-    let profile = manager
-        .get_profile(&emulator_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Profile {} not found", emulator_id))?;
-
-    let mut statuses = Vec::new();
-
-    for path_str in profile.default_save_paths {
-        let path = PathBuf::from(&path_str);
-        let exists = path.exists();
-        let is_dir = path.is_dir();
-
-        // Try to read dir to check permissions if it exists
-        let error = if exists && is_dir {
-            match std::fs::read_dir(&path) {
-                Ok(_) => None,
-                Err(e) => Some(e.to_string()),
-            }
-        } else {
-            None
-        };
-
-        statuses.push(PathStatus {
-            path: path_str,
-            exists,
-            is_dir,
-            error,
-        });
-    }
+) -> Result<Vec<PathReport>, String> {
+    let paths = {
+        let manager = profile_state.read().map_err(|e| e.to_string())?;
+        manager
+            .get_profile(&emulator_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Profile {} not found", emulator_id))?
+            .default_save_paths
+    };
 
-    Ok(statuses)
+    tauri::async_runtime::spawn_blocking(move || {
+        paths.iter().map(|path| check_path(path)).collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
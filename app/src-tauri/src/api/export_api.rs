@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use reqwest::Client;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+use tracing::{error, warn};
+
+use crate::core::cloud::CloudBackend;
+use crate::core::export::{export_version, ExportResult, Provenance};
+use crate::core::history::HistoryManager;
+use crate::core::packager::decode_download;
+use crate::core::profile::ProfileManager;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExportVersionRequest {
+    pub game_id: String,
+    pub version_id: String,
+    pub target_dir: String,
+    /// Skip local history and export the cloud copy
+    #[serde(default)]
+    pub from_cloud: bool,
+}
+
+/// Extract a history or cloud version into `target_dir` for inspection.
+/// Local history is used when it has the version unless `from_cloud` is set.
+#[tauri::command]
+pub async fn export_version_to_folder(
+    app: AppHandle,
+    history: State<'_, Arc<HistoryManager>>,
+    profiles: State<'_, Arc<RwLock<ProfileManager>>>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    request: ExportVersionRequest,
+) -> Result<ExportResult, String> {
+    let game_id = request.game_id.trim().to_string();
+    let version_id = request.version_id.trim().to_string();
+    let target_dir = request.target_dir.trim().to_string();
+    if game_id.is_empty() || version_id.is_empty() || target_dir.is_empty() {
+        return Err("game_id, version_id and target_dir are required".to_string());
+    }
+
+    let local = if request.from_cloud {
+        None
+    } else {
+        history
+            .get_history_item(game_id.clone(), version_id.clone())
+            .ok()
+    };
+
+    // Either the history archive, or a temporary download of the cloud one
+    let (archive_path, provenance, temporary) = match local {
+        Some(entry) => {
+            let metadata = entry.metadata;
+            let provenance = Provenance {
+                game_id: metadata.game_id,
+                version_id: metadata.version_id,
+                emulator_id: metadata.emulator_id,
+                source: metadata.source.unwrap_or_else(|| "local".to_string()),
+                timestamp: metadata.timestamp,
+                sha256: metadata.sha256,
+                note: metadata.note,
+                tags: metadata.tags,
+                ..Provenance::default()
+            };
+            (entry.archive_path, provenance, false)
+        }
+        None => {
+            let (path, provenance) =
+                fetch_cloud_version(&app, &cloud, &game_id, &version_id).await?;
+            (path, provenance, true)
+        }
+    };
+
+    let live_paths = {
+        let manager = profiles.read().map_err(|err| err.to_string())?;
+        manager
+            .get_profile(&provenance.emulator_id)
+            .map_err(|err| err.to_string())?
+            .map(|profile| profile.save_location(Some(&game_id)).paths)
+            .unwrap_or_default()
+    };
+
+    let target = PathBuf::from(target_dir);
+    let archive = archive_path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        export_version(&archive, &target, &live_paths, provenance)
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+
+    if temporary {
+        if let Err(err) = std::fs::remove_file(&archive_path) {
+            warn!(
+                "[CLOUD] Failed to remove export download {:?}: {err}",
+                archive_path
+            );
+        }
+    }
+
+    result.map_err(|err| {
+        error!("[HISTORY] Export of {game_id} version {version_id} failed: {err}");
+        err.to_string()
+    })
+}
+
+async fn fetch_cloud_version(
+    app: &AppHandle,
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    game_id: &str,
+    version_id: &str,
+) -> Result<(PathBuf, Provenance), String> {
+    let info = {
+        let backend = cloud.lock().await;
+        backend
+            .request_download_url(game_id.to_string(), version_id.to_string())
+            .await
+            .map_err(|err| format!("Failed to request cloud version: {err}"))?
+    };
+
+    let bytes = Client::new()
+        .get(&info.download_url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|err| format!("Failed to download cloud version: {err}"))?
+        .bytes()
+        .await
+        .map_err(|err| format!("Failed to download cloud version: {err}"))?;
+    let bytes = tauri::async_runtime::spawn_blocking(move || decode_download(bytes.to_vec()))
+        .await
+        .map_err(|err| format!("Failed to decode cloud version: {err}"))?
+        .map_err(|err| format!("Failed to decode cloud version: {err}"))?;
+
+    let downloads_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|err| format!("path error: {err}"))?
+        .join("data")
+        .join("cloud_downloads");
+    std::fs::create_dir_all(&downloads_dir).map_err(|err| err.to_string())?;
+    let path = downloads_dir.join(format!("export_{game_id}_{version_id}.zip"));
+    std::fs::write(&path, &bytes).map_err(|err| err.to_string())?;
+
+    let provenance = Provenance {
+        game_id: info.game_id,
+        version_id: info.version_id,
+        emulator_id: info.emulator_id.unwrap_or_default(),
+        source: "cloud".to_string(),
+        timestamp: info.timestamp.unwrap_or_default(),
+        sha256: Some(info.sha256),
+        note: info.note,
+        tags: info.tags,
+        ..Provenance::default()
+    };
+    Ok((path, provenance))
+}
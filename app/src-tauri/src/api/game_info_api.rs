@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::core::game_info::{lookup, GameInfo};
+use crate::core::settings::SettingsManager;
+
+/// Display title, platform and cover art hash for `game_id`, from the
+/// bundled name database or the configured online lookup; `None` when
+/// neither knows the game
+#[tauri::command]
+pub async fn get_game_info(
+    settings: State<'_, Arc<SettingsManager>>,
+    game_id: String,
+) -> Result<Option<GameInfo>, String> {
+    let app_settings = settings.get_settings().map_err(|err| err.to_string())?;
+    Ok(lookup(&app_settings, game_id.trim()).await)
+}
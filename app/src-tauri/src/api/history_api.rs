@@ -1,8 +1,22 @@
-use std::sync::Arc;
-use tracing::{error, warn};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tauri::{Emitter, Manager};
+use tracing::{error, info, warn};
 
-use crate::core::history::{HistoryEntry, HistoryManager};
-use crate::core::packager::PackagedSave;
+use crate::core::cloud::{CloudBackend, CloudError};
+use crate::core::extract::{list_archive, ArchiveEntry};
+use crate::core::history::{
+    CompactionReport, HistoryEntry, HistoryManager, HistoryPage, HistoryQuery,
+};
+use crate::core::profile::ProfileManager;
+use crate::core::restore::{
+    backup_before_restore, package_live_saves, plan_restore, restore_archive, restore_target,
+    PlannedFile, RestoreError,
+};
+use crate::core::storage::StorageScope;
+use crate::core::sync::{enforce_history_budget, report_storage_full};
+use crate::core::watcher::WatcherManager;
 
 fn sanitize_input(value: String, field: &str) -> Result<String, String> {
     let trimmed = value.trim();
@@ -33,6 +47,20 @@ pub async fn list_history(
     })
 }
 
+/// Versions of every game, filtered and paged for the unified timeline
+#[tauri::command]
+pub async fn list_all_history(
+    state: tauri::State<'_, Arc<HistoryManager>>,
+    query: Option<HistoryQuery>,
+) -> Result<HistoryPage, String> {
+    state
+        .query_history(&query.unwrap_or_default())
+        .map_err(|err| {
+            error!("[HISTORY] Failed to list all history: {err}");
+            err.to_string()
+        })
+}
+
 #[tauri::command(rename_all = "snake_case")]
 pub async fn get_history_item(
     state: tauri::State<'_, Arc<HistoryManager>>,
@@ -50,21 +78,243 @@ pub async fn get_history_item(
         })
 }
 
+/// What a version's archive holds, from `preview_archive`
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchivePreview {
+    pub game_id: String,
+    pub version_id: String,
+    /// `history`, or `cloud_download` for a downloaded version not yet in history
+    pub source: String,
+    pub total_bytes: u64,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// List the files in a version's archive, with sizes, timestamps and CRCs,
+/// without extracting it, so the user can see what a rollback would write
+#[tauri::command(rename_all = "snake_case")]
+pub async fn preview_archive(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<HistoryManager>>,
+    game_id: String,
+    version_id: String,
+) -> Result<ArchivePreview, String> {
+    let game_id = sanitize_input(game_id, "game_id")?;
+    let version_id = sanitize_input(version_id, "version_id")?;
+
+    let (archive_path, source) = match state.get_history_item(game_id.clone(), version_id.clone()) {
+        Ok(entry) => (entry.archive_path, "history"),
+        Err(err) => {
+            // Ids become part of the download's file name
+            if [&game_id, &version_id]
+                .iter()
+                .any(|id| id.contains(['/', '\\']) || id.contains(".."))
+            {
+                return Err(format!("Version {version_id} not found: {err}"));
+            }
+            let downloaded = app
+                .path()
+                .app_data_dir()
+                .map_err(|err| format!("path error: {err}"))?
+                .join("data")
+                .join("cloud_downloads")
+                .join(format!("{game_id}_{version_id}.zip"));
+            if !downloaded.is_file() {
+                return Err(format!("Version {version_id} not found: {err}"));
+            }
+            (downloaded, "cloud_download")
+        }
+    };
+
+    let entries = tauri::async_runtime::spawn_blocking(move || list_archive(&archive_path))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| {
+            warn!("[HISTORY] Failed to read archive of {game_id}/{version_id}: {err}");
+            err.to_string()
+        })?;
+
+    Ok(ArchivePreview {
+        total_bytes: entries.iter().map(|entry| entry.size_bytes).sum(),
+        game_id,
+        version_id,
+        source: source.to_string(),
+        entries,
+    })
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollbackStage {
+    BackingUp,
+    Restoring,
+    /// Packaging the restored saves as the newest version
+    Recording,
+    Completed,
+    Failed,
+}
+
+/// Progress of `rollback_version`, emitted as `rollback://progress`
+#[derive(Clone, Debug, Serialize)]
+pub struct RollbackProgressPayload {
+    pub game_id: String,
+    pub version_id: String,
+    pub stage: RollbackStage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// What `rollback_version` did, or would do in a dry run
+#[derive(Debug, Serialize)]
+pub struct RollbackReport {
+    pub game_id: String,
+    pub version_id: String,
+    pub target_dir: String,
+    /// Every file the version writes and whether it replaces one on disk
+    pub files: Vec<PlannedFile>,
+    pub dry_run: bool,
+    /// Version holding the saves that were on disk before the restore
+    pub backup: Option<HistoryEntry>,
+}
+
+/// Restore a history version into the game's save folder. What is on disk
+/// is kept as a "pre-restore backup" version first. With `dry_run` nothing
+/// is written and the report only lists the files that would change.
 #[tauri::command(rename_all = "snake_case")]
 pub async fn rollback_version(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<HistoryManager>>,
+    profiles: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
     game_id: String,
     version_id: String,
-) -> Result<PackagedSave, String> {
+    dry_run: Option<bool>,
+) -> Result<RollbackReport, String> {
     let sanitized_game_id = sanitize_input(game_id, "game_id")?;
     let sanitized_version_id = sanitize_input(version_id, "version_id")?;
+    let dry_run = dry_run.unwrap_or(false);
 
-    state
-        .rollback_version(sanitized_game_id, sanitized_version_id)
-        .map_err(|err| {
-            error!("[HISTORY] Rollback failed: {err}");
-            err.to_string()
-        })
+    let history = state.inner().clone();
+    let profiles = profiles.inner().clone();
+    let restore_app = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        rollback_blocking(
+            &restore_app,
+            &history,
+            &profiles,
+            sanitized_game_id,
+            sanitized_version_id,
+            dry_run,
+        )
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+
+    if !dry_run {
+        enforce_history_budget(&app, state.inner());
+    }
+    result
+}
+
+fn rollback_blocking(
+    app: &tauri::AppHandle,
+    history: &HistoryManager,
+    profiles: &RwLock<ProfileManager>,
+    game_id: String,
+    version_id: String,
+    dry_run: bool,
+) -> Result<RollbackReport, String> {
+    let emit = |stage: RollbackStage, message: Option<String>| {
+        if !dry_run {
+            let _ = app.emit(
+                "rollback://progress",
+                RollbackProgressPayload {
+                    game_id: game_id.clone(),
+                    version_id: version_id.clone(),
+                    stage,
+                    message,
+                },
+            );
+        }
+    };
+    let fail = |err: String| {
+        error!("[HISTORY] Rollback of {game_id} to {version_id} failed: {err}");
+        emit(RollbackStage::Failed, Some(err.clone()));
+        err
+    };
+
+    let entry = history
+        .get_history_item(game_id.clone(), version_id.clone())
+        .map_err(|err| fail(err.to_string()))?;
+
+    let profile = {
+        let manager = profiles.read().map_err(|err| fail(err.to_string()))?;
+        manager
+            .get_profile(&entry.metadata.emulator_id)
+            .map_err(|err| fail(err.to_string()))?
+            .ok_or_else(|| fail(format!("Profile {} not found", entry.metadata.emulator_id)))?
+    };
+
+    let target_dir = restore_target(&profile, &game_id).map_err(|err| fail(err.to_string()))?;
+    let files =
+        plan_restore(&entry.archive_path, &target_dir).map_err(|err| fail(err.to_string()))?;
+    let mut report = RollbackReport {
+        game_id: game_id.clone(),
+        version_id: version_id.clone(),
+        target_dir: target_dir.to_string_lossy().to_string(),
+        files,
+        dry_run,
+        backup: None,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    let report_restore_error = |err: RestoreError| {
+        if let RestoreError::StorageFull(message) = &err {
+            report_storage_full(app, StorageScope::Local, message.clone());
+        }
+        fail(err.to_string())
+    };
+
+    emit(RollbackStage::BackingUp, None);
+    report.backup = backup_before_restore(
+        history,
+        &profile,
+        &game_id,
+        &target_dir,
+        &entry.archive_path,
+        None,
+    )
+    .map_err(report_restore_error)?;
+
+    // Keep the watcher from reporting the restored files as new saves
+    let suppress_watcher = || {
+        if let Some(watcher) = app.try_state::<WatcherManager>() {
+            watcher.suppress_path(&target_dir);
+        }
+    };
+    emit(RollbackStage::Restoring, None);
+    suppress_watcher();
+    let restored = restore_archive(&entry.archive_path, &target_dir);
+    suppress_watcher();
+    let restored_files = restored.map_err(report_restore_error)?;
+
+    info!("[HISTORY] Restored {restored_files} files for {game_id} from version {version_id}");
+
+    // Record the restored saves as the newest version so sync carries the
+    // rollback to other devices instead of the pre-restore backup
+    emit(RollbackStage::Recording, None);
+    if let Err(err) = package_live_saves(
+        history,
+        &profile,
+        &game_id,
+        Vec::new(),
+        Some(format!("Restored from version {version_id}")),
+        None,
+    ) {
+        warn!("[HISTORY] Failed to record restored saves for {game_id}: {err}");
+    }
+    emit(RollbackStage::Completed, None);
+    Ok(report)
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -83,3 +333,125 @@ pub async fn delete_history_item(
             err.to_string()
         })
 }
+
+/// Hard-link identical archives within each game and report the space saved
+#[tauri::command]
+pub async fn compact_history(
+    state: tauri::State<'_, Arc<HistoryManager>>,
+) -> Result<CompactionReport, String> {
+    let history = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        history.compact().map_err(|err| {
+            error!("[HISTORY] Compaction failed: {err}");
+            err.to_string()
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn attach_version_thumbnail(
+    state: tauri::State<'_, Arc<HistoryManager>>,
+    game_id: String,
+    version_id: String,
+    image_path: String,
+) -> Result<HistoryEntry, String> {
+    let sanitized_game_id = sanitize_input(game_id, "game_id")?;
+    let sanitized_version_id = sanitize_input(version_id, "version_id")?;
+    let sanitized_image_path = sanitize_input(image_path, "image_path")?;
+
+    state
+        .attach_thumbnail(
+            sanitized_game_id,
+            sanitized_version_id,
+            &PathBuf::from(sanitized_image_path),
+        )
+        .map_err(|err| {
+            warn!("[HISTORY] Failed to attach thumbnail: {err}");
+            err.to_string()
+        })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn update_history_note(
+    state: tauri::State<'_, Arc<HistoryManager>>,
+    game_id: String,
+    version_id: String,
+    note: Option<String>,
+    tags: Vec<String>,
+) -> Result<HistoryEntry, String> {
+    let sanitized_game_id = sanitize_input(game_id, "game_id")?;
+    let sanitized_version_id = sanitize_input(version_id, "version_id")?;
+
+    state
+        .update_note(sanitized_game_id, sanitized_version_id, note, tags)
+        .map_err(|err| {
+            warn!("[HISTORY] Failed to update note: {err}");
+            err.to_string()
+        })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn pin_history_item(
+    state: tauri::State<'_, Arc<HistoryManager>>,
+    cloud: tauri::State<'_, Arc<tokio::sync::Mutex<Box<dyn CloudBackend + Send>>>>,
+    game_id: String,
+    version_id: String,
+) -> Result<HistoryEntry, String> {
+    set_history_item_pinned(&state, &cloud, game_id, version_id, true).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn unpin_history_item(
+    state: tauri::State<'_, Arc<HistoryManager>>,
+    cloud: tauri::State<'_, Arc<tokio::sync::Mutex<Box<dyn CloudBackend + Send>>>>,
+    game_id: String,
+    version_id: String,
+) -> Result<HistoryEntry, String> {
+    set_history_item_pinned(&state, &cloud, game_id, version_id, false).await
+}
+
+/// Pin or unpin a local version and the cloud copy of it, so the server
+/// refuses to delete or prune exactly what is pinned here
+async fn set_history_item_pinned(
+    state: &HistoryManager,
+    cloud: &tokio::sync::Mutex<Box<dyn CloudBackend + Send>>,
+    game_id: String,
+    version_id: String,
+    pinned: bool,
+) -> Result<HistoryEntry, String> {
+    let sanitized_game_id = sanitize_input(game_id, "game_id")?;
+    let sanitized_version_id = sanitize_input(version_id, "version_id")?;
+
+    let entry = state
+        .set_pinned(
+            sanitized_game_id.clone(),
+            sanitized_version_id.clone(),
+            pinned,
+        )
+        .map_err(|err| {
+            warn!("[HISTORY] Failed to update pin: {err}");
+            err.to_string()
+        })?;
+
+    // Versions that never reached the cloud, or no signed-in cloud at all,
+    // have nothing to mirror
+    match cloud
+        .lock()
+        .await
+        .set_version_pinned(sanitized_game_id, sanitized_version_id, pinned)
+        .await
+    {
+        Ok(())
+        | Err(CloudError::Disabled)
+        | Err(CloudError::Unauthorized(_))
+        | Err(CloudError::NotFound(_)) => Ok(entry),
+        Err(err) => {
+            warn!("[HISTORY] Failed to update cloud pin: {err}");
+            Err(format!(
+                "Updated the local pin but not the cloud one: {err}"
+            ))
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+use tracing::{info, Level};
+
+use crate::core::logs::{log_files, sanitized_settings, write_bundle, LogLine};
+use crate::core::settings::SettingsManager;
+
+const DEFAULT_LOG_LIMIT: usize = 500;
+
+/// Files copied into the diagnostics bundle, relative to the app data
+/// directory; the ones missing on this device are left out
+const DIAGNOSTIC_FILES: &[&str] = &[
+    "data/sync_state.json",
+    "data/sync_queue.json",
+    "data/download_queue.json",
+    "config/conflicts.json",
+];
+
+/// The newest log lines, oldest first. `level` keeps that level and more
+/// severe ones and defaults to `info`.
+#[tauri::command]
+pub async fn get_recent_logs(
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogLine>, String> {
+    let max_level = match level.as_deref().map(str::trim) {
+        None | Some("") => Level::INFO,
+        Some(level) => level
+            .parse::<Level>()
+            .map_err(|_| format!("unknown log level: {level}"))?,
+    };
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || log_files().recent(max_level, limit))
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Zip the logs, settings without secrets and the sync journal into
+/// `target_dir` for a bug report. Returns the path of the zip.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_diagnostics(
+    app: AppHandle,
+    settings: State<'_, Arc<SettingsManager>>,
+    target_dir: String,
+) -> Result<String, String> {
+    let target_dir = target_dir.trim().to_string();
+    if target_dir.is_empty() {
+        return Err("target_dir is required".to_string());
+    }
+    let app_data_dir = app.path().app_data_dir().map_err(|err| err.to_string())?;
+    let app_settings = settings.get_settings().map_err(|err| err.to_string())?;
+    let version = app.package_info().version.to_string();
+    let secrets: Vec<String> = app_settings
+        .secrets()
+        .into_iter()
+        .map(|(_, secret)| secret)
+        .collect();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let target_dir = PathBuf::from(target_dir);
+        std::fs::create_dir_all(&target_dir).map_err(|err| err.to_string())?;
+        let destination = target_dir.join(format!(
+            "crosssave-diagnostics-{}.zip",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        let about = json!({
+            "version": version,
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "exported_at": chrono::Utc::now().to_rfc3339(),
+        });
+        let mut files = vec![
+            (
+                "about.json".to_string(),
+                serde_json::to_vec_pretty(&about).map_err(|err| err.to_string())?,
+            ),
+            (
+                "settings.json".to_string(),
+                serde_json::to_vec_pretty(&sanitized_settings(&app_settings))
+                    .map_err(|err| err.to_string())?,
+            ),
+        ];
+        for name in DIAGNOSTIC_FILES {
+            if let Ok(content) = std::fs::read(app_data_dir.join(name)) {
+                files.push((name.to_string(), content));
+            }
+        }
+
+        write_bundle(&destination, &log_files().paths(), &files, &secrets)
+            .map_err(|err| err.to_string())?;
+        info!("[LOGS] Exported diagnostics to {:?}", destination);
+        Ok(destination.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
@@ -1,8 +1,20 @@
+pub mod account_api;
 pub mod cloud_api;
+pub mod conflict_api;
+pub mod convert_api;
+pub mod crash_api;
+pub mod deeplink_api;
 pub mod explorer_api;
+pub mod export_api;
+pub mod game_info_api;
 pub mod history_api;
+pub mod logs_api;
 pub mod packager_api;
+pub mod perf_api;
 pub mod profile_api;
+pub mod pruning_api;
 pub mod settings_api;
+pub mod startup_api;
 pub mod sync_api;
+pub mod update_api;
 pub mod watcher_api;
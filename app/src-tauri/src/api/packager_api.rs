@@ -1,19 +1,50 @@
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
-use serde::Serialize;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use tokio::sync::Semaphore;
 use tracing::{error, info, warn};
 
-use crate::core::history::{HistoryEntry, HistoryManager};
-use crate::core::packager::{PackagedSave, SavePackager};
+use crate::core::history::{normalize_note, HistoryEntry, HistoryError, HistoryManager};
+use crate::core::packager::{PackagePreview, PackagedSave, PackagerError, SavePackager};
+use crate::core::path_check::ensure_readable;
+use crate::core::paths::is_blank;
+use crate::core::profile::ProfileManager;
+use crate::core::staging::staging;
+use crate::core::storage::StorageScope;
+use crate::core::sync::{enforce_history_budget, report_storage_full, SyncManager, UploadJob};
+use crate::core::thumbnail::latest_screenshot;
+
+/// Packaging is CPU and disk bound; more workers mostly add contention
+const MAX_PARALLEL_PACKAGING: usize = 3;
+/// `SaveMetadata::source` of versions made with `create_snapshot`
+const MANUAL_SOURCE: &str = "manual";
+
+/// How `package_profile_game` treats the version it packages
+#[derive(Default)]
+struct PackageOptions {
+    /// Package even when the saves match the latest version
+    force: bool,
+    /// Note stored with the new version
+    note: Option<String>,
+    /// Recorded as the version's source instead of `local`
+    source: Option<&'static str>,
+}
 
 #[derive(Debug, Serialize)]
 pub struct PackageResponse {
     pub packaged: PackagedSave,
     pub history: HistoryEntry,
+    /// Nothing changed since `history`, so no new version was created
+    pub skipped: bool,
 }
 
 #[tauri::command]
 pub async fn package_save(
+    app: tauri::AppHandle,
     state: tauri::State<'_, HistoryManager>,
     game_id: String,
     emulator_id: String,
@@ -38,6 +69,7 @@ pub async fn package_save(
 
     let packager = SavePackager::new(game_id, emulator_id);
 
+    let packager_app = app.clone();
     let join_result = tauri::async_runtime::spawn_blocking(move || {
         let mut packager = packager;
         match packager.package_save(sanitized_paths, sanitized_patterns) {
@@ -47,6 +79,9 @@ pub async fn package_save(
             }
             Err(err) => {
                 error!("[PACKAGER] Packaging failed: {err}");
+                if let PackagerError::StorageFull(message) = &err {
+                    report_storage_full(&packager_app, StorageScope::Local, message.clone());
+                }
                 Err(err.to_string())
             }
         }
@@ -54,21 +89,26 @@ pub async fn package_save(
     .await
     .map_err(|err| err.to_string())?;
 
-    let packaged = join_result?;
+    let mut packaged = join_result?;
 
-    let history_entry = state
-        .save_to_history(
-            packaged.metadata.clone(),
-            PathBuf::from(&packaged.archive_path),
-        )
-        .map_err(|err| {
-            error!("[PACKAGER] Failed to write history: {err}");
-            err.to_string()
-        })?;
+    let saved = state.save_to_history(packaged.metadata.clone(), packaged.archive_path.clone());
+    // History has its own copy now, or the archive is no use
+    staging().release(&packaged.archive_path);
+    let history_entry = saved.map_err(|err| {
+        error!("[PACKAGER] Failed to write history: {err}");
+        if let HistoryError::StorageFull(message) = &err {
+            report_storage_full(&app, StorageScope::Local, message.clone());
+        }
+        err.to_string()
+    })?;
+    // The staged archive is gone; point at the one in history
+    packaged.archive_path = history_entry.archive_path.clone();
+    enforce_history_budget(&app, &state);
 
     Ok(PackageResponse {
         packaged,
         history: history_entry,
+        skipped: false,
     })
 }
 
@@ -109,22 +149,302 @@ pub async fn validate_paths(paths: Vec<String>) -> Result<Vec<String>, String> {
 /// Package a game using its emulator profile configuration
 #[tauri::command(rename_all = "snake_case")]
 pub async fn package_game(
+    app: tauri::AppHandle,
     history: tauri::State<'_, std::sync::Arc<HistoryManager>>,
     profiles: tauri::State<'_, std::sync::Arc<std::sync::RwLock<crate::core::profile::ProfileManager>>>,
     emulator_id: String,
     game_id: String,
+) -> Result<PackageResponse, String> {
+    package_profile_game(
+        app,
+        history.inner().clone(),
+        profiles.inner().clone(),
+        emulator_id,
+        game_id,
+        PackageOptions::default(),
+    )
+    .await
+}
+
+/// Package a game's saves right now, outside the watcher and sync schedule,
+/// and keep them in history under `label`. The emulator defaults to the one
+/// of the game's latest version. With `upload` the version is queued for
+/// the cloud straight away.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn create_snapshot(
+    app: tauri::AppHandle,
+    sync: tauri::State<'_, SyncManager>,
+    game_id: String,
+    label: Option<String>,
+    emulator_id: Option<String>,
+    upload: Option<bool>,
+) -> Result<PackageResponse, String> {
+    let game_id = game_id.trim().to_string();
+    if game_id.is_empty() {
+        return Err("game_id cannot be empty".into());
+    }
+    let note = normalize_note(label).map_err(|err| err.to_string())?;
+    let emulator_id = emulator_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .or_else(|| {
+            sync.history
+                .get_latest_version(&game_id)
+                .map(|entry| entry.metadata.emulator_id)
+        })
+        .ok_or_else(|| format!("{game_id} has no history; pass its emulator_id"))?;
+
+    let response = package_profile_game(
+        app,
+        sync.history.clone(),
+        sync.profiles.clone(),
+        emulator_id,
+        game_id,
+        PackageOptions {
+            force: true,
+            note,
+            source: Some(MANUAL_SOURCE),
+        },
+    )
+    .await?;
+
+    if upload.unwrap_or(false) {
+        sync.queue
+            .add_job(UploadJob::from_entry(&response.history))
+            .await;
+    }
+    info!(
+        "[PACKAGER] Snapshot {} saved for {}",
+        response.history.metadata.version_id, response.history.metadata.game_id
+    );
+    Ok(response)
+}
+
+/// Files, total size and estimated archive size `package_game` would produce
+#[tauri::command(rename_all = "snake_case")]
+pub async fn preview_package(
+    profiles: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+    emulator_id: String,
+    game_id: String,
+) -> Result<PackagePreview, String> {
+    let (
+        paths,
+        patterns,
+        exclude_patterns,
+        max_file_size_bytes,
+        save_encryption,
+        store_only,
+        placeholder_policy,
+    ) = {
+        let manager = profiles.read().map_err(|e| e.to_string())?;
+        let profile = manager
+            .get_profile(&emulator_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Profile {} not found", emulator_id))?;
+
+        let location = profile.save_location(Some(&game_id));
+        (
+            location.paths,
+            location.file_patterns,
+            profile.exclude_patterns.clone(),
+            profile.max_file_size_bytes,
+            profile.save_encryption,
+            profile.store_only_extensions.clone(),
+            profile.placeholder_policy,
+        )
+    };
+
+    let sanitized_paths: Vec<PathBuf> = paths.into_iter().filter(|path| !is_blank(path)).collect();
+    let sanitized_patterns: Vec<String> = patterns
+        .into_iter()
+        .filter(|pattern| !pattern.trim().is_empty())
+        .collect();
+
+    let mut packager = SavePackager::new(game_id, emulator_id);
+    packager.set_filters(exclude_patterns, max_file_size_bytes);
+    packager.set_encryption(save_encryption);
+    packager.add_store_only_extensions(store_only);
+    packager.set_placeholder_policy(placeholder_policy);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut packager = packager;
+        packager
+            .preview(sanitized_paths, sanitized_patterns)
+            .map_err(|err| {
+                warn!("[PACKAGER] Preview failed: {err}");
+                err.to_string()
+            })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Hash)]
+pub struct PackageGameRequest {
+    pub emulator_id: String,
+    pub game_id: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageStage {
+    Queued,
+    Started,
+    Completed,
+    /// The saves matched the last packaged version
+    Skipped,
+    Failed,
+}
+
+/// Per-game progress of `package_games`, emitted as `packager://progress`
+#[derive(Clone, Debug, Serialize)]
+pub struct PackageProgressPayload {
+    pub emulator_id: String,
+    pub game_id: String,
+    pub stage: PackageStage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackageGameResult {
+    pub emulator_id: String,
+    pub game_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<PackageResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Package several games at once, at most `MAX_PARALLEL_PACKAGING` at a time
+#[tauri::command]
+pub async fn package_games(
+    app: tauri::AppHandle,
+    history: tauri::State<'_, Arc<HistoryManager>>,
+    profiles: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+    requests: Vec<PackageGameRequest>,
+) -> Result<Vec<PackageGameResult>, String> {
+    let mut seen = HashSet::new();
+    let requests: Vec<PackageGameRequest> = requests
+        .into_iter()
+        .filter(|request| seen.insert(request.clone()))
+        .collect();
+
+    info!(
+        "[PACKAGER] Packaging {} games with up to {} workers",
+        requests.len(),
+        MAX_PARALLEL_PACKAGING
+    );
+
+    let permits = Arc::new(Semaphore::new(MAX_PARALLEL_PACKAGING));
+    let tasks = requests.into_iter().map(|request| {
+        emit_package_progress(&app, &request, PackageStage::Queued, None);
+
+        let app = app.clone();
+        let history = history.inner().clone();
+        let profiles = profiles.inner().clone();
+        let permits = permits.clone();
+        tauri::async_runtime::spawn(async move {
+            // The semaphore is never closed, so acquiring only waits for a slot
+            let _permit = permits.acquire_owned().await;
+            emit_package_progress(&app, &request, PackageStage::Started, None);
+
+            let result = package_profile_game(
+                app.clone(),
+                history,
+                profiles,
+                request.emulator_id.clone(),
+                request.game_id.clone(),
+                PackageOptions::default(),
+            )
+            .await;
+
+            match &result {
+                Ok(response) if response.skipped => {
+                    emit_package_progress(&app, &request, PackageStage::Skipped, None)
+                }
+                Ok(_) => emit_package_progress(&app, &request, PackageStage::Completed, None),
+                Err(err) => {
+                    emit_package_progress(&app, &request, PackageStage::Failed, Some(err.clone()))
+                }
+            }
+
+            let (response, error) = match result {
+                Ok(response) => (Some(response), None),
+                Err(err) => (None, Some(err)),
+            };
+            PackageGameResult {
+                emulator_id: request.emulator_id,
+                game_id: request.game_id,
+                response,
+                error,
+            }
+        })
+    });
+
+    let mut results = Vec::new();
+    for joined in join_all(tasks).await {
+        results.push(joined.map_err(|err| err.to_string())?);
+    }
+    Ok(results)
+}
+
+fn emit_package_progress(
+    app: &tauri::AppHandle,
+    request: &PackageGameRequest,
+    stage: PackageStage,
+    message: Option<String>,
+) {
+    let _ = app.emit(
+        "packager://progress",
+        PackageProgressPayload {
+            emulator_id: request.emulator_id.clone(),
+            game_id: request.game_id.clone(),
+            stage,
+            message,
+        },
+    );
+}
+
+async fn package_profile_game(
+    app: tauri::AppHandle,
+    history: Arc<HistoryManager>,
+    profiles: Arc<RwLock<ProfileManager>>,
+    emulator_id: String,
+    game_id: String,
+    options: PackageOptions,
 ) -> Result<PackageResponse, String> {
     // Get profile configuration
-    let (paths, patterns) = {
+    let (
+        paths,
+        patterns,
+        exclude_patterns,
+        max_file_size_bytes,
+        save_encryption,
+        store_only,
+        screenshot_dirs,
+        placeholder_policy,
+        lock_retry,
+        snapshot_copy_min_bytes,
+    ) = {
         let manager = profiles.read().map_err(|e| e.to_string())?;
         let profile = manager
             .get_profile(&emulator_id)
             .map_err(|e| e.to_string())?
             .ok_or_else(|| format!("Profile {} not found", emulator_id))?;
 
+        let location = profile.save_location(Some(&game_id));
         (
-            profile.default_save_paths.clone(),
-            profile.file_patterns.clone(),
+            location.paths,
+            location.file_patterns,
+            profile.exclude_patterns.clone(),
+            profile.max_file_size_bytes,
+            profile.save_encryption,
+            profile.store_only_extensions.clone(),
+            profile.screenshot_dirs.clone(),
+            profile.placeholder_policy,
+            profile.lock_retry,
+            profile.snapshot_copy_min_bytes,
         )
     };
 
@@ -133,35 +453,55 @@ pub async fn package_game(
         game_id, emulator_id
     );
 
-    // Convert to PathBuf
-    let sanitized_paths: Vec<PathBuf> = paths
-        .into_iter()
-        .filter(|path| !path.trim().is_empty())
-        .map(PathBuf::from)
-        .collect();
+    let sanitized_paths: Vec<PathBuf> = paths.into_iter().filter(|path| !is_blank(path)).collect();
 
     if sanitized_paths.is_empty() {
         warn!("[PACKAGER] No valid paths in profile {}", emulator_id);
         return Err(format!("Profile {} has no valid paths", emulator_id));
     }
+    ensure_readable(&sanitized_paths).map_err(|err| {
+        warn!("[PACKAGER] Save paths of {emulator_id} failed their check: {err}");
+        err.to_string()
+    })?;
 
     let sanitized_patterns: Vec<String> = patterns
         .into_iter()
         .filter(|pattern| !pattern.trim().is_empty())
         .collect();
 
+    // Skip packaging when the files match the latest version's fingerprint
+    let latest = history.get_latest_version(&game_id);
+
     // Package the save
-    let packager = SavePackager::new(game_id, emulator_id);
+    let mut packager = SavePackager::new(game_id, emulator_id);
+    if !options.force {
+        packager.set_previous_fingerprint(
+            latest
+                .as_ref()
+                .and_then(|entry| entry.metadata.fingerprint.clone()),
+        );
+    }
+    packager.set_filters(exclude_patterns, max_file_size_bytes);
+    packager.set_encryption(save_encryption);
+    packager.add_store_only_extensions(store_only);
+    packager.set_placeholder_policy(placeholder_policy);
+    packager.set_lock_retry(lock_retry);
+    packager.set_snapshot_threshold(snapshot_copy_min_bytes);
 
+    let packager_app = app.clone();
     let join_result = tauri::async_runtime::spawn_blocking(move || {
         let mut packager = packager;
         match packager.package_save(sanitized_paths, sanitized_patterns) {
             Ok(result) => {
                 info!("[PACKAGER] Game packaging completed");
-                Ok(result)
+                Ok(Some(result))
             }
+            Err(PackagerError::Unchanged) => Ok(None),
             Err(err) => {
                 error!("[PACKAGER] Game packaging failed: {err}");
+                if let PackagerError::StorageFull(message) = &err {
+                    report_storage_full(&packager_app, StorageScope::Local, message.clone());
+                }
                 Err(err.to_string())
             }
         }
@@ -169,23 +509,46 @@ pub async fn package_game(
     .await
     .map_err(|err| err.to_string())?;
 
-    let packaged = join_result?;
+    let Some(mut packaged) = join_result? else {
+        let entry = latest.ok_or_else(|| PackagerError::Unchanged.to_string())?;
+        return Ok(PackageResponse {
+            packaged: PackagedSave {
+                archive_path: entry.archive_path.clone(),
+                metadata: entry.metadata.clone(),
+            },
+            history: entry,
+            skipped: true,
+        });
+    };
+
+    // Attach the emulator's screenshot of this save, if it keeps one
+    let mut history_metadata = packaged.metadata.clone();
+    history_metadata.thumbnail = latest_screenshot(&screenshot_dirs, history_metadata.timestamp)
+        .map(|path| path.to_string_lossy().to_string());
+    if let Some(source) = options.source {
+        history_metadata.source = Some(source.to_string());
+    }
+    history_metadata.note = options.note;
 
     // Save to history
-    let history_entry = history
-        .save_to_history(
-            packaged.metadata.clone(),
-            PathBuf::from(&packaged.archive_path),
-        )
-        .map_err(|err| {
-            error!("[PACKAGER] Failed to write history: {err}");
-            err.to_string()
-        })?;
+    let saved = history.save_to_history(history_metadata, packaged.archive_path.clone());
+    staging().release(&packaged.archive_path);
+    let history_entry = saved.map_err(|err| {
+        error!("[PACKAGER] Failed to write history: {err}");
+        if let HistoryError::StorageFull(message) = &err {
+            report_storage_full(&app, StorageScope::Local, message.clone());
+        }
+        err.to_string()
+    })?;
+    // The staged archive is gone; point at the one in history
+    packaged.archive_path = history_entry.archive_path.clone();
+    enforce_history_budget(&app, &history);
 
     info!("[PACKAGER] Game saved to history");
 
     Ok(PackageResponse {
         packaged,
         history: history_entry,
+        skipped: false,
     })
 }
@@ -0,0 +1,24 @@
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::core::perf::{run_perf_report, FixtureSpec, PerfReport};
+
+/// Time packaging, hashing, extraction and history indexing on this
+/// machine, to help tell a slow disk or CPU apart from a slow server
+#[tauri::command]
+pub async fn get_perf_report(app: AppHandle) -> Result<PerfReport, String> {
+    let work_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|err| err.to_string())?
+        .join("data")
+        .join("perf")
+        .join(Uuid::new_v4().to_string());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        run_perf_report(&work_dir, &FixtureSpec::default())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())
+}
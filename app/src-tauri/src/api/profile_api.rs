@@ -1,8 +1,17 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
+use reqwest::Client;
 use tracing::{info, warn};
 
+use crate::core::process_watch::{EmulatorActivity, ProcessWatch};
 use crate::core::profile::{EmulatorProfile, ProfileError, ProfileManager};
+use crate::core::profile_bundle::MAX_PROFILE_DATA_BYTES;
+use crate::core::retroarch::{self, RetroArchImport, RETROARCH_PROFILE_ID};
+use crate::core::steam;
 
 fn map_profile_error(err: ProfileError) -> String {
     err.to_string()
@@ -33,6 +42,19 @@ pub async fn get_profile(
     Ok(profile)
 }
 
+/// Whether the emulator is running and busy, going by its `process_names`.
+/// Packaging while it is `busy` can capture a half-written save.
+#[tauri::command]
+pub async fn get_emulator_activity(
+    watch: tauri::State<'_, Arc<ProcessWatch>>,
+    emulator_id: String,
+) -> Result<EmulatorActivity, String> {
+    let watch = watch.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || watch.activity(&emulator_id))
+        .await
+        .map_err(|err| err.to_string())
+}
+
 #[tauri::command]
 pub async fn save_profile(
     state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
@@ -50,3 +72,138 @@ pub async fn delete_profile(
     let mut mgr = state.write().unwrap();
     mgr.delete_profile(&emulator_id).map_err(map_profile_error)
 }
+
+#[tauri::command]
+pub async fn discover_steam_profiles(
+    state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+) -> Result<Vec<EmulatorProfile>, String> {
+    let discovered = tauri::async_runtime::spawn_blocking(steam::discover_steam_profiles)
+        .await
+        .map_err(|err| err.to_string())?;
+    info!("[PROFILE] Steam discovery found {} candidates", discovered.len());
+
+    let mgr = state.read().map_err(|err| err.to_string())?;
+    mgr.merge_suggestions(discovered).map_err(map_profile_error)
+}
+
+/// Read RetroArch's config and playlists and point the RetroArch profile
+/// at each game's own save files, with game IDs taken from the playlist
+/// labels instead of ROM file names. `config_path` defaults to the usual
+/// install locations.
+#[tauri::command]
+pub async fn import_retroarch_library(
+    state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+    config_path: Option<String>,
+) -> Result<RetroArchImport, String> {
+    let config_path = config_path
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from);
+    let import = tauri::async_runtime::spawn_blocking(move || {
+        retroarch::scan_library(config_path.as_deref())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+    .map_err(|err| err.to_string())?;
+
+    let mut mgr = state.write().map_err(|err| err.to_string())?;
+    let mut profile = mgr
+        .get_profile(RETROARCH_PROFILE_ID)
+        .map_err(map_profile_error)?
+        .ok_or_else(|| "RetroArch profile not found".to_string())?;
+    retroarch::apply_to_profile(&mut profile, &import.games);
+    mgr.save_profile(profile).map_err(map_profile_error)?;
+    info!(
+        "[PROFILE] Imported {} RetroArch games from {:?}",
+        import.games.len(),
+        import.config_path
+    );
+    Ok(import)
+}
+
+#[tauri::command]
+pub async fn list_suggested_profiles(
+    state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+) -> Result<Vec<EmulatorProfile>, String> {
+    let mgr = state.read().map_err(|err| err.to_string())?;
+    mgr.list_suggested_profiles().map_err(map_profile_error)
+}
+
+#[tauri::command]
+pub async fn export_profile(
+    state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+    emulator_id: String,
+    destination: String,
+) -> Result<(), String> {
+    let mgr = state.read().map_err(|err| err.to_string())?;
+    mgr.export_profile(&emulator_id, &PathBuf::from(destination))
+        .map_err(map_profile_error)
+}
+
+/// Install a profile JSON or `.crossprofile` bundle from `path`. One with
+/// the same id as an existing profile is refused unless `replace` is set.
+#[tauri::command]
+pub async fn import_profile(
+    state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+    path: String,
+    replace: Option<bool>,
+) -> Result<EmulatorProfile, String> {
+    let read_path = path.clone();
+    let data = tauri::async_runtime::spawn_blocking(move || std::fs::read(read_path))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| format!("Failed to read {path}: {err}"))?;
+    let mut mgr = state.write().map_err(|err| err.to_string())?;
+    mgr.import_profile_data(&data, replace.unwrap_or(false))
+        .map_err(map_profile_error)
+}
+
+/// Fetch a profile JSON or `.crossprofile` bundle over HTTPS and install it as
+/// a user profile. Existing profiles are only replaced when `replace` is set.
+#[tauri::command]
+pub async fn install_profile_from_url(
+    state: tauri::State<'_, Arc<RwLock<ProfileManager>>>,
+    url: String,
+    replace: Option<bool>,
+) -> Result<EmulatorProfile, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|err| format!("Invalid URL: {err}"))?;
+    if parsed.scheme() != "https" {
+        return Err("Profiles can only be installed over https".to_string());
+    }
+
+    info!("[PROFILE] Installing profile from {}", parsed);
+    let client = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to build client: {e}"))?;
+
+    let mut response = client
+        .get(parsed)
+        .send()
+        .await
+        .map_err(|err| format!("Failed to download profile: {err}"))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to download profile: status {}",
+            response.status()
+        ));
+    }
+
+    let mut data: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| format!("Failed to download profile: {err}"))?
+    {
+        if data.len() + chunk.len() > MAX_PROFILE_DATA_BYTES {
+            return Err(format!(
+                "Profile exceeds the {} byte limit",
+                MAX_PROFILE_DATA_BYTES
+            ));
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    let mut mgr = state.write().map_err(|err| err.to_string())?;
+    mgr.import_profile_data(&data, replace.unwrap_or(false))
+        .map_err(map_profile_error)
+}
@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::State;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::core::cloud::{CloudBackend, CloudVersionSummary};
+use crate::core::history::HistoryManager;
+use crate::core::pruning::{self, DeletionConfirmations, PruneLocation, PruneSuggestion};
+use crate::core::settings::{CloudMode, SettingsManager};
+
+#[derive(Debug, Serialize)]
+pub struct PruneReport {
+    pub local: Vec<PruneSuggestion>,
+    pub cloud: Vec<PruneSuggestion>,
+    pub reclaim_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PruneOutcome {
+    pub suggestion: PruneSuggestion,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+/// Cloud versions a deletion covers. A preview carries the confirmation
+/// token; once carried out it carries one outcome per version instead.
+#[derive(Debug, Serialize)]
+pub struct CloudDeletion {
+    pub game_id: String,
+    pub versions: Vec<CloudVersionSummary>,
+    pub reclaim_bytes: u64,
+    pub confirmation_token: Option<String>,
+    pub outcomes: Vec<CloudDeletionOutcome>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CloudDeletionOutcome {
+    pub version_id: String,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+/// Find versions that duplicate a newer kept version, locally and
+/// optionally in the cloud. Nothing is deleted here.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn suggest_pruning(
+    history: State<'_, Arc<HistoryManager>>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    settings: State<'_, Arc<SettingsManager>>,
+    game_id: Option<String>,
+    include_cloud: bool,
+) -> Result<PruneReport, String> {
+    let game_id = game_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+
+    let cloud_enabled = include_cloud
+        && settings
+            .get_settings()
+            .map(|settings| settings.cloud_mode != CloudMode::Off)
+            .map_err(|err| format!("Failed to load settings: {err}"))?;
+
+    let mut games = match &game_id {
+        Some(id) => vec![id.clone()],
+        None => history.get_games(),
+    };
+
+    let mut cloud_versions: Vec<(String, Vec<CloudVersionSummary>)> = Vec::new();
+    if cloud_enabled {
+        let backend = cloud.lock().await;
+        if game_id.is_none() {
+            match backend.list_games().await {
+                Ok(cloud_games) => {
+                    for cloud_game in cloud_games {
+                        if !games.contains(&cloud_game) {
+                            games.push(cloud_game);
+                        }
+                    }
+                }
+                Err(err) => warn!("[CLOUD] Failed to list games for pruning: {err}"),
+            }
+        }
+
+        for game in &games {
+            match backend.list_versions(game.clone(), None).await {
+                Ok(versions) => cloud_versions.push((game.clone(), versions)),
+                Err(err) => warn!("[CLOUD] Failed to list versions of {game} for pruning: {err}"),
+            }
+        }
+    }
+
+    let history = history.inner().clone();
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        let mut local = Vec::new();
+        let mut cloud = Vec::new();
+
+        for game in &games {
+            let entries = history.list_history(game.clone()).unwrap_or_else(|err| {
+                warn!("[HISTORY] Failed to list history of {game} for pruning: {err}");
+                Vec::new()
+            });
+            local.extend(pruning::local_suggestions(&entries));
+
+            if let Some((_, versions)) = cloud_versions.iter().find(|(id, _)| id == game) {
+                cloud.extend(pruning::cloud_suggestions(game, versions, &entries));
+            }
+        }
+
+        let reclaim_bytes = local
+            .iter()
+            .chain(cloud.iter())
+            .map(|suggestion| suggestion.reclaim_bytes)
+            .sum();
+        PruneReport {
+            local,
+            cloud,
+            reclaim_bytes,
+        }
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+
+    info!(
+        "[HISTORY] Pruning suggestions: {} local, {} cloud",
+        report.local.len(),
+        report.cloud.len()
+    );
+    Ok(report)
+}
+
+/// Delete the given suggestions, re-checking pins first. Each suggestion is
+/// applied independently so one failure does not stop the rest.
+#[tauri::command]
+pub async fn apply_pruning(
+    history: State<'_, Arc<HistoryManager>>,
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    suggestions: Vec<PruneSuggestion>,
+) -> Result<Vec<PruneOutcome>, String> {
+    let mut outcomes = Vec::with_capacity(suggestions.len());
+
+    for suggestion in suggestions {
+        let result = match suggestion.location {
+            PruneLocation::Local => prune_local(&history, &suggestion),
+            PruneLocation::Cloud => {
+                let backend = cloud.lock().await;
+                backend
+                    .delete_version(suggestion.game_id.clone(), suggestion.version_id.clone())
+                    .await
+                    .map_err(|err| err.to_string())
+            }
+        };
+
+        if let Err(err) = &result {
+            warn!(
+                "[HISTORY] Skipped pruning {}:{}: {err}",
+                suggestion.game_id, suggestion.version_id
+            );
+        }
+        outcomes.push(PruneOutcome {
+            deleted: result.is_ok(),
+            error: result.err(),
+            suggestion,
+        });
+    }
+
+    let deleted = outcomes.iter().filter(|outcome| outcome.deleted).count();
+    info!("[HISTORY] Pruned {deleted} of {} versions", outcomes.len());
+    Ok(outcomes)
+}
+
+fn prune_local(history: &HistoryManager, suggestion: &PruneSuggestion) -> Result<(), String> {
+    let entry = history
+        .get_history_item(suggestion.game_id.clone(), suggestion.version_id.clone())
+        .map_err(|err| err.to_string())?;
+    if entry.metadata.pinned {
+        return Err("version is pinned".to_string());
+    }
+
+    // Only prune while the version that covers this one is still around
+    history
+        .get_history_item(
+            suggestion.game_id.clone(),
+            suggestion.kept_version_id.clone(),
+        )
+        .map_err(|_| format!("kept version {} is gone", suggestion.kept_version_id))?;
+
+    history
+        .delete_history_item(suggestion.game_id.clone(), suggestion.version_id.clone())
+        .map_err(|err| err.to_string())
+}
+
+/// Delete one cloud version. Without `confirmation_token` nothing is
+/// deleted: the preview returned holds a token to call again with.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn delete_cloud_version(
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    confirmations: State<'_, Arc<DeletionConfirmations>>,
+    game_id: String,
+    version_id: String,
+    confirmation_token: Option<String>,
+) -> Result<CloudDeletion, String> {
+    let Some(token) = confirmation_token else {
+        let version = list_cloud_versions(&cloud, &game_id)
+            .await?
+            .into_iter()
+            .find(|version| version.version_id == version_id)
+            .ok_or_else(|| format!("Version {version_id} is not in the cloud"))?;
+        if version.pinned {
+            return Err(format!("Version {version_id} is pinned"));
+        }
+        return deletion_preview(&confirmations, game_id, vec![version]);
+    };
+
+    let version_ids = confirmations
+        .redeem(&token, &game_id)
+        .map_err(|err| err.to_string())?;
+    if version_ids != [version_id.as_str()] {
+        return Err("Confirmation was issued for other versions".to_string());
+    }
+    delete_confirmed(&cloud, game_id, version_ids).await
+}
+
+/// Delete every cloud version of `game_id` past the newest `keep_n`; pinned
+/// versions stay. Confirmed with a token the same way as `delete_cloud_version`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn prune_cloud_versions(
+    cloud: State<'_, Arc<Mutex<Box<dyn CloudBackend + Send>>>>,
+    confirmations: State<'_, Arc<DeletionConfirmations>>,
+    game_id: String,
+    keep_n: usize,
+    confirmation_token: Option<String>,
+) -> Result<CloudDeletion, String> {
+    let Some(token) = confirmation_token else {
+        let versions = list_cloud_versions(&cloud, &game_id).await?;
+        let doomed = pruning::versions_beyond(&versions, keep_n);
+        return deletion_preview(&confirmations, game_id, doomed);
+    };
+
+    let version_ids = confirmations
+        .redeem(&token, &game_id)
+        .map_err(|err| err.to_string())?;
+    delete_confirmed(&cloud, game_id, version_ids).await
+}
+
+async fn list_cloud_versions(
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    game_id: &str,
+) -> Result<Vec<CloudVersionSummary>, String> {
+    cloud
+        .lock()
+        .await
+        .list_versions(game_id.to_string(), None)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+fn deletion_preview(
+    confirmations: &DeletionConfirmations,
+    game_id: String,
+    versions: Vec<CloudVersionSummary>,
+) -> Result<CloudDeletion, String> {
+    let version_ids = versions.iter().map(|v| v.version_id.clone()).collect();
+    let token = confirmations
+        .issue(&game_id, version_ids)
+        .map_err(|err| err.to_string())?;
+    Ok(CloudDeletion {
+        reclaim_bytes: versions.iter().map(|version| version.size_bytes).sum(),
+        game_id,
+        versions,
+        confirmation_token: Some(token),
+        outcomes: Vec::new(),
+    })
+}
+
+/// Delete the confirmed versions that are still in the cloud, each one
+/// independently so one failure does not stop the rest
+async fn delete_confirmed(
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    game_id: String,
+    version_ids: Vec<String>,
+) -> Result<CloudDeletion, String> {
+    let listed = list_cloud_versions(cloud, &game_id).await?;
+    let backend = cloud.lock().await;
+
+    let mut versions = Vec::with_capacity(version_ids.len());
+    let mut outcomes = Vec::with_capacity(version_ids.len());
+    for version_id in version_ids {
+        let Some(version) = listed.iter().find(|v| v.version_id == version_id) else {
+            outcomes.push(CloudDeletionOutcome {
+                version_id,
+                deleted: false,
+                error: Some("no longer in the cloud".to_string()),
+            });
+            continue;
+        };
+
+        let result = backend
+            .delete_version(game_id.clone(), version_id.clone())
+            .await
+            .map_err(|err| err.to_string());
+        if let Err(err) = &result {
+            warn!("[CLOUD] Failed to delete {game_id}:{version_id}: {err}");
+        }
+        if result.is_ok() {
+            versions.push(version.clone());
+        }
+        outcomes.push(CloudDeletionOutcome {
+            version_id,
+            deleted: result.is_ok(),
+            error: result.err(),
+        });
+    }
+
+    info!(
+        "[CLOUD] Deleted {} of {} cloud versions of {game_id}",
+        versions.len(),
+        outcomes.len()
+    );
+    Ok(CloudDeletion {
+        reclaim_bytes: versions.iter().map(|version| version.size_bytes).sum(),
+        game_id,
+        versions,
+        confirmation_token: None,
+        outcomes,
+    })
+}
@@ -1,11 +1,19 @@
 use serde::Serialize;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::info;
+use tauri::Manager;
+use tracing::{error, info};
 
+use crate::core::crash::CrashReporter;
 use crate::core::history::HistoryManager;
+use crate::core::notifications::request_permission as request_notification_permission;
 use crate::core::settings::{
-    default_retention_bounds, AppSettings, SettingsError, SettingsManager,
+    default_retention_bounds, AppSettings, CloudCredentials, RetentionSettings, SettingsError,
+    SettingsIssue, SettingsManager,
 };
+use crate::core::staging::{staging, StagingStats};
+use crate::core::storage::{clear_dir, dir_size, volume_space, VolumeSpace};
+use crate::core::sync::SyncManager;
 
 #[derive(Debug, Serialize)]
 pub struct StorageInfo {
@@ -13,6 +21,38 @@ pub struct StorageInfo {
     pub total_size_bytes: u64,
     pub total_versions: usize,
     pub retention_bounds: (usize, usize),
+    /// History per game, largest first
+    pub games: Vec<GameStorage>,
+    pub cloud_downloads_path: String,
+    /// Downloaded cloud versions and files, cleared by `clear_downloads_cache`
+    pub cloud_downloads_bytes: u64,
+    /// Archives in the temp directory between packaging and history
+    pub temp_archives: StagingStats,
+    pub sync_queue: QueueStorage,
+    /// Volume holding the history; `None` when the OS doesn't report it
+    pub volume: Option<VolumeSpace>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameStorage {
+    pub game_id: String,
+    pub size_bytes: u64,
+    pub versions: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueueStorage {
+    pub uploads: usize,
+    pub downloads: usize,
+    /// Archive bytes still to upload
+    pub pending_upload_bytes: u64,
+}
+
+fn cloud_downloads_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("data").join("cloud_downloads"))
+        .map_err(|err| format!("path error: {err}"))
 }
 
 fn map_settings_error(err: SettingsError) -> String {
@@ -28,42 +68,175 @@ pub async fn get_app_settings(
 
 #[tauri::command]
 pub async fn update_app_settings(
+    app: tauri::AppHandle,
     state: tauri::State<'_, Arc<SettingsManager>>,
-    history: tauri::State<'_, Arc<HistoryManager>>,
-    settings: AppSettings,
+    mut settings: AppSettings,
 ) -> Result<AppSettings, String> {
+    let mut notifications_were_enabled = false;
     let updated = state
-        .update_settings(settings)
+        .update_with(|current| {
+            notifications_were_enabled = current.notifications.enabled;
+            // Only `relocate_history` moves the history, along with its files
+            settings.history_dir = current.history_dir.take();
+            *current = settings;
+        })
         .map_err(map_settings_error)?;
-    if let Err(err) = history.set_policy(updated.retention_limit, updated.auto_delete) {
-        return Err(err.to_string());
+    if let Some(reporter) = app.try_state::<Arc<CrashReporter>>() {
+        reporter.set_enabled(updated.crash_reports.enabled);
     }
+    if updated.notifications.enabled && !notifications_were_enabled {
+        request_notification_permission(&app);
+    }
+    #[cfg(desktop)]
+    crate::core::desktop::apply_autostart(&app, &updated.desktop);
 
     Ok(updated)
 }
 
+/// Whether tokens and access keys sit in settings.json in plain text
+/// because the OS keychain is missing or refused them
+#[tauri::command]
+pub async fn secrets_in_plaintext(
+    state: tauri::State<'_, Arc<SettingsManager>>,
+) -> Result<bool, String> {
+    Ok(state.secrets_in_plaintext())
+}
+
+/// Change the history's retention settings and nothing else
+#[tauri::command]
+pub async fn update_retention(
+    state: tauri::State<'_, Arc<SettingsManager>>,
+    retention: RetentionSettings,
+) -> Result<AppSettings, String> {
+    state
+        .update_with(|settings| settings.set_retention(retention))
+        .map_err(map_settings_error)
+}
+
+/// Change the sign-in details of the official or a self-hosted server and
+/// nothing else. Switching servers is left to `update_cloud_mode`.
+#[tauri::command]
+pub async fn update_cloud_credentials(
+    state: tauri::State<'_, Arc<SettingsManager>>,
+    credentials: CloudCredentials,
+) -> Result<AppSettings, String> {
+    state
+        .update_with(|settings| settings.set_credentials(credentials))
+        .map_err(map_settings_error)
+}
+
+/// Every value in `settings` that saving them would reject, by field, so
+/// the settings form can point at each one
+#[tauri::command]
+pub async fn validate_app_settings(settings: AppSettings) -> Result<Vec<SettingsIssue>, String> {
+    Ok(settings
+        .issues()
+        .iter()
+        .filter_map(SettingsError::issue)
+        .collect())
+}
+
+/// Disk usage of history, caches and queues, and the space left on the
+/// history's volume
 #[tauri::command]
 pub async fn get_storage_info(
+    app: tauri::AppHandle,
     history: tauri::State<'_, Arc<HistoryManager>>,
+    sync: tauri::State<'_, SyncManager>,
 ) -> Result<StorageInfo, String> {
-    let total_size_bytes = history.total_size().map_err(|err| err.to_string())?;
-    let bounds = default_retention_bounds();
-    
-    // Count total versions across all games
-    let games = history.get_games();
-    let mut total_versions = 0;
-    for game_id in games {
-        if let Ok(entries) = history.list_history(game_id) {
-            total_versions += entries.len();
+    let status = sync.queue.get_status().await;
+    let sync_queue = QueueStorage {
+        uploads: status.queue_length + usize::from(status.active_job.is_some()),
+        downloads: status.download_queue_length + usize::from(status.active_download.is_some()),
+        pending_upload_bytes: sync.queue.pending_bytes().await,
+    };
+    let downloads_dir = cloud_downloads_dir(&app)?;
+    let history = history.inner().clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let total_size_bytes = history.total_size().map_err(|err| err.to_string())?;
+        let bounds = default_retention_bounds();
+
+        // Count versions and size per game
+        let mut games = Vec::new();
+        let mut total_versions = 0;
+        for game_id in history.get_games() {
+            let versions = history
+                .list_history(game_id.clone())
+                .map(|entries| entries.len())
+                .unwrap_or(0);
+            total_versions += versions;
+            games.push(GameStorage {
+                size_bytes: history.game_size(&game_id).unwrap_or(0),
+                game_id,
+                versions,
+            });
         }
-    }
-    
-    Ok(StorageInfo {
-        history_path: history.base_dir.to_string_lossy().to_string(),
-        total_size_bytes,
-        total_versions,
-        retention_bounds: bounds,
+        games.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes));
+
+        Ok(StorageInfo {
+            history_path: history.base_dir().to_string_lossy().to_string(),
+            total_size_bytes,
+            total_versions,
+            retention_bounds: bounds,
+            games,
+            cloud_downloads_path: downloads_dir.to_string_lossy().to_string(),
+            cloud_downloads_bytes: dir_size(&downloads_dir),
+            temp_archives: staging().stats(),
+            sync_queue,
+            volume: volume_space(&history.base_dir()),
+        })
     })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Delete downloaded cloud versions and files. Returns the bytes freed.
+#[tauri::command]
+pub async fn clear_downloads_cache(
+    app: tauri::AppHandle,
+    sync: tauri::State<'_, SyncManager>,
+) -> Result<u64, String> {
+    // The active download is written into the cache
+    if sync.queue.get_status().await.active_download.is_some() {
+        return Err("A download is in progress; try again once it finishes".to_string());
+    }
+    let downloads_dir = cloud_downloads_dir(&app)?;
+    let freed = tauri::async_runtime::spawn_blocking(move || clear_dir(&downloads_dir))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| err.to_string())?;
+    info!("[STORAGE] Cleared {freed} bytes of cloud downloads");
+    Ok(freed)
+}
+
+/// Move the history to `new_path`, e.g. on a bigger drive or SD card, and
+/// keep it there from now on. Returns the updated settings.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn relocate_history(
+    state: tauri::State<'_, Arc<SettingsManager>>,
+    history: tauri::State<'_, Arc<HistoryManager>>,
+    new_path: String,
+) -> Result<AppSettings, String> {
+    let new_path = new_path.trim().to_string();
+    if new_path.is_empty() {
+        return Err("new_path cannot be empty".into());
+    }
+
+    let history = history.inner().clone();
+    let target = PathBuf::from(&new_path);
+    let games = tauri::async_runtime::spawn_blocking(move || history.relocate(&target))
+        .await
+        .map_err(|err| err.to_string())?
+        .map_err(|err| {
+            error!("[HISTORY] Failed to move history to {new_path}: {err}");
+            err.to_string()
+        })?;
+    info!("[HISTORY] Moved history to {new_path} ({games} games)");
+
+    state
+        .update_with(|settings| settings.history_dir = Some(new_path))
+        .map_err(map_settings_error)
 }
 
 #[tauri::command]
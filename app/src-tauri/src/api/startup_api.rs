@@ -0,0 +1,10 @@
+use tauri::State;
+
+use crate::core::startup::{StartupState, SubsystemState};
+
+#[tauri::command]
+pub async fn get_startup_state(
+    startup: State<'_, StartupState>,
+) -> Result<Vec<SubsystemState>, String> {
+    Ok(startup.snapshot())
+}
@@ -1,6 +1,28 @@
-use crate::core::sync::{SyncManager, SyncStatus};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tauri::State;
 
+use crate::core::conflict::{ConflictManager, ConflictRecord};
+use crate::core::connectivity::ConditionsPayload;
+use crate::core::reconcile::{
+    apply_choices, build_report, ReconcileChoice, ReconcileOutcome, ReconciliationReport,
+};
+use crate::core::sync::{SyncManager, SyncStatus};
+use crate::core::sync_state::{SyncEvent, SyncStateStore};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameSyncStatus {
+    pub game_id: String,
+    pub last_sync: Option<DateTime<Utc>>,
+    pub last_upload: Option<SyncEvent>,
+    pub last_download: Option<SyncEvent>,
+    pub last_conflict_at: Option<DateTime<Utc>>,
+    /// Most recent resolution of a conflict for this game
+    pub last_resolution: Option<ConflictRecord>,
+}
+
 #[tauri::command]
 pub async fn get_sync_status(sync: State<'_, SyncManager>) -> Result<SyncStatus, String> {
     Ok(sync.queue.get_status().await)
@@ -15,5 +37,72 @@ pub async fn force_sync_now(sync: State<'_, SyncManager>) -> Result<(), String>
 #[tauri::command]
 pub async fn clear_sync_queue(sync: State<'_, SyncManager>) -> Result<(), String> {
     sync.queue.clear().await;
+    sync.downloads.clear().await;
     Ok(())
 }
+
+/// Resume sync after it was paused, e.g. by a full disk or cloud quota
+#[tauri::command]
+pub async fn resume_sync(sync: State<'_, SyncManager>) -> Result<(), String> {
+    sync.resume();
+    Ok(())
+}
+
+/// Network and battery state, and whether it is holding uploads or downloads
+#[tauri::command]
+pub async fn get_device_conditions(
+    sync: State<'_, SyncManager>,
+) -> Result<ConditionsPayload, String> {
+    Ok(sync.device_conditions())
+}
+
+/// When a game last uploaded, downloaded and hit a conflict
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_game_sync_status(
+    sync_state: State<'_, Arc<SyncStateStore>>,
+    conflicts: State<'_, Arc<ConflictManager>>,
+    game_id: String,
+) -> Result<GameSyncStatus, String> {
+    let game = sync_state.game(&game_id).map_err(|err| err.to_string())?;
+    let last_resolution = conflicts
+        .records(Some(&game_id))
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .next();
+
+    Ok(GameSyncStatus {
+        last_sync: game.last_sync(),
+        last_upload: game.last_upload,
+        last_download: game.last_download,
+        last_conflict_at: game.last_conflict_at,
+        last_resolution,
+        game_id,
+    })
+}
+
+/// Latest local and cloud version of every game, for the first sync of an
+/// account whose cloud already has saves
+#[tauri::command]
+pub async fn get_reconciliation_report(
+    sync: State<'_, SyncManager>,
+) -> Result<ReconciliationReport, String> {
+    let current_device = sync
+        .settings
+        .get_settings()
+        .map(|s| s.cloud.device_id)
+        .map_err(|err| err.to_string())?;
+    build_report(&sync.cloud, &sync.history, &current_device)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Queue the chosen direction per game and release the first sync
+#[tauri::command(rename_all = "snake_case")]
+pub async fn apply_reconciliation(
+    sync: State<'_, SyncManager>,
+    choices: Vec<ReconcileChoice>,
+) -> Result<ReconcileOutcome, String> {
+    apply_choices(&sync, choices)
+        .await
+        .map_err(|err| err.to_string())
+}
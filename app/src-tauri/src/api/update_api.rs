@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use tauri::{AppHandle, State};
+use tracing::info;
+
+use crate::core::settings::SettingsManager;
+use crate::core::updater::{self, check_for_update, UpdateInfo};
+
+/// Look for a newer release now; `None` when up to date or not yet rolled
+/// out to this device
+#[tauri::command]
+pub async fn check_for_updates(
+    app: AppHandle,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<Option<UpdateInfo>, String> {
+    let current_version = app.package_info().version.to_string();
+    check_for_update(&settings, &current_version)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Install the offered release through Tauri's updater and restart into it.
+/// Fails when the release or this build can't be installed in place.
+#[tauri::command]
+pub async fn install_update(
+    app: AppHandle,
+    settings: State<'_, Arc<SettingsManager>>,
+) -> Result<(), String> {
+    let version = updater::install_update(&app, &settings)
+        .await
+        .map_err(|err| err.to_string())?;
+    info!("[UPDATE] Installed version {version}; restarting");
+    app.restart()
+}
@@ -2,19 +2,21 @@ use std::path::PathBuf;
 
 use tracing::{error, info, warn};
 
-use crate::core::watcher::{WatcherError, WatcherManager};
+use crate::core::watcher::{WatchOptions, WatcherError, WatcherManager};
 
+/// Start a watch session and return its id
 #[tauri::command]
 pub async fn start_watcher(
     app: tauri::AppHandle,
     state: tauri::State<'_, WatcherManager>,
     paths: Vec<String>,
-) -> Result<(), String> {
+    options: Option<WatchOptions>,
+) -> Result<String, String> {
     let resolved_paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
-    match state.start(app, resolved_paths) {
-        Ok(_) => {
-            info!("[WATCHER] Watcher started from API");
-            Ok(())
+    match state.start(app, resolved_paths, options.unwrap_or_default()) {
+        Ok(session_id) => {
+            info!("[WATCHER] Watcher session {session_id} started from API");
+            Ok(session_id)
         }
         Err(err) => {
             error!("[WATCHER] Failed to start watcher: {err}");
@@ -23,15 +25,18 @@ pub async fn start_watcher(
     }
 }
 
-#[tauri::command]
-pub async fn stop_watcher(state: tauri::State<'_, WatcherManager>) -> Result<(), String> {
-    match state.stop().await {
+#[tauri::command(rename_all = "snake_case")]
+pub async fn stop_watcher(
+    state: tauri::State<'_, WatcherManager>,
+    session_id: String,
+) -> Result<(), String> {
+    match state.stop(&session_id).await {
         Ok(_) => {
-            info!("[WATCHER] Watcher stopped from API");
+            info!("[WATCHER] Watcher session {session_id} stopped from API");
             Ok(())
         }
-        Err(WatcherError::NotRunning) => {
-            warn!("[WATCHER] Stop requested but watcher not running");
+        Err(WatcherError::NotRunning(_)) => {
+            warn!("[WATCHER] Stop requested but session {session_id} not running");
             Ok(())
         }
         Err(err) => {
@@ -40,3 +45,10 @@ pub async fn stop_watcher(state: tauri::State<'_, WatcherManager>) -> Result<(),
         }
     }
 }
+
+#[tauri::command]
+pub async fn list_watcher_sessions(
+    state: tauri::State<'_, WatcherManager>,
+) -> Result<Vec<String>, String> {
+    Ok(state.session_ids())
+}
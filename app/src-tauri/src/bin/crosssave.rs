@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    // Logs go to stderr so scripts can parse what `--json` prints
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::WARN)
+        .with_writer(std::io::stderr)
+        .init();
+
+    crosssave_cloud_lib::run_cli(std::env::args().skip(1).collect())
+}
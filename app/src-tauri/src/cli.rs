@@ -0,0 +1,796 @@
+//! `crosssave`, the headless companion to the app. It packages, lists,
+//! restores and syncs saves from scripts or cron on devices without a
+//! desktop, working on the same settings, profiles and history as the app.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, RwLock};
+
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::core::backoff::CircuitBreaker;
+use crate::core::cloud::{default_device_id, CloudBackend, HttpCloudBackend};
+use crate::core::conflict::{ConflictManager, ConflictSide};
+use crate::core::device_id;
+use crate::core::history::{HistoryEntry, HistoryManager};
+use crate::core::profile::{EmulatorProfile, ProfileManager};
+use crate::core::reconcile::{build_report, reconciliation_required, GameReconciliation};
+use crate::core::restore::{package_live_saves, restore_target, restore_version};
+use crate::core::secrets::os_store;
+use crate::core::settings::{CloudMode, SettingsManager};
+use crate::core::sync::{determine_sync_action, downloaded_metadata, SyncDecision, LINEAGE_WINDOW};
+use crate::core::sync_state::{SyncStateError, SyncStateStore};
+
+/// The bundle identifier; Tauri keeps app data in a directory named after it
+const APP_IDENTIFIER: &str = "com.h1dr0n.crosssave-cloud";
+/// Exit status of `sync` when conflicts were left for the user
+const EXIT_CONFLICTS: u8 = 3;
+
+const USAGE: &str = "\
+Usage: crosssave [options] <command>
+
+Commands:
+  package <emulator-id> <game-id>     Package the game's saves into history
+  list-history [game-id]              List games, or the versions of one game
+  rollback <game-id> <version-id>     Restore a version to the save directory
+  sync [game-id] [--accept-suggested] Package changed saves, then upload and
+                                      download like the app's background sync
+  status                              Show the cloud connection and how each
+                                      game compares with the cloud
+
+Options:
+  --data-dir <dir>      App data directory (default: the app's own, or
+                        $CROSSSAVE_DATA_DIR)
+  --profiles-dir <dir>  Bundled emulator profiles (default: next to the binary)
+  --json                Print results as JSON
+  -h, --help            Show this help
+
+`sync` exits with status 3 when conflicts are left to resolve in the app.";
+
+type CliResult<T> = Result<T, String>;
+type CloudHandle = Arc<Mutex<Box<dyn CloudBackend + Send>>>;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Package {
+        emulator_id: String,
+        game_id: String,
+    },
+    ListHistory {
+        game_id: Option<String>,
+    },
+    Rollback {
+        game_id: String,
+        version_id: String,
+    },
+    Sync {
+        game_id: Option<String>,
+        accept_suggested: bool,
+    },
+    Status,
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    data_dir: Option<PathBuf>,
+    profiles_dir: Option<PathBuf>,
+    json: bool,
+}
+
+/// Run the CLI with the arguments after the program name
+pub fn run(args: Vec<String>) -> ExitCode {
+    let (options, command) = match parse_args(args) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = Context::open(&options).and_then(|context| {
+        tauri::async_runtime::block_on(async move { context.execute(command).await })
+    });
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> CliResult<Option<(Options, Command)>> {
+    let mut options = Options::default();
+    let mut accept_suggested = false;
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--json" => options.json = true,
+            "--accept-suggested" => accept_suggested = true,
+            "--data-dir" | "--profiles-dir" => {
+                let value = args
+                    .next()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| format!("{arg} needs a directory"))?;
+                if arg == "--data-dir" {
+                    options.data_dir = Some(value);
+                } else {
+                    options.profiles_dir = Some(value);
+                }
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option {flag}")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let Some(name) = positional.next() else {
+        return Ok(None);
+    };
+    let rest: Vec<String> = positional.collect();
+    let command = match (name.as_str(), rest.as_slice()) {
+        ("package", [emulator_id, game_id]) => Command::Package {
+            emulator_id: emulator_id.clone(),
+            game_id: game_id.clone(),
+        },
+        ("list-history", [] | [_]) => Command::ListHistory {
+            game_id: rest.first().cloned(),
+        },
+        ("rollback", [game_id, version_id]) => Command::Rollback {
+            game_id: game_id.clone(),
+            version_id: version_id.clone(),
+        },
+        ("sync", [] | [_]) => Command::Sync {
+            game_id: rest.first().cloned(),
+            accept_suggested,
+        },
+        ("status", []) => Command::Status,
+        ("package" | "list-history" | "rollback" | "sync" | "status", _) => {
+            return Err(format!("wrong arguments for {name}"))
+        }
+        _ => return Err(format!("unknown command {name}")),
+    };
+    if accept_suggested && !matches!(command, Command::Sync { .. }) {
+        return Err("--accept-suggested only applies to sync".to_string());
+    }
+    Ok(Some((options, command)))
+}
+
+/// Tauri's app data directory for `APP_IDENTIFIER`, resolved without an app
+fn default_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("CROSSSAVE_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home.map(|home| home.join(".local").join("share")))
+    };
+    base.map(|base| base.join(APP_IDENTIFIER))
+}
+
+/// Bundled profiles: next to the binary, or where Linux packages install
+/// the app's resources. Without either only the user's own profiles load.
+fn default_profiles_dir(user_dir: &Path) -> PathBuf {
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    exe_dir
+        .into_iter()
+        .flat_map(|dir| {
+            [
+                dir.join("resources").join("profiles"),
+                dir.join("../lib/crosssave-cloud/resources/profiles"),
+            ]
+        })
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| user_dir.to_path_buf())
+}
+
+/// The app's state, opened from its data directory
+struct Context {
+    data_dir: PathBuf,
+    settings: Arc<SettingsManager>,
+    history: Arc<HistoryManager>,
+    profiles: Arc<RwLock<ProfileManager>>,
+    json: bool,
+}
+
+impl Context {
+    fn open(options: &Options) -> CliResult<Self> {
+        let data_dir = options
+            .data_dir
+            .clone()
+            .or_else(default_data_dir)
+            .ok_or("cannot find the app data directory; pass --data-dir")?;
+        let config_dir = data_dir.join("config");
+        device_id::install(&config_dir, None);
+        let settings = SettingsManager::with_secrets(config_dir.join("settings.json"), os_store())
+            .map_err(|err| format!("loading settings: {err}"))?;
+        let mut app_settings = settings.get_settings().map_err(|err| err.to_string())?;
+        if app_settings.cloud.device_id.trim().is_empty() {
+            app_settings.cloud.device_id = default_device_id();
+            app_settings = settings
+                .update_settings(app_settings)
+                .map_err(|err| format!("saving device id: {err}"))?;
+        }
+
+        let history = HistoryManager::new_unindexed(
+            app_settings.history_dir(&data_dir),
+            app_settings.retention_limit,
+            app_settings.auto_delete,
+        )
+        .map_err(|err| format!("opening history: {err}"))?;
+        history
+            .set_size_budget(app_settings.max_history_size_mb)
+            .map_err(|err| err.to_string())?;
+        history
+            .set_age_retention(
+                app_settings.age_retention.clone(),
+                app_settings.game_age_retention.clone(),
+            )
+            .map_err(|err| err.to_string())?;
+        history
+            .index_with_progress(|_, _, _| {})
+            .map_err(|err| format!("indexing history: {err}"))?;
+
+        let user_profiles = data_dir.join("profiles");
+        let default_profiles = options
+            .profiles_dir
+            .clone()
+            .unwrap_or_else(|| default_profiles_dir(&user_profiles));
+        let profiles = ProfileManager::new(default_profiles, user_profiles)
+            .map_err(|err| format!("loading profiles: {err}"))?;
+
+        Ok(Self {
+            data_dir,
+            settings: Arc::new(settings),
+            history: Arc::new(history),
+            profiles: Arc::new(RwLock::new(profiles)),
+            json: options.json,
+        })
+    }
+
+    async fn execute(self, command: Command) -> CliResult<ExitCode> {
+        match command {
+            Command::Package {
+                emulator_id,
+                game_id,
+            } => self.package(&emulator_id, &game_id).await?,
+            Command::ListHistory { game_id } => self.list_history(game_id)?,
+            Command::Rollback {
+                game_id,
+                version_id,
+            } => self.rollback(game_id, version_id).await?,
+            Command::Sync {
+                game_id,
+                accept_suggested,
+            } => return self.sync(game_id, accept_suggested).await,
+            Command::Status => self.status().await?,
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+
+    fn profile(&self, emulator_id: &str) -> CliResult<EmulatorProfile> {
+        self.profiles
+            .read()
+            .map_err(|err| format!("profile lock error: {err}"))?
+            .get_profile(emulator_id)
+            .map_err(|err| err.to_string())?
+            .ok_or_else(|| format!("no profile {emulator_id}"))
+    }
+
+    fn print<T: Serialize>(&self, value: &T, text: impl FnOnce() -> String) -> CliResult<()> {
+        if self.json {
+            let json = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+            println!("{json}");
+        } else {
+            println!("{}", text());
+        }
+        Ok(())
+    }
+
+    /// Package the saves on disk for one game; `None` when nothing changed
+    async fn package_game(
+        &self,
+        emulator_id: &str,
+        game_id: &str,
+    ) -> CliResult<Option<HistoryEntry>> {
+        let profile = self.profile(emulator_id)?;
+        let history = self.history.clone();
+        let game_id = game_id.to_string();
+        let packaged = tauri::async_runtime::spawn_blocking(move || {
+            let packaged = package_live_saves(&history, &profile, &game_id, Vec::new(), None, None);
+            if let Err(err) = history.enforce_size_budget() {
+                warn!("[HISTORY] Failed to enforce history size budget: {err}");
+            }
+            packaged
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+        packaged.map_err(|err| err.to_string())
+    }
+
+    async fn package(&self, emulator_id: &str, game_id: &str) -> CliResult<()> {
+        let entry = self.package_game(emulator_id, game_id).await?;
+        self.print(&entry, || match &entry {
+            Some(entry) => format!("Packaged {game_id} as {}", entry.metadata.version_id),
+            None => format!("{game_id} is unchanged since its latest version"),
+        })
+    }
+
+    fn list_history(&self, game_id: Option<String>) -> CliResult<()> {
+        let Some(game_id) = game_id else {
+            let mut games = self.history.get_games();
+            games.sort();
+            return self.print(&games, || games.join("\n"));
+        };
+
+        let entries = self
+            .history
+            .list_history(game_id)
+            .map_err(|err| err.to_string())?;
+        self.print(&entries, || {
+            entries
+                .iter()
+                .map(|entry| {
+                    let metadata = &entry.metadata;
+                    let mut line = format!(
+                        "{}  {}  {}",
+                        metadata.version_id,
+                        format_timestamp(metadata.timestamp),
+                        metadata
+                            .size_bytes
+                            .map(|size| format!("{size} bytes"))
+                            .unwrap_or_default()
+                    );
+                    if metadata.pinned {
+                        line.push_str("  [pinned]");
+                    }
+                    for tag in &metadata.tags {
+                        line.push_str(&format!("  [{tag}]"));
+                    }
+                    if let Some(note) = &metadata.note {
+                        line.push_str(&format!("  {note}"));
+                    }
+                    line
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+
+    async fn rollback(&self, game_id: String, version_id: String) -> CliResult<()> {
+        let history = self.history.clone();
+        let entry = history
+            .get_history_item(game_id.clone(), version_id.clone())
+            .map_err(|err| err.to_string())?;
+        let profile = self.profile(&entry.metadata.emulator_id)?;
+        let restored = tauri::async_runtime::spawn_blocking(move || {
+            let target_dir = restore_target(&profile, &game_id).map_err(|err| err.to_string())?;
+            let outcome = restore_version(
+                &history,
+                &profile,
+                &game_id,
+                &target_dir,
+                &entry.archive_path,
+                None,
+            )
+            .map_err(|err| err.to_string())?;
+            // Same as the app: the restored saves become the newest version
+            // so the next sync carries the rollback to other devices
+            if let Err(err) = package_live_saves(
+                &history,
+                &profile,
+                &game_id,
+                Vec::new(),
+                Some(format!("Restored from version {version_id}")),
+                None,
+            ) {
+                warn!("[HISTORY] Failed to record restored saves for {game_id}: {err}");
+            }
+            CliResult::Ok((outcome.restored_files, target_dir))
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+        let (restored_files, target_dir) = restored?;
+
+        #[derive(Serialize)]
+        struct Restored<'a> {
+            restored_files: usize,
+            target_dir: &'a Path,
+        }
+        self.print(
+            &Restored {
+                restored_files,
+                target_dir: &target_dir,
+            },
+            || {
+                format!(
+                    "Restored {restored_files} files into {}",
+                    target_dir.display()
+                )
+            },
+        )
+    }
+
+    fn cloud(&self) -> CliResult<CloudHandle> {
+        let settings = self
+            .settings
+            .get_settings()
+            .map_err(|err| err.to_string())?;
+        let backend: Box<dyn CloudBackend + Send> = match settings.cloud_mode {
+            CloudMode::Official | CloudMode::SelfHost => Box::new(
+                HttpCloudBackend::new(
+                    self.settings.clone(),
+                    settings.cloud_mode,
+                    Arc::new(CircuitBreaker::default()),
+                )
+                .map_err(|err| err.to_string())?,
+            ),
+            CloudMode::Off => return Err("cloud sync is off; turn it on in the app".to_string()),
+            CloudMode::Demo => {
+                return Err("the demo cloud only exists inside the running app".to_string())
+            }
+        };
+        Ok(Arc::new(Mutex::new(backend)))
+    }
+
+    fn device_id(&self) -> CliResult<String> {
+        self.settings
+            .get_settings()
+            .map(|settings| settings.cloud.device_id)
+            .map_err(|err| err.to_string())
+    }
+
+    fn sync_state(&self) -> Option<SyncStateStore> {
+        SyncStateStore::new(self.data_dir.join("data").join("sync_state.json"))
+            .map_err(|err| warn!("[SYNC] Failed to load sync state: {err}"))
+            .ok()
+    }
+
+    async fn sync(&self, only: Option<String>, accept_suggested: bool) -> CliResult<ExitCode> {
+        let cloud = self.cloud()?;
+        let device_id = self.device_id()?;
+        let sync_state = self.sync_state();
+
+        if reconciliation_required(&cloud, &self.settings, sync_state.as_ref())
+            .await
+            .map_err(|err| err.to_string())?
+        {
+            if !accept_suggested {
+                return Err("this account's cloud already has saves; review them with \
+                    `crosssave status`, then run `crosssave sync --accept-suggested`"
+                    .to_string());
+            }
+            let mut settings = self
+                .settings
+                .get_settings()
+                .map_err(|err| err.to_string())?;
+            settings.mark_reconciled();
+            self.settings
+                .update_settings(settings)
+                .map_err(|err| err.to_string())?;
+        }
+
+        let games = match only {
+            Some(game_id) => vec![game_id],
+            None => self.history.get_games(),
+        };
+        let conflicts = ConflictManager::new(self.data_dir.join("config").join("conflicts.json"))
+            .map_err(|err| warn!("[SYNC] Failed to load conflict rules: {err}"))
+            .ok();
+
+        let sync_state = sync_state.as_ref();
+        let mut report = SyncReport::default();
+        for game_id in games {
+            match self
+                .sync_game(&cloud, &device_id, conflicts.as_ref(), &game_id)
+                .await
+            {
+                Ok(Synced::Uploaded(version_id)) => {
+                    record(sync_state, |state| {
+                        state.record_upload(&game_id, &version_id)
+                    });
+                    report.uploaded.push(game_id);
+                }
+                Ok(Synced::Downloaded(version_id)) => {
+                    record(sync_state, |state| {
+                        state.record_download(&game_id, &version_id)
+                    });
+                    report.downloaded.push(game_id);
+                }
+                Ok(Synced::Conflict) => {
+                    record(sync_state, |state| state.record_conflict(&game_id));
+                    report.conflicts.push(game_id);
+                }
+                Ok(Synced::Unchanged) => report.unchanged.push(game_id),
+                Err(err) => {
+                    warn!("[SYNC] Syncing {game_id} failed: {err}");
+                    report.failed.push(FailedGame {
+                        game_id,
+                        message: err,
+                    });
+                }
+            }
+        }
+
+        self.print(&report, || report.summary())?;
+        Ok(if !report.failed.is_empty() {
+            ExitCode::FAILURE
+        } else if !report.conflicts.is_empty() {
+            ExitCode::from(EXIT_CONFLICTS)
+        } else {
+            ExitCode::SUCCESS
+        })
+    }
+
+    /// Package, decide and transfer one game; returns what was done
+    async fn sync_game(
+        &self,
+        cloud: &CloudHandle,
+        device_id: &str,
+        conflicts: Option<&ConflictManager>,
+        game_id: &str,
+    ) -> CliResult<Synced> {
+        // Without the app's watcher, pick up changes on disk first
+        if let Some(latest) = self.history.get_latest_version(game_id) {
+            self.package_game(&latest.metadata.emulator_id, game_id)
+                .await?;
+        }
+
+        let local = self.history.get_latest_version(game_id);
+        let lineage = local
+            .as_ref()
+            .map(|local| self.history.lineage(game_id, &local.metadata.version_id))
+            .unwrap_or_default();
+        let versions = cloud
+            .lock()
+            .await
+            .list_versions(game_id.to_string(), Some(LINEAGE_WINDOW))
+            .await
+            .map_err(|err| err.to_string())?;
+
+        let mut decision = determine_sync_action(local.as_ref(), &lineage, &versions, device_id);
+        if decision == SyncDecision::Conflict {
+            let rule = conflicts.and_then(|conflicts| conflicts.rule_for(game_id));
+            let cloud_latest = versions.iter().max_by_key(|version| version.timestamp);
+            if let (Some(rule), Some(cloud_latest)) = (rule, cloud_latest) {
+                let kept = rule.resolve(device_id, &cloud_latest.device_id);
+                info!("[SYNC] Conflict for {game_id} resolved by rule: keep {kept:?}");
+                decision = match kept {
+                    ConflictSide::Local => SyncDecision::Upload,
+                    ConflictSide::Cloud => SyncDecision::Download(cloud_latest.version_id.clone()),
+                };
+            }
+        }
+
+        match decision {
+            SyncDecision::Upload => {
+                let local = local.ok_or("no local version to upload")?;
+                let version_id = local.metadata.version_id.clone();
+                info!("[SYNC] Uploading {game_id} version {version_id}");
+                cloud
+                    .lock()
+                    .await
+                    .upload_archive(local.metadata, local.archive_path)
+                    .await
+                    .map_err(|err| err.to_string())?;
+                Ok(Synced::Uploaded(version_id))
+            }
+            SyncDecision::Download(version_id) => {
+                info!("[SYNC] Downloading {game_id} version {version_id}");
+                self.download(cloud, game_id, &version_id).await?;
+                Ok(Synced::Downloaded(version_id))
+            }
+            SyncDecision::Conflict => {
+                warn!("[SYNC] Conflict detected for {game_id}");
+                Ok(Synced::Conflict)
+            }
+            SyncDecision::Noop => Ok(Synced::Unchanged),
+        }
+    }
+
+    /// Download a version, restore it to disk and add it to history
+    async fn download(
+        &self,
+        cloud: &CloudHandle,
+        game_id: &str,
+        version_id: &str,
+    ) -> CliResult<()> {
+        let archive_path = self
+            .data_dir
+            .join("data")
+            .join("cloud_downloads")
+            .join(format!("{game_id}_{version_id}.zip"));
+        // Downloading registers the device, which the metadata request needs
+        let info = {
+            let backend = cloud.lock().await;
+            backend
+                .download_version(
+                    game_id.to_string(),
+                    version_id.to_string(),
+                    archive_path.clone(),
+                )
+                .await
+                .map_err(|err| err.to_string())?;
+            backend
+                .request_download_url(game_id.to_string(), version_id.to_string())
+                .await
+                .map_err(|err| err.to_string())?
+        };
+        let emulator_id = info
+            .emulator_id
+            .clone()
+            .filter(|id| !id.trim().is_empty())
+            .ok_or("emulator_id missing in download metadata")?;
+        let profile = self.profile(&emulator_id)?;
+
+        let history = self.history.clone();
+        let game_id = game_id.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            let target_dir = restore_target(&profile, &game_id).map_err(|err| err.to_string())?;
+            restore_version(
+                &history,
+                &profile,
+                &game_id,
+                &target_dir,
+                &archive_path,
+                info.timestamp,
+            )
+            .map_err(|err| err.to_string())?;
+            let metadata = downloaded_metadata(&info, &game_id, &emulator_id);
+            history
+                .add_version_from_cloud(metadata, archive_path)
+                .map_err(|err| err.to_string())?;
+            if let Err(err) = history.enforce_size_budget() {
+                warn!("[HISTORY] Failed to enforce history size budget: {err}");
+            }
+            CliResult::Ok(())
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+
+    async fn status(&self) -> CliResult<()> {
+        let settings = self
+            .settings
+            .get_settings()
+            .map_err(|err| err.to_string())?;
+        let last_sync = self.sync_state().and_then(|state| state.last_sync());
+        let (connected, games) = match self.cloud() {
+            Ok(cloud) => {
+                let connected = cloud.lock().await.check_connection().await.unwrap_or(false);
+                let games = if connected {
+                    build_report(&cloud, &self.history, &settings.cloud.device_id)
+                        .await
+                        .map_err(|err| err.to_string())?
+                        .games
+                } else {
+                    Vec::new()
+                };
+                (connected, games)
+            }
+            Err(err) => {
+                info!("[CLOUD] {err}");
+                (false, Vec::new())
+            }
+        };
+
+        let status = Status {
+            cloud_mode: settings.cloud_mode,
+            device_id: settings.cloud.device_id,
+            connected,
+            last_sync: last_sync.map(|at| at.to_rfc3339()),
+            games,
+        };
+        self.print(&status, || status.summary())
+    }
+}
+
+/// What `sync` did for one game
+enum Synced {
+    Uploaded(String),
+    Downloaded(String),
+    Conflict,
+    Unchanged,
+}
+
+fn record(
+    sync_state: Option<&SyncStateStore>,
+    update: impl FnOnce(&SyncStateStore) -> Result<(), SyncStateError>,
+) {
+    if let Some(state) = sync_state {
+        if let Err(err) = update(state) {
+            warn!("[SYNC] Failed to record sync event: {err}");
+        }
+    }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    Local
+        .timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[derive(Default, Serialize)]
+struct SyncReport {
+    uploaded: Vec<String>,
+    downloaded: Vec<String>,
+    conflicts: Vec<String>,
+    unchanged: Vec<String>,
+    failed: Vec<FailedGame>,
+}
+
+#[derive(Serialize)]
+struct FailedGame {
+    game_id: String,
+    message: String,
+}
+
+impl SyncReport {
+    fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        for (label, games) in [
+            ("Uploaded", &self.uploaded),
+            ("Downloaded", &self.downloaded),
+            ("Conflict, resolve in the app", &self.conflicts),
+        ] {
+            lines.extend(games.iter().map(|game_id| format!("{label}: {game_id}")));
+        }
+        lines.extend(
+            self.failed
+                .iter()
+                .map(|failed| format!("Failed: {}: {}", failed.game_id, failed.message)),
+        );
+        lines.push(format!("{} already in sync", self.unchanged.len()));
+        lines.join("\n")
+    }
+}
+
+#[derive(Serialize)]
+struct Status {
+    cloud_mode: CloudMode,
+    device_id: String,
+    connected: bool,
+    last_sync: Option<String>,
+    games: Vec<GameReconciliation>,
+}
+
+impl Status {
+    fn summary(&self) -> String {
+        let mut lines = vec![
+            format!("Cloud mode: {:?}", self.cloud_mode),
+            format!("Device: {}", self.device_id),
+            format!("Connected: {}", if self.connected { "yes" } else { "no" }),
+            format!(
+                "Last sync: {}",
+                self.last_sync.as_deref().unwrap_or("never")
+            ),
+        ];
+        for game in &self.games {
+            lines.push(format!(
+                "{}: {:?}, suggested {:?}",
+                game.game_id, game.state, game.suggested
+            ));
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::core::history::HistoryManager;
+use crate::core::settings::SettingsManager;
+
+#[derive(Debug, Error)]
+pub enum AccountError {
+    #[error("settings error: {0}")]
+    Settings(String),
+    #[error("history error: {0}")]
+    History(String),
+}
+
+/// A different account signed in than the one the local history was
+/// synced with
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountSwitch {
+    pub previous: String,
+    pub current: String,
+    /// History kept apart for `current` during an earlier switch
+    pub has_separate_history: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountHistoryChoice {
+    /// Set the current history aside for the previous account and use the
+    /// one kept for the new account, or an empty one
+    Separate,
+    /// Hand the current history to the new account; it will be uploaded there
+    Merge,
+}
+
+/// The account switch waiting on the user, if any. A history nobody owns
+/// yet is claimed by the signed-in account.
+pub fn pending_account_switch(
+    settings: &SettingsManager,
+    history: &HistoryManager,
+) -> Result<Option<AccountSwitch>, AccountError> {
+    let mut app_settings = settings
+        .get_settings()
+        .map_err(|err| AccountError::Settings(err.to_string()))?;
+    let Some(current) = app_settings.account_key() else {
+        return Ok(None);
+    };
+
+    match app_settings.cloud.history_owner.clone() {
+        Some(previous) if previous != current => Ok(Some(AccountSwitch {
+            has_separate_history: separate_history_dir(history, &current).is_dir(),
+            previous,
+            current,
+        })),
+        Some(_) => Ok(None),
+        None => {
+            info!("[HISTORY] Local history now belongs to the signed-in account");
+            app_settings.cloud.history_owner = Some(current);
+            settings
+                .update_settings(app_settings)
+                .map_err(|err| AccountError::Settings(err.to_string()))?;
+            Ok(None)
+        }
+    }
+}
+
+/// Apply the user's answer to a pending switch. Blocking: `Separate`
+/// moves the history directory and re-indexes it.
+pub fn apply_account_choice(
+    settings: &SettingsManager,
+    history: &HistoryManager,
+    choice: AccountHistoryChoice,
+) -> Result<(), AccountError> {
+    let Some(switch) = pending_account_switch(settings, history)? else {
+        return Ok(());
+    };
+
+    if choice == AccountHistoryChoice::Separate {
+        let games = history
+            .swap_directory(
+                &separate_history_dir(history, &switch.previous),
+                &separate_history_dir(history, &switch.current),
+            )
+            .map_err(|err| AccountError::History(err.to_string()))?;
+        info!("[HISTORY] Switched to the new account's history ({games} games)");
+    } else {
+        warn!("[HISTORY] Local history handed to the new account");
+    }
+
+    let mut app_settings = settings
+        .get_settings()
+        .map_err(|err| AccountError::Settings(err.to_string()))?;
+    app_settings.cloud.history_owner = Some(switch.current);
+    settings
+        .update_settings(app_settings)
+        .map_err(|err| AccountError::Settings(err.to_string()))?;
+    Ok(())
+}
+
+/// Sibling of the history directory, named by a digest of the account so
+/// user ids and keys never end up in paths
+fn separate_history_dir(history: &HistoryManager, account: &str) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(account.as_bytes()));
+    history
+        .base_dir()
+        .with_file_name(format!("history-{}", &digest[..16]))
+}
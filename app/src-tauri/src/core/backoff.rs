@@ -0,0 +1,260 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Exponential delays with jitter, so clients that failed together do not
+/// retry together
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    pub const fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max }
+    }
+
+    /// Delay before retry `attempt` (1-based): a random point between half
+    /// and all of `base * 2^(attempt - 1)`, capped at `max`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let ceiling = self.base.saturating_mul(1 << exponent).min(self.max);
+        let half = ceiling / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Uniform value in `[0, 1)`, taken from a v4 UUID to avoid a `rand` dependency
+fn jitter() -> f64 {
+    (Uuid::new_v4().as_u128() % 1_000_000) as f64 / 1_000_000.0
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// The backend failed repeatedly; requests are refused until the cooldown ends
+    Open,
+    /// Cooldown over; a single probe request decides whether to close or
+    /// reopen, everything else is still refused
+    HalfOpen,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CircuitSnapshot {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until an open circuit lets a request through again
+    pub retry_in_secs: Option<u64>,
+}
+
+struct CircuitInner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// Times the circuit opened without a success in between; grows the cooldown
+    trips: u32,
+    open_until: Option<Instant>,
+    /// When the half-open probe was let through
+    probe_started: Option<Instant>,
+}
+
+/// A half-open probe that hasn't reported back by then, e.g. because its
+/// request was dropped, is replaced by the next request
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+/// Shared by the cloud backend, the upload queue and the connectivity
+/// monitor so a dead server is backed off from in one place
+pub struct CircuitBreaker {
+    inner: Mutex<CircuitInner>,
+    failure_threshold: u32,
+    cooldown: Backoff,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(
+            3,
+            Backoff::new(Duration::from_secs(15), Duration::from_secs(5 * 60)),
+        )
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Backoff) -> Self {
+        Self {
+            inner: Mutex::new(CircuitInner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                trips: 0,
+                open_until: None,
+                probe_started: None,
+            }),
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CircuitInner> {
+        // The state stays consistent even if a holder panicked
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether a request may be sent now. An open circuit whose cooldown has
+    /// passed moves to half-open and lets this one request through as a
+    /// probe; others are refused until it succeeds or fails.
+    pub fn allow(&self) -> bool {
+        let mut inner = self.lock();
+        let probe = match inner.state {
+            CircuitState::Closed => return true,
+            CircuitState::HalfOpen => !inner
+                .probe_started
+                .is_some_and(|started| started.elapsed() < PROBE_TIMEOUT),
+            CircuitState::Open => !inner.open_until.is_some_and(|until| Instant::now() < until),
+        };
+        if probe {
+            inner.state = CircuitState::HalfOpen;
+            inner.probe_started = Some(Instant::now());
+        }
+        probe
+    }
+
+    pub fn record_success(&self) {
+        if self.lock().state != CircuitState::Closed {
+            info!("[CLOUD] Backend reachable again, closing circuit");
+        }
+        self.reset();
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+
+        let trip = inner.state == CircuitState::HalfOpen
+            || (inner.state == CircuitState::Closed
+                && inner.consecutive_failures >= self.failure_threshold);
+        if trip {
+            inner.trips = inner.trips.saturating_add(1);
+            let cooldown = self.cooldown.delay(inner.trips);
+            inner.state = CircuitState::Open;
+            inner.open_until = Some(Instant::now() + cooldown);
+            inner.probe_started = None;
+            warn!(
+                "[CLOUD] Backend failed {} times, pausing requests for {}s",
+                inner.consecutive_failures,
+                cooldown.as_secs()
+            );
+        }
+    }
+
+    /// Time left before an open circuit allows a probe; `None` once
+    /// requests may be sent
+    pub fn retry_in(&self) -> Option<Duration> {
+        let inner = self.lock();
+        match inner.state {
+            CircuitState::Open => inner
+                .open_until
+                .map(|until| until.saturating_duration_since(Instant::now()))
+                .filter(|left| !left.is_zero()),
+            _ => None,
+        }
+    }
+
+    /// Forget past failures, e.g. after switching to a different server
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.trips = 0;
+        inner.open_until = None;
+        inner.probe_started = None;
+    }
+
+    pub fn snapshot(&self) -> CircuitSnapshot {
+        let retry_in_secs = self.retry_in().map(|left| left.as_secs());
+        let inner = self.lock();
+        CircuitSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            retry_in_secs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(2, Backoff::new(cooldown, cooldown))
+    }
+
+    #[test]
+    fn trips_after_the_failure_threshold() {
+        let breaker = breaker(Duration::from_secs(60));
+        breaker.record_failure();
+        assert!(breaker.allow());
+        assert_eq!(breaker.snapshot().state, CircuitState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.snapshot().state, CircuitState::Open);
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn refuses_requests_until_the_cooldown_ends() {
+        let breaker = breaker(Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_failure();
+
+        let left = breaker.retry_in().unwrap();
+        assert!(left > Duration::from_secs(29) && left <= Duration::from_secs(60));
+        assert!(!breaker.allow());
+        assert_eq!(breaker.snapshot().state, CircuitState::Open);
+    }
+
+    #[test]
+    fn half_open_lets_a_single_probe_through() {
+        let breaker = breaker(Duration::ZERO);
+        breaker.record_failure();
+        breaker.record_failure();
+
+        assert!(breaker.allow(), "cooldown over, probe goes out");
+        assert_eq!(breaker.snapshot().state, CircuitState::HalfOpen);
+        assert!(!breaker.allow(), "only one probe at a time");
+
+        breaker.record_failure();
+        assert_eq!(breaker.snapshot().state, CircuitState::Open);
+
+        assert!(breaker.allow());
+        breaker.record_success();
+        assert_eq!(breaker.snapshot().state, CircuitState::Closed);
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn reset_forgets_failures() {
+        let breaker = breaker(Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(!breaker.allow());
+
+        breaker.reset();
+        let snapshot = breaker.snapshot();
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert_eq!(snapshot.consecutive_failures, 0);
+        assert_eq!(snapshot.retry_in_secs, None);
+        assert!(breaker.allow());
+
+        // The threshold counts from zero again
+        breaker.record_failure();
+        assert!(breaker.allow());
+    }
+}
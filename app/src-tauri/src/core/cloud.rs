@@ -1,11 +1,14 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use reqwest::{
-    header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE},
+    header::{
+        HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RETRY_AFTER,
+    },
     Client,
 };
 use serde::{Deserialize, Serialize};
@@ -15,8 +18,20 @@ use tokio::io::AsyncWriteExt;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
-use crate::core::packager::SaveMetadata;
+use crate::core::backoff::CircuitBreaker;
+use crate::core::device_id::{
+    fingerprint as device_fingerprint, identity as device_identity, new_install,
+};
+use crate::core::listing_cache::ListingCache;
+use crate::core::packager::{
+    decode_download_file, encode_for_upload, ArchiveEncoding, SaveMetadata, ARCHIVE_CONTENT_TYPE,
+};
 use crate::core::settings::{CloudMode, SettingsManager};
+use crate::core::storage::{is_cloud_quota_response, is_storage_full};
+use crate::core::thumbnail::upload_thumbnail;
+
+/// Largest single file `download_file` accepts, matching the server's cap
+pub const MAX_CLOUD_FILE_BYTES: u64 = 256 * 1024 * 1024;
 
 // =============================================================================
 // HELPERS
@@ -40,6 +55,17 @@ fn sanitize_game_id(game_id: &str) -> String {
         .collect()
 }
 
+/// Keep the newest `limit` of versions already sorted newest first
+fn limit_versions(
+    mut versions: Vec<CloudVersionSummary>,
+    limit: Option<usize>,
+) -> Vec<CloudVersionSummary> {
+    if let Some(limit) = limit {
+        versions.truncate(limit);
+    }
+    versions
+}
+
 // =============================================================================
 // Configuration
 // =============================================================================
@@ -85,6 +111,68 @@ pub struct CloudVersionSummary {
     pub device_id: String,
     pub file_list: Vec<String>,
     pub sha256: String,
+    /// Short-lived presigned URL, only set when the version has a thumbnail
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Version this one was made from; `None` for versions uploaded before
+    /// lineage was recorded
+    #[serde(default)]
+    pub parent_version_id: Option<String>,
+    /// The saves are encrypted by the emulator and archived as opaque blobs
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+/// One version as `/save/list` and `/save/latest-batch` return it
+#[derive(Deserialize)]
+struct SaveListVersion {
+    #[serde(default)]
+    game_id: String,
+    version_id: String,
+    size_bytes: u64,
+    timestamp: u64,
+    #[serde(default)]
+    device_id: String,
+    sha256: String,
+    #[serde(default)]
+    file_list: Vec<String>,
+    #[serde(default)]
+    thumbnail_url: Option<String>,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    parent_version_id: Option<String>,
+    #[serde(default)]
+    encrypted: bool,
+}
+
+impl From<SaveListVersion> for CloudVersionSummary {
+    fn from(entry: SaveListVersion) -> Self {
+        Self {
+            version_id: entry.version_id,
+            timestamp: entry.timestamp,
+            size_bytes: entry.size_bytes,
+            device_id: entry.device_id,
+            file_list: entry.file_list,
+            sha256: entry.sha256,
+            thumbnail_url: entry.thumbnail_url,
+            note: entry.note,
+            tags: entry.tags,
+            pinned: entry.pinned,
+            parent_version_id: entry.parent_version_id,
+            encrypted: entry.encrypted,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -95,6 +183,103 @@ pub struct CloudDevice {
     pub last_seen: u64,
 }
 
+/// Devices the server folded into one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MergedDevices {
+    pub kept: String,
+    pub removed: Vec<String>,
+}
+
+/// What `merge_devices` merged, or would merge on a dry run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DeviceMerge {
+    pub merged: Vec<MergedDevices>,
+    /// Entries from before fingerprints that only look like the same
+    /// machine by platform and name; merged once their ids are confirmed
+    #[serde(default)]
+    pub suggested: Vec<MergedDevices>,
+    /// The device list after merging, or as it would be
+    pub devices: Vec<CloudDevice>,
+}
+
+/// One entry of the server's audit log for this account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountActivity {
+    /// e.g. `login`, `login_failed`, `device_added`, `version_deleted`
+    pub action: String,
+    /// Unix seconds
+    pub timestamp: i64,
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub game_id: Option<String>,
+    #[serde(default)]
+    pub version_id: Option<String>,
+    /// Session a `session_revoked` event ended
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Other account in a share event, or who uploaded into a shared game
+    #[serde(default)]
+    pub other_user_id: Option<String>,
+}
+
+/// A login on the account whose token the server still accepts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CloudSession {
+    pub session_id: String,
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// Unix seconds
+    pub created_at: i64,
+    pub expires_at: i64,
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// The session this app is using
+    #[serde(default)]
+    pub current: bool,
+}
+
+/// What a shared game's other account may do with the owner's saves
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareAccess {
+    /// Download versions only
+    Read,
+    /// Also upload new versions
+    Write,
+}
+
+/// One game's saves shared by their owner with another account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SharedGame {
+    pub owner_user_id: String,
+    pub owner_email: String,
+    pub grantee_user_id: String,
+    pub grantee_email: String,
+    pub game_id: String,
+    pub access: ShareAccess,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+/// Games the account shares with others and games shared with it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameShares {
+    #[serde(default)]
+    pub granted: Vec<SharedGame>,
+    #[serde(default)]
+    pub received: Vec<SharedGame>,
+    /// Accounts one game can be shared with, when the server says
+    #[serde(default)]
+    pub max_grantees_per_game: Option<usize>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UploadRequest {
     pub game_id: String,
@@ -106,6 +291,21 @@ pub struct UploadRequest {
     pub device_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub worker_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_version_id: Option<String>,
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Set when the archive is uploaded compressed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ArchiveEncoding>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -115,6 +315,10 @@ pub struct UploadUrlResponse {
     pub version_id: String,
     #[serde(default)]
     pub worker_token: Option<String>,
+    /// Returned when the request carried a thumbnail hash and the backend
+    /// stores thumbnails
+    #[serde(default)]
+    pub thumbnail_upload_url: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -132,6 +336,18 @@ pub struct DownloadUrlResponse {
     pub emulator_id: Option<String>,
     #[serde(default)]
     pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub parent_version_id: Option<String>,
+    #[serde(default)]
+    pub encrypted: bool,
 }
 
 // =============================================================================
@@ -159,6 +375,10 @@ pub enum CloudError {
     Serialization(String),
     #[error("unauthorized: {0}")]
     Unauthorized(String),
+    #[error("cloud storage quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
 }
 
 // =============================================================================
@@ -189,13 +409,40 @@ pub trait CloudBackend: Send + Sync {
         game_id: String,
         limit: Option<usize>,
     ) -> Result<Vec<CloudVersionSummary>, CloudError>;
+    /// Latest version of each of `game_ids`, keyed by game ID, in one
+    /// request. Games without a cloud version are absent; `NotFound` means
+    /// the backend has no batch endpoint.
+    async fn list_latest_versions(
+        &self,
+        game_ids: Vec<String>,
+    ) -> Result<HashMap<String, CloudVersionSummary>, CloudError>;
     async fn download_version(
         &self,
         game_id: String,
         version_id: String,
         target_path: PathBuf,
     ) -> Result<(), CloudError>;
+    /// One file out of a version's archive, by its path in `file_list`.
+    /// `NotFound` also covers backends without per-file downloads.
+    async fn download_file(
+        &self,
+        game_id: String,
+        version_id: String,
+        path: String,
+    ) -> Result<Vec<u8>, CloudError>;
+    /// Delete a single cloud version; pinned versions are refused
+    async fn delete_version(&self, game_id: String, version_id: String) -> Result<(), CloudError>;
+    /// Pin or unpin a cloud version, mirroring a pin in local history
+    async fn set_version_pinned(
+        &self,
+        game_id: String,
+        version_id: String,
+        pinned: bool,
+    ) -> Result<(), CloudError>;
     fn ensure_device_id(&self) -> Result<String, CloudError>;
+    /// Register this device with the account unless the backend already
+    /// knows it, returning its id
+    async fn ensure_device_registered(&self) -> Result<String, CloudError>;
     async fn list_devices(&self, token: String) -> Result<Vec<CloudDevice>, CloudError>;
     async fn register_device(
         &self,
@@ -210,6 +457,114 @@ pub trait CloudBackend: Send + Sync {
     
     /// Check if the cloud backend is reachable and healthy
     async fn check_connection(&self) -> Result<bool, CloudError>;
+
+    /// Whether archives move through `upload_archive` and `download_version`
+    /// instead of the presigned URLs the backend hands out
+    fn holds_archives(&self) -> bool {
+        false
+    }
+
+    /// Whether archives may be uploaded compressed with zstd
+    async fn supports_zstd(&self) -> bool {
+        false
+    }
+
+    /// Newest version of `game_id` whose archive hashes to `sha256`.
+    /// `NotFound` means the backend can't look versions up by hash.
+    async fn find_version_by_hash(
+        &self,
+        _game_id: String,
+        _sha256: String,
+    ) -> Result<Option<String>, CloudError> {
+        Err(CloudError::NotFound("version lookup".into()))
+    }
+
+    /// Record `payload` as a new version reusing the archive of
+    /// `source_version_id`, which has the same hash, so nothing is uploaded
+    async fn link_version(
+        &self,
+        _payload: UploadRequest,
+        _source_version_id: String,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::NotFound("version lookup".into()))
+    }
+
+    /// Newest account events first. `NotFound` means the backend keeps no
+    /// audit log.
+    async fn list_account_activity(
+        &self,
+        _limit: Option<usize>,
+    ) -> Result<Vec<AccountActivity>, CloudError> {
+        Err(CloudError::NotFound("account activity".into()))
+    }
+
+    /// Fold the account's duplicate entries of one machine into one,
+    /// keeping `keep` from its group. Entries without a fingerprint are
+    /// only merged when listed in `confirm`. `dry_run` only reports what
+    /// would be merged. `NotFound` means the backend can't merge devices.
+    async fn merge_devices(
+        &self,
+        _keep: Option<String>,
+        _confirm: Vec<String>,
+        _dry_run: bool,
+    ) -> Result<DeviceMerge, CloudError> {
+        Err(CloudError::NotFound("device merge".into()))
+    }
+
+    /// Active sessions on the account, newest first. `NotFound` means the
+    /// backend doesn't track sessions.
+    async fn list_sessions(&self) -> Result<Vec<CloudSession>, CloudError> {
+        Err(CloudError::NotFound("sessions".into()))
+    }
+
+    /// Revoke `session_id`, or with `None` every session but this app's.
+    /// Returns how many were revoked.
+    async fn revoke_sessions(&self, _session_id: Option<String>) -> Result<usize, CloudError> {
+        Err(CloudError::NotFound("sessions".into()))
+    }
+
+    /// Share `game_id`'s saves with the account registered to `email`.
+    /// Sharing again with the same account changes its access. `NotFound`
+    /// means the backend can't share games.
+    async fn share_game(
+        &self,
+        _game_id: String,
+        _email: String,
+        _access: ShareAccess,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::NotFound("sharing".into()))
+    }
+
+    /// Games shared by and with the account. Shared games also show up in
+    /// `list_games` and sync like the account's own.
+    async fn list_shared_games(&self) -> Result<GameShares, CloudError> {
+        Err(CloudError::NotFound("sharing".into()))
+    }
+
+    /// Stop sharing `game_id` with `grantee_user_id`, or with `None` leave a
+    /// game another account shares with this one
+    async fn unshare_game(
+        &self,
+        _game_id: String,
+        _grantee_user_id: Option<String>,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::NotFound("sharing".into()))
+    }
+
+    /// Email a password reset code to `email`. `NotFound` means the backend
+    /// can't send email.
+    async fn request_password_reset(&self, _email: String) -> Result<(), CloudError> {
+        Err(CloudError::NotFound("password reset".into()))
+    }
+
+    /// Set a new password with the code from the reset email
+    async fn complete_password_reset(
+        &self,
+        _token: String,
+        _new_password: String,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::NotFound("password reset".into()))
+    }
 }
 
 // =============================================================================
@@ -264,6 +619,13 @@ impl CloudBackend for DisabledCloudBackend {
         Err(CloudError::Disabled)
     }
 
+    async fn list_latest_versions(
+        &self,
+        _game_ids: Vec<String>,
+    ) -> Result<HashMap<String, CloudVersionSummary>, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
     async fn download_version(
         &self,
         _game_id: String,
@@ -273,10 +635,40 @@ impl CloudBackend for DisabledCloudBackend {
         Err(CloudError::Disabled)
     }
 
+    async fn download_file(
+        &self,
+        _game_id: String,
+        _version_id: String,
+        _path: String,
+    ) -> Result<Vec<u8>, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
     fn ensure_device_id(&self) -> Result<String, CloudError> {
         Err(CloudError::Disabled)
     }
 
+    async fn ensure_device_registered(&self) -> Result<String, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn delete_version(
+        &self,
+        _game_id: String,
+        _version_id: String,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn set_version_pinned(
+        &self,
+        _game_id: String,
+        _version_id: String,
+        _pinned: bool,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
+
     async fn list_devices(&self, _token: String) -> Result<Vec<CloudDevice>, CloudError> {
         Err(CloudError::Disabled)
     }
@@ -306,12 +698,98 @@ impl CloudBackend for DisabledCloudBackend {
     async fn list_games(&self) -> Result<Vec<String>, CloudError> {
         Err(CloudError::Disabled)
     }
+
+    async fn find_version_by_hash(
+        &self,
+        _game_id: String,
+        _sha256: String,
+    ) -> Result<Option<String>, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn link_version(
+        &self,
+        _payload: UploadRequest,
+        _source_version_id: String,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn list_account_activity(
+        &self,
+        _limit: Option<usize>,
+    ) -> Result<Vec<AccountActivity>, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn merge_devices(
+        &self,
+        _keep: Option<String>,
+        _confirm: Vec<String>,
+        _dry_run: bool,
+    ) -> Result<DeviceMerge, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<CloudSession>, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn revoke_sessions(&self, _session_id: Option<String>) -> Result<usize, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn share_game(
+        &self,
+        _game_id: String,
+        _email: String,
+        _access: ShareAccess,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn list_shared_games(&self) -> Result<GameShares, CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn unshare_game(
+        &self,
+        _game_id: String,
+        _grantee_user_id: Option<String>,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn request_password_reset(&self, _email: String) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
+
+    async fn complete_password_reset(
+        &self,
+        _token: String,
+        _new_password: String,
+    ) -> Result<(), CloudError> {
+        Err(CloudError::Disabled)
+    }
 }
 
 // =============================================================================
 // HTTP Cloud Backend
 // =============================================================================
 
+/// How long a confirmed device registration is trusted before checking again
+const DEVICE_VERIFY_TTL: Duration = Duration::from_secs(10 * 60);
+/// A rate-limited request is waited out and sent again once when the
+/// server's `Retry-After` is at most this; longer waits fail the request
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(15);
+
+/// Device the server last confirmed as registered, for the token in use then
+struct VerifiedDevice {
+    device_id: String,
+    auth_digest: String,
+    verified_at: Instant,
+}
+
 #[derive(Clone)]
 pub struct HttpCloudBackend {
     client: Client,
@@ -319,6 +797,46 @@ pub struct HttpCloudBackend {
     mode: CloudMode,
     log_tag: &'static str,
     access_headers: HeaderMap,
+    /// Held across the check so concurrent transfers register only once
+    verified_device: Arc<tokio::sync::Mutex<Option<VerifiedDevice>>>,
+    breaker: Arc<CircuitBreaker>,
+    listings: Arc<Listings>,
+    /// Whether the server at a base URL takes zstd uploads, as `/health`
+    /// reported it
+    zstd_support: Arc<std::sync::Mutex<Option<(String, bool)>>>,
+}
+
+/// Conditional-request caches for the listing endpoints. Uploads and
+/// deletes through the backend clear them all.
+#[derive(Default)]
+struct Listings {
+    versions: ListingCache<Vec<CloudVersionSummary>>,
+    latest: ListingCache<HashMap<String, CloudVersionSummary>>,
+    games: ListingCache<Vec<String>>,
+}
+
+impl Listings {
+    fn clear(&self) {
+        self.versions.clear();
+        self.latest.clear();
+        self.games.clear();
+    }
+}
+
+/// A listing response, or the cached listing when the backend answered 304
+enum Listed<T> {
+    Cached(T),
+    Fresh {
+        response: reqwest::Response,
+        etag: Option<String>,
+    },
+}
+
+/// Cache key for one listing request; the credentials are part of it so
+/// signing in to another account never reuses a listing
+fn listing_key(base_url: &str, auth: &str, request: &str) -> String {
+    let material = format!("{base_url}\n{auth}\n{request}");
+    format!("{:x}", Sha256::digest(material.as_bytes()))
 }
 
 pub type SelfHostHttpBackend = HttpCloudBackend;
@@ -373,6 +891,11 @@ struct SignupResponse {
     device_id: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct CheckDeviceResponse {
+    registered: bool,
+}
+
 #[derive(Deserialize)]
 struct ListDevicesResponse {
     devices: Vec<CloudDevice>,
@@ -411,7 +934,101 @@ impl HttpCloudBackend {
             .fold(builder, |acc, (name, value)| acc.header(name, value))
     }
 
-    pub fn new(settings: Arc<SettingsManager>, mode: CloudMode) -> Result<Self, CloudError> {
+    /// Send a backend request with the access headers applied
+    async fn send(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, CloudError> {
+        self.send_raw(self.apply_access_headers(builder)).await
+    }
+
+    /// Send a listing request, revalidating the copy cached under `key` when
+    /// there is one
+    async fn send_listing<T: Clone>(
+        &self,
+        cache: &ListingCache<T>,
+        key: &str,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<Listed<T>, CloudError> {
+        let cached = cache.lookup(key);
+        let builder = match &cached {
+            Some((etag, _)) => builder.header(IF_NONE_MATCH, etag.as_str()),
+            None => builder,
+        };
+        let response = self.send(builder).await?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some((_, listing)) = cached {
+                return Ok(Listed::Cached(listing));
+            }
+        }
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        Ok(Listed::Fresh { response, etag })
+    }
+
+    /// Send through the circuit breaker, waiting out a short rate limit
+    async fn send_raw(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, CloudError> {
+        // Streamed bodies can't be cloned and are never resent
+        let retry = builder.try_clone();
+        let resp = self.send_once(builder).await?;
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+
+        match (retry_after(resp.headers()), retry) {
+            (Some(wait), Some(retry)) if wait <= MAX_RATE_LIMIT_WAIT => {
+                warn!(
+                    "{} rate limited, retrying in {}s",
+                    self.log_tag,
+                    wait.as_secs()
+                );
+                tokio::time::sleep(wait).await;
+                self.send_once(retry).await
+            }
+            _ => Ok(resp),
+        }
+    }
+
+    /// Transport errors and 5xx responses count as failures; while the
+    /// circuit is open nothing is sent
+    async fn send_once(
+        &self,
+        builder: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, CloudError> {
+        if !self.breaker.allow() {
+            let retry_in = self.breaker.retry_in().unwrap_or_default().as_secs();
+            return Err(CloudError::NetworkError(format!(
+                "backend unavailable, retrying in {retry_in}s"
+            )));
+        }
+
+        match builder.send().await {
+            Ok(resp) => {
+                if resp.status().is_server_error() {
+                    self.breaker.record_failure();
+                } else {
+                    self.breaker.record_success();
+                }
+                Ok(resp)
+            }
+            Err(err) => {
+                self.breaker.record_failure();
+                Err(CloudError::NetworkError(err.to_string()))
+            }
+        }
+    }
+
+    pub fn new(
+        settings: Arc<SettingsManager>,
+        mode: CloudMode,
+        breaker: Arc<CircuitBreaker>,
+    ) -> Result<Self, CloudError> {
         let config = settings
             .get_settings()
             .map_err(|e| CloudError::InvalidConfig(format!("settings load failed: {e}")))?;
@@ -430,6 +1047,10 @@ impl HttpCloudBackend {
             mode,
             log_tag,
             access_headers,
+            verified_device: Arc::new(tokio::sync::Mutex::new(None)),
+            breaker,
+            listings: Arc::new(Listings::default()),
+            zstd_support: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
@@ -442,7 +1063,7 @@ impl HttpCloudBackend {
         let base_url = match self.mode {
             CloudMode::Official => settings.cloud.base_url,
             CloudMode::SelfHost => settings.self_host.api_server,
-            CloudMode::Off => return Err(CloudError::Disabled),
+            CloudMode::Off | CloudMode::Demo => return Err(CloudError::Disabled),
         };
 
         if base_url.trim().is_empty() {
@@ -470,7 +1091,7 @@ impl HttpCloudBackend {
                 }
                 Ok(format!("Bearer {}", settings.self_host.access_key))
             }
-            CloudMode::Off => Err(CloudError::Disabled),
+            CloudMode::Off | CloudMode::Demo => Err(CloudError::Disabled),
         }
     }
 
@@ -478,26 +1099,70 @@ impl HttpCloudBackend {
         ensure_device_identity(&self.settings)
     }
 
-    async fn ensure_device_registered(&self) -> Result<String, CloudError> {
-        let (device_id, platform, device_name) = self.ensure_local_device_identity()?;
-        let base_url = self.validate_base_url()?;
-        let auth = self.get_auth_header()?;
-        let mut last_status: Option<reqwest::StatusCode> = None;
-        for attempt in 0..2 {
-            let resp = self
-                .apply_access_headers(
-                    self.client
-                        .post(format!("{}/device/register", base_url))
-                        .header("Authorization", auth.clone())
-                        .json(&serde_json::json!({
+    /// Ask the server whether `device_id` is registered. Servers without
+    /// `/device/check` report `false`, which falls back to registering.
+    async fn check_device_registered(
+        &self,
+        base_url: &str,
+        auth: &str,
+        device_id: &str,
+    ) -> Result<bool, CloudError> {
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/device/check", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({ "device_id": device_id })),
+            )
+            .await?;
+
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(CloudError::Unauthorized("invalid token".into()));
+        }
+
+        if !resp.status().is_success() {
+            debug!(
+                "{} device check unavailable ({}), registering",
+                self.log_tag,
+                resp.status()
+            );
+            return Ok(false);
+        }
+
+        let parsed: CheckDeviceResponse = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+        if !parsed.registered {
+            info!("{} device unknown to server, registering", self.log_tag);
+        }
+        Ok(parsed.registered)
+    }
+
+    async fn register_device(
+        &self,
+        base_url: &str,
+        auth: &str,
+        device_id: &str,
+        platform: &str,
+        device_name: &str,
+    ) -> Result<(), CloudError> {
+        let mut last_status: Option<reqwest::StatusCode> = None;
+        for attempt in 0..2 {
+            let resp = self
+                .send(
+                    self.client
+                        .post(format!("{}/device/register", base_url))
+                        .header("Authorization", auth)
+                        .json(&serde_json::json!({
                             "device_id": device_id,
                             "platform": platform,
                             "device_name": device_name,
+                            "fingerprint": device_fingerprint(),
+                            "new_install": new_install(),
                         })),
                 )
-                .send()
-                .await
-                .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+                .await?;
 
             if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
                 return Err(CloudError::Unauthorized("invalid token".into()));
@@ -508,12 +1173,12 @@ impl HttpCloudBackend {
                     .settings
                     .get_settings()
                     .map_err(|e| CloudError::InvalidConfig(format!("settings load failed: {e}")))?;
-                if !app_settings.cloud.has_registered_device {
-                    app_settings.cloud.has_registered_device = true;
+                if !app_settings.device_registered() {
+                    app_settings.mark_device_registered();
                     let _ = self.settings.update_settings(app_settings);
                 }
 
-                return Ok(device_id);
+                return Ok(());
             }
 
             last_status = Some(resp.status());
@@ -532,6 +1197,29 @@ impl HttpCloudBackend {
                 .unwrap_or_else(|| "unknown status".to_string())
         )))
     }
+
+    /// Upload the version's thumbnail, returning the hash to report in
+    /// notify-upload. A failed thumbnail never fails the save upload.
+    async fn upload_version_thumbnail(
+        &self,
+        metadata: &SaveMetadata,
+        upload_url: Option<&str>,
+    ) -> Option<String> {
+        let (Some(url), Some(thumbnail)) = (upload_url, metadata.thumbnail.as_deref()) else {
+            return None;
+        };
+
+        match upload_thumbnail(&self.client, url, Path::new(thumbnail)).await {
+            Ok(()) => metadata.thumbnail_sha256.clone(),
+            Err(err) => {
+                warn!(
+                    "{} Thumbnail upload for {} failed: {}",
+                    self.log_tag, metadata.version_id, err
+                );
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -541,7 +1229,7 @@ impl CloudBackend for HttpCloudBackend {
         let base_url = self.validate_base_url()?;
         let (device_id, platform, device_name) = self.ensure_local_device_identity()?;
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/signup", base_url))
                     .json(&serde_json::json!({
@@ -550,11 +1238,10 @@ impl CloudBackend for HttpCloudBackend {
                         "device_id": device_id,
                         "platform": platform,
                         "device_name": device_name,
+                        "fingerprint": device_fingerprint(),
                     })),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -596,7 +1283,7 @@ impl CloudBackend for HttpCloudBackend {
             app_settings.cloud.device_name = device_name;
         }
         app_settings.cloud.user_id = parsed.user_id.clone();
-        app_settings.cloud.has_registered_device = true;
+        app_settings.mark_device_registered();
         self.settings
             .update_settings(app_settings)
             .map_err(|e| CloudError::InvalidConfig(format!("settings save failed: {e}")))?;
@@ -608,7 +1295,7 @@ impl CloudBackend for HttpCloudBackend {
         let base_url = self.validate_base_url()?;
         let (device_id, platform, device_name) = self.ensure_local_device_identity()?;
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/login", base_url))
                     .json(&serde_json::json!({
@@ -617,11 +1304,11 @@ impl CloudBackend for HttpCloudBackend {
                         "device_id": device_id,
                         "platform": platform,
                         "device_name": device_name,
+                        "fingerprint": device_fingerprint(),
+                        "new_install": new_install(),
                     })),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -711,35 +1398,72 @@ impl CloudBackend for HttpCloudBackend {
             emulator_id: Some(metadata.emulator_id.clone()),
             device_id: Some(device_id.clone()),
             worker_token: None,
+            thumbnail_sha256: metadata.thumbnail_sha256.clone(),
+            note: metadata.note.clone(),
+            tags: metadata.tags.clone(),
+            pinned: metadata.pinned,
+            parent_version_id: metadata.parent_version_id.clone(),
+            encrypted: metadata.encrypted,
+            encoding: None,
         };
 
+        if link_duplicate(self, &upload_request).await {
+            return Ok(CloudVersionSummary {
+                version_id: upload_request.version_id,
+                timestamp: metadata.timestamp,
+                size_bytes: upload_request.size_bytes,
+                device_id,
+                file_list: upload_request.file_list,
+                sha256: hash,
+                thumbnail_url: None,
+                note: upload_request.note,
+                tags: upload_request.tags,
+                pinned: upload_request.pinned,
+                parent_version_id: upload_request.parent_version_id,
+                encrypted: upload_request.encrypted,
+            });
+        }
+
+        let wanted =
+            (!metadata.encrypted && self.supports_zstd().await).then_some(ArchiveEncoding::Zstd);
+        let (archive_file, encoding) =
+            tauri::async_runtime::spawn_blocking(move || encode_for_upload(&archive_path, wanted))
+                .await
+                .map_err(|e| CloudError::Io(e.to_string()))?
+                .map_err(|e| CloudError::Io(e.to_string()))?;
+        upload_request.encoding = encoding;
+
         let signed = self.request_upload_url(upload_request.clone()).await?;
 
         upload_request.worker_token = signed.worker_token.clone();
 
-        let archive_file = fs::read(&archive_path).map_err(|e| CloudError::Io(e.to_string()))?;
+        let content_type = encoding.map_or(ARCHIVE_CONTENT_TYPE, ArchiveEncoding::content_type);
         let resp = self
-            .client
-            .put(&signed.upload_url)
-            .header(CONTENT_TYPE, "application/zip")
-            .header(CONTENT_LENGTH, archive_size)
-            .body(archive_file)
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .send_raw(
+                self.client
+                    .put(&signed.upload_url)
+                    .header(CONTENT_TYPE, content_type)
+                    .header(CONTENT_LENGTH, archive_file.len() as u64)
+                    .body(archive_file),
+            )
+            .await?;
 
         if !resp.status().is_success() {
-            error!(
-                "{} Upload failed with status {}",
-                self.log_tag,
-                resp.status()
-            );
-            return Err(CloudError::NetworkError(format!(
-                "upload failed: {}",
-                resp.status()
-            )));
+            let status = resp.status();
+            error!("{} Upload failed with status {}", self.log_tag, status);
+            let body = resp.text().await.unwrap_or_default();
+            if is_cloud_quota_response(status, &body) {
+                return Err(CloudError::QuotaExceeded(format!(
+                    "upload failed: {status}"
+                )));
+            }
+            return Err(CloudError::NetworkError(format!("upload failed: {status}")));
         }
 
+        upload_request.thumbnail_sha256 = self
+            .upload_version_thumbnail(&metadata, signed.thumbnail_upload_url.as_deref())
+            .await;
+
         self.notify_upload_complete(upload_request.clone()).await?;
 
         Ok(CloudVersionSummary {
@@ -749,9 +1473,142 @@ impl CloudBackend for HttpCloudBackend {
             device_id,
             file_list: upload_request.file_list,
             sha256: hash,
+            thumbnail_url: None,
+            note: upload_request.note,
+            tags: upload_request.tags,
+            pinned: upload_request.pinned,
+            parent_version_id: upload_request.parent_version_id,
+            encrypted: upload_request.encrypted,
         })
     }
 
+    async fn supports_zstd(&self) -> bool {
+        let Ok(base_url) = self.validate_base_url() else {
+            return false;
+        };
+        if let Some((url, zstd)) = &*self.zstd_support.lock().unwrap_or_else(|p| p.into_inner()) {
+            if *url == base_url {
+                return *zstd;
+            }
+        }
+
+        #[derive(Deserialize)]
+        struct Health {
+            #[serde(default)]
+            capabilities: Vec<String>,
+        }
+        // A failed probe isn't cached, so the next upload asks again
+        let health = match self
+            .send(
+                self.client
+                    .get(format!("{}/health", base_url))
+                    .timeout(Duration::from_secs(3)),
+            )
+            .await
+        {
+            Ok(resp) if resp.status().is_success() => resp.json::<Health>().await.ok(),
+            Ok(resp) => {
+                debug!("{} /health returned {}", self.log_tag, resp.status());
+                return false;
+            }
+            Err(err) => {
+                debug!("{} /health failed: {}", self.log_tag, err);
+                return false;
+            }
+        };
+        let zstd = health.is_some_and(|health| health.capabilities.iter().any(|c| c == "zstd"));
+        debug!("{} zstd uploads supported: {}", self.log_tag, zstd);
+        *self.zstd_support.lock().unwrap_or_else(|p| p.into_inner()) = Some((base_url, zstd));
+        zstd
+    }
+
+    async fn find_version_by_hash(
+        &self,
+        game_id: String,
+        sha256: String,
+    ) -> Result<Option<String>, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .get(format!("{}/save/exists", base_url))
+                    .header("Authorization", auth)
+                    .query(&[("game_id", &game_id), ("sha256", &sha256)]),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            // The official cloud and servers from before deduplication
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound("version lookup".into()))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "version lookup failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        #[derive(Deserialize)]
+        struct ExistsResponse {
+            #[serde(default)]
+            version_id: Option<String>,
+        }
+
+        let parsed: ExistsResponse = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+        Ok(parsed.version_id)
+    }
+
+    async fn link_version(
+        &self,
+        payload: UploadRequest,
+        source_version_id: String,
+    ) -> Result<(), CloudError> {
+        let device_id = self.ensure_device_registered().await?;
+        let mut payload = payload;
+        payload.device_id = Some(device_id);
+
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+        let mut body =
+            serde_json::to_value(&payload).map_err(|e| CloudError::Serialization(e.to_string()))?;
+        body["source_version_id"] = serde_json::Value::String(source_version_id);
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/save/link", base_url))
+                    .header("Authorization", auth)
+                    .json(&body),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound("version lookup".into()))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!("link failed: {status}")))
+            }
+            _ => {}
+        }
+
+        self.listings.clear();
+        Ok(())
+    }
+
     async fn request_upload_url(
         &self,
         payload: UploadRequest,
@@ -764,24 +1621,28 @@ impl CloudBackend for HttpCloudBackend {
         let auth = self.get_auth_header()?;
 
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/save/upload-url", base_url))
                     .header("Authorization", auth)
                     .json(&payload),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
         }
 
         if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            if is_cloud_quota_response(status, &body) {
+                return Err(CloudError::QuotaExceeded(format!(
+                    "upload url failed: {status}"
+                )));
+            }
             return Err(CloudError::NetworkError(format!(
-                "upload url failed: {}",
-                resp.status()
+                "upload url failed: {status}"
             )));
         }
 
@@ -802,27 +1663,32 @@ impl CloudBackend for HttpCloudBackend {
         let auth = self.get_auth_header()?;
 
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/save/notify-upload", base_url))
                     .header("Authorization", auth)
                     .json(&payload),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
         }
 
         if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            if is_cloud_quota_response(status, &body) {
+                return Err(CloudError::QuotaExceeded(format!(
+                    "notify upload failed: {status}"
+                )));
+            }
             return Err(CloudError::NetworkError(format!(
-                "notify upload failed: {}",
-                resp.status()
+                "notify upload failed: {status}"
             )));
         }
 
+        self.listings.clear();
         Ok(())
     }
 
@@ -836,15 +1702,18 @@ impl CloudBackend for HttpCloudBackend {
         let auth = self.get_auth_header()?;
 
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/save/download-url", base_url))
                     .header("Authorization", auth)
-                    .json(&serde_json::json!({ "game_id": game_id, "version_id": version_id })),
+                    .json(&serde_json::json!({
+                        "game_id": game_id,
+                        "version_id": version_id,
+                        // Without it the server hands out a plain zip
+                        "accept_encoding": [ArchiveEncoding::Zstd],
+                    })),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
@@ -871,17 +1740,28 @@ impl CloudBackend for HttpCloudBackend {
         let auth = self.get_auth_header()?;
 
         let payload = serde_json::json!({ "game_id": game_id.clone() });
+        let key = listing_key(&base_url, &auth, &format!("list {game_id}"));
 
-        let resp = self
-            .apply_access_headers(
+        let listed = self
+            .send_listing(
+                &self.listings.versions,
+                &key,
                 self.client
                     .post(format!("{}/save/list", base_url))
                     .header("Authorization", auth)
                     .json(&payload),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
+        let (resp, etag) = match listed {
+            Listed::Cached(versions) => {
+                debug!(
+                    "{} list_versions game_id={} not modified",
+                    self.log_tag, game_id
+                );
+                return Ok(limit_versions(versions, limit));
+            }
+            Listed::Fresh { response, etag } => (response, etag),
+        };
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
@@ -900,18 +1780,6 @@ impl CloudBackend for HttpCloudBackend {
             )));
         }
 
-        #[derive(Deserialize)]
-        struct SaveListVersion {
-            version_id: String,
-            size_bytes: u64,
-            timestamp: u64,
-            #[serde(default)]
-            device_id: String,
-            sha256: String,
-            #[serde(default)]
-            file_list: Vec<String>,
-        }
-
         #[derive(Deserialize)]
         struct SaveListResponse {
             ok: bool,
@@ -935,20 +1803,17 @@ impl CloudBackend for HttpCloudBackend {
         let mut versions: Vec<CloudVersionSummary> = parsed
             .versions
             .into_iter()
-            .map(|entry| CloudVersionSummary {
-                version_id: entry.version_id,
-                timestamp: entry.timestamp,
-                size_bytes: entry.size_bytes,
-                device_id: entry.device_id,
-                file_list: entry.file_list,
-                sha256: entry.sha256,
-            })
+            .map(CloudVersionSummary::from)
             .collect();
 
         versions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        if let Some(limit) = limit {
-            versions.truncate(limit.min(versions.len()));
+        if let Some(etag) = etag {
+            let presigned = versions.iter().any(|v| v.thumbnail_url.is_some());
+            self.listings
+                .versions
+                .store(key, etag, versions.clone(), presigned);
         }
+        let versions = limit_versions(versions, limit);
 
         info!(
             "{} list_versions game_id={} count={}",
@@ -960,6 +1825,85 @@ impl CloudBackend for HttpCloudBackend {
         Ok(versions)
     }
 
+    async fn list_latest_versions(
+        &self,
+        game_ids: Vec<String>,
+    ) -> Result<HashMap<String, CloudVersionSummary>, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let requested = game_ids.len();
+        let key = listing_key(&base_url, &auth, &format!("latest {}", game_ids.join(",")));
+        let payload = serde_json::json!({ "game_ids": game_ids });
+        let listed = self
+            .send_listing(
+                &self.listings.latest,
+                &key,
+                self.client
+                    .post(format!("{}/save/latest-batch", base_url))
+                    .header("Authorization", auth)
+                    .json(&payload),
+            )
+            .await?;
+        let (resp, etag) = match listed {
+            Listed::Cached(latest) => {
+                debug!("{} list_latest_versions not modified", self.log_tag);
+                return Ok(latest);
+            }
+            Listed::Fresh { response, etag } => (response, etag),
+        };
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()));
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound("latest-batch endpoint".into()));
+            }
+            status if !status.is_success() => {
+                error!(
+                    "{} list_latest_versions failed: status={}",
+                    self.log_tag, status
+                );
+                return Err(CloudError::NetworkError(format!(
+                    "latest batch failed: {}",
+                    status
+                )));
+            }
+            _ => {}
+        }
+
+        #[derive(Deserialize)]
+        struct LatestBatchResponse {
+            #[serde(default)]
+            versions: Vec<SaveListVersion>,
+        }
+
+        let parsed: LatestBatchResponse = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+
+        let latest: HashMap<String, CloudVersionSummary> = parsed
+            .versions
+            .into_iter()
+            .map(|entry| (entry.game_id.clone(), CloudVersionSummary::from(entry)))
+            .collect();
+        if let Some(etag) = etag {
+            let presigned = latest.values().any(|v| v.thumbnail_url.is_some());
+            self.listings
+                .latest
+                .store(key, etag, latest.clone(), presigned);
+        }
+        debug!(
+            "{} list_latest_versions requested={} found={}",
+            self.log_tag,
+            requested,
+            latest.len()
+        );
+        Ok(latest)
+    }
+
     async fn download_version(
         &self,
         game_id: String,
@@ -975,13 +1919,13 @@ impl CloudBackend for HttpCloudBackend {
         }
 
         let mut resp = self
-            .client
-            .get(format!("{}/save/download", base_url))
-            .header("Authorization", auth)
-            .query(&[("game_id", game_id), ("version_id", version_id)])
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .send(
+                self.client
+                    .get(format!("{}/save/download", base_url))
+                    .header("Authorization", auth)
+                    .query(&[("game_id", game_id), ("version_id", version_id)]),
+            )
+            .await?;
 
         if !resp.status().is_success() {
             error!(
@@ -997,18 +1941,118 @@ impl CloudBackend for HttpCloudBackend {
 
         let mut file = tokio::fs::File::create(&target_path)
             .await
-            .map_err(|e| CloudError::Io(e.to_string()))?;
+            .map_err(write_error)?;
         while let Some(chunk) = resp
             .chunk()
             .await
             .map_err(|e| CloudError::NetworkError(e.to_string()))?
         {
-            file.write_all(&chunk)
-                .await
-                .map_err(|e| CloudError::Io(e.to_string()))?;
+            file.write_all(&chunk).await.map_err(write_error)?;
         }
+        drop(file);
 
-        Ok(())
+        tauri::async_runtime::spawn_blocking(move || decode_download_file(&target_path))
+            .await
+            .map_err(|e| CloudError::Io(e.to_string()))?
+            .map_err(|e| CloudError::Io(e.to_string()))
+    }
+
+    async fn download_file(
+        &self,
+        game_id: String,
+        version_id: String,
+        path: String,
+    ) -> Result<Vec<u8>, CloudError> {
+        self.ensure_device_registered().await?;
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/save/download-file", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({
+                        "game_id": game_id,
+                        "version_id": version_id,
+                        "path": path,
+                    })),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound(format!(
+                    "{path} in version {version_id}"
+                )))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "file download failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        if resp
+            .content_length()
+            .is_some_and(|length| length > MAX_CLOUD_FILE_BYTES)
+        {
+            return Err(CloudError::StorageError(format!(
+                "{path} is larger than {MAX_CLOUD_FILE_BYTES} bytes"
+            )));
+        }
+        let data = resp
+            .bytes()
+            .await
+            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+        debug!(
+            "{} download_file game_id={} version_id={} bytes={}",
+            self.log_tag,
+            game_id,
+            version_id,
+            data.len()
+        );
+        Ok(data.to_vec())
+    }
+
+    /// Make sure the server knows this device. A confirmation is cached for
+    /// `DEVICE_VERIFY_TTL`; after that `/device/check` is asked first and
+    /// `/device/register` is only called when the device is unknown.
+    async fn ensure_device_registered(&self) -> Result<String, CloudError> {
+        let (device_id, platform, device_name) = self.ensure_local_device_identity()?;
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+        let auth_digest = format!("{:x}", Sha256::digest(auth.as_bytes()));
+
+        let mut verified = self.verified_device.lock().await;
+        let cached = verified.as_ref().is_some_and(|entry| {
+            entry.device_id == device_id
+                && entry.auth_digest == auth_digest
+                && entry.verified_at.elapsed() < DEVICE_VERIFY_TTL
+        });
+        if cached {
+            return Ok(device_id);
+        }
+        *verified = None;
+
+        if !self
+            .check_device_registered(&base_url, &auth, &device_id)
+            .await?
+        {
+            self.register_device(&base_url, &auth, &device_id, &platform, &device_name)
+                .await?;
+        }
+
+        *verified = Some(VerifiedDevice {
+            device_id: device_id.clone(),
+            auth_digest,
+            verified_at: Instant::now(),
+        });
+        Ok(device_id)
     }
 
     fn ensure_device_id(&self) -> Result<String, CloudError> {
@@ -1040,17 +2084,102 @@ impl CloudBackend for HttpCloudBackend {
         Ok(device_id)
     }
 
+    async fn delete_version(&self, game_id: String, version_id: String) -> Result<(), CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/save/delete", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({
+                        "game_id": game_id,
+                        "version_id": version_id,
+                        // Without it the server hands out a plain zip
+                        "accept_encoding": [ArchiveEncoding::Zstd],
+                    })),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound(format!("version {version_id}")))
+            }
+            reqwest::StatusCode::CONFLICT => {
+                return Err(CloudError::Conflict(format!(
+                    "version {version_id} is pinned"
+                )))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!("delete failed: {status}")))
+            }
+            _ => {}
+        }
+
+        self.listings.clear();
+        info!(
+            "{} delete_version game_id={} version_id={}",
+            self.log_tag, game_id, version_id
+        );
+        Ok(())
+    }
+
+    async fn set_version_pinned(
+        &self,
+        game_id: String,
+        version_id: String,
+        pinned: bool,
+    ) -> Result<(), CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/save/pin", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({
+                        "game_id": game_id,
+                        "version_id": version_id,
+                        "pinned": pinned,
+                    })),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound(format!("version {version_id}")))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!("pin failed: {status}")))
+            }
+            _ => {}
+        }
+
+        self.listings.clear();
+        info!(
+            "{} set_version_pinned game_id={} version_id={} pinned={}",
+            self.log_tag, game_id, version_id, pinned
+        );
+        Ok(())
+    }
+
     async fn list_devices(&self, token: String) -> Result<Vec<CloudDevice>, CloudError> {
         let base_url = self.validate_base_url()?;
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .get(format!("{}/device/list", base_url))
                     .header("Authorization", format!("Bearer {}", token)),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
@@ -1080,7 +2209,7 @@ impl CloudBackend for HttpCloudBackend {
     ) -> Result<(), CloudError> {
         let base_url = self.validate_base_url()?;
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/device/register", base_url))
                     .header("Authorization", format!("Bearer {}", token))
@@ -1088,11 +2217,11 @@ impl CloudBackend for HttpCloudBackend {
                         "device_id": device_id.clone(),
                         "platform": platform.clone(),
                         "device_name": device_name.clone(),
+                        "fingerprint": device_fingerprint(),
+                        "new_install": new_install(),
                     })),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
@@ -1116,7 +2245,7 @@ impl CloudBackend for HttpCloudBackend {
         if app_settings.cloud.device_name.trim().is_empty() {
             app_settings.cloud.device_name = device_name;
         }
-        app_settings.cloud.has_registered_device = true;
+        app_settings.mark_device_registered();
         self.settings
             .update_settings(app_settings)
             .map_err(|e| CloudError::InvalidConfig(format!("settings save failed: {e}")))?;
@@ -1127,15 +2256,13 @@ impl CloudBackend for HttpCloudBackend {
     async fn remove_device(&self, token: String, device_id: String) -> Result<(), CloudError> {
         let base_url = self.validate_base_url()?;
         let resp = self
-            .apply_access_headers(
+            .send(
                 self.client
                     .post(format!("{}/device/remove", base_url))
                     .header("Authorization", format!("Bearer {}", token))
                     .json(&serde_json::json!({"device_id": device_id.clone()})),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
 
         if !resp.status().is_success() {
             return Err(CloudError::NetworkError(format!(
@@ -1181,6 +2308,11 @@ impl CloudBackend for HttpCloudBackend {
         
         debug!("[CLOUD] check_connection: Checking {}/api/games", base_url);
         
+        if !self.breaker.allow() {
+            debug!("[CLOUD] check_connection: Circuit open, skipping probe");
+            return Ok(false);
+        }
+
         // Simple HEAD request to check if server is reachable
         // ANY HTTP response (even 404) means server is online
         match self.client
@@ -1193,11 +2325,13 @@ impl CloudBackend for HttpCloudBackend {
                 // Any response means server is reachable = online
                 // This includes 404, 401, 500, etc.
                 debug!("[CLOUD] check_connection: Got response status={}, server is ONLINE", resp.status());
+                self.breaker.record_success();
                 Ok(true)
             }
             Err(e) => {
                 // Only network/timeout errors mean offline
                 debug!("[CLOUD] check_connection: Request failed: {}, server is OFFLINE", e);
+                self.breaker.record_failure();
                 Ok(false)
             }
         }
@@ -1207,15 +2341,23 @@ impl CloudBackend for HttpCloudBackend {
         let base_url = self.validate_base_url()?;
         let auth = self.get_auth_header()?;
 
-        let resp = self
-            .apply_access_headers(
+        let key = listing_key(&base_url, &auth, "games");
+        let listed = self
+            .send_listing(
+                &self.listings.games,
+                &key,
                 self.client
                     .post(format!("{}/save/games", base_url))
                     .header("Authorization", auth),
             )
-            .send()
-            .await
-            .map_err(|e| CloudError::NetworkError(e.to_string()))?;
+            .await?;
+        let (resp, etag) = match listed {
+            Listed::Cached(games) => {
+                debug!("{} list_games not modified", self.log_tag);
+                return Ok(games);
+            }
+            Listed::Fresh { response, etag } => (response, etag),
+        };
 
         if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
             return Err(CloudError::Unauthorized("invalid token".into()));
@@ -1249,17 +2391,464 @@ impl CloudBackend for HttpCloudBackend {
             self.log_tag,
             parsed.games.len()
         );
+        if let Some(etag) = etag {
+            self.listings
+                .games
+                .store(key, etag, parsed.games.clone(), false);
+        }
 
         Ok(parsed.games)
     }
+
+    async fn list_account_activity(
+        &self,
+        limit: Option<usize>,
+    ) -> Result<Vec<AccountActivity>, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/account/activity", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({ "limit": limit })),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            // Servers from before the audit log
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound("account activity".into()))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "account activity failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        #[derive(Deserialize)]
+        struct ActivityResponse {
+            events: Vec<AccountActivity>,
+        }
+
+        let parsed: ActivityResponse = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+        Ok(parsed.events)
+    }
+
+    async fn merge_devices(
+        &self,
+        keep: Option<String>,
+        confirm: Vec<String>,
+        dry_run: bool,
+    ) -> Result<DeviceMerge, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/device/merge", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({
+                        "keep": keep,
+                        "confirm": confirm,
+                        "dry_run": dry_run,
+                    })),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            // Servers from before device fingerprints
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(CloudError::NotFound("device merge".into()))
+            }
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "device merge failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        let parsed: DeviceMerge = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+        if !dry_run {
+            info!(
+                "{} merged {} duplicate device groups",
+                self.log_tag,
+                parsed.merged.len()
+            );
+        }
+        Ok(parsed)
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<CloudSession>, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .get(format!("{}/session/list", base_url))
+                    .header("Authorization", auth),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            // The official cloud and servers from before sessions
+            reqwest::StatusCode::NOT_FOUND => return Err(CloudError::NotFound("sessions".into())),
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "session list failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        #[derive(Deserialize)]
+        struct SessionListResponse {
+            sessions: Vec<CloudSession>,
+        }
+
+        let parsed: SessionListResponse = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+        Ok(parsed.sessions)
+    }
+
+    async fn revoke_sessions(&self, session_id: Option<String>) -> Result<usize, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+        let body = match &session_id {
+            Some(session_id) => serde_json::json!({ "session_id": session_id }),
+            None => serde_json::json!({ "others": true }),
+        };
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/session/revoke", base_url))
+                    .header("Authorization", auth)
+                    .json(&body),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND if session_id.is_some() => {
+                return Err(CloudError::NotFound("session".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND => return Err(CloudError::NotFound("sessions".into())),
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "session revoke failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        #[derive(Deserialize)]
+        struct RevokeResponse {
+            #[serde(default)]
+            revoked: usize,
+        }
+
+        let parsed: RevokeResponse = resp
+            .json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))?;
+        info!("{} Revoked {} sessions", self.log_tag, parsed.revoked);
+        Ok(parsed.revoked)
+    }
+
+    async fn share_game(
+        &self,
+        game_id: String,
+        email: String,
+        access: ShareAccess,
+    ) -> Result<(), CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/share/grant", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({
+                        "game_id": game_id,
+                        "email": email.trim(),
+                        "access": access,
+                    })),
+            )
+            .await?;
+
+        let status = resp.status();
+        if status.is_success() {
+            info!(
+                "{} share_game game_id={} access={:?}",
+                self.log_tag, game_id, access
+            );
+            return Ok(());
+        }
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(CloudError::Unauthorized("invalid token".into()));
+        }
+
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            #[serde(default)]
+            error: String,
+        }
+        let error = resp
+            .json::<ErrorBody>()
+            .await
+            .map(|body| body.error)
+            .unwrap_or_default();
+
+        match (status.as_u16(), error.as_str()) {
+            (404, "user_not_found") => Err(CloudError::NotFound("share account".into())),
+            // The official cloud and servers from before sharing
+            (404, _) => Err(CloudError::NotFound("sharing".into())),
+            (_, "cannot_share_with_self") => Err(CloudError::Conflict(
+                "a game can't be shared with your own account".into(),
+            )),
+            (_, "game_shared_with_you") => Err(CloudError::Conflict(
+                "this game is shared with you, only its owner can share it".into(),
+            )),
+            _ => Err(CloudError::NetworkError(format!("share failed: {status}"))),
+        }
+    }
+
+    async fn list_shared_games(&self) -> Result<GameShares, CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .get(format!("{}/share/list", base_url))
+                    .header("Authorization", auth),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            // The official cloud and servers from before sharing
+            reqwest::StatusCode::NOT_FOUND => return Err(CloudError::NotFound("sharing".into())),
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "share list failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        resp.json()
+            .await
+            .map_err(|e| CloudError::Serialization(e.to_string()))
+    }
+
+    async fn unshare_game(
+        &self,
+        game_id: String,
+        grantee_user_id: Option<String>,
+    ) -> Result<(), CloudError> {
+        let base_url = self.validate_base_url()?;
+        let auth = self.get_auth_header()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/share/revoke", base_url))
+                    .header("Authorization", auth)
+                    .json(&serde_json::json!({
+                        "game_id": game_id,
+                        "grantee_user_id": grantee_user_id,
+                    })),
+            )
+            .await?;
+
+        match resp.status() {
+            reqwest::StatusCode::UNAUTHORIZED => {
+                return Err(CloudError::Unauthorized("invalid token".into()))
+            }
+            reqwest::StatusCode::NOT_FOUND => return Err(CloudError::NotFound("share".into())),
+            status if !status.is_success() => {
+                return Err(CloudError::NetworkError(format!(
+                    "unshare failed: {status}"
+                )))
+            }
+            _ => {}
+        }
+
+        // Leaving a shared game brings back this account's own slot for it
+        self.listings.clear();
+        info!("{} unshare_game game_id={}", self.log_tag, game_id);
+        Ok(())
+    }
+
+    async fn request_password_reset(&self, email: String) -> Result<(), CloudError> {
+        info!("{} Requesting password reset", self.log_tag);
+        let base_url = self.validate_base_url()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/account/password-reset/request", base_url))
+                    .json(&serde_json::json!({ "email": email })),
+            )
+            .await?;
+
+        match resp.status() {
+            // The official cloud, or a self-host server without SMTP
+            reqwest::StatusCode::NOT_FOUND => Err(CloudError::NotFound("password reset".into())),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => Err(CloudError::Unauthorized(
+                "Too many reset requests. Please try again later".into(),
+            )),
+            status if !status.is_success() => {
+                warn!(
+                    "{} Password reset request failed with status {}",
+                    self.log_tag, status
+                );
+                Err(CloudError::NetworkError(format!(
+                    "password reset request failed: {status}"
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn complete_password_reset(
+        &self,
+        token: String,
+        new_password: String,
+    ) -> Result<(), CloudError> {
+        info!("{} Completing password reset", self.log_tag);
+        let base_url = self.validate_base_url()?;
+
+        let resp = self
+            .send(
+                self.client
+                    .post(format!("{}/account/password-reset/confirm", base_url))
+                    .json(&serde_json::json!({
+                        "token": token.trim(),
+                        "new_password": new_password,
+                    })),
+            )
+            .await?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        warn!(
+            "{} Password reset failed with status {}",
+            self.log_tag, status
+        );
+
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            #[serde(default)]
+            error: String,
+        }
+        let error = resp
+            .json::<ErrorBody>()
+            .await
+            .map(|body| body.error)
+            .unwrap_or_default();
+
+        let message = match (status.as_u16(), error.as_str()) {
+            (404, _) => return Err(CloudError::NotFound("password reset".into())),
+            (_, "weak_password") => "Password must be at least 8 characters".to_string(),
+            (_, "token_expired") => "This reset code has expired. Request a new one".to_string(),
+            (_, "invalid_token") => "This reset code is invalid or was already used".to_string(),
+            (429, _) => "Too many attempts. Please try again later".to_string(),
+            (500..=599, _) => "Server error. Please try again later".to_string(),
+            _ => "Password reset failed. Please try again".to_string(),
+        };
+        Err(CloudError::Unauthorized(message))
+    }
 }
 
 // =============================================================================
 // Utility helpers
 // =============================================================================
 
+/// Record `payload` as a copy of a cloud version of the game with the same
+/// archive hash, if there is one. False means the archive still has to be
+/// uploaded, including when the backend can't deduplicate.
+pub async fn link_duplicate(backend: &dyn CloudBackend, payload: &UploadRequest) -> bool {
+    let found = backend
+        .find_version_by_hash(payload.game_id.clone(), payload.sha256.clone())
+        .await;
+    let source_version_id = match found {
+        Ok(Some(version_id)) => version_id,
+        Ok(None) | Err(CloudError::NotFound(_)) => return false,
+        Err(err) => {
+            debug!("Duplicate check for {} failed: {}", payload.version_id, err);
+            return false;
+        }
+    };
+
+    match backend
+        .link_version(payload.clone(), source_version_id.clone())
+        .await
+    {
+        Ok(()) => {
+            info!(
+                "Version {} has the archive of cloud version {}; linked without uploading",
+                payload.version_id, source_version_id
+            );
+            true
+        }
+        Err(err) => {
+            warn!("Linking {} failed, uploading it instead: {}", payload.version_id, err);
+            false
+        }
+    }
+}
+
+/// `Retry-After` in seconds; the HTTP-date form isn't sent by our servers
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// This install's stable id, or a random one when the machine has no id
+/// to derive it from
 pub fn default_device_id() -> String {
-    Uuid::new_v4().to_string()
+    device_identity()
+        .map(|identity| identity.device_id.clone())
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
 }
 
 pub fn default_device_name(platform: &str) -> String {
@@ -1274,7 +2863,7 @@ pub fn default_device_name(platform: &str) -> String {
     label.to_string()
 }
 
-fn calculate_sha256(path: &PathBuf) -> Result<String, CloudError> {
+pub(crate) fn calculate_sha256(path: &PathBuf) -> Result<String, CloudError> {
     let mut file = fs::File::open(path).map_err(|e| CloudError::Io(e.to_string()))?;
     let mut hasher = Sha256::new();
     std::io::copy(&mut file, &mut hasher).map_err(|e| CloudError::Io(e.to_string()))?;
@@ -1282,10 +2871,20 @@ fn calculate_sha256(path: &PathBuf) -> Result<String, CloudError> {
     Ok(format!("{:x}", result))
 }
 
+/// Local write failures, keeping "disk full" apart from other I/O errors
+fn write_error(err: std::io::Error) -> CloudError {
+    if is_storage_full(&err) {
+        CloudError::StorageError(format!("no space left on device: {err}"))
+    } else {
+        CloudError::Io(err.to_string())
+    }
+}
+
 pub fn log_tag(mode: &CloudMode) -> &'static str {
     match mode {
         CloudMode::Official => "[CLOUD_OFFICIAL]",
         CloudMode::SelfHost => "[CLOUD_SELF_HOST]",
         CloudMode::Off => "[CLOUD_DISABLED]",
+        CloudMode::Demo => "[CLOUD_DEMO]",
     }
 }
@@ -0,0 +1,281 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Resolutions kept on disk; older ones are dropped first
+const MAX_RECORDS: usize = 200;
+/// Matching manual resolutions in a row before a rule is suggested
+const SUGGESTION_STREAK: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum ConflictError {
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("serialization error: {0}")]
+    Serialization(String),
+    #[error("lock error: {0}")]
+    Lock(String),
+    #[error("no suggestion for {0}")]
+    NoSuggestion(String),
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    Local,
+    Cloud,
+}
+
+/// How one conflict was resolved, with the context needed to spot habits
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConflictRecord {
+    pub game_id: String,
+    pub kept: ConflictSide,
+    pub local_device_id: String,
+    pub cloud_device_id: String,
+    pub local_timestamp: u64,
+    pub cloud_timestamp: u64,
+    #[serde(default)]
+    pub time_gap_secs: u64,
+    pub resolved_at: u64,
+    /// Applied by a rule rather than chosen by the user
+    #[serde(default)]
+    pub automatic: bool,
+}
+
+impl ConflictRecord {
+    pub fn kept_device_id(&self) -> &str {
+        match self.kept {
+            ConflictSide::Local => &self.local_device_id,
+            ConflictSide::Cloud => &self.cloud_device_id,
+        }
+    }
+}
+
+/// Per-game policy used to resolve conflicts without asking
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConflictRule {
+    pub game_id: String,
+    /// Keep whichever side was saved on this device
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// Side to keep when `device_id` is unset or matches neither side
+    pub keep: ConflictSide,
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl ConflictRule {
+    pub fn resolve(&self, local_device_id: &str, cloud_device_id: &str) -> ConflictSide {
+        match self.device_id.as_deref() {
+            Some(device) if device == local_device_id => ConflictSide::Local,
+            Some(device) if device == cloud_device_id => ConflictSide::Cloud,
+            _ => self.keep,
+        }
+    }
+}
+
+/// A rule proposed from the user's recent manual resolutions
+#[derive(Clone, Debug, Serialize)]
+pub struct RuleSuggestion {
+    pub rule: ConflictRule,
+    /// Manual resolutions in a row that agree with `rule`
+    pub occurrences: usize,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ConflictLog {
+    #[serde(default)]
+    records: Vec<ConflictRecord>,
+    #[serde(default)]
+    rules: Vec<ConflictRule>,
+    /// Games whose suggestion the user turned down
+    #[serde(default)]
+    dismissed: Vec<String>,
+}
+
+pub struct ConflictManager {
+    path: PathBuf,
+    state: Mutex<ConflictLog>,
+}
+
+impl ConflictManager {
+    pub fn new(path: PathBuf) -> Result<Self, ConflictError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| ConflictError::Io(err.to_string()))?;
+        }
+
+        let log = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                warn!("[SYNC] Failed to parse conflict log: {err}. Starting empty");
+                ConflictLog::default()
+            }),
+            Err(_) => ConflictLog::default(),
+        };
+
+        Ok(Self {
+            path,
+            state: Mutex::new(log),
+        })
+    }
+
+    /// Store a resolution, returning a rule suggestion when it completes a streak
+    pub fn record(&self, record: ConflictRecord) -> Result<Option<RuleSuggestion>, ConflictError> {
+        let game_id = record.game_id.clone();
+        let mut guard = self.lock()?;
+        guard.records.push(record);
+        if guard.records.len() > MAX_RECORDS {
+            let excess = guard.records.len() - MAX_RECORDS;
+            guard.records.drain(..excess);
+        }
+        self.persist(&guard)?;
+
+        Ok(Self::suggestion_for(&guard, &game_id))
+    }
+
+    pub fn records(&self, game_id: Option<&str>) -> Result<Vec<ConflictRecord>, ConflictError> {
+        let guard = self.lock()?;
+        Ok(guard
+            .records
+            .iter()
+            .rev()
+            .filter(|record| match game_id {
+                Some(id) => record.game_id == id,
+                None => true,
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Suggestions for every game without a rule or a dismissed suggestion
+    pub fn suggestions(&self) -> Result<Vec<RuleSuggestion>, ConflictError> {
+        let guard = self.lock()?;
+        let mut games: Vec<&str> = guard
+            .records
+            .iter()
+            .map(|record| record.game_id.as_str())
+            .collect();
+        games.sort_unstable();
+        games.dedup();
+
+        Ok(games
+            .into_iter()
+            .filter_map(|game_id| Self::suggestion_for(&guard, game_id))
+            .collect())
+    }
+
+    /// Turn the current suggestion for `game_id` into a rule
+    pub fn accept_suggestion(&self, game_id: &str) -> Result<ConflictRule, ConflictError> {
+        let suggestion = {
+            let guard = self.lock()?;
+            Self::suggestion_for(&guard, game_id)
+        }
+        .ok_or_else(|| ConflictError::NoSuggestion(game_id.to_string()))?;
+
+        self.set_rule(suggestion.rule.clone())?;
+        Ok(suggestion.rule)
+    }
+
+    pub fn dismiss_suggestion(&self, game_id: &str) -> Result<(), ConflictError> {
+        let mut guard = self.lock()?;
+        if !guard.dismissed.iter().any(|id| id == game_id) {
+            guard.dismissed.push(game_id.to_string());
+        }
+        self.persist(&guard)
+    }
+
+    pub fn rules(&self) -> Result<Vec<ConflictRule>, ConflictError> {
+        Ok(self.lock()?.rules.clone())
+    }
+
+    pub fn rule_for(&self, game_id: &str) -> Option<ConflictRule> {
+        let guard = self.state.lock().ok()?;
+        guard
+            .rules
+            .iter()
+            .find(|rule| rule.game_id == game_id)
+            .cloned()
+    }
+
+    /// Add or replace the rule for `rule.game_id`
+    pub fn set_rule(&self, mut rule: ConflictRule) -> Result<(), ConflictError> {
+        if rule.created_at == 0 {
+            rule.created_at = Utc::now().timestamp().max(0) as u64;
+        }
+
+        let mut guard = self.lock()?;
+        guard
+            .rules
+            .retain(|existing| existing.game_id != rule.game_id);
+        guard.dismissed.retain(|id| *id != rule.game_id);
+        info!(
+            "[SYNC] Conflict rule for {}: keep {:?} (device {:?})",
+            rule.game_id, rule.keep, rule.device_id
+        );
+        guard.rules.push(rule);
+        self.persist(&guard)
+    }
+
+    pub fn remove_rule(&self, game_id: &str) -> Result<(), ConflictError> {
+        let mut guard = self.lock()?;
+        guard.rules.retain(|rule| rule.game_id != game_id);
+        self.persist(&guard)
+    }
+
+    /// The last `SUGGESTION_STREAK` manual resolutions for a game agree on
+    /// the device kept, or failing that on the side kept
+    fn suggestion_for(log: &ConflictLog, game_id: &str) -> Option<RuleSuggestion> {
+        if log.rules.iter().any(|rule| rule.game_id == game_id)
+            || log.dismissed.iter().any(|id| id == game_id)
+        {
+            return None;
+        }
+
+        let recent: Vec<&ConflictRecord> = log
+            .records
+            .iter()
+            .rev()
+            .filter(|record| record.game_id == game_id && !record.automatic)
+            .take(SUGGESTION_STREAK)
+            .collect();
+        let latest = recent.first()?;
+        if recent.len() < SUGGESTION_STREAK {
+            return None;
+        }
+
+        let device = latest.kept_device_id();
+        let same_device = !device.is_empty()
+            && recent
+                .iter()
+                .all(|record| record.kept_device_id() == device);
+        let same_side = recent.iter().all(|record| record.kept == latest.kept);
+        if !same_device && !same_side {
+            return None;
+        }
+
+        Some(RuleSuggestion {
+            rule: ConflictRule {
+                game_id: game_id.to_string(),
+                device_id: same_device.then(|| device.to_string()),
+                keep: latest.kept,
+                created_at: 0,
+            },
+            occurrences: recent.len(),
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, ConflictLog>, ConflictError> {
+        self.state
+            .lock()
+            .map_err(|err| ConflictError::Lock(err.to_string()))
+    }
+
+    fn persist(&self, log: &ConflictLog) -> Result<(), ConflictError> {
+        let json = serde_json::to_string_pretty(log)
+            .map_err(|err| ConflictError::Serialization(err.to_string()))?;
+        fs::write(&self.path, json).map_err(|err| ConflictError::Io(err.to_string()))
+    }
+}
@@ -0,0 +1,277 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::warn;
+
+use crate::core::settings::NetworkSettings;
+
+/// Network and power state that decides whether transfers may run now
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceConditions {
+    /// Mobile data or a hotspot the OS marks as metered
+    pub metered: bool,
+    pub charging: bool,
+    pub battery_saver: bool,
+    /// Android doze; the OS cuts network access for background work
+    pub idle: bool,
+}
+
+/// Why a transfer is being held back
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldReason {
+    Metered,
+    BatterySaver,
+    DeviceIdle,
+}
+
+impl std::fmt::Display for HoldReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HoldReason::Metered => "on a metered network",
+            HoldReason::BatterySaver => "battery saver is on",
+            HoldReason::DeviceIdle => "the device is idle",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    Upload,
+    Download,
+}
+
+/// Whether `transfer` should wait under `conditions`. Doze always holds
+/// transfers, since the OS would drop them anyway; the rest follow the
+/// user's settings.
+pub fn hold_reason(
+    settings: &NetworkSettings,
+    conditions: &DeviceConditions,
+    transfer: Transfer,
+) -> Option<HoldReason> {
+    if conditions.idle {
+        return Some(HoldReason::DeviceIdle);
+    }
+    if settings.pause_on_battery_saver && conditions.battery_saver && !conditions.charging {
+        return Some(HoldReason::BatterySaver);
+    }
+    if transfer == Transfer::Upload && settings.unmetered_uploads_only && conditions.metered {
+        return Some(HoldReason::Metered);
+    }
+    None
+}
+
+/// Reports the current [`DeviceConditions`] for the platform
+pub trait ConnectivityProvider: Send + Sync {
+    fn conditions(&self) -> DeviceConditions;
+}
+
+/// Asks the Android `ConnectivityPlugin` when it is registered. Elsewhere
+/// nothing is reported that would hold a transfer.
+pub struct PlatformConnectivity {
+    #[cfg_attr(not(target_os = "android"), allow(dead_code))]
+    app: AppHandle,
+}
+
+impl PlatformConnectivity {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl ConnectivityProvider for PlatformConnectivity {
+    fn conditions(&self) -> DeviceConditions {
+        #[cfg(target_os = "android")]
+        {
+            use tauri::Manager;
+
+            if let Some(plugin) = self.app.try_state::<android::ConnectivityPlugin>() {
+                return plugin.conditions();
+            }
+        }
+        DeviceConditions::default()
+    }
+}
+
+/// Last reported conditions, refreshed by the connection monitor so queue
+/// checks don't call into the platform every time
+pub struct Connectivity {
+    provider: Box<dyn ConnectivityProvider>,
+    current: Mutex<DeviceConditions>,
+}
+
+impl Connectivity {
+    /// Starts from the defaults until the first `refresh`
+    pub fn new(provider: Box<dyn ConnectivityProvider>) -> Self {
+        Self {
+            provider,
+            current: Mutex::new(DeviceConditions::default()),
+        }
+    }
+
+    /// Ask the provider again. Returns the new conditions and whether they
+    /// changed.
+    pub fn refresh(&self) -> (DeviceConditions, bool) {
+        let conditions = self.provider.conditions();
+        match self.current.lock() {
+            Ok(mut current) => {
+                let changed = *current != conditions;
+                *current = conditions;
+                (conditions, changed)
+            }
+            Err(err) => {
+                warn!("[SYNC] Failed to store device conditions: {err}");
+                (conditions, false)
+            }
+        }
+    }
+
+    pub fn current(&self) -> DeviceConditions {
+        self.current
+            .lock()
+            .map(|current| *current)
+            .unwrap_or_default()
+    }
+}
+
+/// Payload of `sync://conditions` and `get_device_conditions`
+#[derive(Clone, Debug, Serialize)]
+pub struct ConditionsPayload {
+    #[serde(flatten)]
+    pub conditions: DeviceConditions,
+    pub uploads_held: Option<HoldReason>,
+    pub downloads_held: Option<HoldReason>,
+}
+
+impl ConditionsPayload {
+    pub fn new(settings: &NetworkSettings, conditions: DeviceConditions) -> Self {
+        Self {
+            conditions,
+            uploads_held: hold_reason(settings, &conditions, Transfer::Upload),
+            downloads_held: hold_reason(settings, &conditions, Transfer::Download),
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+pub mod android {
+    use tauri::{
+        plugin::{Builder, PluginHandle, TauriPlugin},
+        Manager, Wry,
+    };
+    use tracing::warn;
+
+    use super::DeviceConditions;
+
+    const PLUGIN_PACKAGE: &str = "com.h1dr0n.crosssave_cloud";
+    const PLUGIN_CLASS: &str = "ConnectivityPlugin";
+
+    /// Network and battery state from `ConnectivityManager`,
+    /// `BatteryManager` and `PowerManager`
+    pub struct ConnectivityPlugin(PluginHandle<Wry>);
+
+    impl ConnectivityPlugin {
+        pub fn conditions(&self) -> DeviceConditions {
+            self.0
+                .run_mobile_plugin::<DeviceConditions>("conditions", serde_json::json!({}))
+                .unwrap_or_else(|err| {
+                    warn!("[SYNC] Failed to read device conditions: {err}");
+                    DeviceConditions::default()
+                })
+        }
+    }
+
+    pub fn init() -> TauriPlugin<Wry> {
+        Builder::new("connectivity")
+            .setup(|app, api| {
+                let handle = api.register_android_plugin(PLUGIN_PACKAGE, PLUGIN_CLASS)?;
+                app.manage(ConnectivityPlugin(handle));
+                Ok(())
+            })
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn settings(unmetered_uploads_only: bool, pause_on_battery_saver: bool) -> NetworkSettings {
+        NetworkSettings {
+            unmetered_uploads_only,
+            pause_on_battery_saver,
+        }
+    }
+
+    #[test]
+    fn metered_networks_hold_only_uploads() {
+        let metered = DeviceConditions {
+            metered: true,
+            ..Default::default()
+        };
+        let wifi_only = settings(true, false);
+        assert_eq!(
+            hold_reason(&wifi_only, &metered, Transfer::Upload),
+            Some(HoldReason::Metered)
+        );
+        assert_eq!(hold_reason(&wifi_only, &metered, Transfer::Download), None);
+        assert_eq!(
+            hold_reason(&settings(false, false), &metered, Transfer::Upload),
+            None
+        );
+    }
+
+    #[test]
+    fn battery_saver_pauses_unless_charging() {
+        let saver = DeviceConditions {
+            battery_saver: true,
+            ..Default::default()
+        };
+        let pause = settings(false, true);
+        assert_eq!(
+            hold_reason(&pause, &saver, Transfer::Download),
+            Some(HoldReason::BatterySaver)
+        );
+
+        let charging = DeviceConditions {
+            charging: true,
+            ..saver
+        };
+        assert_eq!(hold_reason(&pause, &charging, Transfer::Upload), None);
+    }
+
+    #[test]
+    fn doze_always_holds() {
+        let idle = DeviceConditions {
+            idle: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            hold_reason(&settings(false, false), &idle, Transfer::Download),
+            Some(HoldReason::DeviceIdle)
+        );
+    }
+
+    struct Fixed(Arc<Mutex<DeviceConditions>>);
+
+    impl ConnectivityProvider for Fixed {
+        fn conditions(&self) -> DeviceConditions {
+            *self.0.lock().expect("lock")
+        }
+    }
+
+    #[test]
+    fn refresh_reports_changes() {
+        let shared = Arc::new(Mutex::new(DeviceConditions::default()));
+        let connectivity = Connectivity::new(Box::new(Fixed(shared.clone())));
+        assert!(!connectivity.refresh().1);
+
+        shared.lock().expect("lock").metered = true;
+        assert!(connectivity.refresh().1);
+        assert!(connectivity.current().metered);
+        assert!(!connectivity.refresh().1);
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+
+use super::storage::is_storage_full;
+
+/// Save formats the built-in converters know, named after the emulator that
+/// writes them. DeSmuME appends its footer to the backup memory; the others
+/// store the backup memory as is and differ only in extension.
+pub const DESMUME: &str = "desmume";
+pub const DRASTIC: &str = "drastic";
+pub const RETROARCH: &str = "retroarch";
+/// Standalone emulators that write plain `.sav` files, e.g. melonDS or mGBA
+pub const RAW: &str = "raw";
+
+/// DeSmuME's footer: this text, six little-endian `u32` fields, the cookie
+const DESMUME_FOOTER_TEXT: &[u8] =
+    b"|<--Snip above here to create a raw sav by excluding this DeSmuME savedata footer:";
+const DESMUME_COOKIE: &[u8] = b"|-DESMUME SAVE-|";
+const DESMUME_FOOTER_LEN: usize = DESMUME_FOOTER_TEXT.len() + 6 * 4 + DESMUME_COOKIE.len();
+
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("no converter from {0} to {1}")]
+    Unsupported(String, String),
+    #[error("not a {0} save: {1}")]
+    InvalidSave(String, String),
+    #[error("converted save already exists: {0}")]
+    TargetExists(String),
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("not enough disk space: {0}")]
+    StorageFull(String),
+}
+
+impl From<io::Error> for ConvertError {
+    fn from(err: io::Error) -> Self {
+        if is_storage_full(&err) {
+            ConvertError::StorageFull(err.to_string())
+        } else {
+            ConvertError::Io(err.to_string())
+        }
+    }
+}
+
+/// Turns one save format into another. The registry asks converters in
+/// registration order; the first whose formats match converts.
+pub trait SaveConverter: Send + Sync {
+    /// Format read, e.g. `desmume`
+    fn source(&self) -> &str;
+    /// Format written
+    fn target(&self) -> &str;
+    /// Extension of converted files, without the dot
+    fn extension(&self) -> &str;
+    fn convert(&self, data: &[u8]) -> Result<Vec<u8>, ConvertError>;
+}
+
+/// The converters `convert_save` can use, managed as app state
+#[derive(Default)]
+pub struct ConverterRegistry {
+    converters: Vec<Box<dyn SaveConverter>>,
+}
+
+impl ConverterRegistry {
+    /// Registry holding the built-in converters between DeSmuME, DraStic,
+    /// RetroArch and plain `.sav` saves, in both directions
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::default();
+        let plain = [(DRASTIC, "dsv"), (RETROARCH, "srm"), (RAW, "sav")];
+        for (format, extension) in plain {
+            registry.register(Box::new(StripDesmumeFooter {
+                target: format,
+                extension,
+            }));
+            registry.register(Box::new(AddDesmumeFooter { source: format }));
+        }
+        for (source, _) in plain {
+            for (target, extension) in plain {
+                if source != target {
+                    registry.register(Box::new(Passthrough {
+                        source,
+                        target,
+                        extension,
+                    }));
+                }
+            }
+        }
+        registry
+    }
+
+    pub fn register(&mut self, converter: Box<dyn SaveConverter>) {
+        self.converters.push(converter);
+    }
+
+    pub fn find(&self, source: &str, target: &str) -> Option<&dyn SaveConverter> {
+        self.converters
+            .iter()
+            .find(|converter| converter.source() == source && converter.target() == target)
+            .map(|converter| converter.as_ref())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ConvertedSave {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// Convert the save at `source_path` from `source` to `target` format and
+/// write it next to the original, named after it with the target's
+/// extension. The original is left alone and an existing file is never
+/// overwritten.
+pub fn convert_file(
+    registry: &ConverterRegistry,
+    source_path: &Path,
+    source: &str,
+    target: &str,
+) -> Result<ConvertedSave, ConvertError> {
+    let converter = registry
+        .find(source, target)
+        .ok_or_else(|| ConvertError::Unsupported(source.to_string(), target.to_string()))?;
+
+    // DeSmuME and DraStic both use `.dsv`, so those get the format too
+    let mut target_path = source_path.with_extension(converter.extension());
+    if target_path == source_path {
+        target_path = source_path.with_extension(format!("{}.{}", target, converter.extension()));
+    }
+    if target_path.exists() {
+        return Err(ConvertError::TargetExists(
+            target_path.to_string_lossy().to_string(),
+        ));
+    }
+
+    let data = fs::read(source_path)?;
+    let converted = converter.convert(&data)?;
+    write_new(&target_path, &converted)?;
+
+    info!(
+        "[CONVERT] Converted {:?} from {} to {} as {:?}",
+        source_path, source, target, target_path
+    );
+    Ok(ConvertedSave {
+        path: target_path.to_string_lossy().to_string(),
+        size_bytes: converted.len() as u64,
+    })
+}
+
+/// Write through a temporary file so a failed write leaves nothing behind
+fn write_new(path: &Path, data: &[u8]) -> Result<(), ConvertError> {
+    let mut partial = PathBuf::from(path);
+    partial.as_mut_os_string().push(".converting");
+    if let Err(err) = fs::write(&partial, data) {
+        let _ = fs::remove_file(&partial);
+        return Err(err.into());
+    }
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// DeSmuME `.dsv` to a format holding only the backup memory. Everything
+/// above the footer is kept, as the footer text itself instructs.
+struct StripDesmumeFooter {
+    target: &'static str,
+    extension: &'static str,
+}
+
+impl SaveConverter for StripDesmumeFooter {
+    fn source(&self) -> &str {
+        DESMUME
+    }
+
+    fn target(&self) -> &str {
+        self.target
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn convert(&self, data: &[u8]) -> Result<Vec<u8>, ConvertError> {
+        let footer_start = data
+            .len()
+            .checked_sub(DESMUME_FOOTER_LEN)
+            .filter(|start| {
+                data.ends_with(DESMUME_COOKIE) && data[*start..].starts_with(DESMUME_FOOTER_TEXT)
+            })
+            .ok_or_else(|| {
+                ConvertError::InvalidSave(DESMUME.to_string(), "footer missing".to_string())
+            })?;
+        Ok(data[..footer_start].to_vec())
+    }
+}
+
+/// Backup memory to DeSmuME `.dsv`, adding the footer DeSmuME needs to load it
+struct AddDesmumeFooter {
+    source: &'static str,
+}
+
+impl SaveConverter for AddDesmumeFooter {
+    fn source(&self) -> &str {
+        self.source
+    }
+
+    fn target(&self) -> &str {
+        DESMUME
+    }
+
+    fn extension(&self) -> &str {
+        "dsv"
+    }
+
+    fn convert(&self, data: &[u8]) -> Result<Vec<u8>, ConvertError> {
+        if data.is_empty() {
+            return Err(ConvertError::InvalidSave(
+                self.source.to_string(),
+                "save is empty".to_string(),
+            ));
+        }
+        if data.ends_with(DESMUME_COOKIE) {
+            return Err(ConvertError::InvalidSave(
+                self.source.to_string(),
+                "already has a DeSmuME footer".to_string(),
+            ));
+        }
+        let size = u32::try_from(data.len()).map_err(|_| {
+            ConvertError::InvalidSave(self.source.to_string(), "save too large".to_string())
+        })?;
+        // Address width of the backup chip that holds this much
+        let addr_size: u32 = match size {
+            0..=512 => 1,
+            513..=0x10000 => 2,
+            _ => 3,
+        };
+
+        let mut out = Vec::with_capacity(data.len() + DESMUME_FOOTER_LEN);
+        out.extend_from_slice(data);
+        out.extend_from_slice(DESMUME_FOOTER_TEXT);
+        // Size, padded size, chip type (0 lets DeSmuME detect it), address
+        // width, memory size, footer version
+        for field in [size, size, 0, addr_size, size, 0] {
+            out.extend_from_slice(&field.to_le_bytes());
+        }
+        out.extend_from_slice(DESMUME_COOKIE);
+        Ok(out)
+    }
+}
+
+/// Between formats that all store the backup memory as is
+struct Passthrough {
+    source: &'static str,
+    target: &'static str,
+    extension: &'static str,
+}
+
+impl SaveConverter for Passthrough {
+    fn source(&self) -> &str {
+        self.source
+    }
+
+    fn target(&self) -> &str {
+        self.target
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn convert(&self, data: &[u8]) -> Result<Vec<u8>, ConvertError> {
+        if data.is_empty() {
+            return Err(ConvertError::InvalidSave(
+                self.source.to_string(),
+                "save is empty".to_string(),
+            ));
+        }
+        Ok(data.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup_memory(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn round_trip(registry: &ConverterRegistry, formats: &[&str], data: &[u8]) -> Vec<u8> {
+        let mut current = data.to_vec();
+        for pair in formats.windows(2) {
+            let converter = registry
+                .find(pair[0], pair[1])
+                .expect("converter registered");
+            current = converter.convert(&current).expect("conversion succeeds");
+        }
+        current
+    }
+
+    #[test]
+    fn desmume_round_trips_through_every_format() {
+        let registry = ConverterRegistry::with_builtin();
+        let raw = backup_memory(8192);
+        let dsv = registry.find(RAW, DESMUME).unwrap().convert(&raw).unwrap();
+        assert_eq!(dsv.len(), raw.len() + DESMUME_FOOTER_LEN);
+        assert!(dsv.ends_with(DESMUME_COOKIE));
+
+        for target in [DRASTIC, RETROARCH, RAW] {
+            assert_eq!(
+                round_trip(&registry, &[DESMUME, target, DESMUME], &dsv),
+                dsv
+            );
+            assert_eq!(round_trip(&registry, &[DESMUME, target], &dsv), raw);
+        }
+        assert_eq!(
+            round_trip(&registry, &[RETROARCH, DRASTIC, RAW, RETROARCH], &raw),
+            raw
+        );
+    }
+
+    #[test]
+    fn malformed_saves_are_refused() {
+        let registry = ConverterRegistry::with_builtin();
+        let strip = registry.find(DESMUME, DRASTIC).unwrap();
+        assert!(matches!(
+            strip.convert(&backup_memory(8192)),
+            Err(ConvertError::InvalidSave(..))
+        ));
+
+        let add = registry.find(DRASTIC, DESMUME).unwrap();
+        let dsv = add.convert(&backup_memory(512)).unwrap();
+        assert!(matches!(
+            add.convert(&dsv),
+            Err(ConvertError::InvalidSave(..))
+        ));
+        assert!(matches!(
+            add.convert(&[]),
+            Err(ConvertError::InvalidSave(..))
+        ));
+        assert!(registry.find(DESMUME, DESMUME).is_none());
+    }
+
+    #[test]
+    fn converted_file_is_written_next_to_the_original() {
+        let dir = std::env::temp_dir().join(format!("crosssave-convert-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("Pokemon Black.srm");
+        fs::write(&source, backup_memory(1024)).unwrap();
+
+        let registry = ConverterRegistry::with_builtin();
+        let converted = convert_file(&registry, &source, RETROARCH, DESMUME).unwrap();
+        assert_eq!(
+            PathBuf::from(&converted.path),
+            dir.join("Pokemon Black.dsv")
+        );
+        assert_eq!(converted.size_bytes, (1024 + DESMUME_FOOTER_LEN) as u64);
+        assert_eq!(fs::read(&source).unwrap(), backup_memory(1024));
+
+        // A second run must not overwrite the first result
+        assert!(matches!(
+            convert_file(&registry, &source, RETROARCH, DESMUME),
+            Err(ConvertError::TargetExists(_))
+        ));
+
+        let dsv = dir.join("Pokemon Black.dsv");
+        let converted = convert_file(&registry, &dsv, DESMUME, DRASTIC).unwrap();
+        assert_eq!(
+            PathBuf::from(&converted.path),
+            dir.join("Pokemon Black.drastic.dsv")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
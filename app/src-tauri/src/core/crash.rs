@@ -0,0 +1,320 @@
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::core::logs::log_files;
+use crate::core::settings::{AppSettings, CloudMode};
+
+/// Log lines kept for the next crash report
+const TAIL_LINES: usize = 200;
+/// Stored reports; the oldest are dropped first
+const MAX_REPORTS: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum CrashError {
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("serialization error: {0}")]
+    Serialization(String),
+    #[error("crash report not found: {0}")]
+    NotFound(String),
+    #[error("crash reports can only be sent to a self-hosted server")]
+    NoEndpoint,
+    #[error("network error: {0}")]
+    Network(String),
+}
+
+impl From<io::Error> for CrashError {
+    fn from(err: io::Error) -> Self {
+        CrashError::Io(err.to_string())
+    }
+}
+
+/// The last lines the tracing subscriber wrote, so a panic report shows
+/// what the sync engine and watcher were doing just before
+#[derive(Default)]
+pub struct LogTail {
+    lines: Mutex<VecDeque<String>>,
+}
+
+static LOG_TAIL: OnceLock<LogTail> = OnceLock::new();
+
+/// Shared tail fed by `LogTail::writer`, set up in `main` before tracing starts
+pub fn log_tail() -> &'static LogTail {
+    LOG_TAIL.get_or_init(LogTail::default)
+}
+
+impl LogTail {
+    /// Writer for `tracing_subscriber::fmt().with_writer`; it copies every
+    /// event to stdout and the log files, and keeps it in the tail
+    pub fn writer(&'static self) -> LogTailWriter {
+        LogTailWriter {
+            tail: self,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Never blocks, since it also runs inside the panic hook
+    pub fn snapshot(&self) -> Vec<String> {
+        match self.lines.try_lock() {
+            Ok(lines) => lines.iter().cloned().collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn push(&self, text: &str) {
+        let Ok(mut lines) = self.lines.lock() else {
+            return;
+        };
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if lines.len() == TAIL_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(strip_ansi(line));
+        }
+    }
+}
+
+pub struct LogTailWriter {
+    tail: &'static LogTail,
+    buffer: Vec<u8>,
+}
+
+impl Write for LogTailWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write_all(buf)?;
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+impl Drop for LogTailWriter {
+    /// The subscriber makes one writer per event, so the event is complete here
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.buffer);
+        self.tail.push(&text);
+        log_files().append(&strip_ansi(&text));
+    }
+}
+
+/// Drop terminal colour codes from a log line
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the end of the `ESC [ ... m` sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub app_version: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub log_tail: Vec<String>,
+    #[serde(default)]
+    pub submitted: bool,
+}
+
+/// Writes a report for every panic while enabled. Only Rust panics are
+/// caught; a native crash of the process leaves no report.
+pub struct CrashReporter {
+    dir: PathBuf,
+    app_version: String,
+    enabled: AtomicBool,
+}
+
+impl CrashReporter {
+    /// Create the reporter and chain it in front of the current panic hook
+    pub fn install(dir: PathBuf, app_version: String, enabled: bool) -> Arc<Self> {
+        let reporter = Arc::new(Self {
+            dir,
+            app_version,
+            enabled: AtomicBool::new(enabled),
+        });
+
+        let hook_reporter = reporter.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if hook_reporter.enabled.load(Ordering::SeqCst) {
+                let payload = info.payload();
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "panic with a non-string payload".to_string());
+                let location = info
+                    .location()
+                    .map(|location| format!("{}:{}", location.file(), location.line()));
+                hook_reporter.write_report(message, location);
+            }
+            previous(info);
+        }));
+
+        reporter
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Stored reports, newest first
+    pub fn reports(&self) -> Result<Vec<CrashReport>, CrashError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut reports: Vec<CrashReport> = entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| {
+                let content = fs::read_to_string(entry.path()).ok()?;
+                serde_json::from_str(&content).ok()
+            })
+            .collect();
+        reports.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(reports)
+    }
+
+    pub fn remove(&self, report_id: &str) -> Result<(), CrashError> {
+        fs::remove_file(self.report_path(report_id)?).map_err(CrashError::from)
+    }
+
+    /// Send one report to the self-hosted server's `/crash-report`
+    pub async fn submit(&self, settings: &AppSettings, report_id: &str) -> Result<(), CrashError> {
+        if settings.cloud_mode != CloudMode::SelfHost {
+            return Err(CrashError::NoEndpoint);
+        }
+        let base_url = settings.self_host.api_server.trim().trim_end_matches('/');
+        if base_url.is_empty() {
+            return Err(CrashError::NoEndpoint);
+        }
+
+        let path = self.report_path(report_id)?;
+        let content = fs::read_to_string(&path)?;
+        let mut report: CrashReport = serde_json::from_str(&content)
+            .map_err(|err| CrashError::Serialization(err.to_string()))?;
+
+        let timeout = Duration::from_secs(settings.cloud.timeout_seconds.max(1));
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|err| CrashError::Network(err.to_string()))?;
+        let response = client
+            .post(format!("{base_url}/crash-report"))
+            .bearer_auth(settings.self_host.access_key.trim())
+            .json(&report)
+            .send()
+            .await
+            .map_err(|err| CrashError::Network(err.to_string()))?;
+        if !response.status().is_success() {
+            return Err(CrashError::Network(format!(
+                "server returned {}",
+                response.status()
+            )));
+        }
+
+        report.submitted = true;
+        self.write(&report)?;
+        info!("[CRASH] Submitted crash report {report_id}");
+        Ok(())
+    }
+
+    /// Send every report not sent yet, when the user opted in to that
+    pub async fn submit_pending(&self, settings: &AppSettings) {
+        if !settings.crash_reports.auto_submit || settings.cloud_mode != CloudMode::SelfHost {
+            return;
+        }
+        let pending = match self.reports() {
+            Ok(reports) => reports.into_iter().filter(|report| !report.submitted),
+            Err(err) => {
+                warn!("[CRASH] Failed to read crash reports: {err}");
+                return;
+            }
+        };
+        for report in pending {
+            if let Err(err) = self.submit(settings, &report.id).await {
+                warn!("[CRASH] Failed to submit crash report {}: {err}", report.id);
+                return;
+            }
+        }
+    }
+
+    fn write_report(&self, message: String, location: Option<String>) {
+        let report = CrashReport {
+            id: Uuid::new_v4().to_string(),
+            created_at: Utc::now(),
+            app_version: self.app_version.clone(),
+            message,
+            location,
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+            log_tail: log_tail().snapshot(),
+            submitted: false,
+        };
+        match self.write(&report) {
+            Ok(()) => self.prune(),
+            Err(err) => eprintln!("[CRASH] Failed to store crash report: {err}"),
+        }
+    }
+
+    fn write(&self, report: &CrashReport) -> Result<(), CrashError> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string_pretty(report)
+            .map_err(|err| CrashError::Serialization(err.to_string()))?;
+        fs::write(self.dir.join(format!("{}.json", report.id)), json)?;
+        Ok(())
+    }
+
+    fn prune(&self) {
+        let Ok(reports) = self.reports() else {
+            return;
+        };
+        for report in reports.iter().skip(MAX_REPORTS) {
+            let _ = self.remove(&report.id);
+        }
+    }
+
+    /// Report IDs are UUIDs; anything else could point outside the directory
+    fn report_path(&self, report_id: &str) -> Result<PathBuf, CrashError> {
+        Uuid::parse_str(report_id).map_err(|_| CrashError::NotFound(report_id.to_string()))?;
+        let path = self.dir.join(format!("{report_id}.json"));
+        if !path.is_file() {
+            return Err(CrashError::NotFound(report_id.to_string()));
+        }
+        Ok(path)
+    }
+}
@@ -0,0 +1,51 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::ManagerExt;
+use tracing::{info, warn};
+
+use crate::core::settings::DesktopSettings;
+
+/// Passed by the login item, so a launch at login can start in the tray
+pub const AUTOSTART_ARG: &str = "--autostarted";
+
+/// Register or remove the login item to match `settings`
+pub fn apply_autostart(app: &AppHandle, settings: &DesktopSettings) {
+    let autolaunch = app.autolaunch();
+    match autolaunch.is_enabled() {
+        Ok(enabled) if enabled == settings.launch_at_login => return,
+        Ok(_) => {}
+        Err(err) => warn!("[STARTUP] Failed to read launch at login state: {err}"),
+    }
+
+    let result = if settings.launch_at_login {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    match result {
+        Ok(()) => info!(
+            "[STARTUP] Launch at login {}",
+            if settings.launch_at_login {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        ),
+        Err(err) => warn!("[STARTUP] Failed to update launch at login: {err}"),
+    }
+}
+
+/// Whether this process was started by the login item
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+/// Bring the main window back from the tray
+pub fn show_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if let Err(err) = window.unminimize().and_then(|_| window.show()) {
+        warn!("[STARTUP] Failed to show main window: {err}");
+    }
+    let _ = window.set_focus();
+}
@@ -0,0 +1,231 @@
+//! Device ids derived from the machine instead of drawn at random, so a
+//! wiped settings.json doesn't show up on the server as a new device. The
+//! OS's machine id (`/etc/machine-id` on Linux, the IOPlatformUUID on
+//! macOS, `MachineGuid` on Windows, `ANDROID_ID` through
+//! `DeviceIdPlugin.kt`) is hashed with a salt made once per install and
+//! kept next to settings.json, so two installs on one machine get ids of
+//! their own and the machine id itself never leaves it.
+
+use std::{fs, io, path::Path, sync::OnceLock};
+
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// File next to settings.json holding this install's salt
+const SALT_FILE: &str = "install_salt";
+/// Hashed in front of the machine id for the fingerprint
+const FINGERPRINT_DOMAIN: &str = "crosssave-device";
+
+pub struct DeviceIdentity {
+    /// The same for this install on this machine for as long as the salt
+    /// file is kept
+    pub device_id: String,
+    /// The same for every install on this machine. Registering with it
+    /// lets the server replace the entry of an install that lost its salt.
+    pub fingerprint: String,
+    /// The salt was made by this run, so the install is new or lost its
+    /// settings. Only then may the server hand it another entry with the
+    /// same fingerprint; otherwise two installs on one machine would keep
+    /// replacing each other.
+    pub new_install: bool,
+}
+
+static IDENTITY: OnceLock<DeviceIdentity> = OnceLock::new();
+
+/// Derive this install's identity from `machine_id`, or from the OS's
+/// when `None`. Called once at startup; without a machine id new device
+/// ids stay random.
+pub fn install(config_dir: &Path, machine_id: Option<String>) {
+    let Some(machine_id) = machine_id.or_else(os_machine_id) else {
+        warn!("[CLOUD] No machine id on this device; new device ids are random");
+        return;
+    };
+    let (salt, new_install) = match install_salt(config_dir) {
+        Ok(salt) => salt,
+        Err(err) => {
+            warn!("[CLOUD] Failed to read or create the install salt: {err}");
+            return;
+        }
+    };
+    let identity = DeviceIdentity {
+        new_install,
+        ..derive(&machine_id, &salt)
+    };
+    info!("[CLOUD] Stable device id {}", identity.device_id);
+    if IDENTITY.set(identity).is_err() {
+        warn!("[CLOUD] Device identity already installed");
+    }
+}
+
+/// This install's identity, once `install` found a machine id
+pub fn identity() -> Option<&'static DeviceIdentity> {
+    IDENTITY.get()
+}
+
+/// Fingerprint to register with, when there is one
+pub fn fingerprint() -> Option<&'static str> {
+    identity().map(|identity| identity.fingerprint.as_str())
+}
+
+/// Whether this run made the install salt
+pub fn new_install() -> bool {
+    identity().is_some_and(|identity| identity.new_install)
+}
+
+fn derive(machine_id: &str, salt: &str) -> DeviceIdentity {
+    let digest = Sha256::digest(format!("{salt}:{machine_id}").as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    DeviceIdentity {
+        device_id: Uuid::from_bytes(bytes).to_string(),
+        fingerprint: format!(
+            "{:x}",
+            Sha256::digest(format!("{FINGERPRINT_DOMAIN}:{machine_id}").as_bytes())
+        ),
+        new_install: false,
+    }
+}
+
+/// The salt in `config_dir`, made the first time it is asked for, and
+/// whether it was made by this call
+fn install_salt(config_dir: &Path) -> io::Result<(String, bool)> {
+    let path = config_dir.join(SALT_FILE);
+    match fs::read_to_string(&path) {
+        Ok(salt) if !salt.trim().is_empty() => return Ok((salt.trim().to_string(), false)),
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    fs::create_dir_all(config_dir)?;
+    let salt = Uuid::new_v4().simple().to_string();
+    fs::write(&path, &salt)?;
+    Ok((salt, true))
+}
+
+#[cfg(all(unix, not(target_os = "macos"), not(target_os = "android")))]
+fn os_machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+}
+
+#[cfg(target_os = "macos")]
+fn os_machine_id() -> Option<String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_string)
+}
+
+#[cfg(windows)]
+fn os_machine_id() -> Option<String> {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Cryptography",
+            "/v",
+            "MachineGuid",
+            "/reg:64",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(str::to_string)
+}
+
+/// Android's id comes from `android::DeviceIdPlugin`
+#[cfg(target_os = "android")]
+fn os_machine_id() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "android")]
+pub mod android {
+    use serde::Deserialize;
+    use tauri::{
+        plugin::{Builder, PluginHandle, TauriPlugin},
+        Manager, Wry,
+    };
+    use tracing::warn;
+
+    const PLUGIN_PACKAGE: &str = "com.h1dr0n.crosssave_cloud";
+    const PLUGIN_CLASS: &str = "DeviceIdPlugin";
+
+    #[derive(Deserialize)]
+    struct AndroidIdResponse {
+        id: Option<String>,
+    }
+
+    /// `Settings.Secure.ANDROID_ID`, which is kept across reinstalls
+    pub struct DeviceIdPlugin(PluginHandle<Wry>);
+
+    impl DeviceIdPlugin {
+        pub fn android_id(&self) -> Option<String> {
+            self.0
+                .run_mobile_plugin::<AndroidIdResponse>("androidId", serde_json::json!({}))
+                .map_err(|err| warn!("[CLOUD] Failed to read ANDROID_ID: {err}"))
+                .ok()
+                .and_then(|response| response.id)
+                .filter(|id| !id.trim().is_empty())
+        }
+    }
+
+    pub fn init() -> TauriPlugin<Wry> {
+        Builder::new("device-id")
+            .setup(|app, api| {
+                let handle = api.register_android_plugin(PLUGIN_PACKAGE, PLUGIN_CLASS)?;
+                app.manage(DeviceIdPlugin(handle));
+                Ok(())
+            })
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_follow_the_salt_and_fingerprints_the_machine() {
+        let dir = std::env::temp_dir().join(format!("crosssave-deviceid-{}", Uuid::new_v4()));
+        let (salt, made) = install_salt(&dir).unwrap();
+        assert!(made);
+        assert_eq!(
+            install_salt(&dir).unwrap(),
+            (salt.clone(), false),
+            "salt is kept"
+        );
+
+        let first = derive("machine", &salt);
+        let again = derive("machine", &salt);
+        assert_eq!(first.device_id, again.device_id);
+        assert!(Uuid::parse_str(&first.device_id).is_ok());
+
+        let other_install = derive("machine", "another salt");
+        assert_ne!(other_install.device_id, first.device_id);
+        assert_eq!(other_install.fingerprint, first.fingerprint);
+        assert_eq!(first.fingerprint.len(), 64);
+        assert_ne!(
+            derive("other machine", &salt).fingerprint,
+            first.fingerprint
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+use super::profile::SaveEncryption;
+
+/// Bytes read from the start of each file for the entropy check
+const SAMPLE_BYTES: u64 = 16 * 1024;
+/// Shorter files do not give a meaningful entropy estimate
+const MIN_SAMPLE_BYTES: usize = 512;
+/// Bits per byte above which a sample is treated as encrypted
+const ENTROPY_THRESHOLD: f64 = 7.6;
+/// Compressed formats are also high entropy but are not encrypted
+const COMPRESSED_SIGNATURES: &[&[u8]] = &[
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"\x89PNG",
+    b"7z\xbc\xaf\x27\x1c",
+    b"\x28\xb5\x2f\xfd",
+    b"\xfd7zXZ\x00",
+    b"BZh",
+];
+/// Files kept next to saves that are never saves themselves: screenshots,
+/// configs, logs, cheats and disc images. Auto detection leaves them out,
+/// since many of them are high entropy without being encrypted.
+const NON_SAVE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "bmp", "gif", "webp", "txt", "ini", "cfg", "json", "xml", "yml", "yaml",
+    "log", "cht", "chd", "cso", "iso", "rvz", "wbfs", "zip", "7z", "gz", "xz", "zst", "bz2",
+];
+
+/// Whether the saves in `files` should be handled as opaque encrypted blobs
+pub fn saves_are_encrypted(mode: SaveEncryption, files: &[PathBuf]) -> bool {
+    match mode {
+        SaveEncryption::Encrypted => true,
+        SaveEncryption::None => false,
+        SaveEncryption::Auto => files.iter().any(|file| file_is_encrypted(mode, file)),
+    }
+}
+
+/// Whether `path` on its own is an encrypted save under `mode`
+pub fn file_is_encrypted(mode: SaveEncryption, path: &Path) -> bool {
+    match mode {
+        SaveEncryption::Encrypted => true,
+        SaveEncryption::None => false,
+        SaveEncryption::Auto => is_save_file(path) && looks_encrypted(path),
+    }
+}
+
+/// Whether `path` may be a save rather than a file kept beside saves
+pub fn is_save_file(path: &Path) -> bool {
+    let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+        return true;
+    };
+    let extension = extension.to_ascii_lowercase();
+    !NON_SAVE_EXTENSIONS.contains(&extension.as_str())
+}
+
+/// Heuristic: a file that is not a known compressed format and whose first
+/// bytes are close to uniformly random
+fn looks_encrypted(path: &Path) -> bool {
+    match read_sample(path) {
+        Ok(sample) => {
+            if sample.len() < MIN_SAMPLE_BYTES
+                || COMPRESSED_SIGNATURES
+                    .iter()
+                    .any(|signature| sample.starts_with(signature))
+            {
+                return false;
+            }
+
+            let entropy = shannon_entropy(&sample);
+            if entropy >= ENTROPY_THRESHOLD {
+                debug!(
+                    "[PACKAGER] {:?} looks encrypted ({entropy:.2} bits/byte)",
+                    path
+                );
+                return true;
+            }
+            false
+        }
+        Err(err) => {
+            debug!("[PACKAGER] Failed to sample {:?}: {err}", path);
+            false
+        }
+    }
+}
+
+/// Bits per byte of the start of `path`, or `None` when it cannot be read
+pub fn sample_entropy(path: &Path) -> Option<f64> {
+    let sample = read_sample(path).ok()?;
+    if sample.is_empty() {
+        return None;
+    }
+    Some(shannon_entropy(&sample))
+}
+
+fn read_sample(path: &Path) -> io::Result<Vec<u8>> {
+    let mut sample = Vec::new();
+    fs::File::open(path)?
+        .take(SAMPLE_BYTES)
+        .read_to_end(&mut sample)?;
+    Ok(sample)
+}
+
+fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for byte in data {
+        counts[*byte as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use uuid::Uuid;
+
+    /// Bytes that look like ciphertext: chained SHA-256 digests
+    fn noise(len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len);
+        let mut block = Sha256::digest(b"crosssave").to_vec();
+        while bytes.len() < len {
+            bytes.extend_from_slice(&block);
+            block = Sha256::digest(&block).to_vec();
+        }
+        bytes.truncate(len);
+        bytes
+    }
+
+    #[test]
+    fn auto_detection_only_samples_saves() {
+        let dir = std::env::temp_dir().join(format!("crosssave-encryption-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let save = dir.join("user.sav");
+        let screenshot = dir.join("screenshot.jpg");
+        let plain = dir.join("plain.srm");
+        fs::write(&save, noise(8192)).unwrap();
+        fs::write(&screenshot, noise(8192)).unwrap();
+        fs::write(&plain, vec![0u8; 8192]).unwrap();
+
+        assert!(file_is_encrypted(SaveEncryption::Auto, &save));
+        assert!(!file_is_encrypted(SaveEncryption::Auto, &screenshot));
+        assert!(!file_is_encrypted(SaveEncryption::Auto, &plain));
+        assert!(!saves_are_encrypted(
+            SaveEncryption::Auto,
+            &[screenshot.clone(), plain.clone()]
+        ));
+        assert!(saves_are_encrypted(
+            SaveEncryption::Auto,
+            &[screenshot, plain.clone(), save]
+        ));
+        assert!(saves_are_encrypted(SaveEncryption::Encrypted, &[plain]));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
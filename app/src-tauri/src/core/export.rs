@@ -0,0 +1,170 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use super::extract::{extract_archive, ExtractError};
+use super::paths::is_blank;
+use super::storage::is_storage_full;
+
+/// Provenance is written next to the export folder as `<folder>.provenance.json`
+const PROVENANCE_SUFFIX: &str = ".provenance.json";
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("invalid export target: {0}")]
+    InvalidTarget(String),
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("archive error: {0}")]
+    Archive(String),
+    #[error("not enough disk space: {0}")]
+    StorageFull(String),
+}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        if is_storage_full(&err) {
+            ExportError::StorageFull(err.to_string())
+        } else {
+            ExportError::Io(err.to_string())
+        }
+    }
+}
+
+impl From<ExtractError> for ExportError {
+    fn from(err: ExtractError) -> Self {
+        match err {
+            ExtractError::StorageFull(message) => ExportError::StorageFull(message),
+            other => ExportError::Archive(other.to_string()),
+        }
+    }
+}
+
+/// Where an exported version came from, saved alongside the extracted files
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Provenance {
+    pub game_id: String,
+    pub version_id: String,
+    pub emulator_id: String,
+    /// `local` or `cloud`
+    pub source: String,
+    pub timestamp: u64,
+    pub sha256: Option<String>,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+    pub exported_at: u64,
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportResult {
+    pub folder: String,
+    pub provenance_path: String,
+    pub file_count: usize,
+}
+
+/// Extract `archive_path` into a new `<game_id>_<version_id>` folder under
+/// `target_dir`, mark the files read-only and write provenance next to it.
+/// Targets inside (or containing) one of `live_paths` are refused so an
+/// export can never touch the saves the emulator is using.
+pub fn export_version(
+    archive_path: &Path,
+    target_dir: &Path,
+    live_paths: &[PathBuf],
+    mut provenance: Provenance,
+) -> Result<ExportResult, ExportError> {
+    if !target_dir.is_dir() {
+        return Err(ExportError::InvalidTarget(format!(
+            "{} is not a directory",
+            target_dir.display()
+        )));
+    }
+
+    let target_dir = fs::canonicalize(target_dir)?;
+    let folder_name = format!(
+        "{}_{}",
+        folder_safe(&provenance.game_id),
+        folder_safe(&provenance.version_id)
+    );
+    let folder = target_dir.join(&folder_name);
+
+    for live in live_paths.iter().filter(|path| !is_blank(path)) {
+        let Ok(live) = fs::canonicalize(live) else {
+            continue;
+        };
+        if folder.starts_with(&live) || live.starts_with(&folder) {
+            return Err(ExportError::InvalidTarget(format!(
+                "{} overlaps the live save folder {}",
+                folder.display(),
+                live.display()
+            )));
+        }
+    }
+
+    if folder.exists() {
+        return Err(ExportError::InvalidTarget(format!(
+            "{} already exists",
+            folder.display()
+        )));
+    }
+
+    let files = match extract_archive(archive_path, &folder) {
+        Ok(files) => files,
+        Err(err) => {
+            // Do not leave a half-written export behind
+            let _ = fs::remove_dir_all(&folder);
+            return Err(err.into());
+        }
+    };
+
+    for file in &files {
+        if let Err(err) = set_read_only(&folder.join(file)) {
+            debug!("[HISTORY] Could not mark {file} read-only: {err}");
+        }
+    }
+
+    let file_count = files.len();
+    provenance.files = files;
+    provenance.exported_at = Utc::now().timestamp().max(0) as u64;
+
+    let provenance_path = target_dir.join(format!("{folder_name}{PROVENANCE_SUFFIX}"));
+    let json = serde_json::to_string_pretty(&provenance)
+        .map_err(|err| ExportError::Io(err.to_string()))?;
+    fs::write(&provenance_path, json)?;
+
+    info!(
+        "[HISTORY] Exported {} version {} to {:?} ({} files)",
+        provenance.game_id, provenance.version_id, folder, file_count
+    );
+
+    Ok(ExportResult {
+        folder: folder.to_string_lossy().to_string(),
+        provenance_path: provenance_path.to_string_lossy().to_string(),
+        file_count,
+    })
+}
+
+fn set_read_only(path: &Path) -> io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(path, permissions)
+}
+
+fn folder_safe(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,533 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read, Seek},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::debug;
+use zip::ZipArchive;
+
+use crate::core::storage::is_storage_full;
+
+/// File type bits of a unix mode, and the value marking a symlink
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    #[error("unsafe archive entry: {0}")]
+    Unsafe(String),
+    #[error("archive too large: {0}")]
+    TooLarge(String),
+    #[error("archive error: {0}")]
+    Archive(String),
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("not enough disk space: {0}")]
+    StorageFull(String),
+}
+
+impl From<io::Error> for ExtractError {
+    fn from(err: io::Error) -> Self {
+        if is_storage_full(&err) {
+            ExtractError::StorageFull(err.to_string())
+        } else {
+            ExtractError::Io(err.to_string())
+        }
+    }
+}
+
+/// Caps that keep a hostile or corrupt archive from filling the disk
+#[derive(Clone, Copy, Debug)]
+pub struct ExtractLimits {
+    pub max_entries: usize,
+    /// Total uncompressed bytes, checked against both the headers and the
+    /// bytes actually written
+    pub max_total_bytes: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            // Same ceilings the cloud server applies to uploads
+            max_entries: 10_000,
+            max_total_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// One file in a save archive, as its headers describe it
+#[derive(Clone, Debug, Serialize)]
+pub struct ArchiveEntry {
+    /// Path inside the archive, with `/` separators
+    pub path: String,
+    pub size_bytes: u64,
+    pub compressed_bytes: u64,
+    /// Zip timestamps carry no time zone; this is the writer's local time
+    pub modified: Option<NaiveDateTime>,
+    pub crc32: u32,
+}
+
+/// List the files in a save archive without extracting anything. Entries
+/// that extraction would reject are left out.
+pub fn list_archive(archive_path: &Path) -> Result<Vec<ArchiveEntry>, ExtractError> {
+    panic::catch_unwind(AssertUnwindSafe(|| list_entries(archive_path)))
+        .unwrap_or_else(|_| Err(ExtractError::Archive("malformed archive".to_string())))
+}
+
+fn list_entries(archive_path: &Path) -> Result<Vec<ArchiveEntry>, ExtractError> {
+    let file = fs::File::open(archive_path)?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|err| ExtractError::Archive(err.to_string()))?;
+
+    let max_entries = ExtractLimits::default().max_entries;
+    if archive.len() > max_entries {
+        return Err(ExtractError::TooLarge(format!(
+            "{} entries, at most {max_entries} allowed",
+            archive.len()
+        )));
+    }
+
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|err| ExtractError::Archive(err.to_string()))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Ok(Some(relative)) = safe_relative_path(entry.name()) else {
+            debug!(
+                "[HISTORY] Leaving unsafe entry {} out of listing",
+                entry.name()
+            );
+            continue;
+        };
+
+        let modified = entry.last_modified();
+        entries.push(ArchiveEntry {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            size_bytes: entry.size(),
+            compressed_bytes: entry.compressed_size(),
+            modified: NaiveDate::from_ymd_opt(
+                i32::from(modified.year()),
+                u32::from(modified.month()),
+                u32::from(modified.day()),
+            )
+            .and_then(|date| {
+                date.and_hms_opt(
+                    u32::from(modified.hour()),
+                    u32::from(modified.minute()),
+                    u32::from(modified.second()),
+                )
+            }),
+            crc32: entry.crc32(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Read the file at `path` out of an archive, refusing more than
+/// `max_bytes` of uncompressed data
+pub fn read_entry<R: Read + Seek>(
+    reader: R,
+    path: &str,
+    max_bytes: u64,
+) -> Result<Vec<u8>, ExtractError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut archive =
+            ZipArchive::new(reader).map_err(|err| ExtractError::Archive(err.to_string()))?;
+        let mut entry = archive
+            .by_name(path)
+            .map_err(|err| ExtractError::Archive(format!("{path}: {err}")))?;
+        let is_symlink = entry
+            .unix_mode()
+            .is_some_and(|mode| mode & S_IFMT == S_IFLNK);
+        if entry.is_dir() || is_symlink || safe_relative_path(entry.name())?.is_none() {
+            return Err(ExtractError::Unsafe(path.to_string()));
+        }
+        if entry.size() > max_bytes {
+            return Err(ExtractError::TooLarge(format!(
+                "{path} is {} bytes, at most {max_bytes} allowed",
+                entry.size()
+            )));
+        }
+
+        let mut data = Vec::new();
+        (&mut entry).take(max_bytes + 1).read_to_end(&mut data)?;
+        if data.len() as u64 > max_bytes {
+            return Err(ExtractError::TooLarge(format!(
+                "{path} inflates past {max_bytes} bytes"
+            )));
+        }
+        Ok(data)
+    }))
+    .unwrap_or_else(|_| Err(ExtractError::Archive("malformed archive".to_string())))
+}
+
+/// Unpack a save archive into `dest` with the default limits. Returns the
+/// relative paths of the extracted files.
+pub fn extract_archive(archive_path: &Path, dest: &Path) -> Result<Vec<String>, ExtractError> {
+    extract_with_limits(archive_path, dest, ExtractLimits::default())
+}
+
+/// Every entry is validated before anything is written, so a rejected
+/// archive leaves `dest` untouched.
+pub fn extract_with_limits(
+    archive_path: &Path,
+    dest: &Path,
+    limits: ExtractLimits,
+) -> Result<Vec<String>, ExtractError> {
+    // The zip reader can panic on some malformed headers; a corrupt download
+    // is an archive error, not a reason to take down the sync worker
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        extract_entries(archive_path, dest, limits)
+    }))
+    .unwrap_or_else(|_| Err(ExtractError::Archive("malformed archive".to_string())))
+}
+
+fn extract_entries(
+    archive_path: &Path,
+    dest: &Path,
+    limits: ExtractLimits,
+) -> Result<Vec<String>, ExtractError> {
+    let file = fs::File::open(archive_path)?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|err| ExtractError::Archive(err.to_string()))?;
+
+    if archive.len() > limits.max_entries {
+        return Err(ExtractError::TooLarge(format!(
+            "{} entries, at most {} allowed",
+            archive.len(),
+            limits.max_entries
+        )));
+    }
+
+    let entries = validate_entries(&mut archive, limits)?;
+
+    fs::create_dir_all(dest)?;
+    let mut written: u64 = 0;
+    let mut files = Vec::new();
+    for (index, relative) in entries {
+        let Some(relative) = relative else {
+            continue;
+        };
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|err| ExtractError::Archive(err.to_string()))?;
+
+        let out_path = dest.join(&relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Headers can lie about sizes; cap what is actually inflated
+        let remaining = limits.max_total_bytes.saturating_sub(written);
+        let mut out = fs::File::create(&out_path)?;
+        let copied = io::copy(
+            &mut (&mut entry).take(remaining.saturating_add(1)),
+            &mut out,
+        )?;
+        if copied > remaining {
+            drop(out);
+            let _ = fs::remove_file(&out_path);
+            return Err(ExtractError::TooLarge(format!(
+                "more than {} bytes uncompressed",
+                limits.max_total_bytes
+            )));
+        }
+        written += copied;
+
+        let name = relative.to_string_lossy().replace('\\', "/");
+        debug!("[SYNC] Extracted {name}");
+        files.push(name);
+    }
+
+    Ok(files)
+}
+
+/// Resolve every entry to a safe relative path, in archive order.
+/// Directories map to `Some` as well; only `.`-only names map to `None`.
+fn validate_entries(
+    archive: &mut ZipArchive<fs::File>,
+    limits: ExtractLimits,
+) -> Result<Vec<(usize, Option<PathBuf>)>, ExtractError> {
+    let mut declared: u64 = 0;
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut entries = Vec::with_capacity(archive.len());
+
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|err| ExtractError::Archive(err.to_string()))?;
+        let name = entry.name().to_string();
+
+        if entry
+            .unix_mode()
+            .is_some_and(|mode| mode & S_IFMT == S_IFLNK)
+        {
+            return Err(ExtractError::Unsafe(format!("{name} is a symlink")));
+        }
+
+        let relative = safe_relative_path(&name)?;
+        if let Some(path) = &relative {
+            // Case-insensitive filesystems would silently merge these
+            let folded = path.to_string_lossy().to_lowercase();
+            if !entry.is_dir() && !seen.insert(folded) {
+                return Err(ExtractError::Unsafe(format!(
+                    "{name} collides with another entry"
+                )));
+            }
+        }
+
+        declared = declared.saturating_add(entry.size());
+        if declared > limits.max_total_bytes {
+            return Err(ExtractError::TooLarge(format!(
+                "more than {} bytes uncompressed",
+                limits.max_total_bytes
+            )));
+        }
+
+        entries.push((index, relative));
+    }
+
+    Ok(entries)
+}
+
+/// Turn an entry name into a path below the extraction root. Absolute
+/// paths, drive letters, `..` and NUL bytes are rejected on every platform,
+/// whichever separator the archive used.
+fn safe_relative_path(name: &str) -> Result<Option<PathBuf>, ExtractError> {
+    let unsafe_entry = || ExtractError::Unsafe(name.to_string());
+
+    if name.contains('\0') {
+        return Err(unsafe_entry());
+    }
+
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') {
+        return Err(unsafe_entry());
+    }
+
+    let mut path = PathBuf::new();
+    for segment in normalized.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return Err(unsafe_entry()),
+            // `C:` drive prefixes, and alternate data streams on NTFS
+            _ if segment.contains(':') => return Err(unsafe_entry()),
+            _ => path.push(segment),
+        }
+    }
+
+    Ok((!path.as_os_str().is_empty()).then_some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path =
+                std::env::temp_dir().join(format!("crosssave-extract-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    enum Entry<'a> {
+        File(&'a str, &'a [u8]),
+        Symlink(&'a str, &'a str),
+    }
+
+    fn write_archive(dir: &Path, entries: &[Entry]) -> PathBuf {
+        let path = dir.join("archive.zip");
+        let mut writer = ZipWriter::new(fs::File::create(&path).expect("create archive"));
+        let options = FileOptions::default();
+        for entry in entries {
+            match entry {
+                Entry::File(name, data) => {
+                    writer.start_file(*name, options).expect("start file");
+                    writer.write_all(data).expect("write file");
+                }
+                Entry::Symlink(name, target) => {
+                    writer
+                        .add_symlink(*name, *target, options)
+                        .expect("add symlink");
+                }
+            }
+        }
+        writer.finish().expect("finish archive");
+        path
+    }
+
+    fn extract(entries: &[Entry]) -> (TempDir, Result<Vec<String>, ExtractError>) {
+        let dir = TempDir::new();
+        let archive = write_archive(&dir.0, entries);
+        let result = extract_archive(&archive, &dir.0.join("out"));
+        (dir, result)
+    }
+
+    #[test]
+    fn extracts_nested_files() {
+        let (dir, result) = extract(&[
+            Entry::File("GC/USA/Card A/save.gci", b"card"),
+            Entry::File("./top.srm", b"srm"),
+        ]);
+
+        let files = result.expect("extract");
+        assert_eq!(files, vec!["GC/USA/Card A/save.gci", "top.srm"]);
+        let restored = fs::read(dir.0.join("out/GC/USA/Card A/save.gci")).expect("read");
+        assert_eq!(restored, b"card");
+    }
+
+    #[test]
+    fn lists_files_without_extracting() {
+        let dir = TempDir::new();
+        let archive = write_archive(
+            &dir.0,
+            &[
+                Entry::File("GC/save.gci", b"card"),
+                Entry::File("../evil.srm", b"evil"),
+            ],
+        );
+
+        let entries = list_archive(&archive).expect("list");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, "GC/save.gci");
+        assert_eq!(entries[0].size_bytes, 4);
+        assert_eq!(entries[0].crc32, 0x1614_98d3);
+        assert!(entries[0].modified.is_some());
+        assert!(!dir.0.join("GC").exists());
+    }
+
+    #[test]
+    fn reads_one_entry() {
+        let dir = TempDir::new();
+        let archive = write_archive(
+            &dir.0,
+            &[
+                Entry::File("GC/save.gci", b"card"),
+                Entry::File("GC/other.gci", b"other"),
+            ],
+        );
+
+        let file = || fs::File::open(&archive).expect("open archive");
+        assert_eq!(
+            read_entry(file(), "GC/save.gci", 1024).expect("read"),
+            b"card"
+        );
+        assert!(matches!(
+            read_entry(file(), "GC/other.gci", 2),
+            Err(ExtractError::TooLarge(_))
+        ));
+        assert!(matches!(
+            read_entry(file(), "GC/missing.gci", 1024),
+            Err(ExtractError::Archive(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_parent_traversal() {
+        let (dir, result) = extract(&[
+            Entry::File("ok.srm", b"ok"),
+            Entry::File("saves/../../evil.srm", b"evil"),
+        ]);
+
+        assert!(matches!(result, Err(ExtractError::Unsafe(_))));
+        assert!(!dir.0.join("evil.srm").exists());
+        // Validation runs first, so even the safe entry is not written
+        assert!(!dir.0.join("out/ok.srm").exists());
+    }
+
+    #[test]
+    fn rejects_backslash_traversal() {
+        let (_dir, result) = extract(&[Entry::File("..\\evil.srm", b"evil")]);
+        assert!(matches!(result, Err(ExtractError::Unsafe(_))));
+    }
+
+    #[test]
+    fn rejects_absolute_and_drive_paths() {
+        for name in [
+            "/etc/evil",
+            "\\evil.srm",
+            "C:/evil.srm",
+            "C:evil.srm",
+            "save.srm:stream",
+        ] {
+            let (_dir, result) = extract(&[Entry::File(name, b"evil")]);
+            assert!(
+                matches!(result, Err(ExtractError::Unsafe(_))),
+                "{name} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_symlinks() {
+        let (dir, result) = extract(&[Entry::Symlink("link", "/etc/passwd")]);
+        assert!(matches!(result, Err(ExtractError::Unsafe(_))));
+        assert!(!dir.0.join("out/link").exists());
+    }
+
+    #[test]
+    fn rejects_case_collisions() {
+        let (_dir, result) = extract(&[
+            Entry::File("Saves/save.srm", b"one"),
+            Entry::File("saves/SAVE.srm", b"two"),
+        ]);
+        assert!(matches!(result, Err(ExtractError::Unsafe(_))));
+    }
+
+    #[test]
+    fn enforces_size_limit() {
+        let dir = TempDir::new();
+        let data = vec![0u8; 64 * 1024];
+        let archive = write_archive(&dir.0, &[Entry::File("bomb.bin", &data)]);
+        let limits = ExtractLimits {
+            max_entries: 10,
+            max_total_bytes: 1024,
+        };
+
+        let result = extract_with_limits(&archive, &dir.0.join("out"), limits);
+        assert!(matches!(result, Err(ExtractError::TooLarge(_))));
+        assert!(!dir.0.join("out/bomb.bin").exists());
+    }
+
+    #[test]
+    fn enforces_entry_limit() {
+        let dir = TempDir::new();
+        let archive = write_archive(
+            &dir.0,
+            &[Entry::File("a.srm", b"a"), Entry::File("b.srm", b"b")],
+        );
+        let limits = ExtractLimits {
+            max_entries: 1,
+            ..ExtractLimits::default()
+        };
+
+        let result = extract_with_limits(&archive, &dir.0.join("out"), limits);
+        assert!(matches!(result, Err(ExtractError::TooLarge(_))));
+    }
+}
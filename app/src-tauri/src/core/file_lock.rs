@@ -0,0 +1,86 @@
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+/// How long packaging waits for the emulator to let go of a save file it
+/// still has open for writing
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LockRetryPolicy {
+    /// Checks before giving up; `0` packages without checking
+    pub attempts: u32,
+    /// Wait between checks
+    pub delay_ms: u64,
+}
+
+impl Default for LockRetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            delay_ms: 500,
+        }
+    }
+}
+
+impl LockRetryPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Whether another process has `path` open for writing or holds a lock on
+/// its start. Opening it while only sharing reads fails when anyone else
+/// can write to it. Windows only; elsewhere locks are advisory and reads see
+/// what the emulator has written so far.
+#[cfg(windows)]
+pub fn is_locked(path: &Path) -> bool {
+    use std::{fs::OpenOptions, io::Read, os::windows::fs::OpenOptionsExt};
+
+    const FILE_SHARE_READ: u32 = 0x1;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    let lock_error = |err: std::io::Error| {
+        matches!(
+            err.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        )
+    };
+
+    match OpenOptions::new()
+        .read(true)
+        .share_mode(FILE_SHARE_READ)
+        .open(path)
+    {
+        // A byte-range lock only shows once the locked bytes are read
+        Ok(mut file) => file.read(&mut [0u8; 1]).is_err_and(lock_error),
+        Err(err) => lock_error(err),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn is_locked(_path: &Path) -> bool {
+    false
+}
+
+/// Wait until none of `files` is locked, checking up to `policy.attempts`
+/// times. Returns a file that is still locked when the attempts run out.
+pub fn wait_for_unlocked(files: &[PathBuf], policy: LockRetryPolicy) -> Option<PathBuf> {
+    for attempt in 1..=policy.attempts {
+        let locked = files.iter().find(|file| is_locked(file))?;
+        if attempt == policy.attempts {
+            warn!("[PACKAGER] {:?} is still locked by another program", locked);
+            return Some(locked.clone());
+        }
+        debug!(
+            "[PACKAGER] {:?} is locked; checking again in {}ms",
+            locked, policy.delay_ms
+        );
+        thread::sleep(Duration::from_millis(policy.delay_ms));
+    }
+    None
+}
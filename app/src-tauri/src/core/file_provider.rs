@@ -0,0 +1,382 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Scheme of Storage Access Framework document URIs on Android
+pub const CONTENT_SCHEME: &str = "content://";
+
+#[derive(Debug, Error)]
+pub enum FileProviderError {
+    #[error("no file provider for {0}")]
+    Unavailable(String),
+    #[error("invalid file name: {0}")]
+    InvalidName(String),
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("provider error: {0}")]
+    Provider(String),
+}
+
+impl From<std::io::Error> for FileProviderError {
+    fn from(err: std::io::Error) -> Self {
+        FileProviderError::Io(err.to_string())
+    }
+}
+
+/// A file somewhere below a provider root
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvidedFile {
+    /// Relative to the root, with `/` separators
+    pub relative_path: String,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch
+    pub modified_ms: u64,
+}
+
+/// Access to save folders that are not plain paths, like the `content://`
+/// trees Android hands out through its folder picker. Roots are whatever
+/// string the provider understands; files are addressed relative to them.
+pub trait FileProvider: Send + Sync {
+    /// Every file below `root`, recursively
+    fn list_files(&self, root: &str) -> Result<Vec<ProvidedFile>, FileProviderError>;
+    /// Copy `relative` out of `root` into the local file `dest`
+    fn read_to(&self, root: &str, relative: &str, dest: &Path) -> Result<(), FileProviderError>;
+    /// Copy the local file `source` into `root` as `relative`, creating
+    /// folders and replacing an existing file
+    fn write_from(
+        &self,
+        root: &str,
+        relative: &str,
+        source: &Path,
+    ) -> Result<(), FileProviderError>;
+}
+
+/// Plain directories, for tests and for platforms where roots are paths
+pub struct LocalFileProvider;
+
+impl FileProvider for LocalFileProvider {
+    fn list_files(&self, root: &str) -> Result<Vec<ProvidedFile>, FileProviderError> {
+        let root = Path::new(root);
+        let mut files = Vec::new();
+        let mut stack = vec![root.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                let path = entry.path();
+                if metadata.is_dir() {
+                    stack.push(path);
+                    continue;
+                }
+                let Some(relative) = path
+                    .strip_prefix(root)
+                    .ok()
+                    .and_then(crate::core::paths::archive_entry_name)
+                else {
+                    continue;
+                };
+                files.push(ProvidedFile {
+                    relative_path: relative,
+                    size: metadata.len(),
+                    modified_ms: modified_ms(&metadata),
+                });
+            }
+        }
+        files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+        Ok(files)
+    }
+
+    fn read_to(&self, root: &str, relative: &str, dest: &Path) -> Result<(), FileProviderError> {
+        fs::copy(local_path(Path::new(root), relative)?, dest)?;
+        Ok(())
+    }
+
+    fn write_from(
+        &self,
+        root: &str,
+        relative: &str,
+        source: &Path,
+    ) -> Result<(), FileProviderError> {
+        let target = local_path(Path::new(root), relative)?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source, target)?;
+        Ok(())
+    }
+}
+
+struct ContentAccess {
+    provider: Box<dyn FileProvider>,
+    /// Where `content://` trees are copied so the packager can read them
+    mirror_root: PathBuf,
+}
+
+static CONTENT_ACCESS: OnceLock<ContentAccess> = OnceLock::new();
+
+/// Route `content://` save folders through `provider`. Called once at
+/// startup on platforms that have one.
+pub fn install(provider: Box<dyn FileProvider>, mirror_root: PathBuf) {
+    let access = ContentAccess {
+        provider,
+        mirror_root,
+    };
+    if CONTENT_ACCESS.set(access).is_err() {
+        warn!("[PACKAGER] File provider already installed");
+    }
+}
+
+fn content_access(root: &str) -> Result<&'static ContentAccess, FileProviderError> {
+    CONTENT_ACCESS
+        .get()
+        .ok_or_else(|| FileProviderError::Unavailable(root.to_string()))
+}
+
+/// The URI when `path` names a `content://` tree rather than a local path
+pub fn content_uri(path: &Path) -> Option<&str> {
+    path.to_str()
+        .filter(|text| text.starts_with(CONTENT_SCHEME))
+}
+
+pub fn is_content_uri(path: &Path) -> bool {
+    content_uri(path).is_some()
+}
+
+/// Files below a `content://` tree
+pub fn list_content(uri: &str) -> Result<Vec<ProvidedFile>, FileProviderError> {
+    content_access(uri)?.provider.list_files(uri)
+}
+
+/// `paths` with every `content://` tree replaced by a local copy of it,
+/// refreshed first. Trees that can't be read are skipped with a warning,
+/// as missing local paths are.
+pub fn local_roots(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let Some(uri) = content_uri(&path) else {
+                return Some(path);
+            };
+            let mirrored = content_access(uri).and_then(|access| {
+                let dest = mirror_dir(&access.mirror_root, uri);
+                mirror_tree(access.provider.as_ref(), uri, &dest).map(|_| dest)
+            });
+            match mirrored {
+                Ok(dest) => Some(dest),
+                Err(err) => {
+                    warn!("[PACKAGER] Skipping {uri}: {err}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Empty scratch folder for files on their way into `uri`
+pub fn staging_dir(uri: &str) -> Result<PathBuf, FileProviderError> {
+    let access = content_access(uri)?;
+    let dir = mirror_dir(&access.mirror_root, uri).with_extension("staging");
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Copy `files`, relative to the local folder `source`, into the
+/// `content://` tree `uri`
+pub fn write_tree(uri: &str, source: &Path, files: &[String]) -> Result<(), FileProviderError> {
+    let access = content_access(uri)?;
+    for file in files {
+        access
+            .provider
+            .write_from(uri, file, &local_path(source, file)?)?;
+        debug!("[SYNC] Wrote {file} to {uri}");
+    }
+    Ok(())
+}
+
+/// Stable per-tree folder under `mirror_root`
+fn mirror_dir(mirror_root: &Path, uri: &str) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(uri.as_bytes()));
+    mirror_root.join(&digest[..16])
+}
+
+/// Bring `dest` in line with `root`: copy files whose size or modification
+/// time differ and delete files that are gone. Copies keep the source's
+/// modification time, so unchanged saves still fingerprint the same.
+pub fn mirror_tree(
+    provider: &dyn FileProvider,
+    root: &str,
+    dest: &Path,
+) -> Result<Vec<ProvidedFile>, FileProviderError> {
+    let files = provider.list_files(root)?;
+    fs::create_dir_all(dest)?;
+
+    let mut kept = HashSet::new();
+    for file in &files {
+        let local = match local_path(dest, &file.relative_path) {
+            Ok(local) => local,
+            Err(err) => {
+                warn!("[PACKAGER] Skipping file in {root}: {err}");
+                continue;
+            }
+        };
+        kept.insert(local.clone());
+
+        let current = fs::metadata(&local).ok();
+        if current.is_some_and(|metadata| {
+            metadata.len() == file.size && modified_ms(&metadata) == file.modified_ms
+        }) {
+            continue;
+        }
+        if let Some(parent) = local.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        provider.read_to(root, &file.relative_path, &local)?;
+        fs::File::options()
+            .write(true)
+            .open(&local)?
+            .set_modified(UNIX_EPOCH + Duration::from_millis(file.modified_ms))?;
+    }
+
+    remove_stale(dest, &kept);
+    Ok(files)
+}
+
+fn remove_stale(dest: &Path, kept: &HashSet<PathBuf>) {
+    let mut stack = vec![dest.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.is_dir() {
+                stack.push(path);
+            } else if !kept.contains(&path) {
+                if let Err(err) = fs::remove_file(&path) {
+                    warn!("[PACKAGER] Failed to remove stale copy {:?}: {err}", path);
+                }
+            }
+        }
+    }
+}
+
+/// `relative` below `root`, refusing names that would leave it
+fn local_path(root: &Path, relative: &str) -> Result<PathBuf, FileProviderError> {
+    let mut path = root.to_path_buf();
+    for segment in relative.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => return Err(FileProviderError::InvalidName(relative.to_string())),
+            _ if segment.contains(['\\', ':', '\0']) => {
+                return Err(FileProviderError::InvalidName(relative.to_string()))
+            }
+            _ => path.push(segment),
+        }
+    }
+    if path == root {
+        return Err(FileProviderError::InvalidName(relative.to_string()));
+    }
+    Ok(path)
+}
+
+fn modified_ms(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path =
+                std::env::temp_dir().join(format!("crosssave-provider-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&path).expect("create temp dir");
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write(path: &Path, data: &[u8]) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, data).expect("write");
+    }
+
+    #[test]
+    fn mirrors_tree_and_drops_deleted_files() {
+        let source = TempDir::new();
+        let mirror = TempDir::new();
+        let root = source.0.to_string_lossy().to_string();
+        write(&source.0.join("GC/USA/save.gci"), b"card");
+        write(&source.0.join("zelda.srm"), b"save");
+
+        let files = mirror_tree(&LocalFileProvider, &root, &mirror.0).expect("mirror");
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            fs::read(mirror.0.join("GC/USA/save.gci")).expect("read"),
+            b"card"
+        );
+
+        fs::remove_file(source.0.join("zelda.srm")).expect("remove");
+        mirror_tree(&LocalFileProvider, &root, &mirror.0).expect("mirror again");
+        assert!(!mirror.0.join("zelda.srm").exists());
+    }
+
+    #[test]
+    fn mirrored_copies_keep_modification_time() {
+        let source = TempDir::new();
+        let mirror = TempDir::new();
+        write(&source.0.join("zelda.srm"), b"save");
+
+        let files = mirror_tree(&LocalFileProvider, &source.0.to_string_lossy(), &mirror.0)
+            .expect("mirror");
+        let copied = fs::metadata(mirror.0.join("zelda.srm")).expect("metadata");
+        assert_eq!(modified_ms(&copied), files[0].modified_ms);
+    }
+
+    #[test]
+    fn rejects_names_outside_the_root() {
+        let root = Path::new("/saves");
+        assert!(local_path(root, "../evil.srm").is_err());
+        assert!(local_path(root, "a/../../evil.srm").is_err());
+        assert!(local_path(root, "").is_err());
+        assert_eq!(
+            local_path(root, "GC/save.gci").expect("path"),
+            root.join("GC").join("save.gci")
+        );
+    }
+
+    #[test]
+    fn recognises_content_uris() {
+        assert!(is_content_uri(Path::new(
+            "content://com.android.externalstorage.documents/tree/primary%3ARetroArch"
+        )));
+        assert!(!is_content_uri(Path::new("/storage/emulated/0/RetroArch")));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, sync::OnceLock, time::Duration};
+
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use super::{packager::SaveMetadata, settings::AppSettings};
+
+/// No-Intro and Redump names of well-known games, bundled with the app
+const BUNDLED_TITLES: &str = include_str!("../../resources/game_titles.json");
+/// Articles No-Intro moves behind the title, as in "Legend of Zelda, The"
+const ARTICLES: &[&str] = &["The", "A", "An"];
+
+#[derive(Debug, Error)]
+pub enum GameInfoError {
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("invalid game info response: {0}")]
+    InvalidResponse(String),
+}
+
+/// What is known about a game beyond its `game_id`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameInfo {
+    /// Display title without region or revision tags
+    pub title: String,
+    /// Short platform id, e.g. `snes` or `psx`
+    #[serde(default)]
+    pub platform: Option<String>,
+    /// SHA-256 of the game's cover art image
+    #[serde(default)]
+    pub cover_sha256: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TitleFile {
+    games: Vec<TitleEntry>,
+}
+
+#[derive(Deserialize)]
+struct TitleEntry {
+    /// Full No-Intro or Redump name, e.g. "Super Mario World (USA)"
+    name: String,
+    platform: Option<String>,
+    #[serde(default)]
+    cover_sha256: Option<String>,
+}
+
+struct TitleDatabase {
+    entries: Vec<TitleEntry>,
+    /// Match key of each name form to its entry; the first entry listed
+    /// wins when regional releases share a title
+    index: HashMap<String, usize>,
+}
+
+impl TitleDatabase {
+    fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let file: TitleFile = serde_json::from_str(json)?;
+        let mut index = HashMap::new();
+        for (position, entry) in file.games.iter().enumerate() {
+            let title = display_title(&entry.name);
+            let tags = &entry.name[title_end(&entry.name)..];
+            for form in [
+                entry.name.clone(),
+                format!("{title}{tags}"),
+                title_without_tags(&entry.name).to_string(),
+                title,
+            ] {
+                let key = match_key(&form);
+                if !key.is_empty() {
+                    index.entry(key).or_insert(position);
+                }
+            }
+        }
+        Ok(Self {
+            entries: file.games,
+            index,
+        })
+    }
+
+    fn lookup(&self, game_id: &str) -> Option<GameInfo> {
+        let entry = &self.entries[*self.index.get(&match_key(game_id))?];
+        Some(GameInfo {
+            title: display_title(&entry.name),
+            platform: entry.platform.clone(),
+            cover_sha256: entry.cover_sha256.clone(),
+        })
+    }
+}
+
+fn bundled_database() -> &'static TitleDatabase {
+    static DATABASE: OnceLock<TitleDatabase> = OnceLock::new();
+    DATABASE.get_or_init(|| {
+        TitleDatabase::parse(BUNDLED_TITLES).unwrap_or_else(|err| {
+            warn!("[GAME_INFO] Bundled title database is invalid: {err}");
+            TitleDatabase {
+                entries: Vec::new(),
+                index: HashMap::new(),
+            }
+        })
+    })
+}
+
+/// Match `game_id` against the bundled name database. Ids are compared
+/// by their letters and digits only, so "Super_Mario_World",
+/// "super_mario_world_usa" and "Super Mario World (USA)" all match.
+pub fn lookup_bundled(game_id: &str) -> Option<GameInfo> {
+    bundled_database().lookup(game_id)
+}
+
+/// Fill in the title, platform and cover art of `metadata` from the bundled
+/// database, keeping whatever is already set
+pub fn attach_bundled(metadata: &mut SaveMetadata) {
+    let Some(info) = lookup_bundled(&metadata.game_id) else {
+        return;
+    };
+    metadata.title.get_or_insert(info.title);
+    if metadata.platform.is_none() {
+        metadata.platform = info.platform;
+    }
+    if metadata.cover_sha256.is_none() {
+        metadata.cover_sha256 = info.cover_sha256;
+    }
+}
+
+/// Look `game_id` up in the bundled database, then ask the online lookup
+/// when one is configured and the bundled entry is missing or incomplete.
+/// A failed online lookup falls back to the bundled entry.
+pub async fn lookup(settings: &AppSettings, game_id: &str) -> Option<GameInfo> {
+    let bundled = lookup_bundled(game_id);
+    let complete = bundled
+        .as_ref()
+        .is_some_and(|info| info.platform.is_some() && info.cover_sha256.is_some());
+    let lookup_url = settings
+        .game_info
+        .lookup_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty());
+    let Some(lookup_url) = lookup_url.filter(|_| !complete) else {
+        return bundled;
+    };
+
+    let timeout = Duration::from_secs(settings.cloud.timeout_seconds.max(1));
+    let online = match lookup_online(lookup_url, game_id, timeout).await {
+        Ok(online) => online,
+        Err(err) => {
+            warn!("[GAME_INFO] Online lookup for {game_id} failed: {err}");
+            None
+        }
+    };
+    match (bundled, online) {
+        (Some(mut bundled), Some(online)) => {
+            bundled.platform = bundled.platform.or(online.platform);
+            bundled.cover_sha256 = bundled.cover_sha256.or(online.cover_sha256);
+            Some(bundled)
+        }
+        (bundled, online) => bundled.or(online),
+    }
+}
+
+/// `GET <lookup_url>?game_id=<id>`, answered with a `GameInfo` or `404`
+async fn lookup_online(
+    lookup_url: &str,
+    game_id: &str,
+    timeout: Duration,
+) -> Result<Option<GameInfo>, GameInfoError> {
+    let client = Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|err| GameInfoError::Network(err.to_string()))?;
+    let response = client
+        .get(lookup_url)
+        .query(&[("game_id", game_id)])
+        .send()
+        .await
+        .map_err(|err| GameInfoError::Network(err.to_string()))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        debug!("[GAME_INFO] Online lookup doesn't know {game_id}");
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(GameInfoError::Network(format!(
+            "lookup returned {}",
+            response.status()
+        )));
+    }
+    let info: GameInfo = response
+        .json()
+        .await
+        .map_err(|err| GameInfoError::InvalidResponse(err.to_string()))?;
+    Ok(Some(info).filter(|info| !info.title.trim().is_empty()))
+}
+
+/// End of the title, where the first `(region)` or `[flag]` tag starts
+fn title_end(name: &str) -> usize {
+    [" (", " ["]
+        .iter()
+        .filter_map(|tag| name.find(tag))
+        .min()
+        .unwrap_or(name.len())
+}
+
+fn title_without_tags(name: &str) -> &str {
+    name[..title_end(name)].trim()
+}
+
+/// Title as people write it: tags dropped and a trailing article moved back
+/// to the front, "Legend of Zelda, The - A Link to the Past (USA)" becoming
+/// "The Legend of Zelda - A Link to the Past"
+fn display_title(name: &str) -> String {
+    let title = title_without_tags(name);
+    let (main, subtitle) = match title.split_once(" - ") {
+        Some((main, subtitle)) => (main, Some(subtitle)),
+        None => (title, None),
+    };
+    let main = match main.rsplit_once(", ") {
+        Some((rest, article)) if ARTICLES.contains(&article) => format!("{article} {rest}"),
+        _ => main.to_string(),
+    };
+    match subtitle {
+        Some(subtitle) => format!("{main} - {subtitle}"),
+        None => main,
+    }
+}
+
+fn match_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitized_ids_match_bundled_names() {
+        let info = lookup_bundled("Super_Mario_World").expect("bundled title");
+        assert_eq!(info.title, "Super Mario World");
+        assert_eq!(info.platform.as_deref(), Some("snes"));
+
+        assert_eq!(lookup_bundled("super_mario_world_usa"), Some(info.clone()));
+        assert_eq!(lookup_bundled("Super Mario World (USA)"), Some(info));
+
+        let zelda = lookup_bundled("The_Legend_of_Zelda_A_Link_to_the_Past").unwrap();
+        assert_eq!(zelda.title, "The Legend of Zelda - A Link to the Past");
+        assert_eq!(
+            lookup_bundled("Legend_of_Zelda_The_A_Link_to_the_Past_USA"),
+            Some(zelda)
+        );
+
+        assert_eq!(lookup_bundled("Not_A_Real_Game"), None);
+    }
+
+    #[test]
+    fn first_listed_release_wins_a_shared_title() {
+        let database = TitleDatabase::parse(
+            r#"{ "games": [
+                { "name": "Tetris (World) (Rev 1)", "platform": "gb", "cover_sha256": "ab" },
+                { "name": "Tetris (Japan)", "platform": "gb" }
+            ] }"#,
+        )
+        .unwrap();
+        let info = database.lookup("Tetris").unwrap();
+        assert_eq!(info.cover_sha256.as_deref(), Some("ab"));
+        assert_eq!(database.lookup("tetris_japan").unwrap().cover_sha256, None);
+    }
+}
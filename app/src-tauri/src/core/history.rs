@@ -1,25 +1,148 @@
 use std::{
-    collections::HashMap,
-    fs,
+    collections::{HashMap, HashSet},
+    fs, io,
     path::{Path, PathBuf},
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 use tracing::{error, info, warn};
 
-use crate::core::packager::{PackagedSave, SaveMetadata};
+use crate::core::file_provider::is_content_uri;
+use crate::core::packager::{file_sha256, SaveMetadata, METADATA_SCHEMA_VERSION};
+use crate::core::paths::serde_path;
+use crate::core::settings::AgeRetention;
+use crate::core::storage::is_storage_full;
+use crate::core::thumbnail::{thumbnail_sha256, validate_thumbnail};
 
 const DEFAULT_RETENTION: usize = 10;
+/// Label limits, matching the cloud server's validation
+const MAX_NOTE_CHARS: usize = 500;
+const MAX_TAGS: usize = 16;
+const MAX_TAG_CHARS: usize = 32;
+/// Page size for `query_history` when the caller does not pass one
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 500;
+const SECS_PER_HOUR: u64 = 60 * 60;
+const SECS_PER_DAY: u64 = 24 * SECS_PER_HOUR;
+/// Thinning keeps daily versions this long, then weekly ones
+const DAILY_THINNING_DAYS: u64 = 30;
+
+/// Upgrade of an entry's metadata by one schema version. `MIGRATIONS[n]`
+/// takes version `n` to `n + 1`.
+type Migration = fn(&mut HistoryEntry) -> Result<(), HistoryError>;
+const MIGRATIONS: &[Migration] = &[fill_archive_checksums];
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HistoryEntry {
-    pub archive_path: String,
-    pub metadata_path: String,
+    #[serde(with = "serde_path")]
+    pub archive_path: PathBuf,
+    #[serde(with = "serde_path")]
+    pub metadata_path: PathBuf,
     pub metadata: SaveMetadata,
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySort {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+    /// Alphabetical by game, newest first within a game
+    GameId,
+}
+
+/// Filters for the cross-game timeline. Every field is optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    pub emulator_id: Option<String>,
+    /// `"local"`, `"manual"` or `"cloud"`
+    pub source: Option<String>,
+    /// Inclusive Unix-second bounds on the version timestamp
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+    /// Case-insensitive match against game ID, note and tags
+    pub search: Option<String>,
+    pub sort: HistorySort,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    /// Matching entries before pagination
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// False while startup indexing is still running, so results may be partial
+    pub indexed: bool,
+}
+
+/// Outcome of `HistoryManager::compact`
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CompactionReport {
+    pub games_scanned: usize,
+    pub duplicates_linked: usize,
+    pub reclaimed_bytes: u64,
+}
+
+/// A version removed to keep local history under its size budget
+#[derive(Clone, Debug, Serialize)]
+pub struct EvictedVersion {
+    pub game_id: String,
+    pub version_id: String,
+    pub timestamp: u64,
+    pub size_bytes: u64,
+}
+
+impl HistoryQuery {
+    fn matches(&self, metadata: &SaveMetadata, search: Option<&str>) -> bool {
+        if let Some(emulator_id) = &self.emulator_id {
+            if &metadata.emulator_id != emulator_id {
+                return false;
+            }
+        }
+
+        if let Some(source) = &self.source {
+            // Entries written before `source` existed were packaged locally
+            if metadata.source.as_deref().unwrap_or("local") != source {
+                return false;
+            }
+        }
+
+        if self
+            .from_timestamp
+            .is_some_and(|from| metadata.timestamp < from)
+            || self.to_timestamp.is_some_and(|to| metadata.timestamp > to)
+        {
+            return false;
+        }
+
+        match search {
+            Some(search) => {
+                metadata.game_id.to_lowercase().contains(search)
+                    || metadata
+                        .note
+                        .as_ref()
+                        .is_some_and(|note| note.to_lowercase().contains(search))
+                    || metadata
+                        .tags
+                        .iter()
+                        .any(|tag| tag.to_lowercase().contains(search))
+            }
+            None => true,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum HistoryError {
     #[error("io error: {0}")]
@@ -32,14 +155,34 @@ pub enum HistoryError {
     Lock(String),
     #[error("history item not found: {0}")]
     NotFound(String),
+    #[error("not enough disk space: {0}")]
+    StorageFull(String),
+}
+
+/// Map a failed write, keeping "disk full" apart from other I/O errors
+fn write_error(err: std::io::Error) -> HistoryError {
+    if is_storage_full(&err) {
+        HistoryError::StorageFull(err.to_string())
+    } else {
+        HistoryError::Io(err.to_string())
+    }
 }
 
 #[derive(Debug)]
 pub struct HistoryManager {
-    pub base_dir: PathBuf,
+    /// Moved by `relocate`
+    base_dir: RwLock<PathBuf>,
     cache: Mutex<HashMap<String, Vec<HistoryEntry>>>,
     retention_limit: Mutex<usize>,
     auto_delete: Mutex<bool>,
+    size_budget: Mutex<Option<u64>>,
+    age_retention: Mutex<AgeRetention>,
+    /// Per-game replacements for `age_retention`
+    game_age_retention: Mutex<HashMap<String, AgeRetention>>,
+    indexed: AtomicBool,
+    /// Game IDs of locally saved versions, for sync to pick up right away
+    changes: UnboundedSender<String>,
+    change_receiver: Mutex<Option<UnboundedReceiver<String>>>,
 }
 
 impl HistoryManager {
@@ -48,12 +191,46 @@ impl HistoryManager {
         retention_limit: usize,
         auto_delete: bool,
     ) -> Result<Self, HistoryError> {
-        fs::create_dir_all(&base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
+        let manager = Self::new_unindexed(base_dir, retention_limit, auto_delete)?;
+        manager.index_with_progress(|_, _, _| {})?;
+        Ok(manager)
+    }
 
-        let mut cache: HashMap<String, Vec<HistoryEntry>> = HashMap::new();
+    /// Create the manager without scanning the history directory. The cache
+    /// is filled later by `index_with_progress`, so startup does not wait on disk.
+    pub fn new_unindexed(
+        base_dir: PathBuf,
+        retention_limit: usize,
+        auto_delete: bool,
+    ) -> Result<Self, HistoryError> {
+        fs::create_dir_all(&base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
+        let (changes, change_receiver) = unbounded_channel();
 
-        let entries = fs::read_dir(&base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
+        Ok(Self {
+            base_dir: RwLock::new(base_dir),
+            cache: Mutex::new(HashMap::new()),
+            retention_limit: Mutex::new(retention_limit),
+            auto_delete: Mutex::new(auto_delete),
+            size_budget: Mutex::new(None),
+            age_retention: Mutex::new(AgeRetention::default()),
+            game_age_retention: Mutex::new(HashMap::new()),
+            indexed: AtomicBool::new(false),
+            changes,
+            change_receiver: Mutex::new(Some(change_receiver)),
+        })
+    }
 
+    /// Load every game directory into the cache, reporting
+    /// `(indexed_games, total_games, game_id)` after each one.
+    pub fn index_with_progress<F>(&self, mut on_progress: F) -> Result<usize, HistoryError>
+    where
+        F: FnMut(usize, usize, &str),
+    {
+        let (retention_limit, auto_delete) = self.policy()?;
+        let entries =
+            fs::read_dir(self.base_dir()).map_err(|err| HistoryError::Io(err.to_string()))?;
+
+        let mut game_dirs: Vec<(String, PathBuf)> = Vec::new();
         for entry in entries {
             let entry = match entry {
                 Ok(value) => value,
@@ -77,20 +254,55 @@ impl HistoryManager {
                 continue;
             };
 
+            game_dirs.push((game_id, path));
+        }
+
+        let total = game_dirs.len();
+        for (index, (game_id, path)) in game_dirs.into_iter().enumerate() {
             let mut history_entries = Self::load_history_entries(&path, &game_id)?;
             if auto_delete {
-                Self::enforce_retention(&mut history_entries, retention_limit)?;
+                Self::enforce_retention(
+                    &mut history_entries,
+                    retention_limit,
+                    &self.age_retention_for(&game_id)?,
+                    unix_now(),
+                )?;
+            }
+
+            {
+                let mut guard = self
+                    .cache
+                    .lock()
+                    .map_err(|err| HistoryError::Lock(err.to_string()))?;
+                // Versions saved while indexing are already cached; keep them
+                let cached = guard.entry(game_id.clone()).or_default();
+                for entry in history_entries {
+                    if !cached
+                        .iter()
+                        .any(|existing| existing.metadata.version_id == entry.metadata.version_id)
+                    {
+                        cached.push(entry);
+                    }
+                }
+                cached.sort_by(|a, b| b.metadata.timestamp.cmp(&a.metadata.timestamp));
             }
 
-            cache.insert(game_id, history_entries);
+            on_progress(index + 1, total, &game_id);
         }
 
-        Ok(Self {
-            base_dir,
-            cache: Mutex::new(cache),
-            retention_limit: Mutex::new(retention_limit),
-            auto_delete: Mutex::new(auto_delete),
-        })
+        self.indexed.store(true, Ordering::SeqCst);
+        info!("[HISTORY] Indexed {} games", total);
+        Ok(total)
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.indexed.load(Ordering::SeqCst)
+    }
+
+    /// Receiver of the game IDs of locally saved versions. There is one
+    /// receiver; later calls return `None`.
+    pub fn take_change_receiver(&self) -> Option<UnboundedReceiver<String>> {
+        self.change_receiver.lock().ok()?.take()
     }
 
     pub fn with_defaults() -> Self {
@@ -103,11 +315,18 @@ impl HistoryManager {
             Ok(manager) => manager,
             Err(err) => {
                 error!("[HISTORY] Failed to initialize history manager: {err}");
+                let (changes, change_receiver) = unbounded_channel();
                 Self {
-                    base_dir,
+                    base_dir: RwLock::new(base_dir),
                     cache: Mutex::new(HashMap::new()),
                     retention_limit: Mutex::new(DEFAULT_RETENTION),
                     auto_delete: Mutex::new(true),
+                    size_budget: Mutex::new(None),
+                    age_retention: Mutex::new(AgeRetention::default()),
+                    game_age_retention: Mutex::new(HashMap::new()),
+                    indexed: AtomicBool::new(true),
+                    changes,
+                    change_receiver: Mutex::new(Some(change_receiver)),
                 }
             }
         }
@@ -141,6 +360,39 @@ impl HistoryManager {
         Ok(())
     }
 
+    /// Prune by age with `global`, or the entry in `games` for a game that
+    /// has one
+    pub fn set_age_retention(
+        &self,
+        global: AgeRetention,
+        games: HashMap<String, AgeRetention>,
+    ) -> Result<(), HistoryError> {
+        *self
+            .age_retention
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))? = global;
+        *self
+            .game_age_retention
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))? = games;
+        self.trim_all()
+    }
+
+    fn age_retention_for(&self, game_id: &str) -> Result<AgeRetention, HistoryError> {
+        let games = self
+            .game_age_retention
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))?;
+        if let Some(retention) = games.get(game_id) {
+            return Ok(retention.clone());
+        }
+        drop(games);
+        self.age_retention
+            .lock()
+            .map(|retention| retention.clone())
+            .map_err(|err| HistoryError::Lock(err.to_string()))
+    }
+
     pub fn policy(&self) -> Result<(usize, bool), HistoryError> {
         let limit = *self
             .retention_limit
@@ -153,9 +405,143 @@ impl HistoryManager {
         Ok((limit, auto_delete))
     }
 
+    /// Limit the total size of local history; `None` removes the limit.
+    /// Call `enforce_size_budget` afterwards to apply it.
+    pub fn set_size_budget(&self, max_size_mb: Option<u64>) -> Result<(), HistoryError> {
+        let mut guard = self
+            .size_budget
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))?;
+        *guard = max_size_mb.map(|size_mb| size_mb.saturating_mul(1024 * 1024));
+        Ok(())
+    }
+
+    /// Evict the oldest unpinned versions across all games until history fits
+    /// its size budget. The newest version of each game is always kept.
+    pub fn enforce_size_budget(&self) -> Result<Vec<EvictedVersion>, HistoryError> {
+        let Some(budget) = *self
+            .size_budget
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut total = self.total_size()?;
+        if total <= budget {
+            return Ok(Vec::new());
+        }
+
+        let mut guard = self
+            .cache
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))?;
+
+        // Entries are newest first, so skipping the first keeps each game's latest
+        let mut candidates: Vec<(u64, String, String)> = guard
+            .iter()
+            .flat_map(|(game_id, entries)| {
+                entries
+                    .iter()
+                    .skip(1)
+                    .filter(|entry| !entry.metadata.pinned)
+                    .map(move |entry| {
+                        (
+                            entry.metadata.timestamp,
+                            game_id.clone(),
+                            entry.metadata.version_id.clone(),
+                        )
+                    })
+            })
+            .collect();
+        candidates.sort();
+
+        let mut evicted = Vec::new();
+        for (timestamp, game_id, version_id) in candidates {
+            if total <= budget {
+                break;
+            }
+
+            let Some(entries) = guard.get_mut(&game_id) else {
+                continue;
+            };
+            let Some(index) = entries
+                .iter()
+                .position(|entry| entry.metadata.version_id == version_id)
+            else {
+                continue;
+            };
+
+            let removed = entries.remove(index);
+            let size_bytes = entry_disk_size(&removed);
+            Self::remove_files(&removed)?;
+            total = total.saturating_sub(size_bytes);
+            info!("[HISTORY] Evicted version {version_id} for {game_id} ({size_bytes} bytes)");
+            evicted.push(EvictedVersion {
+                game_id,
+                version_id,
+                timestamp,
+                size_bytes,
+            });
+        }
+
+        if total > budget {
+            warn!(
+                "[HISTORY] History is {total} bytes, over its {budget} byte budget; remaining versions are pinned or latest"
+            );
+        }
+
+        Ok(evicted)
+    }
+
+    /// Replace byte-identical archives within each game by hard links to the
+    /// oldest copy. Entries without a recorded sha256 are left alone.
+    pub fn compact(&self) -> Result<CompactionReport, HistoryError> {
+        // Held for the whole pass so no entry is removed while it is relinked
+        let guard = self
+            .cache
+            .lock()
+            .map_err(|err| HistoryError::Lock(err.to_string()))?;
+
+        let mut report = CompactionReport::default();
+        for (game_id, entries) in guard.iter() {
+            report.games_scanned += 1;
+            let mut originals: HashMap<&str, &HistoryEntry> = HashMap::new();
+
+            // Entries are newest first; walk oldest first to keep the original
+            for entry in entries.iter().rev() {
+                let Some(hash) = entry.metadata.sha256.as_deref() else {
+                    continue;
+                };
+                let Some(original) = originals.get(hash) else {
+                    originals.insert(hash, entry);
+                    continue;
+                };
+
+                match link_archive(&original.archive_path, &entry.archive_path) {
+                    Ok(Some(bytes)) => {
+                        report.duplicates_linked += 1;
+                        report.reclaimed_bytes += bytes;
+                    }
+                    Ok(None) => {}
+                    Err(err) => warn!(
+                        "[HISTORY] Failed to deduplicate {game_id} version {}: {err}",
+                        entry.metadata.version_id
+                    ),
+                }
+            }
+        }
+
+        info!(
+            "[HISTORY] Compacted history: {} duplicates linked, {} bytes reclaimed",
+            report.duplicates_linked, report.reclaimed_bytes
+        );
+        Ok(report)
+    }
+
     pub fn save_to_history(
         &self,
-        metadata: SaveMetadata,
+        mut metadata: SaveMetadata,
         archive_path: PathBuf,
     ) -> Result<HistoryEntry, HistoryError> {
         if metadata.game_id.trim().is_empty() {
@@ -172,27 +558,54 @@ impl HistoryManager {
             ));
         }
 
-        let game_dir = self.base_dir.join(&metadata.game_id);
-        fs::create_dir_all(&game_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
+        // A local version continues from the newest one here, which may have
+        // been downloaded from another device
+        if metadata.parent_version_id.is_none() && metadata.source.as_deref() != Some("cloud") {
+            metadata.parent_version_id = self
+                .get_latest_version(&metadata.game_id)
+                .map(|latest| latest.metadata.version_id)
+                .filter(|parent| *parent != metadata.version_id);
+        }
+
+        let game_dir = self.base_dir().join(&metadata.game_id);
+        fs::create_dir_all(&game_dir).map_err(write_error)?;
 
         let archive_destination = game_dir.join(format!("{}.zip", metadata.version_id));
-        fs::copy(&archive_path, &archive_destination)
-            .map_err(|err| HistoryError::Io(err.to_string()))?;
+        fs::copy(&archive_path, &archive_destination).map_err(write_error)?;
+
+        if let Some(source) = metadata.thumbnail.take() {
+            match Self::store_thumbnail(&game_dir, &metadata.version_id, Path::new(&source)) {
+                Ok((thumbnail, sha256)) => {
+                    metadata.thumbnail = Some(thumbnail);
+                    metadata.thumbnail_sha256 = Some(sha256);
+                }
+                Err(err) => {
+                    warn!(
+                        "[HISTORY] Dropping thumbnail for {}: {err}",
+                        metadata.version_id
+                    );
+                    metadata.thumbnail_sha256 = None;
+                }
+            }
+        }
 
         let metadata_destination = game_dir.join(format!("{}.json", metadata.version_id));
         let metadata_json = serde_json::to_string_pretty(&metadata)
             .map_err(|err| HistoryError::Serialization(err.to_string()))?;
-        fs::write(&metadata_destination, metadata_json)
-            .map_err(|err| HistoryError::Io(err.to_string()))?;
+        fs::write(&metadata_destination, metadata_json).map_err(write_error)?;
 
         let entry = HistoryEntry {
-            archive_path: archive_destination.to_string_lossy().to_string(),
-            metadata_path: metadata_destination.to_string_lossy().to_string(),
+            archive_path: archive_destination,
+            metadata_path: metadata_destination,
             metadata,
         };
 
         self.insert_entry(entry.clone())?;
         self.trim_cache_for_game(&entry.metadata.game_id)?;
+        // A version fetched from the cloud is already in sync
+        if entry.metadata.source.as_deref() != Some("cloud") {
+            let _ = self.changes.send(entry.metadata.game_id.clone());
+        }
         info!(
             "[HISTORY] Saved version {} to history",
             entry.metadata.version_id
@@ -221,12 +634,17 @@ impl HistoryManager {
             return Ok(entries.clone());
         }
 
-        let game_dir = self.base_dir.join(&game_id);
+        let game_dir = self.base_dir().join(&game_id);
         if game_dir.exists() {
             let mut history_entries = Self::load_history_entries(&game_dir, &game_id)?;
             let (limit, auto_delete) = self.policy()?;
             if auto_delete {
-                Self::enforce_retention(&mut history_entries, limit)?;
+                Self::enforce_retention(
+                    &mut history_entries,
+                    limit,
+                    &self.age_retention_for(&game_id)?,
+                    unix_now(),
+                )?;
             }
             guard.insert(game_id.clone(), history_entries.clone());
             return Ok(history_entries);
@@ -235,17 +653,69 @@ impl HistoryManager {
         Ok(Vec::new())
     }
 
+    /// Filter, sort and page versions of every game from the in-memory cache
+    pub fn query_history(&self, query: &HistoryQuery) -> Result<HistoryPage, HistoryError> {
+        let search = query
+            .search
+            .as_ref()
+            .map(|search| search.trim().to_lowercase())
+            .filter(|search| !search.is_empty());
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+
+        let mut matches: Vec<HistoryEntry> = {
+            let guard = self
+                .cache
+                .lock()
+                .map_err(|err| HistoryError::Lock(err.to_string()))?;
+            guard
+                .values()
+                .flatten()
+                .filter(|entry| query.matches(&entry.metadata, search.as_deref()))
+                .cloned()
+                .collect()
+        };
+
+        match query.sort {
+            HistorySort::NewestFirst => {
+                matches.sort_by(|a, b| b.metadata.timestamp.cmp(&a.metadata.timestamp))
+            }
+            HistorySort::OldestFirst => {
+                matches.sort_by(|a, b| a.metadata.timestamp.cmp(&b.metadata.timestamp))
+            }
+            HistorySort::GameId => matches.sort_by(|a, b| {
+                a.metadata
+                    .game_id
+                    .cmp(&b.metadata.game_id)
+                    .then(b.metadata.timestamp.cmp(&a.metadata.timestamp))
+            }),
+        }
+
+        let total = matches.len();
+        let entries = matches.into_iter().skip(query.offset).take(limit).collect();
+
+        Ok(HistoryPage {
+            entries,
+            total,
+            offset: query.offset,
+            limit,
+            indexed: self.is_indexed(),
+        })
+    }
+
     pub fn get_games(&self) -> Vec<String> {
-        // Check cache first
+        // Check cache first (only complete once indexing has finished)
         if let Ok(guard) = self.cache.lock() {
-            if !guard.is_empty() {
+            if self.is_indexed() && !guard.is_empty() {
                 return guard.keys().cloned().collect();
             }
         }
 
         // Fallback to disk
         let mut games = Vec::new();
-        if let Ok(entries) = fs::read_dir(&self.base_dir) {
+        if let Ok(entries) = fs::read_dir(self.base_dir()) {
             for entry in entries.flatten() {
                 if let Ok(file_type) = entry.file_type() {
                     if file_type.is_dir() {
@@ -296,34 +766,113 @@ impl HistoryManager {
             .ok_or_else(|| HistoryError::NotFound(format!("{game_id}:{version_id}")))
     }
 
-    pub fn rollback_version(
+    /// `version_id` followed by the versions it was made from, newest first,
+    /// as far as local history knows them. The last one may be a parent that
+    /// is not stored here.
+    pub fn lineage(&self, game_id: &str, version_id: &str) -> Vec<String> {
+        let mut lineage = vec![version_id.to_string()];
+        let Ok(guard) = self.cache.lock() else {
+            return lineage;
+        };
+        let Some(entries) = guard.get(game_id) else {
+            return lineage;
+        };
+
+        let parents: HashMap<&str, &str> = entries
+            .iter()
+            .filter_map(|entry| {
+                let parent = entry.metadata.parent_version_id.as_deref()?;
+                Some((entry.metadata.version_id.as_str(), parent))
+            })
+            .collect();
+        let mut current = version_id;
+        while let Some(&parent) = parents.get(current) {
+            if lineage.iter().any(|seen| seen == parent) {
+                break;
+            }
+            lineage.push(parent.to_string());
+            current = parent;
+        }
+        lineage
+    }
+
+    /// Copy a PNG next to a version's archive and record it in the metadata
+    pub fn attach_thumbnail(
         &self,
         game_id: String,
         version_id: String,
-    ) -> Result<PackagedSave, HistoryError> {
-        let entry = self.get_history_item(game_id.clone(), version_id.clone())?;
-        let archives_root = self.archives_root();
-        fs::create_dir_all(&archives_root).map_err(|err| HistoryError::Io(err.to_string()))?;
+        image_path: &Path,
+    ) -> Result<HistoryEntry, HistoryError> {
+        let mut entry = self.get_history_item(game_id.clone(), version_id.clone())?;
+        let game_dir = self.base_dir().join(&game_id);
 
-        let archive_destination = archives_root.join(format!("{}_{}.zip", game_id, version_id));
-        fs::copy(Path::new(&entry.archive_path), &archive_destination)
-            .map_err(|err| HistoryError::Io(err.to_string()))?;
+        let (thumbnail, sha256) = Self::store_thumbnail(&game_dir, &version_id, image_path)?;
+        entry.metadata.thumbnail = Some(thumbnail);
+        entry.metadata.thumbnail_sha256 = Some(sha256);
 
-        let metadata_destination = archives_root.join(format!("{}_{}.json", game_id, version_id));
-        let metadata_json = serde_json::to_string_pretty(&entry.metadata)
-            .map_err(|err| HistoryError::Serialization(err.to_string()))?;
-        fs::write(&metadata_destination, metadata_json)
-            .map_err(|err| HistoryError::Io(err.to_string()))?;
+        self.persist_entry(&entry)?;
+        info!("[HISTORY] Attached thumbnail to {game_id} version {version_id}");
+        Ok(entry)
+    }
+
+    /// Replace the user's note and tags on a version. A blank note clears it.
+    pub fn update_note(
+        &self,
+        game_id: String,
+        version_id: String,
+        note: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<HistoryEntry, HistoryError> {
+        let note = normalize_note(note)?;
+
+        let mut normalized_tags: Vec<String> = Vec::new();
+        for tag in tags {
+            let tag = tag.trim();
+            if tag.is_empty()
+                || normalized_tags
+                    .iter()
+                    .any(|existing| existing.eq_ignore_ascii_case(tag))
+            {
+                continue;
+            }
+            if tag.chars().count() > MAX_TAG_CHARS {
+                return Err(HistoryError::InvalidInput(format!(
+                    "tag exceeds {MAX_TAG_CHARS} characters: {tag}"
+                )));
+            }
+            normalized_tags.push(tag.to_string());
+        }
+        if normalized_tags.len() > MAX_TAGS {
+            return Err(HistoryError::InvalidInput(format!(
+                "at most {MAX_TAGS} tags are allowed"
+            )));
+        }
+
+        let mut entry = self.get_history_item(game_id.clone(), version_id.clone())?;
+        entry.metadata.note = note;
+        entry.metadata.tags = normalized_tags;
 
+        self.persist_entry(&entry)?;
+        info!("[HISTORY] Updated note for {game_id} version {version_id}");
+        Ok(entry)
+    }
+
+    /// Pin or unpin a version so retention keeps it
+    pub fn set_pinned(
+        &self,
+        game_id: String,
+        version_id: String,
+        pinned: bool,
+    ) -> Result<HistoryEntry, HistoryError> {
+        let mut entry = self.get_history_item(game_id.clone(), version_id.clone())?;
+        entry.metadata.pinned = pinned;
+
+        self.persist_entry(&entry)?;
         info!(
-            "[HISTORY] Rolled back {game_id} to version {}",
-            entry.metadata.version_id
+            "[HISTORY] {} {game_id} version {version_id}",
+            if pinned { "Pinned" } else { "Unpinned" }
         );
-
-        Ok(PackagedSave {
-            archive_path: archive_destination.to_string_lossy().to_string(),
-            metadata: entry.metadata,
-        })
+        Ok(entry)
     }
 
     pub fn delete_history_item(
@@ -354,11 +903,12 @@ impl HistoryManager {
     }
 
     pub fn clear_all(&self) -> Result<(), HistoryError> {
-        if self.base_dir.exists() {
-            fs::remove_dir_all(&self.base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
+        let base_dir = self.base_dir();
+        if base_dir.exists() {
+            fs::remove_dir_all(&base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
         }
 
-        fs::create_dir_all(&self.base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
+        fs::create_dir_all(&base_dir).map_err(|err| HistoryError::Io(err.to_string()))?;
         let mut guard = self
             .cache
             .lock()
@@ -369,8 +919,127 @@ impl HistoryManager {
         Ok(())
     }
 
+    /// Move the whole history to `stash` and take over the one kept at
+    /// `restore`, starting empty if there is none. Returns the number of
+    /// games indexed from the restored history.
+    pub fn swap_directory(&self, stash: &Path, restore: &Path) -> Result<usize, HistoryError> {
+        if stash.exists() {
+            return Err(HistoryError::InvalidInput(format!(
+                "{} already exists",
+                stash.display()
+            )));
+        }
+
+        {
+            // Held throughout so no version is written mid-swap
+            let mut guard = self
+                .cache
+                .lock()
+                .map_err(|err| HistoryError::Lock(err.to_string()))?;
+            let base_dir = self.base_dir();
+            if let Some(parent) = stash.parent() {
+                fs::create_dir_all(parent).map_err(write_error)?;
+            }
+            fs::rename(&base_dir, stash).map_err(write_error)?;
+            let restored = if restore.is_dir() {
+                fs::rename(restore, &base_dir)
+            } else {
+                fs::create_dir_all(&base_dir)
+            };
+            if let Err(err) = restored {
+                // Put the original history back rather than leave none
+                let _ = fs::rename(stash, &base_dir);
+                return Err(write_error(err));
+            }
+            guard.clear();
+        }
+
+        info!(
+            "[HISTORY] Stashed history in {:?}, restored {:?}",
+            stash, restore
+        );
+        self.index_with_progress(|_, _, _| {})
+    }
+
+    /// Move the history to `new_dir`, which must not exist yet or be
+    /// empty. Every file is copied and compared with its original before
+    /// the old directory is deleted; a failed copy leaves the history where
+    /// it was. Returns the number of games indexed at the new location.
+    pub fn relocate(&self, new_dir: &Path) -> Result<usize, HistoryError> {
+        if is_content_uri(new_dir) || !new_dir.is_absolute() {
+            return Err(HistoryError::InvalidInput(format!(
+                "{} is not a local folder",
+                new_dir.display()
+            )));
+        }
+        let old_dir = self.base_dir();
+        if new_dir.starts_with(&old_dir) || old_dir.starts_with(new_dir) {
+            return Err(HistoryError::InvalidInput(format!(
+                "{} overlaps the current history folder",
+                new_dir.display()
+            )));
+        }
+        let existed = new_dir.exists();
+        if existed
+            && fs::read_dir(new_dir)
+                .map_err(|err| HistoryError::Io(err.to_string()))?
+                .next()
+                .is_some()
+        {
+            return Err(HistoryError::InvalidInput(format!(
+                "{} is not empty",
+                new_dir.display()
+            )));
+        }
+
+        {
+            // Held throughout so no version is written mid-move
+            let mut guard = self
+                .cache
+                .lock()
+                .map_err(|err| HistoryError::Lock(err.to_string()))?;
+            let copied = copy_verified(&old_dir, new_dir)
+                .and_then(|()| rebase_thumbnails(new_dir, &old_dir));
+            if let Err(err) = copied {
+                let _ = fs::remove_dir_all(new_dir);
+                if existed {
+                    let _ = fs::create_dir_all(new_dir);
+                }
+                return Err(write_error(err));
+            }
+
+            *self
+                .base_dir
+                .write()
+                .map_err(|err| HistoryError::Lock(err.to_string()))? = new_dir.to_path_buf();
+            guard.clear();
+            if let Err(err) = fs::remove_dir_all(&old_dir) {
+                warn!("[HISTORY] Moved history but failed to delete {old_dir:?}: {err}");
+            }
+        }
+
+        info!(
+            "[HISTORY] Moved history from {:?} to {:?}",
+            old_dir, new_dir
+        );
+        self.index_with_progress(|_, _, _| {})
+    }
+
+    /// Directory holding one folder of versions per game
+    pub fn base_dir(&self) -> PathBuf {
+        self.base_dir
+            .read()
+            .map(|dir| dir.clone())
+            .unwrap_or_else(|err| err.into_inner().clone())
+    }
+
     pub fn total_size(&self) -> Result<u64, HistoryError> {
-        calculate_dir_size(&self.base_dir)
+        calculate_dir_size(&self.base_dir())
+    }
+
+    /// Bytes of archives, metadata and thumbnails kept for `game_id`
+    pub fn game_size(&self, game_id: &str) -> Result<u64, HistoryError> {
+        calculate_dir_size(&self.base_dir().join(game_id))
     }
 
     fn load_history_entries(
@@ -401,7 +1070,15 @@ impl HistoryManager {
             }
 
             match Self::load_entry(&file_path, game_id) {
-                Ok(entry) => history_entries.push(entry),
+                Ok(mut entry) => {
+                    if let Err(err) = Self::migrate_entry(&mut entry) {
+                        warn!(
+                            "[HISTORY] Failed to migrate {:?}, keeping it as is: {err}",
+                            file_path
+                        );
+                    }
+                    history_entries.push(entry);
+                }
                 Err(err) => warn!("[HISTORY] Skipping malformed history entry: {err}"),
             }
         }
@@ -432,12 +1109,70 @@ impl HistoryManager {
         }
 
         Ok(HistoryEntry {
-            archive_path: archive_path.to_string_lossy().to_string(),
-            metadata_path: path.to_string_lossy().to_string(),
+            archive_path,
+            metadata_path: path.to_path_buf(),
             metadata,
         })
     }
 
+    /// Bring metadata written by an older version up to
+    /// `METADATA_SCHEMA_VERSION` and rewrite its file, so each migration
+    /// runs once. Metadata from a newer version is left untouched.
+    fn migrate_entry(entry: &mut HistoryEntry) -> Result<(), HistoryError> {
+        let from = entry.metadata.schema_version;
+        if from >= METADATA_SCHEMA_VERSION {
+            if from > METADATA_SCHEMA_VERSION {
+                warn!(
+                    "[HISTORY] {:?} has schema version {from}, newer than this app's {}",
+                    entry.metadata_path, METADATA_SCHEMA_VERSION
+                );
+            }
+            return Ok(());
+        }
+
+        let mut migrated = entry.clone();
+        for migration in &MIGRATIONS[from as usize..] {
+            migration(&mut migrated)?;
+            migrated.metadata.schema_version += 1;
+        }
+        let metadata_json = serde_json::to_string_pretty(&migrated.metadata)
+            .map_err(|err| HistoryError::Serialization(err.to_string()))?;
+        fs::write(&migrated.metadata_path, metadata_json).map_err(write_error)?;
+
+        info!(
+            "[HISTORY] Migrated {} from schema version {from} to {}",
+            migrated.metadata.version_id, METADATA_SCHEMA_VERSION
+        );
+        *entry = migrated;
+        Ok(())
+    }
+
+    /// Copy a validated PNG to `<version_id>.png`, returning its path and hash
+    fn store_thumbnail(
+        game_dir: &Path,
+        version_id: &str,
+        source: &Path,
+    ) -> Result<(String, String), HistoryError> {
+        validate_thumbnail(source).map_err(HistoryError::InvalidInput)?;
+
+        let destination = game_dir.join(format!("{version_id}.png"));
+        if source != destination {
+            fs::copy(source, &destination).map_err(write_error)?;
+        }
+        let sha256 =
+            thumbnail_sha256(&destination).map_err(|err| HistoryError::Io(err.to_string()))?;
+
+        Ok((destination.to_string_lossy().to_string(), sha256))
+    }
+
+    /// Rewrite an entry's metadata file and refresh the cache
+    fn persist_entry(&self, entry: &HistoryEntry) -> Result<(), HistoryError> {
+        let metadata_json = serde_json::to_string_pretty(&entry.metadata)
+            .map_err(|err| HistoryError::Serialization(err.to_string()))?;
+        fs::write(&entry.metadata_path, metadata_json).map_err(write_error)?;
+        self.insert_entry(entry.clone())
+    }
+
     fn insert_entry(&self, entry: HistoryEntry) -> Result<(), HistoryError> {
         let mut guard = self
             .cache
@@ -461,7 +1196,8 @@ impl HistoryManager {
             .lock()
             .map_err(|err| HistoryError::Lock(err.to_string()))?;
         if let Some(entries) = guard.get_mut(game_id) {
-            Self::enforce_retention(entries, limit)?;
+            let age = self.age_retention_for(game_id)?;
+            Self::enforce_retention(entries, limit, &age, unix_now())?;
         }
 
         Ok(())
@@ -478,28 +1214,76 @@ impl HistoryManager {
             .lock()
             .map_err(|err| HistoryError::Lock(err.to_string()))?;
 
-        for entries in guard.values_mut() {
-            Self::enforce_retention(entries, limit)?;
+        let now = unix_now();
+        for (game_id, entries) in guard.iter_mut() {
+            let age = self.age_retention_for(game_id)?;
+            Self::enforce_retention(entries, limit, &age, now)?;
         }
 
         Ok(())
     }
 
+    /// Trim `entries` to `limit` versions, then prune them by `age` as of
+    /// `now` in Unix seconds
     fn enforce_retention(
         entries: &mut Vec<HistoryEntry>,
         limit: usize,
+        age: &AgeRetention,
+        now: u64,
     ) -> Result<(), HistoryError> {
+        // Entries are newest first; pinned ones count toward the limit but
+        // are never removed
         while entries.len() > limit {
-            if let Some(removed) = entries.pop() {
-                warn!(
-                    "[HISTORY] Removing oldest history entry {} for {}",
-                    removed.metadata.version_id, removed.metadata.game_id
-                );
-                if let Err(err) = Self::remove_files(&removed) {
-                    warn!("[HISTORY] Failed to remove trimmed entry: {err}");
-                }
-            } else {
+            let Some(oldest_unpinned) = entries.iter().rposition(|entry| !entry.metadata.pinned)
+            else {
                 break;
+            };
+
+            let removed = entries.remove(oldest_unpinned);
+            warn!(
+                "[HISTORY] Removing oldest history entry {} for {}",
+                removed.metadata.version_id, removed.metadata.game_id
+            );
+            if let Err(err) = Self::remove_files(&removed) {
+                warn!("[HISTORY] Failed to remove trimmed entry: {err}");
+            }
+        }
+
+        // The latest version stays at any age, so a game left alone for a
+        // while keeps its saves
+        let cutoff = age
+            .max_age_days
+            .map(|days| now.saturating_sub(days.saturating_mul(SECS_PER_DAY)));
+        let mut buckets: HashSet<(u8, u64)> = entries
+            .first()
+            .map(|latest| thinning_bucket(latest.metadata.timestamp, now))
+            .into_iter()
+            .collect();
+        let mut index = 1;
+        while index < entries.len() {
+            let entry = &entries[index];
+            if entry.metadata.pinned {
+                index += 1;
+                continue;
+            }
+            let expired = cutoff.is_some_and(|cutoff| entry.metadata.timestamp < cutoff);
+            // Entries are newest first, so each bucket keeps its newest
+            let thinned =
+                age.thin && !buckets.insert(thinning_bucket(entry.metadata.timestamp, now));
+            if !expired && !thinned {
+                index += 1;
+                continue;
+            }
+
+            let removed = entries.remove(index);
+            info!(
+                "[HISTORY] Removing {} history entry {} for {}",
+                if expired { "expired" } else { "thinned" },
+                removed.metadata.version_id,
+                removed.metadata.game_id
+            );
+            if let Err(err) = Self::remove_files(&removed) {
+                warn!("[HISTORY] Failed to remove pruned entry: {err}");
             }
         }
 
@@ -510,26 +1294,192 @@ impl HistoryManager {
         if let Err(err) = fs::remove_file(&entry.archive_path) {
             warn!(
                 "[HISTORY] Failed to delete archive {}: {}",
-                &entry.archive_path, err
+                entry.archive_path.display(),
+                err
             );
         }
 
         if let Err(err) = fs::remove_file(&entry.metadata_path) {
             warn!(
                 "[HISTORY] Failed to delete metadata {}: {}",
-                &entry.metadata_path, err
+                entry.metadata_path.display(),
+                err
             );
         }
 
+        if let Some(thumbnail) = &entry.metadata.thumbnail {
+            if let Err(err) = fs::remove_file(thumbnail) {
+                warn!(
+                    "[HISTORY] Failed to delete thumbnail {}: {}",
+                    thumbnail, err
+                );
+            }
+        }
+
         Ok(())
     }
+}
+
+/// Which of thinning's buckets a version from `timestamp` falls in: its
+/// hour within the last day, its day within the last 30 days, otherwise
+/// its week
+fn thinning_bucket(timestamp: u64, now: u64) -> (u8, u64) {
+    let age = now.saturating_sub(timestamp);
+    if age < SECS_PER_DAY {
+        (0, timestamp / SECS_PER_HOUR)
+    } else if age < DAILY_THINNING_DAYS * SECS_PER_DAY {
+        (1, timestamp / SECS_PER_DAY)
+    } else {
+        (2, timestamp / (7 * SECS_PER_DAY))
+    }
+}
 
-    fn archives_root(&self) -> PathBuf {
-        self.base_dir
-            .parent()
-            .map(Path::to_path_buf)
-            .unwrap_or_else(|| self.base_dir.clone())
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Trim a version note, dropping it when blank. Fails when it is longer
+/// than the cloud server accepts.
+pub fn normalize_note(note: Option<String>) -> Result<Option<String>, HistoryError> {
+    let note = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    if note
+        .as_ref()
+        .is_some_and(|note| note.chars().count() > MAX_NOTE_CHARS)
+    {
+        return Err(HistoryError::InvalidInput(format!(
+            "note exceeds {MAX_NOTE_CHARS} characters"
+        )));
+    }
+    Ok(note)
+}
+
+/// Copy the tree under `source` to `target`, reading each copy back and
+/// comparing its hash with the original's
+fn copy_verified(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let destination = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_verified(&entry.path(), &destination)?;
+            continue;
+        }
+        fs::copy(entry.path(), &destination)?;
+        let original = file_sha256(&entry.path()).map_err(io::Error::other)?;
+        if file_sha256(&destination).map_err(io::Error::other)? != original {
+            return Err(io::Error::other(format!(
+                "copy of {} does not match the original",
+                entry.path().display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Point thumbnail paths recorded under `old_dir` at the moved files in
+/// `new_dir`
+fn rebase_thumbnails(new_dir: &Path, old_dir: &Path) -> io::Result<()> {
+    for game_dir in fs::read_dir(new_dir)? {
+        let game_dir = game_dir?.path();
+        if !game_dir.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(&game_dir)? {
+            let path = file?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(mut metadata) =
+                serde_json::from_str::<SaveMetadata>(&fs::read_to_string(&path)?)
+            else {
+                continue;
+            };
+            let Some(relative) = metadata
+                .thumbnail
+                .as_deref()
+                .and_then(|thumbnail| Path::new(thumbnail).strip_prefix(old_dir).ok())
+            else {
+                continue;
+            };
+            metadata.thumbnail = Some(new_dir.join(relative).to_string_lossy().to_string());
+            fs::write(&path, serde_json::to_string_pretty(&metadata)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Point `duplicate` at the same file as `original`, returning the bytes
+/// freed, or `None` when they already share a file or differ in size
+fn link_archive(original: &Path, duplicate: &Path) -> io::Result<Option<u64>> {
+    if same_file(original, duplicate)? {
+        return Ok(None);
+    }
+
+    let size = fs::metadata(duplicate)?.len();
+    if fs::metadata(original)?.len() != size {
+        return Ok(None);
+    }
+
+    // Link next to the duplicate first so a failure never leaves it missing
+    let staged = duplicate.with_extension("zip.link");
+    let _ = fs::remove_file(&staged);
+    fs::hard_link(original, &staged)?;
+    if let Err(err) = fs::rename(&staged, duplicate) {
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+
+    Ok(Some(size))
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let (a, b) = (fs::metadata(a)?, fs::metadata(b)?);
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+/// File identity is not exposed here; relinking an existing link is harmless
+#[cfg(not(unix))]
+fn same_file(_a: &Path, _b: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// Schema 0 to 1: record the archive's size and SHA-256, which metadata
+/// written before they were added lacks
+fn fill_archive_checksums(entry: &mut HistoryEntry) -> Result<(), HistoryError> {
+    if entry.metadata.size_bytes.is_none() {
+        let size = fs::metadata(&entry.archive_path)
+            .map_err(|err| HistoryError::Io(err.to_string()))?
+            .len();
+        entry.metadata.size_bytes = Some(size);
+    }
+    if entry.metadata.sha256.is_none() {
+        let sha256 =
+            file_sha256(&entry.archive_path).map_err(|err| HistoryError::Io(err.to_string()))?;
+        entry.metadata.sha256 = Some(sha256);
     }
+    Ok(())
+}
+
+/// Bytes an entry's archive, metadata and thumbnail take on disk
+fn entry_disk_size(entry: &HistoryEntry) -> u64 {
+    [
+        Some(entry.archive_path.as_path()),
+        Some(entry.metadata_path.as_path()),
+        entry.metadata.thumbnail.as_deref().map(Path::new),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|path| fs::metadata(path).ok())
+    .map(|metadata| metadata.len())
+    .sum()
 }
 
 fn calculate_dir_size(path: &Path) -> Result<u64, HistoryError> {
@@ -563,3 +1513,217 @@ fn calculate_dir_size(path: &Path) -> Result<u64, HistoryError> {
 //   "file_list": ["save.srm"],
 //   "hash": "a1b2c3d4"
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Entries newest first, as the cache keeps them. The paths do not
+    /// exist, so removing an entry only logs.
+    fn entries(pinned: &[bool]) -> Vec<HistoryEntry> {
+        let count = pinned.len();
+        pinned
+            .iter()
+            .enumerate()
+            .map(|(index, &pinned)| {
+                let mut metadata: SaveMetadata = serde_json::from_value(serde_json::json!({
+                    "game_id": "game",
+                    "emulator_id": "emu",
+                    "timestamp": (count - index) as u64,
+                    "version_id": format!("v{index}"),
+                    "file_list": ["save.srm"],
+                    "hash": format!("hash-{index}"),
+                }))
+                .expect("metadata");
+                metadata.pinned = pinned;
+                HistoryEntry {
+                    archive_path: PathBuf::from(format!("/nonexistent/crosssave/v{index}.zip")),
+                    metadata_path: PathBuf::from(format!("/nonexistent/crosssave/v{index}.json")),
+                    metadata,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn legacy_entries_are_migrated_once_on_load() {
+        assert_eq!(MIGRATIONS.len(), METADATA_SCHEMA_VERSION as usize);
+
+        let base_dir =
+            std::env::temp_dir().join(format!("crosssave-history-{}", uuid::Uuid::new_v4()));
+        let game_dir = base_dir.join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        fs::write(game_dir.join("v1.zip"), b"archive").unwrap();
+        let legacy = serde_json::json!({
+            "game_id": "game",
+            "emulator_id": "emu",
+            "timestamp": 1,
+            "version_id": "v1",
+            "file_list": ["save.srm"],
+            "hash": "legacy",
+        });
+        fs::write(game_dir.join("v1.json"), legacy.to_string()).unwrap();
+
+        let manager = HistoryManager::init(base_dir.clone(), 10, false).unwrap();
+        let entry = manager.get_latest_version("game").unwrap();
+        assert_eq!(entry.metadata.schema_version, METADATA_SCHEMA_VERSION);
+        assert_eq!(entry.metadata.size_bytes, Some(7));
+        let expected = file_sha256(&game_dir.join("v1.zip")).unwrap();
+        assert_eq!(entry.metadata.sha256.as_deref(), Some(expected.as_str()));
+
+        let on_disk: SaveMetadata =
+            serde_json::from_str(&fs::read_to_string(game_dir.join("v1.json")).unwrap()).unwrap();
+        assert_eq!(on_disk.schema_version, METADATA_SCHEMA_VERSION);
+        assert_eq!(on_disk.hash, "legacy");
+
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+
+    #[test]
+    fn relocating_moves_every_version() {
+        let root = std::env::temp_dir().join(format!("crosssave-history-{}", uuid::Uuid::new_v4()));
+        let game_dir = root.join("old").join("game");
+        fs::create_dir_all(&game_dir).unwrap();
+        fs::write(game_dir.join("v1.zip"), b"archive").unwrap();
+        fs::write(game_dir.join("v1.png"), b"png").unwrap();
+        let metadata = serde_json::json!({
+            "schema_version": METADATA_SCHEMA_VERSION,
+            "game_id": "game",
+            "emulator_id": "emu",
+            "timestamp": 1,
+            "version_id": "v1",
+            "file_list": ["save.srm"],
+            "hash": "hash",
+            "thumbnail": game_dir.join("v1.png"),
+        });
+        fs::write(game_dir.join("v1.json"), metadata.to_string()).unwrap();
+
+        let manager = HistoryManager::init(root.join("old"), 10, false).unwrap();
+        assert!(manager.relocate(&root.join("old").join("nested")).is_err());
+        assert_eq!(manager.relocate(&root.join("new")).unwrap(), 1);
+
+        assert_eq!(manager.base_dir(), root.join("new"));
+        assert!(!root.join("old").exists());
+        let entry = manager.get_latest_version("game").unwrap();
+        assert_eq!(
+            entry.archive_path,
+            root.join("new").join("game").join("v1.zip")
+        );
+        let thumbnail = root.join("new").join("game").join("v1.png");
+        assert_eq!(
+            entry.metadata.thumbnail.as_deref(),
+            Some(thumbnail.to_string_lossy().as_ref())
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn age_limits_and_thinning_keep_latest_and_pinned() {
+        const NOW: u64 = 400 * SECS_PER_DAY + 30 * 60;
+        let ages = [
+            0,
+            10 * 60,
+            20 * 60,
+            2 * SECS_PER_HOUR,
+            3 * SECS_PER_DAY,
+            3 * SECS_PER_DAY + 60,
+            60 * SECS_PER_DAY,
+            200 * SECS_PER_DAY,
+        ];
+        let with_ages = |pinned: &[bool]| {
+            let mut entries = entries(pinned);
+            for (entry, age) in entries.iter_mut().zip(ages) {
+                entry.metadata.timestamp = NOW - age;
+            }
+            entries
+        };
+        let ids = |entries: &[HistoryEntry]| {
+            entries
+                .iter()
+                .map(|entry| entry.metadata.version_id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut kept = with_ages(&[false; 8]);
+        let expiring = AgeRetention {
+            max_age_days: Some(30),
+            thin: false,
+        };
+        HistoryManager::enforce_retention(&mut kept, 100, &expiring, NOW).unwrap();
+        assert_eq!(ids(&kept), ["v0", "v1", "v2", "v3", "v4", "v5"]);
+
+        let mut kept = with_ages(&[false, false, false, false, false, true, false, false]);
+        let thinning = AgeRetention {
+            max_age_days: None,
+            thin: true,
+        };
+        HistoryManager::enforce_retention(&mut kept, 100, &thinning, NOW).unwrap();
+        assert_eq!(ids(&kept), ["v0", "v3", "v4", "v5", "v6", "v7"]);
+
+        // A latest version past the limit stays
+        let mut kept = with_ages(&[false; 8]);
+        for entry in &mut kept {
+            entry.metadata.timestamp -= 100 * SECS_PER_DAY;
+        }
+        HistoryManager::enforce_retention(&mut kept, 100, &expiring, NOW).unwrap();
+        assert_eq!(ids(&kept), ["v0"]);
+    }
+
+    proptest! {
+        #[test]
+        fn retention_never_removes_pinned_versions(
+            pinned in proptest::collection::vec(any::<bool>(), 0..24),
+            limit in 1usize..12,
+        ) {
+            let mut kept = entries(&pinned);
+            let before = kept.clone();
+            HistoryManager::enforce_retention(&mut kept, limit, &AgeRetention::default(), 0)
+                .expect("retention");
+
+            let pinned_count = pinned.iter().filter(|&&pinned| pinned).count();
+            prop_assert!(kept.len() <= limit.max(pinned_count));
+            prop_assert_eq!(kept.len(), before.len().min(limit.max(pinned_count)));
+
+            let kept_ids: Vec<&str> = kept
+                .iter()
+                .map(|entry| entry.metadata.version_id.as_str())
+                .collect();
+            for entry in &before {
+                if entry.metadata.pinned {
+                    prop_assert!(kept_ids.contains(&entry.metadata.version_id.as_str()));
+                }
+            }
+        }
+
+        /// Only the oldest unpinned versions go, and order is kept
+        #[test]
+        fn retention_removes_oldest_unpinned_first(
+            pinned in proptest::collection::vec(any::<bool>(), 0..24),
+            limit in 1usize..12,
+        ) {
+            let mut kept = entries(&pinned);
+            let before = kept.clone();
+            HistoryManager::enforce_retention(&mut kept, limit, &AgeRetention::default(), 0)
+                .expect("retention");
+
+            let oldest_kept_unpinned = kept
+                .iter()
+                .filter(|entry| !entry.metadata.pinned)
+                .map(|entry| entry.metadata.timestamp)
+                .min();
+            for entry in &before {
+                let removed = !kept
+                    .iter()
+                    .any(|kept| kept.metadata.version_id == entry.metadata.version_id);
+                if let (true, Some(oldest)) = (removed, oldest_kept_unpinned) {
+                    prop_assert!(entry.metadata.timestamp < oldest);
+                }
+            }
+            prop_assert!(kept
+                .windows(2)
+                .all(|pair| pair[0].metadata.timestamp > pair[1].metadata.timestamp));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Backends presign URLs for five minutes. A cached listing holding any is
+/// fetched again before they run out instead of being revalidated.
+const PRESIGNED_LIFETIME: Duration = Duration::from_secs(4 * 60);
+/// Listings kept per cache; the oldest is dropped to make room
+const MAX_ENTRIES: usize = 512;
+
+/// Parsed listing responses kept with the ETag they came with, so a listing
+/// that has not changed costs a 304 and no JSON parsing
+pub struct ListingCache<T> {
+    entries: Mutex<HashMap<String, CachedListing<T>>>,
+}
+
+struct CachedListing<T> {
+    etag: String,
+    value: T,
+    stored_at: Instant,
+    /// The listing carries presigned URLs, which expire
+    presigned: bool,
+}
+
+impl<T> Default for ListingCache<T> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> ListingCache<T> {
+    /// The ETag to revalidate `key` with, and the listing it stands for
+    pub fn lookup(&self, key: &str) -> Option<(String, T)> {
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.get(key)?;
+        if entry.presigned && entry.stored_at.elapsed() >= PRESIGNED_LIFETIME {
+            return None;
+        }
+        Some((entry.etag.clone(), entry.value.clone()))
+    }
+
+    pub fn store(&self, key: String, etag: String, value: T, presigned: bool) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CachedListing {
+                etag,
+                value,
+                stored_at: Instant::now(),
+                presigned,
+            },
+        );
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
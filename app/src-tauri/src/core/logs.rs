@@ -0,0 +1,333 @@
+//! Log files under `app_data_dir/logs`, rotated by size, and the
+//! diagnostics bundle attached to bug reports.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::Level;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::core::crash::log_tail;
+use crate::core::settings::AppSettings;
+
+const LOG_FILE_NAME: &str = "crosssave.log";
+/// The current file is rotated once it grows past this
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+/// Rotated files kept besides the current one: `crosssave.1.log` is the
+/// newest, `crosssave.4.log` the oldest
+const ROTATED_LOG_FILES: usize = 4;
+/// Settings fields replaced before settings leave the device
+const SECRET_KEYS: &[&str] = &["api_key", "access_key", "password", "token"];
+const REDACTED: &str = "<redacted>";
+/// Shorter secrets aren't scrubbed from bundled files, where they would
+/// match ordinary words
+const MIN_SCRUBBED_SECRET: usize = 8;
+
+/// One line of a log file
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct LogLine {
+    /// `ERROR` through `TRACE`
+    pub level: String,
+    pub text: String,
+}
+
+struct OpenLog {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+/// Appends every event the tracing subscriber writes, once `open` was
+/// called. Never logs itself, since it runs inside the subscriber.
+#[derive(Default)]
+pub struct LogFiles {
+    open: Mutex<Option<OpenLog>>,
+}
+
+static LOG_FILES: OnceLock<LogFiles> = OnceLock::new();
+
+pub fn log_files() -> &'static LogFiles {
+    LOG_FILES.get_or_init(LogFiles::default)
+}
+
+impl LogFiles {
+    /// Start writing to `dir`. Lines logged before this, while the app data
+    /// directory was not known yet, are copied over from the crash tail.
+    pub fn open(&self, dir: PathBuf) -> io::Result<()> {
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(LOG_FILE_NAME);
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        for line in log_tail().snapshot() {
+            writeln!(file, "{line}")?;
+        }
+        let size = file.metadata()?.len();
+
+        let mut open = self
+            .open
+            .lock()
+            .map_err(|_| io::Error::other("log file lock poisoned"))?;
+        *open = Some(OpenLog { dir, file, size });
+        Ok(())
+    }
+
+    /// Directory the logs are written to, once opened
+    pub fn dir(&self) -> Option<PathBuf> {
+        let open = self.open.lock().ok()?;
+        open.as_ref().map(|open| open.dir.clone())
+    }
+
+    /// Errors are dropped: there is nowhere left to report them
+    pub fn append(&self, text: &str) {
+        let Ok(mut guard) = self.open.try_lock() else {
+            return;
+        };
+        let Some(open) = guard.as_mut() else {
+            return;
+        };
+        if open.file.write_all(text.as_bytes()).is_ok() {
+            open.size += text.len() as u64;
+        }
+        if open.size > MAX_LOG_BYTES {
+            if let Ok(file) = rotate(&open.dir) {
+                open.file = file;
+                open.size = 0;
+            }
+        }
+    }
+
+    /// Log files oldest first, so their lines read in order
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.dir().map(|dir| log_paths(&dir)).unwrap_or_default()
+    }
+
+    /// The last `limit` lines at `max_level` or more severe
+    pub fn recent(&self, max_level: Level, limit: usize) -> Vec<LogLine> {
+        recent_lines(&self.paths(), max_level, limit)
+    }
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("crosssave.{index}.log"))
+}
+
+/// Shift `crosssave.log` into `crosssave.1.log` and so on, dropping the
+/// oldest, and start an empty current file
+fn rotate(dir: &Path) -> io::Result<File> {
+    let _ = fs::remove_file(rotated_path(dir, ROTATED_LOG_FILES));
+    for index in (1..ROTATED_LOG_FILES).rev() {
+        let from = rotated_path(dir, index);
+        if from.exists() {
+            fs::rename(&from, rotated_path(dir, index + 1))?;
+        }
+    }
+    fs::rename(dir.join(LOG_FILE_NAME), rotated_path(dir, 1))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE_NAME))
+}
+
+fn log_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = (1..=ROTATED_LOG_FILES)
+        .rev()
+        .map(|index| rotated_path(dir, index))
+        .collect();
+    paths.push(dir.join(LOG_FILE_NAME));
+    paths.into_iter().filter(|path| path.is_file()).collect()
+}
+
+/// Level of a line written by `tracing_subscriber::fmt`, which puts it
+/// right after the timestamp
+fn line_level(line: &str) -> Option<Level> {
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn recent_lines(paths: &[PathBuf], max_level: Level, limit: usize) -> Vec<LogLine> {
+    if limit == 0 {
+        return Vec::new();
+    }
+    let mut lines: VecDeque<LogLine> = VecDeque::with_capacity(limit.min(1024));
+    for path in paths {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        // Continuation lines of a multi-line event share its level
+        let mut level = Level::INFO;
+        for text in content.lines().filter(|text| !text.trim().is_empty()) {
+            if let Some(parsed) = line_level(text) {
+                level = parsed;
+            }
+            // Less severe levels compare greater
+            if level > max_level {
+                continue;
+            }
+            if lines.len() == limit {
+                lines.pop_front();
+            }
+            lines.push_back(LogLine {
+                level: level.to_string(),
+                text: text.to_string(),
+            });
+        }
+    }
+    lines.into()
+}
+
+/// Settings as JSON with keys, passwords and tokens replaced
+pub fn sanitized_settings(settings: &AppSettings) -> Value {
+    let mut value = serde_json::to_value(settings).unwrap_or(Value::Null);
+    redact(&mut value);
+    value
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                let secret = SECRET_KEYS.iter().any(|secret| key.contains(secret));
+                if secret && field.as_str().is_some_and(|text| !text.is_empty()) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Zip the log files under `logs/` and each of `files` by its name, with
+/// every occurrence of `secrets` replaced
+pub fn write_bundle(
+    destination: &Path,
+    logs: &[PathBuf],
+    files: &[(String, Vec<u8>)],
+    secrets: &[String],
+) -> io::Result<()> {
+    let mut zip = ZipWriter::new(File::create(destination)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for path in logs {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        zip.start_file(format!("logs/{name}"), options)
+            .map_err(io::Error::other)?;
+        zip.write_all(scrub(&fs::read(path)?, secrets).as_bytes())?;
+    }
+    for (name, content) in files {
+        zip.start_file(name.as_str(), options)
+            .map_err(io::Error::other)?;
+        zip.write_all(scrub(content, secrets).as_bytes())?;
+    }
+    zip.finish().map_err(io::Error::other)?;
+    Ok(())
+}
+
+fn scrub(content: &[u8], secrets: &[String]) -> String {
+    let mut text = String::from_utf8_lossy(content).into_owned();
+    for secret in secrets {
+        if secret.len() >= MIN_SCRUBBED_SECRET && text.contains(secret.as_str()) {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crosssave_logs_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create dir");
+        dir
+    }
+
+    #[test]
+    fn recent_lines_filter_by_level_and_keep_the_newest() {
+        let dir = temp_dir("recent");
+        let older = dir.join("crosssave.1.log");
+        let current = dir.join(LOG_FILE_NAME);
+        fs::write(
+            &older,
+            "2026-01-01T00:00:00Z ERROR app: first failure\n  caused by: disk\n",
+        )
+        .expect("write rotated");
+        fs::write(
+            &current,
+            "2026-01-01T00:01:00Z DEBUG app: noise\n2026-01-01T00:02:00Z  WARN app: slow\n",
+        )
+        .expect("write current");
+        let paths = log_paths(&dir);
+        assert_eq!(paths, vec![older, current]);
+
+        let warnings = recent_lines(&paths, Level::WARN, 10);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|line| line.level.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ERROR", "ERROR", "WARN"]
+        );
+        assert_eq!(recent_lines(&paths, Level::TRACE, 1)[0].level, "WARN");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotation_shifts_files_and_drops_the_oldest() {
+        let dir = temp_dir("rotate");
+        for index in 1..=ROTATED_LOG_FILES {
+            fs::write(rotated_path(&dir, index), format!("{index}")).expect("write rotated");
+        }
+        fs::write(dir.join(LOG_FILE_NAME), "current").expect("write current");
+
+        rotate(&dir).expect("rotate");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&dir, 1)).expect("read"),
+            "current"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&dir, ROTATED_LOG_FILES)).expect("read"),
+            format!("{}", ROTATED_LOG_FILES - 1)
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(LOG_FILE_NAME)).expect("read"),
+            ""
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let mut settings = AppSettings::default();
+        settings.cloud.api_key = "secret-key".to_string();
+        settings.self_host.access_key = "secret-access".to_string();
+        settings.cloud.device_name = "Deck".to_string();
+
+        let json = sanitized_settings(&settings).to_string();
+        assert!(!json.contains("secret-key"));
+        assert!(!json.contains("secret-access"));
+        assert!(json.contains("Deck"));
+    }
+
+    #[test]
+    fn secrets_are_scrubbed_from_bundled_text() {
+        let secrets = vec!["secret-token-1".to_string(), "short".to_string()];
+        let scrubbed = scrub(
+            b"GET /saves?token=secret-token-1 failed: short read",
+            &secrets,
+        );
+        assert_eq!(scrubbed, "GET /saves?token=<redacted> failed: short read");
+    }
+}
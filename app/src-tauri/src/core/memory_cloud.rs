@@ -0,0 +1,451 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::time::sleep;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use crate::core::cloud::{
+    calculate_sha256, CloudBackend, CloudDevice, CloudError, CloudVersionSummary,
+    DownloadUrlResponse, UploadRequest, UploadUrlResponse, MAX_CLOUD_FILE_BYTES,
+};
+use crate::core::extract::read_entry;
+use crate::core::packager::SaveMetadata;
+
+const LOG_TAG: &str = "[CLOUD_DEMO]";
+
+/// Latency and failures injected into every backend call
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FaultConfig {
+    pub latency: Duration,
+    /// Share of calls, 0.0 to 1.0, that fail with a network error
+    pub failure_rate: f64,
+}
+
+struct StoredVersion {
+    game_id: String,
+    emulator_id: Option<String>,
+    summary: CloudVersionSummary,
+    /// `None` for versions announced through `notify_upload_complete`, whose
+    /// archive went to a URL this backend never serves
+    archive: Option<Arc<Vec<u8>>>,
+}
+
+#[derive(Default)]
+struct CloudState {
+    versions: Vec<StoredVersion>,
+    devices: Vec<CloudDevice>,
+}
+
+/// Cloud backend that keeps versions, devices and archives in memory, for
+/// tests and the hidden demo cloud mode. Clones share the same cloud, so a
+/// test can keep a handle to one it gave away.
+#[derive(Clone)]
+pub struct InMemoryCloudBackend {
+    state: Arc<Mutex<CloudState>>,
+    device_id: String,
+    faults: Arc<Mutex<FaultConfig>>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl InMemoryCloudBackend {
+    pub fn new(device_id: impl Into<String>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CloudState::default())),
+            device_id: device_id.into(),
+            faults: Arc::new(Mutex::new(FaultConfig::default())),
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Another device on the same cloud
+    pub fn for_device(&self, device_id: impl Into<String>) -> Self {
+        Self {
+            device_id: device_id.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn set_faults(&self, faults: FaultConfig) {
+        if let Ok(mut current) = self.faults.lock() {
+            *current = faults;
+        }
+    }
+
+    /// Backend calls made so far, failed ones included
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Store a version as if `summary.device_id` had uploaded `archive`
+    pub fn seed_version(
+        &self,
+        game_id: &str,
+        summary: CloudVersionSummary,
+        archive: Vec<u8>,
+    ) -> Result<(), CloudError> {
+        self.lock()?.versions.push(StoredVersion {
+            game_id: game_id.to_string(),
+            emulator_id: None,
+            summary,
+            archive: Some(Arc::new(archive)),
+        });
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, CloudState>, CloudError> {
+        self.state
+            .lock()
+            .map_err(|err| CloudError::StorageError(format!("demo cloud lock poisoned: {err}")))
+    }
+
+    /// Count the call, wait out the latency and maybe fail it
+    async fn simulate(&self, operation: &str) -> Result<(), CloudError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let faults = self.faults.lock().map(|faults| *faults).unwrap_or_default();
+        if !faults.latency.is_zero() {
+            sleep(faults.latency).await;
+        }
+        if faults.failure_rate > 0.0 && roll() < faults.failure_rate {
+            debug!("{LOG_TAG} Injected failure in {operation}");
+            return Err(CloudError::NetworkError(format!(
+                "injected failure in {operation}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn find<'a>(
+        state: &'a CloudState,
+        game_id: &str,
+        version_id: &str,
+    ) -> Result<&'a StoredVersion, CloudError> {
+        state
+            .versions
+            .iter()
+            .find(|v| v.game_id == game_id && v.summary.version_id == version_id)
+            .ok_or_else(|| CloudError::NotFound(format!("version {version_id}")))
+    }
+}
+
+/// Uniform value in `[0, 1)`, taken from a v4 UUID to avoid a `rand` dependency
+fn roll() -> f64 {
+    (Uuid::new_v4().as_u128() % 1_000_000) as f64 / 1_000_000.0
+}
+
+#[async_trait]
+impl CloudBackend for InMemoryCloudBackend {
+    async fn login(&self, _email: String, _password: String) -> Result<String, CloudError> {
+        self.simulate("login").await?;
+        Ok("demo-token".to_string())
+    }
+
+    async fn signup(&self, _email: String, _password: String) -> Result<String, CloudError> {
+        self.simulate("signup").await?;
+        Ok("demo-token".to_string())
+    }
+
+    async fn upload_archive(
+        &self,
+        metadata: SaveMetadata,
+        archive_path: PathBuf,
+    ) -> Result<CloudVersionSummary, CloudError> {
+        self.simulate("upload_archive").await?;
+        let archive = tokio::fs::read(&archive_path)
+            .await
+            .map_err(|err| CloudError::NotFound(format!("archive {archive_path:?}: {err}")))?;
+        let summary = CloudVersionSummary {
+            version_id: metadata.version_id.clone(),
+            timestamp: metadata.timestamp,
+            size_bytes: archive.len() as u64,
+            device_id: self.device_id.clone(),
+            file_list: metadata.file_list.clone(),
+            sha256: calculate_sha256(&archive_path)?,
+            thumbnail_url: None,
+            note: metadata.note.clone(),
+            tags: metadata.tags.clone(),
+            pinned: metadata.pinned,
+            parent_version_id: metadata.parent_version_id.clone(),
+            encrypted: metadata.encrypted,
+        };
+
+        let mut state = self.lock()?;
+        state.versions.retain(|v| {
+            !(v.game_id == metadata.game_id && v.summary.version_id == metadata.version_id)
+        });
+        state.versions.push(StoredVersion {
+            game_id: metadata.game_id.clone(),
+            emulator_id: Some(metadata.emulator_id.clone()),
+            summary: summary.clone(),
+            archive: Some(Arc::new(archive)),
+        });
+        info!(
+            "{LOG_TAG} Stored {} version {}",
+            metadata.game_id, metadata.version_id
+        );
+        Ok(summary)
+    }
+
+    async fn request_upload_url(
+        &self,
+        payload: UploadRequest,
+    ) -> Result<UploadUrlResponse, CloudError> {
+        self.simulate("request_upload_url").await?;
+        let r2_key = format!("{}/{}", payload.game_id, payload.version_id);
+        Ok(UploadUrlResponse {
+            upload_url: format!("memory://{r2_key}"),
+            r2_key,
+            version_id: payload.version_id,
+            worker_token: None,
+            thumbnail_upload_url: None,
+        })
+    }
+
+    async fn notify_upload_complete(&self, payload: UploadRequest) -> Result<(), CloudError> {
+        self.simulate("notify_upload_complete").await?;
+        let summary = CloudVersionSummary {
+            version_id: payload.version_id,
+            timestamp: Utc::now().timestamp().max(0) as u64,
+            size_bytes: payload.size_bytes,
+            device_id: payload.device_id.unwrap_or_else(|| self.device_id.clone()),
+            file_list: payload.file_list,
+            sha256: payload.sha256,
+            thumbnail_url: None,
+            note: payload.note,
+            tags: payload.tags,
+            pinned: payload.pinned,
+            parent_version_id: payload.parent_version_id,
+            encrypted: payload.encrypted,
+        };
+        self.lock()?.versions.push(StoredVersion {
+            game_id: payload.game_id,
+            emulator_id: payload.emulator_id,
+            summary,
+            archive: None,
+        });
+        Ok(())
+    }
+
+    async fn request_download_url(
+        &self,
+        game_id: String,
+        version_id: String,
+    ) -> Result<DownloadUrlResponse, CloudError> {
+        self.simulate("request_download_url").await?;
+        let state = self.lock()?;
+        let stored = Self::find(&state, &game_id, &version_id)?;
+        let summary = &stored.summary;
+        Ok(DownloadUrlResponse {
+            ok: true,
+            download_url: format!("memory://{game_id}/{version_id}"),
+            r2_key: format!("{game_id}/{version_id}"),
+            version_id: summary.version_id.clone(),
+            game_id: stored.game_id.clone(),
+            size_bytes: summary.size_bytes,
+            sha256: summary.sha256.clone(),
+            file_list: summary.file_list.clone(),
+            emulator_id: stored.emulator_id.clone(),
+            timestamp: Some(summary.timestamp),
+            thumbnail_url: None,
+            note: summary.note.clone(),
+            tags: summary.tags.clone(),
+            pinned: summary.pinned,
+            parent_version_id: summary.parent_version_id.clone(),
+            encrypted: summary.encrypted,
+        })
+    }
+
+    async fn list_versions(
+        &self,
+        game_id: String,
+        limit: Option<usize>,
+    ) -> Result<Vec<CloudVersionSummary>, CloudError> {
+        self.simulate("list_versions").await?;
+        let mut versions: Vec<CloudVersionSummary> = self
+            .lock()?
+            .versions
+            .iter()
+            .filter(|v| v.game_id == game_id)
+            .map(|v| v.summary.clone())
+            .collect();
+        versions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        if let Some(limit) = limit {
+            versions.truncate(limit);
+        }
+        Ok(versions)
+    }
+
+    async fn list_latest_versions(
+        &self,
+        game_ids: Vec<String>,
+    ) -> Result<HashMap<String, CloudVersionSummary>, CloudError> {
+        self.simulate("list_latest_versions").await?;
+        let state = self.lock()?;
+        let mut latest: HashMap<String, CloudVersionSummary> = HashMap::new();
+        for stored in state
+            .versions
+            .iter()
+            .filter(|v| game_ids.contains(&v.game_id))
+        {
+            let newer = latest
+                .get(&stored.game_id)
+                .map_or(true, |current| stored.summary.timestamp > current.timestamp);
+            if newer {
+                latest.insert(stored.game_id.clone(), stored.summary.clone());
+            }
+        }
+        Ok(latest)
+    }
+
+    async fn download_version(
+        &self,
+        game_id: String,
+        version_id: String,
+        target_path: PathBuf,
+    ) -> Result<(), CloudError> {
+        self.simulate("download_version").await?;
+        let archive = {
+            let state = self.lock()?;
+            Self::find(&state, &game_id, &version_id)?
+                .archive
+                .clone()
+                .ok_or_else(|| CloudError::NotFound(format!("archive of {version_id}")))?
+        };
+        if let Some(parent) = target_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| CloudError::Io(err.to_string()))?;
+        }
+        tokio::fs::write(&target_path, archive.as_slice())
+            .await
+            .map_err(|err| CloudError::Io(err.to_string()))
+    }
+
+    async fn download_file(
+        &self,
+        game_id: String,
+        version_id: String,
+        path: String,
+    ) -> Result<Vec<u8>, CloudError> {
+        self.simulate("download_file").await?;
+        let archive = {
+            let state = self.lock()?;
+            let stored = Self::find(&state, &game_id, &version_id)?;
+            if !stored.summary.file_list.contains(&path) {
+                return Err(CloudError::NotFound(format!(
+                    "{path} in version {version_id}"
+                )));
+            }
+            stored
+                .archive
+                .clone()
+                .ok_or_else(|| CloudError::NotFound(format!("archive of {version_id}")))?
+        };
+        read_entry(Cursor::new(archive.as_slice()), &path, MAX_CLOUD_FILE_BYTES)
+            .map_err(|err| CloudError::StorageError(err.to_string()))
+    }
+
+    async fn delete_version(&self, game_id: String, version_id: String) -> Result<(), CloudError> {
+        self.simulate("delete_version").await?;
+        let mut state = self.lock()?;
+        if Self::find(&state, &game_id, &version_id)?.summary.pinned {
+            return Err(CloudError::Conflict(format!(
+                "version {version_id} is pinned"
+            )));
+        }
+        state
+            .versions
+            .retain(|v| !(v.game_id == game_id && v.summary.version_id == version_id));
+        Ok(())
+    }
+
+    async fn set_version_pinned(
+        &self,
+        game_id: String,
+        version_id: String,
+        pinned: bool,
+    ) -> Result<(), CloudError> {
+        self.simulate("set_version_pinned").await?;
+        let mut state = self.lock()?;
+        let stored = state
+            .versions
+            .iter_mut()
+            .find(|v| v.game_id == game_id && v.summary.version_id == version_id)
+            .ok_or_else(|| CloudError::NotFound(format!("version {version_id}")))?;
+        stored.summary.pinned = pinned;
+        Ok(())
+    }
+
+    fn ensure_device_id(&self) -> Result<String, CloudError> {
+        Ok(self.device_id.clone())
+    }
+
+    async fn ensure_device_registered(&self) -> Result<String, CloudError> {
+        self.simulate("ensure_device_registered").await?;
+        Ok(self.device_id.clone())
+    }
+
+    async fn list_devices(&self, _token: String) -> Result<Vec<CloudDevice>, CloudError> {
+        self.simulate("list_devices").await?;
+        Ok(self.lock()?.devices.clone())
+    }
+
+    async fn register_device(
+        &self,
+        _token: String,
+        device_id: String,
+        platform: String,
+        device_name: String,
+    ) -> Result<(), CloudError> {
+        self.simulate("register_device").await?;
+        let mut state = self.lock()?;
+        state.devices.retain(|device| device.device_id != device_id);
+        state.devices.push(CloudDevice {
+            device_id,
+            platform,
+            device_name,
+            last_seen: Utc::now().timestamp().max(0) as u64,
+        });
+        Ok(())
+    }
+
+    async fn remove_device(&self, _token: String, device_id: String) -> Result<(), CloudError> {
+        self.simulate("remove_device").await?;
+        let mut state = self.lock()?;
+        let before = state.devices.len();
+        state.devices.retain(|device| device.device_id != device_id);
+        if state.devices.len() == before {
+            return Err(CloudError::NotFound(format!("device {device_id}")));
+        }
+        Ok(())
+    }
+
+    fn get_device_id(&self) -> Result<String, CloudError> {
+        Ok(self.device_id.clone())
+    }
+
+    async fn list_games(&self) -> Result<Vec<String>, CloudError> {
+        self.simulate("list_games").await?;
+        let games: BTreeSet<String> = self
+            .lock()?
+            .versions
+            .iter()
+            .map(|v| v.game_id.clone())
+            .collect();
+        Ok(games.into_iter().collect())
+    }
+
+    async fn check_connection(&self) -> Result<bool, CloudError> {
+        Ok(self.simulate("check_connection").await.is_ok())
+    }
+
+    fn holds_archives(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::core::cloud::CloudBackend;
+use crate::core::extract::extract_archive;
+use crate::core::history::{HistoryEntry, HistoryManager};
+use crate::core::packager::SavePackager;
+use crate::core::profile::{EmulatorProfile, ProfileManager, SaveEncryption};
+use crate::core::restore::{restore_target, restore_version};
+use crate::core::staging::staging;
+use crate::core::sync_state::SyncStateStore;
+use crate::core::watcher::WatcherManager;
+
+/// Tag on versions produced by merging a conflict
+pub const MERGED_TAG: &str = "merged";
+
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("cloud error: {0}")]
+    Cloud(String),
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("packaging failed: {0}")]
+    Package(String),
+    #[error("restore failed: {0}")]
+    Restore(String),
+    /// Both sides changed these files differently and no merger could combine them
+    #[error("changed on both sides: {}", .0.join(", "))]
+    Conflicting(Vec<String>),
+}
+
+impl From<std::io::Error> for MergeError {
+    fn from(err: std::io::Error) -> Self {
+        MergeError::Io(err.to_string())
+    }
+}
+
+/// Format-specific merge for one file that both sides changed. Mergers are
+/// asked in registration order; the first that `handles` the file decides.
+pub trait FileMerger: Send + Sync {
+    /// Whether this merger understands `path`, an archive entry name
+    fn handles(&self, path: &str) -> bool;
+    /// Combine both copies, or `None` when they cannot be reconciled
+    fn merge(&self, base: Option<&[u8]>, local: &[u8], cloud: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// The mergers consulted for files changed on both sides, managed as app
+/// state. With none registered only files changed on one side can be merged.
+#[derive(Default)]
+pub struct MergeRegistry {
+    mergers: Vec<Box<dyn FileMerger>>,
+}
+
+impl MergeRegistry {
+    pub fn register(&mut self, merger: Box<dyn FileMerger>) {
+        self.mergers.push(merger);
+    }
+
+    fn merge(
+        &self,
+        path: &str,
+        base: Option<&[u8]>,
+        local: &[u8],
+        cloud: &[u8],
+    ) -> Option<Vec<u8>> {
+        self.mergers
+            .iter()
+            .find(|merger| merger.handles(path))
+            .and_then(|merger| merger.merge(base, local, cloud))
+    }
+}
+
+/// Where each file of a merged version came from
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MergeSummary {
+    pub from_local: usize,
+    pub from_cloud: usize,
+    /// Files changed on both sides and combined by a format merger
+    pub combined: usize,
+    /// Files one side deleted while the other left them as in `base`
+    pub deleted: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MergeOutcome {
+    pub version_id: String,
+    #[serde(flatten)]
+    pub summary: MergeSummary,
+}
+
+/// Combine the files of the local and cloud versions of a directory save.
+/// A file changed on one side only since `base` (the version both sides
+/// last agreed on) is taken from that side, and a file one side deleted
+/// stays deleted when the other side still has it as in `base`. Without a
+/// base, or when the other side edited it, a file missing on one side is
+/// kept.
+pub fn merge_trees(
+    base: Option<&Path>,
+    local: &Path,
+    cloud: &Path,
+    local_files: &[String],
+    cloud_files: &[String],
+    out: &Path,
+    registry: &MergeRegistry,
+) -> Result<MergeSummary, MergeError> {
+    let read = |root: &Path, file: &str| fs::read(root.join(file)).ok();
+    let paths: BTreeSet<&String> = local_files.iter().chain(cloud_files).collect();
+
+    let mut summary = MergeSummary::default();
+    let mut conflicting = Vec::new();
+    let mut merged = Vec::with_capacity(paths.len());
+    for path in paths {
+        let local_bytes = read(local, path);
+        let cloud_bytes = read(cloud, path);
+        let base_bytes = || base.and_then(|base| read(base, path));
+        let bytes = match (local_bytes, cloud_bytes) {
+            (Some(local), None) => {
+                if base_bytes().as_deref() == Some(local.as_slice()) {
+                    summary.deleted += 1;
+                    continue;
+                }
+                summary.from_local += 1;
+                local
+            }
+            (None, Some(cloud)) => {
+                if base_bytes().as_deref() == Some(cloud.as_slice()) {
+                    summary.deleted += 1;
+                    continue;
+                }
+                summary.from_cloud += 1;
+                cloud
+            }
+            (Some(local), Some(cloud)) if local == cloud => local,
+            (Some(local), Some(cloud)) => {
+                let base_bytes = base_bytes();
+                if base_bytes.as_deref() == Some(local.as_slice()) {
+                    summary.from_cloud += 1;
+                    cloud
+                } else if base_bytes.as_deref() == Some(cloud.as_slice()) {
+                    summary.from_local += 1;
+                    local
+                } else if let Some(combined) =
+                    registry.merge(path, base_bytes.as_deref(), &local, &cloud)
+                {
+                    summary.combined += 1;
+                    combined
+                } else {
+                    conflicting.push(path.clone());
+                    continue;
+                }
+            }
+            (None, None) => continue,
+        };
+        merged.push((path, bytes));
+    }
+
+    if !conflicting.is_empty() {
+        return Err(MergeError::Conflicting(conflicting));
+    }
+
+    for (path, bytes) in merged {
+        let target = out.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, bytes)?;
+    }
+    Ok(summary)
+}
+
+/// Resolve a conflict on `game_id` by merging the latest local and cloud
+/// versions into a new local version and restoring it to disk. The next
+/// sync uploads it, since it is newer than both.
+pub async fn merge_conflict(
+    app: &AppHandle,
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    history: Arc<HistoryManager>,
+    profiles: &Arc<RwLock<ProfileManager>>,
+    registry: Arc<MergeRegistry>,
+    game_id: &str,
+) -> Result<MergeOutcome, MergeError> {
+    let local = history
+        .get_latest_version(game_id)
+        .ok_or_else(|| MergeError::NotFound(format!("no local version of {game_id}")))?;
+    let cloud_latest = cloud
+        .lock()
+        .await
+        .list_versions(game_id.to_string(), Some(1))
+        .await
+        .map_err(|err| MergeError::Cloud(err.to_string()))?
+        .into_iter()
+        .max_by_key(|version| version.timestamp)
+        .ok_or_else(|| MergeError::NotFound(format!("no cloud version of {game_id}")))?;
+
+    let profile = profiles
+        .read()
+        .map_err(|err| MergeError::NotFound(format!("profile lock error: {err}")))?
+        .get_profile(&local.metadata.emulator_id)
+        .map_err(|err| MergeError::NotFound(err.to_string()))?
+        .ok_or_else(|| MergeError::NotFound(format!("profile {}", local.metadata.emulator_id)))?;
+    let base = app
+        .try_state::<Arc<SyncStateStore>>()
+        .and_then(|state| state.game(game_id).ok())
+        .and_then(|state| {
+            let version_id = state.last_synced_version()?.to_string();
+            history
+                .get_history_item(game_id.to_string(), version_id)
+                .ok()
+        });
+    if base.is_none() {
+        debug!("[SYNC] No common version for {game_id}; files changed on both sides conflict");
+    }
+
+    let work_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|err| MergeError::Io(err.to_string()))?
+        .join("data")
+        .join("merge")
+        .join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&work_dir)?;
+
+    let result = async {
+        let cloud_archive = work_dir.join("cloud.zip");
+        cloud
+            .lock()
+            .await
+            .download_version(
+                game_id.to_string(),
+                cloud_latest.version_id.clone(),
+                cloud_archive.clone(),
+            )
+            .await
+            .map_err(|err| MergeError::Cloud(err.to_string()))?;
+        let target_dir = restore_target(&profile, game_id)
+            .map_err(|err| MergeError::Restore(err.to_string()))?;
+
+        let job = MergeJob {
+            history,
+            registry,
+            profile,
+            game_id: game_id.to_string(),
+            encrypted: local.metadata.encrypted || cloud_latest.encrypted,
+            local,
+            base,
+            cloud_version_id: cloud_latest.version_id.clone(),
+            cloud_archive,
+            work_dir: work_dir.clone(),
+            target_dir: target_dir.clone(),
+        };
+        // Keep the watcher from reporting the restored files as new saves
+        suppress_watcher(app, &target_dir);
+        let outcome = tauri::async_runtime::spawn_blocking(move || job.run())
+            .await
+            .map_err(|err| MergeError::Io(err.to_string()))?;
+        suppress_watcher(app, &target_dir);
+        outcome
+    }
+    .await;
+
+    if let Err(err) = fs::remove_dir_all(&work_dir) {
+        debug!(
+            "[SYNC] Could not remove merge workspace {:?}: {err}",
+            work_dir
+        );
+    }
+    result
+}
+
+fn suppress_watcher(app: &AppHandle, path: &Path) {
+    if let Some(watcher) = app.try_state::<WatcherManager>() {
+        watcher.suppress_path(path);
+    }
+}
+
+/// The blocking half of `merge_conflict`: unpack, merge, package, restore
+struct MergeJob {
+    history: Arc<HistoryManager>,
+    registry: Arc<MergeRegistry>,
+    profile: EmulatorProfile,
+    game_id: String,
+    /// Either side holds encrypted saves, whose bytes no merger can read
+    encrypted: bool,
+    local: HistoryEntry,
+    base: Option<HistoryEntry>,
+    cloud_version_id: String,
+    cloud_archive: PathBuf,
+    work_dir: PathBuf,
+    target_dir: PathBuf,
+}
+
+impl MergeJob {
+    fn run(self) -> Result<MergeOutcome, MergeError> {
+        let unpack = |archive: &Path, name: &str| {
+            let dir = self.work_dir.join(name);
+            extract_archive(archive, &dir)
+                .map(|files| (dir, files))
+                .map_err(|err| MergeError::Io(format!("unpacking {name} version: {err}")))
+        };
+        let (local_dir, local_files) = unpack(&self.local.archive_path, "local")?;
+        let (cloud_dir, cloud_files) = unpack(&self.cloud_archive, "cloud")?;
+        let base_dir = match &self.base {
+            Some(base) => match unpack(&base.archive_path, "base") {
+                Ok((dir, _)) => Some(dir),
+                Err(err) => {
+                    warn!(
+                        "[SYNC] Merging {} without a common version: {err}",
+                        self.game_id
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        // Format mergers would take ciphertext for save data, so files
+        // both sides changed conflict instead
+        let no_mergers = MergeRegistry::default();
+        let registry = if self.encrypted {
+            &no_mergers
+        } else {
+            self.registry.as_ref()
+        };
+        let merged_dir = self.work_dir.join("merged");
+        let summary = merge_trees(
+            base_dir.as_deref(),
+            &local_dir,
+            &cloud_dir,
+            &local_files,
+            &cloud_files,
+            &merged_dir,
+            registry,
+        )?;
+
+        let mut packager =
+            SavePackager::new(self.game_id.clone(), self.profile.emulator_id.clone());
+        packager.set_encryption(if self.encrypted {
+            SaveEncryption::Encrypted
+        } else {
+            self.profile.save_encryption
+        });
+        packager.add_store_only_extensions(self.profile.store_only_extensions.clone());
+        let packaged = packager
+            .package_save(vec![merged_dir], Vec::new())
+            .map_err(|err| MergeError::Package(err.to_string()))?;
+        let archive_path = packaged.archive_path.clone();
+
+        let mut metadata = packaged.metadata;
+        metadata.tags = vec![MERGED_TAG.to_string()];
+        metadata.source = Some("local".to_string());
+        // Made from the cloud version, so the upload fast-forwards it
+        metadata.parent_version_id = Some(self.cloud_version_id.clone());
+        let timestamp = metadata.timestamp;
+
+        restore_version(
+            &self.history,
+            &self.profile,
+            &self.game_id,
+            &self.target_dir,
+            &archive_path,
+            Some(timestamp),
+        )
+        .map_err(|err| MergeError::Restore(err.to_string()))?;
+        let saved = self.history.save_to_history(metadata, archive_path.clone());
+        staging().release(&archive_path);
+        let entry = saved.map_err(|err| MergeError::Package(err.to_string()))?;
+
+        info!(
+            "[SYNC] Merged {} into {} ({} local, {} cloud, {} combined, {} deleted)",
+            self.game_id,
+            entry.metadata.version_id,
+            summary.from_local,
+            summary.from_cloud,
+            summary.combined,
+            summary.deleted
+        );
+        Ok(MergeOutcome {
+            version_id: entry.metadata.version_id,
+            summary,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dirs {
+        root: PathBuf,
+    }
+
+    impl Dirs {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("crosssave-merge-{}", Uuid::new_v4()));
+            Self { root }
+        }
+
+        /// Write `files` under the side called `name` and list them
+        fn side(&self, name: &str, files: &[(&str, &str)]) -> (PathBuf, Vec<String>) {
+            let dir = self.root.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            for (file, content) in files {
+                fs::write(dir.join(file), content).unwrap();
+            }
+            (
+                dir,
+                files.iter().map(|(file, _)| file.to_string()).collect(),
+            )
+        }
+
+        fn read(&self, file: &str) -> Option<String> {
+            fs::read_to_string(self.root.join("out").join(file)).ok()
+        }
+
+        fn merge(
+            &self,
+            base: &[(&str, &str)],
+            local: &[(&str, &str)],
+            cloud: &[(&str, &str)],
+            registry: &MergeRegistry,
+        ) -> Result<MergeSummary, MergeError> {
+            let (base_dir, _) = self.side("base", base);
+            let (local_dir, local_files) = self.side("local", local);
+            let (cloud_dir, cloud_files) = self.side("cloud", cloud);
+            merge_trees(
+                Some(base_dir.as_path()),
+                &local_dir,
+                &cloud_dir,
+                &local_files,
+                &cloud_files,
+                &self.root.join("out"),
+                registry,
+            )
+        }
+    }
+
+    impl Drop for Dirs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    /// Joins both copies of `.txt` files
+    struct Concat;
+
+    impl FileMerger for Concat {
+        fn handles(&self, path: &str) -> bool {
+            path.ends_with(".txt")
+        }
+
+        fn merge(&self, _base: Option<&[u8]>, local: &[u8], cloud: &[u8]) -> Option<Vec<u8>> {
+            Some([local, cloud].concat())
+        }
+    }
+
+    #[test]
+    fn takes_edits_made_on_one_side() {
+        let dirs = Dirs::new();
+        let summary = dirs
+            .merge(
+                &[("a.sav", "a0"), ("b.sav", "b0")],
+                &[("a.sav", "a1"), ("b.sav", "b0")],
+                &[("a.sav", "a0"), ("b.sav", "b2")],
+                &MergeRegistry::default(),
+            )
+            .unwrap();
+        assert_eq!(dirs.read("a.sav").as_deref(), Some("a1"));
+        assert_eq!(dirs.read("b.sav").as_deref(), Some("b2"));
+        assert_eq!((summary.from_local, summary.from_cloud), (1, 1));
+    }
+
+    #[test]
+    fn keeps_deletions_made_on_one_side() {
+        let dirs = Dirs::new();
+        let summary = dirs
+            .merge(
+                &[
+                    ("gone.sav", "old"),
+                    ("edited.sav", "old"),
+                    ("kept.sav", "k"),
+                ],
+                &[("edited.sav", "new"), ("kept.sav", "k"), ("added.sav", "n")],
+                &[("gone.sav", "old"), ("kept.sav", "k")],
+                &MergeRegistry::default(),
+            )
+            .unwrap();
+        assert_eq!(dirs.read("gone.sav"), None, "deleted in the cloud");
+        assert_eq!(
+            dirs.read("edited.sav").as_deref(),
+            Some("new"),
+            "an edit wins over a deletion"
+        );
+        assert_eq!(dirs.read("added.sav").as_deref(), Some("n"));
+        assert_eq!(dirs.read("kept.sav").as_deref(), Some("k"));
+        assert_eq!(summary.deleted, 1);
+    }
+
+    #[test]
+    fn files_changed_on_both_sides_conflict() {
+        let dirs = Dirs::new();
+        let err = dirs
+            .merge(
+                &[("a.sav", "a0")],
+                &[("a.sav", "a1")],
+                &[("a.sav", "a2")],
+                &MergeRegistry::default(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, MergeError::Conflicting(files) if files == ["a.sav"]));
+        assert_eq!(dirs.read("a.sav"), None, "nothing written on conflict");
+    }
+
+    #[test]
+    fn registered_mergers_combine_both_changes() {
+        let mut registry = MergeRegistry::default();
+        registry.register(Box::new(Concat));
+
+        let dirs = Dirs::new();
+        let summary = dirs
+            .merge(
+                &[("notes.txt", "0")],
+                &[("notes.txt", "L")],
+                &[("notes.txt", "C")],
+                &registry,
+            )
+            .unwrap();
+        assert_eq!(dirs.read("notes.txt").as_deref(), Some("LC"));
+        assert_eq!(summary.combined, 1);
+
+        let err = Dirs::new()
+            .merge(
+                &[("a.sav", "0")],
+                &[("a.sav", "L")],
+                &[("a.sav", "C")],
+                &registry,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(err, MergeError::Conflicting(_)),
+            "no merger for .sav"
+        );
+    }
+}
@@ -1,7 +1,51 @@
+pub mod account;
+pub mod backoff;
 pub mod cloud;
+pub mod conflict;
+pub mod connectivity;
+pub mod convert;
+pub mod crash;
+#[cfg(desktop)]
+pub mod desktop;
+pub mod device_id;
+pub mod encryption;
+pub mod export;
+pub mod extract;
+pub mod file_lock;
+pub mod file_provider;
+pub mod game_info;
 pub mod history;
+pub mod listing_cache;
+pub mod logs;
+pub mod memory_cloud;
+pub mod merge;
+pub mod notifications;
 pub mod packager;
+pub mod path_check;
+pub mod paths;
+pub mod perf;
+pub mod placeholder;
+pub mod process_watch;
 pub mod profile;
+pub mod profile_bundle;
+pub mod pruning;
+pub mod reconcile;
+pub mod restore;
+pub mod retroarch;
+#[cfg(target_os = "android")]
+pub mod saf;
+pub mod scheduler;
+pub mod secrets;
 pub mod settings;
+pub mod snapshot_copy;
+pub mod staging;
+pub mod startup;
+pub mod steam;
+pub mod storage;
 pub mod sync;
+pub mod sync_state;
+pub mod thumbnail;
+#[cfg(desktop)]
+pub mod tray;
+pub mod updater;
 pub mod watcher;
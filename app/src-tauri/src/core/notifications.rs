@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tauri::{AppHandle, Listener};
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+use tracing::{debug, info, warn};
+
+use crate::core::settings::SettingsManager;
+use crate::core::storage::StorageScope;
+
+/// A warning that keeps recurring, like an expired login on every retry,
+/// is raised at most once per this long
+const WARNING_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Deserialize)]
+struct CompletePayload {
+    game_id: String,
+}
+
+#[derive(Deserialize)]
+struct UploadErrorPayload {
+    reason: String,
+}
+
+#[derive(Deserialize)]
+struct StorageFullPayload {
+    scope: StorageScope,
+    hint: String,
+}
+
+/// Raises OS notifications for sync milestones and errors while
+/// `notifications.enabled` is set
+struct Notifier {
+    app: AppHandle,
+    settings: Arc<SettingsManager>,
+    /// When each warning was last raised, for `WARNING_COOLDOWN`
+    warned_at: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl Notifier {
+    fn enabled(&self) -> bool {
+        self.settings
+            .get_settings()
+            .map(|settings| settings.notifications.enabled)
+            .unwrap_or(false)
+    }
+
+    fn show(&self, title: &str, body: &str) {
+        if !self.enabled() {
+            return;
+        }
+        let notification = self.app.notification();
+        match notification.permission_state() {
+            Ok(PermissionState::Granted) => {}
+            Ok(state) => {
+                debug!("[NOTIFY] Notification permission is {state:?}; skipping");
+                return;
+            }
+            Err(err) => {
+                warn!("[NOTIFY] Failed to read notification permission: {err}");
+                return;
+            }
+        }
+        if let Err(err) = notification.builder().title(title).body(body).show() {
+            warn!("[NOTIFY] Failed to show notification: {err}");
+        }
+    }
+
+    /// `show`, unless the same `kind` of warning was raised recently
+    fn warn_once(&self, kind: &'static str, title: &str, body: &str) {
+        if !self.enabled() {
+            return;
+        }
+        if let Ok(mut warned_at) = self.warned_at.lock() {
+            let now = Instant::now();
+            if warned_at
+                .get(kind)
+                .is_some_and(|at| now.duration_since(*at) < WARNING_COOLDOWN)
+            {
+                return;
+            }
+            warned_at.insert(kind, now);
+        }
+        self.show(title, body);
+    }
+
+    fn auth_expired(&self) {
+        self.warn_once(
+            "auth",
+            "Cloud sign-in expired",
+            "Sign in again to keep your saves in sync",
+        );
+    }
+}
+
+/// Listen for the sync events worth a notification. Nothing is shown until
+/// the user turns notifications on; Android asks for permission then.
+pub fn install(app: &AppHandle, settings: Arc<SettingsManager>) {
+    let notifier = Arc::new(Notifier {
+        app: app.clone(),
+        settings,
+        warned_at: Mutex::new(HashMap::new()),
+    });
+    let on = |event: &str, handle: fn(&Notifier, &str)| {
+        let notifier = notifier.clone();
+        app.listen(event, move |event| handle(&notifier, event.payload()));
+    };
+
+    on("sync://upload-complete", |notifier, payload| {
+        if let Ok(complete) = serde_json::from_str::<CompletePayload>(payload) {
+            notifier.show(
+                "Save uploaded",
+                &format!("{} is backed up", complete.game_id),
+            );
+        }
+    });
+    on("sync://download-complete", |notifier, payload| {
+        if let Ok(complete) = serde_json::from_str::<CompletePayload>(payload) {
+            notifier.show(
+                "Save downloaded",
+                &format!("{} was updated from the cloud", complete.game_id),
+            );
+        }
+    });
+    on("sync://conflict-detected", |notifier, payload| {
+        if let Ok(game_id) = serde_json::from_str::<String>(payload) {
+            notifier.show(
+                "Sync conflict",
+                &format!("{game_id} changed on this device and in the cloud; choose which to keep"),
+            );
+        }
+    });
+    on("sync://upload-error", |notifier, payload| {
+        if let Ok(error) = serde_json::from_str::<UploadErrorPayload>(payload) {
+            if error.reason == "unauthorized" {
+                notifier.auth_expired();
+            }
+        }
+    });
+    on("cloud://reconnect-required", |notifier, _| {
+        notifier.auth_expired()
+    });
+    on("sync://storage-full", |notifier, payload| {
+        if let Ok(full) = serde_json::from_str::<StorageFullPayload>(payload) {
+            let (kind, title) = match full.scope {
+                StorageScope::Cloud => ("cloud-quota", "Cloud storage is full"),
+                StorageScope::Local => ("local-disk", "Device storage is full"),
+            };
+            notifier.warn_once(kind, title, &full.hint);
+        }
+    });
+
+    if notifier.enabled() {
+        // Android waits on the UI thread for the answer, so never ask from it
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || request_permission(&app));
+    }
+    info!("[NOTIFY] Listening for sync notifications");
+}
+
+/// Ask for notification permission if the OS has not been asked yet
+pub fn request_permission(app: &AppHandle) {
+    let notification = app.notification();
+    if let Ok(PermissionState::Prompt | PermissionState::PromptWithRationale) =
+        notification.permission_state()
+    {
+        match notification.request_permission() {
+            Ok(state) => info!("[NOTIFY] Notification permission: {state:?}"),
+            Err(err) => warn!("[NOTIFY] Failed to request notification permission: {err}"),
+        }
+    }
+}
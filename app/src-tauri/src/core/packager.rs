@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     fs,
     io::{self, Read},
     path::{Path, PathBuf},
@@ -9,8 +10,33 @@ use glob::Pattern;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tracing::{info, warn};
-use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+use tracing::{debug, info, warn};
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::core::encryption::{sample_entropy, saves_are_encrypted};
+use crate::core::file_lock::{wait_for_unlocked, LockRetryPolicy};
+use crate::core::file_provider::local_roots;
+use crate::core::game_info::attach_bundled;
+use crate::core::paths::{archive_entry_name, extended, serde_path, simplified};
+use crate::core::placeholder::{hydrate, icloud_target, is_placeholder, PlaceholderPolicy};
+use crate::core::profile::SaveEncryption;
+use crate::core::snapshot_copy::{snapshot_files, SnapshotError};
+use crate::core::staging::staging;
+use crate::core::storage::is_storage_full;
+
+/// Format of `SaveMetadata` written by this version. Older history entries
+/// are upgraded by `HistoryManager` when it loads them.
+pub const METADATA_SCHEMA_VERSION: u32 = 1;
+/// Content type of an archive uploaded as a plain zip
+pub const ARCHIVE_CONTENT_TYPE: &str = "application/zip";
+/// Frame header every zstd stream starts with; a zip never does
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_LEVEL: i32 = 9;
+
+/// Already-compressed formats that are stored instead of deflated again
+const DEFAULT_STORE_ONLY_EXTENSIONS: &[&str] = &[
+    "chd", "cso", "rvz", "wbfs", "gz", "zip", "7z", "xz", "zst", "bz2", "png", "jpg", "jpeg",
+];
 
 #[derive(Debug, Error)]
 pub enum PackagerError {
@@ -28,10 +54,56 @@ pub enum PackagerError {
     MissingArchive,
     #[error("hash calculation failed: {0}")]
     Hash(String),
+    #[error("not enough disk space: {0}")]
+    StorageFull(String),
+    #[error("saves unchanged since the last packaged version")]
+    Unchanged,
+    #[error("{0} save files are online-only cloud placeholders")]
+    Placeholders(usize),
+    #[error("{0} is open in another program; close the emulator or let it finish saving")]
+    FileLocked(String),
+    #[error("{0} kept changing while it was copied; let the emulator finish saving")]
+    StillWriting(String),
+}
+
+impl PackagerError {
+    fn write(err: io::Error) -> Self {
+        if is_storage_full(&err) {
+            PackagerError::StorageFull(err.to_string())
+        } else {
+            PackagerError::Archive(err.to_string())
+        }
+    }
+
+    fn zip(err: ZipError) -> Self {
+        match err {
+            ZipError::Io(err) => PackagerError::write(err),
+            other => PackagerError::Archive(other.to_string()),
+        }
+    }
+}
+
+/// Compression applied to a whole archive for the transfer, on top of the
+/// zip. Only used with backends that advertise it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveEncoding {
+    Zstd,
+}
+
+impl ArchiveEncoding {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ArchiveEncoding::Zstd => "application/zstd",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SaveMetadata {
+    /// `0` for metadata written before the format was versioned
+    #[serde(default)]
+    pub schema_version: u32,
     pub game_id: String,
     pub emulator_id: String,
     pub timestamp: u64,
@@ -44,14 +116,71 @@ pub struct SaveMetadata {
     pub sha256: Option<String>,
     #[serde(default)]
     pub source: Option<String>,
+    /// Saves were archived as opaque encrypted blobs
+    #[serde(default)]
+    pub encrypted: bool,
+    /// PNG screenshot kept next to the archive in history
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+    #[serde(default)]
+    pub thumbnail_sha256: Option<String>,
+    /// User label, e.g. "before final boss"
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Hash of the packaged files' paths, sizes and mtimes, compared before
+    /// the next packaging run to skip unchanged saves
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Pinned versions are never removed by retention
+    #[serde(default)]
+    pub pinned: bool,
+    /// Version these saves were made from, so sync can tell a fast-forward
+    /// from two devices diverging. Filled in by `HistoryManager::save_to_history`.
+    #[serde(default)]
+    pub parent_version_id: Option<String>,
+    /// Display title, platform and cover art hash matched from the game id
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub cover_sha256: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PackagedSave {
-    pub archive_path: String,
+    #[serde(with = "serde_path")]
+    pub archive_path: PathBuf,
     pub metadata: SaveMetadata,
 }
 
+/// One file as it would be written by `package_save`
+#[derive(Clone, Debug, Serialize)]
+pub struct PreviewFile {
+    pub path: String,
+    pub entry_name: String,
+    pub size_bytes: u64,
+    /// Archived without compression
+    pub stored: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PackagePreview {
+    pub files: Vec<PreviewFile>,
+    pub total_size_bytes: u64,
+    /// Rough archive size from each file's byte entropy plus zip overhead
+    pub estimated_compressed_bytes: u64,
+    pub encrypted: bool,
+    /// Online-only files left out because the profile skips placeholders
+    pub placeholders: Vec<String>,
+}
+
+/// Local header, central directory entry and data descriptor per file
+const ZIP_ENTRY_OVERHEAD_BYTES: u64 = 30 + 46 + 16;
+const ZIP_END_RECORD_BYTES: u64 = 22;
+
 #[derive(Debug)]
 pub struct SavePackager {
     game_id: String,
@@ -59,6 +188,17 @@ pub struct SavePackager {
     archive_path: Option<PathBuf>,
     timestamp: Option<u64>,
     version_id: Option<String>,
+    roots: Vec<PathBuf>,
+    exclude_patterns: Vec<Pattern>,
+    max_file_size_bytes: Option<u64>,
+    encryption: SaveEncryption,
+    encrypted: bool,
+    store_only_extensions: Vec<String>,
+    placeholders: PlaceholderPolicy,
+    lock_retry: LockRetryPolicy,
+    snapshot_min_bytes: Option<u64>,
+    previous_fingerprint: Option<String>,
+    fingerprint: Option<String>,
 }
 
 impl SavePackager {
@@ -69,41 +209,118 @@ impl SavePackager {
             archive_path: None,
             timestamp: None,
             version_id: None,
+            roots: Vec::new(),
+            exclude_patterns: Vec::new(),
+            max_file_size_bytes: None,
+            encryption: SaveEncryption::Auto,
+            encrypted: false,
+            store_only_extensions: DEFAULT_STORE_ONLY_EXTENSIONS
+                .iter()
+                .map(|ext| format!(".{ext}"))
+                .collect(),
+            placeholders: PlaceholderPolicy::Skip,
+            lock_retry: LockRetryPolicy::default(),
+            snapshot_min_bytes: None,
+            previous_fingerprint: None,
+            fingerprint: None,
+        }
+    }
+
+    /// Skip files matching any of `exclude_patterns` or larger than `max_file_size_bytes`.
+    pub fn set_filters(&mut self, exclude_patterns: Vec<String>, max_file_size_bytes: Option<u64>) {
+        self.exclude_patterns = compile_patterns(exclude_patterns);
+        self.max_file_size_bytes = max_file_size_bytes;
+    }
+
+    /// Fingerprint of the last packaged version; `package_save` fails with
+    /// `PackagerError::Unchanged` when the files still match it.
+    pub fn set_previous_fingerprint(&mut self, fingerprint: Option<String>) {
+        self.previous_fingerprint = fingerprint;
+    }
+
+    /// How to decide whether the packaged saves are encrypted.
+    pub fn set_encryption(&mut self, encryption: SaveEncryption) {
+        self.encryption = encryption;
+    }
+
+    /// Also store files ending in one of `extensions` (e.g. `chd` or
+    /// `state.gz`) without recompressing them.
+    pub fn add_store_only_extensions(&mut self, extensions: Vec<String>) {
+        for ext in extensions {
+            let ext = ext.trim().trim_start_matches('.').to_ascii_lowercase();
+            if !ext.is_empty() {
+                self.store_only_extensions.push(format!(".{ext}"));
+            }
         }
     }
 
+    /// Whether OneDrive or iCloud placeholders are downloaded or left out.
+    pub fn set_placeholder_policy(&mut self, policy: PlaceholderPolicy) {
+        self.placeholders = policy;
+    }
+
+    /// How long to wait for files another program has open for writing.
+    pub fn set_lock_retry(&mut self, policy: LockRetryPolicy) {
+        self.lock_retry = policy;
+    }
+
+    /// Copy saves totalling at least `min_bytes` to a snapshot and zip the
+    /// copies, so an emulator saving meanwhile can't tear the archive.
+    /// `None` zips the saves in place.
+    pub fn set_snapshot_threshold(&mut self, min_bytes: Option<u64>) {
+        self.snapshot_min_bytes = min_bytes;
+    }
+
+    /// Directories that archive entry names are made relative to.
+    pub fn set_roots(&mut self, roots: Vec<PathBuf>) {
+        self.roots = roots.iter().map(|root| extended(root)).collect();
+    }
+
     pub fn collect_files(
         &self,
         paths: Vec<PathBuf>,
         patterns: Vec<String>,
     ) -> Result<Vec<PathBuf>, PackagerError> {
-        let mut compiled_patterns: Vec<Pattern> = Vec::new();
-        for pattern in patterns {
-            if pattern.trim().is_empty() {
-                continue;
-            }
+        self.collect(paths, patterns).map(|(files, _)| files)
+    }
 
-            match Pattern::new(&pattern) {
-                Ok(p) => compiled_patterns.push(p),
-                Err(err) => warn!("[PACKAGER] Ignoring invalid pattern {pattern}: {err}"),
-            }
-        }
+    /// Matching files, and the placeholders that were left out
+    fn collect(
+        &self,
+        paths: Vec<PathBuf>,
+        patterns: Vec<String>,
+    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>), PackagerError> {
+        let compiled_patterns = compile_patterns(patterns);
 
         let mut files: Vec<PathBuf> = Vec::new();
+        let mut placeholders: Vec<PathBuf> = Vec::new();
 
-        for path in paths {
+        // `content://` trees are read from a local copy
+        for path in local_roots(paths) {
             if path.as_os_str().is_empty() {
                 continue;
             }
+            // Files found under an extended directory stay extended, so
+            // saves deeper than MAX_PATH still open on Windows
+            let path = extended(&path);
 
             match fs::metadata(&path) {
                 Ok(metadata) => {
                     if metadata.is_dir() {
-                        self.collect_from_directory(&path, &compiled_patterns, &mut files);
+                        self.collect_from_directory(
+                            &path,
+                            &compiled_patterns,
+                            &mut files,
+                            &mut placeholders,
+                        );
                     } else if metadata.is_file() {
-                        if self.matches_patterns(&path, &compiled_patterns) {
-                            files.push(path);
-                        }
+                        self.add_file(
+                            path,
+                            &metadata,
+                            &compiled_patterns,
+                            &mut files,
+                            &mut placeholders,
+                        );
                     }
                 }
                 Err(err) => {
@@ -114,13 +331,26 @@ impl SavePackager {
 
         files.sort();
         files.dedup();
+        placeholders.sort();
+        placeholders.dedup();
 
         if files.is_empty() {
+            // Packaging nothing but stubs would overwrite real saves elsewhere
+            if !placeholders.is_empty() {
+                return Err(PackagerError::Placeholders(placeholders.len()));
+            }
             return Err(PackagerError::NoFiles);
         }
 
+        if !placeholders.is_empty() {
+            warn!(
+                "[PACKAGER] Left out {} online-only files for {}",
+                placeholders.len(),
+                self.game_id
+            );
+        }
         info!("[PACKAGER] Found {} files for packaging", files.len());
-        Ok(files)
+        Ok((files, placeholders))
     }
 
     pub fn create_archive(&mut self, files: Vec<PathBuf>) -> Result<PathBuf, PackagerError> {
@@ -129,39 +359,104 @@ impl SavePackager {
             .clone()
             .ok_or_else(|| PackagerError::InvalidInput("version_id not set".into()))?;
 
-        // Use system temp directory to avoid triggering file watcher
-        let archives_dir = std::env::temp_dir().join("crosssave_archives");
+        let archives_dir = staging().dir();
 
-        fs::create_dir_all(&archives_dir).map_err(|err| PackagerError::Io(err.to_string()))?;
+        fs::create_dir_all(archives_dir).map_err(|err| {
+            if is_storage_full(&err) {
+                PackagerError::StorageFull(err.to_string())
+            } else {
+                PackagerError::Io(err.to_string())
+            }
+        })?;
 
         let archive_path = archives_dir.join(format!("{}_{}.zip", self.game_id, version_id));
-        let file = fs::File::create(&archive_path)
-            .map_err(|err| PackagerError::Archive(err.to_string()))?;
+        let file = fs::File::create(&archive_path).map_err(PackagerError::write)?;
+        staging().track(&archive_path);
+        if let Err(err) = self.write_archive(file, &files) {
+            // A partial archive is no use to anyone
+            staging().release(&archive_path);
+            return Err(err);
+        }
+
+        self.archive_path = Some(archive_path.clone());
+        info!("[PACKAGER] Archive created at {:?}", archive_path);
+        Ok(archive_path)
+    }
+
+    /// Write a zstd-compressed copy of `archive_path` to staging as
+    /// `<name>.zst`, for backends that take compressed uploads. Returns
+    /// `None` when it wouldn't be smaller than the zip; otherwise the caller
+    /// releases it from staging once uploaded.
+    pub fn compress_archive(archive_path: &Path) -> Result<Option<PathBuf>, PackagerError> {
+        let archives_dir = staging().dir();
+        fs::create_dir_all(archives_dir).map_err(PackagerError::write)?;
+
+        let name = archive_path
+            .file_stem()
+            .ok_or_else(|| PackagerError::InvalidInput("archive has no name".into()))?;
+        let compressed_path = archives_dir.join(format!("{}.zst", name.to_string_lossy()));
+        let source =
+            fs::File::open(archive_path).map_err(|err| PackagerError::Io(err.to_string()))?;
+        let target = fs::File::create(&compressed_path).map_err(PackagerError::write)?;
+        staging().track(&compressed_path);
+        if let Err(err) =
+            zstd::stream::copy_encode(source, target, ZSTD_LEVEL).map_err(PackagerError::write)
+        {
+            staging().release(&compressed_path);
+            return Err(err);
+        }
+
+        let size = |path: &Path| fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+        let (original, compressed) = (size(archive_path), size(&compressed_path));
+        if compressed == 0 || compressed >= original {
+            staging().release(&compressed_path);
+            return Ok(None);
+        }
+        debug!(
+            "[PACKAGER] Compressed {:?} from {} to {} bytes",
+            archive_path, original, compressed
+        );
+        Ok(Some(compressed_path))
+    }
+
+    fn write_archive(&self, file: fs::File, files: &[PathBuf]) -> Result<(), PackagerError> {
         let mut zip = ZipWriter::new(file);
-        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut written: HashSet<String> = HashSet::new();
 
         for (index, file_path) in files.iter().enumerate() {
-            let entry_name = self.entry_name(file_path, index);
+            let entry_name = self.entry_name(file_path, index)?;
             if entry_name.is_empty() {
-                warn!("[PACKAGER] Skipping file with empty name: {:?}", file_path);
+                warn!("[PACKAGER] Skipping {:?}: its name is empty", file_path);
                 continue;
             }
 
+            if !written.insert(entry_name.clone()) {
+                warn!(
+                    "[PACKAGER] Skipping duplicate entry {entry_name} from {:?}",
+                    file_path
+                );
+                continue;
+            }
+
+            // Encrypted or already-compressed data does not shrink, so store
+            // it byte for byte
+            let options = if self.encrypted || self.is_store_only(file_path) {
+                stored
+            } else {
+                deflated
+            };
             zip.start_file(entry_name.clone(), options)
-                .map_err(|err| PackagerError::Archive(err.to_string()))?;
+                .map_err(PackagerError::zip)?;
 
             let mut source =
                 fs::File::open(file_path).map_err(|err| PackagerError::Io(err.to_string()))?;
-            io::copy(&mut source, &mut zip)
-                .map_err(|err| PackagerError::Archive(err.to_string()))?;
+            io::copy(&mut source, &mut zip).map_err(PackagerError::write)?;
         }
 
-        zip.finish()
-            .map_err(|err| PackagerError::Archive(err.to_string()))?;
-
-        self.archive_path = Some(archive_path.clone());
-        info!("[PACKAGER] Archive created at {:?}", archive_path);
-        Ok(archive_path)
+        zip.finish().map_err(PackagerError::zip)?;
+        Ok(())
     }
 
     pub fn generate_metadata(&self, files: Vec<PathBuf>) -> Result<SaveMetadata, PackagerError> {
@@ -187,7 +482,8 @@ impl SavePackager {
 
         info!("[PACKAGER] Archive hash: {archive_hash}");
 
-        let metadata = SaveMetadata {
+        let mut metadata = SaveMetadata {
+            schema_version: METADATA_SCHEMA_VERSION,
             game_id: self.game_id.clone(),
             emulator_id: self.emulator_id.clone(),
             timestamp,
@@ -197,7 +493,19 @@ impl SavePackager {
             size_bytes: Some(archive_size),
             sha256: Some(archive_hash.clone()),
             source: Some("local".to_string()),
+            encrypted: self.encrypted,
+            thumbnail: None,
+            thumbnail_sha256: None,
+            note: None,
+            tags: Vec::new(),
+            fingerprint: self.fingerprint.clone(),
+            pinned: false,
+            parent_version_id: None,
+            title: None,
+            platform: None,
+            cover_sha256: None,
         };
+        attach_bundled(&mut metadata);
 
         info!("[PACKAGER] Final metadata: {:?}", metadata);
         Ok(metadata)
@@ -208,24 +516,161 @@ impl SavePackager {
         paths: Vec<PathBuf>,
         patterns: Vec<String>,
     ) -> Result<PackagedSave, PackagerError> {
+        let paths = local_roots(paths);
+        self.roots = extended_dirs(&paths);
+
         let files = self.collect_files(paths, patterns)?;
+
+        let fingerprint = content_fingerprint(&files);
+        if self.previous_fingerprint.as_deref() == Some(fingerprint.as_str()) {
+            info!(
+                "[PACKAGER] Saves for {} unchanged; skipping archive",
+                self.game_id
+            );
+            return Err(PackagerError::Unchanged);
+        }
+        self.fingerprint = Some(fingerprint);
+
+        // Zipping a file the emulator is still writing captures half a save
+        if let Some(locked) = wait_for_unlocked(&files, self.lock_retry) {
+            return Err(PackagerError::FileLocked(
+                simplified(&locked).display().to_string(),
+            ));
+        }
+
         let timestamp = self.current_timestamp()?;
         let file_list = self.file_names_for_metadata(&files);
         let version_id = Self::generate_version_id(timestamp, &file_list);
 
         self.timestamp = Some(timestamp);
         self.version_id = Some(version_id.clone());
+        self.encrypted = saves_are_encrypted(self.encryption, &files);
+        if self.encrypted {
+            info!(
+                "[PACKAGER] Saves for {} are encrypted; storing as-is",
+                self.game_id
+            );
+        }
 
-        let archive_path = self.create_archive(files.clone())?;
-        let metadata = self.generate_metadata(files)?;
+        let archive_path = self.archive_files(&files)?;
+        let metadata = match self.generate_metadata(files) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                staging().release(&archive_path);
+                return Err(err);
+            }
+        };
 
         Ok(PackagedSave {
-            archive_path: archive_path.to_string_lossy().to_string(),
+            archive_path,
             metadata,
         })
     }
 
-    fn collect_from_directory(&self, dir: &Path, patterns: &[Pattern], files: &mut Vec<PathBuf>) {
+    /// Zip `files`, from a snapshot copy when they are large enough to
+    /// take a while
+    fn archive_files(&mut self, files: &[PathBuf]) -> Result<PathBuf, PackagerError> {
+        let total_bytes: u64 = files
+            .iter()
+            .filter_map(|file| fs::metadata(file).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        let Some(min_bytes) = self.snapshot_min_bytes else {
+            return self.create_archive(files.to_vec());
+        };
+        if total_bytes < min_bytes {
+            return self.create_archive(files.to_vec());
+        }
+
+        // Copies keep their entry names, so the archive reads the same as
+        // one zipped in place
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut sources: Vec<(PathBuf, String)> = Vec::with_capacity(files.len());
+        for (index, file) in files.iter().enumerate() {
+            let entry_name = self.entry_name(file, index)?;
+            if !entry_name.is_empty() && seen.insert(entry_name.clone()) {
+                sources.push((file.clone(), entry_name));
+            }
+        }
+        let snapshot = snapshot_files(&sources, staging().dir()).map_err(|err| match err {
+            SnapshotError::Io(err) => PackagerError::write(err),
+            SnapshotError::Changing(path) => {
+                PackagerError::StillWriting(simplified(&path).display().to_string())
+            }
+        })?;
+
+        let roots = std::mem::replace(&mut self.roots, vec![snapshot.dir.clone()]);
+        let archived = self.create_archive(snapshot.files.clone());
+        self.roots = roots;
+        archived
+    }
+
+    /// Resolve the files `package_save` would archive without writing anything
+    pub fn preview(
+        &mut self,
+        paths: Vec<PathBuf>,
+        patterns: Vec<String>,
+    ) -> Result<PackagePreview, PackagerError> {
+        let paths = local_roots(paths);
+        self.roots = extended_dirs(&paths);
+
+        let (files, placeholders) = self.collect(paths, patterns)?;
+        let encrypted = saves_are_encrypted(self.encryption, &files);
+
+        let mut preview_files = Vec::with_capacity(files.len());
+        let mut total_size_bytes = 0u64;
+        let mut estimated_compressed_bytes = ZIP_END_RECORD_BYTES;
+        let mut seen: HashSet<String> = HashSet::new();
+
+        for (index, path) in files.iter().enumerate() {
+            // Same entry naming and duplicate handling as `create_archive`
+            let entry_name = self.entry_name(path, index)?;
+            if entry_name.is_empty() || !seen.insert(entry_name.clone()) {
+                continue;
+            }
+
+            let size_bytes = fs::metadata(path)
+                .map(|metadata| metadata.len())
+                .unwrap_or_default();
+            let stored = encrypted || self.is_store_only(path);
+
+            let compressed = if stored {
+                size_bytes
+            } else {
+                let ratio = sample_entropy(path).map_or(1.0, |entropy| entropy / 8.0);
+                (size_bytes as f64 * ratio).ceil() as u64
+            };
+
+            total_size_bytes += size_bytes;
+            estimated_compressed_bytes +=
+                compressed + ZIP_ENTRY_OVERHEAD_BYTES + 2 * entry_name.len() as u64;
+            preview_files.push(PreviewFile {
+                path: simplified(path).to_string_lossy().to_string(),
+                entry_name,
+                size_bytes,
+                stored,
+            });
+        }
+
+        Ok(PackagePreview {
+            files: preview_files,
+            total_size_bytes,
+            estimated_compressed_bytes,
+            encrypted,
+            placeholders: placeholders
+                .iter()
+                .map(|path| simplified(path).to_string_lossy().to_string())
+                .collect(),
+        })
+    }
+
+    fn collect_from_directory(
+        &self,
+        dir: &Path,
+        patterns: &[Pattern],
+        files: &mut Vec<PathBuf>,
+        placeholders: &mut Vec<PathBuf>,
+    ) {
         let mut stack = vec![dir.to_path_buf()];
 
         while let Some(current) = stack.pop() {
@@ -244,9 +689,7 @@ impl SavePackager {
                         if metadata.is_dir() {
                             stack.push(path);
                         } else if metadata.is_file() {
-                            if self.matches_patterns(&path, patterns) {
-                                files.push(path);
-                            }
+                            self.add_file(path, &metadata, patterns, files, placeholders);
                         }
                     }
                     Err(err) => warn!("[PACKAGER] Failed to read metadata for {:?}: {}", path, err),
@@ -255,6 +698,97 @@ impl SavePackager {
         }
     }
 
+    /// Add `path` when it matches; a placeholder is downloaded first or
+    /// left out, depending on the profile
+    fn add_file(
+        &self,
+        path: PathBuf,
+        metadata: &fs::Metadata,
+        patterns: &[Pattern],
+        files: &mut Vec<PathBuf>,
+        placeholders: &mut Vec<PathBuf>,
+    ) {
+        if !is_placeholder(&path, metadata) {
+            if self.should_include(&path, metadata.len(), patterns) {
+                files.push(path);
+            }
+            return;
+        }
+
+        // Patterns are meant for the file an iCloud stub stands for
+        let target = icloud_target(&path).unwrap_or_else(|| path.clone());
+        if !self.should_include(&target, metadata.len(), patterns) {
+            return;
+        }
+
+        match self.placeholders {
+            PlaceholderPolicy::Skip => {
+                warn!(
+                    "[PACKAGER] Skipping {:?}: only an online placeholder is on disk",
+                    simplified(&target)
+                );
+                placeholders.push(target);
+            }
+            PlaceholderPolicy::Hydrate => match hydrate(&path) {
+                Ok(real) => {
+                    let size = fs::metadata(&real)
+                        .map(|metadata| metadata.len())
+                        .unwrap_or_default();
+                    if self.should_include(&real, size, patterns) {
+                        files.push(real);
+                    }
+                }
+                Err(err) => {
+                    warn!(
+                        "[PACKAGER] Skipping {:?}: failed to download placeholder: {}",
+                        simplified(&target),
+                        err
+                    );
+                    placeholders.push(target);
+                }
+            },
+        }
+    }
+
+    fn should_include(&self, path: &Path, size: u64, patterns: &[Pattern]) -> bool {
+        // Patterns are written against ordinary paths, not `\\?\` ones
+        let path = &simplified(path);
+        if !self.matches_patterns(path, patterns) {
+            return false;
+        }
+
+        if self
+            .exclude_patterns
+            .iter()
+            .any(|pattern| pattern.matches_path(path))
+        {
+            debug!("[PACKAGER] Excluding {:?} by pattern", path);
+            return false;
+        }
+
+        if let Some(max) = self.max_file_size_bytes {
+            if size > max {
+                debug!(
+                    "[PACKAGER] Excluding {:?}: {} bytes exceeds limit of {}",
+                    path, size, max
+                );
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn is_store_only(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        let name = name.to_ascii_lowercase();
+        self.store_only_extensions
+            .iter()
+            .any(|suffix| name.ends_with(suffix.as_str()))
+    }
+
     fn matches_patterns(&self, path: &Path, patterns: &[Pattern]) -> bool {
         if patterns.is_empty() {
             return true;
@@ -263,15 +797,38 @@ impl SavePackager {
         patterns.iter().any(|pattern| pattern.matches_path(path))
     }
 
-    fn entry_name(&self, path: &Path, index: usize) -> String {
-        path.file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| name.to_string())
-            .unwrap_or_else(|| format!("file_{index}"))
+    /// Archive entry name for `path`: relative to the deepest matching root,
+    /// using `/` separators, or the bare file name when no root contains it.
+    /// Fails when the name is not valid UTF-8: the file could be neither
+    /// archived under a name it would restore to nor left out unnoticed.
+    fn entry_name(&self, path: &Path, index: usize) -> Result<String, PackagerError> {
+        let relative = self
+            .roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .and_then(|root| path.strip_prefix(root).ok())
+            .filter(|relative| !relative.as_os_str().is_empty());
+
+        let name = match (relative, path.file_name()) {
+            (Some(relative), _) => archive_entry_name(relative),
+            (None, Some(name)) => name.to_str().map(str::to_string),
+            (None, None) => Some(format!("file_{index}")),
+        };
+        name.ok_or_else(|| {
+            PackagerError::InvalidInput(format!(
+                "{} has a name that isn't valid UTF-8",
+                simplified(path).display()
+            ))
+        })
     }
 
     fn file_names_for_metadata(&self, files: &[PathBuf]) -> Vec<String> {
-        let mut names: Vec<String> = files.iter().map(|path| self.entry_name(path, 0)).collect();
+        let mut names: Vec<String> = files
+            .iter()
+            .filter_map(|path| self.entry_name(path, 0).ok())
+            .filter(|name| !name.is_empty())
+            .collect();
         names.sort();
         names.dedup();
         names
@@ -292,12 +849,175 @@ impl SavePackager {
     }
 
     fn calculate_archive_hash(&self, archive_path: &Path) -> Result<String, PackagerError> {
-        let mut file =
-            fs::File::open(archive_path).map_err(|err| PackagerError::Hash(err.to_string()))?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .map_err(|err| PackagerError::Hash(err.to_string()))?;
+        file_sha256(archive_path)
+    }
+}
+
+/// Hex SHA-256 of a file's contents, as recorded in `SaveMetadata::hash`
+pub fn file_sha256(path: &Path) -> Result<String, PackagerError> {
+    let mut file = fs::File::open(path).map_err(|err| PackagerError::Hash(err.to_string()))?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)
+        .map_err(|err| PackagerError::Hash(err.to_string()))?;
+
+    Ok(format!("{:x}", Sha256::digest(buffer)))
+}
+
+/// The archive's bytes as they are uploaded, and the encoding they ended up
+/// with. With `encoding` set, `SavePackager::compress_archive` writes a
+/// `.zst` of the archive and that is sent instead; the zip is sent as is
+/// when `encoding` is `None` or compressing doesn't make it smaller.
+pub fn encode_for_upload(
+    archive_path: &Path,
+    encoding: Option<ArchiveEncoding>,
+) -> Result<(Vec<u8>, Option<ArchiveEncoding>), PackagerError> {
+    if encoding == Some(ArchiveEncoding::Zstd) {
+        if let Some(compressed_path) = SavePackager::compress_archive(archive_path)? {
+            let compressed = fs::read(&compressed_path);
+            staging().release(&compressed_path);
+            let compressed = compressed.map_err(|err| PackagerError::Io(err.to_string()))?;
+            return Ok((compressed, encoding));
+        }
+    }
+    let archive = fs::read(archive_path).map_err(|err| PackagerError::Io(err.to_string()))?;
+    Ok((archive, None))
+}
+
+/// Downloaded archive bytes as a zip, decompressing them when they were
+/// uploaded with zstd
+pub fn decode_download(bytes: Vec<u8>) -> Result<Vec<u8>, PackagerError> {
+    if !bytes.starts_with(&ZSTD_MAGIC) {
+        return Ok(bytes);
+    }
+    zstd::stream::decode_all(bytes.as_slice())
+        .map_err(|err| PackagerError::Archive(err.to_string()))
+}
+
+/// Like `decode_download`, for an archive already written to `path`. The
+/// zip replaces the compressed file only once it is complete.
+pub fn decode_download_file(path: &Path) -> Result<(), PackagerError> {
+    let mut magic = [0u8; 4];
+    let mut source = fs::File::open(path).map_err(|err| PackagerError::Io(err.to_string()))?;
+    if source.read_exact(&mut magic).is_err() || magic != ZSTD_MAGIC {
+        return Ok(());
+    }
+    drop(source);
+
+    let source = fs::File::open(path).map_err(|err| PackagerError::Io(err.to_string()))?;
+    let decoded_path = path.with_extension("zip.decoding");
+    let decoded = fs::File::create(&decoded_path).map_err(PackagerError::write)?;
+    if let Err(err) = zstd::stream::copy_decode(source, decoded).map_err(PackagerError::write) {
+        let _ = fs::remove_file(&decoded_path);
+        return Err(err);
+    }
+    fs::rename(&decoded_path, path).map_err(|err| PackagerError::Io(err.to_string()))
+}
+
+fn compile_patterns(patterns: Vec<String>) -> Vec<Pattern> {
+    let mut compiled: Vec<Pattern> = Vec::new();
+    for pattern in patterns {
+        if pattern.trim().is_empty() {
+            continue;
+        }
+
+        match Pattern::new(&pattern) {
+            Ok(p) => compiled.push(p),
+            Err(err) => warn!("[PACKAGER] Ignoring invalid pattern {pattern}: {err}"),
+        }
+    }
+    compiled
+}
+
+/// Directories among `paths`, extended so entry names can be taken
+/// relative to them
+fn extended_dirs(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .map(|path| extended(path))
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// Cheap change detector: hashes each file's path, size and modification
+/// time without reading its contents
+fn content_fingerprint(files: &[PathBuf]) -> String {
+    let mut entries: Vec<String> = files
+        .iter()
+        .map(|path| {
+            let (size, modified) = fs::metadata(path)
+                .map(|metadata| {
+                    let modified = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|duration| duration.as_nanos())
+                        .unwrap_or_default();
+                    (metadata.len(), modified)
+                })
+                .unwrap_or_default();
+            // The plain form, so fingerprints stay as they were before
+            // paths were extended
+            format!("{}|{size}|{modified}", simplified(path).to_string_lossy())
+        })
+        .collect();
+    entries.sort();
+    entries.dedup();
+
+    format!("{:x}", Sha256::digest(entries.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn save_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crosssave-packager-{name}-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_names_that_are_not_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = save_dir("utf8");
+        fs::write(dir.join("game.srm"), b"battery").unwrap();
+        fs::write(dir.join(OsStr::from_bytes(b"slot\xff.state")), b"state").unwrap();
+
+        let mut packager = SavePackager::new("game".into(), "emu".into());
+        let result = packager.package_save(vec![dir.clone()], Vec::new());
+        assert!(matches!(result, Err(PackagerError::InvalidInput(_))));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filters_leave_out_excluded_and_oversized_files() {
+        let dir = save_dir("filters");
+        fs::create_dir_all(dir.join("slot1")).unwrap();
+        fs::write(dir.join("game.srm"), b"battery").unwrap();
+        fs::write(dir.join("slot1").join("game.state"), b"state").unwrap();
+        fs::write(dir.join("slot1").join("game.state.bak"), b"backup").unwrap();
+        fs::write(dir.join("movie.mp4"), vec![0u8; 64]).unwrap();
+
+        let mut packager = SavePackager::new("game".into(), "emu".into());
+        packager.set_filters(vec!["*.bak".into()], Some(32));
+        let packaged = packager
+            .package_save(vec![dir.clone()], Vec::new())
+            .unwrap();
+
+        let mut archive =
+            zip::ZipArchive::new(fs::File::open(&packaged.archive_path).unwrap()).unwrap();
+        let mut names: Vec<String> = (0..archive.len())
+            .map(|index| archive.by_index(index).unwrap().name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["game.srm", "slot1/game.state"]);
 
-        Ok(format!("{:x}", Sha256::digest(buffer)))
+        staging().release(&packaged.archive_path);
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,259 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::core::file_provider::{content_uri, is_content_uri, list_content};
+use crate::core::paths::{extended, simplified};
+use crate::core::placeholder::find_placeholders;
+use crate::core::storage::{is_network_filesystem, volume_space};
+
+/// Free space below which a save directory is flagged before it fills up
+const LOW_SPACE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Something that will make packaging or restoring a save path fail.
+/// Serialized as the code errors start with.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+pub enum PathIssue {
+    #[serde(rename = "path_missing")]
+    Missing,
+    #[serde(rename = "path_not_directory")]
+    NotADirectory,
+    #[serde(rename = "path_not_readable")]
+    NotReadable,
+    #[serde(rename = "path_not_writable")]
+    NotWritable,
+    #[serde(rename = "path_low_space")]
+    LowSpace,
+}
+
+impl PathIssue {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PathIssue::Missing => "path_missing",
+            PathIssue::NotADirectory => "path_not_directory",
+            PathIssue::NotReadable => "path_not_readable",
+            PathIssue::NotWritable => "path_not_writable",
+            PathIssue::LowSpace => "path_low_space",
+        }
+    }
+}
+
+/// Error for a save path that failed its check; the message starts with
+/// the issue's code so the UI can tell what to fix
+#[derive(Debug, Error)]
+#[error("{}: {}", .issue.code(), .path)]
+pub struct PathCheckError {
+    pub issue: PathIssue,
+    pub path: String,
+}
+
+impl PathCheckError {
+    fn new(issue: PathIssue, path: &Path) -> Self {
+        Self {
+            issue,
+            path: simplified(path).to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// What `check_path_status` found for one save path
+#[derive(Debug, Serialize)]
+pub struct PathReport {
+    pub path: String,
+    pub exists: bool,
+    pub is_dir: bool,
+    pub readable: bool,
+    pub writable: bool,
+    /// SMB, NFS and similar shares, which are watched by polling
+    pub is_network_fs: bool,
+    pub free_space_bytes: Option<u64>,
+    /// `None` when it couldn't be probed, e.g. in a read-only folder
+    pub case_sensitive: Option<bool>,
+    pub issues: Vec<PathIssue>,
+    pub error: Option<String>,
+    /// OneDrive or iCloud files under the path that are only online
+    pub placeholders: Vec<String>,
+}
+
+/// Check existence, permissions, free space and case sensitivity of a save
+/// path. Writability is probed with a temporary file the watcher ignores.
+pub fn check_path(path: &Path) -> PathReport {
+    if let Some(uri) = content_uri(path) {
+        // Granted SAF trees are checked by listing them; the grant covers
+        // writing too
+        let error = list_content(uri).err().map(|err| err.to_string());
+        let ok = error.is_none();
+        return PathReport {
+            path: uri.to_string(),
+            exists: ok,
+            is_dir: ok,
+            readable: ok,
+            writable: ok,
+            is_network_fs: false,
+            free_space_bytes: None,
+            case_sensitive: None,
+            issues: if ok {
+                Vec::new()
+            } else {
+                vec![PathIssue::NotReadable]
+            },
+            error,
+            placeholders: Vec::new(),
+        };
+    }
+
+    let mut report = PathReport {
+        path: path.display().to_string(),
+        exists: path.exists(),
+        is_dir: path.is_dir(),
+        readable: false,
+        writable: false,
+        is_network_fs: false,
+        free_space_bytes: None,
+        case_sensitive: None,
+        issues: Vec::new(),
+        error: None,
+        placeholders: Vec::new(),
+    };
+    if !report.exists {
+        report.issues.push(PathIssue::Missing);
+        return report;
+    }
+    if !report.is_dir {
+        report.issues.push(PathIssue::NotADirectory);
+        return report;
+    }
+
+    report.is_network_fs = is_network_filesystem(path);
+    report.free_space_bytes = volume_space(path).map(|space| space.available_bytes);
+    if report
+        .free_space_bytes
+        .is_some_and(|free| free < LOW_SPACE_BYTES)
+    {
+        report.issues.push(PathIssue::LowSpace);
+    }
+
+    if let Err(err) = fs::read_dir(extended(path)) {
+        report.issues.push(PathIssue::NotReadable);
+        report.error = Some(err.to_string());
+        return report;
+    }
+    report.readable = true;
+    report.placeholders = find_placeholders(path)
+        .iter()
+        .map(|file| simplified(file).to_string_lossy().to_string())
+        .collect();
+
+    match probe_write(path) {
+        Ok(case_sensitive) => {
+            report.writable = true;
+            report.case_sensitive = Some(case_sensitive);
+        }
+        Err(err) => {
+            report.issues.push(PathIssue::NotWritable);
+            report.error = Some(err.to_string());
+        }
+    }
+    report
+}
+
+/// Before packaging: at least one save path exists and none that exist is
+/// unreadable. `content://` trees are left to the provider.
+pub fn ensure_readable(paths: &[PathBuf]) -> Result<(), PathCheckError> {
+    let mut found = false;
+    for path in paths {
+        if is_content_uri(path) {
+            found = true;
+            continue;
+        }
+        if !path.exists() {
+            continue;
+        }
+        found = true;
+        let readable = if path.is_dir() {
+            fs::read_dir(extended(path)).is_ok()
+        } else {
+            fs::File::open(extended(path)).is_ok()
+        };
+        if !readable {
+            return Err(PathCheckError::new(PathIssue::NotReadable, path));
+        }
+    }
+    match paths.first() {
+        Some(first) if !found => Err(PathCheckError::new(PathIssue::Missing, first)),
+        _ => Ok(()),
+    }
+}
+
+/// Before restoring into `dir`: it can be written and has room for at least
+/// `needed_bytes`. Free space the OS doesn't report is not checked.
+pub fn ensure_writable(dir: &Path, needed_bytes: u64) -> Result<(), PathCheckError> {
+    if is_content_uri(dir) {
+        return Ok(());
+    }
+    if probe_write(dir).is_err() {
+        return Err(PathCheckError::new(PathIssue::NotWritable, dir));
+    }
+    if volume_space(dir).is_some_and(|space| space.available_bytes < needed_bytes) {
+        return Err(PathCheckError::new(PathIssue::LowSpace, dir));
+    }
+    Ok(())
+}
+
+/// Create and remove a lowercase-named file in `dir`, reporting whether the
+/// folder told it apart from the same name in uppercase
+fn probe_write(dir: &Path) -> std::io::Result<bool> {
+    // `.tmp` is in the watcher's default ignores
+    let name = format!(".crosssave-probe-{}.tmp", Uuid::new_v4().simple());
+    let probe = extended(&dir.join(&name));
+    fs::write(&probe, b"")?;
+    let case_sensitive = !extended(&dir.join(name.to_uppercase())).exists();
+    let _ = fs::remove_file(&probe);
+    Ok(case_sensitive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_flags_missing_and_file_paths() {
+        let dir = std::env::temp_dir().join(format!("crosssave-pathcheck-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let report = check_path(&dir);
+        assert!(report.readable && report.writable);
+        assert!(report.case_sensitive.is_some());
+        assert!(!report.issues.contains(&PathIssue::NotWritable));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0, "probe removed");
+
+        let missing = check_path(&dir.join("missing"));
+        assert_eq!(missing.issues, vec![PathIssue::Missing]);
+
+        let file = dir.join("save.srm");
+        fs::write(&file, b"save").unwrap();
+        assert_eq!(check_path(&file).issues, vec![PathIssue::NotADirectory]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn packaging_needs_one_existing_path() {
+        let dir = std::env::temp_dir().join(format!("crosssave-pathcheck-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let missing = dir.join("missing");
+
+        assert!(ensure_readable(&[missing.clone(), dir.clone()]).is_ok());
+        let err = ensure_readable(&[missing]).unwrap_err();
+        assert_eq!(err.issue, PathIssue::Missing);
+        assert!(err.to_string().starts_with("path_missing: "));
+
+        assert!(ensure_writable(&dir, 0).is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,291 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// `path` in a form the OS opens regardless of length. On Windows, absolute
+/// paths get the `\\?\` (or `\\?\UNC\`) prefix, which also turns off `/`
+/// and `..` handling, so the path is normalized first. Every file found
+/// under an extended directory is extended too, however short the
+/// directory itself is. Elsewhere paths are returned unchanged.
+pub fn extended(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        use std::ffi::OsString;
+        use std::path::Prefix;
+
+        let mut components = path.components();
+        let Some(Component::Prefix(prefix)) = components.next() else {
+            return path.to_path_buf();
+        };
+        let mut extended = match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut head = OsString::from(r"\\?\");
+                head.push(prefix.as_os_str());
+                head
+            }
+            Prefix::UNC(server, share) => {
+                let mut head = OsString::from(r"\\?\UNC\");
+                head.push(server);
+                head.push(r"\");
+                head.push(share);
+                head
+            }
+            // Already verbatim, or a device path
+            _ => return path.to_path_buf(),
+        };
+        // `C:saves` is relative to the drive's current directory
+        if components.next() != Some(Component::RootDir) {
+            return path.to_path_buf();
+        }
+
+        let mut segments = Vec::new();
+        for component in components {
+            match component {
+                Component::Normal(name) => segments.push(name),
+                Component::ParentDir => {
+                    segments.pop();
+                }
+                _ => {}
+            }
+        }
+        if segments.is_empty() {
+            extended.push(r"\");
+        }
+        for segment in segments {
+            extended.push(r"\");
+            extended.push(segment);
+        }
+        PathBuf::from(extended)
+    }
+
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+/// `path` without a `\\?\` prefix, for showing and comparing. The inverse
+/// of `extended`; a no-op outside Windows.
+pub fn simplified(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        use std::ffi::OsString;
+        use std::path::Prefix;
+
+        let mut components = path.components();
+        let Some(Component::Prefix(prefix)) = components.next() else {
+            return path.to_path_buf();
+        };
+        let mut simplified = match prefix.kind() {
+            Prefix::VerbatimDisk(drive) => OsString::from(format!("{}:", char::from(drive))),
+            Prefix::VerbatimUNC(server, share) => {
+                let mut head = OsString::from(r"\\");
+                head.push(server);
+                head.push(r"\");
+                head.push(share);
+                head
+            }
+            _ => return path.to_path_buf(),
+        };
+        let rest = components.as_path();
+        if !rest.as_os_str().is_empty() && !rest.has_root() {
+            return path.to_path_buf();
+        }
+        simplified.push(rest.as_os_str());
+        PathBuf::from(simplified)
+    }
+
+    #[cfg(not(windows))]
+    {
+        path.to_path_buf()
+    }
+}
+
+/// Whether `path` is empty or only whitespace, as left by a cleared field
+/// in the profile editor
+pub fn is_blank(path: &Path) -> bool {
+    path.to_str().is_some_and(|text| text.trim().is_empty())
+}
+
+/// Archive entry name for a relative path, with `/` separators. `None` when
+/// a component is not valid UTF-8, since zip names are text and a lossy name
+/// would restore as a different file.
+pub fn archive_entry_name(relative: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        if let Component::Normal(part) = component {
+            parts.push(part.to_str()?);
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// A path as stored in JSON. Valid UTF-8 is a plain string, as paths have
+/// always been stored; anything else keeps its raw form so it survives a
+/// round trip.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EncodedPath {
+    Text(String),
+    /// Bytes of a Unix path
+    Bytes {
+        bytes: Vec<u8>,
+    },
+    /// UTF-16 units of a Windows path
+    Wide {
+        wide: Vec<u16>,
+    },
+}
+
+impl EncodedPath {
+    fn encode(path: &Path) -> Self {
+        if let Some(text) = path.to_str() {
+            return EncodedPath::Text(text.to_string());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            EncodedPath::Bytes {
+                bytes: path.as_os_str().as_bytes().to_vec(),
+            }
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+            EncodedPath::Wide {
+                wide: path.as_os_str().encode_wide().collect(),
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            EncodedPath::Text(path.to_string_lossy().to_string())
+        }
+    }
+
+    fn decode(self) -> PathBuf {
+        match self {
+            EncodedPath::Text(text) => PathBuf::from(text),
+            EncodedPath::Bytes { bytes } => {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::ffi::OsStringExt;
+                    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+                }
+                #[cfg(not(unix))]
+                {
+                    PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+                }
+            }
+            EncodedPath::Wide { wide } => {
+                #[cfg(windows)]
+                {
+                    use std::os::windows::ffi::OsStringExt;
+                    PathBuf::from(std::ffi::OsString::from_wide(&wide))
+                }
+                #[cfg(not(windows))]
+                {
+                    PathBuf::from(String::from_utf16_lossy(&wide))
+                }
+            }
+        }
+    }
+}
+
+/// `#[serde(with = "crate::core::paths::serde_path")]` for `PathBuf` fields
+pub mod serde_path {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        EncodedPath::encode(path).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        EncodedPath::deserialize(deserializer).map(EncodedPath::decode)
+    }
+}
+
+/// `#[serde(with = "crate::core::paths::serde_paths")]` for `Vec<PathBuf>`
+pub mod serde_paths {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(paths.iter().map(|path| EncodedPath::encode(path)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<PathBuf>, D::Error> {
+        Vec::<EncodedPath>::deserialize(deserializer)
+            .map(|paths| paths.into_iter().map(EncodedPath::decode).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Stored {
+        #[serde(with = "serde_path")]
+        path: PathBuf,
+    }
+
+    #[test]
+    fn utf8_paths_stay_plain_strings() {
+        let stored = Stored {
+            path: PathBuf::from("saves/zelda.srm"),
+        };
+        let json = serde_json::to_string(&stored).expect("serialize");
+        assert_eq!(json, r#"{"path":"saves/zelda.srm"}"#);
+        assert_eq!(
+            serde_json::from_str::<Stored>(&json).expect("deserialize"),
+            stored
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let stored = Stored {
+            path: PathBuf::from(OsStr::from_bytes(b"saves/\xffzelda.srm")),
+        };
+        let json = serde_json::to_string(&stored).expect("serialize");
+        assert_eq!(
+            serde_json::from_str::<Stored>(&json).expect("deserialize"),
+            stored
+        );
+        assert_eq!(archive_entry_name(&stored.path), None);
+    }
+
+    #[test]
+    fn entry_names_use_forward_slashes() {
+        let relative: PathBuf = ["GC", "USA", "save.gci"].iter().collect();
+        assert_eq!(
+            archive_entry_name(&relative).as_deref(),
+            Some("GC/USA/save.gci")
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn absolute_paths_get_the_verbatim_prefix() {
+        let long = format!(r"C:\Saves\{}\..\game.srm", "a".repeat(300));
+        let prefixed = extended(Path::new(&long));
+        assert!(prefixed.to_string_lossy().starts_with(r"\\?\C:\Saves\"));
+        assert!(!prefixed.to_string_lossy().contains(".."));
+        assert_eq!(simplified(&prefixed), PathBuf::from(r"C:\Saves\game.srm"));
+        assert_eq!(
+            extended(Path::new("saves/game.srm")),
+            PathBuf::from("saves/game.srm")
+        );
+
+        let unc = format!(r"\\server\share\{}", "b".repeat(300));
+        assert!(extended(Path::new(&unc))
+            .to_string_lossy()
+            .starts_with(r"\\?\UNC\server\share\"));
+    }
+}
@@ -0,0 +1,300 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use chrono::Utc;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::core::extract::extract_archive;
+use crate::core::history::HistoryManager;
+use crate::core::packager::{file_sha256, PackagedSave, SavePackager};
+use crate::core::staging::staging;
+
+/// Each step runs this many times and the fastest run is reported, so one
+/// slow disk flush does not decide the result
+const RUNS: usize = 3;
+
+/// Time each step is expected to take on the default fixture
+const PACKAGING_BUDGET_MS: u64 = 1_500;
+const HASHING_BUDGET_MS: u64 = 250;
+const EXTRACTION_BUDGET_MS: u64 = 1_000;
+const INDEXING_BUDGET_MS: u64 = 500;
+
+#[derive(Debug, Error)]
+pub enum PerfError {
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("packaging failed: {0}")]
+    Package(String),
+    #[error("extraction failed: {0}")]
+    Extract(String),
+    #[error("history error: {0}")]
+    History(String),
+}
+
+impl From<std::io::Error> for PerfError {
+    fn from(err: std::io::Error) -> Self {
+        PerfError::Io(err.to_string())
+    }
+}
+
+/// Shape of the generated save tree and history. The default resembles a
+/// handheld library: a few memory cards and saves plus two save states.
+#[derive(Clone, Debug)]
+pub struct FixtureSpec {
+    pub small_files: usize,
+    pub small_file_bytes: usize,
+    pub large_files: usize,
+    pub large_file_bytes: usize,
+    pub history_games: usize,
+    pub history_versions: usize,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        Self {
+            small_files: 24,
+            small_file_bytes: 128 * 1024,
+            large_files: 2,
+            large_file_bytes: 4 * 1024 * 1024,
+            history_games: 40,
+            history_versions: 10,
+        }
+    }
+}
+
+/// One measured step and how it compares to its budget
+#[derive(Clone, Debug, Serialize)]
+pub struct PerfMeasurement {
+    pub name: String,
+    pub millis: u64,
+    pub budget_ms: u64,
+    pub within_budget: bool,
+    /// Bytes processed per second, for steps that read the fixture
+    pub throughput_mb_s: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PerfReport {
+    pub fixture_files: usize,
+    pub fixture_bytes: u64,
+    pub history_versions: usize,
+    pub measurements: Vec<PerfMeasurement>,
+    /// Every step finished inside its budget
+    pub within_budget: bool,
+    pub generated_at: u64,
+}
+
+/// Write the save tree described by `spec` under `dir`, returning its size
+pub fn create_save_tree(dir: &Path, spec: &FixtureSpec) -> Result<u64, PerfError> {
+    let mut total = 0u64;
+    let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+    for index in 0..spec.small_files {
+        let sub_dir = dir.join(if index % 2 == 0 { "saves" } else { "memcards" });
+        fs::create_dir_all(&sub_dir)?;
+        let bytes = fixture_bytes(&mut seed, spec.small_file_bytes);
+        fs::write(sub_dir.join(format!("slot{index:02}.srm")), &bytes)?;
+        total += bytes.len() as u64;
+    }
+    let states = dir.join("states");
+    fs::create_dir_all(&states)?;
+    for index in 0..spec.large_files {
+        let bytes = fixture_bytes(&mut seed, spec.large_file_bytes);
+        fs::write(states.join(format!("state{index}.state")), &bytes)?;
+        total += bytes.len() as u64;
+    }
+    Ok(total)
+}
+
+/// Package `dir` the way a sync does
+pub fn package_tree(dir: &Path, game_id: &str) -> Result<PackagedSave, PerfError> {
+    let mut packager = SavePackager::new(game_id.to_string(), "perf".to_string());
+    packager
+        .package_save(vec![dir.to_path_buf()], Vec::new())
+        .map_err(|err| PerfError::Package(err.to_string()))
+}
+
+pub fn hash_archive(archive: &Path) -> Result<String, PerfError> {
+    file_sha256(archive).map_err(|err| PerfError::Package(err.to_string()))
+}
+
+pub fn extract_tree(archive: &Path, dest: &Path) -> Result<usize, PerfError> {
+    extract_archive(archive, dest)
+        .map(|files| files.len())
+        .map_err(|err| PerfError::Extract(err.to_string()))
+}
+
+/// Fill `dir` with `history_games` games of `history_versions` versions,
+/// each a copy of `packaged`
+pub fn create_history(
+    dir: &Path,
+    packaged: &PackagedSave,
+    spec: &FixtureSpec,
+) -> Result<usize, PerfError> {
+    let history = HistoryManager::new_unindexed(dir.to_path_buf(), spec.history_versions, false)
+        .map_err(|err| PerfError::History(err.to_string()))?;
+    let archive = packaged.archive_path.clone();
+    let mut created = 0;
+    for game in 0..spec.history_games {
+        for version in 0..spec.history_versions {
+            let mut metadata = packaged.metadata.clone();
+            metadata.game_id = format!("perf-game-{game:03}");
+            metadata.version_id = format!("{}-{version:02}", metadata.version_id);
+            metadata.timestamp += version as u64;
+            history
+                .save_to_history(metadata, archive.clone())
+                .map_err(|err| PerfError::History(err.to_string()))?;
+            created += 1;
+        }
+    }
+    Ok(created)
+}
+
+/// Load the history under `dir` as startup does, returning the game count
+pub fn index_history(dir: &Path) -> Result<usize, PerfError> {
+    HistoryManager::new_unindexed(dir.to_path_buf(), usize::MAX, false)
+        .and_then(|history| history.index_with_progress(|_, _, _| {}))
+        .map_err(|err| PerfError::History(err.to_string()))
+}
+
+/// Time packaging, hashing, extraction and history indexing on generated
+/// fixtures inside `work_dir`, which is removed afterwards. Blocking.
+pub fn run_perf_report(work_dir: &Path, spec: &FixtureSpec) -> Result<PerfReport, PerfError> {
+    fs::create_dir_all(work_dir)?;
+    let result = measure(work_dir, spec);
+    if let Err(err) = fs::remove_dir_all(work_dir) {
+        debug!("[PERF] Could not remove perf workspace {work_dir:?}: {err}");
+    }
+
+    let report = result?;
+    for measurement in &report.measurements {
+        if !measurement.within_budget {
+            warn!(
+                "[PERF] {} took {} ms, over its {} ms budget",
+                measurement.name, measurement.millis, measurement.budget_ms
+            );
+        }
+    }
+    info!(
+        "[PERF] Measured {} steps; within budget: {}",
+        report.measurements.len(),
+        report.within_budget
+    );
+    Ok(report)
+}
+
+fn measure(work_dir: &Path, spec: &FixtureSpec) -> Result<PerfReport, PerfError> {
+    let tree = work_dir.join("tree");
+    let fixture_bytes = create_save_tree(&tree, spec)?;
+    let game_id = format!("perf-{}", Uuid::new_v4());
+
+    let mut archives = Vec::with_capacity(RUNS);
+    let packaging = fastest(|| {
+        let packaged = package_tree(&tree, &game_id)?;
+        archives.push(packaged.archive_path.clone());
+        Ok(packaged)
+    });
+    let result = packaging.and_then(|(packaging_ms, packaged)| {
+        measure_packaged(work_dir, spec, fixture_bytes, packaging_ms, &packaged)
+    });
+    // Packaged archives are staged outside the workspace
+    for archive in archives {
+        staging().release(&archive);
+    }
+    result
+}
+
+fn measure_packaged(
+    work_dir: &Path,
+    spec: &FixtureSpec,
+    fixture_bytes: u64,
+    packaging_ms: u64,
+    packaged: &PackagedSave,
+) -> Result<PerfReport, PerfError> {
+    let archive = packaged.archive_path.clone();
+    let archive_bytes = fs::metadata(&archive)?.len();
+    let (hashing_ms, _) = fastest(|| hash_archive(&archive))?;
+
+    let mut run = 0;
+    let (extraction_ms, _) = fastest(|| {
+        run += 1;
+        extract_tree(&archive, &work_dir.join(format!("extract-{run}")))
+    })?;
+
+    let history_dir = work_dir.join("history");
+    let history_versions = create_history(&history_dir, packaged, spec)?;
+    let (indexing_ms, _) = fastest(|| index_history(&history_dir))?;
+
+    let (tree, zip) = (Some(fixture_bytes), Some(archive_bytes));
+    let steps = [
+        ("packaging", packaging_ms, PACKAGING_BUDGET_MS, tree),
+        ("hashing", hashing_ms, HASHING_BUDGET_MS, zip),
+        ("extraction", extraction_ms, EXTRACTION_BUDGET_MS, tree),
+        ("history_indexing", indexing_ms, INDEXING_BUDGET_MS, None),
+    ];
+    let measurements: Vec<PerfMeasurement> = steps
+        .into_iter()
+        .map(|(name, millis, budget_ms, bytes)| measurement(name, millis, budget_ms, bytes))
+        .collect();
+    Ok(PerfReport {
+        fixture_files: spec.small_files + spec.large_files,
+        fixture_bytes,
+        history_versions,
+        within_budget: measurements.iter().all(|m| m.within_budget),
+        measurements,
+        generated_at: Utc::now().timestamp().max(0) as u64,
+    })
+}
+
+/// Run `step` `RUNS` times, returning the fastest time and the last result
+fn fastest<T, F>(mut step: F) -> Result<(u64, T), PerfError>
+where
+    F: FnMut() -> Result<T, PerfError>,
+{
+    let mut best = u64::MAX;
+    let mut last = None;
+    for _ in 0..RUNS {
+        let started = Instant::now();
+        let value = step()?;
+        best = best.min(started.elapsed().as_millis() as u64);
+        last = Some(value);
+    }
+    last.map(|value| (best, value))
+        .ok_or_else(|| PerfError::Io("no runs".into()))
+}
+
+fn measurement(name: &str, millis: u64, budget_ms: u64, bytes: Option<u64>) -> PerfMeasurement {
+    PerfMeasurement {
+        name: name.to_string(),
+        millis,
+        budget_ms,
+        within_budget: millis <= budget_ms,
+        throughput_mb_s: bytes.map(|bytes| {
+            let seconds = (millis.max(1) as f64) / 1000.0;
+            (bytes as f64 / (1024.0 * 1024.0)) / seconds
+        }),
+    }
+}
+
+/// Half noise, half repeated runs, so the data compresses about as well as
+/// real saves do
+fn fixture_bytes(seed: &mut u64, len: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(len);
+    while bytes.len() < len {
+        // xorshift64
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        let word = seed.to_le_bytes();
+        if bytes.len() / 4096 % 2 == 0 {
+            bytes.extend_from_slice(&word);
+        } else {
+            bytes.extend(std::iter::repeat(word[0]).take(word.len()));
+        }
+    }
+    bytes.truncate(len);
+    bytes
+}
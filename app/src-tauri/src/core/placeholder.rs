@@ -0,0 +1,203 @@
+//! Cloud-drive placeholders: files OneDrive (Files On-Demand) or iCloud
+//! Drive list in a folder but keep only online. Reading them either
+//! returns nothing useful or blocks on a download, so they are found up
+//! front and either fetched or skipped.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// What packaging does with a placeholder
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaceholderPolicy {
+    /// Leave it out and warn
+    #[default]
+    Skip,
+    /// Have the cloud drive download it first
+    Hydrate,
+}
+
+impl PlaceholderPolicy {
+    pub fn is_skip(&self) -> bool {
+        *self == PlaceholderPolicy::Skip
+    }
+}
+
+/// Whether `path` is only a stand-in for a file kept online
+pub fn is_placeholder(path: &Path, metadata: &fs::Metadata) -> bool {
+    icloud_target(path).is_some() || has_placeholder_flags(metadata)
+}
+
+/// The file an iCloud Drive stub stands for: `.save.srm.icloud` is left in
+/// place of an evicted `save.srm`
+pub fn icloud_target(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let real = name.strip_prefix('.')?.strip_suffix(".icloud")?;
+    if real.is_empty() {
+        return None;
+    }
+    Some(path.with_file_name(real))
+}
+
+#[cfg(windows)]
+fn has_placeholder_flags(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x4_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x40_0000;
+
+    metadata.file_attributes()
+        & (FILE_ATTRIBUTE_OFFLINE
+            | FILE_ATTRIBUTE_RECALL_ON_OPEN
+            | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+#[cfg(target_os = "macos")]
+fn has_placeholder_flags(metadata: &fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+
+    /// Contents live with the file provider until first read
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn has_placeholder_flags(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Download a placeholder and return the path of the real file, which for
+/// an iCloud stub is not the stub itself
+pub fn hydrate(path: &Path) -> io::Result<PathBuf> {
+    if let Some(target) = icloud_target(path) {
+        return download_icloud(path, &target);
+    }
+
+    // OneDrive and dataless APFS files download on first read
+    let mut file = fs::File::open(path)?;
+    io::copy(&mut file, &mut io::sink())?;
+
+    let metadata = fs::metadata(path)?;
+    if has_placeholder_flags(&metadata) {
+        return Err(io::Error::other("still online-only after reading it"));
+    }
+    info!("[PLACEHOLDER] Downloaded {:?}", path);
+    Ok(path.to_path_buf())
+}
+
+#[cfg(target_os = "macos")]
+fn download_icloud(stub: &Path, target: &Path) -> io::Result<PathBuf> {
+    use std::time::Duration;
+
+    /// How long to wait for iCloud to replace the stub with the real file
+    const ICLOUD_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+    const ICLOUD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    let status = std::process::Command::new("brctl")
+        .arg("download")
+        .arg(target)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "brctl download exited with {status}"
+        )));
+    }
+
+    let mut waited = Duration::ZERO;
+    while waited < ICLOUD_DOWNLOAD_TIMEOUT {
+        if target.is_file() && !stub.exists() {
+            info!("[PLACEHOLDER] Downloaded {:?} from iCloud", target);
+            return Ok(target.to_path_buf());
+        }
+        std::thread::sleep(ICLOUD_POLL_INTERVAL);
+        waited += ICLOUD_POLL_INTERVAL;
+    }
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "iCloud did not finish downloading",
+    ))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn download_icloud(_stub: &Path, target: &Path) -> io::Result<PathBuf> {
+    // A stub copied off a Mac; only iCloud there can fetch it
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{:?} can only be downloaded by iCloud Drive", target),
+    ))
+}
+
+/// Placeholders anywhere under `dir`, for `check_path_status`
+pub fn find_placeholders(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!(
+                    "[PLACEHOLDER] Failed to read directory {:?}: {}",
+                    current, err
+                );
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(path);
+            } else if metadata.is_file() && is_placeholder(&path, &metadata) {
+                found.push(path);
+            }
+        }
+    }
+
+    found.sort();
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icloud_stubs_name_the_evicted_file() {
+        assert_eq!(
+            icloud_target(Path::new("/saves/.game.srm.icloud")),
+            Some(PathBuf::from("/saves/game.srm"))
+        );
+        assert_eq!(icloud_target(Path::new("/saves/game.srm")), None);
+        assert_eq!(icloud_target(Path::new("/saves/game.icloud")), None);
+        assert_eq!(icloud_target(Path::new("/saves/..icloud")), None);
+    }
+
+    #[test]
+    fn finds_stubs_but_not_ordinary_files() {
+        let dir =
+            std::env::temp_dir().join(format!("crosssave_placeholder_test_{}", std::process::id()));
+        let nested = dir.join("slot1");
+        fs::create_dir_all(&nested).expect("create dirs");
+        fs::write(dir.join("game.srm"), b"save").expect("write save");
+        fs::write(nested.join(".other.srm.icloud"), b"").expect("write stub");
+
+        assert_eq!(
+            find_placeholders(&dir),
+            vec![nested.join(".other.srm.icloud")]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    sync::{Arc, Mutex, RwLock},
+    thread,
+};
+
+use serde::Serialize;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+use tracing::{debug, warn};
+
+use crate::core::profile::ProfileManager;
+
+/// Combined CPU use, in percent of one core, below which a running
+/// emulator is taken to be paused or sitting in a menu
+const IDLE_CPU_PERCENT: f32 = 5.0;
+
+/// What an emulator's processes are doing right now
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmulatorActivity {
+    NotRunning,
+    /// Running but barely using the CPU; saves are unlikely to be mid-write
+    Idle,
+    Busy,
+}
+
+impl EmulatorActivity {
+    /// Whether packaging now could capture a half-written save
+    pub fn should_defer(self) -> bool {
+        self == EmulatorActivity::Busy
+    }
+}
+
+/// Finds running emulators by the `process_names` in their profiles
+pub struct ProcessWatch {
+    profiles: Arc<RwLock<ProfileManager>>,
+    /// Kept between checks; CPU use is measured from one refresh to the next
+    system: Mutex<Option<System>>,
+}
+
+impl ProcessWatch {
+    pub fn new(profiles: Arc<RwLock<ProfileManager>>) -> Self {
+        Self {
+            profiles,
+            system: Mutex::new(None),
+        }
+    }
+
+    pub fn is_emulator_running(&self, emulator_id: &str) -> bool {
+        self.activity(emulator_id) != EmulatorActivity::NotRunning
+    }
+
+    /// Blocks for a moment on the first call to take a CPU sample, so call
+    /// it off the async runtime
+    pub fn activity(&self, emulator_id: &str) -> EmulatorActivity {
+        let names = match self.profiles.read() {
+            Ok(profiles) => match profiles.get_profile(emulator_id) {
+                Ok(Some(profile)) => profile.process_names,
+                Ok(None) => return EmulatorActivity::NotRunning,
+                Err(err) => {
+                    warn!("[WATCHER] Failed to load profile {emulator_id}: {err}");
+                    return EmulatorActivity::NotRunning;
+                }
+            },
+            Err(err) => {
+                warn!("[WATCHER] Failed to read profiles: {err}");
+                return EmulatorActivity::NotRunning;
+            }
+        };
+        if names.is_empty() {
+            return EmulatorActivity::NotRunning;
+        }
+
+        let Ok(mut guard) = self.system.lock() else {
+            return EmulatorActivity::NotRunning;
+        };
+        let system = guard.get_or_insert_with(|| {
+            let mut system = System::new();
+            refresh(&mut system);
+            thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            system
+        });
+        refresh(system);
+
+        let mut running = false;
+        let mut cpu = 0.0;
+        for process in system.processes().values() {
+            if matches_process(&process.name().to_string_lossy(), &names) {
+                running = true;
+                cpu += process.cpu_usage();
+            }
+        }
+
+        let activity = if !running {
+            EmulatorActivity::NotRunning
+        } else if cpu < IDLE_CPU_PERCENT {
+            EmulatorActivity::Idle
+        } else {
+            EmulatorActivity::Busy
+        };
+        debug!("[WATCHER] {emulator_id} is {activity:?} ({cpu:.1}% CPU)");
+        activity
+    }
+}
+
+fn refresh(system: &mut System) {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::new().with_cpu(),
+    );
+}
+
+/// Compare without case or a trailing `.exe`, so one name in a profile
+/// covers Windows, macOS and Linux builds
+fn matches_process(process_name: &str, names: &[String]) -> bool {
+    let strip = |name: &str| {
+        let name = name.trim().to_ascii_lowercase();
+        match name.strip_suffix(".exe") {
+            Some(stem) => stem.to_string(),
+            None => name,
+        }
+    };
+    let process_name = strip(process_name);
+    names.iter().any(|name| strip(name) == process_name)
+}
@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    ffi::OsString,
     fs,
     path::{Path, PathBuf},
     sync::Mutex,
@@ -9,6 +10,11 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, info};
 
+use super::file_lock::LockRetryPolicy;
+use super::paths::serde_paths;
+use super::placeholder::PlaceholderPolicy;
+use super::profile_bundle::{self, BUNDLE_EXTENSION};
+
 #[derive(Debug, Error)]
 pub enum ProfileError {
     #[error("profiles directory missing at {0}")]
@@ -25,22 +31,187 @@ pub enum ProfileError {
     Io(String),
     #[error("lock error: {0}")]
     Lock(String),
+    #[error("invalid profile bundle: {0}")]
+    Bundle(String),
+    /// Importing would replace it; the user has to confirm first
+    #[error("{0} already exists")]
+    Exists(String),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EmulatorProfile {
     pub emulator_id: String,
     pub name: String,
-    pub default_save_paths: Vec<String>,
+    #[serde(with = "serde_paths")]
+    pub default_save_paths: Vec<PathBuf>,
+    pub file_patterns: Vec<String>,
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    #[serde(default)]
+    pub max_file_size_bytes: Option<u64>,
+    #[serde(default)]
+    pub path_groups: Vec<SavePathGroup>,
+    #[serde(default)]
+    pub game_overrides: HashMap<String, GameOverride>,
+    #[serde(default)]
+    pub save_encryption: SaveEncryption,
+    /// Extra extensions archived without compression, on top of the
+    /// packager's built-in list
+    #[serde(default)]
+    pub store_only_extensions: Vec<String>,
+    /// Folders the emulator writes screenshots to; the newest one taken
+    /// around a save becomes that version's thumbnail
+    #[serde(default, with = "serde_paths")]
+    pub screenshot_dirs: Vec<PathBuf>,
+    /// Timed snapshots to history, on top of whatever the watcher catches
+    #[serde(default)]
+    pub snapshot_schedule: Option<SnapshotSchedule>,
+    /// Executable names of the emulator, with or without `.exe`, so
+    /// packaging can wait while it is writing saves
+    #[serde(default)]
+    pub process_names: Vec<String>,
+    /// Download OneDrive or iCloud placeholders before packaging instead
+    /// of leaving them out
+    #[serde(default)]
+    pub placeholder_policy: PlaceholderPolicy,
+    /// How long packaging waits for save files the emulator keeps open
+    #[serde(default)]
+    pub lock_retry: LockRetryPolicy,
+    /// Saves totalling at least this many bytes are copied to a snapshot
+    /// before zipping, so saving meanwhile can't tear the archive
+    #[serde(default)]
+    pub snapshot_copy_min_bytes: Option<u64>,
+}
+
+/// Whether a profile's saves are encrypted by the emulator. Encrypted saves
+/// are archived as opaque blobs and flagged in their metadata.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveEncryption {
+    /// Sample the files and flag saves that look encrypted
+    #[default]
+    Auto,
+    Encrypted,
+    None,
+}
+
+impl SaveEncryption {
+    fn is_auto(&self) -> bool {
+        *self == SaveEncryption::Auto
+    }
+}
+
+/// Named set of save directories, e.g. one per RetroArch core
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavePathGroup {
+    pub name: String,
+    #[serde(with = "serde_paths")]
+    pub paths: Vec<PathBuf>,
+    /// Falls back to the profile's `file_patterns` when empty
+    #[serde(default)]
+    pub file_patterns: Vec<String>,
+}
+
+/// Package `games` to history every `interval_minutes`, whether or not the
+/// watcher saw a change
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotSchedule {
+    pub interval_minutes: u32,
+    #[serde(default)]
+    pub games: Vec<String>,
+    /// Skip snapshots while none of the profile's `process_names` runs
+    #[serde(default)]
+    pub only_while_running: bool,
+}
+
+/// Per-game replacement for the profile's save location. Explicit `paths`
+/// win over `path_group`; empty `file_patterns` inherit from the group or
+/// profile.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameOverride {
+    #[serde(default)]
+    pub path_group: Option<String>,
+    #[serde(default, with = "serde_paths")]
+    pub paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub file_patterns: Vec<String>,
+}
+
+/// Save paths and patterns that apply to one game
+#[derive(Clone, Debug, Serialize)]
+pub struct SaveLocation {
+    #[serde(serialize_with = "serde_paths::serialize")]
+    pub paths: Vec<PathBuf>,
     pub file_patterns: Vec<String>,
 }
 
+impl EmulatorProfile {
+    /// Resolve where `game_id` keeps its saves. Games without an override use
+    /// `default_save_paths`.
+    pub fn save_location(&self, game_id: Option<&str>) -> SaveLocation {
+        let mut location = SaveLocation {
+            paths: self.default_save_paths.clone(),
+            file_patterns: self.file_patterns.clone(),
+        };
+
+        let Some(game_override) = game_id.and_then(|id| self.game_overrides.get(id)) else {
+            return location;
+        };
+
+        if let Some(group) = game_override
+            .path_group
+            .as_deref()
+            .and_then(|name| self.path_groups.iter().find(|group| group.name == name))
+        {
+            location.paths = group.paths.clone();
+            if !group.file_patterns.is_empty() {
+                location.file_patterns = group.file_patterns.clone();
+            }
+        }
+
+        if !game_override.paths.is_empty() {
+            location.paths = game_override.paths.clone();
+        }
+
+        if !game_override.file_patterns.is_empty() {
+            location.file_patterns = game_override.file_patterns.clone();
+        }
+
+        location
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct RawEmulatorProfile {
     emulator_id: String,
     name: String,
-    default_save_paths: Vec<String>,
+    #[serde(with = "serde_paths")]
+    default_save_paths: Vec<PathBuf>,
     file_patterns: Vec<String>,
+    #[serde(default)]
+    exclude_patterns: Vec<String>,
+    #[serde(default)]
+    max_file_size_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    path_groups: Vec<SavePathGroup>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    game_overrides: HashMap<String, GameOverride>,
+    #[serde(default, skip_serializing_if = "SaveEncryption::is_auto")]
+    save_encryption: SaveEncryption,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    store_only_extensions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "serde_paths")]
+    screenshot_dirs: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot_schedule: Option<SnapshotSchedule>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    process_names: Vec<String>,
+    #[serde(default, skip_serializing_if = "PlaceholderPolicy::is_skip")]
+    placeholder_policy: PlaceholderPolicy,
+    #[serde(default, skip_serializing_if = "LockRetryPolicy::is_default")]
+    lock_retry: LockRetryPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot_copy_min_bytes: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -48,6 +219,8 @@ pub struct ProfileManager {
     default_dir: PathBuf,
     user_dir: PathBuf,
     cache: Mutex<HashMap<String, EmulatorProfile>>,
+    /// Discovered profiles that are offered to the user but not persisted
+    suggestions: Mutex<HashMap<String, EmulatorProfile>>,
 }
 
 impl ProfileManager {
@@ -67,6 +240,7 @@ impl ProfileManager {
             default_dir,
             user_dir,
             cache: Mutex::new(HashMap::new()),
+            suggestions: Mutex::new(HashMap::new()),
         };
 
         manager.reload()?;
@@ -98,6 +272,10 @@ impl ProfileManager {
         Self::validate_profile(&profile)?;
         self.persist_profile(&profile)?;
         self.reload()?;
+        self.suggestions
+            .lock()
+            .map_err(|err| ProfileError::Lock(err.to_string()))?
+            .remove(&profile.emulator_id);
         info!("[PROFILE] Saved profile {}", profile.emulator_id);
         Ok(profile)
     }
@@ -106,6 +284,7 @@ impl ProfileManager {
         let user_profile = self.user_dir.join(format!("{emulator_id}.json"));
         if user_profile.exists() {
             fs::remove_file(&user_profile).map_err(|err| ProfileError::Io(err.to_string()))?;
+            let _ = fs::remove_file(self.icon_path(emulator_id));
             info!("[PROFILE] Deleted user profile {emulator_id}");
         } else {
             return Err(ProfileError::InvalidProfile(format!(
@@ -117,6 +296,143 @@ impl ProfileManager {
         Ok(())
     }
 
+    /// Write a profile to `destination`, as a `.crossprofile` bundle (with
+    /// icon, if any) or as plain JSON depending on the extension. Exports the
+    /// profile as stored on disk so path templates are kept.
+    pub fn export_profile(
+        &self,
+        emulator_id: &str,
+        destination: &Path,
+    ) -> Result<(), ProfileError> {
+        let profile = self.stored_profile(emulator_id)?;
+
+        let data = if destination.extension().and_then(|ext| ext.to_str()) == Some(BUNDLE_EXTENSION)
+        {
+            let icon = fs::read(self.icon_path(emulator_id)).ok();
+            profile_bundle::encode_bundle(&profile, icon.as_deref())?
+        } else {
+            serde_json::to_vec_pretty(&profile)
+                .map_err(|err| ProfileError::ProfileParse("serialize".into(), err.to_string()))?
+        };
+
+        fs::write(destination, data).map_err(|err| ProfileError::Io(err.to_string()))?;
+        info!(
+            "[PROFILE] Exported profile {emulator_id} to {:?}",
+            destination
+        );
+        Ok(())
+    }
+
+    /// Validate and install a profile JSON document or `.crossprofile` bundle
+    /// as a user profile. A profile with the same id, built in or not, is
+    /// only replaced when `replace` is set.
+    pub fn import_profile_data(
+        &mut self,
+        data: &[u8],
+        replace: bool,
+    ) -> Result<EmulatorProfile, ProfileError> {
+        let bundle = profile_bundle::decode_profile_data(data)?;
+        let emulator_id = &bundle.profile.emulator_id;
+        if !replace && self.get_profile(emulator_id)?.is_some() {
+            let built_in = self
+                .default_dir
+                .join(format!("{emulator_id}.json"))
+                .exists();
+            return Err(ProfileError::Exists(if built_in {
+                format!("built-in profile {emulator_id}")
+            } else {
+                format!("profile {emulator_id}")
+            }));
+        }
+        let profile = self.save_profile(bundle.profile)?;
+
+        if let Some(icon) = bundle.icon {
+            let icon_path = self.icon_path(&profile.emulator_id);
+            if let Some(parent) = icon_path.parent() {
+                fs::create_dir_all(parent).map_err(|err| ProfileError::Io(err.to_string()))?;
+            }
+            fs::write(&icon_path, icon).map_err(|err| ProfileError::Io(err.to_string()))?;
+        }
+
+        info!("[PROFILE] Imported profile {}", profile.emulator_id);
+        Ok(profile)
+    }
+
+    pub fn list_suggested_profiles(&self) -> Result<Vec<EmulatorProfile>, ProfileError> {
+        let guard = self
+            .suggestions
+            .lock()
+            .map_err(|err| ProfileError::Lock(err.to_string()))?;
+        let mut profiles: Vec<EmulatorProfile> = guard.values().cloned().collect();
+        profiles.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        Ok(profiles)
+    }
+
+    /// Record discovered profiles as suggestions, skipping ids that already
+    /// exist as real profiles. Returns the full suggestion list.
+    pub fn merge_suggestions(
+        &self,
+        profiles: Vec<EmulatorProfile>,
+    ) -> Result<Vec<EmulatorProfile>, ProfileError> {
+        {
+            let existing = self
+                .cache
+                .lock()
+                .map_err(|err| ProfileError::Lock(err.to_string()))?;
+            let mut suggestions = self
+                .suggestions
+                .lock()
+                .map_err(|err| ProfileError::Lock(err.to_string()))?;
+
+            for profile in profiles {
+                if existing.contains_key(&profile.emulator_id) {
+                    continue;
+                }
+                if let Err(err) = Self::validate_profile(&profile) {
+                    debug!(
+                        "[PROFILE] Ignoring suggested profile {}: {err}",
+                        profile.emulator_id
+                    );
+                    continue;
+                }
+                suggestions.insert(profile.emulator_id.clone(), profile);
+            }
+        }
+
+        let suggestions = self.list_suggested_profiles()?;
+        info!(
+            "[PROFILE] {} suggested profiles available",
+            suggestions.len()
+        );
+        Ok(suggestions)
+    }
+
+    fn icon_path(&self, emulator_id: &str) -> PathBuf {
+        self.user_dir
+            .join("icons")
+            .join(format!("{emulator_id}.png"))
+    }
+
+    /// Profile as written in its JSON file (templates unresolved), falling
+    /// back to the cached copy
+    fn stored_profile(&self, emulator_id: &str) -> Result<EmulatorProfile, ProfileError> {
+        for dir in [&self.user_dir, &self.default_dir] {
+            let path = dir.join(format!("{emulator_id}.json"));
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let profile: EmulatorProfile = serde_json::from_str(&content).map_err(|err| {
+                ProfileError::ProfileParse(path.display().to_string(), err.to_string())
+            })?;
+            if profile.emulator_id == emulator_id {
+                return Ok(profile);
+            }
+        }
+
+        self.get_profile(emulator_id)?
+            .ok_or_else(|| ProfileError::InvalidProfile(format!("profile {emulator_id} not found")))
+    }
+
     fn reload(&mut self) -> Result<(), ProfileError> {
         let mut merged: HashMap<String, EmulatorProfile> = HashMap::new();
         let default_profiles = self.load_dir(&self.default_dir)?;
@@ -171,11 +487,33 @@ impl ProfileManager {
 
             let normalized_paths = self.normalize_paths(&raw_profile.default_save_paths)?;
 
+            let mut path_groups = raw_profile.path_groups;
+            for group in &mut path_groups {
+                group.paths = self.normalize_paths(&group.paths)?;
+            }
+
+            let mut game_overrides = raw_profile.game_overrides;
+            for game_override in game_overrides.values_mut() {
+                game_override.paths = self.normalize_paths(&game_override.paths)?;
+            }
+
             profiles.push(EmulatorProfile {
                 emulator_id: raw_profile.emulator_id,
                 name: raw_profile.name,
                 default_save_paths: normalized_paths,
                 file_patterns: raw_profile.file_patterns,
+                exclude_patterns: raw_profile.exclude_patterns,
+                max_file_size_bytes: raw_profile.max_file_size_bytes,
+                path_groups,
+                game_overrides,
+                save_encryption: raw_profile.save_encryption,
+                store_only_extensions: raw_profile.store_only_extensions,
+                screenshot_dirs: self.normalize_paths(&raw_profile.screenshot_dirs)?,
+                snapshot_schedule: raw_profile.snapshot_schedule,
+                process_names: raw_profile.process_names,
+                placeholder_policy: raw_profile.placeholder_policy,
+                lock_retry: raw_profile.lock_retry,
+                snapshot_copy_min_bytes: raw_profile.snapshot_copy_min_bytes,
             });
         }
 
@@ -188,6 +526,18 @@ impl ProfileManager {
             name: profile.name.clone(),
             default_save_paths: profile.default_save_paths.clone(),
             file_patterns: profile.file_patterns.clone(),
+            exclude_patterns: profile.exclude_patterns.clone(),
+            max_file_size_bytes: profile.max_file_size_bytes,
+            path_groups: profile.path_groups.clone(),
+            game_overrides: profile.game_overrides.clone(),
+            save_encryption: profile.save_encryption,
+            store_only_extensions: profile.store_only_extensions.clone(),
+            screenshot_dirs: profile.screenshot_dirs.clone(),
+            snapshot_schedule: profile.snapshot_schedule.clone(),
+            process_names: profile.process_names.clone(),
+            placeholder_policy: profile.placeholder_policy,
+            lock_retry: profile.lock_retry,
+            snapshot_copy_min_bytes: profile.snapshot_copy_min_bytes,
         };
 
         let json = serde_json::to_string_pretty(&raw)
@@ -197,42 +547,84 @@ impl ProfileManager {
         Ok(())
     }
 
-    fn normalize_paths(&self, paths: &[String]) -> Result<Vec<String>, ProfileError> {
-        let mut validated: Vec<String> = Vec::new();
+    fn normalize_paths(&self, paths: &[PathBuf]) -> Result<Vec<PathBuf>, ProfileError> {
+        let mut validated: Vec<PathBuf> = Vec::new();
 
         for path in paths {
-            let expanded = self.expand_home(path)?;
-            validated.push(expanded.to_string_lossy().to_string());
+            // Templates are plain text; a path that is not has none to expand
+            let templated = match path.to_str() {
+                Some(text) => match self.expand_templates(text) {
+                    Some(expanded) => expanded,
+                    None => continue,
+                },
+                None => path.clone(),
+            };
+            validated.push(self.expand_home(templated)?);
         }
 
         Ok(validated)
     }
 
-    fn expand_home(&self, path: &str) -> Result<PathBuf, ProfileError> {
-        if path == "~" || path.starts_with("~/") {
-            match std::env::var("HOME") {
-                Ok(home) => {
-                    let suffix = path.trim_start_matches('~').trim_start_matches('/');
-                    let expanded = if suffix.is_empty() {
-                        PathBuf::from(home)
-                    } else {
-                        Path::new(&home).join(suffix)
-                    };
-                    Ok(expanded)
-                }
-                Err(_) => {
-                    // If HOME is not set (e.g. Android), just return the path as is
-                    // This prevents the app from crashing, even if the path is invalid
-                    debug!("[PROFILE] HOME not set, keeping path as is: {}", path);
-                    Ok(PathBuf::from(path))
+    /// Replace `{VARIABLE}` placeholders with the matching directory for the
+    /// current platform. `None` when a variable has no directory here, e.g.
+    /// `{INTERNAL_STORAGE}` off Android, so the path is dropped instead of
+    /// pointing somewhere unrelated. Braces around anything but an upper
+    /// case name are left in place.
+    fn expand_templates(&self, path: &str) -> Option<PathBuf> {
+        let mut result = OsString::with_capacity(path.len());
+        let mut rest = path;
+
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 1..start + len];
+            result.push(&rest[..start]);
+
+            if is_template_name(name) {
+                match resolve_template(name) {
+                    Some(value) => result.push(value.as_os_str()),
+                    None => {
+                        debug!("[PROFILE] No {{{name}}} on this platform, skipping {path}");
+                        return None;
+                    }
                 }
+            } else {
+                result.push(&rest[start..=start + len]);
+            }
+            rest = &rest[start + len + 1..];
+        }
+
+        result.push(rest);
+        Some(PathBuf::from(result))
+    }
+
+    fn expand_home(&self, path: PathBuf) -> Result<PathBuf, ProfileError> {
+        let Ok(suffix) = path.strip_prefix("~") else {
+            return Ok(path);
+        };
+        match std::env::var_os("HOME") {
+            Some(home) => {
+                let expanded = if suffix.as_os_str().is_empty() {
+                    PathBuf::from(home)
+                } else {
+                    Path::new(&home).join(suffix)
+                };
+                Ok(expanded)
+            }
+            None => {
+                // If HOME is not set (e.g. Android), just return the path as is
+                // This prevents the app from crashing, even if the path is invalid
+                debug!(
+                    "[PROFILE] HOME not set, keeping path as is: {}",
+                    path.display()
+                );
+                Ok(path)
             }
-        } else {
-            Ok(PathBuf::from(path))
         }
     }
 
-    fn validate_profile(profile: &EmulatorProfile) -> Result<(), ProfileError> {
+    pub fn validate_profile(profile: &EmulatorProfile) -> Result<(), ProfileError> {
         if profile.name.trim().is_empty() {
             return Err(ProfileError::InvalidProfile("name cannot be empty".into()));
         }
@@ -243,6 +635,17 @@ impl ProfileManager {
             ));
         }
 
+        // The id doubles as the file name of the user profile
+        if !profile
+            .emulator_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(ProfileError::InvalidProfile(
+                "emulator_id may only contain letters, digits, '_' and '-'".into(),
+            ));
+        }
+
         if profile.default_save_paths.is_empty() {
             return Err(ProfileError::InvalidProfile(
                 "default_save_paths cannot be empty".into(),
@@ -255,6 +658,240 @@ impl ProfileManager {
             ));
         }
 
+        let mut group_names: HashSet<&str> = HashSet::new();
+        for group in &profile.path_groups {
+            if group.name.trim().is_empty() {
+                return Err(ProfileError::InvalidProfile(
+                    "path group name cannot be empty".into(),
+                ));
+            }
+            if !group_names.insert(group.name.as_str()) {
+                return Err(ProfileError::InvalidProfile(format!(
+                    "duplicate path group {}",
+                    group.name
+                )));
+            }
+            if group.paths.is_empty() {
+                return Err(ProfileError::InvalidProfile(format!(
+                    "path group {} has no paths",
+                    group.name
+                )));
+            }
+        }
+
+        for (game_id, game_override) in &profile.game_overrides {
+            if let Some(group) = &game_override.path_group {
+                if !group_names.contains(group.as_str()) {
+                    return Err(ProfileError::InvalidProfile(format!(
+                        "override for {game_id} references unknown path group {group}"
+                    )));
+                }
+            } else if game_override.paths.is_empty() && game_override.file_patterns.is_empty() {
+                return Err(ProfileError::InvalidProfile(format!(
+                    "override for {game_id} changes nothing"
+                )));
+            }
+        }
+
+        let group_patterns = profile
+            .path_groups
+            .iter()
+            .flat_map(|group| &group.file_patterns);
+        let override_patterns = profile
+            .game_overrides
+            .values()
+            .flat_map(|game_override| &game_override.file_patterns);
+
+        for pattern in profile
+            .file_patterns
+            .iter()
+            .chain(&profile.exclude_patterns)
+            .chain(group_patterns)
+            .chain(override_patterns)
+        {
+            if let Err(err) = glob::Pattern::new(pattern) {
+                return Err(ProfileError::InvalidProfile(format!(
+                    "invalid pattern {pattern}: {err}"
+                )));
+            }
+        }
+
+        if profile.max_file_size_bytes == Some(0) {
+            return Err(ProfileError::InvalidProfile(
+                "max_file_size_bytes must be greater than zero".into(),
+            ));
+        }
+
         Ok(())
     }
 }
+
+const ANDROID_INTERNAL_STORAGE: &str = "/storage/emulated/0";
+
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "windows") {
+        env_path("USERPROFILE")
+    } else {
+        env_path("HOME")
+    }
+}
+
+fn is_template_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Directory for a profile template variable on the current platform, or
+/// `None` when the platform has no such directory
+fn resolve_template(name: &str) -> Option<PathBuf> {
+    let android = cfg!(target_os = "android");
+    let windows = cfg!(target_os = "windows");
+    let macos = cfg!(target_os = "macos");
+
+    match name {
+        "HOME" => home_dir(),
+        // Shared storage only exists on Android
+        "INTERNAL_STORAGE" => {
+            if android {
+                env_path("EXTERNAL_STORAGE")
+                    .or_else(|| Some(PathBuf::from(ANDROID_INTERNAL_STORAGE)))
+            } else {
+                None
+            }
+        }
+        "DOCUMENTS" => {
+            if android {
+                resolve_template("INTERNAL_STORAGE").map(|root| root.join("Documents"))
+            } else {
+                home_dir().map(|home| home.join("Documents"))
+            }
+        }
+        "APPDATA" => {
+            if windows {
+                env_path("APPDATA")
+            } else if macos {
+                home_dir().map(|home| home.join("Library").join("Application Support"))
+            } else if android {
+                resolve_template("INTERNAL_STORAGE")
+            } else {
+                env_path("XDG_CONFIG_HOME").or_else(|| home_dir().map(|home| home.join(".config")))
+            }
+        }
+        "LOCALAPPDATA" => {
+            if windows {
+                env_path("LOCALAPPDATA")
+            } else {
+                resolve_template("XDG_DATA_HOME")
+            }
+        }
+        "XDG_DATA_HOME" => {
+            if windows {
+                env_path("LOCALAPPDATA")
+            } else if macos {
+                home_dir().map(|home| home.join("Library").join("Application Support"))
+            } else if android {
+                resolve_template("INTERNAL_STORAGE")
+            } else {
+                env_path("XDG_DATA_HOME")
+                    .or_else(|| home_dir().map(|home| home.join(".local").join("share")))
+            }
+        }
+        "XDG_CONFIG_HOME" => {
+            if windows || macos || android {
+                resolve_template("APPDATA")
+            } else {
+                env_path("XDG_CONFIG_HOME").or_else(|| home_dir().map(|home| home.join(".config")))
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[cfg(not(target_os = "android"))]
+    #[test]
+    fn internal_storage_only_resolves_on_android() {
+        assert_eq!(resolve_template("INTERNAL_STORAGE"), None);
+        assert_eq!(resolve_template("NOT_A_TEMPLATE"), None);
+        assert_eq!(resolve_template("HOME"), home_dir());
+        assert_eq!(
+            resolve_template("DOCUMENTS"),
+            home_dir().map(|home| home.join("Documents"))
+        );
+    }
+
+    #[cfg(not(target_os = "android"))]
+    #[test]
+    fn paths_with_unavailable_templates_are_dropped() {
+        let root = std::env::temp_dir().join(format!("crosssave-profile-{}", Uuid::new_v4()));
+        let default_dir = root.join("defaults");
+        fs::create_dir_all(&default_dir).unwrap();
+        fs::write(
+            default_dir.join("retroarch.json"),
+            r#"{
+                "emulator_id": "retroarch",
+                "name": "RetroArch",
+                "default_save_paths": [
+                    "{INTERNAL_STORAGE}/retroarch/saves",
+                    "{HOME}/retroarch/saves",
+                    "/games/{slot}/saves"
+                ],
+                "file_patterns": ["*.srm"]
+            }"#,
+        )
+        .unwrap();
+
+        let manager = ProfileManager::new(default_dir, root.join("user")).unwrap();
+        let profile = manager.get_profile("retroarch").unwrap().unwrap();
+        let mut expected = Vec::new();
+        if let Some(home) = home_dir() {
+            expected.push(home.join("retroarch/saves"));
+        }
+        expected.push(PathBuf::from("/games/{slot}/saves"));
+        assert_eq!(profile.default_save_paths, expected);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn imports_do_not_replace_profiles_unasked() {
+        let root = std::env::temp_dir().join(format!("crosssave-profile-{}", Uuid::new_v4()));
+        let default_dir = root.join("defaults");
+        fs::create_dir_all(&default_dir).unwrap();
+        let profile = |name: &str| {
+            serde_json::json!({
+                "emulator_id": "retroarch",
+                "name": name,
+                "default_save_paths": ["/games/saves"],
+                "file_patterns": ["*.srm"]
+            })
+            .to_string()
+        };
+        fs::write(default_dir.join("retroarch.json"), profile("RetroArch")).unwrap();
+        let mut manager = ProfileManager::new(default_dir, root.join("user")).unwrap();
+
+        let imported = profile("Imported").into_bytes();
+        let err = manager.import_profile_data(&imported, false).unwrap_err();
+        assert!(matches!(err, ProfileError::Exists(ref what) if what.contains("built-in")));
+        let kept = manager.get_profile("retroarch").unwrap().unwrap();
+        assert_eq!(kept.name, "RetroArch");
+
+        manager.import_profile_data(&imported, true).unwrap();
+        let replaced = manager.get_profile("retroarch").unwrap().unwrap();
+        assert_eq!(replaced.name, "Imported");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{Cursor, Read, Write};
+
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use super::profile::{EmulatorProfile, ProfileError};
+
+/// File extension for shareable profile bundles (a zip with the profile JSON
+/// and an optional PNG icon)
+pub const BUNDLE_EXTENSION: &str = "crossprofile";
+/// Largest profile file or bundle accepted on import
+pub const MAX_PROFILE_DATA_BYTES: usize = 2 * 1024 * 1024;
+
+const PROFILE_ENTRY: &str = "profile.json";
+const ICON_ENTRY: &str = "icon.png";
+const MAX_ICON_BYTES: u64 = 512 * 1024;
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Clone, Debug)]
+pub struct ProfileBundle {
+    pub profile: EmulatorProfile,
+    pub icon: Option<Vec<u8>>,
+}
+
+pub fn is_bundle_data(data: &[u8]) -> bool {
+    data.starts_with(ZIP_SIGNATURE)
+}
+
+/// Build a `.crossprofile` bundle
+pub fn encode_bundle(
+    profile: &EmulatorProfile,
+    icon: Option<&[u8]>,
+) -> Result<Vec<u8>, ProfileError> {
+    let json = serde_json::to_vec_pretty(profile)
+        .map_err(|err| ProfileError::ProfileParse("serialize".into(), err.to_string()))?;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file(PROFILE_ENTRY, options)
+        .map_err(|err| ProfileError::Bundle(err.to_string()))?;
+    zip.write_all(&json)
+        .map_err(|err| ProfileError::Bundle(err.to_string()))?;
+
+    if let Some(icon) = icon {
+        zip.start_file(ICON_ENTRY, options)
+            .map_err(|err| ProfileError::Bundle(err.to_string()))?;
+        zip.write_all(icon)
+            .map_err(|err| ProfileError::Bundle(err.to_string()))?;
+    }
+
+    let cursor = zip
+        .finish()
+        .map_err(|err| ProfileError::Bundle(err.to_string()))?;
+    Ok(cursor.into_inner())
+}
+
+/// Parse either a plain profile JSON document or a `.crossprofile` bundle
+pub fn decode_profile_data(data: &[u8]) -> Result<ProfileBundle, ProfileError> {
+    if data.len() > MAX_PROFILE_DATA_BYTES {
+        return Err(ProfileError::InvalidProfile(format!(
+            "profile data exceeds {MAX_PROFILE_DATA_BYTES} bytes"
+        )));
+    }
+
+    if !is_bundle_data(data) {
+        return Ok(ProfileBundle {
+            profile: parse_profile_json(data)?,
+            icon: None,
+        });
+    }
+
+    let mut archive =
+        ZipArchive::new(Cursor::new(data)).map_err(|err| ProfileError::Bundle(err.to_string()))?;
+
+    let profile_json = {
+        let entry = archive
+            .by_name(PROFILE_ENTRY)
+            .map_err(|_| ProfileError::Bundle(format!("missing {PROFILE_ENTRY}")))?;
+        read_limited(entry, MAX_PROFILE_DATA_BYTES as u64)?
+    };
+    let profile = parse_profile_json(&profile_json)?;
+
+    let icon = match archive.by_name(ICON_ENTRY) {
+        Ok(entry) => {
+            let icon = read_limited(entry, MAX_ICON_BYTES)?;
+            if !icon.starts_with(PNG_SIGNATURE) {
+                return Err(ProfileError::Bundle(format!("{ICON_ENTRY} is not a PNG")));
+            }
+            Some(icon)
+        }
+        Err(_) => None,
+    };
+
+    Ok(ProfileBundle { profile, icon })
+}
+
+fn parse_profile_json(data: &[u8]) -> Result<EmulatorProfile, ProfileError> {
+    serde_json::from_slice(data)
+        .map_err(|err| ProfileError::ProfileParse("import".into(), err.to_string()))
+}
+
+fn read_limited(entry: impl Read, limit: u64) -> Result<Vec<u8>, ProfileError> {
+    let mut buffer = Vec::new();
+    entry
+        .take(limit + 1)
+        .read_to_end(&mut buffer)
+        .map_err(|err| ProfileError::Bundle(err.to_string()))?;
+
+    if buffer.len() as u64 > limit {
+        return Err(ProfileError::Bundle(format!(
+            "bundle entry exceeds {limit} bytes"
+        )));
+    }
+
+    Ok(buffer)
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::Path,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use super::cloud::CloudVersionSummary;
+use super::history::HistoryEntry;
+
+/// Versions at least this similar to the one kept after them are suggested
+pub const SIMILARITY_THRESHOLD: f64 = 0.99;
+/// Changed entries are compared in blocks of this size
+const BLOCK_BYTES: usize = 4 * 1024;
+/// How long a cloud deletion preview can still be confirmed
+const CONFIRMATION_TTL: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Error)]
+pub enum PruneError {
+    #[error("failed to read {0}: {1}")]
+    Read(String, String),
+    #[error("confirmation rejected: {0}")]
+    Confirmation(String),
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneLocation {
+    Local,
+    Cloud,
+}
+
+/// A version that can go because a newer kept version is (nearly) the same
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PruneSuggestion {
+    pub game_id: String,
+    pub version_id: String,
+    /// Newer version that stays and covers the removed one
+    pub kept_version_id: String,
+    pub similarity: f64,
+    pub location: PruneLocation,
+    pub reclaim_bytes: u64,
+}
+
+/// Share of bytes two save archives have in common, from 0.0 to 1.0.
+/// Entries with the same CRC and size count as identical; changed entries
+/// are compared block by block at the same offsets.
+pub fn similarity(a: &Path, b: &Path) -> Result<f64, PruneError> {
+    let mut left = open_archive(a)?;
+    let mut right = open_archive(b)?;
+    let left_entries = entry_index(&mut left, a)?;
+    let right_entries = entry_index(&mut right, b)?;
+
+    let mut total: u64 = 0;
+    let mut same: u64 = 0;
+
+    for (name, (left_crc, left_size)) in &left_entries {
+        let Some((right_crc, right_size)) = right_entries.get(name) else {
+            total += left_size;
+            continue;
+        };
+
+        total += (*left_size).max(*right_size);
+        if left_crc == right_crc && left_size == right_size {
+            same += left_size;
+            continue;
+        }
+
+        let left_bytes = read_entry(&mut left, name, a)?;
+        let right_bytes = read_entry(&mut right, name, b)?;
+        same += left_bytes
+            .chunks(BLOCK_BYTES)
+            .zip(right_bytes.chunks(BLOCK_BYTES))
+            .filter(|(l, r)| l == r)
+            .map(|(l, _)| l.len() as u64)
+            .sum::<u64>();
+    }
+
+    total += right_entries
+        .iter()
+        .filter(|(name, _)| !left_entries.contains_key(*name))
+        .map(|(_, (_, size))| size)
+        .sum::<u64>();
+
+    if total == 0 {
+        return Ok(1.0);
+    }
+    Ok(same as f64 / total as f64)
+}
+
+/// Local versions of one game that duplicate the next newer kept version.
+/// `entries` must be newest first; the newest and pinned versions are kept.
+pub fn local_suggestions(entries: &[HistoryEntry]) -> Vec<PruneSuggestion> {
+    let mut suggestions = Vec::new();
+    let Some(mut kept) = entries.first() else {
+        return suggestions;
+    };
+
+    for candidate in entries.iter().skip(1) {
+        if candidate.metadata.pinned {
+            kept = candidate;
+            continue;
+        }
+
+        match entry_similarity(kept, candidate) {
+            Some(score) if score >= SIMILARITY_THRESHOLD => {
+                suggestions.push(PruneSuggestion {
+                    game_id: candidate.metadata.game_id.clone(),
+                    version_id: candidate.metadata.version_id.clone(),
+                    kept_version_id: kept.metadata.version_id.clone(),
+                    similarity: score,
+                    location: PruneLocation::Local,
+                    reclaim_bytes: archive_size(candidate),
+                });
+            }
+            _ => kept = candidate,
+        }
+    }
+
+    suggestions
+}
+
+/// Cloud versions of `game_id` that duplicate the next newer kept version.
+/// Identical hashes always match; otherwise both versions must also be in
+/// local history so their archives can be compared.
+pub fn cloud_suggestions(
+    game_id: &str,
+    versions: &[CloudVersionSummary],
+    local: &[HistoryEntry],
+) -> Vec<PruneSuggestion> {
+    let mut sorted: Vec<&CloudVersionSummary> = versions.iter().collect();
+    sorted.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    let local_by_id: HashMap<&str, &HistoryEntry> = local
+        .iter()
+        .map(|entry| (entry.metadata.version_id.as_str(), entry))
+        .collect();
+
+    let mut suggestions = Vec::new();
+    let Some(mut kept) = sorted.first().copied() else {
+        return suggestions;
+    };
+
+    for candidate in sorted.into_iter().skip(1) {
+        if candidate.pinned {
+            kept = candidate;
+            continue;
+        }
+
+        let score = if !candidate.sha256.is_empty() && candidate.sha256 == kept.sha256 {
+            Some(1.0)
+        } else {
+            match (
+                local_by_id.get(kept.version_id.as_str()),
+                local_by_id.get(candidate.version_id.as_str()),
+            ) {
+                (Some(kept_entry), Some(candidate_entry)) => {
+                    entry_similarity(kept_entry, candidate_entry)
+                }
+                _ => None,
+            }
+        };
+
+        match score {
+            Some(score) if score >= SIMILARITY_THRESHOLD => {
+                suggestions.push(PruneSuggestion {
+                    game_id: game_id.to_string(),
+                    version_id: candidate.version_id.clone(),
+                    kept_version_id: kept.version_id.clone(),
+                    similarity: score,
+                    location: PruneLocation::Cloud,
+                    reclaim_bytes: candidate.size_bytes,
+                });
+            }
+            _ => kept = candidate,
+        }
+    }
+
+    suggestions
+}
+
+/// Cloud versions past the newest `keep`, newest first. Pinned versions
+/// count toward `keep` but are never returned.
+pub fn versions_beyond(versions: &[CloudVersionSummary], keep: usize) -> Vec<CloudVersionSummary> {
+    let mut sorted: Vec<&CloudVersionSummary> = versions.iter().collect();
+    sorted.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    sorted
+        .into_iter()
+        .skip(keep)
+        .filter(|version| !version.pinned)
+        .cloned()
+        .collect()
+}
+
+/// One-time tokens that tie a cloud deletion to the exact versions the user
+/// was shown, so nothing is deleted without a preview first
+#[derive(Default)]
+pub struct DeletionConfirmations {
+    pending: Mutex<HashMap<String, PendingDeletion>>,
+}
+
+struct PendingDeletion {
+    game_id: String,
+    version_ids: Vec<String>,
+    issued_at: Instant,
+}
+
+impl DeletionConfirmations {
+    pub fn issue(&self, game_id: &str, version_ids: Vec<String>) -> Result<String, PruneError> {
+        let mut pending = self.lock()?;
+        pending.retain(|_, deletion| deletion.issued_at.elapsed() < CONFIRMATION_TTL);
+
+        let token = Uuid::new_v4().to_string();
+        pending.insert(
+            token.clone(),
+            PendingDeletion {
+                game_id: game_id.to_string(),
+                version_ids,
+                issued_at: Instant::now(),
+            },
+        );
+        Ok(token)
+    }
+
+    /// Use up `token`, returning the versions it was issued for
+    pub fn redeem(&self, token: &str, game_id: &str) -> Result<Vec<String>, PruneError> {
+        let deletion = self
+            .lock()?
+            .remove(token)
+            .ok_or_else(|| PruneError::Confirmation("unknown or used token".into()))?;
+        if deletion.issued_at.elapsed() >= CONFIRMATION_TTL {
+            return Err(PruneError::Confirmation("token expired".into()));
+        }
+        if deletion.game_id != game_id {
+            return Err(PruneError::Confirmation(format!(
+                "token was issued for {}",
+                deletion.game_id
+            )));
+        }
+        Ok(deletion.version_ids)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, PendingDeletion>>, PruneError> {
+        self.pending
+            .lock()
+            .map_err(|err| PruneError::Confirmation(format!("lock error: {err}")))
+    }
+}
+
+fn entry_similarity(kept: &HistoryEntry, candidate: &HistoryEntry) -> Option<f64> {
+    if let (Some(kept_hash), Some(candidate_hash)) =
+        (&kept.metadata.sha256, &candidate.metadata.sha256)
+    {
+        if kept_hash == candidate_hash {
+            return Some(1.0);
+        }
+    }
+
+    match similarity(&kept.archive_path, &candidate.archive_path) {
+        Ok(score) => Some(score),
+        Err(err) => {
+            debug!("[HISTORY] Skipping similarity check: {err}");
+            None
+        }
+    }
+}
+
+fn archive_size(entry: &HistoryEntry) -> u64 {
+    entry.metadata.size_bytes.unwrap_or_else(|| {
+        fs::metadata(&entry.archive_path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    })
+}
+
+fn open_archive(path: &Path) -> Result<ZipArchive<fs::File>, PruneError> {
+    let file = fs::File::open(path).map_err(|err| read_error(path, err))?;
+    ZipArchive::new(file)
+        .map_err(|err| PruneError::Read(path.display().to_string(), err.to_string()))
+}
+
+fn entry_index(
+    archive: &mut ZipArchive<fs::File>,
+    path: &Path,
+) -> Result<HashMap<String, (u32, u64)>, PruneError> {
+    let mut entries = HashMap::new();
+    for index in 0..archive.len() {
+        let file = archive
+            .by_index(index)
+            .map_err(|err| PruneError::Read(path.display().to_string(), err.to_string()))?;
+        if file.is_dir() {
+            continue;
+        }
+        entries.insert(file.name().to_string(), (file.crc32(), file.size()));
+    }
+    Ok(entries)
+}
+
+fn read_entry(
+    archive: &mut ZipArchive<fs::File>,
+    name: &str,
+    path: &Path,
+) -> Result<Vec<u8>, PruneError> {
+    let mut file = archive
+        .by_name(name)
+        .map_err(|err| PruneError::Read(path.display().to_string(), err.to_string()))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|err| read_error(path, err))?;
+    Ok(bytes)
+}
+
+fn read_error(path: &Path, err: io::Error) -> PruneError {
+    PruneError::Read(path.display().to_string(), err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// `(timestamp, hash, pinned)` per version; few hashes, so many
+    /// versions look like duplicates
+    fn cloud_versions(specs: &[(u64, u8, bool)]) -> Vec<CloudVersionSummary> {
+        specs
+            .iter()
+            .enumerate()
+            .map(|(index, &(timestamp, hash, pinned))| CloudVersionSummary {
+                version_id: format!("v{index}"),
+                timestamp,
+                size_bytes: 1,
+                device_id: "device".to_string(),
+                file_list: vec!["save.srm".to_string()],
+                sha256: format!("hash-{hash}"),
+                thumbnail_url: None,
+                note: None,
+                tags: Vec::new(),
+                pinned,
+                parent_version_id: None,
+                encrypted: false,
+            })
+            .collect()
+    }
+
+    fn specs() -> impl Strategy<Value = Vec<(u64, u8, bool)>> {
+        proptest::collection::vec((0u64..1_000, 0u8..3, any::<bool>()), 0..20)
+    }
+
+    proptest! {
+        #[test]
+        fn keep_n_never_deletes_pinned_or_kept_versions(specs in specs(), keep in 0usize..10) {
+            let versions = cloud_versions(&specs);
+            let beyond = versions_beyond(&versions, keep);
+
+            let mut newest: Vec<&CloudVersionSummary> = versions.iter().collect();
+            newest.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            let unpinned_beyond = newest.iter().skip(keep).filter(|v| !v.pinned).count();
+            prop_assert_eq!(beyond.len(), unpinned_beyond);
+            prop_assert!(beyond.iter().all(|version| !version.pinned));
+
+            // Nothing newer than the `keep`th version is deleted
+            if let Some(cutoff) = keep.checked_sub(1).and_then(|index| newest.get(index)) {
+                prop_assert!(beyond.iter().all(|version| version.timestamp <= cutoff.timestamp));
+            }
+        }
+
+        #[test]
+        fn suggestions_keep_newest_and_pinned_versions(specs in specs()) {
+            let versions = cloud_versions(&specs);
+            let suggestions = cloud_suggestions("game", &versions, &[]);
+
+            let suggested: Vec<&str> =
+                suggestions.iter().map(|s| s.version_id.as_str()).collect();
+            // Ties keep their listed order, and the first of them is the newest
+            let mut sorted: Vec<&CloudVersionSummary> = versions.iter().collect();
+            sorted.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            if let Some(newest) = sorted.first() {
+                prop_assert!(!suggested.contains(&newest.version_id.as_str()));
+            }
+            for version in versions.iter().filter(|version| version.pinned) {
+                prop_assert!(!suggested.contains(&version.version_id.as_str()));
+            }
+
+            // Each removed version is covered by one that stays, with the same content
+            let by_id: HashMap<&str, &CloudVersionSummary> =
+                versions.iter().map(|v| (v.version_id.as_str(), v)).collect();
+            for suggestion in &suggestions {
+                prop_assert!(!suggested.contains(&suggestion.kept_version_id.as_str()));
+                let kept = by_id[suggestion.kept_version_id.as_str()];
+                let removed = by_id[suggestion.version_id.as_str()];
+                prop_assert_eq!(&kept.sha256, &removed.sha256);
+                prop_assert!(kept.timestamp >= removed.timestamp);
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::core::cloud::{CloudBackend, CloudVersionSummary};
+use crate::core::history::{HistoryEntry, HistoryManager};
+use crate::core::settings::SettingsManager;
+use crate::core::sync::{determine_sync_action, SyncDecision, SyncManager, UploadJob};
+use crate::core::sync_state::SyncStateStore;
+
+#[derive(Debug, Error)]
+pub enum ReconcileError {
+    #[error("cloud error: {0}")]
+    Cloud(String),
+    #[error("settings error: {0}")]
+    Settings(String),
+    #[error("invalid choice: {0}")]
+    InvalidChoice(String),
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileState {
+    LocalOnly,
+    CloudOnly,
+    /// Both sides have saves and the latest ones differ
+    Differs,
+    InSync,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileDirection {
+    /// Push the latest local version
+    Upload,
+    /// Restore the latest cloud version
+    Download,
+    /// Leave the game to the background sync rules
+    Auto,
+}
+
+/// The latest version on one side
+#[derive(Clone, Debug, Serialize)]
+pub struct VersionBrief {
+    pub version_id: String,
+    pub timestamp: u64,
+    pub size_bytes: Option<u64>,
+    pub device_id: Option<String>,
+}
+
+impl From<&HistoryEntry> for VersionBrief {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            version_id: entry.metadata.version_id.clone(),
+            timestamp: entry.metadata.timestamp,
+            size_bytes: entry.metadata.size_bytes,
+            device_id: None,
+        }
+    }
+}
+
+impl From<&CloudVersionSummary> for VersionBrief {
+    fn from(version: &CloudVersionSummary) -> Self {
+        Self {
+            version_id: version.version_id.clone(),
+            timestamp: version.timestamp,
+            size_bytes: Some(version.size_bytes),
+            device_id: Some(version.device_id.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GameReconciliation {
+    pub game_id: String,
+    pub state: ReconcileState,
+    pub local: Option<VersionBrief>,
+    pub cloud: Option<VersionBrief>,
+    /// What background sync would do on its own; `Auto` when it would ask
+    pub suggested: ReconcileDirection,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReconciliationReport {
+    pub games: Vec<GameReconciliation>,
+    pub generated_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReconcileChoice {
+    pub game_id: String,
+    pub direction: ReconcileDirection,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ReconcileOutcome {
+    pub uploads_queued: usize,
+    pub downloads_queued: usize,
+    pub left_to_sync: usize,
+}
+
+/// Whether background sync has to wait for the user's reconciliation.
+/// It does not when the account's cloud is empty, or when this install
+/// already synced before reconciliation existed; both count as reconciled.
+pub async fn reconciliation_required(
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    settings: &SettingsManager,
+    sync_state: Option<&SyncStateStore>,
+) -> Result<bool, ReconcileError> {
+    let mut app_settings = settings
+        .get_settings()
+        .map_err(|err| ReconcileError::Settings(err.to_string()))?;
+    if !app_settings.needs_reconciliation() {
+        return Ok(false);
+    }
+
+    let synced_before = app_settings.cloud.reconciled_for.is_none()
+        && sync_state.is_some_and(|state| state.last_sync().is_some());
+    let cloud_empty = !synced_before
+        && cloud
+            .lock()
+            .await
+            .list_games()
+            .await
+            .map_err(|err| ReconcileError::Cloud(err.to_string()))?
+            .is_empty();
+
+    if synced_before || cloud_empty {
+        info!("[SYNC] Nothing to reconcile for this account");
+        app_settings.mark_reconciled();
+        settings
+            .update_settings(app_settings)
+            .map_err(|err| ReconcileError::Settings(err.to_string()))?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Compare the latest local and cloud version of every game either side has
+pub async fn build_report(
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    history: &HistoryManager,
+    current_device: &str,
+) -> Result<ReconciliationReport, ReconcileError> {
+    let cloud_games = cloud
+        .lock()
+        .await
+        .list_games()
+        .await
+        .map_err(|err| ReconcileError::Cloud(err.to_string()))?;
+    let games: BTreeSet<String> = history.get_games().into_iter().chain(cloud_games).collect();
+
+    let mut report = Vec::with_capacity(games.len());
+    for game_id in games {
+        let local = history.get_latest_version(&game_id);
+        let lineage = local
+            .as_ref()
+            .map(|local| history.lineage(&game_id, &local.metadata.version_id))
+            .unwrap_or_default();
+        let cloud_versions = cloud
+            .lock()
+            .await
+            .list_versions(game_id.clone(), None)
+            .await
+            .map_err(|err| ReconcileError::Cloud(err.to_string()))?;
+        let cloud_latest = cloud_versions.iter().max_by_key(|v| v.timestamp);
+
+        let state = match (&local, cloud_latest) {
+            (Some(_), None) => ReconcileState::LocalOnly,
+            (None, Some(_)) => ReconcileState::CloudOnly,
+            (Some(local), Some(cloud)) if cloud.sha256 == local.metadata.hash => {
+                ReconcileState::InSync
+            }
+            _ => ReconcileState::Differs,
+        };
+        let decision =
+            determine_sync_action(local.as_ref(), &lineage, &cloud_versions, current_device);
+        let suggested = match decision {
+            SyncDecision::Upload => ReconcileDirection::Upload,
+            SyncDecision::Download(_) => ReconcileDirection::Download,
+            SyncDecision::Conflict | SyncDecision::Noop => ReconcileDirection::Auto,
+        };
+
+        report.push(GameReconciliation {
+            local: local.as_ref().map(VersionBrief::from),
+            cloud: cloud_latest.map(VersionBrief::from),
+            game_id,
+            state,
+            suggested,
+        });
+    }
+
+    Ok(ReconciliationReport {
+        games: report,
+        generated_at: Utc::now().timestamp().max(0) as u64,
+    })
+}
+
+/// Queue the chosen direction for each game, then let background sync run.
+/// Games without a choice, or with `Auto`, follow the normal sync rules.
+pub async fn apply_choices(
+    sync: &SyncManager,
+    choices: Vec<ReconcileChoice>,
+) -> Result<ReconcileOutcome, ReconcileError> {
+    let mut outcome = ReconcileOutcome::default();
+    for choice in choices {
+        match choice.direction {
+            ReconcileDirection::Upload => {
+                let local = sync
+                    .history
+                    .get_latest_version(&choice.game_id)
+                    .ok_or_else(|| {
+                        ReconcileError::InvalidChoice(format!(
+                            "{} has no local version",
+                            choice.game_id
+                        ))
+                    })?;
+                sync.queue.add_job(UploadJob::from_entry(&local)).await;
+                outcome.uploads_queued += 1;
+            }
+            ReconcileDirection::Download => {
+                let latest = sync
+                    .cloud
+                    .lock()
+                    .await
+                    .list_versions(choice.game_id.clone(), Some(1))
+                    .await
+                    .map_err(|err| ReconcileError::Cloud(err.to_string()))?
+                    .into_iter()
+                    .max_by_key(|v| v.timestamp)
+                    .ok_or_else(|| {
+                        ReconcileError::InvalidChoice(format!(
+                            "{} has no cloud version",
+                            choice.game_id
+                        ))
+                    })?;
+                sync.downloads
+                    .add_job(choice.game_id, latest.version_id)
+                    .await;
+                outcome.downloads_queued += 1;
+            }
+            ReconcileDirection::Auto => outcome.left_to_sync += 1,
+        }
+    }
+
+    let mut app_settings = sync
+        .settings
+        .get_settings()
+        .map_err(|err| ReconcileError::Settings(err.to_string()))?;
+    app_settings.mark_reconciled();
+    sync.settings
+        .update_settings(app_settings)
+        .map_err(|err| ReconcileError::Settings(err.to_string()))?;
+
+    info!(
+        "[SYNC] Reconciled first sync: {} uploads, {} downloads, {} left to sync",
+        outcome.uploads_queued, outcome.downloads_queued, outcome.left_to_sync
+    );
+    sync.trigger_sync();
+    Ok(outcome)
+}
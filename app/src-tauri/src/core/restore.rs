@@ -0,0 +1,397 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::core::extract::{extract_archive, list_archive, read_entry, ExtractError};
+use crate::core::file_provider::{self, content_uri, is_content_uri, FileProviderError};
+use crate::core::history::{HistoryEntry, HistoryError, HistoryManager};
+use crate::core::packager::{PackagerError, SavePackager};
+use crate::core::path_check::{ensure_writable, PathCheckError, PathIssue};
+use crate::core::paths::{extended, is_blank};
+use crate::core::profile::EmulatorProfile;
+use crate::core::staging;
+use crate::core::storage::is_storage_full;
+
+/// Tag on the version packaged from disk right before a restore
+pub const PRE_RESTORE_TAG: &str = "pre-restore backup";
+
+#[derive(Debug, Error)]
+pub enum RestoreError {
+    #[error("invalid restore target: {0}")]
+    InvalidTarget(String),
+    #[error("pre-restore backup failed: {0}")]
+    Snapshot(String),
+    #[error("packaging failed: {0}")]
+    Package(String),
+    #[error("restore failed: {0}")]
+    Extract(String),
+    #[error("not enough disk space: {0}")]
+    StorageFull(String),
+}
+
+impl RestoreError {
+    /// Stage reported with `sync://download-error`
+    pub fn stage(&self) -> &'static str {
+        match self {
+            RestoreError::InvalidTarget(_) | RestoreError::Extract(_) => "unzip",
+            RestoreError::Snapshot(_) => "pre-restore-backup",
+            RestoreError::Package(_) => "package",
+            RestoreError::StorageFull(_) => "disk-full",
+        }
+    }
+
+    fn write(err: io::Error) -> Self {
+        if is_storage_full(&err) {
+            RestoreError::StorageFull(err.to_string())
+        } else {
+            RestoreError::Extract(err.to_string())
+        }
+    }
+}
+
+impl From<PathCheckError> for RestoreError {
+    fn from(err: PathCheckError) -> Self {
+        match err.issue {
+            PathIssue::LowSpace => RestoreError::StorageFull(err.to_string()),
+            _ => RestoreError::InvalidTarget(err.to_string()),
+        }
+    }
+}
+
+impl From<ExtractError> for RestoreError {
+    fn from(err: ExtractError) -> Self {
+        match err {
+            ExtractError::StorageFull(message) => RestoreError::StorageFull(message),
+            other => RestoreError::Extract(other.to_string()),
+        }
+    }
+}
+
+/// What restoring an archive does to one file in the save directory
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreAction {
+    Create,
+    Overwrite,
+    /// The file on disk already has the archived contents
+    Unchanged,
+}
+
+/// One file an archive would write, from `plan_restore`
+#[derive(Clone, Debug, Serialize)]
+pub struct PlannedFile {
+    /// Relative to the save directory, with `/` separators
+    pub path: String,
+    pub size_bytes: u64,
+    pub action: RestoreAction,
+}
+
+#[derive(Clone, Debug)]
+pub struct RestoreOutcome {
+    pub restored_files: usize,
+    /// Version holding what was on disk before the restore, if anything
+    /// was there that history did not already have
+    pub backup: Option<HistoryEntry>,
+}
+
+/// The directory `profile` restores `game_id` into
+pub fn restore_target(profile: &EmulatorProfile, game_id: &str) -> Result<PathBuf, RestoreError> {
+    let location = profile.save_location(Some(game_id));
+    let Some(save_path) = location.paths.iter().find(|path| !is_blank(path)) else {
+        return Err(RestoreError::InvalidTarget(format!(
+            "no save path for {} / {game_id}",
+            profile.emulator_id
+        )));
+    };
+
+    let target_dir = save_path.clone();
+    // Granted `content://` trees are checked by the provider when written
+    if !target_dir.is_dir() && !is_content_uri(&target_dir) {
+        return Err(RestoreError::InvalidTarget(format!(
+            "save directory missing: {}",
+            target_dir.display()
+        )));
+    }
+    Ok(target_dir)
+}
+
+/// Back up the live saves of `game_id`, then replace the files in
+/// `target_dir` with the contents of `archive_path`. `restoring_timestamp`
+/// is the restored version's time; the backup is dated just before it so
+/// sync keeps treating the restored version as the newest one.
+pub fn restore_version(
+    history: &HistoryManager,
+    profile: &EmulatorProfile,
+    game_id: &str,
+    target_dir: &Path,
+    archive_path: &Path,
+    restoring_timestamp: Option<u64>,
+) -> Result<RestoreOutcome, RestoreError> {
+    let backup = backup_before_restore(
+        history,
+        profile,
+        game_id,
+        target_dir,
+        archive_path,
+        restoring_timestamp,
+    )?;
+    let restored_files = restore_archive(archive_path, target_dir)?;
+
+    Ok(RestoreOutcome {
+        restored_files,
+        backup,
+    })
+}
+
+/// The first half of `restore_version`: check `target_dir` can take the
+/// archive and keep what is on disk as a "pre-restore backup" version
+pub fn backup_before_restore(
+    history: &HistoryManager,
+    profile: &EmulatorProfile,
+    game_id: &str,
+    target_dir: &Path,
+    archive_path: &Path,
+    restoring_timestamp: Option<u64>,
+) -> Result<Option<HistoryEntry>, RestoreError> {
+    // The archive's size is a lower bound on what extracting it needs
+    let archive_size = fs::metadata(archive_path)
+        .map(|metadata| metadata.len())
+        .unwrap_or_default();
+    ensure_writable(target_dir, archive_size)?;
+
+    package_live_saves(
+        history,
+        profile,
+        game_id,
+        vec![PRE_RESTORE_TAG.to_string()],
+        None,
+        restoring_timestamp.map(|timestamp| timestamp.saturating_sub(1)),
+    )
+    .map_err(|err| match err {
+        RestoreError::Package(message) => RestoreError::Snapshot(message),
+        other => other,
+    })
+}
+
+/// What restoring `archive_path` into `target_dir` would write, without
+/// touching anything. Files on disk with the same size are compared byte
+/// for byte; files in `content://` trees only by whether they exist.
+pub fn plan_restore(
+    archive_path: &Path,
+    target_dir: &Path,
+) -> Result<Vec<PlannedFile>, RestoreError> {
+    let entries = list_archive(archive_path)?;
+    let provided: Option<Vec<String>> = match content_uri(target_dir) {
+        Some(uri) => Some(
+            file_provider::list_content(uri)
+                .map_err(|err| RestoreError::InvalidTarget(err.to_string()))?
+                .into_iter()
+                .map(|file| file.relative_path)
+                .collect(),
+        ),
+        None => None,
+    };
+
+    let mut planned = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let action = match &provided {
+            Some(existing) if existing.contains(&entry.path) => RestoreAction::Overwrite,
+            Some(_) => RestoreAction::Create,
+            None => {
+                let on_disk = entry_path(&extended(target_dir), &entry.path);
+                match fs::metadata(&on_disk) {
+                    Err(_) => RestoreAction::Create,
+                    Ok(metadata) if metadata.len() != entry.size_bytes => RestoreAction::Overwrite,
+                    Ok(_) => {
+                        let archived = fs::File::open(archive_path)
+                            .map_err(ExtractError::from)
+                            .and_then(|file| read_entry(file, &entry.path, entry.size_bytes))?;
+                        if fs::read(&on_disk).is_ok_and(|current| current == archived) {
+                            RestoreAction::Unchanged
+                        } else {
+                            RestoreAction::Overwrite
+                        }
+                    }
+                }
+            }
+        };
+        planned.push(PlannedFile {
+            path: entry.path,
+            size_bytes: entry.size_bytes,
+            action,
+        });
+    }
+    Ok(planned)
+}
+
+/// Package the saves currently on disk into a new history version, dated no
+/// later than `not_after`. Returns `None` when there is nothing on disk or it
+/// matches the latest version already in history.
+pub fn package_live_saves(
+    history: &HistoryManager,
+    profile: &EmulatorProfile,
+    game_id: &str,
+    tags: Vec<String>,
+    note: Option<String>,
+    not_after: Option<u64>,
+) -> Result<Option<HistoryEntry>, RestoreError> {
+    let location = profile.save_location(Some(game_id));
+    let paths: Vec<PathBuf> = location
+        .paths
+        .iter()
+        .filter(|path| !is_blank(path))
+        .cloned()
+        .collect();
+
+    let mut packager = SavePackager::new(game_id.to_string(), profile.emulator_id.clone());
+    packager.set_previous_fingerprint(
+        history
+            .get_latest_version(game_id)
+            .and_then(|entry| entry.metadata.fingerprint),
+    );
+    packager.set_filters(
+        profile.exclude_patterns.clone(),
+        profile.max_file_size_bytes,
+    );
+    packager.set_encryption(profile.save_encryption);
+    packager.add_store_only_extensions(profile.store_only_extensions.clone());
+    packager.set_placeholder_policy(profile.placeholder_policy);
+    packager.set_lock_retry(profile.lock_retry);
+    packager.set_snapshot_threshold(profile.snapshot_copy_min_bytes);
+
+    let packaged = match packager.package_save(paths, location.file_patterns) {
+        Ok(packaged) => packaged,
+        Err(PackagerError::NoFiles) | Err(PackagerError::Unchanged) => {
+            debug!("[HISTORY] Saves for {game_id} already in history");
+            return Ok(None);
+        }
+        // Nothing real on disk; the cloud drive still has those files
+        Err(PackagerError::Placeholders(count)) => {
+            warn!(
+                "[HISTORY] Saves for {game_id} are {count} online-only placeholders; nothing to back up"
+            );
+            return Ok(None);
+        }
+        Err(PackagerError::StorageFull(message)) => return Err(RestoreError::StorageFull(message)),
+        Err(err) => return Err(RestoreError::Package(err.to_string())),
+    };
+
+    let mut metadata = packaged.metadata;
+    metadata.tags = tags;
+    metadata.note = note;
+    metadata.source = Some("local".to_string());
+    if let Some(limit) = not_after {
+        metadata.timestamp = metadata.timestamp.min(limit);
+    }
+
+    let archive_path = packaged.archive_path.clone();
+    let saved = history.save_to_history(metadata, archive_path.clone());
+    staging::staging().release(&archive_path);
+
+    let entry = saved.map_err(|err| match err {
+        HistoryError::StorageFull(message) => RestoreError::StorageFull(message),
+        other => RestoreError::Package(other.to_string()),
+    })?;
+    info!(
+        "[HISTORY] Packaged saves on disk for {game_id} as {}",
+        entry.metadata.version_id
+    );
+    Ok(Some(entry))
+}
+
+/// Extract into a staging folder beside `target_dir`, then move each file
+/// into place. A crash mid-extract only leaves the staging folder behind;
+/// every save file is either the old one or the complete new one.
+pub fn restore_archive(archive_path: &Path, target_dir: &Path) -> Result<usize, RestoreError> {
+    if let Some(uri) = content_uri(target_dir) {
+        return restore_content_archive(archive_path, uri);
+    }
+
+    let target_dir = &extended(target_dir);
+    let staging = staging_dir(target_dir)?;
+    if staging.exists() {
+        warn!("[SYNC] Removing leftover restore staging {:?}", staging);
+        fs::remove_dir_all(&staging).map_err(RestoreError::write)?;
+    }
+
+    let result = extract_archive(archive_path, &staging)
+        .map_err(RestoreError::from)
+        .and_then(|files| move_into_place(&staging, target_dir, &files));
+
+    if let Err(err) = fs::remove_dir_all(&staging) {
+        debug!(
+            "[SYNC] Could not remove restore staging {:?}: {err}",
+            staging
+        );
+    }
+    result
+}
+
+/// `content://` trees have no sibling to stage in, so files are extracted
+/// locally and then copied into the tree one by one through the provider
+fn restore_content_archive(archive_path: &Path, uri: &str) -> Result<usize, RestoreError> {
+    let provider_error = |err: FileProviderError| RestoreError::Extract(err.to_string());
+    let staging = file_provider::staging_dir(uri).map_err(provider_error)?;
+
+    let result = extract_archive(archive_path, &staging)
+        .map_err(RestoreError::from)
+        .and_then(|files| {
+            file_provider::write_tree(uri, &staging, &files)
+                .map(|_| files.len())
+                .map_err(provider_error)
+        });
+
+    if let Err(err) = fs::remove_dir_all(&staging) {
+        debug!(
+            "[SYNC] Could not remove restore staging {:?}: {err}",
+            staging
+        );
+    }
+    result
+}
+
+/// Entry names are relative to the profile's save root (e.g.
+/// `GC/USA/Card A/save.gci`), so they map straight onto `target_dir`
+fn move_into_place(
+    staging: &Path,
+    target_dir: &Path,
+    files: &[String],
+) -> Result<usize, RestoreError> {
+    for file in files {
+        let out_path = entry_path(target_dir, file);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(RestoreError::write)?;
+        }
+        fs::rename(entry_path(staging, file), &out_path).map_err(RestoreError::write)?;
+        debug!("[SYNC] Restored {file}");
+    }
+    Ok(files.len())
+}
+
+/// Join `/`-separated entry names one segment at a time; `\\?\` paths
+/// take `/` literally
+fn entry_path(root: &Path, file: &str) -> PathBuf {
+    file.split('/')
+        .fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+/// Sibling of `target_dir`, so the final renames stay on one filesystem
+/// without the staged files showing up inside the watched save folder
+fn staging_dir(target_dir: &Path) -> Result<PathBuf, RestoreError> {
+    let name = target_dir.file_name().ok_or_else(|| {
+        RestoreError::InvalidTarget(format!("{} has no parent", target_dir.display()))
+    })?;
+    let parent = target_dir.parent().ok_or_else(|| {
+        RestoreError::InvalidTarget(format!("{} has no parent", target_dir.display()))
+    })?;
+    let mut staging = OsString::from(".");
+    staging.push(name);
+    staging.push(".crosssave-restore");
+    Ok(parent.join(staging))
+}
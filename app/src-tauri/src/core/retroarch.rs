@@ -0,0 +1,443 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use super::profile::{EmulatorProfile, GameOverride, SavePathGroup};
+
+/// Profile the import updates
+pub const RETROARCH_PROFILE_ID: &str = "retroarch";
+/// Playlist core name for entries whose core RetroArch picks at launch
+const DETECT_CORE: &str = "DETECT";
+
+#[derive(Debug, Error)]
+pub enum RetroArchError {
+    #[error("retroarch.cfg not found")]
+    ConfigNotFound,
+    #[error("failed to read {0}: {1}")]
+    Read(String, String),
+}
+
+/// One playlist entry resolved to where RetroArch keeps its save
+#[derive(Clone, Debug, Serialize)]
+pub struct RetroArchGame {
+    pub game_id: String,
+    pub label: String,
+    pub content_path: PathBuf,
+    /// `None` when the playlist leaves the core to be picked at launch
+    pub core_name: Option<String>,
+    pub save_dir: PathBuf,
+    /// Save file name RetroArch uses for this content, e.g. `Tetris (World).srm`
+    pub save_name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RetroArchImport {
+    pub config_path: PathBuf,
+    pub playlists: usize,
+    pub games: Vec<RetroArchGame>,
+    /// Entries whose path names no file
+    pub skipped: usize,
+}
+
+/// Settings from `retroarch.cfg` that decide where saves go
+#[derive(Clone, Debug, PartialEq)]
+struct SaveSettings {
+    /// `None` puts saves next to the content
+    savefile_dir: Option<PathBuf>,
+    playlist_dir: PathBuf,
+    sort_by_core: bool,
+    sort_by_content_dir: bool,
+}
+
+/// Read RetroArch's config and playlists and resolve every playlist entry
+/// to a game ID and save location. `config_path` defaults to the usual
+/// install locations.
+pub fn scan_library(config_path: Option<&Path>) -> Result<RetroArchImport, RetroArchError> {
+    let config_path = match config_path {
+        Some(path) => path.to_path_buf(),
+        None => config_candidates()
+            .into_iter()
+            .find(|path| path.is_file())
+            .ok_or(RetroArchError::ConfigNotFound)?,
+    };
+    let content = fs::read_to_string(&config_path)
+        .map_err(|err| RetroArchError::Read(config_path.display().to_string(), err.to_string()))?;
+    let config_dir = config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let settings = save_settings(&parse_config(&content), &config_dir);
+    debug!(
+        "[RETROARCH] Save settings from {:?}: {:?}",
+        config_path, settings
+    );
+
+    let mut playlists = 0;
+    let mut skipped = 0;
+    let mut games: BTreeMap<String, RetroArchGame> = BTreeMap::new();
+    for playlist in playlist_files(&settings.playlist_dir) {
+        let content = match fs::read_to_string(&playlist) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!("[RETROARCH] Skipping playlist {:?}: {err}", playlist);
+                continue;
+            }
+        };
+        playlists += 1;
+
+        for entry in parse_playlist(&content) {
+            match resolve_entry(&entry, &settings) {
+                // The same content in several playlists is one game
+                Some(game) => {
+                    games.entry(game.game_id.clone()).or_insert(game);
+                }
+                None => skipped += 1,
+            }
+        }
+    }
+
+    info!(
+        "[RETROARCH] Found {} games in {} playlists",
+        games.len(),
+        playlists
+    );
+    Ok(RetroArchImport {
+        config_path,
+        playlists,
+        games: games.into_values().collect(),
+        skipped,
+    })
+}
+
+/// Point `profile` at the imported games: one path group per core save
+/// folder and an override per game matching only its own save files.
+/// Existing overrides for other games are kept.
+pub fn apply_to_profile(profile: &mut EmulatorProfile, games: &[RetroArchGame]) {
+    for game in games {
+        let group_name = match &game.core_name {
+            Some(core) => core.clone(),
+            None => "RetroArch".to_string(),
+        };
+        match profile
+            .path_groups
+            .iter_mut()
+            .find(|group| group.name == group_name)
+        {
+            Some(group) if !group.paths.contains(&game.save_dir) => {
+                group.paths.push(game.save_dir.clone())
+            }
+            Some(_) => {}
+            None => profile.path_groups.push(SavePathGroup {
+                name: group_name.clone(),
+                paths: vec![game.save_dir.clone()],
+                file_patterns: Vec::new(),
+            }),
+        }
+
+        // Save states share the save's stem, so they sync along with it
+        let stem = Pattern::escape(game.save_name.trim_end_matches(".srm"));
+        profile.game_overrides.insert(
+            game.game_id.clone(),
+            GameOverride {
+                path_group: Some(group_name),
+                paths: Vec::new(),
+                file_patterns: vec![format!("{stem}.srm"), format!("{stem}.state*")],
+            },
+        );
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct PlaylistEntry {
+    path: String,
+    label: String,
+    core_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonPlaylist {
+    #[serde(default)]
+    default_core_name: Option<String>,
+    #[serde(default)]
+    items: Vec<JsonPlaylistItem>,
+}
+
+#[derive(Deserialize)]
+struct JsonPlaylistItem {
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    core_name: Option<String>,
+}
+
+/// Entries of a playlist, in the JSON format of RetroArch 1.7.6 and later
+/// or the older six-lines-per-entry text format
+fn parse_playlist(content: &str) -> Vec<PlaylistEntry> {
+    if let Ok(playlist) = serde_json::from_str::<JsonPlaylist>(content) {
+        return playlist
+            .items
+            .into_iter()
+            .map(|item| PlaylistEntry {
+                path: item.path,
+                label: item.label,
+                core_name: known_core(item.core_name)
+                    .or_else(|| known_core(playlist.default_core_name.clone())),
+            })
+            .collect();
+    }
+
+    // path, label, core path, core name, CRC, database name
+    let lines: Vec<&str> = content.lines().collect();
+    lines
+        .chunks(6)
+        .filter(|entry| entry.len() >= 4)
+        .map(|entry| PlaylistEntry {
+            path: entry[0].trim().to_string(),
+            label: entry[1].trim().to_string(),
+            core_name: known_core(Some(entry[3].trim().to_string())),
+        })
+        .collect()
+}
+
+fn known_core(name: Option<String>) -> Option<String> {
+    name.filter(|name| !name.trim().is_empty() && name != DETECT_CORE)
+}
+
+fn resolve_entry(entry: &PlaylistEntry, settings: &SaveSettings) -> Option<RetroArchGame> {
+    // Content inside an archive is addressed as `archive.zip#rom.sfc`;
+    // the save is named after the archive
+    let content_path = PathBuf::from(entry.path.split('#').next()?.trim());
+    let stem = content_path.file_stem()?.to_str()?.to_string();
+    let label = if entry.label.trim().is_empty() {
+        stem.clone()
+    } else {
+        entry.label.trim().to_string()
+    };
+    let game_id = game_id_for(&label);
+    if game_id.is_empty() {
+        return None;
+    }
+
+    let mut save_dir = match &settings.savefile_dir {
+        Some(dir) => dir.clone(),
+        None => content_path.parent()?.to_path_buf(),
+    };
+    if settings.savefile_dir.is_some() {
+        if settings.sort_by_core {
+            if let Some(core) = &entry.core_name {
+                save_dir.push(core);
+            }
+        }
+        if settings.sort_by_content_dir {
+            if let Some(dir) = content_path.parent().and_then(Path::file_name) {
+                save_dir.push(dir);
+            }
+        }
+    }
+
+    Some(RetroArchGame {
+        game_id,
+        label,
+        content_path: content_path.clone(),
+        core_name: entry.core_name.clone(),
+        save_dir,
+        save_name: format!("{stem}.srm"),
+    })
+}
+
+/// Game ID from a playlist label, which comes from RetroArch's database
+/// when the content was scanned, so it is the same on every device however
+/// the ROM file is named: `Super Mario World (USA)` becomes
+/// `super_mario_world_usa`
+fn game_id_for(label: &str) -> String {
+    let mut id = String::new();
+    for c in label.chars() {
+        if c.is_alphanumeric() {
+            id.extend(c.to_lowercase());
+        } else if !id.is_empty() && !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    id.trim_end_matches('_').chars().take(128).collect()
+}
+
+/// `key = "value"` lines of `retroarch.cfg`
+fn parse_config(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with('#') {
+                return None;
+            }
+            let (key, value) = line.split_once('=')?;
+            Some((
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn save_settings(config: &HashMap<String, String>, config_dir: &Path) -> SaveSettings {
+    let dir = |key: &str| {
+        config
+            .get(key)
+            .filter(|value| !value.is_empty() && value.as_str() != "default")
+            .map(|value| expand_dir(value, config_dir))
+    };
+    let enabled = |key: &str| config.get(key).is_some_and(|value| value == "true");
+
+    let savefile_dir = if enabled("savefiles_in_content_dir") {
+        None
+    } else {
+        dir("savefile_directory")
+    };
+    SaveSettings {
+        savefile_dir,
+        playlist_dir: dir("playlist_directory").unwrap_or_else(|| config_dir.join("playlists")),
+        sort_by_core: enabled("sort_savefiles_enable"),
+        sort_by_content_dir: enabled("sort_savefiles_by_content_enable"),
+    }
+}
+
+/// `~` is the home directory and a leading `:` RetroArch's own directory
+fn expand_dir(value: &str, config_dir: &Path) -> PathBuf {
+    if let Some(rest) = value.strip_prefix(':') {
+        return config_dir.join(rest.trim_start_matches(['/', '\\']));
+    }
+    if let Some(rest) = value.strip_prefix('~') {
+        if let Some(home) = home_dir() {
+            return home.join(rest.trim_start_matches(['/', '\\']));
+        }
+    }
+    PathBuf::from(value)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os(if cfg!(target_os = "windows") {
+        "USERPROFILE"
+    } else {
+        "HOME"
+    })
+    .map(PathBuf::from)
+}
+
+fn config_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if cfg!(target_os = "windows") {
+        if let Ok(appdata) = std::env::var("APPDATA") {
+            candidates.push(Path::new(&appdata).join("RetroArch/retroarch.cfg"));
+        }
+        candidates.push(PathBuf::from("C:/RetroArch-Win64/retroarch.cfg"));
+    } else if cfg!(target_os = "android") {
+        candidates.push(PathBuf::from(
+            "/storage/emulated/0/Android/data/com.retroarch/files/retroarch.cfg",
+        ));
+        candidates.push(PathBuf::from("/storage/emulated/0/RetroArch/retroarch.cfg"));
+    } else if let Some(home) = home_dir() {
+        if cfg!(target_os = "macos") {
+            candidates
+                .push(home.join("Library/Application Support/RetroArch/config/retroarch.cfg"));
+        } else {
+            let config_home = std::env::var("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| home.join(".config"));
+            candidates.push(config_home.join("retroarch/retroarch.cfg"));
+            candidates
+                .push(home.join(".var/app/org.libretro.RetroArch/config/retroarch/retroarch.cfg"));
+        }
+    }
+    candidates
+}
+
+fn playlist_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        debug!("[RETROARCH] No playlists at {:?}", dir);
+        return Vec::new();
+    };
+    let mut playlists: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lpl"))
+        .collect();
+    playlists.sort();
+    playlists
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(savefile_dir: Option<&str>) -> SaveSettings {
+        SaveSettings {
+            savefile_dir: savefile_dir.map(PathBuf::from),
+            playlist_dir: PathBuf::from("/ra/playlists"),
+            sort_by_core: true,
+            sort_by_content_dir: false,
+        }
+    }
+
+    #[test]
+    fn both_playlist_formats_are_read() {
+        let json = r#"{
+            "version": "1.5",
+            "default_core_name": "Snes9x",
+            "items": [
+                { "path": "/roms/snes/smw.sfc", "label": "Super Mario World (USA)", "core_name": "DETECT" },
+                { "path": "/roms/snes/pack.zip#zelda.sfc", "label": "", "core_name": "bsnes" }
+            ]
+        }"#;
+        let entries = parse_playlist(json);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].core_name.as_deref(), Some("Snes9x"));
+        assert_eq!(entries[1].core_name.as_deref(), Some("bsnes"));
+
+        let legacy = "/roms/gb/tetris.gb\nTetris (World)\n/cores/gambatte.so\nGambatte\nDETECT\nNintendo - Game Boy.lpl\n";
+        assert_eq!(
+            parse_playlist(legacy),
+            vec![PlaylistEntry {
+                path: "/roms/gb/tetris.gb".to_string(),
+                label: "Tetris (World)".to_string(),
+                core_name: Some("Gambatte".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn entries_resolve_to_stable_ids_and_core_folders() {
+        let entry = PlaylistEntry {
+            path: "/roms/snes/pack.zip#smw.sfc".to_string(),
+            label: "Super Mario World (USA)".to_string(),
+            core_name: Some("Snes9x".to_string()),
+        };
+        let game = resolve_entry(&entry, &settings(Some("/ra/saves"))).unwrap();
+        assert_eq!(game.game_id, "super_mario_world_usa");
+        assert_eq!(game.save_dir, PathBuf::from("/ra/saves/Snes9x"));
+        assert_eq!(game.save_name, "pack.srm");
+
+        let game = resolve_entry(&entry, &settings(None)).unwrap();
+        assert_eq!(game.save_dir, PathBuf::from("/roms/snes"));
+    }
+
+    #[test]
+    fn config_paths_are_expanded() {
+        let config = parse_config(
+            "# comment\nsavefile_directory = \":/saves\"\nsort_savefiles_enable = \"true\"\nplaylist_directory = \"default\"\n",
+        );
+        let settings = save_settings(&config, Path::new("/ra"));
+        assert_eq!(settings.savefile_dir, Some(PathBuf::from("/ra/saves")));
+        assert_eq!(settings.playlist_dir, PathBuf::from("/ra/playlists"));
+        assert!(settings.sort_by_core);
+        assert!(!settings.sort_by_content_dir);
+    }
+}
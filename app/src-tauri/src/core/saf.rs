@@ -0,0 +1,117 @@
+//! Storage Access Framework bridge. Android 11+ keeps other apps' folders
+//! out of reach of plain paths, so save folders are granted through the
+//! system picker and read through `SafPlugin` on the Kotlin side.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    plugin::{Builder, PluginHandle, TauriPlugin},
+    Manager, Runtime,
+};
+use tracing::info;
+
+use crate::core::file_provider::{self, FileProvider, FileProviderError, ProvidedFile};
+
+const PLUGIN_PACKAGE: &str = "com.h1dr0n.crosssave_cloud";
+const PLUGIN_CLASS: &str = "SafPlugin";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeArgs<'a> {
+    uri: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileArgs<'a> {
+    uri: &'a str,
+    relative_path: &'a str,
+    local_path: String,
+}
+
+#[derive(Deserialize)]
+struct ListResponse {
+    files: Vec<ProvidedFile>,
+}
+
+#[derive(Deserialize)]
+struct PickResponse {
+    uri: Option<String>,
+}
+
+/// `content://` trees read through the Kotlin plugin
+pub struct SafFileProvider<R: Runtime>(PluginHandle<R>);
+
+impl<R: Runtime> SafFileProvider<R> {
+    fn file_args<'a>(uri: &'a str, relative_path: &'a str, local: &Path) -> FileArgs<'a> {
+        FileArgs {
+            uri,
+            relative_path,
+            local_path: local.to_string_lossy().to_string(),
+        }
+    }
+}
+
+impl<R: Runtime> FileProvider for SafFileProvider<R> {
+    fn list_files(&self, root: &str) -> Result<Vec<ProvidedFile>, FileProviderError> {
+        self.0
+            .run_mobile_plugin::<ListResponse>("listFiles", TreeArgs { uri: root })
+            .map(|response| response.files)
+            .map_err(|err| FileProviderError::Provider(err.to_string()))
+    }
+
+    fn read_to(&self, root: &str, relative: &str, dest: &Path) -> Result<(), FileProviderError> {
+        self.0
+            .run_mobile_plugin::<serde_json::Value>(
+                "readFile",
+                Self::file_args(root, relative, dest),
+            )
+            .map(|_| ())
+            .map_err(|err| FileProviderError::Provider(err.to_string()))
+    }
+
+    fn write_from(
+        &self,
+        root: &str,
+        relative: &str,
+        source: &Path,
+    ) -> Result<(), FileProviderError> {
+        self.0
+            .run_mobile_plugin::<serde_json::Value>(
+                "writeFile",
+                Self::file_args(root, relative, source),
+            )
+            .map(|_| ())
+            .map_err(|err| FileProviderError::Provider(err.to_string()))
+    }
+}
+
+/// Picker access for `select_directory`
+pub struct SafPicker<R: Runtime>(PluginHandle<R>);
+
+impl<R: Runtime> SafPicker<R> {
+    /// Let the user pick a folder and keep access to it across restarts.
+    /// `None` when the picker was dismissed.
+    pub fn pick_folder(&self) -> Result<Option<String>, String> {
+        self.0
+            .run_mobile_plugin::<PickResponse>("pickFolder", serde_json::json!({}))
+            .map(|response| response.uri)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Registers the Kotlin plugin and routes `content://` save folders
+/// through it
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("saf")
+        .setup(|app, api| {
+            let handle = api.register_android_plugin(PLUGIN_PACKAGE, PLUGIN_CLASS)?;
+            let mirror_root = app.path().app_cache_dir()?.join("saf_mirror");
+            file_provider::install(Box::new(SafFileProvider(handle.clone())), mirror_root);
+            app.manage(SafPicker(handle));
+            info!("[STARTUP] Storage Access Framework provider ready");
+            Ok(())
+        })
+        .build()
+}
@@ -0,0 +1,335 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::core::history::HistoryManager;
+use crate::core::process_watch::{EmulatorActivity, ProcessWatch};
+use crate::core::profile::{EmulatorProfile, ProfileManager};
+use crate::core::restore::{package_live_saves, RestoreError};
+use crate::core::storage::StorageScope;
+use crate::core::sync::{enforce_history_budget, report_storage_full, SyncManager};
+
+/// Tag on versions the scheduler packaged
+pub const SCHEDULED_TAG: &str = "scheduled";
+/// How often schedules are checked; intervals are whole minutes
+const TICK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("serialization error: {0}")]
+    Serialization(String),
+    #[error("lock error: {0}")]
+    Lock(String),
+}
+
+/// A scheduled game whose interval has passed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DueSnapshot {
+    pub emulator_id: String,
+    pub game_id: String,
+    /// More than one interval was missed, as when the app was closed. Only
+    /// one snapshot is taken for all of them.
+    pub catch_up: bool,
+}
+
+/// When each scheduled game was last packaged, emulator id -> game id
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ScheduleFile {
+    #[serde(default)]
+    last_runs: HashMap<String, HashMap<String, DateTime<Utc>>>,
+}
+
+/// Games whose schedule is due at `now`. A game never snapshotted before is
+/// due right away.
+fn due_snapshots(
+    profiles: &[EmulatorProfile],
+    last_runs: &HashMap<String, HashMap<String, DateTime<Utc>>>,
+    now: DateTime<Utc>,
+) -> Vec<DueSnapshot> {
+    let mut due = Vec::new();
+    for profile in profiles {
+        let Some(schedule) = &profile.snapshot_schedule else {
+            continue;
+        };
+        if schedule.interval_minutes == 0 {
+            continue;
+        }
+        let interval = chrono::Duration::minutes(i64::from(schedule.interval_minutes));
+        let runs = last_runs.get(&profile.emulator_id);
+        for game_id in &schedule.games {
+            let last_run = runs.and_then(|runs| runs.get(game_id));
+            let (is_due, catch_up) = match last_run {
+                Some(at) => (now - *at >= interval, now - *at >= interval * 2),
+                None => (true, false),
+            };
+            if is_due {
+                due.push(DueSnapshot {
+                    emulator_id: profile.emulator_id.clone(),
+                    game_id: game_id.clone(),
+                    catch_up,
+                });
+            }
+        }
+    }
+    due
+}
+
+/// Packages the games each profile's `snapshot_schedule` names at a fixed
+/// interval. Run times are kept on disk, so a schedule that came due while
+/// the app was closed runs once as soon as it starts again.
+pub struct SnapshotScheduler {
+    path: PathBuf,
+    state: Mutex<ScheduleFile>,
+}
+
+impl SnapshotScheduler {
+    pub fn new(path: PathBuf) -> Result<Self, SchedulerError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| SchedulerError::Io(err.to_string()))?;
+        }
+
+        let state = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                warn!("[HISTORY] Failed to parse snapshot schedule: {err}. Starting empty");
+                ScheduleFile::default()
+            }),
+            Err(_) => ScheduleFile::default(),
+        };
+
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Check schedules every minute for as long as the app runs
+    pub fn start(
+        self: Arc<Self>,
+        app: AppHandle,
+        history: Arc<HistoryManager>,
+        profiles: Arc<RwLock<ProfileManager>>,
+    ) {
+        tauri::async_runtime::spawn(async move {
+            info!("[HISTORY] Snapshot scheduler started");
+            loop {
+                let scheduler = self.clone();
+                let app = app.clone();
+                let history = history.clone();
+                let profiles = profiles.clone();
+                let result = tauri::async_runtime::spawn_blocking(move || {
+                    scheduler.run_due(&app, &history, &profiles)
+                })
+                .await;
+                match result {
+                    Ok(Err(err)) => warn!("[HISTORY] Scheduled snapshots failed: {err}"),
+                    Err(err) => warn!("[HISTORY] Snapshot scheduler task failed: {err}"),
+                    Ok(Ok(())) => {}
+                }
+                tokio::time::sleep(TICK).await;
+            }
+        });
+    }
+
+    /// Package every game that is due. Returns once all of them were tried.
+    fn run_due(
+        &self,
+        app: &AppHandle,
+        history: &HistoryManager,
+        profiles: &RwLock<ProfileManager>,
+    ) -> Result<(), SchedulerError> {
+        let profiles = profiles
+            .read()
+            .map_err(|err| SchedulerError::Lock(err.to_string()))?
+            .list_profiles()
+            .unwrap_or_else(|err| {
+                warn!("[HISTORY] Failed to list profiles for scheduled snapshots: {err}");
+                Vec::new()
+            });
+        let due = due_snapshots(&profiles, &self.lock()?.last_runs, Utc::now());
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        let mut packaged = 0;
+        for snapshot in due {
+            let Some(profile) = profiles
+                .iter()
+                .find(|profile| profile.emulator_id == snapshot.emulator_id)
+            else {
+                continue;
+            };
+            let activity = app
+                .try_state::<Arc<ProcessWatch>>()
+                .map(|watch| watch.activity(&profile.emulator_id))
+                .unwrap_or(EmulatorActivity::NotRunning);
+            // Not recorded as a run, so the next tick tries again
+            if activity.should_defer() {
+                debug!(
+                    "[HISTORY] Deferring snapshot of {}; {} is busy",
+                    snapshot.game_id, profile.emulator_id
+                );
+                continue;
+            }
+            let only_while_running = profile
+                .snapshot_schedule
+                .as_ref()
+                .is_some_and(|schedule| schedule.only_while_running);
+            if only_while_running && activity == EmulatorActivity::NotRunning {
+                continue;
+            }
+
+            if snapshot.catch_up {
+                info!(
+                    "[HISTORY] Catching up on missed snapshots of {}",
+                    snapshot.game_id
+                );
+            }
+
+            match package_live_saves(
+                history,
+                profile,
+                &snapshot.game_id,
+                vec![SCHEDULED_TAG.to_string()],
+                None,
+                None,
+            ) {
+                Ok(Some(entry)) => {
+                    info!(
+                        "[HISTORY] Scheduled snapshot of {} saved as {}",
+                        snapshot.game_id, entry.metadata.version_id
+                    );
+                    packaged += 1;
+                }
+                Ok(None) => debug!(
+                    "[HISTORY] Scheduled snapshot of {} skipped; nothing changed",
+                    snapshot.game_id
+                ),
+                Err(RestoreError::StorageFull(message)) => {
+                    report_storage_full(app, StorageScope::Local, message);
+                    // The rest would fail the same way
+                    self.record_run(&snapshot)?;
+                    break;
+                }
+                Err(err) => warn!(
+                    "[HISTORY] Scheduled snapshot of {} failed: {err}",
+                    snapshot.game_id
+                ),
+            }
+            // Failures wait a full interval too, instead of retrying every tick
+            self.record_run(&snapshot)?;
+        }
+
+        if packaged > 0 {
+            enforce_history_budget(app, history);
+            if let Some(sync) = app.try_state::<SyncManager>() {
+                sync.note_activity();
+                sync.trigger_sync();
+            }
+        }
+        Ok(())
+    }
+
+    fn record_run(&self, snapshot: &DueSnapshot) -> Result<(), SchedulerError> {
+        let mut guard = self.lock()?;
+        guard
+            .last_runs
+            .entry(snapshot.emulator_id.clone())
+            .or_default()
+            .insert(snapshot.game_id.clone(), Utc::now());
+        self.persist(&guard)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, ScheduleFile>, SchedulerError> {
+        self.state
+            .lock()
+            .map_err(|err| SchedulerError::Lock(err.to_string()))
+    }
+
+    fn persist(&self, state: &ScheduleFile) -> Result<(), SchedulerError> {
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|err| SchedulerError::Serialization(err.to_string()))?;
+        fs::write(&self.path, json).map_err(|err| SchedulerError::Io(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::profile::SnapshotSchedule;
+
+    fn profile(interval_minutes: u32, games: &[&str]) -> EmulatorProfile {
+        EmulatorProfile {
+            emulator_id: "retroarch".to_string(),
+            name: "RetroArch".to_string(),
+            default_save_paths: Vec::new(),
+            file_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            max_file_size_bytes: None,
+            path_groups: Vec::new(),
+            game_overrides: HashMap::new(),
+            save_encryption: Default::default(),
+            store_only_extensions: Vec::new(),
+            screenshot_dirs: Vec::new(),
+            snapshot_schedule: Some(SnapshotSchedule {
+                interval_minutes,
+                games: games.iter().map(|game| game.to_string()).collect(),
+                only_while_running: false,
+            }),
+            process_names: Vec::new(),
+            placeholder_policy: Default::default(),
+            lock_retry: Default::default(),
+            snapshot_copy_min_bytes: None,
+        }
+    }
+
+    fn ran_at(game_id: &str, at: DateTime<Utc>) -> HashMap<String, HashMap<String, DateTime<Utc>>> {
+        HashMap::from([(
+            "retroarch".to_string(),
+            HashMap::from([(game_id.to_string(), at)]),
+        )])
+    }
+
+    #[test]
+    fn due_once_the_interval_has_passed() {
+        let now = Utc::now();
+        let profiles = [profile(30, &["zelda", "metroid"])];
+        let runs = ran_at("zelda", now - chrono::Duration::minutes(10));
+
+        let due = due_snapshots(&profiles, &runs, now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].game_id, "metroid");
+        assert!(!due[0].catch_up);
+
+        let later = now + chrono::Duration::minutes(20);
+        assert_eq!(due_snapshots(&profiles, &runs, later).len(), 2);
+    }
+
+    #[test]
+    fn missed_intervals_are_caught_up_once() {
+        let now = Utc::now();
+        let profiles = [profile(30, &["zelda"])];
+        let runs = ran_at("zelda", now - chrono::Duration::hours(5));
+
+        let due = due_snapshots(&profiles, &runs, now);
+        assert_eq!(due.len(), 1);
+        assert!(due[0].catch_up);
+    }
+
+    #[test]
+    fn zero_interval_is_off() {
+        let profiles = [profile(0, &["zelda"])];
+        assert!(due_snapshots(&profiles, &HashMap::new(), Utc::now()).is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! Tokens and access keys kept out of settings.json, in the keychain the
+//! OS provides: the login keychain on macOS, the Secret Service on Linux
+//! and the Credential Manager on Windows, all through `keyring`, and the
+//! Android keystore through `SecretsPlugin.kt`.
+
+use thiserror::Error;
+
+/// Service name secrets are filed under in the keychain
+#[cfg(any(target_os = "macos", target_os = "linux", windows))]
+const SERVICE: &str = "crosssave-cloud";
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    /// No keychain on this device, e.g. Linux without a Secret Service
+    #[error("secret store unavailable: {0}")]
+    Unavailable(String),
+    #[error("secret store error: {0}")]
+    Failed(String),
+}
+
+/// Secrets by name, e.g. `cloud.api_key`
+pub trait SecretStore: Send + Sync {
+    fn get(&self, name: &str) -> Result<Option<String>, SecretError>;
+    fn set(&self, name: &str, secret: &str) -> Result<(), SecretError>;
+    /// Deleting a secret that isn't stored succeeds
+    fn delete(&self, name: &str) -> Result<(), SecretError>;
+}
+
+/// The OS keychain of this desktop. `None` on Android, whose store needs
+/// the app running and comes from `android::init`.
+pub fn os_store() -> Option<Box<dyn SecretStore>> {
+    #[cfg(any(target_os = "macos", target_os = "linux", windows))]
+    {
+        Some(Box::new(keychain::Keychain))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+    {
+        None
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", windows))]
+mod keychain {
+    use keyring::{Entry, Error};
+
+    use super::{SecretError, SecretStore, SERVICE};
+
+    pub struct Keychain;
+
+    fn entry(name: &str) -> Result<Entry, SecretError> {
+        Entry::new(SERVICE, name).map_err(SecretError::from)
+    }
+
+    impl From<Error> for SecretError {
+        fn from(err: Error) -> Self {
+            match err {
+                // Without a running Secret Service, e.g. on a headless box
+                // or a Steam Deck in game mode, D-Bus can't be reached
+                Error::NoStorageAccess(_) | Error::PlatformFailure(_) => {
+                    SecretError::Unavailable(err.to_string())
+                }
+                _ => SecretError::Failed(err.to_string()),
+            }
+        }
+    }
+
+    impl SecretStore for Keychain {
+        fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+            match entry(name)?.get_password() {
+                Ok(secret) => Ok(Some(secret)),
+                Err(Error::NoEntry) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        }
+
+        fn set(&self, name: &str, secret: &str) -> Result<(), SecretError> {
+            entry(name)?.set_password(secret).map_err(SecretError::from)
+        }
+
+        fn delete(&self, name: &str) -> Result<(), SecretError> {
+            match entry(name)?.delete_credential() {
+                Ok(()) | Err(Error::NoEntry) => Ok(()),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "android")]
+pub mod android {
+    use serde::Deserialize;
+    use tauri::{
+        plugin::{Builder, PluginHandle, TauriPlugin},
+        Manager, Wry,
+    };
+
+    use super::{SecretError, SecretStore};
+
+    const PLUGIN_PACKAGE: &str = "com.h1dr0n.crosssave_cloud";
+    const PLUGIN_CLASS: &str = "SecretsPlugin";
+
+    #[derive(Deserialize)]
+    struct SecretResponse {
+        secret: Option<String>,
+    }
+
+    /// Secrets encrypted with a key the Android keystore never lets out
+    #[derive(Clone)]
+    pub struct SecretsPlugin(PluginHandle<Wry>);
+
+    impl SecretStore for SecretsPlugin {
+        fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+            self.0
+                .run_mobile_plugin::<SecretResponse>("get", serde_json::json!({ "name": name }))
+                .map(|response| response.secret)
+                .map_err(|err| SecretError::Failed(err.to_string()))
+        }
+
+        fn set(&self, name: &str, secret: &str) -> Result<(), SecretError> {
+            self.0
+                .run_mobile_plugin::<serde_json::Value>(
+                    "set",
+                    serde_json::json!({ "name": name, "secret": secret }),
+                )
+                .map(|_| ())
+                .map_err(|err| SecretError::Failed(err.to_string()))
+        }
+
+        fn delete(&self, name: &str) -> Result<(), SecretError> {
+            self.0
+                .run_mobile_plugin::<serde_json::Value>(
+                    "delete",
+                    serde_json::json!({ "name": name }),
+                )
+                .map(|_| ())
+                .map_err(|err| SecretError::Failed(err.to_string()))
+        }
+    }
+
+    pub fn init() -> TauriPlugin<Wry> {
+        Builder::new("secrets")
+            .setup(|app, api| {
+                let handle = api.register_android_plugin(PLUGIN_PACKAGE, PLUGIN_CLASS)?;
+                app.manage(SecretsPlugin(handle));
+                Ok(())
+            })
+            .build()
+    }
+}
+
+/// Secrets held in memory, for tests
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct MemoryStore(std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, String>>>);
+
+#[cfg(test)]
+impl SecretStore for MemoryStore {
+    fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+        Ok(self.0.lock().unwrap().get(name).cloned())
+    }
+
+    fn set(&self, name: &str, secret: &str) -> Result<(), SecretError> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), SecretError> {
+        self.0.lock().unwrap().remove(name);
+        Ok(())
+    }
+}
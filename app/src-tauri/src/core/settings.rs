@@ -1,32 +1,516 @@
-use std::{fs, path::PathBuf, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
+use tokio::sync::watch;
 use tracing::{info, warn};
 
+use crate::core::secrets::{SecretError, SecretStore};
+
 const MIN_RETENTION: usize = 5;
 const MAX_RETENTION: usize = 20;
+/// Smallest local history budget accepted, in megabytes
+const MIN_HISTORY_SIZE_MB: u64 = 50;
+/// Shortest sync or connectivity interval accepted, in seconds
+const MIN_SCHEDULE_SECS: u64 = 5;
+/// Name of the official endpoint every install starts with
+pub const DEFAULT_ENDPOINT: &str = "production";
+const DEFAULT_BASE_URL: &str = "https://crosssave-official-cloud.hdrn151.workers.dev";
+/// Layout version of the settings file; older files are migrated on load
+pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
+/// Settings file key listing the secrets kept in the secret store, whose
+/// fields are left empty in the file
+const STORED_SECRETS_KEY: &str = "stored_secrets";
+
+/// Upgrades a settings file by one schema version, from the version at its
+/// index
+type Migration = fn(&mut Value);
+const MIGRATIONS: &[Migration] = &[infer_cloud_mode];
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppSettings {
+    /// `SETTINGS_SCHEMA_VERSION` the file was written with; 0 for files
+    /// from before versioning
+    #[serde(default)]
+    pub schema_version: u32,
     pub retention_limit: usize,
     pub auto_delete: bool,
+    /// Total size local history may use before the oldest versions are
+    /// evicted across games; `None` means no limit
+    #[serde(default)]
+    pub max_history_size_mb: Option<u64>,
+    /// Age limit and thinning of every game's history
+    #[serde(default)]
+    pub age_retention: AgeRetention,
+    /// Games whose history is pruned by age differently, by game ID
+    #[serde(default)]
+    pub game_age_retention: HashMap<String, AgeRetention>,
+    /// Folder holding the history instead of the app data directory, set
+    /// by `relocate_history`
+    #[serde(default)]
+    pub history_dir: Option<String>,
     #[serde(default)]
     pub cloud: CloudSettings,
     #[serde(default)]
     pub cloud_mode: CloudMode,
     #[serde(default)]
     pub self_host: SelfHostSettings,
+    #[serde(default)]
+    pub sync_schedule: SyncSchedule,
+    #[serde(default)]
+    pub updates: UpdateSettings,
+    #[serde(default)]
+    pub crash_reports: CrashReportSettings,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub desktop: DesktopSettings,
+    #[serde(default)]
+    pub network: NetworkSettings,
+    #[serde(default)]
+    pub game_info: GameInfoSettings,
 }
 
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            schema_version: SETTINGS_SCHEMA_VERSION,
             retention_limit: 10,
             auto_delete: true,
+            max_history_size_mb: None,
+            age_retention: AgeRetention::default(),
+            game_age_retention: HashMap::new(),
+            history_dir: None,
             cloud: CloudSettings::default(),
             cloud_mode: CloudMode::default(),
             self_host: SelfHostSettings::default(),
+            sync_schedule: SyncSchedule::default(),
+            updates: UpdateSettings::default(),
+            crash_reports: CrashReportSettings::default(),
+            notifications: NotificationSettings::default(),
+            desktop: DesktopSettings::default(),
+            network: NetworkSettings::default(),
+            game_info: GameInfoSettings::default(),
+        }
+    }
+}
+
+/// Panic reports are only written, and only sent anywhere, when opted in
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrashReportSettings {
+    pub enabled: bool,
+    /// Send stored reports to the self-hosted server on startup
+    pub auto_submit: bool,
+}
+
+/// OS notifications for finished transfers, conflicts, expired logins and
+/// full storage; off until the user turns them on
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+}
+
+/// When transfers wait for a better network or more battery; mostly
+/// matters on Android
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// Hold uploads on metered networks such as mobile data
+    pub unmetered_uploads_only: bool,
+    /// Pause sync while battery saver is on and the device isn't charging
+    pub pause_on_battery_saver: bool,
+}
+
+/// Pruning of local history by age, on top of `retention_limit`. Pinned
+/// versions and each game's latest version are always kept.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AgeRetention {
+    /// Versions older than this are removed; `None` keeps them at any age
+    pub max_age_days: Option<u64>,
+    /// Keep one version per hour for a day, one per day for 30 days and
+    /// one per week after that
+    pub thin: bool,
+}
+
+/// The history settings `update_retention` changes
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetentionSettings {
+    pub retention_limit: usize,
+    pub auto_delete: bool,
+    /// `None` removes the size limit
+    #[serde(default)]
+    pub max_history_size_mb: Option<u64>,
+    /// Left as it is when `None`
+    #[serde(default)]
+    pub age_retention: Option<AgeRetention>,
+    /// Left as it is when `None`
+    #[serde(default)]
+    pub game_age_retention: Option<HashMap<String, AgeRetention>>,
+}
+
+/// Sign-in details for one kind of server, tagged with its cloud mode
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CloudCredentials {
+    Official {
+        api_key: String,
+        #[serde(default)]
+        user_id: String,
+    },
+    SelfHost(SelfHostSettings),
+}
+
+/// A value validation rejected, by its path in the settings file
+#[derive(Debug, Serialize)]
+pub struct SettingsIssue {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Where game titles come from when the bundled name database doesn't
+/// know a game
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameInfoSettings {
+    /// Online title lookup, queried as `?game_id=<id>`; off when unset
+    pub lookup_url: Option<String>,
+}
+
+/// Login item and window behaviour; ignored on Android
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopSettings {
+    pub launch_at_login: bool,
+    /// Stay in the tray when launched at login instead of opening the window
+    pub start_minimized: bool,
+    /// Closing the window hides it to the tray and sync keeps running
+    pub keep_running_on_close: bool,
+}
+
+/// Where and how often to look for a newer client release
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub auto_check: bool,
+    pub check_interval_hours: u64,
+    /// Release metadata URL; defaults to `/client/release` on the active server
+    pub metadata_url: Option<String>,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            auto_check: true,
+            check_interval_hours: 24,
+            metadata_url: None,
+        }
+    }
+}
+
+/// How often the background sync loop and connectivity check run
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SyncSchedule {
+    /// Seconds between full scans of every game while saves are changing.
+    /// A new local version is synced right away without waiting for one.
+    pub sync_interval_secs: u64,
+    /// Longest wait between full scans once nothing has changed for a while
+    pub idle_sync_interval_secs: u64,
+    /// Seconds between connectivity checks
+    pub connection_check_secs: u64,
+}
+
+impl Default for SyncSchedule {
+    fn default() -> Self {
+        Self {
+            sync_interval_secs: 60,
+            idle_sync_interval_secs: 10 * 60,
+            connection_check_secs: 30,
+        }
+    }
+}
+
+impl AppSettings {
+    /// Where the history is kept: `history_dir` when set, otherwise under
+    /// the app data directory
+    pub fn history_dir(&self, data_dir: &Path) -> PathBuf {
+        self.history_dir
+            .as_deref()
+            .map(str::trim)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir.join("archives").join("history"))
+    }
+
+    pub fn set_retention(&mut self, retention: RetentionSettings) {
+        self.retention_limit = retention.retention_limit;
+        self.auto_delete = retention.auto_delete;
+        self.max_history_size_mb = retention.max_history_size_mb;
+        if let Some(age_retention) = retention.age_retention {
+            self.age_retention = age_retention;
+        }
+        if let Some(game_age_retention) = retention.game_age_retention {
+            self.game_age_retention = game_age_retention;
+        }
+    }
+
+    /// Replace the sign-in details of one kind of server. The cloud mode
+    /// and device registration are left to their own commands.
+    pub fn set_credentials(&mut self, credentials: CloudCredentials) {
+        match credentials {
+            CloudCredentials::Official { api_key, user_id } => {
+                self.cloud.api_key = api_key.trim().to_string();
+                self.cloud.user_id = user_id.trim().to_string();
+            }
+            CloudCredentials::SelfHost(self_host) => self.self_host = self_host,
+        }
+    }
+
+    /// Tokens and access keys that are set, by their name in the secret store
+    pub fn secrets(&self) -> Vec<(String, String)> {
+        let mut secrets = vec![
+            ("cloud.api_key".to_string(), self.cloud.api_key.clone()),
+            (
+                "self_host.access_key".to_string(),
+                self.self_host.access_key.clone(),
+            ),
+        ];
+        secrets.extend(self.cloud.endpoints.iter().map(|endpoint| {
+            (
+                format!("endpoint.{}.api_key", endpoint.name),
+                endpoint.api_key.clone(),
+            )
+        }));
+        secrets.retain(|(_, secret)| !secret.is_empty());
+        secrets
+    }
+
+    fn secret_mut(&mut self, name: &str) -> Option<&mut String> {
+        match name {
+            "cloud.api_key" => Some(&mut self.cloud.api_key),
+            "self_host.access_key" => Some(&mut self.self_host.access_key),
+            _ => {
+                let endpoint = name.strip_prefix("endpoint.")?.strip_suffix(".api_key")?;
+                self.cloud
+                    .endpoints
+                    .iter_mut()
+                    .find(|candidate| candidate.name == endpoint)
+                    .map(|candidate| &mut candidate.api_key)
+            }
+        }
+    }
+
+    /// Every value that fails validation, in the order the file lists them
+    pub fn issues(&self) -> Vec<SettingsError> {
+        let mut issues = Vec::new();
+        if self.retention_limit < MIN_RETENTION || self.retention_limit > MAX_RETENTION {
+            issues.push(SettingsError::InvalidRetention(
+                self.retention_limit,
+                MIN_RETENTION,
+                MAX_RETENTION,
+            ));
+        }
+
+        if let Some(size_mb) = self.max_history_size_mb {
+            if size_mb < MIN_HISTORY_SIZE_MB {
+                issues.push(SettingsError::InvalidHistorySize(
+                    size_mb,
+                    MIN_HISTORY_SIZE_MB,
+                ));
+            }
+        }
+
+        if self.age_retention.max_age_days == Some(0) {
+            issues.push(SettingsError::InvalidHistoryAge(None));
+        }
+        if let Some((game_id, _)) = self
+            .game_age_retention
+            .iter()
+            .find(|(_, retention)| retention.max_age_days == Some(0))
+        {
+            issues.push(SettingsError::InvalidHistoryAge(Some(game_id.clone())));
+        }
+
+        if self.cloud.timeout_seconds == 0 {
+            issues.push(SettingsError::InvalidTimeout);
+        }
+        let endpoints = &self.cloud.endpoints;
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            if endpoint.name.trim().is_empty() || endpoint.base_url.trim().is_empty() {
+                issues.push(SettingsError::InvalidEndpoint(
+                    "endpoints need a name and a base URL".to_string(),
+                ));
+                break;
+            }
+            if endpoints[..index]
+                .iter()
+                .any(|other| other.name == endpoint.name)
+            {
+                issues.push(SettingsError::InvalidEndpoint(format!(
+                    "duplicate endpoint name {}",
+                    endpoint.name
+                )));
+                break;
+            }
+        }
+
+        let schedule = &self.sync_schedule;
+        if schedule.sync_interval_secs < MIN_SCHEDULE_SECS
+            || schedule.connection_check_secs < MIN_SCHEDULE_SECS
+        {
+            issues.push(SettingsError::InvalidSchedule(
+                "sync_schedule",
+                format!("intervals must be at least {MIN_SCHEDULE_SECS} seconds"),
+            ));
+        } else if schedule.idle_sync_interval_secs < schedule.sync_interval_secs {
+            issues.push(SettingsError::InvalidSchedule(
+                "sync_schedule",
+                "idle interval must not be shorter than the sync interval".to_string(),
+            ));
+        }
+
+        if self.updates.check_interval_hours == 0 {
+            issues.push(SettingsError::InvalidSchedule(
+                "updates.check_interval_hours",
+                "update checks need an interval of at least one hour".to_string(),
+            ));
+        }
+        for (field, url) in [
+            ("updates.metadata_url", &self.updates.metadata_url),
+            ("game_info.lookup_url", &self.game_info.lookup_url),
+        ] {
+            if let Some(url) = url.as_deref().map(str::trim).filter(|url| !url.is_empty()) {
+                if !url.starts_with("https://") && !url.starts_with("http://") {
+                    issues.push(SettingsError::InvalidUrl(field, url.to_string()));
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Put the value `issue` is about back to its default
+    fn reset_invalid(&mut self, issue: &SettingsError) {
+        let defaults = AppSettings::default();
+        match issue {
+            SettingsError::InvalidRetention(..) => self.retention_limit = defaults.retention_limit,
+            SettingsError::InvalidHistorySize(..) => self.max_history_size_mb = None,
+            SettingsError::InvalidHistoryAge(None) => self.age_retention = defaults.age_retention,
+            SettingsError::InvalidHistoryAge(Some(_)) => self
+                .game_age_retention
+                .retain(|_, retention| retention.max_age_days != Some(0)),
+            SettingsError::InvalidTimeout => {
+                self.cloud.timeout_seconds = defaults.cloud.timeout_seconds
+            }
+            SettingsError::InvalidEndpoint(_) => {
+                let mut names = HashSet::new();
+                self.cloud.endpoints.retain(|endpoint| {
+                    !endpoint.name.trim().is_empty()
+                        && !endpoint.base_url.trim().is_empty()
+                        && names.insert(endpoint.name.clone())
+                });
+            }
+            SettingsError::InvalidSchedule("sync_schedule", _) => {
+                self.sync_schedule = defaults.sync_schedule
+            }
+            SettingsError::InvalidSchedule(..) => {
+                self.updates.check_interval_hours = defaults.updates.check_interval_hours
+            }
+            SettingsError::InvalidUrl("updates.metadata_url", _) => {
+                self.updates.metadata_url = None
+            }
+            SettingsError::InvalidUrl(..) => self.game_info.lookup_url = None,
+            SettingsError::Io(_)
+            | SettingsError::Serialization(_)
+            | SettingsError::UnknownEndpoint(_)
+            | SettingsError::Lock(_) => {}
+        }
+    }
+
+    /// The server account a device registration belongs to: mode, base URL
+    /// and user. Without a user ID (access key logins) a digest of the token
+    /// stands in, so switching keys still counts as switching accounts.
+    pub fn registration_key(&self) -> String {
+        let (mode, base_url, token) = match self.cloud_mode {
+            CloudMode::Official => ("official", &self.cloud.base_url, &self.cloud.api_key),
+            CloudMode::SelfHost => (
+                "self_host",
+                &self.self_host.api_server,
+                &self.self_host.access_key,
+            ),
+            CloudMode::Off => ("off", &self.cloud.base_url, &self.cloud.api_key),
+            CloudMode::Demo => ("demo", &self.cloud.base_url, &self.cloud.api_key),
+        };
+
+        let account = if self.cloud.user_id.trim().is_empty() {
+            let digest = format!("{:x}", Sha256::digest(token.trim().as_bytes()));
+            format!("key:{}", &digest[..16])
+        } else {
+            format!("user:{}", self.cloud.user_id.trim())
+        };
+
+        format!("{mode}|{}|{account}", base_url.trim().trim_end_matches('/'))
+    }
+
+    /// Whether this device was registered with the account now in use
+    pub fn device_registered(&self) -> bool {
+        self.cloud.has_registered_device
+            && self.cloud.registered_for.as_deref() == Some(self.registration_key().as_str())
+    }
+
+    pub fn mark_device_registered(&mut self) {
+        self.cloud.has_registered_device = true;
+        self.cloud.registered_for = Some(self.registration_key());
+    }
+
+    pub fn clear_device_registration(&mut self) {
+        self.cloud.has_registered_device = false;
+        self.cloud.registered_for = None;
+    }
+
+    /// `registration_key` of the signed-in account, or `None` while signed
+    /// out or when the server did not say which user a token belongs to
+    pub fn account_key(&self) -> Option<String> {
+        let signed_in = match self.cloud_mode {
+            CloudMode::Official => !self.cloud.user_id.trim().is_empty(),
+            CloudMode::SelfHost => !self.self_host.access_key.trim().is_empty(),
+            CloudMode::Off => false,
+            CloudMode::Demo => true,
+        };
+        signed_in.then(|| self.registration_key())
+    }
+
+    /// Whether the signed-in account still has to go through the first-sync
+    /// reconciliation before background sync may pick directions itself
+    pub fn needs_reconciliation(&self) -> bool {
+        self.account_key()
+            .is_some_and(|account| self.cloud.reconciled_for.as_deref() != Some(account.as_str()))
+    }
+
+    pub fn mark_reconciled(&mut self) {
+        self.cloud.reconciled_for = self.account_key();
+    }
+
+    /// False once another account signed in on top of the one whose saves
+    /// fill the local history; nothing may be synced until the user decides
+    pub fn history_belongs_to_account(&self) -> bool {
+        match (&self.cloud.history_owner, self.account_key()) {
+            (Some(owner), Some(account)) => *owner == account,
+            _ => true,
         }
     }
 }
@@ -37,6 +521,8 @@ pub enum CloudMode {
     Official,
     SelfHost,
     Off,
+    /// In-memory cloud for demos and tests; not offered in the UI
+    Demo,
 }
 
 impl Default for CloudMode {
@@ -60,13 +546,68 @@ pub struct CloudSettings {
     pub timeout_seconds: u64,
     #[serde(default)]
     pub has_registered_device: bool,
+    /// `AppSettings::registration_key` of the account `has_registered_device`
+    /// refers to; a flag set for another mode, server or user is stale
+    #[serde(default)]
+    pub registered_for: Option<String>,
+    /// `AppSettings::account_key` of the account the local history was
+    /// synced with
+    #[serde(default)]
+    pub history_owner: Option<String>,
+    /// `AppSettings::account_key` of the last account whose first sync was
+    /// reconciled by the user
+    #[serde(default)]
+    pub reconciled_for: Option<String>,
+    /// Official servers to choose from. The active one's session lives in
+    /// the fields above; the others keep theirs here until selected.
+    #[serde(default = "default_endpoints")]
+    pub endpoints: Vec<OfficialEndpoint>,
+    #[serde(default = "default_endpoint_name")]
+    pub active_endpoint: String,
+}
+
+/// A named official server, e.g. production or a staging worker, with the
+/// session last used against it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfficialEndpoint {
+    pub name: String,
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub has_registered_device: bool,
+    #[serde(default)]
+    pub registered_for: Option<String>,
+}
+
+impl OfficialEndpoint {
+    pub fn new(name: &str, base_url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            api_key: String::new(),
+            user_id: String::new(),
+            has_registered_device: false,
+            registered_for: None,
+        }
+    }
+}
+
+fn default_endpoints() -> Vec<OfficialEndpoint> {
+    vec![OfficialEndpoint::new(DEFAULT_ENDPOINT, DEFAULT_BASE_URL)]
+}
+
+fn default_endpoint_name() -> String {
+    DEFAULT_ENDPOINT.to_string()
 }
 
 impl Default for CloudSettings {
     fn default() -> Self {
         Self {
             enabled: false,
-            base_url: "https://crosssave-official-cloud.hdrn151.workers.dev".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
             api_key: String::new(),
             device_id: String::new(),
             device_name: String::new(),
@@ -74,10 +615,57 @@ impl Default for CloudSettings {
             user_id: String::new(),
             timeout_seconds: 30,
             has_registered_device: false,
+            registered_for: None,
+            history_owner: None,
+            reconciled_for: None,
+            endpoints: default_endpoints(),
+            active_endpoint: default_endpoint_name(),
         }
     }
 }
 
+impl CloudSettings {
+    /// Make `name` the active official endpoint. The current session is
+    /// kept with the endpoint it belongs to and the selected one's restored,
+    /// so switching back does not need another login.
+    pub fn select_endpoint(&mut self, name: &str) -> Result<(), SettingsError> {
+        let selected = self
+            .endpoints
+            .iter()
+            .find(|endpoint| endpoint.name == name)
+            .cloned()
+            .ok_or_else(|| SettingsError::UnknownEndpoint(name.to_string()))?;
+        if self.active_endpoint == name {
+            return Ok(());
+        }
+
+        let current = OfficialEndpoint {
+            name: self.active_endpoint.clone(),
+            base_url: self.base_url.clone(),
+            api_key: std::mem::take(&mut self.api_key),
+            user_id: std::mem::take(&mut self.user_id),
+            has_registered_device: self.has_registered_device,
+            registered_for: self.registered_for.take(),
+        };
+        match self
+            .endpoints
+            .iter_mut()
+            .find(|endpoint| endpoint.name == current.name)
+        {
+            Some(endpoint) => *endpoint = current,
+            None => self.endpoints.push(current),
+        }
+
+        self.base_url = selected.base_url;
+        self.api_key = selected.api_key;
+        self.user_id = selected.user_id;
+        self.has_registered_device = selected.has_registered_device;
+        self.registered_for = selected.registered_for;
+        self.active_endpoint = selected.name;
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SelfHostSettings {
     pub id_server: String,
@@ -105,39 +693,211 @@ pub enum SettingsError {
     Serialization(String),
     #[error("invalid retention limit {0}, expected {1}-{2}")]
     InvalidRetention(usize, usize, usize),
+    #[error("invalid history size limit {0} MB, expected at least {1} MB")]
+    InvalidHistorySize(u64, u64),
+    /// For one game's limit, or every game's when `None`
+    #[error(
+        "invalid history age limit for {}, expected at least one day",
+        .0.as_deref().unwrap_or("all games")
+    )]
+    InvalidHistoryAge(Option<String>),
+    #[error("invalid cloud timeout, expected at least one second")]
+    InvalidTimeout,
+    /// A schedule setting, with its path in the settings file
+    #[error("invalid sync schedule: {1}")]
+    InvalidSchedule(&'static str, String),
+    #[error("invalid {0}: {1} is not an http(s) URL")]
+    InvalidUrl(&'static str, String),
+    #[error("unknown cloud endpoint: {0}")]
+    UnknownEndpoint(String),
+    #[error("invalid cloud endpoint: {0}")]
+    InvalidEndpoint(String),
     #[error("lock error: {0}")]
     Lock(String),
 }
 
+impl SettingsError {
+    /// Path in the settings file of the value this error rejects; `None`
+    /// for errors that aren't about a value
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            SettingsError::InvalidRetention(..) => Some("retention_limit"),
+            SettingsError::InvalidHistorySize(..) => Some("max_history_size_mb"),
+            SettingsError::InvalidHistoryAge(None) => Some("age_retention"),
+            SettingsError::InvalidHistoryAge(Some(_)) => Some("game_age_retention"),
+            SettingsError::InvalidTimeout => Some("cloud.timeout_seconds"),
+            SettingsError::InvalidSchedule(field, _) | SettingsError::InvalidUrl(field, _) => {
+                Some(*field)
+            }
+            SettingsError::InvalidEndpoint(_) => Some("cloud.endpoints"),
+            SettingsError::Io(_)
+            | SettingsError::Serialization(_)
+            | SettingsError::UnknownEndpoint(_)
+            | SettingsError::Lock(_) => None,
+        }
+    }
+
+    pub fn issue(&self) -> Option<SettingsIssue> {
+        self.field().map(|field| SettingsIssue {
+            field,
+            message: self.to_string(),
+        })
+    }
+}
+
 pub struct SettingsManager {
     path: PathBuf,
     state: Mutex<AppSettings>,
+    secrets: Option<Box<dyn SecretStore>>,
+    /// What the secret store holds, by name, as last written or read
+    stored_secrets: Mutex<HashMap<String, String>>,
+    /// Set once the store turned out not to exist on this device
+    secrets_unavailable: AtomicBool,
+    /// Whether settings.json holds secrets the store couldn't take
+    plaintext_secrets: watch::Sender<bool>,
+    changes: watch::Sender<SettingsChange>,
+    /// What the app last wrote to the file, to tell its own writes apart
+    /// from outside edits
+    last_written: Mutex<Option<String>>,
+    file_watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+/// Settings as of their latest change, for `SettingsManager::subscribe`
+#[derive(Clone, Debug)]
+pub struct SettingsChange {
+    pub settings: AppSettings,
+    /// Made by editing settings.json outside the app
+    pub external: bool,
 }
 
 impl SettingsManager {
+    /// Settings kept in `path`, secrets included
     pub fn new(path: PathBuf) -> Result<Self, SettingsError> {
+        Self::with_secrets(path, None)
+    }
+
+    /// Settings kept in `path`, with tokens and access keys kept in
+    /// `secrets` instead. Ones still in the file are moved there, and kept
+    /// in the file when the store can't take them.
+    pub fn with_secrets(
+        path: PathBuf,
+        secrets: Option<Box<dyn SecretStore>>,
+    ) -> Result<Self, SettingsError> {
         if let Some(parent) = path.parent() {
             if let Err(err) = fs::create_dir_all(parent) {
                 return Err(SettingsError::Io(err.to_string()));
             }
         }
 
-        let loaded = match fs::read_to_string(&path) {
-            Ok(content) => match serde_json::from_str::<AppSettings>(&content) {
-                Ok(settings) => settings,
-                Err(err) => {
-                    warn!("[SETTINGS] Failed to parse settings file: {err}. Using defaults");
-                    AppSettings::default()
-                }
-            },
-            Err(_) => AppSettings::default(),
+        let manager = Self {
+            path,
+            state: Mutex::new(AppSettings::default()),
+            secrets,
+            stored_secrets: Mutex::new(HashMap::new()),
+            secrets_unavailable: AtomicBool::new(false),
+            plaintext_secrets: watch::Sender::new(false),
+            changes: watch::Sender::new(SettingsChange {
+                settings: AppSettings::default(),
+                external: false,
+            }),
+            last_written: Mutex::new(None),
+            file_watcher: Mutex::new(None),
+        };
+        let content = fs::read_to_string(&manager.path).ok();
+        let (mut loaded, mut changed) = match &content {
+            Some(content) => manager.parse(content).unwrap_or_else(|err| {
+                warn!("[SETTINGS] Failed to parse settings file: {err}. Using defaults");
+                (AppSettings::default(), false)
+            }),
+            None => (AppSettings::default(), false),
         };
 
+        // One bad value shouldn't cost the user every other setting
+        for issue in loaded.issues() {
+            warn!("[SETTINGS] {issue}; using the default instead");
+            loaded.reset_invalid(&issue);
+            changed = true;
+        }
         let validated = Self::validate(loaded)?;
-        Ok(Self {
-            path,
-            state: Mutex::new(validated),
-        })
+
+        *manager
+            .state
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))? = validated.clone();
+        if changed {
+            if let Err(err) = manager.write(&validated) {
+                warn!("[SETTINGS] Failed to save migrated settings: {err}");
+            }
+        } else {
+            *manager
+                .last_written
+                .lock()
+                .map_err(|err| SettingsError::Lock(err.to_string()))? = content;
+            manager.note_plaintext_secrets(&validated)?;
+        }
+        manager.changes.send_replace(SettingsChange {
+            settings: validated,
+            external: false,
+        });
+        Ok(manager)
+    }
+
+    /// Read a settings file, migrating it from older schema versions and
+    /// filling in its secrets. Returns whether it needs saving again.
+    fn parse(&self, content: &str) -> Result<(AppSettings, bool), SettingsError> {
+        let parsed = serde_json::from_str::<Value>(content).and_then(|mut value| {
+            let migrated = migrate(&mut value);
+            let stored: Vec<String> = value
+                .get(STORED_SECRETS_KEY)
+                .and_then(|names| serde_json::from_value(names.clone()).ok())
+                .unwrap_or_default();
+            serde_json::from_value::<AppSettings>(value)
+                .map(|settings| (settings, migrated, stored))
+        });
+        let (mut settings, migrated, stored) =
+            parsed.map_err(|err| SettingsError::Serialization(err.to_string()))?;
+        let unmoved = self.load_secrets(&mut settings, &stored)?;
+        Ok((settings, migrated || unmoved))
+    }
+
+    /// Fill in the secrets listed as `stored` from the secret store. Returns
+    /// whether the file holds secrets that should move there.
+    fn load_secrets(
+        &self,
+        settings: &mut AppSettings,
+        stored: &[String],
+    ) -> Result<bool, SettingsError> {
+        let Some(store) = &self.secrets else {
+            if !stored.is_empty() {
+                warn!("[SETTINGS] No secret store to read saved logins from; sign in again");
+            }
+            return Ok(false);
+        };
+        let mut held = self
+            .stored_secrets
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))?;
+
+        for name in stored {
+            let Some(field) = settings.secret_mut(name) else {
+                continue;
+            };
+            if !field.is_empty() {
+                continue;
+            }
+            match store.get(name) {
+                Ok(Some(secret)) => {
+                    *field = secret.clone();
+                    held.insert(name.clone(), secret);
+                }
+                Ok(None) => warn!("[SETTINGS] {name} is missing from the secret store"),
+                Err(err) => warn!("[SETTINGS] Failed to read {name}: {err}"),
+            }
+        }
+        Ok(settings
+            .secrets()
+            .iter()
+            .any(|(name, _)| !held.contains_key(name)))
     }
 
     pub fn get_settings(&self) -> Result<AppSettings, SettingsError> {
@@ -149,37 +909,402 @@ impl SettingsManager {
     }
 
     pub fn update_settings(&self, settings: AppSettings) -> Result<AppSettings, SettingsError> {
-        let validated = Self::validate(settings)?;
+        let validated = self.update_with(|current| *current = settings)?;
+        info!(
+            "[SETTINGS] Updated retention to {} (auto_delete={})",
+            validated.retention_limit, validated.auto_delete
+        );
+        Ok(validated)
+    }
+
+    /// Change some settings and keep the rest as they are, including
+    /// changes saved by someone else since they were last read
+    pub fn update_with(
+        &self,
+        change: impl FnOnce(&mut AppSettings),
+    ) -> Result<AppSettings, SettingsError> {
         let mut guard = self
             .state
             .lock()
             .map_err(|err| SettingsError::Lock(err.to_string()))?;
+        let mut updated = guard.clone();
+        change(&mut updated);
+        let validated = Self::validate(updated)?;
         *guard = validated.clone();
+        self.write(&validated)?;
+        self.changes.send_replace(SettingsChange {
+            settings: validated.clone(),
+            external: false,
+        });
+        Ok(validated)
+    }
+
+    /// Every change from now on, whether saved through the app or by
+    /// editing settings.json while it runs
+    pub fn subscribe(&self) -> watch::Receiver<SettingsChange> {
+        self.changes.subscribe()
+    }
+
+    /// Whether tokens or access keys are kept in settings.json in plain
+    /// text, because there is no secret store or it refused them
+    pub fn secrets_in_plaintext(&self) -> bool {
+        *self.plaintext_secrets.borrow()
+    }
+
+    /// Every change to `secrets_in_plaintext`, so the user can be told
+    pub fn subscribe_plaintext_secrets(&self) -> watch::Receiver<bool> {
+        self.plaintext_secrets.subscribe()
+    }
+
+    /// Reload the settings whenever settings.json is edited outside the
+    /// app, for as long as the manager lives
+    pub fn watch_file(self: &Arc<Self>) -> Result<(), SettingsError> {
+        let file_name = self.path.file_name().map(ToOwned::to_owned);
+        let manager = Arc::downgrade(self);
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                || !event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == file_name.as_deref())
+            {
+                return;
+            }
+            let Some(manager) = manager.upgrade() else {
+                return;
+            };
+            match manager.reload() {
+                Ok(true) => info!("[SETTINGS] Reloaded settings.json after an outside edit"),
+                Ok(false) => {}
+                Err(err) => warn!("[SETTINGS] Ignoring edit to settings.json: {err}"),
+            }
+        })
+        .map_err(|err| SettingsError::Io(err.to_string()))?;
+        // Editors often replace the file, so its folder is watched
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|err| SettingsError::Io(err.to_string()))?;
+
+        *self
+            .file_watcher
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))? = Some(watcher);
+        Ok(())
+    }
+
+    /// Take in settings.json as it is on disk. Returns false when it holds
+    /// what the app wrote last; edits that don't parse or validate are
+    /// refused and the current settings kept.
+    pub fn reload(&self) -> Result<bool, SettingsError> {
+        let content =
+            fs::read_to_string(&self.path).map_err(|err| SettingsError::Io(err.to_string()))?;
+        let mut last_written = self
+            .last_written
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))?;
+        if last_written.as_deref() == Some(content.as_str()) {
+            return Ok(false);
+        }
+
+        let (loaded, needs_save) = self.parse(&content)?;
+        let validated = Self::validate(loaded)?;
+        *last_written = Some(content);
+        drop(last_written);
+
+        *self
+            .state
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))? = validated.clone();
+        if needs_save {
+            self.write(&validated)?;
+        } else {
+            self.note_plaintext_secrets(&validated)?;
+        }
+        self.changes.send_replace(SettingsChange {
+            settings: validated,
+            external: true,
+        });
+        Ok(true)
+    }
 
-        let json = serde_json::to_string_pretty(&validated)
+    fn write(&self, settings: &AppSettings) -> Result<(), SettingsError> {
+        let stored = self.store_secrets(settings)?;
+        let mut on_disk = settings.clone();
+        for name in &stored {
+            if let Some(field) = on_disk.secret_mut(name) {
+                field.clear();
+            }
+        }
+        let mut value = serde_json::to_value(&on_disk)
             .map_err(|err| SettingsError::Serialization(err.to_string()))?;
+        if !stored.is_empty() {
+            value[STORED_SECRETS_KEY] = Value::from(stored);
+        }
+
+        let json = serde_json::to_string_pretty(&value)
+            .map_err(|err| SettingsError::Serialization(err.to_string()))?;
+        // Noted first, so the watcher sees the write as the app's own
+        *self
+            .last_written
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))? = Some(json.clone());
         fs::write(&self.path, json).map_err(|err| SettingsError::Io(err.to_string()))?;
+        self.note_plaintext_secrets(settings)
+    }
 
-        info!(
-            "[SETTINGS] Updated retention to {} (auto_delete={})",
-            validated.retention_limit, validated.auto_delete
-        );
-        Ok(validated)
+    /// Note whether any of the secrets in `settings` stays in the file
+    fn note_plaintext_secrets(&self, settings: &AppSettings) -> Result<(), SettingsError> {
+        let held = self
+            .stored_secrets
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))?;
+        let plaintext = settings
+            .secrets()
+            .iter()
+            .any(|(name, _)| !held.contains_key(name));
+        drop(held);
+        let changed = self
+            .plaintext_secrets
+            .send_if_modified(|current| std::mem::replace(current, plaintext) != plaintext);
+        if changed && plaintext {
+            warn!("[SETTINGS] Secrets are kept in settings.json in plain text");
+        }
+        Ok(())
+    }
+
+    /// Bring the secret store in line with `settings`, deleting secrets
+    /// that were cleared. Returns the names it holds; the rest stay in the
+    /// settings file.
+    fn store_secrets(&self, settings: &AppSettings) -> Result<Vec<String>, SettingsError> {
+        let Some(store) = &self.secrets else {
+            return Ok(Vec::new());
+        };
+        if self.secrets_unavailable.load(Ordering::Relaxed) {
+            return Ok(Vec::new());
+        }
+        let mut held = self
+            .stored_secrets
+            .lock()
+            .map_err(|err| SettingsError::Lock(err.to_string()))?;
+
+        let mut stored = Vec::new();
+        for (name, secret) in settings.secrets() {
+            if held.get(&name) != Some(&secret) {
+                if let Err(err) = store.set(&name, &secret) {
+                    if let SecretError::Unavailable(_) = err {
+                        warn!("[SETTINGS] No secret store available; keeping secrets in settings.json: {err}");
+                        self.secrets_unavailable.store(true, Ordering::Relaxed);
+                        return Ok(Vec::new());
+                    }
+                    warn!("[SETTINGS] Failed to store {name}; keeping it in settings.json: {err}");
+                    continue;
+                }
+                held.insert(name.clone(), secret);
+            }
+            stored.push(name);
+        }
+        held.retain(|name, _| {
+            if stored.contains(name) {
+                return true;
+            }
+            if let Err(err) = store.delete(name) {
+                warn!("[SETTINGS] Failed to delete {name} from the secret store: {err}");
+            }
+            false
+        });
+        Ok(stored)
     }
 
     fn validate(settings: AppSettings) -> Result<AppSettings, SettingsError> {
-        if settings.retention_limit < MIN_RETENTION || settings.retention_limit > MAX_RETENTION {
-            return Err(SettingsError::InvalidRetention(
-                settings.retention_limit,
-                MIN_RETENTION,
-                MAX_RETENTION,
-            ));
+        match settings.issues().into_iter().next() {
+            Some(issue) => Err(issue),
+            None => Ok(settings),
+        }
+    }
+}
+
+/// Run the migrations a settings file is missing. Returns whether any ran;
+/// files from a newer client are read as they are.
+fn migrate(value: &mut Value) -> bool {
+    let Some(object) = value.as_object_mut() else {
+        return false;
+    };
+    let version = object
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version >= u64::from(SETTINGS_SCHEMA_VERSION) {
+        if version > u64::from(SETTINGS_SCHEMA_VERSION) {
+            warn!("[SETTINGS] Settings file is from a newer version (schema {version})");
         }
+        return false;
+    }
 
-        Ok(settings)
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(value);
+    }
+    value["schema_version"] = Value::from(SETTINGS_SCHEMA_VERSION);
+    info!("[SETTINGS] Migrated settings from schema {version} to {SETTINGS_SCHEMA_VERSION}");
+    true
+}
+
+/// Schema 0 to 1. Files from before `cloud_mode` were read as official
+/// even when they held a self-hosted server's access key, which is the
+/// server they were actually used with.
+fn infer_cloud_mode(value: &mut Value) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    if object.contains_key("cloud_mode") {
+        return;
+    }
+    let self_hosted = object
+        .get("self_host")
+        .and_then(|self_host| self_host.get("access_key"))
+        .and_then(Value::as_str)
+        .is_some_and(|key| !key.trim().is_empty());
+    if self_hosted {
+        object.insert("cloud_mode".to_string(), Value::from("self_host"));
     }
 }
 
 pub fn default_retention_bounds() -> (usize, usize) {
     (MIN_RETENTION, MAX_RETENTION)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::secrets::MemoryStore;
+    use uuid::Uuid;
+
+    #[test]
+    fn every_schema_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len(), SETTINGS_SCHEMA_VERSION as usize);
+    }
+
+    #[test]
+    fn legacy_files_are_migrated_and_bad_values_reset() {
+        let dir = std::env::temp_dir().join(format!("crosssave-settings-{}", Uuid::new_v4()));
+        let path = dir.join("settings.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &path,
+            serde_json::json!({
+                "retention_limit": 99,
+                "auto_delete": false,
+                "self_host": {
+                    "id_server": "id.example",
+                    "relay_server": "relay.example",
+                    "api_server": "https://api.example",
+                    "access_key": "key"
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let settings = SettingsManager::new(path.clone())
+            .unwrap()
+            .get_settings()
+            .unwrap();
+        assert_eq!(settings.schema_version, SETTINGS_SCHEMA_VERSION);
+        assert_eq!(settings.cloud_mode, CloudMode::SelfHost);
+        assert_eq!(
+            settings.retention_limit,
+            AppSettings::default().retention_limit
+        );
+        assert!(!settings.auto_delete, "valid values are kept");
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["schema_version"], SETTINGS_SCHEMA_VERSION);
+        assert_eq!(saved["cloud_mode"], "self_host");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn secrets_move_to_the_store_and_back() {
+        let dir = std::env::temp_dir().join(format!("crosssave-settings-{}", Uuid::new_v4()));
+        let path = dir.join("settings.json");
+        let plaintext = SettingsManager::new(path.clone()).unwrap();
+        plaintext
+            .update_with(|settings| settings.cloud.api_key = "token-1234".to_string())
+            .unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("token-1234"));
+        assert!(plaintext.secrets_in_plaintext());
+
+        let store = MemoryStore::default();
+        let open = || SettingsManager::with_secrets(path.clone(), Some(Box::new(store.clone())));
+        let settings = open().unwrap();
+        assert_eq!(settings.get_settings().unwrap().cloud.api_key, "token-1234");
+        assert!(!fs::read_to_string(&path).unwrap().contains("token-1234"));
+        assert!(!settings.secrets_in_plaintext());
+        assert_eq!(
+            store.get("cloud.api_key").unwrap().as_deref(),
+            Some("token-1234")
+        );
+
+        let reopened = open().unwrap();
+        assert_eq!(reopened.get_settings().unwrap().cloud.api_key, "token-1234");
+        assert!(!reopened.secrets_in_plaintext());
+        reopened
+            .update_with(|settings| settings.cloud.api_key.clear())
+            .unwrap();
+        assert_eq!(store.get("cloud.api_key").unwrap(), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn outside_edits_are_reloaded_and_broadcast() {
+        let dir = std::env::temp_dir().join(format!("crosssave-settings-{}", Uuid::new_v4()));
+        let path = dir.join("settings.json");
+        let settings = SettingsManager::new(path.clone()).unwrap();
+        let mut changes = settings.subscribe();
+
+        settings
+            .update_with(|settings| settings.retention_limit = 6)
+            .unwrap();
+        assert!(changes.has_changed().unwrap());
+        assert!(!changes.borrow_and_update().external);
+        assert!(!settings.reload().unwrap(), "the app's own write");
+
+        let mut edited: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        edited["retention_limit"] = Value::from(12);
+        fs::write(&path, edited.to_string()).unwrap();
+        assert!(settings.reload().unwrap());
+        let change = changes.borrow_and_update().clone();
+        assert!(change.external);
+        assert_eq!(change.settings.retention_limit, 12);
+
+        edited["retention_limit"] = Value::from(1000);
+        fs::write(&path, edited.to_string()).unwrap();
+        assert!(settings.reload().is_err());
+        assert_eq!(settings.get_settings().unwrap().retention_limit, 12);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn issues_name_their_fields() {
+        let mut settings = AppSettings::default();
+        settings.retention_limit = 1;
+        settings.sync_schedule.sync_interval_secs = 1;
+        settings.game_info.lookup_url = Some("ftp://titles.example".to_string());
+
+        let fields: Vec<_> = settings
+            .issues()
+            .iter()
+            .filter_map(SettingsError::field)
+            .collect();
+        assert_eq!(
+            fields,
+            ["retention_limit", "sync_schedule", "game_info.lookup_url"]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Copy-then-zip for large save folders. Saves are copied to a private
+//! folder first, so the archive is built from files the emulator can't
+//! write to halfway through. `fs::copy` clones where the filesystem can
+//! (APFS clones, btrfs and XFS reflinks through `copy_file_range`), which
+//! makes the copy near instant. On Windows the copy is read from a Volume
+//! Shadow Copy when the app is allowed to make one.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Name prefix of snapshot folders in the staging directory
+pub const SNAPSHOT_DIR_PREFIX: &str = "snapshot-";
+/// Copies of a file made before giving up on it changing mid-copy
+const COPY_ATTEMPTS: u32 = 3;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The file kept changing while it was being copied
+    Changing(PathBuf),
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyMethod {
+    /// Read from a Volume Shadow Copy, consistent across all files
+    #[cfg_attr(not(windows), allow(dead_code))]
+    ShadowCopy,
+    /// Copied or cloned file by file, each checked for changes mid-copy
+    Copy,
+}
+
+/// Copies of a game's save files, deleted when dropped
+pub struct SnapshotCopy {
+    pub dir: PathBuf,
+    /// Copies in the order of the files they were made from
+    pub files: Vec<PathBuf>,
+    pub method: CopyMethod,
+}
+
+impl Drop for SnapshotCopy {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            debug!("[PACKAGER] Could not remove snapshot {:?}: {err}", self.dir);
+        }
+    }
+}
+
+/// Copy each file to a new folder under `parent`, at its archive entry name
+pub fn snapshot_files(
+    files: &[(PathBuf, String)],
+    parent: &Path,
+) -> Result<SnapshotCopy, SnapshotError> {
+    let dir = parent.join(format!("{SNAPSHOT_DIR_PREFIX}{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir)?;
+    let mut snapshot = SnapshotCopy {
+        dir,
+        files: Vec::with_capacity(files.len()),
+        method: CopyMethod::Copy,
+    };
+
+    #[cfg(windows)]
+    let shadow = shadow::ShadowCopies::create(files.iter().map(|(source, _)| source.as_path()));
+    #[cfg(windows)]
+    if shadow.is_some() {
+        snapshot.method = CopyMethod::ShadowCopy;
+    }
+
+    for (source, entry_name) in files {
+        let target = snapshot.dir.join(entry_name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        #[cfg(windows)]
+        if let Some(shadowed) = shadow.as_ref().and_then(|shadow| shadow.path_for(source)) {
+            fs::copy(shadowed, &target)?;
+            snapshot.files.push(target);
+            continue;
+        }
+        copy_unchanged(source, &target)?;
+        snapshot.files.push(target);
+    }
+
+    info!(
+        "[PACKAGER] Snapshotted {} files via {:?}",
+        snapshot.files.len(),
+        snapshot.method
+    );
+    Ok(snapshot)
+}
+
+/// Copy `source`, again if its size or modification time moved meanwhile
+fn copy_unchanged(source: &Path, target: &Path) -> Result<(), SnapshotError> {
+    for _ in 0..COPY_ATTEMPTS {
+        let before = stamp(source)?;
+        fs::copy(source, target)?;
+        if stamp(source)? == before {
+            return Ok(());
+        }
+        debug!(
+            "[PACKAGER] {:?} changed while copying; copying again",
+            source
+        );
+    }
+    Err(SnapshotError::Changing(source.to_path_buf()))
+}
+
+fn stamp(path: &Path) -> io::Result<(u64, Option<SystemTime>)> {
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+#[cfg(windows)]
+mod shadow {
+    use std::{
+        io,
+        os::windows::process::CommandExt,
+        path::{Component, Path, PathBuf, Prefix},
+        process::Command,
+    };
+
+    use tracing::{debug, warn};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    struct Shadow {
+        /// Drive the copy was made of, e.g. `C:\`
+        volume: String,
+        id: String,
+        /// `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopyN`
+        device: String,
+    }
+
+    /// Shadow copies of the drives holding some files, deleted when dropped
+    pub struct ShadowCopies {
+        shadows: Vec<Shadow>,
+    }
+
+    impl ShadowCopies {
+        /// `None` when a drive couldn't be shadowed, which needs admin
+        /// rights, or a file isn't on a local drive
+        pub fn create<'a>(files: impl Iterator<Item = &'a Path>) -> Option<Self> {
+            let mut volumes = Vec::new();
+            for file in files {
+                let volume = volume_of(file)?;
+                if !volumes.contains(&volume) {
+                    volumes.push(volume);
+                }
+            }
+
+            let mut copies = Self {
+                shadows: Vec::new(),
+            };
+            for volume in volumes {
+                match create_shadow(&volume) {
+                    Ok((id, device)) => copies.shadows.push(Shadow { volume, id, device }),
+                    Err(err) => {
+                        debug!("[PACKAGER] No shadow copy of {volume}: {err}");
+                        return None;
+                    }
+                }
+            }
+            Some(copies)
+        }
+
+        /// Where `path` can be read in its drive's shadow copy
+        pub fn path_for(&self, path: &Path) -> Option<PathBuf> {
+            let volume = volume_of(path)?;
+            let shadow = self.shadows.iter().find(|shadow| shadow.volume == volume)?;
+            let relative: PathBuf = path
+                .components()
+                .skip_while(|component| {
+                    matches!(component, Component::Prefix(_) | Component::RootDir)
+                })
+                .collect();
+            Some(Path::new(&format!("{}\\", shadow.device)).join(relative))
+        }
+    }
+
+    impl Drop for ShadowCopies {
+        fn drop(&mut self) {
+            for shadow in &self.shadows {
+                let script = format!(
+                    "Get-CimInstance Win32_ShadowCopy | Where-Object ID -eq '{}' | Remove-CimInstance",
+                    shadow.id
+                );
+                if let Err(err) = powershell(&script) {
+                    warn!(
+                        "[PACKAGER] Failed to delete shadow copy {}: {err}",
+                        shadow.id
+                    );
+                }
+            }
+        }
+    }
+
+    fn volume_of(path: &Path) -> Option<String> {
+        match path.components().next()? {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                    Some(format!("{}:\\", letter.to_ascii_uppercase() as char))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn create_shadow(volume: &str) -> io::Result<(String, String)> {
+        let script = format!(
+            "$r = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create \
+             -Arguments @{{Volume='{volume}'; Context='ClientAccessible'}}; \
+             if ($r.ReturnValue -ne 0) {{ exit 1 }}; \
+             $c = Get-CimInstance Win32_ShadowCopy | Where-Object ID -eq $r.ShadowID; \
+             $c.ID; $c.DeviceObject"
+        );
+        let output = powershell(&script)?;
+        let mut lines = output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        match (lines.next(), lines.next()) {
+            (Some(id), Some(device)) => Ok((id.to_string(), device.to_string())),
+            _ => Err(io::Error::other("no shadow copy reported")),
+        }
+    }
+
+    fn powershell(script: &str) -> io::Result<String> {
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_land_at_their_entry_names_and_go_away() {
+        let root = std::env::temp_dir().join(format!("crosssave-snapshot-{}", Uuid::new_v4()));
+        let saves = root.join("saves");
+        fs::create_dir_all(saves.join("slot1")).unwrap();
+        fs::write(saves.join("game.srm"), b"battery").unwrap();
+        fs::write(saves.join("slot1").join("game.state"), b"state").unwrap();
+
+        let files = vec![
+            (saves.join("game.srm"), "game.srm".to_string()),
+            (
+                saves.join("slot1").join("game.state"),
+                "slot1/game.state".to_string(),
+            ),
+        ];
+        let snapshot = snapshot_files(&files, &root).unwrap();
+        let dir = snapshot.dir.clone();
+        assert_eq!(fs::read(&snapshot.files[0]).unwrap(), b"battery");
+        assert_eq!(
+            fs::read(dir.join("slot1").join("game.state")).unwrap(),
+            b"state"
+        );
+
+        drop(snapshot);
+        assert!(!dir.exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,197 @@
+//! Archives between packaging and history. `SavePackager` writes them to
+//! the temp directory and tracks them here; whoever saves one to history
+//! releases it afterwards. Anything left behind by a crash is swept at the
+//! next start.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::core::snapshot_copy::SNAPSHOT_DIR_PREFIX;
+
+/// Untracked archives older than this are left over from an earlier run.
+/// Younger ones may belong to the CLI packaging at the same time.
+pub const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Temp archive usage, reported by `get_storage_info`
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct StagingStats {
+    pub files: usize,
+    pub bytes: u64,
+    /// Archives this run packaged and hasn't released yet
+    pub in_use: usize,
+}
+
+pub struct Staging {
+    dir: PathBuf,
+    tracked: Mutex<HashSet<PathBuf>>,
+}
+
+impl Staging {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            tracked: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Note an archive that was just written
+    pub fn track(&self, archive: &Path) {
+        if let Ok(mut tracked) = self.tracked.lock() {
+            tracked.insert(archive.to_path_buf());
+        }
+    }
+
+    /// Delete an archive once history has its own copy, or packaging was
+    /// abandoned
+    pub fn release(&self, archive: &Path) {
+        if let Ok(mut tracked) = self.tracked.lock() {
+            tracked.remove(archive);
+        }
+        if let Err(err) = fs::remove_file(archive) {
+            debug!("[PACKAGER] Could not remove staged archive {archive:?}: {err}");
+        }
+    }
+
+    /// Delete untracked archives, and snapshot folders of copy-then-zip
+    /// packaging, last modified more than `max_age` ago. Returns how many
+    /// were removed.
+    pub fn sweep_stale(&self, max_age: Duration) -> usize {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        let tracked = self
+            .tracked
+            .lock()
+            .map(|tracked| tracked.clone())
+            .unwrap_or_default();
+        let now = SystemTime::now();
+
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let is_snapshot = metadata.is_dir()
+                && entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(SNAPSHOT_DIR_PREFIX);
+            if !(metadata.is_file() || is_snapshot) || tracked.contains(&path) {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < max_age {
+                continue;
+            }
+            let result = if is_snapshot {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            match result {
+                Ok(()) => removed += 1,
+                Err(err) => warn!("[PACKAGER] Failed to remove stale archive {path:?}: {err}"),
+            }
+        }
+        if removed > 0 {
+            info!("[PACKAGER] Removed {removed} stale temp archives");
+        }
+        removed
+    }
+
+    pub fn stats(&self) -> StagingStats {
+        let mut stats = StagingStats {
+            in_use: self
+                .tracked
+                .lock()
+                .map(|tracked| tracked.len())
+                .unwrap_or(0),
+            ..StagingStats::default()
+        };
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for metadata in entries.flatten().filter_map(|entry| entry.metadata().ok()) {
+                if metadata.is_file() {
+                    stats.files += 1;
+                    stats.bytes += metadata.len();
+                }
+            }
+        }
+        stats
+    }
+}
+
+static STAGING: OnceLock<Staging> = OnceLock::new();
+
+/// The process-wide staging area in the system temp directory, so writing
+/// archives doesn't trigger the file watcher
+pub fn staging() -> &'static Staging {
+    STAGING.get_or_init(|| Staging::new(std::env::temp_dir().join("crosssave_archives")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_staging(name: &str) -> Staging {
+        let dir =
+            std::env::temp_dir().join(format!("crosssave_staging_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create staging dir");
+        Staging::new(dir)
+    }
+
+    #[test]
+    fn release_deletes_and_untracks() {
+        let staging = temp_staging("release");
+        let archive = staging.dir().join("game_v1.zip");
+        fs::write(&archive, b"zip").expect("write archive");
+        staging.track(&archive);
+        assert_eq!(
+            staging.stats(),
+            StagingStats {
+                files: 1,
+                bytes: 3,
+                in_use: 1
+            }
+        );
+
+        staging.release(&archive);
+        assert!(!archive.exists());
+        assert_eq!(staging.stats(), StagingStats::default());
+
+        let _ = fs::remove_dir_all(staging.dir());
+    }
+
+    #[test]
+    fn sweep_keeps_tracked_archives() {
+        let staging = temp_staging("sweep");
+        let leftover = staging.dir().join("old_v1.zip");
+        let current = staging.dir().join("game_v2.zip");
+        fs::write(&leftover, b"old").expect("write leftover");
+        fs::write(&current, b"new").expect("write current");
+        staging.track(&current);
+
+        assert_eq!(staging.sweep_stale(Duration::ZERO), 1);
+        assert!(!leftover.exists());
+        assert!(current.exists());
+
+        let _ = fs::remove_dir_all(staging.dir());
+    }
+}
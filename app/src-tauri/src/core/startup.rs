@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    Settings,
+    Profiles,
+    History,
+    Cloud,
+    Sync,
+}
+
+const SUBSYSTEMS: [Subsystem; 5] = [
+    Subsystem::Settings,
+    Subsystem::Profiles,
+    Subsystem::History,
+    Subsystem::Cloud,
+    Subsystem::Sync,
+];
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubsystemStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+/// Readiness of one subsystem, emitted as `startup://state`
+#[derive(Clone, Debug, Serialize)]
+pub struct SubsystemState {
+    pub subsystem: Subsystem,
+    pub status: SubsystemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Tracks which managers have finished initializing. The frontend listens to
+/// `startup://state` and can call `get_startup_state` for a snapshot when it
+/// subscribes late.
+#[derive(Clone, Debug)]
+pub struct StartupState {
+    states: Arc<RwLock<HashMap<Subsystem, SubsystemState>>>,
+}
+
+impl Default for StartupState {
+    fn default() -> Self {
+        let states = SUBSYSTEMS
+            .iter()
+            .map(|subsystem| {
+                (
+                    *subsystem,
+                    SubsystemState {
+                        subsystem: *subsystem,
+                        status: SubsystemStatus::Pending,
+                        message: None,
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            states: Arc::new(RwLock::new(states)),
+        }
+    }
+}
+
+impl StartupState {
+    pub fn mark_ready(&self, app: &AppHandle, subsystem: Subsystem) {
+        self.update(app, subsystem, SubsystemStatus::Ready, None);
+    }
+
+    pub fn mark_failed(&self, app: &AppHandle, subsystem: Subsystem, message: String) {
+        self.update(app, subsystem, SubsystemStatus::Failed, Some(message));
+    }
+
+    pub fn snapshot(&self) -> Vec<SubsystemState> {
+        let Ok(guard) = self.states.read() else {
+            return Vec::new();
+        };
+        SUBSYSTEMS
+            .iter()
+            .filter_map(|subsystem| guard.get(subsystem).cloned())
+            .collect()
+    }
+
+    /// True once no subsystem is still pending
+    pub fn is_settled(&self) -> bool {
+        self.snapshot()
+            .iter()
+            .all(|state| state.status != SubsystemStatus::Pending)
+    }
+
+    fn update(
+        &self,
+        app: &AppHandle,
+        subsystem: Subsystem,
+        status: SubsystemStatus,
+        message: Option<String>,
+    ) {
+        let state = SubsystemState {
+            subsystem,
+            status,
+            message,
+        };
+
+        match self.states.write() {
+            Ok(mut guard) => {
+                guard.insert(subsystem, state.clone());
+            }
+            Err(err) => {
+                warn!("[STARTUP] Failed to record {:?} state: {err}", subsystem);
+                return;
+            }
+        }
+
+        info!("[STARTUP] {:?} is {:?}", subsystem, status);
+        let _ = app.emit("startup://state", &state);
+
+        if self.is_settled() {
+            let _ = app.emit("startup://complete", self.snapshot());
+        }
+    }
+}
@@ -0,0 +1,531 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use super::profile::{EmulatorProfile, SaveEncryption};
+
+/// Steam tools that show up as installed apps but never hold saves
+const IGNORED_APP_PREFIXES: &[&str] = &[
+    "Proton",
+    "Steam Linux Runtime",
+    "Steamworks Common Redistributables",
+    "SteamVR",
+];
+
+#[derive(Debug, Error)]
+pub enum SteamError {
+    #[error("failed to read {0}: {1}")]
+    Read(String, String),
+    #[error("invalid manifest {0}: missing {1}")]
+    InvalidManifest(String, &'static str),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SteamGame {
+    pub app_id: String,
+    pub name: String,
+    pub install_dir: String,
+    pub library_path: PathBuf,
+    pub save_paths: Vec<PathBuf>,
+}
+
+impl SteamGame {
+    /// Suggested profile syncing every file under the discovered save paths
+    pub fn to_profile(&self) -> EmulatorProfile {
+        EmulatorProfile {
+            emulator_id: format!("steam_{}", self.app_id),
+            name: self.name.clone(),
+            default_save_paths: self.save_paths.clone(),
+            file_patterns: vec!["*".to_string()],
+            exclude_patterns: Vec::new(),
+            max_file_size_bytes: None,
+            path_groups: Vec::new(),
+            game_overrides: HashMap::new(),
+            save_encryption: SaveEncryption::Auto,
+            store_only_extensions: Vec::new(),
+            screenshot_dirs: Vec::new(),
+            snapshot_schedule: None,
+            process_names: Vec::new(),
+            placeholder_policy: Default::default(),
+            lock_retry: Default::default(),
+            snapshot_copy_min_bytes: None,
+        }
+    }
+}
+
+/// Scan local Steam installations for games with a detectable save location
+pub fn discover_steam_games() -> Vec<SteamGame> {
+    let mut games = Vec::new();
+    let mut seen_apps: HashSet<String> = HashSet::new();
+
+    for root in steam_roots() {
+        debug!("[STEAM] Scanning Steam root {:?}", root);
+        for library in library_folders(&root) {
+            for manifest in app_manifests(&library) {
+                let app = match parse_app_manifest(&manifest) {
+                    Ok(app) => app,
+                    Err(err) => {
+                        warn!("[STEAM] Skipping manifest: {err}");
+                        continue;
+                    }
+                };
+
+                if is_ignored_app(&app.name) || !seen_apps.insert(app.app_id.clone()) {
+                    continue;
+                }
+
+                let save_paths = save_locations(&root, &library, &app);
+                if save_paths.is_empty() {
+                    debug!("[STEAM] No save location found for {} ({})", app.name, app.app_id);
+                    continue;
+                }
+
+                games.push(SteamGame {
+                    app_id: app.app_id,
+                    name: app.name,
+                    install_dir: app.install_dir,
+                    library_path: library.clone(),
+                    save_paths,
+                });
+            }
+        }
+    }
+
+    info!("[STEAM] Discovered {} games with save locations", games.len());
+    games
+}
+
+/// Profiles generated from `discover_steam_games`
+pub fn discover_steam_profiles() -> Vec<EmulatorProfile> {
+    discover_steam_games()
+        .iter()
+        .map(SteamGame::to_profile)
+        .collect()
+}
+
+struct AppManifest {
+    app_id: String,
+    name: String,
+    install_dir: String,
+}
+
+fn steam_roots() -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+
+    if cfg!(target_os = "windows") {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Ok(base) = std::env::var(var) {
+                candidates.push(Path::new(&base).join("Steam"));
+            }
+        }
+    } else if let Ok(home) = std::env::var("HOME") {
+        let home = PathBuf::from(home);
+        if cfg!(target_os = "macos") {
+            candidates.push(home.join("Library/Application Support/Steam"));
+        } else {
+            candidates.push(home.join(".steam/steam"));
+            candidates.push(home.join(".local/share/Steam"));
+            candidates.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        }
+    }
+
+    dedup_existing(candidates)
+}
+
+fn library_folders(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+    let vdf_path = root.join("steamapps").join("libraryfolders.vdf");
+
+    match fs::read_to_string(&vdf_path) {
+        Ok(content) => {
+            for (depth, key, value) in parse_vdf(&content) {
+                // New format nests `"path"` inside numbered blocks, the old
+                // format stores the path directly under the numbered key
+                let is_old_format = depth == 1 && key.chars().all(|c| c.is_ascii_digit());
+                if key.eq_ignore_ascii_case("path") || is_old_format {
+                    libraries.push(PathBuf::from(value));
+                }
+            }
+        }
+        Err(err) => debug!("[STEAM] No library folders at {:?}: {err}", vdf_path),
+    }
+
+    dedup_existing(libraries)
+}
+
+fn app_manifests(library: &Path) -> Vec<PathBuf> {
+    let steamapps = library.join("steamapps");
+    let entries = match fs::read_dir(&steamapps) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("[STEAM] Failed to read {:?}: {err}", steamapps);
+            return Vec::new();
+        }
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with("appmanifest_") && name.ends_with(".acf"))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn parse_app_manifest(path: &Path) -> Result<AppManifest, SteamError> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| SteamError::Read(path.display().to_string(), err.to_string()))?;
+
+    let mut app_id = None;
+    let mut name = None;
+    let mut install_dir = None;
+
+    for (depth, key, value) in parse_vdf(&content) {
+        if depth != 1 {
+            continue;
+        }
+        match key.to_ascii_lowercase().as_str() {
+            "appid" => app_id = Some(value),
+            "name" => name = Some(value),
+            "installdir" => install_dir = Some(value),
+            _ => {}
+        }
+    }
+
+    let display = path.display().to_string();
+    let app_id = app_id.ok_or_else(|| SteamError::InvalidManifest(display.clone(), "appid"))?;
+    let name = name.ok_or_else(|| SteamError::InvalidManifest(display.clone(), "name"))?;
+    let install_dir = install_dir.unwrap_or_else(|| name.clone());
+
+    Ok(AppManifest {
+        app_id,
+        name,
+        install_dir,
+    })
+}
+
+fn save_locations(root: &Path, library: &Path, app: &AppManifest) -> Vec<PathBuf> {
+    let mut locations = Vec::new();
+
+    // Steam Cloud mirror: userdata/<account>/<appid>/remote
+    if let Ok(accounts) = fs::read_dir(root.join("userdata")) {
+        for account in accounts.filter_map(|entry| entry.ok()) {
+            let remote = account.path().join(&app.app_id).join("remote");
+            if remote.is_dir() {
+                locations.push(remote);
+            }
+        }
+    }
+
+    let names = [normalize_name(&app.name), normalize_name(&app.install_dir)];
+
+    // Proton keeps a separate Windows prefix per game
+    let proton_user = library
+        .join("steamapps/compatdata")
+        .join(&app.app_id)
+        .join("pfx/drive_c/users/steamuser");
+    if proton_user.is_dir() {
+        for base in windows_save_bases(&proton_user) {
+            locations.extend(matching_dirs(&base, &names));
+        }
+    }
+
+    for base in native_save_bases() {
+        locations.extend(matching_dirs(&base, &names));
+    }
+
+    dedup_existing(locations)
+}
+
+/// Save roots inside a Windows user profile directory
+fn windows_save_bases(user_dir: &Path) -> Vec<PathBuf> {
+    vec![
+        user_dir.join("AppData/Roaming"),
+        user_dir.join("AppData/Local"),
+        user_dir.join("AppData/LocalLow"),
+        user_dir.join("Documents/My Games"),
+        user_dir.join("Documents"),
+        user_dir.join("Saved Games"),
+    ]
+}
+
+fn native_save_bases() -> Vec<PathBuf> {
+    if cfg!(target_os = "windows") {
+        return std::env::var("USERPROFILE")
+            .map(|profile| windows_save_bases(Path::new(&profile)))
+            .unwrap_or_default();
+    }
+
+    let Ok(home) = std::env::var("HOME") else {
+        return Vec::new();
+    };
+    let home = PathBuf::from(home);
+
+    if cfg!(target_os = "macos") {
+        return vec![home.join("Library/Application Support")];
+    }
+
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".local/share"));
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".config"));
+    vec![data_home, config_home]
+}
+
+/// Directories under `base` (one or two levels, for `Publisher/Game`
+/// layouts) whose name matches one of `names`
+fn matching_dirs(base: &Path, names: &[String]) -> Vec<PathBuf> {
+    let mut matches = Vec::new();
+    let Ok(entries) = fs::read_dir(base) else {
+        return matches;
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        if dir_matches(&path, names) {
+            matches.push(path);
+            continue;
+        }
+
+        if let Ok(children) = fs::read_dir(&path) {
+            matches.extend(
+                children
+                    .filter_map(|child| child.ok())
+                    .map(|child| child.path())
+                    .filter(|child| child.is_dir() && dir_matches(child, names)),
+            );
+        }
+    }
+
+    matches
+}
+
+fn dir_matches(path: &Path, names: &[String]) -> bool {
+    let Some(dir_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let normalized = normalize_name(dir_name);
+    !normalized.is_empty() && names.iter().any(|name| *name == normalized)
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn is_ignored_app(name: &str) -> bool {
+    IGNORED_APP_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn dedup_existing(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| path.is_dir())
+        .filter(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+        .collect()
+}
+
+/// Flatten a Valve KeyValues (VDF) document into `(depth, key, value)`
+/// string pairs; depth 1 is the body of the top-level block.
+fn parse_vdf(content: &str) -> Vec<(usize, String, String)> {
+    let mut pairs = Vec::new();
+    let mut depth = 0usize;
+    let mut pending_key: Option<String> = None;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => token.push('\n'),
+                            Some('t') => token.push('\t'),
+                            Some(other) => token.push(other),
+                            None => break,
+                        },
+                        _ => token.push(c),
+                    }
+                }
+
+                match pending_key.take() {
+                    Some(key) => pairs.push((depth, key, token)),
+                    None => pending_key = Some(token),
+                }
+            }
+            '{' => {
+                pending_key = None;
+                depth += 1;
+            }
+            '}' => {
+                pending_key = None;
+                depth = depth.saturating_sub(1);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("crosssave-steam-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("steamapps")).unwrap();
+        root
+    }
+
+    /// A library folder to point the VDF at, escaped the way Steam writes
+    /// paths
+    fn library(root: &Path, name: &str) -> (PathBuf, String) {
+        let path = root.join(name);
+        fs::create_dir_all(&path).unwrap();
+        let escaped = path.display().to_string().replace('\\', "\\\\");
+        (path, escaped)
+    }
+
+    #[test]
+    fn parse_vdf_unescapes_quoted_tokens() {
+        let pairs = parse_vdf(
+            r#""AppState"
+            {
+                "name"  "Say \"Hi\"\tNow"
+                "path"  "D:\\SteamLibrary"
+            }"#,
+        );
+
+        assert_eq!(
+            pairs,
+            vec![
+                (1, "name".to_string(), "Say \"Hi\"\tNow".to_string()),
+                (1, "path".to_string(), "D:\\SteamLibrary".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_vdf_tracks_nested_blocks_and_skips_comments() {
+        let pairs = parse_vdf(
+            r#""libraryfolders"
+            {
+                // a comment with "quotes" in it
+                "contentstatsid"  "123"
+                "0"
+                {
+                    "path"  "/games"
+                    "apps"
+                    {
+                        "220"  "1024"
+                    }
+                    "label"  ""
+                }
+            }"#,
+        );
+
+        assert_eq!(
+            pairs,
+            vec![
+                (1, "contentstatsid".to_string(), "123".to_string()),
+                (2, "path".to_string(), "/games".to_string()),
+                (3, "220".to_string(), "1024".to_string()),
+                (2, "label".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn library_folders_reads_the_old_format() {
+        let root = temp_root();
+        let (first, first_escaped) = library(&root, "first");
+        let (second, second_escaped) = library(&root, "second");
+        fs::write(
+            root.join("steamapps").join("libraryfolders.vdf"),
+            format!(
+                "\"LibraryFolders\"\n{{\n\t\"TimeNextStatsReport\"\t\"1700000000\"\n\
+                 \t\"ContentStatsID\"\t\"-123\"\n\t\"1\"\t\"{first_escaped}\"\n\
+                 \t\"2\"\t\"{second_escaped}\"\n}}\n"
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(library_folders(&root), vec![root.clone(), first, second]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn library_folders_reads_the_new_format() {
+        let root = temp_root();
+        let (extra, extra_escaped) = library(&root, "extra");
+        let root_escaped = root.display().to_string().replace('\\', "\\\\");
+        fs::write(
+            root.join("steamapps").join("libraryfolders.vdf"),
+            format!(
+                "\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{root_escaped}\"\n\
+                 \t\t\"apps\"\n\t\t{{\n\t\t\t\"228980\"\t\t\"1024\"\n\t\t}}\n\t}}\n\
+                 \t\"1\"\n\t{{\n\t\t\"path\"\t\t\"{extra_escaped}\"\n\t\t\"apps\"\n\t\t{{\n\t\t}}\n\t}}\n}}\n"
+            ),
+        )
+        .unwrap();
+
+        // The root listed again and the app ids under it aren't libraries
+        assert_eq!(library_folders(&root), vec![root.clone(), extra]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn parse_app_manifest_reads_top_level_keys() {
+        let root = temp_root();
+        let path = root.join("steamapps").join("appmanifest_620.acf");
+        fs::write(
+            &path,
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"620\"\n\t\"name\"\t\t\"Portal 2\"\n\
+             \t\"installdir\"\t\t\"Portal 2\"\n\t\"UserConfig\"\n\t{\n\t\t\"name\"\t\t\"Other\"\n\t}\n}\n",
+        )
+        .unwrap();
+
+        let manifest = parse_app_manifest(&path).unwrap();
+        assert_eq!(manifest.app_id, "620");
+        assert_eq!(manifest.name, "Portal 2");
+        assert_eq!(manifest.install_dir, "Portal 2");
+
+        fs::write(&path, "\"AppState\"\n{\n\t\"name\"\t\t\"Portal 2\"\n}\n").unwrap();
+        assert!(matches!(
+            parse_app_manifest(&path),
+            Err(SteamError::InvalidManifest(_, "appid"))
+        ));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    fs, io,
+    path::{Component, Path, Prefix},
+};
+
+use serde::{Deserialize, Serialize};
+use sysinfo::Disks;
+
+/// `ENOSPC` on Linux, macOS and Android
+const ENOSPC: i32 = 28;
+/// `EDQUOT`, which differs between Linux/Android and the BSD family
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+const EDQUOT: i32 = 69;
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+const EDQUOT: i32 = 122;
+/// `ERROR_HANDLE_DISK_FULL` / `ERROR_DISK_FULL`
+const WIN_HANDLE_DISK_FULL: i32 = 39;
+const WIN_DISK_FULL: i32 = 112;
+/// Filesystem types of network shares, as listed in the mount table
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "9p",
+    "ceph",
+    "fuse.sshfs",
+    "fuse.rclone",
+];
+
+/// Which side ran out of space, sent with `sync://storage-full`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageScope {
+    Local,
+    Cloud,
+}
+
+impl StorageScope {
+    pub fn hint(&self) -> &'static str {
+        match self {
+            StorageScope::Local => {
+                "Free up disk space on this device, then resume sync from the status bar"
+            }
+            StorageScope::Cloud => {
+                "Your cloud storage is full. Delete old save versions or lower the history limit, then resume sync"
+            }
+        }
+    }
+}
+
+/// True when a write failed because the disk or the user's disk quota is full
+pub fn is_storage_full(err: &io::Error) -> bool {
+    match err.raw_os_error() {
+        Some(code) if cfg!(windows) => code == WIN_DISK_FULL || code == WIN_HANDLE_DISK_FULL,
+        Some(code) => code == ENOSPC || code == EDQUOT,
+        None => false,
+    }
+}
+
+/// 507 Insufficient Storage, or an S3-style quota error body
+pub fn is_cloud_quota_response(status: reqwest::StatusCode, body: &str) -> bool {
+    status == reqwest::StatusCode::INSUFFICIENT_STORAGE
+        || body.contains("QuotaExceeded")
+        || body.contains("InsufficientStorage")
+}
+
+/// Bytes of every file under `path`; unreadable entries count as empty
+pub fn dir_size(path: &Path) -> u64 {
+    let mut total = 0u64;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    total
+}
+
+/// Delete everything inside `dir`, keeping the directory. Returns the bytes
+/// freed.
+pub fn clear_dir(dir: &Path) -> io::Result<u64> {
+    let mut freed = 0u64;
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            let size = dir_size(&path);
+            fs::remove_dir_all(&path)?;
+            freed += size;
+        } else {
+            let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            fs::remove_file(&path)?;
+            freed += size;
+        }
+    }
+    Ok(freed)
+}
+
+/// Size and free space of a mounted volume
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeSpace {
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+/// The volume `path` lives on: the mounted disk with the longest mount
+/// point that contains it. `None` when the OS doesn't list one, as on
+/// some Android builds.
+pub fn volume_space(path: &Path) -> Option<VolumeSpace> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+        .map(|disk| VolumeSpace {
+            mount_point: disk.mount_point().to_string_lossy().to_string(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+        })
+}
+
+/// True when `path` is on an SMB, NFS or other network share, where OS
+/// change notifications are often missed. UNC paths always count.
+pub fn is_network_filesystem(path: &Path) -> bool {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if let Some(Component::Prefix(prefix)) = path.components().next() {
+        if matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..)) {
+            return true;
+        }
+    }
+    mount_filesystem(&path)
+        .map(|fs_type| NETWORK_FILESYSTEMS.contains(&fs_type.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Filesystem type of the mount holding `path`. The mount table is read
+/// directly rather than through `Disks`, which queries each mount's size and
+/// can hang on an unreachable share.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn mount_filesystem(path: &Path) -> Option<String> {
+    let table = fs::read_to_string("/proc/self/mounts").ok()?;
+    mount_type_in_table(&table, path)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn mount_filesystem(path: &Path) -> Option<String> {
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+        .map(|disk| disk.file_system().to_string_lossy().to_string())
+}
+
+/// Type of the longest mount point in a `/proc/mounts` table containing
+/// `path`. Spaces in mount points are escaped as `\040`.
+#[cfg_attr(not(any(target_os = "linux", target_os = "android")), allow(dead_code))]
+fn mount_type_in_table(table: &str, path: &Path) -> Option<String> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            Some((mount_point, fs_type))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| Path::new(mount_point).components().count())
+        .map(|(_, fs_type)| fs_type.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_mount_point_decides_the_filesystem() {
+        let table = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+//nas/games /mnt/nas\\040share cifs rw,vers=3.0 0 0
+nas:/export/saves /mnt/nas\\040share/nfs nfs4 rw 0 0
+";
+        let fs_type = |path: &str| mount_type_in_table(table, Path::new(path));
+        assert_eq!(fs_type("/home/deck/saves").as_deref(), Some("ext4"));
+        assert_eq!(fs_type("/mnt/nas share/snes").as_deref(), Some("cifs"));
+        assert_eq!(fs_type("/mnt/nas share/nfs/psx").as_deref(), Some("nfs4"));
+        assert_eq!(fs_type("/mnt/nas sharex").as_deref(), Some("ext4"));
+    }
+}
@@ -1,32 +1,48 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc, RwLock,
 };
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use reqwest::{header::CONTENT_LENGTH, header::CONTENT_TYPE, Client};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
 use sha2::{Digest, Sha256};
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::AsyncWriteExt;
-use tokio::sync::{Mutex, Notify};
-use tokio::time::sleep;
+use tokio::sync::{watch, Mutex, Notify};
+use tokio::time::{sleep, sleep_until};
 use tracing::{debug, error, info, warn};
 
+use crate::core::account::pending_account_switch;
+use crate::core::backoff::{Backoff, CircuitBreaker, CircuitSnapshot};
 use crate::core::cloud::{
-    ensure_device_identity, log_tag, CloudBackend, CloudVersionSummary, DownloadUrlResponse,
-    UploadRequest, UploadUrlResponse,
+    ensure_device_identity, link_duplicate, log_tag, CloudBackend, CloudError, CloudVersionSummary,
+    DownloadUrlResponse, ShareAccess, UploadRequest, UploadUrlResponse,
+};
+use crate::core::conflict::{ConflictManager, ConflictRecord, ConflictSide};
+use crate::core::connectivity::{
+    hold_reason, ConditionsPayload, Connectivity, HoldReason, PlatformConnectivity, Transfer,
+};
+use crate::core::game_info::attach_bundled;
+use crate::core::history::{EvictedVersion, HistoryEntry, HistoryError, HistoryManager};
+use crate::core::packager::{
+    decode_download_file, encode_for_upload, ArchiveEncoding, PackagerError, SaveMetadata,
+    ARCHIVE_CONTENT_TYPE, METADATA_SCHEMA_VERSION,
 };
-use crate::core::history::{HistoryEntry, HistoryManager};
-use crate::core::packager::SaveMetadata;
 use crate::core::profile::ProfileManager;
-use crate::core::settings::{CloudMode, SettingsManager};
-use zip::ZipArchive;
+use crate::core::reconcile::reconciliation_required;
+use crate::core::restore::{restore_target, restore_version, RestoreError};
+use crate::core::settings::{AppSettings, CloudMode, SettingsManager, SyncSchedule};
+use crate::core::storage::{is_cloud_quota_response, is_storage_full, StorageScope};
+use crate::core::sync_state::{SyncStateError, SyncStateStore};
+use crate::core::thumbnail::upload_thumbnail;
+use crate::core::watcher::WatcherManager;
 
 // ============================================================================
 // Sync Decision Logic
@@ -45,6 +61,7 @@ pub struct ConnectionStatus {
     pub connected: bool,
     pub last_success: Option<u64>, // timestamp in seconds
     pub last_error: Option<String>,
+    pub circuit: CircuitSnapshot,
 }
 
 /// Determines the sync action based on local and cloud state.
@@ -54,12 +71,20 @@ pub struct ConnectionStatus {
 /// - If local has no history -> Download latest cloud
 /// - If both exist:
 ///   - If hashes match -> Noop
+///   - If one version was made from the other (see `compare_lineage`) -> that one wins
+///   - If both were made from a version they share -> Conflict
+///
+/// When lineage cannot tell, e.g. for versions from before it was recorded:
+///   - If the cloud version came from this device -> no conflict; its clock
+///     drifting is no reason to ask, so the latest timestamp wins
+///   - If timestamps are equal and the cloud version came from another device -> Download
 ///   - If |timestamp difference| <= 2s -> Conflict
 ///   - Otherwise latest timestamp wins (upload if local is newer, download if cloud is newer)
 pub fn determine_sync_action(
     local_entry: Option<&HistoryEntry>,
+    local_lineage: &[String],
     cloud_versions: &[CloudVersionSummary],
-    _current_device: &str,
+    current_device: &str,
 ) -> SyncDecision {
     let latest_cloud = cloud_versions.iter().max_by_key(|v| v.timestamp);
 
@@ -75,16 +100,27 @@ pub fn determine_sync_action(
                 return SyncDecision::Noop;
             }
 
+            match compare_lineage(local_lineage, cloud, cloud_versions) {
+                Lineage::LocalAhead => return SyncDecision::Upload,
+                Lineage::CloudAhead => return SyncDecision::Download(cloud.version_id.clone()),
+                Lineage::Diverged => return SyncDecision::Conflict,
+                Lineage::Unknown => {}
+            }
+
             let time_diff = if cloud_time > local_time {
                 cloud_time - local_time
             } else {
                 local_time - cloud_time
             };
 
-            let conflict_window = time_diff <= 2;
-
-            if conflict_window {
-                return SyncDecision::Conflict;
+            let from_this_device = !current_device.is_empty() && cloud.device_id == current_device;
+            if !from_this_device {
+                if cloud_time == local_time {
+                    return SyncDecision::Download(cloud.version_id.clone());
+                }
+                if time_diff <= 2 {
+                    return SyncDecision::Conflict;
+                }
             }
 
             if cloud_time > local_time {
@@ -96,6 +132,75 @@ pub fn determine_sync_action(
     }
 }
 
+/// How the latest local and cloud versions relate through their parents
+#[derive(Debug, PartialEq, Eq)]
+enum Lineage {
+    /// The cloud version is one the local version was made from
+    LocalAhead,
+    /// The local version is one the cloud version was made from
+    CloudAhead,
+    /// Both were made from a version they share, on different devices
+    Diverged,
+    Unknown,
+}
+
+/// `local_lineage` is the local version and its ancestors, newest first, from
+/// `HistoryManager::lineage`; the cloud side walks `cloud_versions`
+fn compare_lineage(
+    local_lineage: &[String],
+    cloud: &CloudVersionSummary,
+    cloud_versions: &[CloudVersionSummary],
+) -> Lineage {
+    let Some(local_id) = local_lineage.first() else {
+        return Lineage::Unknown;
+    };
+    if local_lineage.contains(&cloud.version_id) {
+        return Lineage::LocalAhead;
+    }
+
+    let parents: HashMap<&str, &str> = cloud_versions
+        .iter()
+        .filter_map(|version| {
+            let parent = version.parent_version_id.as_deref()?;
+            Some((version.version_id.as_str(), parent))
+        })
+        .collect();
+    let mut current = cloud.version_id.as_str();
+    // Bounded by the listed versions, in case the metadata holds a cycle
+    for _ in 0..=cloud_versions.len() {
+        let Some(&parent) = parents.get(current) else {
+            break;
+        };
+        if parent == local_id.as_str() {
+            return Lineage::CloudAhead;
+        }
+        if local_lineage.iter().any(|id| id == parent) {
+            return Lineage::Diverged;
+        }
+        current = parent;
+    }
+    Lineage::Unknown
+}
+
+/// Cloud versions to decide `game_id` with, from the batch of latest
+/// versions alone: none when the cloud has none, or the latest when it
+/// matches the local version or is one of its ancestors. `None` when the
+/// decision needs the game's other versions.
+fn batched_versions(
+    latest: &HashMap<String, CloudVersionSummary>,
+    game_id: &str,
+    local_entry: Option<&HistoryEntry>,
+    local_lineage: &[String],
+) -> Option<Vec<CloudVersionSummary>> {
+    let Some(cloud) = latest.get(game_id) else {
+        return Some(Vec::new());
+    };
+    let settled = local_entry.is_some_and(|local| {
+        local.metadata.hash == cloud.sha256 || local_lineage.contains(&cloud.version_id)
+    });
+    settled.then(|| vec![cloud.clone()])
+}
+
 async fn ensure_registered_device_for_sync(
     cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
     settings: &Arc<SettingsManager>,
@@ -108,6 +213,14 @@ async fn ensure_registered_device_for_sync(
     if settings_snapshot.cloud_mode == CloudMode::Off {
         return Err("Cloud sync is disabled".to_string());
     }
+    if settings_snapshot.cloud_mode == CloudMode::Demo {
+        // The in-memory cloud has no accounts to register with
+        return cloud
+            .lock()
+            .await
+            .ensure_device_id()
+            .map_err(|e| e.to_string());
+    }
 
     let token = match settings_snapshot.cloud_mode {
         CloudMode::SelfHost => settings_snapshot.self_host.access_key.clone(),
@@ -118,54 +231,41 @@ async fn ensure_registered_device_for_sync(
         return Err("Cloud sync is not configured.".to_string());
     }
 
-    let (device_id, platform, device_name) =
-        ensure_device_identity(settings).map_err(|e| e.to_string())?;
+    let (device_id, _, _) = ensure_device_identity(settings).map_err(|e| e.to_string())?;
 
-    {
-        let backend = cloud.lock().await;
-        let devices = backend
-            .list_devices(token.clone())
-            .await
-            .map_err(|e| e.to_string())?;
-        if devices.iter().any(|device| device.device_id == device_id) {
-            return Ok(device_id);
-        }
+    // Registered with this exact mode, server and account already
+    if settings_snapshot.device_registered() && settings_snapshot.cloud.device_id == device_id {
+        return Ok(device_id);
     }
 
-    let _ = app_handle.emit("sync://device-missing", device_id.clone());
-
-    {
-        let backend = cloud.lock().await;
-        backend
-            .register_device(
-                token.clone(),
-                device_id.clone(),
-                platform.clone(),
-                device_name.clone(),
-            )
-            .await
-            .map_err(|e| {
-                let message = e.to_string();
-                let _ = app_handle.emit("sync://device-error", message.clone());
-                message
-            })?;
+    // The backend asks `/device/check` and registers only an unknown
+    // device, caching the answer for a while
+    let registered = cloud.lock().await.ensure_device_registered().await;
+    match registered {
+        Ok(registered_id) => {
+            mark_device_registered(settings);
+            let _ = app_handle.emit("cloud://device-registered", registered_id.clone());
+            Ok(registered_id)
+        }
+        Err(err) => {
+            let message = err.to_string();
+            let _ = app_handle.emit("sync://device-error", message.clone());
+            Err(message)
+        }
     }
+}
 
-    {
-        let backend = cloud.lock().await;
-        let devices = backend
-            .list_devices(token)
-            .await
-            .map_err(|e| e.to_string())?;
-        if devices.iter().any(|device| device.device_id == device_id) {
-            let _ = app_handle.emit("cloud://device-registered", device_id.clone());
-            return Ok(device_id);
+fn mark_device_registered(settings: &SettingsManager) {
+    match settings.get_settings() {
+        Ok(mut current) if !current.device_registered() => {
+            current.mark_device_registered();
+            if let Err(err) = settings.update_settings(current) {
+                warn!("[SYNC] Failed to persist device registration: {err}");
+            }
         }
+        Ok(_) => {}
+        Err(err) => warn!("[SYNC] Failed to load settings: {err}"),
     }
-
-    let message = "Device registration verification failed".to_string();
-    let _ = app_handle.emit("sync://device-error", message.clone());
-    Err(message)
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -177,6 +277,7 @@ struct DownloadProgressPayload {
 
 #[derive(Clone, Debug, Serialize)]
 struct DownloadCompletePayload {
+    game_id: String,
     version_id: String,
 }
 
@@ -195,6 +296,7 @@ struct UploadProgressPayload {
 
 #[derive(Clone, Debug, Serialize)]
 struct UploadCompletePayload {
+    game_id: String,
     version_id: String,
 }
 
@@ -208,6 +310,449 @@ struct UploadErrorPayload {
     status: Option<u16>,
 }
 
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStage {
+    IndexingLocal,
+    FetchingCloud,
+    Ready,
+}
+
+/// Staged startup progress, emitted as `sync://progress`
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncProgressPayload {
+    pub stage: SyncStage,
+    pub current: usize,
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+}
+
+/// Emit at most this many progress events per stage
+const PROGRESS_EVENT_STEPS: usize = 100;
+/// Cloud versions listed per game when deciding what to sync, so the
+/// latest one's parents can be followed back to a local version
+pub(crate) const LINEAGE_WINDOW: usize = 32;
+
+fn emit_sync_progress(
+    app_handle: &AppHandle,
+    stage: SyncStage,
+    current: usize,
+    total: usize,
+    game_id: Option<&str>,
+) {
+    // Throttle large scans so the frontend is not flooded
+    let step = (total / PROGRESS_EVENT_STEPS).max(1);
+    if current != total && current % step != 0 {
+        return;
+    }
+
+    let _ = app_handle.emit(
+        "sync://progress",
+        SyncProgressPayload {
+            stage,
+            current,
+            total,
+            game_id: game_id.map(String::from),
+        },
+    );
+}
+
+// ============================================================================
+// Storage Full
+// ============================================================================
+
+/// Emitted as `sync://storage-full` when the disk or the cloud quota is full
+#[derive(Clone, Debug, Serialize)]
+pub struct StorageFullPayload {
+    pub scope: StorageScope,
+    pub message: String,
+    pub hint: String,
+}
+
+/// Pause sync and tell the user which side ran out of space. Sync stays
+/// paused until `resume_sync` is called, so queued uploads are kept instead
+/// of burning through their retries.
+pub fn report_storage_full(app_handle: &AppHandle, scope: StorageScope, message: String) {
+    warn!("[SYNC] Storage full ({:?}): {}", scope, message);
+
+    if let Some(manager) = app_handle.try_state::<SyncManager>() {
+        manager.pause();
+    }
+
+    let _ = app_handle.emit(
+        "sync://storage-full",
+        StorageFullPayload {
+            scope,
+            message,
+            hint: scope.hint().to_string(),
+        },
+    );
+}
+
+/// Emitted as `history://evicted` after versions were removed to stay under
+/// the local history size budget
+#[derive(Clone, Debug, Serialize)]
+pub struct HistoryEvictedPayload {
+    pub evicted: Vec<EvictedVersion>,
+    pub freed_bytes: u64,
+}
+
+/// Apply the local history size budget and tell the UI what was removed
+pub fn enforce_history_budget(app_handle: &AppHandle, history: &HistoryManager) {
+    let evicted = match history.enforce_size_budget() {
+        Ok(evicted) => evicted,
+        Err(err) => {
+            warn!("[HISTORY] Failed to enforce history size budget: {err}");
+            return;
+        }
+    };
+    if evicted.is_empty() {
+        return;
+    }
+
+    let freed_bytes = evicted.iter().map(|version| version.size_bytes).sum();
+    info!(
+        "[HISTORY] Evicted {} versions ({} bytes) to stay under budget",
+        evicted.len(),
+        freed_bytes
+    );
+    let _ = app_handle.emit(
+        "history://evicted",
+        HistoryEvictedPayload {
+            evicted,
+            freed_bytes,
+        },
+    );
+}
+
+/// Bring history retention in line with `settings`, then apply the size
+/// budget
+pub fn apply_history_settings(
+    app_handle: &AppHandle,
+    history: &HistoryManager,
+    settings: &AppSettings,
+) {
+    if let Err(err) = history.set_policy(settings.retention_limit, settings.auto_delete) {
+        warn!("[HISTORY] Failed to apply retention limit: {err}");
+    }
+    if let Err(err) = history.set_size_budget(settings.max_history_size_mb) {
+        warn!("[HISTORY] Failed to apply history size budget: {err}");
+    }
+    if let Err(err) = history.set_age_retention(
+        settings.age_retention.clone(),
+        settings.game_age_retention.clone(),
+    ) {
+        warn!("[HISTORY] Failed to apply history age limits: {err}");
+    }
+    enforce_history_budget(app_handle, history);
+}
+
+/// Log how a conflict for `game_id` was resolved. When the user has now made
+/// the same choice several times in a row, `sync://conflict-rule-suggested`
+/// offers to turn it into a rule.
+pub async fn record_conflict_resolution(
+    app_handle: &AppHandle,
+    history: &HistoryManager,
+    cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    settings: &SettingsManager,
+    game_id: &str,
+    kept: ConflictSide,
+) {
+    let Some(conflicts) = app_handle.try_state::<Arc<ConflictManager>>() else {
+        return;
+    };
+    let Some(local) = history.get_latest_version(game_id) else {
+        return;
+    };
+    let cloud_latest = match cloud
+        .lock()
+        .await
+        .list_versions(game_id.to_string(), Some(1))
+        .await
+    {
+        Ok(versions) => versions.into_iter().next(),
+        Err(err) => {
+            warn!("[SYNC] Failed to load conflict context for {game_id}: {err}");
+            return;
+        }
+    };
+    let Some(cloud_latest) = cloud_latest else {
+        return;
+    };
+    let local_device_id = settings
+        .get_settings()
+        .map(|s| s.cloud.device_id)
+        .unwrap_or_default();
+
+    let record = conflict_record(game_id, kept, &local, &cloud_latest, local_device_id, false);
+    match conflicts.record(record) {
+        Ok(Some(suggestion)) => {
+            info!("[SYNC] Suggesting a conflict rule for {game_id}");
+            let _ = app_handle.emit("sync://conflict-rule-suggested", suggestion);
+        }
+        Ok(None) => {}
+        Err(err) => warn!("[SYNC] Failed to record conflict resolution: {err}"),
+    }
+}
+
+/// Whether the local history may be synced with the signed-in account
+fn history_owned_by_account(settings: &SettingsManager) -> bool {
+    settings
+        .get_settings()
+        .is_ok_and(|s| s.history_belongs_to_account())
+}
+
+/// Update the persisted sync times, if the store loaded at startup
+fn record_sync_event(
+    app_handle: &AppHandle,
+    change: impl FnOnce(&SyncStateStore) -> Result<(), SyncStateError>,
+) {
+    let Some(state) = app_handle.try_state::<Arc<SyncStateStore>>() else {
+        return;
+    };
+    if let Err(err) = change(&state) {
+        warn!("[SYNC] Failed to record sync state: {err}");
+    }
+}
+
+/// Resolve a conflict with the game's rule, if the user created one
+fn resolve_with_rule(
+    app_handle: &AppHandle,
+    game_id: &str,
+    local: Option<&HistoryEntry>,
+    cloud_versions: &[CloudVersionSummary],
+    current_device: &str,
+) -> Option<SyncDecision> {
+    let conflicts = app_handle.try_state::<Arc<ConflictManager>>()?;
+    let rule = conflicts.rule_for(game_id)?;
+    let local = local?;
+    let cloud_latest = cloud_versions.iter().max_by_key(|v| v.timestamp)?;
+
+    let kept = rule.resolve(current_device, &cloud_latest.device_id);
+    info!("[SYNC] Conflict for {game_id} resolved by rule: keep {kept:?}");
+    record_sync_event(app_handle, |state| state.record_conflict(game_id));
+
+    let record = conflict_record(
+        game_id,
+        kept,
+        local,
+        cloud_latest,
+        current_device.to_string(),
+        true,
+    );
+    if let Err(err) = conflicts.record(record) {
+        warn!("[SYNC] Failed to record conflict resolution: {err}");
+    }
+
+    Some(match kept {
+        ConflictSide::Local => SyncDecision::Upload,
+        ConflictSide::Cloud => SyncDecision::Download(cloud_latest.version_id.clone()),
+    })
+}
+
+fn conflict_record(
+    game_id: &str,
+    kept: ConflictSide,
+    local: &HistoryEntry,
+    cloud: &CloudVersionSummary,
+    local_device_id: String,
+    automatic: bool,
+) -> ConflictRecord {
+    ConflictRecord {
+        game_id: game_id.to_string(),
+        kept,
+        local_device_id,
+        cloud_device_id: cloud.device_id.clone(),
+        local_timestamp: local.metadata.timestamp,
+        cloud_timestamp: cloud.timestamp,
+        time_gap_secs: local.metadata.timestamp.abs_diff(cloud.timestamp),
+        resolved_at: Utc::now().timestamp().max(0) as u64,
+        automatic,
+    }
+}
+
+// ============================================================================
+// Transfer Watchdog
+// ============================================================================
+
+/// Abort a transfer when no bytes have moved for this long
+const TRANSFER_STALL_TIMEOUT: Duration = Duration::from_secs(45);
+/// Abort an upload whose response hasn't come this long after the whole
+/// body went out. Servers may take a while to commit a large object, so
+/// this wait doesn't count as a stall.
+const UPLOAD_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Download attempts (resuming with a Range request) before giving up
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+const UPLOAD_CHUNK_BYTES: usize = 64 * 1024;
+
+#[derive(Debug)]
+enum TransferError {
+    Stalled,
+    /// The body was sent but no response followed
+    NoResponse,
+    Http(String),
+    Io(String),
+    StorageFull(String),
+}
+
+impl TransferError {
+    fn write(err: std::io::Error) -> Self {
+        if is_storage_full(&err) {
+            TransferError::StorageFull(err.to_string())
+        } else {
+            TransferError::Io(err.to_string())
+        }
+    }
+
+    fn stage(&self) -> &'static str {
+        match self {
+            TransferError::Stalled => "stalled",
+            TransferError::NoResponse => "no-response",
+            TransferError::Http(_) => "http-get",
+            TransferError::Io(_) => "write-file",
+            TransferError::StorageFull(_) => "disk-full",
+        }
+    }
+}
+
+impl std::fmt::Display for TransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferError::Stalled => write!(
+                f,
+                "transfer stalled: no data for {}s",
+                TRANSFER_STALL_TIMEOUT.as_secs()
+            ),
+            TransferError::NoResponse => write!(
+                f,
+                "no response {}s after the upload finished",
+                UPLOAD_RESPONSE_TIMEOUT.as_secs()
+            ),
+            TransferError::Http(msg) | TransferError::Io(msg) | TransferError::StorageFull(msg) => {
+                write!(f, "{msg}")
+            }
+        }
+    }
+}
+
+/// Tracks when a streaming body last moved bytes, and whether all of it
+/// has been handed over
+struct TransferActivity {
+    started: Instant,
+    last_activity_ms: AtomicU64,
+    body_done: AtomicBool,
+}
+
+impl TransferActivity {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            last_activity_ms: AtomicU64::new(0),
+            body_done: AtomicBool::new(false),
+        }
+    }
+
+    fn record(&self) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        self.last_activity_ms.store(elapsed, Ordering::SeqCst);
+    }
+
+    /// The last chunk of the body was taken; from here on the transfer
+    /// waits for the response
+    fn finish_body(&self) {
+        self.record();
+        self.body_done.store(true, Ordering::SeqCst);
+    }
+
+    fn idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_activity_ms.load(Ordering::SeqCst));
+        self.started.elapsed().saturating_sub(last)
+    }
+}
+
+/// Run `future` until it finishes, `activity` has been idle for
+/// `TRANSFER_STALL_TIMEOUT` while sending the body, or no response came
+/// within `UPLOAD_RESPONSE_TIMEOUT` after it
+async fn watch_transfer<F: std::future::Future>(
+    future: F,
+    activity: &TransferActivity,
+) -> Result<F::Output, TransferError> {
+    tokio::pin!(future);
+    loop {
+        tokio::select! {
+            output = &mut future => return Ok(output),
+            _ = sleep(Duration::from_secs(1)) => {
+                let idle = activity.idle_for();
+                if activity.body_done.load(Ordering::SeqCst) {
+                    if idle >= UPLOAD_RESPONSE_TIMEOUT {
+                        return Err(TransferError::NoResponse);
+                    }
+                } else if idle >= TRANSFER_STALL_TIMEOUT {
+                    return Err(TransferError::Stalled);
+                }
+            }
+        }
+    }
+}
+
+/// Stream `url` into `target_path`, resuming from `received_bytes` with a
+/// Range request when a previous attempt stalled
+async fn stream_download(
+    client: &Client,
+    url: &str,
+    target_path: &PathBuf,
+    received_bytes: &mut u64,
+    mut on_progress: impl FnMut(u64),
+) -> Result<(), TransferError> {
+    let mut request = client.get(url);
+    if *received_bytes > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", received_bytes));
+    }
+
+    let mut response = tokio::time::timeout(TRANSFER_STALL_TIMEOUT, request.send())
+        .await
+        .map_err(|_| TransferError::Stalled)?
+        .map_err(|e| TransferError::Http(e.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(TransferError::Http(format!(
+            "download failed: {}",
+            response.status()
+        )));
+    }
+
+    let resumed = *received_bytes > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(target_path)
+            .await
+    } else {
+        // Server ignored the Range header (or this is the first attempt)
+        *received_bytes = 0;
+        tokio::fs::File::create(target_path).await
+    }
+    .map_err(TransferError::write)?;
+
+    loop {
+        let chunk = tokio::time::timeout(TRANSFER_STALL_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| TransferError::Stalled)?
+            .map_err(|e| TransferError::Http(e.to_string()))?;
+
+        let Some(chunk) = chunk else {
+            break;
+        };
+
+        file.write_all(&chunk).await.map_err(TransferError::write)?;
+        *received_bytes += chunk.len() as u64;
+        on_progress(*received_bytes);
+    }
+
+    file.flush().await.map_err(TransferError::write)
+}
+
 // ...
 
 // ============================================================================
@@ -235,47 +780,364 @@ pub struct UploadJob {
     pub status: UploadStatus,
     pub total_size: u64,
     pub hash: String,
+    /// Set after a failed attempt; the job is skipped until then
+    #[serde(default)]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl UploadJob {
+    /// A pending upload of a local history version
+    pub fn from_entry(entry: &HistoryEntry) -> Self {
+        Self {
+            game_id: entry.metadata.game_id.clone(),
+            emulator_id: entry.metadata.emulator_id.clone(),
+            version_id: entry.metadata.version_id.clone(),
+            archive_path: entry.archive_path.clone(),
+            metadata: entry.metadata.clone(),
+            created_at: DateTime::from_timestamp(entry.metadata.timestamp as i64, 0)
+                .unwrap_or_default(),
+            retries: 0,
+            status: UploadStatus::Pending,
+            total_size: fs::metadata(&entry.archive_path)
+                .map(|m| m.len())
+                .unwrap_or_default(),
+            hash: entry.metadata.hash.clone(),
+            next_attempt_at: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct SyncStatus {
+    pub queue_length: usize,
+    pub active_job: Option<UploadJob>,
+    pub download_queue_length: usize,
+    pub active_download: Option<DownloadJob>,
+    pub last_sync: Option<DateTime<Utc>>,
+    pub is_syncing: bool,
+}
+
+/// Both queues' jobs, shared so either queue can report the combined
+/// `sync://status`
+#[derive(Clone, Default)]
+pub struct QueueStatus {
+    uploads: Arc<Mutex<VecDeque<UploadJob>>>,
+    active_upload: Arc<Mutex<Option<UploadJob>>>,
+    downloads: Arc<Mutex<VecDeque<DownloadJob>>>,
+    active_download: Arc<Mutex<Option<DownloadJob>>>,
+    sync_state: Option<Arc<SyncStateStore>>,
+}
+
+impl QueueStatus {
+    fn new(sync_state: Option<Arc<SyncStateStore>>) -> Self {
+        Self {
+            sync_state,
+            ..Self::default()
+        }
+    }
+
+    async fn snapshot(&self) -> SyncStatus {
+        let queue_length = self.uploads.lock().await.len();
+        let active_job = self.active_upload.lock().await.clone();
+        let download_queue_length = self.downloads.lock().await.len();
+        let active_download = self.active_download.lock().await.clone();
+
+        let is_syncing = queue_length > 0
+            || active_job.is_some()
+            || download_queue_length > 0
+            || active_download.is_some();
+
+        SyncStatus {
+            queue_length,
+            active_job,
+            download_queue_length,
+            active_download,
+            last_sync: self.sync_state.as_ref().and_then(|state| state.last_sync()),
+            is_syncing,
+        }
+    }
+
+    async fn emit(&self, app_handle: &AppHandle) {
+        let status = self.snapshot().await;
+        let _ = app_handle.emit("sync://status", status);
+    }
+}
+
+/// Delay between attempts of a failed upload or download job
+const JOB_RETRY_BACKOFF: Backoff = Backoff::new(Duration::from_secs(2), Duration::from_secs(120));
+
+/// How often held queues look again, since a settings change doesn't wake them
+const CONDITIONS_RECHECK: Duration = Duration::from_secs(60);
+
+/// When a failed job may run again
+fn retry_time(delay: Duration) -> Option<DateTime<Utc>> {
+    chrono::Duration::from_std(delay)
+        .ok()
+        .map(|delay| Utc::now() + delay)
+}
+
+/// Remove the first job that is due. A job waiting out its backoff is
+/// skipped, and so are later jobs of the same game so versions stay in
+/// order. Without a due job, returns how long until the earliest retry.
+fn take_due_job<T>(
+    queue: &mut VecDeque<T>,
+    game_id: impl Fn(&T) -> &str,
+    retry_at: impl Fn(&T) -> Option<DateTime<Utc>>,
+) -> (Option<T>, Option<Duration>) {
+    let now = Utc::now();
+    let mut waiting: Vec<&str> = Vec::new();
+    let mut earliest: Option<DateTime<Utc>> = None;
+    let mut due = None;
+
+    for (index, job) in queue.iter().enumerate() {
+        match retry_at(job) {
+            Some(at) if at > now => {
+                waiting.push(game_id(job));
+                earliest = Some(earliest.map_or(at, |current| current.min(at)));
+            }
+            _ if waiting.contains(&game_id(job)) => {}
+            _ => {
+                due = Some(index);
+                break;
+            }
+        }
+    }
+
+    match due {
+        Some(index) => (queue.remove(index), None),
+        None => (
+            None,
+            earliest.map(|at| (at - now).to_std().unwrap_or_default()),
+        ),
+    }
+}
+
+/// Why `transfer` has to wait under the last reported device conditions
+fn transfer_hold(
+    connectivity: &Connectivity,
+    settings: &SettingsManager,
+    transfer: Transfer,
+) -> Option<HoldReason> {
+    let network = settings
+        .get_settings()
+        .map(|settings| settings.network)
+        .unwrap_or_default();
+    hold_reason(&network, &connectivity.current(), transfer)
+}
+
+/// Sleep until `wait` passes, or forever when there is nothing to wait for
+async fn sleep_for(wait: Option<Duration>) {
+    match wait {
+        Some(wait) => sleep(wait).await,
+        None => std::future::pending().await,
+    }
 }
 
-#[derive(Clone, Serialize)]
-pub struct SyncStatus {
-    pub queue_length: usize,
-    pub active_job: Option<UploadJob>,
-    pub last_sync: Option<DateTime<Utc>>,
-    pub is_syncing: bool,
+/// Whether sync is paused, shared by the manager and both queues. Waiters
+/// read the flag through the channel they wait on, so a resume that lands
+/// between the check and the wait still wakes them.
+#[derive(Clone)]
+pub struct PauseSwitch(Arc<watch::Sender<bool>>);
+
+impl PauseSwitch {
+    fn new() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Returns whether this changed the state
+    fn set(&self, paused: bool) -> bool {
+        self.0
+            .send_if_modified(|state| std::mem::replace(state, paused) != paused)
+    }
+
+    /// Until sync is no longer paused
+    async fn resumed(&self) {
+        let mut state = self.0.subscribe();
+        let _ = state.wait_for(|paused| !*paused).await;
+    }
+}
+
+/// A job kept by a `JobQueue`
+trait QueuedJob: Clone + Serialize + DeserializeOwned {
+    /// Whether the job is still worth saving for the next start
+    fn persist(&self) -> bool {
+        true
+    }
+
+    /// Undo state that only held for the run that saved the job
+    fn restore(&mut self) {}
+}
+
+impl QueuedJob for UploadJob {
+    fn persist(&self) -> bool {
+        matches!(self.status, UploadStatus::Pending | UploadStatus::Uploading)
+    }
+
+    fn restore(&mut self) {
+        if self.status == UploadStatus::Uploading {
+            self.status = UploadStatus::Pending;
+        }
+    }
+}
+
+impl QueuedJob for DownloadJob {}
+
+/// What the upload and download queues share: the jobs waiting and the one
+/// in progress, the signals their processors sleep on, and the file that
+/// keeps the jobs across restarts
+struct JobQueue<T> {
+    /// Names the queue in logs
+    label: &'static str,
+    queue: Arc<Mutex<VecDeque<T>>>,
+    active_job: Arc<Mutex<Option<T>>>,
+    notify: Notify,
+    online_notify: Notify,
+    online_status: Arc<AtomicBool>,
+    paused: PauseSwitch,
+    path: PathBuf,
+}
+
+impl<T: QueuedJob> JobQueue<T> {
+    fn new(
+        app_handle: &AppHandle,
+        file_name: &str,
+        label: &'static str,
+        (queue, active_job): (Arc<Mutex<VecDeque<T>>>, Arc<Mutex<Option<T>>>),
+        online_status: Arc<AtomicBool>,
+        paused: PauseSwitch,
+    ) -> Self {
+        let path = app_handle
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join("data").join(file_name))
+            .unwrap_or_else(|_| PathBuf::from(file_name));
+        Self {
+            label,
+            queue,
+            active_job,
+            notify: Notify::new(),
+            online_notify: Notify::new(),
+            online_status,
+            paused,
+            path,
+        }
+    }
+
+    async fn load_from_disk(&self) {
+        let Ok(content) = fs::read_to_string(&self.path) else {
+            return;
+        };
+
+        match serde_json::from_str::<Vec<T>>(&content) {
+            Ok(jobs) if !jobs.is_empty() => {
+                let mut q = self.queue.lock().await;
+                q.clear();
+                q.extend(jobs.into_iter().map(|mut job| {
+                    job.restore();
+                    job
+                }));
+                info!("[QUEUE] Restored {} jobs to the {}", q.len(), self.label);
+                drop(q);
+                self.notify.notify_one();
+            }
+            Ok(_) => {}
+            Err(err) => warn!("[QUEUE] Failed to parse the {}: {}", self.label, err),
+        }
+    }
+
+    /// The active job is saved too, so a transfer cut short by a crash or
+    /// shutdown resumes on the next start
+    async fn save_to_disk(&self) {
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        let mut jobs: Vec<T> = Vec::new();
+        if let Some(active) = self.active_job.lock().await.clone() {
+            jobs.push(active);
+        }
+        jobs.extend(self.queue.lock().await.iter().cloned());
+        jobs.retain(QueuedJob::persist);
+
+        match serde_json::to_string_pretty(&jobs) {
+            Ok(json) => {
+                if let Err(e) = fs::write(&self.path, json) {
+                    warn!("[QUEUE] Failed to save the {} to disk: {}", self.label, e);
+                }
+            }
+            Err(e) => warn!("[QUEUE] Failed to serialize the {}: {}", self.label, e),
+        }
+    }
+
+    async fn clear(&self) {
+        self.queue.lock().await.clear();
+        self.save_to_disk().await;
+    }
+
+    fn signal_online(&self) {
+        self.online_notify.notify_waiters();
+    }
+
+    async fn wait_for_online(&self) {
+        while !self.online_status.load(Ordering::SeqCst) {
+            let _ = self.online_notify.notified().await;
+        }
+    }
+
+    async fn wait_for_resume(&self) {
+        self.paused.resumed().await;
+    }
+
+    /// Until device conditions change, or a while in case the settings did
+    async fn wait_for_conditions(&self) {
+        tokio::select! {
+            _ = sleep(CONDITIONS_RECHECK) => {},
+            _ = self.online_notify.notified() => {},
+        }
+    }
 }
 
 pub struct UploadQueue {
-    queue: Arc<Mutex<VecDeque<UploadJob>>>,
-    active_job: Arc<Mutex<Option<UploadJob>>>,
-    notify: Arc<Notify>,
-    online_notify: Arc<Notify>,
-    online_status: Arc<AtomicBool>,
+    jobs: JobQueue<UploadJob>,
+    status: QueueStatus,
+    breaker: Arc<CircuitBreaker>,
+    connectivity: Arc<Connectivity>,
     app_handle: AppHandle,
-    queue_path: PathBuf,
 }
 
 impl UploadQueue {
-    pub fn new(app_handle: AppHandle, online_status: Arc<AtomicBool>) -> Self {
-        let queue_path = app_handle
-            .path()
-            .app_data_dir()
-            .map(|dir| dir.join("data").join("sync_queue.json"))
-            .unwrap_or_else(|_| PathBuf::from("sync_queue.json"));
-        Self {
-            queue: Arc::new(Mutex::new(VecDeque::new())),
-            active_job: Arc::new(Mutex::new(None)),
-            notify: Arc::new(Notify::new()),
-            online_notify: Arc::new(Notify::new()),
+    pub fn new(
+        app_handle: AppHandle,
+        online_status: Arc<AtomicBool>,
+        paused: PauseSwitch,
+        breaker: Arc<CircuitBreaker>,
+        connectivity: Arc<Connectivity>,
+        status: QueueStatus,
+    ) -> Self {
+        let jobs = JobQueue::new(
+            &app_handle,
+            "sync_queue.json",
+            "upload queue",
+            (status.uploads.clone(), status.active_upload.clone()),
             online_status,
+            paused,
+        );
+        Self {
+            jobs,
+            status,
+            breaker,
+            connectivity,
             app_handle,
-            queue_path,
         }
     }
 
     pub async fn add_job(&self, job: UploadJob) {
         let should_notify = {
-            let mut q = self.queue.lock().await;
+            let mut q = self.jobs.queue.lock().await;
             // Avoid duplicates
             if !q.iter().any(|j| j.version_id == job.version_id) {
                 q.push_back(job.clone());
@@ -288,94 +1150,44 @@ impl UploadQueue {
         }; // Lock dropped here
         
         if should_notify {
-            self.notify.notify_one(); // Notify AFTER lock is dropped
+            self.jobs.notify.notify_one(); // Notify AFTER lock is dropped
             self.emit_status().await;
-            self.save_to_disk().await;
+            self.jobs.save_to_disk().await;
         }
     }
 
     pub async fn load_from_disk(&self) {
-        if !self.queue_path.exists() {
-            return;
-        }
-
-        if let Ok(content) = fs::read_to_string(&self.queue_path) {
-            if let Ok(jobs) = serde_json::from_str::<Vec<UploadJob>>(&content) {
-                let mut q = self.queue.lock().await;
-                q.clear();
-                let mut added = false;
-                for mut job in jobs {
-                    if job.status == UploadStatus::Uploading {
-                        job.status = UploadStatus::Pending;
-                    }
-                    q.push_back(job);
-                    added = true;
-                }
-                if added {
-                    self.notify.notify_one();
-                }
-            }
-        }
-    }
-
-    pub async fn save_to_disk(&self) {
-        if let Some(parent) = self.queue_path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        let q = self.queue.lock().await;
-        // Only save pending and uploading jobs, not completed or failed
-        let jobs_to_save: Vec<UploadJob> = q
-            .iter()
-            .filter(|job| {
-                matches!(
-                    job.status,
-                    UploadStatus::Pending | UploadStatus::Uploading
-                )
-            })
-            .cloned()
-            .collect();
-
-        if let Err(e) = std::fs::write(
-            &self.queue_path,
-            serde_json::to_string_pretty(&jobs_to_save).unwrap_or_default(),
-        ) {
-            warn!("[QUEUE] Failed to save queue to disk: {}", e);
-        }
+        self.jobs.load_from_disk().await;
     }
 
     pub async fn get_status(&self) -> SyncStatus {
-        let q_len = self.queue.lock().await.len();
-        let active = self.active_job.lock().await.clone();
-
-        let is_syncing = q_len > 0 || active.is_some();
+        self.status.snapshot().await
+    }
 
-        SyncStatus {
-            queue_length: q_len,
-            active_job: active,
-            last_sync: Some(Utc::now()), // TODO: Store actual last sync time
-            is_syncing,
-        }
+    /// Archive bytes still to upload, counting the job in progress
+    pub async fn pending_bytes(&self) -> u64 {
+        let queued: u64 = self.jobs.queue.lock().await.iter().map(|job| job.total_size).sum();
+        let active = self
+            .jobs
+            .active_job
+            .lock()
+            .await
+            .as_ref()
+            .map_or(0, |job| job.total_size);
+        queued + active
     }
 
     pub async fn clear(&self) {
-        let mut q = self.queue.lock().await;
-        q.clear();
+        self.jobs.clear().await;
         self.emit_status().await;
     }
 
     pub fn signal_online(&self) {
-        self.online_notify.notify_waiters();
-    }
-
-    async fn wait_for_online(&self) {
-        while !self.online_status.load(Ordering::SeqCst) {
-            let _ = self.online_notify.notified().await;
-        }
+        self.jobs.signal_online();
     }
 
     async fn emit_status(&self) {
-        let status = self.get_status().await;
-        let _ = self.app_handle.emit("sync://status", status);
+        self.status.emit(&self.app_handle).await;
     }
 
     pub async fn process_queue(
@@ -386,34 +1198,56 @@ impl UploadQueue {
         info!("[QUEUE] Queue processor started");
         loop {
             debug!("[QUEUE] Loop iteration start");
-            if !self.online_status.load(Ordering::SeqCst) {
+            if !self.jobs.online_status.load(Ordering::SeqCst) {
                 debug!("[QUEUE] Waiting for online status...");
-                self.wait_for_online().await;
+                self.jobs.wait_for_online().await;
+                continue;
+            }
+            if self.jobs.paused.is_paused() {
+                debug!("[QUEUE] Sync paused, holding queue...");
+                self.jobs.wait_for_resume().await;
+                continue;
+            }
+            if let Some(reason) = transfer_hold(&self.connectivity, &settings, Transfer::Upload) {
+                debug!("[QUEUE] Holding uploads while {reason}");
+                self.jobs.wait_for_conditions().await;
+                continue;
+            }
+            if !history_owned_by_account(&settings) {
+                debug!("[QUEUE] Local history belongs to another account, holding queue...");
+                let _ = self.jobs.online_notify.notified().await;
+                continue;
+            }
+            if let Some(wait) = self.breaker.retry_in() {
+                debug!(
+                    "[QUEUE] Backend unavailable, holding queue for {}s",
+                    wait.as_secs()
+                );
+                tokio::select! {
+                    _ = sleep(wait) => {},
+                    _ = self.jobs.online_notify.notified() => {},
+                }
                 continue;
             }
             debug!("[QUEUE] Online status OK, proceeding to check queue");
 
             // Wait for a job
-            let job = {
-                let mut q = self.queue.lock().await;
+            let (job, retry_wait) = {
+                let mut q = self.jobs.queue.lock().await;
                 let queue_len = q.len();
                 debug!("[QUEUE] Acquired queue lock, queue length={}", queue_len);
-                if q.is_empty() {
-                    None
-                } else {
-                    let job = q.pop_front();
-                    if job.is_some() {
-                        info!("[QUEUE] Popped job from queue, {} jobs remaining", q.len());
-                    }
-                    job
+                let next = take_due_job(&mut q, |job| &job.game_id, |job| job.next_attempt_at);
+                if next.0.is_some() {
+                    info!("[QUEUE] Popped job from queue, {} jobs remaining", q.len());
                 }
+                next
             };
 
             if let Some(mut job) = job {
                 info!("[QUEUE] Processing upload job for game_id={}", job.game_id);
                 // Set active
                 {
-                    let mut active = self.active_job.lock().await;
+                    let mut active = self.jobs.active_job.lock().await;
                     *active = Some(job.clone());
                 }
                 self.emit_status().await;
@@ -427,7 +1261,7 @@ impl UploadQueue {
                 info!("{} [SYNC] Starting upload for {}", tag, job.game_id);
 
                 job.status = UploadStatus::Uploading;
-                self.save_to_disk().await;
+                self.jobs.save_to_disk().await;
 
                 // Perform Upload
                 let result = self.perform_upload(&job, &cloud, &settings).await;
@@ -436,17 +1270,30 @@ impl UploadQueue {
                     Ok(_) => {
                         info!("{} [SYNC] Upload complete for {}", tag, job.game_id);
                         job.status = UploadStatus::Completed;
+                        record_sync_event(&self.app_handle, |state| {
+                            state.record_upload(&job.game_id, &job.version_id)
+                        });
                     }
                     Err(e) => {
                         error!("{} [SYNC] Upload failed for {}: {}", tag, job.game_id, e);
-                        if job.retries < 3 {
+                        if self.jobs.paused.is_paused() {
+                            // Quota errors pause sync; keep the job and its retries
+                            info!("{} [SYNC] Holding {} until sync resumes", tag, job.game_id);
+                            job.status = UploadStatus::Pending;
+                            let mut q = self.jobs.queue.lock().await;
+                            q.push_front(job);
+                        } else if job.retries < 3 {
                             job.retries += 1;
-                            let retries = job.retries;
-                            warn!("{} [SYNC] Retrying job (attempt {})", tag, retries);
-                            let mut q = self.queue.lock().await;
-                            q.push_front(job); // Put back at front
-                            sleep(Duration::from_secs(2u64.pow(retries))).await;
-                        // Exponential backoff
+                            let delay = JOB_RETRY_BACKOFF.delay(job.retries);
+                            warn!(
+                                "{} [SYNC] Retrying job (attempt {}) in {}s",
+                                tag,
+                                job.retries,
+                                delay.as_secs()
+                            );
+                            job.next_attempt_at = retry_time(delay);
+                            let mut q = self.jobs.queue.lock().await;
+                            q.push_front(job); // Other games' jobs run while it waits
                         } else {
                             error!("{} [SYNC] Job failed after 3 retries, dropping.", tag);
                             job.status = UploadStatus::Failed;
@@ -454,24 +1301,26 @@ impl UploadQueue {
                     }
                 }
 
-                self.save_to_disk().await;
-
                 // Clear active
                 {
-                    let mut active = self.active_job.lock().await;
+                    let mut active = self.jobs.active_job.lock().await;
                     *active = None;
                 }
+                self.jobs.save_to_disk().await;
                 self.emit_status().await;
             } else {
-                // Queue is empty, wait for notification
-                debug!("[QUEUE] Queue empty, waiting for notification...");
+                // Nothing due, wait for notification or the next retry
+                debug!("[QUEUE] No job due, waiting for notification...");
                 tokio::select! {
-                    _ = self.notify.notified() => {
+                    _ = self.jobs.notify.notified() => {
                         debug!("[QUEUE] Woke up from notify signal");
                     },
-                    _ = self.online_notify.notified() => {
+                    _ = self.jobs.online_notify.notified() => {
                         debug!("[QUEUE] Woke up from online signal");
                     },
+                    _ = sleep_for(retry_wait) => {
+                        debug!("[QUEUE] Retry backoff elapsed");
+                    },
                 }
                 debug!("[QUEUE] Notification received, looping back to check queue");
             }
@@ -502,6 +1351,11 @@ impl UploadQueue {
             )
         })?;
 
+        let holds_archives = cloud.lock().await.holds_archives();
+        if holds_archives {
+            return self.upload_held_archive(job, cloud).await;
+        }
+
         let base_url = match settings_snapshot.cloud_mode {
             crate::core::settings::CloudMode::SelfHost => {
                 settings_snapshot.self_host.api_server.clone()
@@ -595,7 +1449,38 @@ impl UploadQueue {
             emulator_id: Some(job.metadata.emulator_id.clone()),
             device_id: Some(device_id),
             worker_token: None,
+            thumbnail_sha256: job.metadata.thumbnail_sha256.clone(),
+            note: job.metadata.note.clone(),
+            tags: job.metadata.tags.clone(),
+            pinned: job.metadata.pinned,
+            parent_version_id: job.metadata.parent_version_id.clone(),
+            encrypted: job.metadata.encrypted,
+            encoding: None,
+        };
+
+        // An archive the game already has in the cloud is copied there
+        // instead of being sent again
+        let linked = {
+            let backend = cloud.lock().await;
+            link_duplicate(backend.as_ref(), &payload).await
         };
+        if linked {
+            let _ = self.app_handle.emit(
+                "sync://upload-progress",
+                UploadProgressPayload {
+                    version_id: job.version_id.clone(),
+                    progress: 100,
+                },
+            );
+            let _ = self.app_handle.emit(
+                "sync://upload-complete",
+                UploadCompletePayload {
+                    game_id: payload.game_id,
+                    version_id: payload.version_id,
+                },
+            );
+            return Ok(());
+        }
 
         let start_progress = UploadProgressPayload {
             version_id: job.version_id.clone(),
@@ -605,6 +1490,37 @@ impl UploadQueue {
             .app_handle
             .emit("sync://upload-progress", start_progress.clone());
 
+        // Compressed for servers that take zstd; hash and size still
+        // describe the zip
+        let wanted = if job.metadata.encrypted {
+            None
+        } else {
+            let backend = cloud.lock().await;
+            backend
+                .supports_zstd()
+                .await
+                .then_some(ArchiveEncoding::Zstd)
+        };
+        let archive_path = job.archive_path.clone();
+        let (archive_bytes, encoding) =
+            tauri::async_runtime::spawn_blocking(move || encode_for_upload(&archive_path, wanted))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|encoded| encoded.map_err(|e| e.to_string()))
+                .map_err(|message| {
+                    emit_error(
+                        UploadErrorPayload {
+                            version_id: job.version_id.clone(),
+                            stage: "upload".to_string(),
+                            reason: "io".to_string(),
+                            message,
+                            status: None,
+                        },
+                        &self.app_handle,
+                    )
+                })?;
+        payload.encoding = encoding;
+
         let signed: UploadUrlResponse = {
             let backend = cloud.lock().await;
             match backend.request_upload_url(payload.clone()).await {
@@ -614,7 +1530,7 @@ impl UploadQueue {
                         UploadErrorPayload {
                             version_id: payload.version_id.clone(),
                             stage: "request_url".to_string(),
-                            reason: "backend_error".to_string(),
+                            reason: self.backend_error_reason(&err).to_string(),
                             message: err.to_string(),
                             status: None,
                         },
@@ -626,30 +1542,63 @@ impl UploadQueue {
 
         payload.worker_token = signed.worker_token.clone();
 
-        let archive_bytes = fs::read(&job.archive_path).map_err(|e| {
-            emit_error(
-                UploadErrorPayload {
-                    version_id: job.version_id.clone(),
-                    stage: "upload".to_string(),
-                    reason: "io".to_string(),
-                    message: e.to_string(),
-                    status: None,
-                },
-                &self.app_handle,
-            )
-        })?;
         let client = Client::new();
-        let put_resp = client
+        let content_length = archive_bytes.len() as u64;
+
+        // Stream the body in chunks so the watchdog can see bytes moving
+        let activity = Arc::new(TransferActivity::new());
+        let body_activity = activity.clone();
+        let archive_bytes = Bytes::from(archive_bytes);
+        let chunk_count = archive_bytes.len().div_ceil(UPLOAD_CHUNK_BYTES);
+        let body_stream = futures::stream::iter((0..chunk_count).map(move |index| {
+            let start = index * UPLOAD_CHUNK_BYTES;
+            let end = (start + UPLOAD_CHUNK_BYTES).min(archive_bytes.len());
+            if index + 1 == chunk_count {
+                body_activity.finish_body();
+            } else {
+                body_activity.record();
+            }
+            Ok::<_, std::io::Error>(archive_bytes.slice(start..end))
+        }));
+
+        let put_request = client
             .put(&signed.upload_url)
-            .header(CONTENT_TYPE, "application/zip")
-            .header(CONTENT_LENGTH, archive_bytes.len() as u64)
-            .body(archive_bytes)
-            .send()
-            .await;
+            .header(
+                CONTENT_TYPE,
+                encoding.map_or(ARCHIVE_CONTENT_TYPE, ArchiveEncoding::content_type),
+            )
+            .header(CONTENT_LENGTH, content_length)
+            .body(reqwest::Body::wrap_stream(body_stream))
+            .send();
 
-        let put_resp = match put_resp {
+        let put_resp = match watch_transfer(put_request, &activity).await {
             Ok(resp) => resp,
             Err(err) => {
+                warn!("[SYNC] Upload of {} aborted: {err}", job.version_id);
+                return Err(emit_error(
+                    UploadErrorPayload {
+                        version_id: payload.version_id.clone(),
+                        stage: "upload".to_string(),
+                        reason: err.stage().to_string(),
+                        message: err.to_string(),
+                        status: None,
+                    },
+                    &self.app_handle,
+                ));
+            }
+        };
+
+        let put_resp = match put_resp {
+            Ok(resp) => {
+                if resp.status().is_server_error() {
+                    self.breaker.record_failure();
+                } else {
+                    self.breaker.record_success();
+                }
+                resp
+            }
+            Err(err) => {
+                self.breaker.record_failure();
                 return Err(emit_error(
                     UploadErrorPayload {
                         version_id: payload.version_id.clone(),
@@ -664,20 +1613,47 @@ impl UploadQueue {
         };
 
         if !put_resp.status().is_success() {
-            let status = put_resp.status().as_u16();
-            let message = format!("upload failed: {}", put_resp.status());
+            let status = put_resp.status();
+            let message = format!("upload failed: {}", status);
+            let body = put_resp.text().await.unwrap_or_default();
+            let reason = if is_cloud_quota_response(status, &body) {
+                report_storage_full(&self.app_handle, StorageScope::Cloud, message.clone());
+                "cloud_quota"
+            } else {
+                "http_status"
+            };
             return Err(emit_error(
                 UploadErrorPayload {
                     version_id: payload.version_id.clone(),
                     stage: "upload".to_string(),
-                    reason: "http_status".to_string(),
+                    reason: reason.to_string(),
                     message,
-                    status: Some(status),
+                    status: Some(status.as_u16()),
                 },
                 &self.app_handle,
             ));
         }
 
+        // The archive is in; a thumbnail that fails to upload is just dropped
+        payload.thumbnail_sha256 = match (
+            signed.thumbnail_upload_url.as_deref(),
+            job.metadata.thumbnail.as_deref(),
+        ) {
+            (Some(url), Some(thumbnail)) => {
+                match upload_thumbnail(&client, url, Path::new(thumbnail)).await {
+                    Ok(()) => payload.thumbnail_sha256.take(),
+                    Err(err) => {
+                        warn!(
+                            "[SYNC] Thumbnail upload for {} failed: {err}",
+                            job.version_id
+                        );
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
         let mid_progress = UploadProgressPayload {
             version_id: job.version_id.clone(),
             progress: 80,
@@ -693,7 +1669,7 @@ impl UploadQueue {
                     UploadErrorPayload {
                         version_id: payload.version_id.clone(),
                         stage: "notify".to_string(),
-                        reason: "backend_error".to_string(),
+                        reason: self.backend_error_reason(&err).to_string(),
                         message: err.to_string(),
                         status: None,
                     },
@@ -710,14 +1686,312 @@ impl UploadQueue {
             },
         );
 
-        let _ = self.app_handle.emit(
-            "sync://upload-complete",
-            UploadCompletePayload {
-                version_id: payload.version_id,
-            },
-        );
+        let _ = self.app_handle.emit(
+            "sync://upload-complete",
+            UploadCompletePayload {
+                game_id: payload.game_id,
+                version_id: payload.version_id,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Upload through a backend that stores archives itself, so there are
+    /// no URLs or credentials to check
+    async fn upload_held_archive(
+        &self,
+        job: &UploadJob,
+        cloud: &Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+    ) -> Result<(), String> {
+        let result = cloud
+            .lock()
+            .await
+            .upload_archive(job.metadata.clone(), job.archive_path.clone())
+            .await;
+        if let Err(err) = result {
+            let payload = UploadErrorPayload {
+                version_id: job.version_id.clone(),
+                stage: "upload".to_string(),
+                reason: self.backend_error_reason(&err).to_string(),
+                message: err.to_string(),
+                status: None,
+            };
+            let _ = self.app_handle.emit("sync://upload-error", payload.clone());
+            return Err(payload.message);
+        }
+
+        let _ = self.app_handle.emit(
+            "sync://upload-complete",
+            UploadCompletePayload {
+                game_id: job.game_id.clone(),
+                version_id: job.version_id.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Classify a backend failure for `sync://upload-error`, pausing sync
+    /// when the cloud quota is exhausted
+    fn backend_error_reason(&self, err: &CloudError) -> &'static str {
+        match err {
+            CloudError::QuotaExceeded(message) => {
+                report_storage_full(&self.app_handle, StorageScope::Cloud, message.clone());
+                "cloud_quota"
+            }
+            CloudError::Unauthorized(_) => "unauthorized",
+            _ => "backend_error",
+        }
+    }
+}
+
+// ============================================================================
+// Download Queue
+// ============================================================================
+
+/// Attempts per queued download before it is dropped
+const DOWNLOAD_JOB_RETRIES: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadJob {
+    pub game_id: String,
+    pub version_id: String,
+    pub created_at: DateTime<Utc>,
+    pub retries: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+/// Cloud versions the sync loop decided to restore, kept on disk so a
+/// download that fails or is interrupted is retried rather than forgotten
+pub struct DownloadQueue {
+    jobs: JobQueue<DownloadJob>,
+    status: QueueStatus,
+    breaker: Arc<CircuitBreaker>,
+    connectivity: Arc<Connectivity>,
+    app_handle: AppHandle,
+}
+
+impl DownloadQueue {
+    pub fn new(
+        app_handle: AppHandle,
+        online_status: Arc<AtomicBool>,
+        paused: PauseSwitch,
+        breaker: Arc<CircuitBreaker>,
+        connectivity: Arc<Connectivity>,
+        status: QueueStatus,
+    ) -> Self {
+        let jobs = JobQueue::new(
+            &app_handle,
+            "download_queue.json",
+            "download queue",
+            (status.downloads.clone(), status.active_download.clone()),
+            online_status,
+            paused,
+        );
+        Self {
+            jobs,
+            status,
+            breaker,
+            connectivity,
+            app_handle,
+        }
+    }
+
+    /// Queue `version_id` for `game_id`, replacing any older pending
+    /// download of the same game since only the newest one matters
+    pub async fn add_job(&self, game_id: String, version_id: String) {
+        let already_active = self
+            .jobs
+            .active_job
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|job| job.game_id == game_id && job.version_id == version_id);
+        if already_active {
+            return;
+        }
+
+        {
+            let mut q = self.jobs.queue.lock().await;
+            if q.iter()
+                .any(|job| job.game_id == game_id && job.version_id == version_id)
+            {
+                debug!("[QUEUE] Download of {} already queued", version_id);
+                return;
+            }
+            q.retain(|job| job.game_id != game_id);
+            q.push_back(DownloadJob {
+                game_id: game_id.clone(),
+                version_id,
+                created_at: Utc::now(),
+                retries: 0,
+                last_error: None,
+                next_attempt_at: None,
+            });
+            info!(
+                "[QUEUE] Added download for game_id={}, queue length={}",
+                game_id,
+                q.len()
+            );
+        }
+
+        self.jobs.notify.notify_one();
+        self.emit_status().await;
+        self.jobs.save_to_disk().await;
+    }
+
+    pub async fn load_from_disk(&self) {
+        self.jobs.load_from_disk().await;
+    }
+
+    pub async fn clear(&self) {
+        self.jobs.clear().await;
+        self.emit_status().await;
+    }
+
+    pub fn signal_online(&self) {
+        self.jobs.signal_online();
+    }
+
+    async fn emit_status(&self) {
+        self.status.emit(&self.app_handle).await;
+    }
+
+    pub async fn process_queue(
+        &self,
+        cloud: Arc<Mutex<Box<dyn CloudBackend + Send>>>,
+        history: Arc<HistoryManager>,
+        profiles: Arc<RwLock<ProfileManager>>,
+        settings: Arc<SettingsManager>,
+    ) {
+        info!("[QUEUE] Download queue processor started");
+        loop {
+            if !self.jobs.online_status.load(Ordering::SeqCst) {
+                debug!("[QUEUE] Downloads waiting for online status...");
+                self.jobs.wait_for_online().await;
+                continue;
+            }
+            if self.jobs.paused.is_paused() {
+                debug!("[QUEUE] Sync paused, holding downloads...");
+                self.jobs.wait_for_resume().await;
+                continue;
+            }
+            if let Some(reason) = transfer_hold(&self.connectivity, &settings, Transfer::Download) {
+                debug!("[QUEUE] Holding downloads while {reason}");
+                self.jobs.wait_for_conditions().await;
+                continue;
+            }
+            if !history_owned_by_account(&settings) {
+                debug!("[QUEUE] Local history belongs to another account, holding downloads...");
+                let _ = self.jobs.online_notify.notified().await;
+                continue;
+            }
+            if let Some(wait) = self.breaker.retry_in() {
+                debug!(
+                    "[QUEUE] Backend unavailable, holding downloads for {}s",
+                    wait.as_secs()
+                );
+                tokio::select! {
+                    _ = sleep(wait) => {},
+                    _ = self.jobs.online_notify.notified() => {},
+                }
+                continue;
+            }
+
+            let (job, retry_wait) = take_due_job(
+                &mut *self.jobs.queue.lock().await,
+                |job| &job.game_id,
+                |job| job.next_attempt_at,
+            );
+            let Some(mut job) = job else {
+                tokio::select! {
+                    _ = self.jobs.notify.notified() => {},
+                    _ = self.jobs.online_notify.notified() => {},
+                    _ = sleep_for(retry_wait) => {},
+                }
+                continue;
+            };
+
+            *self.jobs.active_job.lock().await = Some(job.clone());
+            self.emit_status().await;
+            self.jobs.save_to_disk().await;
 
-        Ok(())
+            let tag = log_tag(
+                &settings
+                    .get_settings()
+                    .map(|s| s.cloud_mode)
+                    .unwrap_or(CloudMode::Off),
+            );
+            info!(
+                "{} [SYNC] Downloading {} version {}",
+                tag, job.game_id, job.version_id
+            );
+
+            let result = perform_download(
+                cloud.clone(),
+                history.clone(),
+                profiles.clone(),
+                self.app_handle.clone(),
+                settings.clone(),
+                job.game_id.clone(),
+                job.version_id.clone(),
+            )
+            .await;
+
+            *self.jobs.active_job.lock().await = None;
+
+            if let Err(err) = result {
+                warn!(
+                    "{} [SYNC] Download failed for {}: {}",
+                    tag, job.game_id, err
+                );
+                job.last_error = Some(err);
+
+                // A newer decision for this game may have been queued meanwhile
+                let superseded = self
+                    .jobs
+                    .queue
+                    .lock()
+                    .await
+                    .iter()
+                    .any(|queued| queued.game_id == job.game_id);
+
+                if superseded {
+                    debug!("[QUEUE] Dropping superseded download of {}", job.version_id);
+                } else if self.jobs.paused.is_paused() {
+                    // Disk-full errors pause sync; keep the job and its retries
+                    info!(
+                        "{} [SYNC] Holding download of {} until sync resumes",
+                        tag, job.game_id
+                    );
+                    self.jobs.queue.lock().await.push_front(job);
+                } else if job.retries < DOWNLOAD_JOB_RETRIES {
+                    job.retries += 1;
+                    let delay = JOB_RETRY_BACKOFF.delay(job.retries);
+                    warn!(
+                        "{} [SYNC] Retrying download (attempt {}) in {}s",
+                        tag,
+                        job.retries,
+                        delay.as_secs()
+                    );
+                    job.next_attempt_at = retry_time(delay);
+                    self.jobs.queue.lock().await.push_front(job);
+                } else {
+                    error!(
+                        "{} [SYNC] Download failed after {} retries, dropping.",
+                        tag, DOWNLOAD_JOB_RETRIES
+                    );
+                }
+            } else {
+                info!("{} [SYNC] Download complete for {}", tag, job.game_id);
+            }
+
+            self.jobs.save_to_disk().await;
+            self.emit_status().await;
+        }
     }
 }
 
@@ -725,18 +1999,49 @@ impl UploadQueue {
 // Sync Engine
 // ============================================================================
 
+/// Spaces out full scans: the configured interval while saves are
+/// changing, doubling with every quiet cycle up to the idle interval
+#[derive(Default)]
+struct SyncScheduler {
+    quiet_cycles: u32,
+}
+
+impl SyncScheduler {
+    fn next_wait(&self, schedule: &SyncSchedule) -> Duration {
+        let base = Duration::from_secs(schedule.sync_interval_secs);
+        let idle = Duration::from_secs(schedule.idle_sync_interval_secs).max(base);
+        base.saturating_mul(1 << self.quiet_cycles.min(16))
+            .min(idle)
+    }
+
+    /// A cycle is quiet when it queued nothing, was not started by a new
+    /// version and the watcher reported no activity
+    fn record_cycle(&mut self, changed: bool) {
+        self.quiet_cycles = if changed {
+            0
+        } else {
+            self.quiet_cycles.saturating_add(1)
+        };
+    }
+}
+
 pub struct SyncManager {
     pub queue: Arc<UploadQueue>,
+    pub downloads: Arc<DownloadQueue>,
     pub cloud: Arc<Mutex<Box<dyn CloudBackend + Send>>>,
     pub history: Arc<HistoryManager>,
     pub profiles: Arc<RwLock<ProfileManager>>,
     pub settings: Arc<SettingsManager>,
     online: Arc<AtomicBool>,
-    paused: Arc<AtomicBool>,
+    paused: PauseSwitch,
+    breaker: Arc<CircuitBreaker>,
+    connectivity: Arc<Connectivity>,
     connection_status: Arc<RwLock<ConnectionStatus>>,
     app_handle: AppHandle,
     sync_trigger: Arc<Notify>,
     running: Arc<AtomicBool>,
+    /// Set by the watcher between cycles; keeps the scheduler from idling
+    activity: Arc<AtomicBool>,
 }
 
 impl SyncManager {
@@ -746,28 +2051,54 @@ impl SyncManager {
         history: Arc<HistoryManager>,
         profiles: Arc<RwLock<ProfileManager>>,
         settings: Arc<SettingsManager>,
+        breaker: Arc<CircuitBreaker>,
+        sync_state: Option<Arc<SyncStateStore>>,
     ) -> Self {
         let online = Arc::new(AtomicBool::new(true));
-        let paused = Arc::new(AtomicBool::new(false));
-        let queue = Arc::new(UploadQueue::new(app_handle.clone(), online.clone()));
+        let paused = PauseSwitch::new();
+        let connectivity = Arc::new(Connectivity::new(Box::new(PlatformConnectivity::new(
+            app_handle.clone(),
+        ))));
+        let status = QueueStatus::new(sync_state);
+        let queue = Arc::new(UploadQueue::new(
+            app_handle.clone(),
+            online.clone(),
+            paused.clone(),
+            breaker.clone(),
+            connectivity.clone(),
+            status.clone(),
+        ));
+        let downloads = Arc::new(DownloadQueue::new(
+            app_handle.clone(),
+            online.clone(),
+            paused.clone(),
+            breaker.clone(),
+            connectivity.clone(),
+            status,
+        ));
         let connection_status = Arc::new(RwLock::new(ConnectionStatus {
             connected: false,
             last_success: None,
             last_error: None,
+            circuit: breaker.snapshot(),
         }));
 
         Self {
             queue,
+            downloads,
             cloud,
             history,
             profiles,
             settings,
             online,
             paused,
+            breaker,
+            connectivity,
             connection_status,
             app_handle,
             sync_trigger: Arc::new(Notify::new()),
             running: Arc::new(AtomicBool::new(false)),
+            activity: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -775,6 +2106,56 @@ impl SyncManager {
         self.sync_trigger.notify_one();
     }
 
+    /// Save files changed on disk; the sync loop stays at its short interval
+    pub fn note_activity(&self) {
+        self.activity.store(true, Ordering::SeqCst);
+    }
+
+    /// Fill the history cache off the async runtime, emitting
+    /// `sync://progress` events for the local indexing stage.
+    pub async fn index_local_history(&self) -> Result<usize, String> {
+        let history = self.history.clone();
+        let app_handle = self.app_handle.clone();
+        emit_sync_progress(&app_handle, SyncStage::IndexingLocal, 0, 0, None);
+
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            history.index_with_progress(|current, total, game_id| {
+                emit_sync_progress(
+                    &app_handle,
+                    SyncStage::IndexingLocal,
+                    current,
+                    total,
+                    Some(game_id),
+                );
+            })
+        })
+        .await;
+
+        match result {
+            Ok(Ok(total)) => {
+                info!("[SYNC] Local history indexed ({} games)", total);
+                enforce_history_budget(&self.app_handle, &self.history);
+
+                // Deduplicate in the background; nothing waits on it
+                let history = self.history.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    if let Err(err) = history.compact() {
+                        warn!("[HISTORY] Background compaction failed: {err}");
+                    }
+                });
+                Ok(total)
+            }
+            Ok(Err(err)) => {
+                error!("[SYNC] Failed to index local history: {}", err);
+                Err(err.to_string())
+            }
+            Err(err) => {
+                error!("[SYNC] History indexing task failed: {}", err);
+                Err(err.to_string())
+            }
+        }
+    }
+
     pub fn start_background_task(&self) {
         info!("[SYNC] start_background_task() called - initializing background tasks");
         let queue = self.queue.clone();
@@ -784,6 +2165,7 @@ impl SyncManager {
         let app_for_ping = self.app_handle.clone();
         let online_flag = self.online.clone();
         let queue_for_online = self.queue.clone();
+        let downloads_for_online = self.downloads.clone();
         let trigger_for_online = self.sync_trigger.clone();
 
         let settings_for_queue = self.settings.clone();
@@ -793,8 +2175,73 @@ impl SyncManager {
             queue.process_queue(cloud, settings_for_queue).await;
         });
 
+        // Spawn Download Queue Processor
+        let downloads = self.downloads.clone();
+        let cloud_for_downloads = self.cloud.clone();
+        let history_for_downloads = self.history.clone();
+        let profiles_for_downloads = self.profiles.clone();
+        let settings_for_downloads = self.settings.clone();
+        tokio::spawn(async move {
+            downloads.load_from_disk().await;
+            downloads
+                .process_queue(
+                    cloud_for_downloads,
+                    history_for_downloads,
+                    profiles_for_downloads,
+                    settings_for_downloads,
+                )
+                .await;
+        });
+
+        // Settings changes reach history retention and the cloud backend
+        let mut settings_changes = self.settings.subscribe();
+        let settings_for_changes = self.settings.clone();
+        let history_for_changes = self.history.clone();
+        let cloud_for_changes = self.cloud.clone();
+        let app_for_changes = self.app_handle.clone();
+        tokio::spawn(async move {
+            let mut applied = settings_changes.borrow().settings.clone();
+            while settings_changes.changed().await.is_ok() {
+                let change = settings_changes.borrow_and_update().clone();
+                let settings = change.settings;
+
+                let app = app_for_changes.clone();
+                let history = history_for_changes.clone();
+                let retention = settings.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || {
+                    apply_history_settings(&app, &history, &retention)
+                })
+                .await;
+
+                // Mode switches made in the app switch the backend themselves;
+                // the client's timeout is fixed when it is built
+                let mode_edited = change.external && settings.cloud_mode != applied.cloud_mode;
+                if mode_edited || settings.cloud.timeout_seconds != applied.cloud.timeout_seconds {
+                    if let Err(err) = crate::switch_cloud_backend(
+                        &app_for_changes,
+                        &cloud_for_changes,
+                        settings_for_changes.clone(),
+                        settings.cloud_mode.clone(),
+                        settings.clone(),
+                    )
+                    .await
+                    {
+                        warn!("[SYNC] Failed to rebuild the cloud backend: {err}");
+                    }
+                }
+                if change.external {
+                    let _ = app_for_changes.emit("settings://changed", &settings);
+                }
+                applied = settings;
+            }
+        });
+
         // Enhanced connectivity monitor with connection status tracking
         let connection_status_clone = self.connection_status.clone();
+        let breaker_for_monitor = self.breaker.clone();
+        let settings_for_monitor = self.settings.clone();
+        let mut monitor_changes = self.settings.subscribe();
+        let connectivity_for_monitor = self.connectivity.clone();
         tokio::spawn(async move {
             info!("[SYNC] Connection monitoring loop started");
             loop {
@@ -820,6 +2267,7 @@ impl SyncManager {
                         );
                         status.last_error = None;
                     }
+                    status.circuit = breaker_for_monitor.snapshot();
                     
                     // Emit connection status event to frontend
                     let _ = app_for_ping.emit("connection-status", status.clone());
@@ -831,27 +2279,75 @@ impl SyncManager {
                     info!("[SYNC] Connection restored - triggering sync");
                     let _ = app_for_ping.emit("sync://online", "online");
                     queue_for_online.signal_online();
+                    downloads_for_online.signal_online();
                     trigger_for_online.notify_one();
                 } else if !connected && previous {
                     warn!("[SYNC] Connection lost");
                     let _ = app_for_ping.emit("sync://offline", "offline");
                 }
 
-                sleep(Duration::from_secs(30)).await; // Check every 30 seconds
+                let connectivity = connectivity_for_monitor.clone();
+                if let Ok((conditions, true)) =
+                    tauri::async_runtime::spawn_blocking(move || connectivity.refresh()).await
+                {
+                    let network = settings_for_monitor
+                        .get_settings()
+                        .map(|s| s.network)
+                        .unwrap_or_default();
+                    let payload = ConditionsPayload::new(&network, conditions);
+                    info!("[SYNC] Device conditions changed: {:?}", payload);
+                    let _ = app_for_ping.emit("sync://conditions", &payload);
+                    // Held transfers check again; lifting a hold starts a cycle
+                    queue_for_online.signal_online();
+                    downloads_for_online.signal_online();
+                    if payload.downloads_held.is_none() {
+                        trigger_for_online.notify_one();
+                    }
+                }
+
+                // Check on the configured interval, or once an open circuit allows a probe
+                let interval = settings_for_monitor
+                    .get_settings()
+                    .map(|s| s.sync_schedule.connection_check_secs)
+                    .unwrap_or_else(|_| SyncSchedule::default().connection_check_secs);
+                let wait = breaker_for_monitor
+                    .retry_in()
+                    .unwrap_or(Duration::from_secs(interval));
+                let waiting = sleep(wait);
+                tokio::pin!(waiting);
+                // A new interval applies right away rather than after this wait
+                loop {
+                    tokio::select! {
+                        _ = &mut waiting => break,
+                        Ok(()) = monitor_changes.changed() => {
+                            let check_secs = monitor_changes
+                                .borrow_and_update()
+                                .settings
+                                .sync_schedule
+                                .connection_check_secs;
+                            if check_secs != interval {
+                                break;
+                            }
+                        }
+                    }
+                }
             }
         });
 
         // Spawn Sync Loop
         let queue_clone = self.queue.clone();
+        let downloads_clone = self.downloads.clone();
         let cloud_clone = self.cloud.clone();
         let history_clone = self.history.clone();
-        let profiles_clone = self.profiles.clone();
         let settings_clone = self.settings.clone();
         let app_handle_clone = self.app_handle.clone();
         let sync_trigger = self.sync_trigger.clone();
         let running_flag = self.running.clone();
         let online_status = self.online.clone();
         let paused_flag = self.paused.clone();
+        let activity_flag = self.activity.clone();
+        let connectivity_for_loop = self.connectivity.clone();
+        let mut schedule_changes = self.settings.subscribe();
 
         tokio::spawn(async move {
             if running_flag.swap(true, Ordering::SeqCst) {
@@ -859,13 +2355,68 @@ impl SyncManager {
             }
 
             queue_clone.load_from_disk().await;
+            let Some(mut history_changes) = history_clone.take_change_receiver() else {
+                error!("[SYNC] History changes already consumed; sync loop not started");
+                return;
+            };
+            // Report cloud listing progress until the first full cycle completes
+            let mut first_cycle = true;
+            // Account switch already announced, so it is not re-emitted every cycle
+            let mut reported_switch: Option<String> = None;
+            // Same for the first-sync reconciliation prompt
+            let mut reported_reconcile = false;
+            // Mode whose backend answered the batch listing with 404, so the
+            // loop lists per game there instead of asking every cycle
+            let mut batch_unsupported: Option<CloudMode> = None;
+            // Same for backends that can't share games
+            let mut sharing_unsupported: Option<CloudMode> = None;
+            // Games other accounts share read-only with this one, as of the
+            // last full scan; local versions of them are never uploaded
+            let mut read_only_shares: Vec<String> = Vec::new();
+            let mut scheduler = SyncScheduler::default();
+            // Kept across targeted syncs so frequent saves cannot postpone it
+            let mut next_full_scan: Option<Instant> = None;
+            let mut schedule = SyncSchedule::default();
             loop {
-                // Wait for either timeout (10s) or manual trigger
-                tokio::select! {
-                    _ = sleep(Duration::from_secs(10)) => {},
-                    _ = sync_trigger.notified() => {
-                        // We can't easily get tag here without settings, but we'll get it below
+                let deadline = *next_full_scan.get_or_insert_with(|| {
+                    schedule = settings_clone
+                        .get_settings()
+                        .map(|s| s.sync_schedule)
+                        .unwrap_or_default();
+                    let wait = scheduler.next_wait(&schedule);
+                    debug!("[SYNC] Next full scan in {}s", wait.as_secs());
+                    Instant::now() + wait
+                });
+
+                // A new local version syncs just its game; the timer and manual
+                // triggers scan everything. Changes skipped below (offline,
+                // paused) are picked up by the next full scan.
+                let changed_games: Option<Vec<String>> = tokio::select! {
+                    _ = sleep_until(deadline.into()) => None,
+                    _ = sync_trigger.notified() => None,
+                    Ok(()) = schedule_changes.changed() => {
+                        // A new schedule moves the next scan without running one
+                        let changed =
+                            schedule_changes.borrow_and_update().settings.sync_schedule != schedule;
+                        if changed {
+                            next_full_scan = None;
+                        }
+                        continue;
                     }
+                    Some(game_id) = history_changes.recv() => {
+                        let mut games = vec![game_id];
+                        while let Ok(game_id) = history_changes.try_recv() {
+                            if !games.contains(&game_id) {
+                                games.push(game_id);
+                            }
+                        }
+                        debug!("[SYNC] New versions saved for {:?}, syncing now", games);
+                        Some(games)
+                    }
+                };
+                let full_scan = changed_games.is_none();
+                if full_scan {
+                    next_full_scan = None;
                 }
 
                 // Check if cloud mode is enabled (not Off)
@@ -881,107 +2432,253 @@ impl SyncManager {
                     continue;
                 }
 
-                if paused_flag.load(Ordering::SeqCst) {
+                if paused_flag.is_paused() {
                     info!("{} [SYNC] Sync paused; skipping cycle", tag);
                     continue;
                 }
 
+                if let Some(reason) =
+                    transfer_hold(&connectivity_for_loop, &settings_clone, Transfer::Download)
+                {
+                    info!("{} [SYNC] Skipping sync cycle while {}", tag, reason);
+                    continue;
+                }
+
                 if !online_status.load(Ordering::SeqCst) {
                     info!("{} [SYNC] Skipping sync cycle while offline", tag);
                     continue;
                 }
 
-                info!("{} [SYNC] Starting sync cycle...", tag);
+                match pending_account_switch(&settings_clone, &history_clone) {
+                    Ok(None) => reported_switch = None,
+                    Ok(Some(switch)) => {
+                        if reported_switch.as_deref() != Some(switch.current.as_str()) {
+                            warn!(
+                                "{} [SYNC] Signed in with a different account; sync held until the local history is assigned",
+                                tag
+                            );
+                            reported_switch = Some(switch.current.clone());
+                            let _ = app_handle_clone.emit("cloud://account-changed", switch);
+                        }
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!("{} [SYNC] Failed to check history owner: {}", tag, err);
+                        continue;
+                    }
+                }
+
+                let sync_state = app_handle_clone.try_state::<Arc<SyncStateStore>>();
+                match reconciliation_required(
+                    &cloud_clone,
+                    &settings_clone,
+                    sync_state.as_deref().map(Arc::as_ref),
+                )
+                .await
+                {
+                    Ok(false) => reported_reconcile = false,
+                    Ok(true) => {
+                        if !reported_reconcile {
+                            info!(
+                                "{} [SYNC] Cloud already has saves for this account; waiting for first-sync reconciliation",
+                                tag
+                            );
+                            reported_reconcile = true;
+                            let _ = app_handle_clone.emit("sync://reconcile-required", ());
+                        }
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!(
+                            "{} [SYNC] Failed to check first-sync reconciliation: {}",
+                            tag, err
+                        );
+                        continue;
+                    }
+                }
+
+                let mut queued_work = false;
 
-                // 1. List all games from History
-                let games = history_clone.get_games();
+                // 1. List the games to check
+                let games = match changed_games {
+                    Some(games) => {
+                        info!("{} [SYNC] Syncing changed games {:?}", tag, games);
+                        games
+                    }
+                    None => {
+                        info!("{} [SYNC] Starting full sync scan...", tag);
+                        let mut games = history_clone.get_games();
+                        // Games shared with this account sync into its history
+                        // like its own, even before it has a local version
+                        if sharing_unsupported.as_ref() != Some(&mode) {
+                            let shares = cloud_clone.lock().await.list_shared_games().await;
+                            match shares {
+                                Ok(shares) => {
+                                    read_only_shares = shares
+                                        .received
+                                        .iter()
+                                        .filter(|grant| grant.access == ShareAccess::Read)
+                                        .map(|grant| grant.game_id.clone())
+                                        .collect();
+                                    for grant in shares.received {
+                                        if !games.contains(&grant.game_id) {
+                                            games.push(grant.game_id);
+                                        }
+                                    }
+                                }
+                                Err(CloudError::NotFound(_)) => {
+                                    sharing_unsupported = Some(mode.clone());
+                                    read_only_shares.clear();
+                                }
+                                Err(err) => {
+                                    warn!("{} [SYNC] Failed to list shared games: {}", tag, err)
+                                }
+                            }
+                        }
+                        games
+                    }
+                };
                 let current_device = settings_clone
                     .get_settings()
                     .map(|s| s.cloud.device_id)
                     .unwrap_or_default();
 
-                for game_id in games {
-                    // Get Local State
-                    let local_latest = history_clone.get_latest_version(&game_id);
-
-                    // Get Cloud State
+                // One request for every game's latest cloud version; games
+                // that need more are listed on their own below
+                let latest_batch = if games.is_empty() || batch_unsupported.as_ref() == Some(&mode)
+                {
+                    None
+                } else {
                     let backend = cloud_clone.lock().await;
-                    let cloud_versions = match backend.list_versions(game_id.clone(), Some(1)).await
-                    {
-                        Ok(versions) => versions,
-                        Err(err) => {
-                            warn!(
-                                "{} [SYNC] Failed to list versions for {}: {}",
-                                tag, game_id, err
-                            );
-                            let _ = app_handle_clone.emit(
-                                "sync://cloud-list-error",
-                                json!({ "gameId": game_id, "message": err.to_string() }),
+                    match backend.list_latest_versions(games.clone()).await {
+                        Ok(latest) => Some(latest),
+                        Err(CloudError::NotFound(_)) => {
+                            info!(
+                                "{} [SYNC] Backend has no batch listing; listing games one by one",
+                                tag
                             );
-                            continue;
+                            batch_unsupported = Some(mode.clone());
+                            None
+                        }
+                        Err(err) => {
+                            warn!("{} [SYNC] Batch version listing failed: {}", tag, err);
+                            None
+                        }
+                    }
+                };
+
+                let total_games = games.len();
+                for (index, game_id) in games.into_iter().enumerate() {
+                    if first_cycle && full_scan {
+                        emit_sync_progress(
+                            &app_handle_clone,
+                            SyncStage::FetchingCloud,
+                            index + 1,
+                            total_games,
+                            Some(&game_id),
+                        );
+                    }
+
+                    // Get Local State
+                    let local_latest = history_clone.get_latest_version(&game_id);
+                    let local_lineage = local_latest
+                        .as_ref()
+                        .map(|local| history_clone.lineage(&game_id, &local.metadata.version_id))
+                        .unwrap_or_default();
+
+                    // Get Cloud State. The batch settles games with no cloud
+                    // version, or whose latest one this device already has;
+                    // the rest need enough versions to follow the lineage.
+                    let batched = latest_batch.as_ref().and_then(|latest| {
+                        batched_versions(latest, &game_id, local_latest.as_ref(), &local_lineage)
+                    });
+                    let cloud_versions = match batched {
+                        Some(versions) => versions,
+                        None => {
+                            let backend = cloud_clone.lock().await;
+                            let listed =
+                                backend.list_versions(game_id.clone(), Some(LINEAGE_WINDOW));
+                            match listed.await {
+                                Ok(versions) => versions,
+                                Err(err) => {
+                                    warn!(
+                                        "{} [SYNC] Failed to list versions for {}: {}",
+                                        tag, game_id, err
+                                    );
+                                    let _ = app_handle_clone.emit(
+                                        "sync://cloud-list-error",
+                                        json!({ "gameId": game_id, "message": err.to_string() }),
+                                    );
+                                    continue;
+                                }
+                            }
                         }
                     };
-                    drop(backend); // Release lock
 
                     // Decide
-                    let decision = determine_sync_action(
+                    let decision = match determine_sync_action(
                         local_latest.as_ref(),
+                        &local_lineage,
                         &cloud_versions,
                         &current_device,
-                    );
+                    ) {
+                        SyncDecision::Conflict => resolve_with_rule(
+                            &app_handle_clone,
+                            &game_id,
+                            local_latest.as_ref(),
+                            &cloud_versions,
+                            &current_device,
+                        )
+                        .unwrap_or(SyncDecision::Conflict),
+                        decision => decision,
+                    };
 
                     match decision {
+                        SyncDecision::Upload if read_only_shares.contains(&game_id) => {
+                            debug!(
+                                "{} [SYNC] {} is shared read-only; keeping local changes local",
+                                tag, game_id
+                            );
+                        }
                         SyncDecision::Upload => {
                             if let Some(local) = local_latest {
+                                queued_work = true;
                                 info!("{} [SYNC] Queueing upload for {}", tag, game_id);
-                                queue_clone
-                                    .add_job(UploadJob {
-                                        game_id: game_id.clone(),
-                                        emulator_id: local.metadata.emulator_id.clone(),
-                                        version_id: local.metadata.version_id.clone(),
-                                        archive_path: PathBuf::from(&local.archive_path),
-                                        metadata: local.metadata.clone(),
-                                        created_at: DateTime::from_timestamp(
-                                            local.metadata.timestamp as i64,
-                                            0,
-                                        )
-                                        .unwrap_or_default(),
-                                        retries: 0,
-                                        status: UploadStatus::Pending,
-                                        total_size: fs::metadata(&local.archive_path)
-                                            .map(|m| m.len())
-                                            .unwrap_or_default(),
-                                        hash: local.metadata.hash.clone(),
-                                    })
-                                    .await;
+                                queue_clone.add_job(UploadJob::from_entry(&local)).await;
                             }
                         }
                         SyncDecision::Download(version_id) => {
+                            queued_work = true;
                             info!(
-                                "{} [SYNC] Should download {} version {}",
+                                "{} [SYNC] Queueing download of {} version {}",
                                 tag, game_id, version_id
                             );
-                            if let Err(err) = perform_download(
-                                cloud_clone.clone(),
-                                history_clone.clone(),
-                                profiles_clone.clone(),
-                                app_handle_clone.clone(),
-                                settings_clone.clone(),
-                                game_id.clone(),
-                                version_id,
-                            )
-                            .await
-                            {
-                                warn!("{} [SYNC] Download failed: {}", tag, err);
-                            }
+                            downloads_clone.add_job(game_id.clone(), version_id).await;
                         }
                         SyncDecision::Conflict => {
                             warn!("{} [SYNC] Conflict detected for {}", tag, game_id);
+                            record_sync_event(&app_handle_clone, |state| {
+                                state.record_conflict(&game_id)
+                            });
                             let _ = app_handle_clone.emit("sync://conflict-detected", game_id);
                         }
                         SyncDecision::Noop => {}
                     }
                 }
+
+                let activity = activity_flag.swap(false, Ordering::SeqCst);
+                scheduler.record_cycle(queued_work || activity || !full_scan);
+
+                if first_cycle && full_scan {
+                    first_cycle = false;
+                    emit_sync_progress(
+                        &app_handle_clone,
+                        SyncStage::Ready,
+                        total_games,
+                        total_games,
+                        None,
+                    );
+                }
             }
         });
     }
@@ -1024,34 +2721,47 @@ impl Clone for SyncManager {
     fn clone(&self) -> Self {
         Self {
             queue: self.queue.clone(),
+            downloads: self.downloads.clone(),
             cloud: self.cloud.clone(),
             history: self.history.clone(),
             profiles: self.profiles.clone(),
             settings: self.settings.clone(),
             online: self.online.clone(),
             paused: self.paused.clone(),
+            breaker: self.breaker.clone(),
+            connectivity: self.connectivity.clone(),
             connection_status: self.connection_status.clone(),
             app_handle: self.app_handle.clone(),
             sync_trigger: self.sync_trigger.clone(),
             running: self.running.clone(),
+            activity: self.activity.clone(),
         }
     }
 }
 
 impl SyncManager {
     pub fn pause(&self) {
-        self.paused.store(true, Ordering::SeqCst);
+        self.paused.set(true);
     }
 
     pub fn resume(&self) {
-        let was_paused = self.paused.swap(false, Ordering::SeqCst);
-        if was_paused {
+        if self.paused.set(false) {
             self.sync_trigger.notify_one();
         }
     }
 
     pub fn is_paused(&self) -> bool {
-        self.paused.load(Ordering::SeqCst)
+        self.paused.is_paused()
+    }
+
+    /// Network and battery state as of the last check, and what it holds
+    pub fn device_conditions(&self) -> ConditionsPayload {
+        let network = self
+            .settings
+            .get_settings()
+            .map(|settings| settings.network)
+            .unwrap_or_default();
+        ConditionsPayload::new(&network, self.connectivity.current())
     }
 }
 
@@ -1113,35 +2823,77 @@ pub async fn perform_download(
         },
     );
 
-    let client = Client::new();
-    let response = client
-        .get(&download_info.download_url)
-        .send()
-        .await
-        .map_err(|e| emit_error("http-get", e.to_string(), &app_handle))?;
-
-    if !response.status().is_success() {
-        return Err(emit_error(
-            "http-get",
-            format!("download failed: {}", response.status()),
-            &app_handle,
-        ));
-    }
+    let holds_archives = cloud.lock().await.holds_archives();
+    if holds_archives {
+        cloud
+            .lock()
+            .await
+            .download_version(game_id.clone(), version_id.clone(), target_path.clone())
+            .await
+            .map_err(|e| emit_error("download", e.to_string(), &app_handle))?;
+        received_bytes = total_bytes;
+    } else {
+        let client = Client::new();
+        let progress_step = (total_bytes / PROGRESS_EVENT_STEPS as u64).max(64 * 1024);
+        let mut last_reported: u64 = 0;
+        let mut attempt = 1;
+        loop {
+            let result = stream_download(
+                &client,
+                &download_info.download_url,
+                &target_path,
+                &mut received_bytes,
+                |received| {
+                    if received < last_reported || received - last_reported >= progress_step {
+                        last_reported = received;
+                        let _ = app_handle.emit(
+                            "sync://download-progress",
+                            DownloadProgressPayload {
+                                version_id: version_id.clone(),
+                                received_bytes: received,
+                                total_bytes,
+                            },
+                        );
+                    }
+                },
+            )
+            .await;
 
-    let mut file = tokio::fs::File::create(&target_path)
-        .await
-        .map_err(|e| emit_error("write-file", e.to_string(), &app_handle))?;
+            match result {
+                Ok(()) => break,
+                Err(TransferError::Stalled) if attempt < DOWNLOAD_ATTEMPTS => {
+                    warn!(
+                        "[SYNC] Download of {} stalled at {} bytes; resuming (attempt {})",
+                        version_id,
+                        received_bytes,
+                        attempt + 1
+                    );
+                    attempt += 1;
+                }
+                Err(TransferError::StorageFull(message)) => {
+                    report_storage_full(&app_handle, StorageScope::Local, message.clone());
+                    return Err(emit_error("disk-full", message, &app_handle));
+                }
+                Err(err) => {
+                    return Err(emit_error(err.stage(), err.to_string(), &app_handle));
+                }
+            }
+        }
 
-    // Download the entire file
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| emit_error("http-get", e.to_string(), &app_handle))?;
-    
-    received_bytes = bytes.len() as u64;
-    file.write_all(&bytes)
-        .await
-        .map_err(|e| emit_error("write-file", e.to_string(), &app_handle))?;
+        // Archives uploaded with zstd arrive compressed
+        let archive_path = target_path.clone();
+        tauri::async_runtime::spawn_blocking(move || decode_download_file(&archive_path))
+            .await
+            .map_err(|e| emit_error("download", e.to_string(), &app_handle))?
+            .map_err(|e| match e {
+                PackagerError::StorageFull(message) => {
+                    report_storage_full(&app_handle, StorageScope::Local, message.clone());
+                    emit_error("disk-full", message, &app_handle)
+                }
+                other => emit_error("download", other.to_string(), &app_handle),
+            })?;
+        received_bytes = total_bytes;
+    }
 
     let _ = app_handle.emit(
         "sync://download-progress",
@@ -1152,10 +2904,6 @@ pub async fn perform_download(
         },
     );
 
-    file.flush()
-        .await
-        .map_err(|e| emit_error("write-file", e.to_string(), &app_handle))?;
-
     let emulator_id = download_info.emulator_id.clone().unwrap_or_default();
     if emulator_id.trim().is_empty() {
         return Err(emit_error(
@@ -1182,74 +2930,80 @@ pub async fn perform_download(
         ));
     };
 
-    let Some(save_path) = profile.default_save_paths.first() else {
-        return Err(emit_error(
-            "unzip",
-            format!("no default save path for {emulator_id}"),
-            &app_handle,
-        ));
-    };
-
-    let target_dir = PathBuf::from(save_path);
-    if !target_dir.exists() {
-        return Err(emit_error(
-            "unzip",
-            format!("save directory missing: {}", target_dir.display()),
-            &app_handle,
-        ));
-    }
+    // Restore into the game's own location when the profile overrides it
+    let target_dir = restore_target(&profile, &game_id)
+        .map_err(|e| emit_error(e.stage(), e.to_string(), &app_handle))?;
 
-    let file = fs::File::open(&target_path)
-        .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
-    let mut archive =
-        ZipArchive::new(file).map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
+    // Keep the watcher from reporting our own writes back as save changes
+    let suppress_watcher = || {
+        if let Some(watcher) = app_handle.try_state::<WatcherManager>() {
+            watcher.suppress_path(&target_dir);
+        }
+    };
+    suppress_watcher();
+
+    let outcome = {
+        let history = history.clone();
+        let game_id = game_id.clone();
+        let target_dir = target_dir.clone();
+        let archive_path = target_path.clone();
+        let restoring_timestamp = download_info.timestamp;
+        tauri::async_runtime::spawn_blocking(move || {
+            restore_version(
+                &history,
+                &profile,
+                &game_id,
+                &target_dir,
+                &archive_path,
+                restoring_timestamp,
+            )
+        })
+        .await
+        .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?
+    };
 
-    for i in 0..archive.len() {
-        let mut file = archive
-            .by_index(i)
-            .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
-        let Some(name) = file.enclosed_name().map(|p| p.to_owned()) else {
-            continue;
-        };
+    suppress_watcher();
 
-        let out_path = target_dir.join(name);
-        if file.is_dir() {
-            fs::create_dir_all(&out_path)
-                .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
-        } else {
-            if let Some(parent) = out_path.parent() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
-            }
-            let mut outfile = fs::File::create(&out_path)
-                .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
-            std::io::copy(&mut file, &mut outfile)
-                .map_err(|e| emit_error("unzip", e.to_string(), &app_handle))?;
+    let outcome = outcome.map_err(|e| {
+        if let RestoreError::StorageFull(message) = &e {
+            report_storage_full(&app_handle, StorageScope::Local, message.clone());
         }
+        emit_error(e.stage(), e.to_string(), &app_handle)
+    })?;
+
+    info!(
+        "[SYNC] Restored {} files for {} into {}",
+        outcome.restored_files,
+        game_id,
+        target_dir.display()
+    );
+    if let Some(backup) = &outcome.backup {
+        info!(
+            "[SYNC] Previous saves for {} kept as version {}",
+            game_id, backup.metadata.version_id
+        );
     }
 
-    let timestamp = download_info
-        .timestamp
-        .unwrap_or_else(|| Utc::now().timestamp().max(0) as u64);
-    let metadata = SaveMetadata {
-        game_id: game_id.clone(),
-        emulator_id: emulator_id.clone(),
-        timestamp,
-        version_id: download_info.version_id.clone(),
-        file_list: download_info.file_list.clone(),
-        hash: download_info.sha256.clone(),
-        size_bytes: Some(download_info.size_bytes),
-        sha256: Some(download_info.sha256.clone()),
-        source: Some("cloud".to_string()),
-    };
+    let metadata = downloaded_metadata(&download_info, &game_id, &emulator_id);
 
     history
         .add_version_from_cloud(metadata, target_path.clone())
-        .map_err(|e| emit_error("write-history", e.to_string(), &app_handle))?;
+        .map_err(|e| {
+            if let HistoryError::StorageFull(message) = &e {
+                report_storage_full(&app_handle, StorageScope::Local, message.clone());
+                return emit_error("disk-full", e.to_string(), &app_handle);
+            }
+            emit_error("write-history", e.to_string(), &app_handle)
+        })?;
+    enforce_history_budget(&app_handle, &history);
+    record_sync_event(&app_handle, |state| {
+        state.record_download(&game_id, &download_info.version_id)
+    });
 
     let _ = app_handle.emit(
         "sync://download-complete",
         DownloadCompletePayload {
+            game_id: game_id.clone(),
             version_id: version_id.clone(),
         },
     );
@@ -1257,6 +3011,42 @@ pub async fn perform_download(
     Ok(())
 }
 
+/// History metadata for a version downloaded from the cloud
+pub(crate) fn downloaded_metadata(
+    download_info: &DownloadUrlResponse,
+    game_id: &str,
+    emulator_id: &str,
+) -> SaveMetadata {
+    let timestamp = download_info
+        .timestamp
+        .unwrap_or_else(|| Utc::now().timestamp().max(0) as u64);
+    let mut metadata = SaveMetadata {
+        schema_version: METADATA_SCHEMA_VERSION,
+        game_id: game_id.to_string(),
+        emulator_id: emulator_id.to_string(),
+        timestamp,
+        version_id: download_info.version_id.clone(),
+        file_list: download_info.file_list.clone(),
+        hash: download_info.sha256.clone(),
+        size_bytes: Some(download_info.size_bytes),
+        sha256: Some(download_info.sha256.clone()),
+        source: Some("cloud".to_string()),
+        encrypted: download_info.encrypted,
+        thumbnail: None,
+        thumbnail_sha256: None,
+        note: download_info.note.clone(),
+        tags: download_info.tags.clone(),
+        fingerprint: None,
+        pinned: download_info.pinned,
+        parent_version_id: download_info.parent_version_id.clone(),
+        title: None,
+        platform: None,
+        cover_sha256: None,
+    };
+    attach_bundled(&mut metadata);
+    metadata
+}
+
 fn calculate_sha256(path: &PathBuf) -> Result<String, std::io::Error> {
     let mut file = fs::File::open(path)?;
     let mut hasher = Sha256::new();
@@ -1264,3 +3054,213 @@ fn calculate_sha256(path: &PathBuf) -> Result<String, std::io::Error> {
     let result = hasher.finalize();
     Ok(format!("{:x}", result))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const THIS_DEVICE: &str = "this-device";
+    const OTHER_DEVICE: &str = "other-device";
+
+    fn local_entry(version_id: &str, timestamp: u64, hash: &str) -> HistoryEntry {
+        let metadata: SaveMetadata = serde_json::from_value(json!({
+            "game_id": "game",
+            "emulator_id": "emu",
+            "timestamp": timestamp,
+            "version_id": version_id,
+            "file_list": ["save.srm"],
+            "hash": hash,
+        }))
+        .expect("metadata");
+        HistoryEntry {
+            archive_path: PathBuf::new(),
+            metadata_path: PathBuf::new(),
+            metadata,
+        }
+    }
+
+    fn cloud_version(
+        version_id: &str,
+        timestamp: u64,
+        device_id: &str,
+        parent: Option<&str>,
+    ) -> CloudVersionSummary {
+        CloudVersionSummary {
+            version_id: version_id.to_string(),
+            timestamp,
+            size_bytes: 1,
+            device_id: device_id.to_string(),
+            file_list: vec!["save.srm".to_string()],
+            // Each version has its own content
+            sha256: format!("hash-{version_id}"),
+            thumbnail_url: None,
+            note: None,
+            tags: Vec::new(),
+            pinned: false,
+            parent_version_id: parent.map(str::to_string),
+            encrypted: false,
+        }
+    }
+
+    /// A shared history of `base` versions that the local and cloud sides
+    /// then extended by `local_ahead` and `cloud_ahead` versions each
+    struct Branches {
+        local: Option<HistoryEntry>,
+        local_lineage: Vec<String>,
+        cloud: Vec<CloudVersionSummary>,
+    }
+
+    fn branches(base: usize, local_ahead: usize, cloud_ahead: usize, local_time: u64) -> Branches {
+        let mut cloud = Vec::new();
+        let mut shared = Vec::new();
+        let mut parent: Option<String> = None;
+        for index in 0..base {
+            let id = format!("base-{index}");
+            let timestamp = 100 + index as u64;
+            let parent_id = parent.as_deref();
+            cloud.push(cloud_version(&id, timestamp, OTHER_DEVICE, parent_id));
+            shared.push(id.clone());
+            parent = Some(id);
+        }
+
+        for index in 0..cloud_ahead {
+            let id = format!("cloud-{index}");
+            let timestamp = 1_000 + index as u64;
+            let parent_id = parent.as_deref();
+            cloud.push(cloud_version(&id, timestamp, OTHER_DEVICE, parent_id));
+            parent = Some(id);
+        }
+
+        let mut local_ids = shared;
+        local_ids.extend((0..local_ahead).map(|index| format!("local-{index}")));
+        let local = local_ids.last().map(|id| {
+            let hash = if id.starts_with("base-") {
+                format!("hash-{id}")
+            } else {
+                format!("local-hash-{id}")
+            };
+            local_entry(id, local_time, &hash)
+        });
+        local_ids.reverse();
+        Branches {
+            local,
+            local_lineage: local_ids,
+            cloud,
+        }
+    }
+
+    fn latest_id(cloud: &[CloudVersionSummary]) -> Option<String> {
+        cloud
+            .iter()
+            .max_by_key(|version| version.timestamp)
+            .map(|version| version.version_id.clone())
+    }
+
+    proptest! {
+        /// With lineage recorded, a conflict is raised exactly when both
+        /// sides made versions since the one they share
+        #[test]
+        fn lineage_decides_when_recorded(
+            base in 1usize..6,
+            local_ahead in 0usize..4,
+            cloud_ahead in 0usize..4,
+            local_time in 0u64..3_000,
+        ) {
+            let b = branches(base, local_ahead, cloud_ahead, local_time);
+            let decision =
+                determine_sync_action(b.local.as_ref(), &b.local_lineage, &b.cloud, THIS_DEVICE);
+
+            let expected = match (local_ahead > 0, cloud_ahead > 0) {
+                (false, false) => SyncDecision::Noop,
+                (true, false) => SyncDecision::Upload,
+                (false, true) => SyncDecision::Download(latest_id(&b.cloud).expect("cloud")),
+                (true, true) => SyncDecision::Conflict,
+            };
+            prop_assert_eq!(decision, expected);
+        }
+
+        /// Whatever the timestamps, a side that only fast-forwarded the other
+        /// is never overwritten by it
+        #[test]
+        fn newest_data_is_never_replaced_by_an_ancestor(
+            base in 1usize..6,
+            ahead in 1usize..4,
+            local_time in 0u64..3_000,
+            local_leads in any::<bool>(),
+        ) {
+            let (local_ahead, cloud_ahead) = if local_leads { (ahead, 0) } else { (0, ahead) };
+            let b = branches(base, local_ahead, cloud_ahead, local_time);
+            let decision =
+                determine_sync_action(b.local.as_ref(), &b.local_lineage, &b.cloud, THIS_DEVICE);
+
+            if local_leads {
+                prop_assert_eq!(decision, SyncDecision::Upload);
+            } else {
+                prop_assert!(matches!(decision, SyncDecision::Download(_)));
+            }
+        }
+
+        /// Without lineage, the timestamp fallback only asks when the cloud
+        /// version came from another device and the clocks are too close to tell
+        #[test]
+        fn timestamp_fallback_conflicts_only_when_ambiguous(
+            local_time in 0u64..100,
+            cloud_time in 0u64..100,
+            from_this_device in any::<bool>(),
+        ) {
+            let device = if from_this_device { THIS_DEVICE } else { OTHER_DEVICE };
+            let local = local_entry("local", local_time, "local-hash");
+            let cloud = vec![cloud_version("cloud", cloud_time, device, None)];
+            let decision = determine_sync_action(Some(&local), &[], &cloud, THIS_DEVICE);
+
+            let close = local_time.abs_diff(cloud_time) <= 2;
+            let ambiguous = !from_this_device && close && local_time != cloud_time;
+            prop_assert_eq!(decision == SyncDecision::Conflict, ambiguous);
+            if !ambiguous {
+                let cloud_wins = cloud_time > local_time
+                    || (!from_this_device && cloud_time == local_time);
+                let expected = if cloud_wins {
+                    SyncDecision::Download("cloud".to_string())
+                } else {
+                    SyncDecision::Upload
+                };
+                prop_assert_eq!(decision, expected);
+            }
+        }
+
+        /// A side with nothing never wins, and matching content never moves
+        #[test]
+        fn missing_or_identical_sides(
+            timestamps in proptest::collection::vec(0u64..1_000, 0..6),
+            local_time in 0u64..1_000,
+            has_local in any::<bool>(),
+        ) {
+            let cloud: Vec<CloudVersionSummary> = timestamps
+                .iter()
+                .enumerate()
+                .map(|(index, &timestamp)| {
+                    cloud_version(&format!("v{index}"), timestamp, OTHER_DEVICE, None)
+                })
+                .collect();
+            let local = has_local.then(|| local_entry("local", local_time, "local-hash"));
+            let decision = determine_sync_action(local.as_ref(), &[], &cloud, THIS_DEVICE);
+
+            match (&local, latest_id(&cloud)) {
+                (None, None) => prop_assert_eq!(decision, SyncDecision::Noop),
+                (Some(_), None) => prop_assert_eq!(decision, SyncDecision::Upload),
+                (None, Some(latest)) => prop_assert_eq!(decision, SyncDecision::Download(latest)),
+                (Some(_), Some(latest)) => {
+                    if let SyncDecision::Download(version_id) = &decision {
+                        prop_assert_eq!(version_id, &latest);
+                    }
+                    let same = local_entry("local", local_time, &format!("hash-{latest}"));
+                    prop_assert_eq!(
+                        determine_sync_action(Some(&same), &[], &cloud, THIS_DEVICE),
+                        SyncDecision::Noop
+                    );
+                }
+            }
+        }
+    }
+}
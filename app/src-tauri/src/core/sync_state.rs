@@ -0,0 +1,150 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+#[derive(Debug, Error)]
+pub enum SyncStateError {
+    #[error("io error: {0}")]
+    Io(String),
+    #[error("serialization error: {0}")]
+    Serialization(String),
+    #[error("lock error: {0}")]
+    Lock(String),
+}
+
+/// A version that finished moving in one direction
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncEvent {
+    pub version_id: String,
+    pub at: DateTime<Utc>,
+}
+
+/// What last happened to one game's saves
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameSyncState {
+    #[serde(default)]
+    pub last_upload: Option<SyncEvent>,
+    #[serde(default)]
+    pub last_download: Option<SyncEvent>,
+    /// When sync last found local and cloud saves diverged
+    #[serde(default)]
+    pub last_conflict_at: Option<DateTime<Utc>>,
+}
+
+impl GameSyncState {
+    /// The later of the last upload and the last download
+    pub fn last_sync(&self) -> Option<DateTime<Utc>> {
+        let upload = self.last_upload.as_ref().map(|event| event.at);
+        let download = self.last_download.as_ref().map(|event| event.at);
+        upload.max(download)
+    }
+
+    /// Version ID of whichever transfer happened last; both sides held it then
+    pub fn last_synced_version(&self) -> Option<&str> {
+        match (&self.last_upload, &self.last_download) {
+            (Some(upload), Some(download)) if download.at > upload.at => {
+                Some(download.version_id.as_str())
+            }
+            (Some(upload), _) => Some(upload.version_id.as_str()),
+            (None, download) => download.as_ref().map(|event| event.version_id.as_str()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct SyncStateFile {
+    #[serde(default)]
+    last_sync: Option<DateTime<Utc>>,
+    #[serde(default)]
+    games: HashMap<String, GameSyncState>,
+}
+
+/// Last successful transfer times, kept across restarts so the UI does not
+/// have to guess when a game was last in sync
+pub struct SyncStateStore {
+    path: PathBuf,
+    state: Mutex<SyncStateFile>,
+}
+
+impl SyncStateStore {
+    pub fn new(path: PathBuf) -> Result<Self, SyncStateError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| SyncStateError::Io(err.to_string()))?;
+        }
+
+        let state = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                warn!("[SYNC] Failed to parse sync state: {err}. Starting empty");
+                SyncStateFile::default()
+            }),
+            Err(_) => SyncStateFile::default(),
+        };
+
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn record_upload(&self, game_id: &str, version_id: &str) -> Result<(), SyncStateError> {
+        let event = Self::event(version_id);
+        self.update(game_id, true, |game| game.last_upload = Some(event))
+    }
+
+    pub fn record_download(&self, game_id: &str, version_id: &str) -> Result<(), SyncStateError> {
+        let event = Self::event(version_id);
+        self.update(game_id, true, |game| game.last_download = Some(event))
+    }
+
+    pub fn record_conflict(&self, game_id: &str) -> Result<(), SyncStateError> {
+        let now = Utc::now();
+        self.update(game_id, false, |game| game.last_conflict_at = Some(now))
+    }
+
+    /// Time of the last successful upload or download of any game
+    pub fn last_sync(&self) -> Option<DateTime<Utc>> {
+        self.state.lock().ok()?.last_sync
+    }
+
+    pub fn game(&self, game_id: &str) -> Result<GameSyncState, SyncStateError> {
+        Ok(self.lock()?.games.get(game_id).cloned().unwrap_or_default())
+    }
+
+    fn event(version_id: &str) -> SyncEvent {
+        SyncEvent {
+            version_id: version_id.to_string(),
+            at: Utc::now(),
+        }
+    }
+
+    /// Apply `change` to `game_id`; `synced` also moves the global time
+    fn update(
+        &self,
+        game_id: &str,
+        synced: bool,
+        change: impl FnOnce(&mut GameSyncState),
+    ) -> Result<(), SyncStateError> {
+        let mut guard = self.lock()?;
+        let game = guard.games.entry(game_id.to_string()).or_default();
+        change(game);
+        if synced {
+            guard.last_sync = Some(Utc::now());
+        }
+        self.persist(&guard)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, SyncStateFile>, SyncStateError> {
+        self.state
+            .lock()
+            .map_err(|err| SyncStateError::Lock(err.to_string()))
+    }
+
+    fn persist(&self, state: &SyncStateFile) -> Result<(), SyncStateError> {
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|err| SyncStateError::Serialization(err.to_string()))?;
+        fs::write(&self.path, json).map_err(|err| SyncStateError::Io(err.to_string()))
+    }
+}
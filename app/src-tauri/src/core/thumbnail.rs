@@ -0,0 +1,120 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use reqwest::{header::CONTENT_TYPE, Client};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::core::paths::is_blank;
+
+/// Largest screenshot accepted as a version thumbnail
+pub const MAX_THUMBNAIL_BYTES: u64 = 4 * 1024 * 1024;
+/// Screenshots taken this long before a save are still matched to it
+const SCREENSHOT_WINDOW_SECS: u64 = 10 * 60;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Check that `path` is a PNG small enough to keep next to an archive
+pub fn validate_thumbnail(path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    if metadata.len() > MAX_THUMBNAIL_BYTES {
+        return Err(format!(
+            "thumbnail exceeds {MAX_THUMBNAIL_BYTES} bytes: {}",
+            path.display()
+        ));
+    }
+
+    let mut signature = [0u8; 8];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut signature))
+        .map_err(|err| format!("{}: {err}", path.display()))?;
+    if signature != PNG_SIGNATURE {
+        return Err(format!("{} is not a PNG", path.display()));
+    }
+
+    Ok(())
+}
+
+/// Newest PNG in `dirs` taken shortly before (or after) a save made at
+/// `saved_at`, used to attach emulator screenshots automatically
+pub fn latest_screenshot(dirs: &[PathBuf], saved_at: u64) -> Option<PathBuf> {
+    let earliest = saved_at.saturating_sub(SCREENSHOT_WINDOW_SECS);
+    let mut latest: Option<(u64, PathBuf)> = None;
+
+    for dir in dirs.iter().filter(|dir| !is_blank(dir)) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            debug!(
+                "[HISTORY] Screenshot folder {} is not readable",
+                dir.display()
+            );
+            continue;
+        };
+
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            let is_png = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.eq_ignore_ascii_case("png"))
+                .unwrap_or(false);
+            if !is_png {
+                continue;
+            }
+
+            let Some(modified) = modified_secs(&path) else {
+                continue;
+            };
+            let newer = match &latest {
+                Some((newest, _)) => modified > *newest,
+                None => true,
+            };
+            if modified >= earliest && newer {
+                latest = Some((modified, path));
+            }
+        }
+    }
+
+    latest
+        .map(|(_, path)| path)
+        .filter(|path| validate_thumbnail(path).is_ok())
+}
+
+pub fn thumbnail_sha256(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// PUT a thumbnail to the presigned URL returned next to the archive URL
+pub async fn upload_thumbnail(client: &Client, url: &str, path: &Path) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    let resp = client
+        .put(url)
+        .header(CONTENT_TYPE, "image/png")
+        .body(bytes)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if !resp.status().is_success() {
+        return Err(format!("thumbnail upload failed: {}", resp.status()));
+    }
+    Ok(())
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+}
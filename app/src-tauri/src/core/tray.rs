@@ -0,0 +1,309 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use serde::Deserialize;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{App, AppHandle, Listener, Manager, Wry};
+use tauri_plugin_opener::OpenerExt;
+use tracing::{info, warn};
+
+use crate::core::desktop::show_main_window;
+use crate::core::history::HistoryManager;
+use crate::core::sync::SyncManager;
+
+const TRAY_ID: &str = "sync";
+const FORCE_SYNC: &str = "tray-force-sync";
+const PAUSE_SYNC: &str = "tray-pause-sync";
+const OPEN_FOLDER: &str = "tray-open-folder";
+const SHOW_WINDOW: &str = "tray-show-window";
+const QUIT: &str = "tray-quit";
+/// Entries kept under "Recent Activity"
+const RECENT_ACTIVITY: usize = 5;
+
+/// What the tray says about sync, most urgent first
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TrayStatus {
+    /// A conflict is waiting for the user
+    Conflict,
+    Offline,
+    Syncing,
+    Idle,
+}
+
+impl TrayStatus {
+    fn label(self) -> &'static str {
+        match self {
+            TrayStatus::Conflict => "Conflict needs attention",
+            TrayStatus::Offline => "Offline",
+            TrayStatus::Syncing => "Syncing…",
+            TrayStatus::Idle => "Up to date",
+        }
+    }
+
+    fn tooltip(self) -> String {
+        format!("CrossSave Cloud: {}", self.label())
+    }
+}
+
+#[derive(Deserialize)]
+struct StatusPayload {
+    is_syncing: bool,
+}
+
+#[derive(Deserialize)]
+struct CompletePayload {
+    game_id: String,
+}
+
+/// Sync state as told by the events `SyncManager` emits
+struct TrayModel {
+    online: bool,
+    syncing: bool,
+    /// Games with a conflict; cleared once the game uploads or downloads
+    conflicts: BTreeSet<String>,
+    activity: VecDeque<String>,
+    /// Bumped whenever `activity` changes, so the submenu is only rebuilt then
+    activity_revision: u64,
+    shown_status: TrayStatus,
+    shown_revision: u64,
+    activity_items: Vec<MenuItem<Wry>>,
+}
+
+impl TrayModel {
+    fn status(&self) -> TrayStatus {
+        if !self.conflicts.is_empty() {
+            TrayStatus::Conflict
+        } else if !self.online {
+            TrayStatus::Offline
+        } else if self.syncing {
+            TrayStatus::Syncing
+        } else {
+            TrayStatus::Idle
+        }
+    }
+
+    fn record(&mut self, entry: String) {
+        self.activity
+            .push_front(format!("{} {entry}", Local::now().format("%H:%M")));
+        self.activity.truncate(RECENT_ACTIVITY);
+        self.activity_revision += 1;
+    }
+
+    fn completed(&mut self, payload: &str, action: &str) {
+        if let Ok(complete) = serde_json::from_str::<CompletePayload>(payload) {
+            self.conflicts.remove(&complete.game_id);
+            self.record(format!("{action} {}", complete.game_id));
+        }
+    }
+}
+
+/// The tray icon and the menu items it keeps current, managed as app state
+pub struct SyncTray {
+    app: AppHandle,
+    tray: TrayIcon,
+    status_item: MenuItem<Wry>,
+    pause_item: CheckMenuItem<Wry>,
+    recent: Submenu<Wry>,
+    model: Mutex<TrayModel>,
+}
+
+/// Add the tray icon and keep it in step with sync events. "Open Folder"
+/// opens the history directory, looked up on each click so a relocated
+/// history or a switched account opens where the archives are now.
+pub fn install(app: &App) -> tauri::Result<()> {
+    let handle = app.handle();
+    let status_item = MenuItem::with_id(
+        handle,
+        "tray-status",
+        TrayStatus::Idle.label(),
+        false,
+        None::<&str>,
+    )?;
+    let force_item = MenuItem::with_id(handle, FORCE_SYNC, "Force Sync Now", true, None::<&str>)?;
+    let pause_item =
+        CheckMenuItem::with_id(handle, PAUSE_SYNC, "Pause Sync", true, false, None::<&str>)?;
+    let folder_item = MenuItem::with_id(handle, OPEN_FOLDER, "Open Folder", true, None::<&str>)?;
+    let show_item = MenuItem::with_id(handle, SHOW_WINDOW, "Show CrossSave", true, None::<&str>)?;
+    let quit_item = MenuItem::with_id(handle, QUIT, "Quit", true, None::<&str>)?;
+    let placeholder = MenuItem::new(handle, "No recent activity", false, None::<&str>)?;
+    let recent = Submenu::with_items(handle, "Recent Activity", true, &[&placeholder])?;
+    let menu = Menu::with_items(
+        handle,
+        &[
+            &status_item,
+            &PredefinedMenuItem::separator(handle)?,
+            &force_item,
+            &pause_item,
+            &folder_item,
+            &PredefinedMenuItem::separator(handle)?,
+            &recent,
+            &PredefinedMenuItem::separator(handle)?,
+            &show_item,
+            &quit_item,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(TrayStatus::Idle.tooltip())
+        .menu(&menu)
+        // Left click brings the window back; the menu is on right click
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        })
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    let tray = builder.build(handle)?;
+
+    let sync_tray = Arc::new(SyncTray {
+        app: handle.clone(),
+        tray,
+        status_item,
+        pause_item,
+        recent,
+        model: Mutex::new(TrayModel {
+            online: true,
+            syncing: false,
+            conflicts: BTreeSet::new(),
+            activity: VecDeque::new(),
+            activity_revision: 0,
+            shown_status: TrayStatus::Idle,
+            shown_revision: 0,
+            activity_items: vec![placeholder],
+        }),
+    });
+    sync_tray.listen();
+    app.manage(sync_tray);
+    info!("[TRAY] Tray icon ready");
+    Ok(())
+}
+
+impl SyncTray {
+    fn listen(self: &Arc<Self>) {
+        let on = |event: &str, apply: fn(&mut TrayModel, &str)| {
+            let tray = self.clone();
+            self.app.listen(event, move |event| {
+                tray.update(|model| apply(model, event.payload()));
+            });
+        };
+
+        on("sync://status", |model, payload| {
+            if let Ok(status) = serde_json::from_str::<StatusPayload>(payload) {
+                model.syncing = status.is_syncing;
+            }
+        });
+        on("sync://online", |model, _| model.online = true);
+        on("cloud://online", |model, _| model.online = true);
+        on("sync://offline", |model, _| model.online = false);
+        on("sync://conflict-detected", |model, payload| {
+            if let Ok(game_id) = serde_json::from_str::<String>(payload) {
+                model.record(format!("Conflict on {game_id}"));
+                model.conflicts.insert(game_id);
+            }
+        });
+        on("sync://upload-complete", |model, payload| {
+            model.completed(payload, "Uploaded")
+        });
+        on("sync://download-complete", |model, payload| {
+            model.completed(payload, "Downloaded")
+        });
+        // A full disk or cloud quota pauses sync without going through the tray
+        on("sync://storage-full", |_, _| {});
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut TrayModel)) {
+        let Ok(mut model) = self.model.lock() else {
+            return;
+        };
+        apply(&mut model);
+        if let Err(err) = self.refresh(&mut model) {
+            warn!("[TRAY] Failed to update tray menu: {err}");
+        }
+    }
+
+    fn refresh(&self, model: &mut TrayModel) -> tauri::Result<()> {
+        let status = model.status();
+        if status != model.shown_status {
+            self.status_item.set_text(status.label())?;
+            self.tray.set_tooltip(Some(status.tooltip()))?;
+            model.shown_status = status;
+        }
+
+        if model.activity_revision != model.shown_revision {
+            for item in model.activity_items.drain(..) {
+                self.recent.remove(&item)?;
+            }
+            for entry in &model.activity {
+                let item = MenuItem::new(&self.app, entry, false, None::<&str>)?;
+                self.recent.append(&item)?;
+                model.activity_items.push(item);
+            }
+            model.shown_revision = model.activity_revision;
+        }
+
+        if let Some(sync) = self.app.try_state::<SyncManager>() {
+            self.pause_item.set_checked(sync.is_paused())?;
+        }
+        Ok(())
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        FORCE_SYNC => {
+            if let Some(sync) = app.try_state::<SyncManager>() {
+                info!("[SYNC] Sync requested from the tray");
+                sync.trigger_sync();
+            }
+        }
+        PAUSE_SYNC => {
+            if let Some(sync) = app.try_state::<SyncManager>() {
+                if sync.is_paused() {
+                    info!("[SYNC] Sync resumed from the tray");
+                    sync.resume();
+                } else {
+                    info!("[SYNC] Sync paused from the tray");
+                    sync.pause();
+                }
+            }
+            // The click toggled the check mark; match it to the real state
+            if let Some(tray) = app.try_state::<Arc<SyncTray>>() {
+                tray.update(|_| {});
+            }
+        }
+        OPEN_FOLDER => {
+            let Some(history) = app.try_state::<Arc<HistoryManager>>() else {
+                warn!("[TRAY] History is not ready yet");
+                return;
+            };
+            let folder = history.base_dir();
+            if let Err(err) = std::fs::create_dir_all(&folder) {
+                warn!("[TRAY] Failed to create {:?}: {err}", folder);
+                return;
+            }
+            if let Err(err) = app
+                .opener()
+                .open_path(folder.to_string_lossy(), None::<&str>)
+            {
+                warn!("[TRAY] Failed to open {:?}: {err}", folder);
+            }
+        }
+        SHOW_WINDOW => show_main_window(app),
+        QUIT => {
+            info!("[TRAY] Quit requested from the tray");
+            app.exit(0);
+        }
+        _ => {}
+    }
+}
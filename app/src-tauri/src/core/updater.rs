@@ -0,0 +1,376 @@
+use std::cmp::Ordering as CmpOrdering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+use thiserror::Error;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+use crate::core::settings::{AppSettings, CloudMode, SettingsManager};
+
+/// Key release metadata is signed with, baked in at build time. Settings
+/// can't replace it, or anyone able to edit them could offer a release.
+const BUILT_IN_PUBLIC_KEY: Option<&str> = option_env!("CROSSSAVE_UPDATE_PUBLIC_KEY");
+/// Minisign key Tauri's updater checks installers against. Builds without
+/// one only point users at the release's download page.
+pub const UPDATER_PUBLIC_KEY: Option<&str> = option_env!("CROSSSAVE_UPDATER_PUBKEY");
+/// Give startup a moment before the first check
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum UpdateError {
+    #[error("no release metadata source configured")]
+    NoSource,
+    #[error("no update signing key configured")]
+    NoPublicKey,
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("invalid release metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("release metadata signature check failed: {0}")]
+    BadSignature(String),
+    #[error("settings error: {0}")]
+    Settings(String),
+    #[error("this build can't install the release itself")]
+    NotInstallable,
+    #[error("no newer release to install")]
+    UpToDate,
+    #[error("installing the update failed: {0}")]
+    Install(String),
+}
+
+/// What the metadata endpoint serves. `release` is kept as the exact string
+/// that was signed, so no re-serialization can change the signed bytes.
+#[derive(Debug, Deserialize)]
+struct SignedRelease {
+    release: String,
+    /// Hex ed25519 signature over `release`
+    signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseInfo {
+    version: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    pub_date: Option<String>,
+    /// Download page or installer for the release
+    url: String,
+    /// Share of devices, 0-100, offered the release so far
+    #[serde(default = "full_rollout")]
+    rollout_percent: u8,
+    /// Tauri updater endpoint serving the release's installers and their
+    /// signatures
+    #[serde(default)]
+    updater_url: Option<String>,
+}
+
+fn full_rollout() -> u8 {
+    100
+}
+
+/// Payload of `update://available`. When `installable`, `install_update`
+/// installs the release through Tauri's updater; otherwise users get it
+/// from the download page at `url`.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub version: String,
+    pub notes: String,
+    pub pub_date: Option<String>,
+    pub url: String,
+    pub installable: bool,
+    #[serde(skip)]
+    updater_url: Option<String>,
+}
+
+/// Fetch and verify the release metadata, returning the release when it is
+/// newer than `current_version` and this device is inside its rollout
+pub async fn check_for_update(
+    settings: &SettingsManager,
+    current_version: &str,
+) -> Result<Option<UpdateInfo>, UpdateError> {
+    let app_settings = settings
+        .get_settings()
+        .map_err(|err| UpdateError::Settings(err.to_string()))?;
+    let url = metadata_url(&app_settings).ok_or(UpdateError::NoSource)?;
+    let public_key = BUILT_IN_PUBLIC_KEY
+        .filter(|key| !key.trim().is_empty())
+        .ok_or(UpdateError::NoPublicKey)?;
+
+    let timeout = Duration::from_secs(app_settings.cloud.timeout_seconds.max(1));
+    let client = Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|err| UpdateError::Network(err.to_string()))?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|err| UpdateError::Network(err.to_string()))?;
+    if !response.status().is_success() {
+        return Err(UpdateError::Network(format!(
+            "metadata request returned {}",
+            response.status()
+        )));
+    }
+    let signed: SignedRelease = response
+        .json()
+        .await
+        .map_err(|err| UpdateError::InvalidMetadata(err.to_string()))?;
+
+    verify_signature(public_key, &signed)?;
+    let release: ReleaseInfo = serde_json::from_str(&signed.release)
+        .map_err(|err| UpdateError::InvalidMetadata(err.to_string()))?;
+
+    if compare_versions(&release.version, current_version) != CmpOrdering::Greater {
+        debug!("[UPDATE] {current_version} is up to date");
+        return Ok(None);
+    }
+    if rollout_bucket(&app_settings.cloud.device_id) >= release.rollout_percent {
+        debug!(
+            "[UPDATE] {} is rolling out to {}% of devices; not offered here yet",
+            release.version, release.rollout_percent
+        );
+        return Ok(None);
+    }
+
+    let updater_url = release
+        .updater_url
+        .filter(|_| cfg!(desktop) && UPDATER_PUBLIC_KEY.is_some());
+    Ok(Some(UpdateInfo {
+        current_version: current_version.to_string(),
+        version: release.version,
+        notes: release.notes,
+        pub_date: release.pub_date,
+        url: release.url,
+        installable: updater_url.is_some(),
+        updater_url,
+    }))
+}
+
+/// Download and install the offered release with Tauri's updater, which
+/// checks the installer against `UPDATER_PUBLIC_KEY`. Returns the installed
+/// version; the app has to restart to run it.
+#[cfg(desktop)]
+pub async fn install_update(
+    app: &AppHandle,
+    settings: &SettingsManager,
+) -> Result<String, UpdateError> {
+    use tauri_plugin_updater::UpdaterExt;
+
+    let current_version = app.package_info().version.to_string();
+    let update = check_for_update(settings, &current_version)
+        .await?
+        .ok_or(UpdateError::UpToDate)?;
+    let endpoint = update.updater_url.ok_or(UpdateError::NotInstallable)?;
+    let endpoint = tauri::Url::parse(&endpoint)
+        .map_err(|err| UpdateError::InvalidMetadata(err.to_string()))?;
+
+    let install_error = |err: tauri_plugin_updater::Error| UpdateError::Install(err.to_string());
+    let pending = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(install_error)?
+        .build()
+        .map_err(install_error)?
+        .check()
+        .await
+        .map_err(install_error)?
+        .ok_or(UpdateError::UpToDate)?;
+    info!("[UPDATE] Installing version {}", pending.version);
+    pending
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(install_error)?;
+    Ok(pending.version.clone())
+}
+
+/// Mobile releases come from the app stores
+#[cfg(mobile)]
+pub async fn install_update(
+    _app: &AppHandle,
+    _settings: &SettingsManager,
+) -> Result<String, UpdateError> {
+    Err(UpdateError::NotInstallable)
+}
+
+/// Check on startup and then every `check_interval_hours`, emitting
+/// `update://available` once per new release
+pub fn start_update_checks(app: AppHandle, settings: Arc<SettingsManager>) {
+    let current_version = app.package_info().version.to_string();
+    tauri::async_runtime::spawn(async move {
+        let mut announced: Option<String> = None;
+        sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            let updates = settings
+                .get_settings()
+                .map(|s| s.updates)
+                .unwrap_or_default();
+
+            if updates.auto_check {
+                match check_for_update(&settings, &current_version).await {
+                    Ok(Some(update)) => {
+                        if announced.as_deref() != Some(update.version.as_str()) {
+                            info!("[UPDATE] Version {} is available", update.version);
+                            announced = Some(update.version.clone());
+                            let _ = app.emit("update://available", update);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(UpdateError::NoSource) | Err(UpdateError::NoPublicKey) => {
+                        debug!("[UPDATE] Update checks not configured");
+                    }
+                    Err(err) => warn!("[UPDATE] Update check failed: {err}"),
+                }
+            }
+
+            let interval = updates.check_interval_hours.max(1).saturating_mul(60 * 60);
+            sleep(Duration::from_secs(interval)).await;
+        }
+    });
+}
+
+/// The configured URL, or `/client/release` on the active cloud server
+fn metadata_url(settings: &AppSettings) -> Option<String> {
+    if let Some(url) = settings.updates.metadata_url.as_deref() {
+        return Some(url.trim().to_string()).filter(|url| !url.is_empty());
+    }
+    let base_url = match settings.cloud_mode {
+        CloudMode::Official => &settings.cloud.base_url,
+        CloudMode::SelfHost => &settings.self_host.api_server,
+        CloudMode::Off | CloudMode::Demo => return None,
+    };
+    let base_url = base_url.trim().trim_end_matches('/');
+    (!base_url.is_empty()).then(|| format!("{base_url}/client/release"))
+}
+
+fn verify_signature(public_key: &str, signed: &SignedRelease) -> Result<(), UpdateError> {
+    let key_bytes: [u8; 32] = decode_hex(public_key)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| UpdateError::BadSignature("public key is not 32 hex bytes".into()))?;
+    let key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|err| UpdateError::BadSignature(err.to_string()))?;
+    let signature = decode_hex(&signed.signature)
+        .ok_or_else(|| UpdateError::BadSignature("signature is not hex".into()))
+        .and_then(|bytes| {
+            Signature::from_slice(&bytes).map_err(|err| UpdateError::BadSignature(err.to_string()))
+        })?;
+    key.verify(signed.release.as_bytes(), &signature)
+        .map_err(|err| UpdateError::BadSignature(err.to_string()))
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    value
+        .trim()
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            if pair.len() != 2 {
+                return None;
+            }
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+/// Stable 0-99 bucket for the device, so a rollout reaches the same devices
+/// on every check
+fn rollout_bucket(device_id: &str) -> u8 {
+    let digest = Sha256::digest(device_id.as_bytes());
+    (u16::from_be_bytes([digest[0], digest[1]]) % 100) as u8
+}
+
+/// Compare dotted numeric versions, ignoring a leading `v` and any
+/// pre-release or build suffix
+fn compare_versions(left: &str, right: &str) -> CmpOrdering {
+    fn parts(version: &str) -> Vec<u64> {
+        version
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+
+    let (left, right) = (parts(left), parts(right));
+    let len = left.len().max(right.len());
+    (0..len)
+        .map(|index| {
+            let a = left.get(index).copied().unwrap_or(0);
+            let b = right.get(index).copied().unwrap_or(0);
+            a.cmp(&b)
+        })
+        .find(|ordering| *ordering != CmpOrdering::Equal)
+        .unwrap_or(CmpOrdering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn signed(key: &SigningKey, release: &str) -> SignedRelease {
+        SignedRelease {
+            release: release.to_string(),
+            signature: hex(&key.sign(release.as_bytes()).to_bytes()),
+        }
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(compare_versions("1.10.0", "1.9.3"), CmpOrdering::Greater);
+        assert_eq!(compare_versions("v2.0", "2.0.0"), CmpOrdering::Equal);
+        assert_eq!(
+            compare_versions("1.2.0-beta.1", "1.2.0"),
+            CmpOrdering::Equal
+        );
+        assert_eq!(compare_versions("0.9", "0.10"), CmpOrdering::Less);
+    }
+
+    #[test]
+    fn only_the_signed_release_verifies() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = hex(key.verifying_key().as_bytes());
+        let release = r#"{"version":"1.2.0","url":"https://example.com"}"#;
+
+        assert!(verify_signature(&public_key, &signed(&key, release)).is_ok());
+
+        let mut tampered = signed(&key, release);
+        tampered.release = release.replace("1.2.0", "9.9.9");
+        assert!(matches!(
+            verify_signature(&public_key, &tampered),
+            Err(UpdateError::BadSignature(_))
+        ));
+
+        let other = SigningKey::from_bytes(&[8; 32]);
+        assert!(verify_signature(&public_key, &signed(&other, release)).is_err());
+        assert!(verify_signature("not hex", &signed(&key, release)).is_err());
+    }
+
+    #[test]
+    fn rollout_buckets_are_stable_and_in_range() {
+        assert_eq!(rollout_bucket("device-a"), rollout_bucket("device-a"));
+        let buckets: Vec<u8> = (0..500)
+            .map(|index| rollout_bucket(&format!("device-{index}")))
+            .collect();
+        assert!(buckets.iter().all(|bucket| *bucket < 100));
+        assert!(buckets.iter().any(|bucket| *bucket < 50));
+        assert!(buckets.iter().any(|bucket| *bucket >= 50));
+    }
+}
@@ -1,25 +1,53 @@
-use std::{collections::HashMap, path::PathBuf, pin::Pin, sync::Mutex, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
+use glob::Pattern;
 use notify::{
-    Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher,
+    event::{CreateKind, ModifyKind, RemoveKind},
+    Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode,
+    Result as NotifyResult, Watcher,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::select;
 use tokio::time::{sleep, Instant, Sleep};
 use tracing::{debug, error, info, warn};
 
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::core::file_provider::{content_uri, is_content_uri, list_content};
+use crate::core::paths::{extended, serde_path, serde_paths, simplified};
+use crate::core::storage::is_network_filesystem;
+use crate::core::sync::SyncManager;
 
 const DEFAULT_DEBOUNCE_MS: u64 = 200;
-const WATCHER_EVENT_NAME: &str = "watcher://fs-event";
+const WATCHER_EVENT_NAME: &str = "watcher://fs-batch";
+const STARTED_EVENT_NAME: &str = "watcher://started";
+/// How often paths on network shares are rescanned unless the session says
+const DEFAULT_POLL_INTERVAL_MS: u64 = 5_000;
+/// A window with at least this many changed paths is reported as a burst
+const BURST_EVENT_THRESHOLD: usize = 16;
+/// Bursts double the debounce up to this multiple of the session's base
+const MAX_DEBOUNCE_MULTIPLIER: u32 = 8;
+/// Temp and lock files most emulators and editors write next to saves
+const DEFAULT_IGNORE_GLOBS: &[&str] = &["*.tmp", "*.temp", "*.swp", "*~", "*.lock", "*/.#*"];
+/// How long after a restore its own writes are still ignored
+const RESTORE_SUPPRESS_WINDOW: Duration = Duration::from_secs(3);
+/// How often `content://` trees are listed for changes
+const CONTENT_POLL_INTERVAL: Duration = Duration::from_secs(15);
 
 #[derive(Debug, Error)]
 pub enum WatcherError {
-    #[error("watcher already running")]
-    AlreadyRunning,
-    #[error("no watcher is currently running")]
-    NotRunning,
+    #[error("watcher session {0} already running")]
+    AlreadyRunning(String),
+    #[error("no watcher session {0} is running")]
+    NotRunning(String),
     #[error("failed to acquire watcher lock: {0}")]
     Lock(String),
     #[error("failed to create watcher: {0}")]
@@ -31,9 +59,14 @@ pub enum WatcherError {
 #[derive(Debug)]
 struct WatcherInstance {
     #[allow(dead_code)]
-    watcher: RecommendedWatcher,
+    watcher: Option<RecommendedWatcher>,
+    /// Rescans paths on network shares, where OS notifications get lost
+    #[allow(dead_code)]
+    poll_watcher: Option<PollWatcher>,
     stop_tx: async_channel::Sender<()>,
     task_handle: tauri::async_runtime::JoinHandle<()>,
+    /// Polls `content://` trees, which the OS watcher can't see
+    poll_handle: Option<tauri::async_runtime::JoinHandle<()>>,
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -45,17 +78,174 @@ pub enum WatchEventType {
 
 #[derive(Clone, Debug, Serialize)]
 pub struct WatchEventPayload {
+    #[serde(serialize_with = "serde_path::serialize")]
     pub path: PathBuf,
     pub event_type: WatchEventType,
 }
 
+/// How a session notices changes
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchBackend {
+    /// OS change notifications
+    Native,
+    /// Rescanning every path on an interval
+    Poll,
+    /// Notifications for local paths, rescans for the rest
+    Mixed,
+}
+
+/// Emitted as `watcher://started` once a session is running
+#[derive(Clone, Debug, Serialize)]
+pub struct WatcherStartedPayload {
+    pub session_id: String,
+    pub backend: WatchBackend,
+    /// Paths rescanned instead of watched, such as network shares and
+    /// `content://` trees
+    #[serde(serialize_with = "serde_paths::serialize")]
+    pub polled_paths: Vec<PathBuf>,
+    /// Rescan interval of the network share paths
+    pub poll_interval_ms: u64,
+}
+
+/// All changes from one debounce window, emitted as `watcher://fs-batch`.
+/// `burst` means the emulator is still writing a lot of files; wait for a
+/// batch without it before packaging. A burst is always followed by such a
+/// batch, which may have no events once writes have stopped.
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchBatchPayload {
+    pub session_id: String,
+    pub events: Vec<WatchEventPayload>,
+    pub burst: bool,
+    /// Debounce applied to the next window
+    pub debounce_ms: u64,
+}
+
+/// Debounce that backs off while events keep arriving in bursts
+struct AdaptiveDebounce {
+    base: Duration,
+    current: Duration,
+}
+
+impl AdaptiveDebounce {
+    fn new(base: Duration) -> Self {
+        Self {
+            base,
+            current: base,
+        }
+    }
+
+    fn in_burst(&self) -> bool {
+        self.current > self.base
+    }
+
+    /// Update the interval after a window with `count` changes and report
+    /// whether it was a burst
+    fn record_batch(&mut self, count: usize) -> bool {
+        let burst = count >= BURST_EVENT_THRESHOLD;
+        self.current = if burst {
+            (self.current * 2).min(self.base * MAX_DEBOUNCE_MULTIPLIER)
+        } else {
+            self.base
+        };
+        burst
+    }
+}
+
+/// Per-session settings passed to `start_watcher`
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct WatchOptions {
+    /// Session name; a random id is generated when omitted
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Defaults to `DEFAULT_DEBOUNCE_MS`
+    #[serde(default)]
+    pub debounce_ms: Option<u64>,
+    /// Globs matched against the full path; events for matching paths are dropped
+    #[serde(default)]
+    pub ignore_globs: Vec<String>,
+    /// When non-empty, only files with one of these extensions are reported
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Skip the built-in temp file globs
+    #[serde(default)]
+    pub disable_default_ignores: bool,
+    /// Rescan interval for paths on network shares; defaults to
+    /// `DEFAULT_POLL_INTERVAL_MS`
+    #[serde(default)]
+    pub poll_interval_ms: Option<u64>,
+    /// Poll every path, for shares the network check doesn't recognise
+    #[serde(default)]
+    pub force_polling: bool,
+}
+
+#[derive(Debug)]
+struct EventFilter {
+    ignore: Vec<Pattern>,
+    extensions: HashSet<String>,
+}
+
+impl EventFilter {
+    fn new(options: WatchOptions) -> Self {
+        let mut globs: Vec<String> = Vec::new();
+        if !options.disable_default_ignores {
+            globs.extend(DEFAULT_IGNORE_GLOBS.iter().map(|glob| glob.to_string()));
+        }
+        globs.extend(options.ignore_globs);
+
+        let mut ignore = Vec::new();
+        for glob in globs.iter().filter(|glob| !glob.trim().is_empty()) {
+            match Pattern::new(glob) {
+                Ok(pattern) => ignore.push(pattern),
+                Err(err) => warn!("[WATCHER] Ignoring invalid glob {glob}: {err}"),
+            }
+        }
+
+        let extensions = options
+            .extensions
+            .iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+
+        Self { ignore, extensions }
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        if self.ignore.iter().any(|pattern| pattern.matches_path(path)) {
+            return false;
+        }
+
+        if self.extensions.is_empty() || path.is_dir() {
+            return true;
+        }
+
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| self.extensions.contains(&ext.to_ascii_lowercase()))
+            .unwrap_or(false)
+    }
+}
+
+/// Directories CrossSave is writing to itself, mapped to when the
+/// suppression expires
+type SuppressedPaths = Arc<Mutex<HashMap<PathBuf, Instant>>>;
+
+/// Running watch sessions keyed by session id. Each session has its own
+/// paths, filters and debounce, e.g. one per emulator.
 #[derive(Default)]
 pub struct WatcherManager {
-    inner: Mutex<Option<WatcherInstance>>,
+    sessions: Mutex<HashMap<String, WatcherInstance>>,
+    suppressed: SuppressedPaths,
 }
 
 impl WatcherManager {
-    pub fn start(&self, app: AppHandle, paths: Vec<PathBuf>) -> Result<(), WatcherError> {
+    pub fn start(
+        &self,
+        app: AppHandle,
+        paths: Vec<PathBuf>,
+        options: WatchOptions,
+    ) -> Result<String, WatcherError> {
         if paths.is_empty() {
             return Err(WatcherError::WatchPath(
                 "<empty>".into(),
@@ -63,15 +253,29 @@ impl WatcherManager {
             ));
         }
 
+        let session_id = options
+            .session_id
+            .clone()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
         let mut guard = self
-            .inner
+            .sessions
             .lock()
             .map_err(|err| WatcherError::Lock(err.to_string()))?;
-        if guard.is_some() {
-            warn!("[WATCHER] Attempted to start watcher while already running");
-            return Err(WatcherError::AlreadyRunning);
+        if guard.contains_key(&session_id) {
+            warn!("[WATCHER] Attempted to start session {session_id} while already running");
+            return Err(WatcherError::AlreadyRunning(session_id));
         }
 
+        let (content_paths, paths): (Vec<PathBuf>, Vec<PathBuf>) =
+            paths.into_iter().partition(|path| is_content_uri(path));
+        let content_uris: Vec<String> = content_paths
+            .iter()
+            .filter_map(|path| content_uri(path).map(str::to_string))
+            .collect();
+
         let filtered_paths: Vec<PathBuf> = paths
             .into_iter()
             .filter(|path| {
@@ -84,96 +288,214 @@ impl WatcherManager {
             })
             .collect();
 
-        if filtered_paths.is_empty() {
+        if filtered_paths.is_empty() && content_uris.is_empty() {
             return Err(WatcherError::WatchPath(
                 "<empty>".into(),
                 "no valid paths".into(),
             ));
         }
 
+        let debounce = Duration::from_millis(options.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS));
+        let poll_interval = Duration::from_millis(
+            options
+                .poll_interval_ms
+                .unwrap_or(DEFAULT_POLL_INTERVAL_MS)
+                .max(1),
+        );
+        // notify's OS backends miss changes made through SMB and NFS
+        // mounts, so paths on those are rescanned instead
+        let (polled_paths, native_paths): (Vec<PathBuf>, Vec<PathBuf>) = filtered_paths
+            .into_iter()
+            .partition(|path| options.force_polling || is_network_filesystem(path));
         info!(
-            "[WATCHER] Starting watcher for {} paths",
-            filtered_paths.len()
+            "[WATCHER] Starting session {session_id} for {} paths ({} polled)",
+            native_paths.len() + polled_paths.len() + content_uris.len(),
+            polled_paths.len()
         );
 
         let (event_tx, event_rx) = async_channel::unbounded::<NotifyResult<Event>>();
         let (stop_tx, stop_rx) = async_channel::bounded::<()>(1);
-        let mut watcher = RecommendedWatcher::new(
-            move |res| {
-                if let Err(err) = event_tx.try_send(res) {
-                    warn!("[WATCHER] Dropped event due to channel error: {err}");
-                }
-            },
-            Config::default().with_poll_interval(Duration::from_millis(DEFAULT_DEBOUNCE_MS)),
-        )
-        .map_err(|err| WatcherError::Create(err.to_string()))?;
-
-        for path in &filtered_paths {
-            watcher
-                .watch(path, RecursiveMode::Recursive)
-                .map_err(|err| {
-                    WatcherError::WatchPath(path.display().to_string(), err.to_string())
-                })?;
-            debug!("[WATCHER] Watching path: {:?}", path);
-        }
+        let poll_handle = (!content_uris.is_empty())
+            .then(|| spawn_content_poller(content_uris.clone(), event_tx.clone()));
+        let watcher = if native_paths.is_empty() {
+            None
+        } else {
+            let mut watcher = RecommendedWatcher::new(
+                event_handler(event_tx.clone()),
+                Config::default().with_poll_interval(debounce),
+            )
+            .map_err(|err| WatcherError::Create(err.to_string()))?;
+            watch_paths(&mut watcher, &native_paths)?;
+            Some(watcher)
+        };
+        let poll_watcher = if polled_paths.is_empty() {
+            None
+        } else {
+            let mut watcher = PollWatcher::new(
+                event_handler(event_tx),
+                Config::default().with_poll_interval(poll_interval),
+            )
+            .map_err(|err| WatcherError::Create(err.to_string()))?;
+            watch_paths(&mut watcher, &polled_paths)?;
+            Some(watcher)
+        };
 
         let handle = spawn_processor(
             app.clone(),
+            session_id.clone(),
             stop_rx,
             event_rx,
-            Duration::from_millis(DEFAULT_DEBOUNCE_MS),
+            debounce,
+            EventFilter::new(options),
+            self.suppressed.clone(),
         );
 
-        *guard = Some(WatcherInstance {
-            watcher,
-            stop_tx,
-            task_handle: handle,
-        });
+        guard.insert(
+            session_id.clone(),
+            WatcherInstance {
+                watcher,
+                poll_watcher,
+                stop_tx,
+                task_handle: handle,
+                poll_handle,
+            },
+        );
+        drop(guard);
+
+        let backend = match (
+            native_paths.is_empty(),
+            polled_paths.is_empty() && content_uris.is_empty(),
+        ) {
+            (false, true) => WatchBackend::Native,
+            (true, _) => WatchBackend::Poll,
+            (false, false) => WatchBackend::Mixed,
+        };
+        if backend != WatchBackend::Native {
+            info!("[WATCHER] Session {session_id} polls {:?}", polled_paths);
+        }
+        let payload = WatcherStartedPayload {
+            session_id: session_id.clone(),
+            backend,
+            polled_paths: polled_paths
+                .into_iter()
+                .chain(content_uris.into_iter().map(PathBuf::from))
+                .collect(),
+            poll_interval_ms: poll_interval.as_millis() as u64,
+        };
+        if let Err(err) = app.emit(STARTED_EVENT_NAME, &payload) {
+            error!("[WATCHER] Failed to emit session start: {err}");
+        }
 
-        Ok(())
+        Ok(session_id)
     }
 
-    pub async fn stop(&self) -> Result<(), WatcherError> {
+    pub async fn stop(&self, session_id: &str) -> Result<(), WatcherError> {
         let instance = {
             let mut guard = self
-                .inner
+                .sessions
                 .lock()
                 .map_err(|err| WatcherError::Lock(err.to_string()))?;
-            let Some(instance) = guard.take() else {
-                warn!("[WATCHER] Attempted to stop watcher but none running");
-                return Err(WatcherError::NotRunning);
+            let Some(instance) = guard.remove(session_id) else {
+                warn!("[WATCHER] Attempted to stop session {session_id} but it is not running");
+                return Err(WatcherError::NotRunning(session_id.to_string()));
             };
             instance
         };
 
-        info!("[WATCHER] Stopping watcher");
+        info!("[WATCHER] Stopping session {session_id}");
         let _ = instance.stop_tx.send(()).await;
         instance.task_handle.abort();
+        if let Some(poll_handle) = instance.poll_handle {
+            poll_handle.abort();
+        }
         Ok(())
     }
+
+    pub fn session_ids(&self) -> Vec<String> {
+        match self.sessions.lock() {
+            Ok(guard) => {
+                let mut ids: Vec<String> = guard.keys().cloned().collect();
+                ids.sort();
+                ids
+            }
+            Err(err) => {
+                warn!("[WATCHER] Failed to list sessions: {err}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Ignore events under `path` while CrossSave restores files into it.
+    /// Call again once the restore finishes to cover late events.
+    pub fn suppress_path(&self, path: &Path) {
+        match self.suppressed.lock() {
+            Ok(mut guard) => {
+                let now = Instant::now();
+                guard.retain(|_, until| *until > now);
+                guard.insert(simplified(path), now + RESTORE_SUPPRESS_WINDOW);
+                debug!("[WATCHER] Suppressing events under {:?}", path);
+            }
+            Err(err) => warn!("[WATCHER] Failed to suppress {:?}: {err}", path),
+        }
+    }
+}
+
+/// Forward notify's callbacks into the session's event channel
+fn event_handler(
+    event_tx: async_channel::Sender<NotifyResult<Event>>,
+) -> impl Fn(NotifyResult<Event>) + Send + 'static {
+    move |res| {
+        if let Err(err) = event_tx.try_send(res) {
+            warn!("[WATCHER] Dropped event due to channel error: {err}");
+        }
+    }
+}
+
+fn watch_paths(watcher: &mut impl Watcher, paths: &[PathBuf]) -> Result<(), WatcherError> {
+    for path in paths {
+        watcher
+            .watch(&extended(path), RecursiveMode::Recursive)
+            .map_err(|err| WatcherError::WatchPath(path.display().to_string(), err.to_string()))?;
+        debug!("[WATCHER] Watching path: {:?}", path);
+    }
+    Ok(())
+}
+
+fn is_suppressed(suppressed: &SuppressedPaths, path: &Path) -> bool {
+    let Ok(guard) = suppressed.lock() else {
+        return false;
+    };
+    let now = Instant::now();
+    guard
+        .iter()
+        .any(|(root, until)| *until > now && path.starts_with(root))
 }
 
 fn spawn_processor(
     app: AppHandle,
+    session_id: String,
     stop_rx: async_channel::Receiver<()>,
     event_rx: async_channel::Receiver<NotifyResult<Event>>,
     debounce: Duration,
+    filter: EventFilter,
+    suppressed: SuppressedPaths,
 ) -> tauri::async_runtime::JoinHandle<()> {
     tauri::async_runtime::spawn(async move {
         let mut pending_events: HashMap<PathBuf, WatchEventType> = HashMap::new();
-        let mut debounce_timer: Pin<Box<Sleep>> = Box::pin(sleep(debounce));
+        let mut debounce = AdaptiveDebounce::new(debounce);
+        let mut debounce_timer: Pin<Box<Sleep>> = Box::pin(sleep(debounce.current));
 
         loop {
             select! {
                 _ = stop_rx.recv() => {
-                    debug!("[WATCHER] Received stop signal");
+                    debug!("[WATCHER] Session {session_id} received stop signal");
                     break;
                 }
                 maybe_event = event_rx.recv() => {
                     match maybe_event {
                         Ok(Ok(event)) => {
-                            if register_event(&mut pending_events, &event) {
-                                debounce_timer.as_mut().reset(Instant::now() + debounce);
+                            if register_event(&mut pending_events, &event, &filter, &suppressed) {
+                                debounce_timer.as_mut().reset(Instant::now() + debounce.current);
                             }
                         }
                         Ok(Err(err)) => error!("[WATCHER] Error from watcher: {err}"),
@@ -181,18 +503,87 @@ fn spawn_processor(
                     }
                 }
                 _ = &mut debounce_timer => {
-                    flush_events(&app, &mut pending_events).await;
-                    debounce_timer.as_mut().reset(Instant::now() + debounce);
+                    flush_events(&app, &session_id, &mut pending_events, &mut debounce).await;
+                    debounce_timer.as_mut().reset(Instant::now() + debounce.current);
                 }
             }
         }
 
-        flush_events(&app, &mut pending_events).await;
-        info!("[WATCHER] Watcher processor stopped");
+        flush_events(&app, &session_id, &mut pending_events, &mut debounce).await;
+        info!("[WATCHER] Session {session_id} processor stopped");
+    })
+}
+
+/// List each tree every `CONTENT_POLL_INTERVAL` and feed what changed
+/// since the last listing into the session as if the OS had reported it
+fn spawn_content_poller(
+    uris: Vec<String>,
+    event_tx: async_channel::Sender<NotifyResult<Event>>,
+) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        let mut previous: HashMap<String, HashMap<String, (u64, u64)>> = HashMap::new();
+        while !event_tx.is_closed() {
+            for uri in &uris {
+                let listing = {
+                    let uri = uri.clone();
+                    tauri::async_runtime::spawn_blocking(move || list_content(&uri)).await
+                };
+                let files = match listing {
+                    Ok(Ok(files)) => files,
+                    Ok(Err(err)) => {
+                        warn!("[WATCHER] Failed to list {uri}: {err}");
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!("[WATCHER] Listing {uri} failed: {err}");
+                        continue;
+                    }
+                };
+                let current: HashMap<String, (u64, u64)> = files
+                    .into_iter()
+                    .map(|file| (file.relative_path, (file.size, file.modified_ms)))
+                    .collect();
+
+                // The first listing is the baseline
+                if let Some(before) = previous.get(uri) {
+                    let root = Path::new(uri);
+                    let mut changes: Vec<(EventKind, PathBuf)> = Vec::new();
+                    for (name, state) in &current {
+                        match before.get(name) {
+                            None => {
+                                changes.push((EventKind::Create(CreateKind::File), root.join(name)))
+                            }
+                            Some(old) if old != state => {
+                                changes.push((EventKind::Modify(ModifyKind::Any), root.join(name)))
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                    for name in before.keys().filter(|name| !current.contains_key(*name)) {
+                        changes.push((EventKind::Remove(RemoveKind::File), root.join(name)));
+                    }
+                    for (kind, path) in changes {
+                        if event_tx
+                            .try_send(Ok(Event::new(kind).add_path(path)))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+                previous.insert(uri.clone(), current);
+            }
+            sleep(CONTENT_POLL_INTERVAL).await;
+        }
     })
 }
 
-fn register_event(pending: &mut HashMap<PathBuf, WatchEventType>, event: &Event) -> bool {
+fn register_event(
+    pending: &mut HashMap<PathBuf, WatchEventType>,
+    event: &Event,
+    filter: &EventFilter,
+    suppressed: &SuppressedPaths,
+) -> bool {
     let event_type = match map_event_kind(&event.kind) {
         Some(kind) => kind,
         None => return false,
@@ -200,15 +591,31 @@ fn register_event(pending: &mut HashMap<PathBuf, WatchEventType>, event: &Event)
 
     let mut registered = false;
     for path in &event.paths {
-        pending.insert(path.clone(), event_type.clone());
+        // Watched roots are extended; report paths the way profiles hold them
+        let path = simplified(path);
+        if !filter.allows(&path) {
+            continue;
+        }
+        if is_suppressed(suppressed, &path) {
+            debug!("[WATCHER] Ignoring restore write to {:?}", path);
+            continue;
+        }
+        pending.insert(path, event_type.clone());
         registered = true;
     }
 
     registered
 }
 
-async fn flush_events(app: &AppHandle, pending: &mut HashMap<PathBuf, WatchEventType>) {
-    if pending.is_empty() {
+async fn flush_events(
+    app: &AppHandle,
+    session_id: &str,
+    pending: &mut HashMap<PathBuf, WatchEventType>,
+    debounce: &mut AdaptiveDebounce,
+) {
+    // A quiet window right after a burst still gets an (empty) batch so
+    // listeners know the writes have settled
+    if pending.is_empty() && !debounce.in_burst() {
         return;
     }
 
@@ -216,16 +623,26 @@ async fn flush_events(app: &AppHandle, pending: &mut HashMap<PathBuf, WatchEvent
         .drain()
         .map(|(path, event_type)| WatchEventPayload { path, event_type })
         .collect();
-
-    for event in events {
-        debug!(
-            "[WATCHER] Emitting {:?} for {:?}",
-            event.event_type, event.path
-        );
-        if let Err(err) = app.emit(WATCHER_EVENT_NAME, &event) {
-            error!("[WATCHER] Failed to emit event: {err}");
+    let burst = debounce.record_batch(events.len());
+    if !events.is_empty() {
+        if let Some(sync) = app.try_state::<SyncManager>() {
+            sync.note_activity();
         }
     }
+
+    debug!(
+        "[WATCHER] Emitting {} changes for session {session_id} (burst: {burst})",
+        events.len()
+    );
+    let payload = WatchBatchPayload {
+        session_id: session_id.to_string(),
+        events,
+        burst,
+        debounce_ms: debounce.current.as_millis() as u64,
+    };
+    if let Err(err) = app.emit(WATCHER_EVENT_NAME, &payload) {
+        error!("[WATCHER] Failed to emit event batch: {err}");
+    }
 }
 
 fn map_event_kind(kind: &EventKind) -> Option<WatchEventType> {
@@ -1,31 +1,80 @@
 mod api;
+mod cli;
 mod core;
 
+use api::account_api::{get_account_switch, resolve_account_switch};
 use api::cloud_api::{
-    download_cloud_save, download_cloud_version, get_cloud_config, get_cloud_status,
+    complete_password_reset, download_cloud_file, download_cloud_save, download_cloud_version,
+    get_account_activity, get_cloud_config,
+    get_cloud_status,
     get_conflict_details, get_upload_url, list_all_cloud_games, list_cloud_devices,
-    list_cloud_versions, login_cloud,
-    logout_cloud, notify_upload, reconnect_cloud, register_cloud_device, remove_cloud_device,
-    resolve_conflict_download, resolve_conflict_upload, signup_cloud, update_cloud_config,
+    list_cloud_endpoints, list_cloud_sessions, list_cloud_versions, list_shared_games, login_cloud,
+    logout_cloud, merge_duplicate_devices, notify_upload, reconnect_cloud, register_cloud_device, remove_cloud_device,
+    remove_cloud_endpoint, request_password_reset, resolve_conflict_download,
+    resolve_conflict_merge, revoke_cloud_session,
+    resolve_conflict_upload, save_cloud_endpoint, select_cloud_endpoint, share_game, signup_cloud,
+    unshare_game, update_cloud_config,
     update_cloud_mode, upload_cloud_save, validate_official_cloud_settings,
     validate_self_host_settings,
 };
+use api::conflict_api::{
+    accept_conflict_suggestion, delete_conflict_rule, dismiss_conflict_suggestion,
+    list_conflict_history, list_conflict_rules, list_conflict_suggestions, set_conflict_rule,
+};
+use api::convert_api::convert_save;
+use api::crash_api::{delete_crash_report, list_crash_reports, submit_crash_report};
+use api::deeplink_api::{begin_link_login, confirm_deep_link_restore, DeepLinkState};
 use api::explorer_api::{check_path_status, open_folder, scan_save_files};
-use api::history_api::{delete_history_item, get_history_item, list_games_from_history, list_history, rollback_version};
-use api::packager_api::{package_game, package_save, validate_paths};
-use api::profile_api::{delete_profile, get_profile, list_profiles, save_profile};
+use api::export_api::export_version_to_folder;
+use api::game_info_api::get_game_info;
+use api::history_api::{
+    attach_version_thumbnail, compact_history, delete_history_item, get_history_item,
+    list_all_history, list_games_from_history, list_history, pin_history_item, preview_archive,
+    rollback_version, unpin_history_item, update_history_note,
+};
+use api::logs_api::{export_diagnostics, get_recent_logs};
+use api::packager_api::{
+    create_snapshot, package_game, package_games, package_save, preview_package, validate_paths,
+};
+use api::perf_api::get_perf_report;
+use api::profile_api::{
+    delete_profile, discover_steam_profiles, export_profile, get_emulator_activity, get_profile,
+    import_profile, import_retroarch_library, install_profile_from_url, list_profiles,
+    list_suggested_profiles, save_profile,
+};
+use api::pruning_api::{
+    apply_pruning, delete_cloud_version, prune_cloud_versions, suggest_pruning,
+};
 use api::settings_api::{
-    clear_history_cache, get_app_settings, get_storage_info, update_app_settings,
+    clear_downloads_cache, clear_history_cache, get_app_settings, get_storage_info,
+    relocate_history, secrets_in_plaintext, update_app_settings, update_cloud_credentials,
+    update_retention, validate_app_settings,
+};
+use api::startup_api::get_startup_state;
+use api::sync_api::{
+    apply_reconciliation, clear_sync_queue, force_sync_now, get_device_conditions,
+    get_game_sync_status, get_reconciliation_report, get_sync_status, resume_sync,
 };
-use api::sync_api::{clear_sync_queue, force_sync_now, get_sync_status};
-use api::watcher_api::{start_watcher, stop_watcher};
+use api::update_api::{check_for_updates, install_update};
+use api::watcher_api::{list_watcher_sessions, start_watcher, stop_watcher};
+use core::backoff::CircuitBreaker;
 use core::cloud::{
     default_device_id, log_tag, CloudBackend, CloudError, DisabledCloudBackend, HttpCloudBackend,
 };
+use core::conflict::ConflictManager;
+use core::convert::ConverterRegistry;
+use core::crash::CrashReporter;
 use core::history::HistoryManager;
-use core::profile::ProfileManager;
+use core::memory_cloud::InMemoryCloudBackend;
+use core::merge::MergeRegistry;
+use core::process_watch::ProcessWatch;
+use core::pruning::DeletionConfirmations;
+use core::scheduler::SnapshotScheduler;
 use core::settings::{AppSettings, CloudMode, SettingsManager};
+use core::startup::{StartupState, Subsystem};
 use core::sync::SyncManager;
+use core::sync_state::SyncStateStore;
+use core::updater::start_update_checks;
 use core::watcher::WatcherManager;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
@@ -34,6 +83,15 @@ use tauri::Emitter;
 use tauri::Manager;
 use tokio::sync::Mutex;
 
+pub use cli::run as run_cli;
+pub use core::crash::log_tail;
+/// Fixtures and timed steps behind `get_perf_report`, shared with the benches
+pub use core::perf;
+/// The in-memory cloud and the sync code the integration tests drive with it
+pub use core::{cloud, history, memory_cloud, reconcile, sync};
+/// Archive extraction and the save and profile parsers the fuzz targets drive
+pub use core::{extract, packager::SaveMetadata, profile::ProfileManager, profile_bundle};
+
 type CloudBackendState = Arc<Mutex<Box<dyn CloudBackend + Send>>>;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -46,6 +104,21 @@ fn greet(name: &str) -> String {
 async fn select_directory(app_handle: tauri::AppHandle) -> Result<Option<String>, String> {
     use tauri_plugin_dialog::{DialogExt, FilePath};
 
+    // Plain paths to other apps' folders aren't readable; grant a SAF tree
+    #[cfg(target_os = "android")]
+    if app_handle
+        .try_state::<core::saf::SafPicker<tauri::Wry>>()
+        .is_some()
+    {
+        let app = app_handle.clone();
+        return tauri::async_runtime::spawn_blocking(move || {
+            app.state::<core::saf::SafPicker<tauri::Wry>>()
+                .pick_folder()
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+    }
+
     let result = app_handle.dialog().file().blocking_pick_folder();
 
     match result {
@@ -58,9 +131,38 @@ async fn select_directory(app_handle: tauri::AppHandle) -> Result<Option<String>
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tracing::info!("[STARTUP] Rust backend starting...");
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+    // Must come first: a second launch, as when a crosssave:// link is opened
+    // on Windows or Linux, hands its link to this instance and exits
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+        core::desktop::show_main_window(app);
+    }));
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_autostart::init(
+        tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+        Some(vec![core::desktop::AUTOSTART_ARG]),
+    ));
+    // Only builds with an installer signing key can update in place
+    #[cfg(desktop)]
+    let builder = match core::updater::UPDATER_PUBLIC_KEY {
+        Some(pubkey) => builder.plugin(tauri_plugin_updater::Builder::new().pubkey(pubkey).build()),
+        None => builder,
+    };
+    let builder = builder
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init());
+    #[cfg(target_os = "android")]
+    let builder = builder.plugin(core::saf::init());
+    #[cfg(target_os = "android")]
+    let builder = builder.plugin(core::connectivity::android::init());
+    #[cfg(target_os = "android")]
+    let builder = builder.plugin(core::secrets::android::init());
+    #[cfg(target_os = "android")]
+    let builder = builder.plugin(core::device_id::android::init());
+    builder
         .setup(|app| {
             tracing::info!("[STARTUP] Tauri setup hook running...");
             // Get app data directory (works on all platforms including Android)
@@ -69,19 +171,53 @@ pub fn run() {
                 .app_data_dir()
                 .expect("failed to get app data directory");
 
+            if let Err(err) = core::logs::log_files().open(app_data_dir.join("logs")) {
+                tracing::warn!("[LOGS] Failed to open log files: {err}");
+            }
+
             // Settings path
-            let settings_path = app_data_dir.join("config").join("settings.json");
-            let settings_manager = match SettingsManager::new(settings_path) {
+            let config_dir = app_data_dir.join("config");
+            let settings_path = config_dir.join("settings.json");
+
+            // Stable device id, derived before anything registers the device
+            #[cfg(not(target_os = "android"))]
+            let machine_id = None;
+            #[cfg(target_os = "android")]
+            let machine_id = app
+                .try_state::<core::device_id::android::DeviceIdPlugin>()
+                .and_then(|plugin| plugin.android_id());
+            core::device_id::install(&config_dir, machine_id);
+
+            let secrets = core::secrets::os_store();
+            #[cfg(target_os = "android")]
+            let secrets = secrets.or_else(|| {
+                app.try_state::<core::secrets::android::SecretsPlugin>()
+                    .map(|plugin| {
+                        Box::new(plugin.inner().clone()) as Box<dyn core::secrets::SecretStore>
+                    })
+            });
+            let settings_manager = match SettingsManager::with_secrets(settings_path, secrets) {
                 Ok(manager) => manager,
                 Err(err) => {
                     tracing::error!("[SETTINGS] Failed to load settings: {err}");
                     // Fallback to default
-                    SettingsManager::new(app_data_dir.join("config").join("settings.json"))
+                    SettingsManager::new(config_dir.join("settings.json"))
                         .expect("failed to initialize settings")
                 }
             };
 
             let settings_arc = Arc::new(settings_manager);
+            if let Err(err) = settings_arc.watch_file() {
+                tracing::warn!("[SETTINGS] Not watching settings.json for edits: {err}");
+            }
+            let mut plaintext_secrets = settings_arc.subscribe_plaintext_secrets();
+            let app_for_secrets = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while plaintext_secrets.changed().await.is_ok() {
+                    let plaintext = *plaintext_secrets.borrow_and_update();
+                    let _ = app_for_secrets.emit("settings://secrets-plaintext", plaintext);
+                }
+            });
 
             let mut current_settings = settings_arc
                 .get_settings()
@@ -94,9 +230,17 @@ pub fn run() {
                 }
             }
 
+            let crash_reporter = CrashReporter::install(
+                app_data_dir.join("crash"),
+                app.package_info().version.to_string(),
+                current_settings.crash_reports.enabled,
+            );
+            app.manage(crash_reporter.clone());
+
             // History directory
-            let history_base_dir = app_data_dir.join("archives").join("history");
-            let history_manager = HistoryManager::init(
+            let history_base_dir = current_settings.history_dir(&app_data_dir);
+            // Indexed in the background below so large histories don't block setup
+            let history_manager = HistoryManager::new_unindexed(
                 history_base_dir,
                 current_settings.retention_limit,
                 current_settings.auto_delete,
@@ -105,6 +249,16 @@ pub fn run() {
                 tracing::error!("[HISTORY] Failed to initialize history manager: {err}");
                 HistoryManager::with_defaults()
             });
+            if let Err(err) = history_manager.set_size_budget(current_settings.max_history_size_mb)
+            {
+                tracing::warn!("[HISTORY] Failed to apply history size budget: {err}");
+            }
+            if let Err(err) = history_manager.set_age_retention(
+                current_settings.age_retention.clone(),
+                current_settings.game_age_retention.clone(),
+            ) {
+                tracing::warn!("[HISTORY] Failed to apply history age limits: {err}");
+            }
 
             // Profile directories
             let (default_profiles, user_profiles) = default_profile_dirs_for_app(app);
@@ -117,7 +271,7 @@ pub fn run() {
                 }
             };
 
-            // Cloud backend initialization
+            // Cloud backend initialization: start disabled, switch in the background
             let cloud_downloads_dir = app_data_dir.join("data").join("cloud_downloads");
             if let Err(e) = std::fs::create_dir_all(&cloud_downloads_dir) {
                 tracing::warn!("[CLOUD] Failed to create cloud downloads dir: {e}");
@@ -125,87 +279,247 @@ pub fn run() {
             let cloud: Box<dyn CloudBackend + Send> = Box::new(DisabledCloudBackend);
 
             let cloud_arc: CloudBackendState = Arc::new(Mutex::new(cloud));
-
-            if let Err(err) = tauri::async_runtime::block_on(switch_cloud_backend(
-                &app.handle(),
-                &cloud_arc,
-                settings_arc.clone(),
-                current_settings.cloud_mode.clone(),
-                current_settings.clone(),
-            )) {
-                tracing::error!("[CLOUD] Failed to initialize cloud backend: {err}");
-            }
-
-            tracing::info!(
-                "{} Cloud backend initialized",
-                log_tag(&current_settings.cloud_mode)
-            );
+            let breaker = Arc::new(CircuitBreaker::default());
 
             // Register state
             let history_arc = Arc::new(history_manager);
 
             let profiles_arc = Arc::new(RwLock::new(profile_manager));
 
+            let startup = StartupState::default();
+
             app.manage(WatcherManager::default());
             app.manage(history_arc.clone());
             app.manage(profiles_arc.clone());
+            app.manage(Arc::new(ProcessWatch::new(profiles_arc.clone())));
             app.manage(settings_arc.clone());
             app.manage(cloud_arc.clone());
+            app.manage(breaker.clone());
+            app.manage(startup.clone());
+            app.manage(Arc::new(MergeRegistry::default()));
+            app.manage(Arc::new(ConverterRegistry::with_builtin()));
+            app.manage(Arc::new(DeletionConfirmations::default()));
+
+            match ConflictManager::new(app_data_dir.join("config").join("conflicts.json")) {
+                Ok(conflicts) => {
+                    app.manage(Arc::new(conflicts));
+                }
+                Err(err) => tracing::error!("[SYNC] Failed to load conflict log: {err}"),
+            }
+
+            let sync_state =
+                match SyncStateStore::new(app_data_dir.join("data").join("sync_state.json")) {
+                    Ok(sync_state) => {
+                        let sync_state = Arc::new(sync_state);
+                        app.manage(sync_state.clone());
+                        Some(sync_state)
+                    }
+                    Err(err) => {
+                        tracing::error!("[SYNC] Failed to load sync state: {err}");
+                        None
+                    }
+                };
+
+            let scheduler = match SnapshotScheduler::new(
+                app_data_dir.join("data").join("snapshot_schedule.json"),
+            ) {
+                Ok(scheduler) => Some(Arc::new(scheduler)),
+                Err(err) => {
+                    tracing::error!("[HISTORY] Failed to load snapshot schedule: {err}");
+                    None
+                }
+            };
+
+            startup.mark_ready(app.handle(), Subsystem::Settings);
+            startup.mark_ready(app.handle(), Subsystem::Profiles);
 
             // Initialize SyncManager
             let sync_manager = SyncManager::new(
                 app.handle().clone(),
-                cloud_arc,
+                cloud_arc.clone(),
                 history_arc,
                 profiles_arc,
-                settings_arc,
+                settings_arc.clone(),
+                breaker,
+                sync_state,
             );
 
             app.manage(sync_manager.clone());
 
-            // Start background tasks after all state is managed
-            // Use tauri::async_runtime to spawn in Tauri's runtime context
-            info!("[INIT] About to spawn SyncManager background task...");
+            core::notifications::install(app.handle(), settings_arc.clone());
+            install_deep_links(app);
+            #[cfg(desktop)]
+            {
+                if let Err(err) = core::tray::install(app) {
+                    tracing::warn!("[TRAY] Failed to create tray icon: {err}");
+                }
+                core::desktop::apply_autostart(app.handle(), &current_settings.desktop);
+                if core::desktop::launched_at_login() && current_settings.desktop.start_minimized {
+                    info!("[STARTUP] Launched at login; starting in the tray");
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.hide();
+                    }
+                }
+            }
+
+            // Archives a crash left between packaging and history
+            tauri::async_runtime::spawn_blocking(|| {
+                core::staging::staging().sweep_stale(core::staging::STALE_AFTER)
+            });
+
+            // Heavy initialization runs after setup returns so the window appears
+            // immediately; progress is reported through `startup://state`
+            let app_handle = app.handle().clone();
+            info!("[INIT] About to spawn background initialization...");
             tauri::async_runtime::spawn(async move {
+                let index_history = async {
+                    match sync_manager.index_local_history().await {
+                        Ok(_) => startup.mark_ready(&app_handle, Subsystem::History),
+                        Err(err) => startup.mark_failed(&app_handle, Subsystem::History, err),
+                    }
+                };
+
+                let init_cloud = async {
+                    let mode = current_settings.cloud_mode.clone();
+                    match switch_cloud_backend(
+                        &app_handle,
+                        &cloud_arc,
+                        settings_arc.clone(),
+                        mode.clone(),
+                        current_settings.clone(),
+                    )
+                    .await
+                    {
+                        Ok(()) => {
+                            tracing::info!("{} Cloud backend initialized", log_tag(&mode));
+                            startup.mark_ready(&app_handle, Subsystem::Cloud);
+                        }
+                        Err(err) => {
+                            tracing::error!("[CLOUD] Failed to initialize cloud backend: {err}");
+                            startup.mark_failed(&app_handle, Subsystem::Cloud, err.to_string());
+                        }
+                    }
+                };
+
+                futures::join!(index_history, init_cloud);
+                if let Ok(settings) = settings_arc.get_settings() {
+                    crash_reporter.submit_pending(&settings).await;
+                }
+
                 info!("[INIT] Inside async block, calling start_background_task()");
                 sync_manager.start_background_task();
+                startup.mark_ready(&app_handle, Subsystem::Sync);
+                // Started after indexing so unchanged saves are recognised
+                if let Some(scheduler) = scheduler {
+                    scheduler.start(
+                        app_handle.clone(),
+                        sync_manager.history.clone(),
+                        sync_manager.profiles.clone(),
+                    );
+                }
+                start_update_checks(app_handle.clone(), settings_arc.clone());
                 info!("[INIT] start_background_task() returned");
             });
-            info!("[INIT] Background task spawned");
+            info!("[INIT] Background initialization spawned");
 
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // With background mode on, closing only hides the window so the
+            // sync loop keeps running; the tray brings it back or quits
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                let keep_running = window
+                    .app_handle()
+                    .try_state::<Arc<SettingsManager>>()
+                    .and_then(|settings| settings.get_settings().ok())
+                    .is_some_and(|settings| settings.desktop.keep_running_on_close);
+                if keep_running {
+                    api.prevent_close();
+                    if let Err(err) = window.hide() {
+                        tracing::warn!("[STARTUP] Failed to hide window: {err}");
+                    }
+                    info!("[SYNC] Window closed; syncing in the background");
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             select_directory,
             start_watcher,
             stop_watcher,
+            list_watcher_sessions,
             list_profiles,
             get_profile,
+            get_emulator_activity,
             save_profile,
             delete_profile,
+            discover_steam_profiles,
+            import_retroarch_library,
+            list_suggested_profiles,
+            export_profile,
+            import_profile,
+            install_profile_from_url,
             package_save,
             package_game,
+            create_snapshot,
+            package_games,
+            preview_package,
             validate_paths,
             list_games_from_history,
             list_history,
+            list_all_history,
             get_history_item,
+            preview_archive,
             rollback_version,
             delete_history_item,
+            compact_history,
+            attach_version_thumbnail,
+            update_history_note,
+            pin_history_item,
+            unpin_history_item,
+            suggest_pruning,
+            apply_pruning,
+            delete_cloud_version,
+            prune_cloud_versions,
             get_app_settings,
             update_app_settings,
+            update_retention,
+            update_cloud_credentials,
+            validate_app_settings,
+            secrets_in_plaintext,
             get_storage_info,
             clear_history_cache,
+            relocate_history,
+            clear_downloads_cache,
+            get_startup_state,
             scan_save_files,
             check_path_status,
             open_folder,
+            export_version_to_folder,
+            convert_save,
+            get_game_info,
             upload_cloud_save,
             list_all_cloud_games,
             list_cloud_versions,
             download_cloud_save,
             download_cloud_version,
+            download_cloud_file,
             get_cloud_config,
             update_cloud_config,
+            list_cloud_endpoints,
+            save_cloud_endpoint,
+            remove_cloud_endpoint,
+            select_cloud_endpoint,
+            check_for_updates,
+            install_update,
+            list_crash_reports,
+            submit_crash_report,
+            delete_crash_report,
+            get_recent_logs,
+            export_diagnostics,
+            begin_link_login,
+            confirm_deep_link_restore,
+            get_perf_report,
             validate_official_cloud_settings,
             validate_self_host_settings,
             get_cloud_status,
@@ -213,6 +527,15 @@ pub fn run() {
             signup_cloud,
             logout_cloud,
             list_cloud_devices,
+            merge_duplicate_devices,
+            get_account_activity,
+            request_password_reset,
+            complete_password_reset,
+            list_cloud_sessions,
+            revoke_cloud_session,
+            share_game,
+            list_shared_games,
+            unshare_game,
             register_cloud_device,
             remove_cloud_device,
             reconnect_cloud,
@@ -220,11 +543,26 @@ pub fn run() {
             get_upload_url,
             notify_upload,
             get_sync_status,
+            get_device_conditions,
+            get_game_sync_status,
+            get_reconciliation_report,
+            apply_reconciliation,
+            get_account_switch,
+            resolve_account_switch,
             force_sync_now,
             clear_sync_queue,
+            resume_sync,
             get_conflict_details,
             resolve_conflict_upload,
             resolve_conflict_download,
+            resolve_conflict_merge,
+            list_conflict_history,
+            list_conflict_suggestions,
+            accept_conflict_suggestion,
+            dismiss_conflict_suggestion,
+            list_conflict_rules,
+            set_conflict_rule,
+            delete_conflict_rule,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -240,12 +578,20 @@ pub(crate) async fn switch_cloud_backend(
     let tag = log_tag(&mode);
     tracing::debug!("{tag} Switching backend to {:?}", mode);
 
+    // Failures against the previous backend say nothing about the new one
+    let breaker = match app.try_state::<Arc<CircuitBreaker>>() {
+        Some(state) => state.inner().clone(),
+        None => Arc::new(CircuitBreaker::default()),
+    };
+    breaker.reset();
+
     let backend: Box<dyn CloudBackend + Send> = match mode {
         CloudMode::Official => {
             tracing::info!("{tag} Preparing official cloud backend");
             Box::new(HttpCloudBackend::new(
                 settings_manager.clone(),
                 CloudMode::Official,
+                breaker.clone(),
             )?)
         }
         CloudMode::SelfHost => {
@@ -253,12 +599,20 @@ pub(crate) async fn switch_cloud_backend(
             Box::new(HttpCloudBackend::new(
                 settings_manager.clone(),
                 CloudMode::SelfHost,
+                breaker.clone(),
             )?)
         }
         CloudMode::Off => {
             tracing::info!("{tag} Disabling cloud backend");
             Box::new(DisabledCloudBackend)
         }
+        CloudMode::Demo => {
+            tracing::info!("{tag} Preparing in-memory demo cloud");
+            let device_id = Some(settings.cloud.device_id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(default_device_id);
+            Box::new(InMemoryCloudBackend::new(device_id))
+        }
     };
 
     {
@@ -275,6 +629,30 @@ pub(crate) async fn switch_cloud_backend(
     Ok(())
 }
 
+/// Route `crosssave://` links, including the one the app was launched with,
+/// to `deeplink_api`
+fn install_deep_links(app: &tauri::App) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    app.manage(DeepLinkState::default());
+    // Installed builds register the scheme on install; dev builds have to
+    // do it at runtime
+    #[cfg(all(debug_assertions, any(target_os = "linux", windows)))]
+    if let Err(err) = app.deep_link().register_all() {
+        tracing::warn!("[STARTUP] Failed to register crosssave:// links: {err}");
+    }
+
+    let handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        api::deeplink_api::handle_urls(&handle, event.urls());
+    });
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => api::deeplink_api::handle_urls(app.handle(), urls),
+        Ok(None) => {}
+        Err(err) => tracing::warn!("[STARTUP] Failed to read launch link: {err}"),
+    }
+}
+
 fn default_profile_dirs_for_app(app: &tauri::App) -> (PathBuf, PathBuf) {
     let app_data_dir = app
         .path()
@@ -5,6 +5,7 @@ fn main() {
     // Initialize tracing for logging
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::DEBUG)
+        .with_writer(|| crosssave_cloud_lib::log_tail().writer())
         .init();
 
     crosssave_cloud_lib::run()
@@ -0,0 +1,291 @@
+//! Sync decisions and reconciliation against the in-memory cloud, with two
+//! devices sharing one cloud the way two installs share an account.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crosssave_cloud_lib::cloud::{CloudBackend, CloudError, CloudVersionSummary};
+use crosssave_cloud_lib::history::HistoryManager;
+use crosssave_cloud_lib::memory_cloud::{FaultConfig, InMemoryCloudBackend};
+use crosssave_cloud_lib::perf::{create_save_tree, package_tree, FixtureSpec};
+use crosssave_cloud_lib::reconcile::{build_report, ReconcileState};
+use crosssave_cloud_lib::sync::{determine_sync_action, SyncDecision};
+use crosssave_cloud_lib::SaveMetadata;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+const GAME: &str = "demo-game";
+
+struct Workspace {
+    root: PathBuf,
+    staged: Vec<PathBuf>,
+}
+
+impl Workspace {
+    fn new() -> Self {
+        let root = std::env::temp_dir().join(format!("crosssave-memory-cloud-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("create workspace");
+        Self {
+            root,
+            staged: Vec::new(),
+        }
+    }
+
+    fn history(&self, name: &str) -> HistoryManager {
+        HistoryManager::new_unindexed(self.root.join(name), 10, false).expect("history")
+    }
+
+    /// Package a small save tree, `seed` making its contents unique
+    fn package(&mut self, game_id: &str, seed: &str, timestamp: u64) -> (SaveMetadata, PathBuf) {
+        let spec = FixtureSpec {
+            small_files: 2,
+            small_file_bytes: 1024,
+            large_files: 0,
+            ..FixtureSpec::default()
+        };
+        let tree = self.root.join(format!("tree-{seed}"));
+        create_save_tree(&tree, &spec).expect("create save tree");
+        fs::write(tree.join("seed.txt"), seed).expect("write seed");
+
+        let packaged = package_tree(&tree, game_id).expect("package save");
+        let archive = packaged.archive_path.clone();
+        self.staged.push(archive.clone());
+        let mut metadata = packaged.metadata;
+        metadata.timestamp = timestamp;
+        (metadata, archive)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        for archive in &self.staged {
+            let _ = fs::remove_file(archive);
+        }
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn summary(version_id: &str, timestamp: u64, pinned: bool) -> CloudVersionSummary {
+    CloudVersionSummary {
+        version_id: version_id.to_string(),
+        timestamp,
+        size_bytes: 4,
+        device_id: "seeded".to_string(),
+        file_list: vec!["save.srm".to_string()],
+        sha256: format!("hash-{version_id}"),
+        thumbnail_url: None,
+        note: None,
+        tags: Vec::new(),
+        pinned,
+        parent_version_id: None,
+        encrypted: false,
+    }
+}
+
+#[tokio::test]
+async fn uploaded_version_reaches_the_other_device() {
+    let mut workspace = Workspace::new();
+    let device_a = InMemoryCloudBackend::new("device-a");
+    let device_b = device_a.for_device("device-b");
+
+    let history_a = workspace.history("history-a");
+    let (metadata, archive) = workspace.package(GAME, "first", 1_000);
+    let entry = history_a
+        .save_to_history(metadata, archive)
+        .expect("save to history");
+    device_a
+        .upload_archive(entry.metadata.clone(), entry.archive_path.clone())
+        .await
+        .expect("upload");
+
+    let versions = device_b
+        .list_versions(GAME.to_string(), None)
+        .await
+        .expect("list versions");
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions[0].device_id, "device-a");
+    assert_eq!(
+        determine_sync_action(None, &[], &versions, "device-b"),
+        SyncDecision::Download(entry.metadata.version_id.clone())
+    );
+    // The uploader already has what the cloud has
+    assert_eq!(
+        determine_sync_action(Some(&entry), &[], &versions, "device-a"),
+        SyncDecision::Noop
+    );
+
+    let target = workspace.root.join("downloads").join("first.zip");
+    device_b
+        .download_version(
+            GAME.to_string(),
+            entry.metadata.version_id.clone(),
+            target.clone(),
+        )
+        .await
+        .expect("download");
+    assert_eq!(
+        fs::read(&target).expect("read download"),
+        fs::read(&entry.archive_path).expect("read archive")
+    );
+}
+
+#[tokio::test]
+async fn recorded_parents_turn_a_newer_upload_into_a_download() {
+    let mut workspace = Workspace::new();
+    let device_a = InMemoryCloudBackend::new("device-a");
+    let device_b = device_a.for_device("device-b");
+    let history_a = workspace.history("history-a");
+    let history_b = workspace.history("history-b");
+
+    let (metadata, archive) = workspace.package(GAME, "base", 1_000);
+    let base = history_a
+        .save_to_history(metadata.clone(), archive.clone())
+        .expect("save base");
+    history_b
+        .save_to_history(metadata, archive)
+        .expect("save base on b");
+    device_a
+        .upload_archive(base.metadata.clone(), base.archive_path.clone())
+        .await
+        .expect("upload base");
+
+    // Played on device A; history records the base as its parent
+    let (metadata, archive) = workspace.package(GAME, "newer", 2_000);
+    let newer = history_a
+        .save_to_history(metadata, archive)
+        .expect("save newer");
+    assert_eq!(
+        newer.metadata.parent_version_id.as_deref(),
+        Some(base.metadata.version_id.as_str())
+    );
+    device_a
+        .upload_archive(newer.metadata.clone(), newer.archive_path.clone())
+        .await
+        .expect("upload newer");
+
+    let local = history_b
+        .get_latest_version(GAME)
+        .expect("device b version");
+    let lineage = history_b.lineage(GAME, &local.metadata.version_id);
+    let versions = device_b
+        .list_versions(GAME.to_string(), None)
+        .await
+        .expect("list versions");
+    assert_eq!(
+        determine_sync_action(Some(&local), &lineage, &versions, "device-b"),
+        SyncDecision::Download(newer.metadata.version_id.clone())
+    );
+
+    let latest = device_b
+        .list_latest_versions(vec![GAME.to_string(), "missing".to_string()])
+        .await
+        .expect("latest batch");
+    assert_eq!(latest.len(), 1);
+    assert_eq!(latest[GAME].version_id, newer.metadata.version_id);
+}
+
+#[tokio::test]
+async fn reconciliation_reports_each_side() {
+    let mut workspace = Workspace::new();
+    let backend = InMemoryCloudBackend::new("device-a");
+    backend
+        .seed_version(
+            "cloud-only",
+            summary("cloud-v1", 500, false),
+            b"zip!".to_vec(),
+        )
+        .expect("seed");
+
+    let history = workspace.history("history");
+    let (metadata, archive) = workspace.package("local-only", "local", 1_000);
+    history
+        .save_to_history(metadata, archive)
+        .expect("save to history");
+
+    let cloud: Arc<Mutex<Box<dyn CloudBackend + Send>>> = Arc::new(Mutex::new(Box::new(backend)));
+    let report = build_report(&cloud, &history, "device-a")
+        .await
+        .expect("build report");
+    let state = |game_id: &str| {
+        report
+            .games
+            .iter()
+            .find(|game| game.game_id == game_id)
+            .map(|game| game.state)
+    };
+    assert_eq!(report.games.len(), 2);
+    assert_eq!(state("cloud-only"), Some(ReconcileState::CloudOnly));
+    assert_eq!(state("local-only"), Some(ReconcileState::LocalOnly));
+}
+
+#[tokio::test]
+async fn injected_failures_and_latency_apply_to_every_call() {
+    let backend = InMemoryCloudBackend::new("device-a");
+    backend.set_faults(FaultConfig {
+        latency: Duration::ZERO,
+        failure_rate: 1.0,
+    });
+    let result = backend.list_games().await;
+    assert!(matches!(result, Err(CloudError::NetworkError(_))));
+    assert!(!backend.check_connection().await.expect("check connection"));
+
+    backend.set_faults(FaultConfig {
+        latency: Duration::from_millis(20),
+        failure_rate: 0.0,
+    });
+    let started = Instant::now();
+    assert!(backend.list_games().await.expect("list games").is_empty());
+    assert!(started.elapsed() >= Duration::from_millis(20));
+    assert_eq!(backend.calls(), 3);
+}
+
+#[tokio::test]
+async fn pinned_versions_cannot_be_deleted() {
+    let backend = InMemoryCloudBackend::new("device-a");
+    backend
+        .seed_version(GAME, summary("pinned", 100, true), b"zip!".to_vec())
+        .expect("seed pinned");
+    backend
+        .seed_version(GAME, summary("loose", 200, false), b"zip!".to_vec())
+        .expect("seed unpinned");
+
+    let pinned = backend
+        .delete_version(GAME.to_string(), "pinned".to_string())
+        .await;
+    assert!(matches!(pinned, Err(CloudError::Conflict(_))));
+    backend
+        .delete_version(GAME.to_string(), "loose".to_string())
+        .await
+        .expect("delete unpinned");
+
+    let remaining = backend
+        .list_versions(GAME.to_string(), None)
+        .await
+        .expect("list versions");
+    let ids: Vec<&str> = remaining.iter().map(|v| v.version_id.as_str()).collect();
+    assert_eq!(ids, vec!["pinned"]);
+}
+
+#[tokio::test]
+async fn unpinned_versions_can_be_deleted() {
+    let backend = InMemoryCloudBackend::new("device-a");
+    backend
+        .seed_version(GAME, summary("pinned", 100, true), b"zip!".to_vec())
+        .expect("seed pinned");
+
+    backend
+        .set_version_pinned(GAME.to_string(), "pinned".to_string(), false)
+        .await
+        .expect("unpin");
+    backend
+        .delete_version(GAME.to_string(), "pinned".to_string())
+        .await
+        .expect("delete unpinned");
+
+    let missing = backend
+        .set_version_pinned(GAME.to_string(), "pinned".to_string(), true)
+        .await;
+    assert!(matches!(missing, Err(CloudError::NotFound(_))));
+}
@@ -1,19 +1,47 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type Event, type UnlistenFn } from "@tauri-apps/api/event";
+import type { CloudVersion, SelfHostSettings } from "./stores/cloudStore";
 
 export interface FsEventPayload {
+  session_id: string;
   path: string;
   kind: string;
   timestamp?: string;
 }
 
+/** One debounce window of changes from a watch session */
+export interface FsEventBatch {
+  session_id: string;
+  events: { path: string; event_type: string }[];
+  /** The emulator is still writing; wait for a batch without this flag */
+  burst: boolean;
+  debounce_ms: number;
+}
+
 export interface SaveMetadata {
+  /** Metadata format version; 0 before it was versioned */
+  schema_version?: number;
   game_id: string;
   emulator_id: string;
   timestamp: number;
   version_id: string;
   file_list: string[];
   hash: string;
+  encrypted?: boolean;
+  /** Path of the PNG kept next to the archive in history */
+  thumbnail?: string | null;
+  thumbnail_sha256?: string | null;
+  note?: string | null;
+  tags?: string[];
+  fingerprint?: string | null;
+  /** Pinned versions are never removed by retention */
+  pinned?: boolean;
+  /** Version these saves were made from */
+  parent_version_id?: string | null;
+  /** Display title matched from the game id */
+  title?: string | null;
+  platform?: string | null;
+  cover_sha256?: string | null;
 }
 
 export interface PackagedSave {
@@ -32,11 +60,159 @@ export interface EmulatorProfile {
   name: string;
   default_save_paths: string[];
   file_patterns: string[];
+  exclude_patterns?: string[];
+  max_file_size_bytes?: number | null;
+  path_groups?: SavePathGroup[];
+  game_overrides?: Record<string, GameOverride>;
+  save_encryption?: SaveEncryption;
+  store_only_extensions?: string[];
+  screenshot_dirs?: string[];
+  snapshot_schedule?: SnapshotSchedule | null;
+  process_names?: string[];
+  /** Download OneDrive or iCloud placeholders before packaging, or leave them out */
+  placeholder_policy?: PlaceholderPolicy;
+  /** How long packaging waits for save files the emulator keeps open (Windows) */
+  lock_retry?: LockRetryPolicy;
+  /** Copy saves at least this large to a snapshot before zipping them */
+  snapshot_copy_min_bytes?: number | null;
+}
+
+export type PlaceholderPolicy = "skip" | "hydrate";
+
+export interface LockRetryPolicy {
+  /** Checks before giving up; 0 packages without checking */
+  attempts: number;
+  delay_ms: number;
+}
+
+export interface SnapshotSchedule {
+  interval_minutes: number;
+  games?: string[];
+  only_while_running?: boolean;
+}
+
+export type SaveEncryption = "auto" | "encrypted" | "none";
+
+export interface SavePathGroup {
+  name: string;
+  paths: string[];
+  file_patterns?: string[];
+}
+
+export interface GameOverride {
+  path_group?: string | null;
+  paths?: string[];
+  file_patterns?: string[];
 }
 
 export interface AppSettings {
+  /** Settings file layout version; files are migrated on load */
+  schema_version?: number;
+  retention_limit: number;
+  auto_delete: boolean;
+  /** Local history budget across all games; null means no limit */
+  max_history_size_mb?: number | null;
+  /** Folder the history was moved to; set through relocateHistory */
+  history_dir?: string | null;
+  age_retention?: AgeRetention;
+  /** Per-game replacements for age_retention, by game ID */
+  game_age_retention?: Record<string, AgeRetention>;
+  sync_schedule?: SyncSchedule;
+  updates?: UpdateSettings;
+  crash_reports?: CrashReportSettings;
+  notifications?: NotificationSettings;
+  desktop?: DesktopSettings;
+  network?: NetworkSettings;
+  game_info?: GameInfoSettings;
+}
+
+/** What updateRetention changes; omitted age settings are kept */
+export interface RetentionSettings {
   retention_limit: number;
   auto_delete: boolean;
+  max_history_size_mb?: number | null;
+  age_retention?: AgeRetention;
+  game_age_retention?: Record<string, AgeRetention>;
+}
+
+export type CloudCredentials =
+  | { mode: "official"; api_key: string; user_id?: string }
+  | ({ mode: "self_host" } & SelfHostSettings);
+
+/** A rejected value, by its path in the settings, e.g. "sync_schedule" */
+export interface SettingsIssue {
+  field: string;
+  message: string;
+}
+
+export interface AgeRetention {
+  /** Versions older than this many days are removed; null keeps them */
+  max_age_days?: number | null;
+  /** Keep hourly versions for a day, daily for 30 days, weekly after */
+  thin?: boolean;
+}
+
+export interface GameInfoSettings {
+  /** Online title lookup for games the bundled database doesn't know */
+  lookup_url: string | null;
+}
+
+/** Mostly matters on Android */
+export interface NetworkSettings {
+  /** Hold uploads on metered networks such as mobile data */
+  unmetered_uploads_only: boolean;
+  /** Pause sync while battery saver is on and the device isn't charging */
+  pause_on_battery_saver: boolean;
+}
+
+/** Ignored on Android */
+export interface DesktopSettings {
+  launch_at_login: boolean;
+  /** Stay in the tray when launched at login */
+  start_minimized: boolean;
+  /** Closing the window hides it to the tray and sync keeps running */
+  keep_running_on_close: boolean;
+}
+
+export interface NotificationSettings {
+  /** OS notifications for transfers, conflicts, expired logins and full storage */
+  enabled: boolean;
+}
+
+export interface CrashReportSettings {
+  /** Write a report when the app panics */
+  enabled: boolean;
+  /** Send new reports to the self-hosted server on the next start */
+  auto_submit: boolean;
+}
+
+export interface UpdateSettings {
+  auto_check: boolean;
+  check_interval_hours: number;
+  /** Defaults to `/client/release` on the active server */
+  metadata_url: string | null;
+}
+
+/** Background sync timing, in seconds */
+export interface SyncSchedule {
+  /** Between full scans of every game; new local versions sync right away */
+  sync_interval_secs: number;
+  /** Longest wait between full scans once nothing has changed */
+  idle_sync_interval_secs: number;
+  connection_check_secs: number;
+}
+
+export interface EvictedVersion {
+  game_id: string;
+  version_id: string;
+  timestamp: number;
+  size_bytes: number;
+}
+
+/** Payload of `history://evicted` */
+export interface HistoryEvictedPayload {
+  evicted: EvictedVersion[];
+  freed_bytes: number;
 }
 
 export interface StorageInfo {
@@ -45,11 +221,54 @@ export interface StorageInfo {
   total_size_bytes: number;
   total_versions: number;
   retention_bounds: [number, number];
+  /** History per game, largest first */
+  games: GameStorage[];
+  cloud_downloads_path: string;
+  /** Downloaded cloud versions and files, cleared by clearDownloadsCache */
+  cloud_downloads_bytes: number;
+  /** Archives in the temp directory between packaging and history */
+  temp_archives: StagingStats;
+  sync_queue: QueueStorage;
+  /** Volume holding the history; null when the OS doesn't report it */
+  volume: VolumeSpace | null;
+}
+
+export interface StagingStats {
+  files: number;
+  bytes: number;
+  /** Packaged this run and not yet saved to history */
+  in_use: number;
+}
+
+export interface GameStorage {
+  game_id: string;
+  size_bytes: number;
+  versions: number;
+}
+
+export interface QueueStorage {
+  uploads: number;
+  downloads: number;
+  pending_upload_bytes: number;
+}
+
+export interface VolumeSpace {
+  mount_point: string;
+  total_bytes: number;
+  available_bytes: number;
+}
+
+export interface SubsystemState {
+  subsystem: "settings" | "profiles" | "history" | "cloud" | "sync";
+  status: "pending" | "ready" | "failed";
+  message?: string;
 }
 
 export interface PackageResponse {
   packaged: PackagedSave;
   history: HistoryEntry;
+  /** Nothing changed since the latest version, so none was created */
+  skipped: boolean;
 }
 
 export function packageGame(
@@ -59,18 +278,126 @@ export function packageGame(
   return invoke("package_game", { emulator_id: emulatorId, game_id: gameId });
 }
 
-export function startWatcher(paths: string[]): Promise<void> {
-  return invoke("start_watcher", { paths });
+/** Package a game's saves now and keep them in history under `label` */
+export function createSnapshot(
+  gameId: string,
+  label?: string | null,
+  options: { emulatorId?: string; upload?: boolean } = {}
+): Promise<PackageResponse> {
+  return invoke("create_snapshot", {
+    game_id: gameId,
+    label: label ?? null,
+    emulator_id: options.emulatorId ?? null,
+    upload: options.upload ?? false,
+  });
+}
+
+export interface PreviewFile {
+  path: string;
+  entry_name: string;
+  size_bytes: number;
+  /** Archived without compression */
+  stored: boolean;
+}
+
+export interface PackagePreview {
+  files: PreviewFile[];
+  total_size_bytes: number;
+  estimated_compressed_bytes: number;
+  encrypted: boolean;
+  /** Online-only files left out because the profile skips placeholders */
+  placeholders: string[];
+}
+
+/** What packageGame would archive, without creating anything */
+export function previewPackage(emulatorId: string, gameId: string): Promise<PackagePreview> {
+  return invoke("preview_package", { emulator_id: emulatorId, game_id: gameId });
 }
 
-export function stopWatcher(): Promise<void> {
-  return invoke("stop_watcher");
+export interface WatchOptions {
+  session_id?: string;
+  debounce_ms?: number;
+  ignore_globs?: string[];
+  extensions?: string[];
+  disable_default_ignores?: boolean;
+  /** Rescan interval for paths on network shares */
+  poll_interval_ms?: number;
+  /** Poll every path, for shares that aren't detected as network mounts */
+  force_polling?: boolean;
+}
+
+export type WatchBackend = "native" | "poll" | "mixed";
+
+export interface WatcherStartedPayload {
+  session_id: string;
+  backend: WatchBackend;
+  /** Network shares and `content://` trees rescanned instead of watched */
+  polled_paths: string[];
+  poll_interval_ms: number;
+}
+
+export interface PackageGameRequest {
+  emulator_id: string;
+  game_id: string;
+}
+
+export interface PackageGameResult extends PackageGameRequest {
+  response?: PackageResponse;
+  error?: string;
+}
+
+export interface PackageProgressPayload extends PackageGameRequest {
+  stage: "queued" | "started" | "completed" | "skipped" | "failed";
+  message?: string;
+}
+
+/** Package several games concurrently; one result per unique request */
+export function packageGames(requests: PackageGameRequest[]): Promise<PackageGameResult[]> {
+  return invoke("package_games", { requests });
+}
+
+export function subscribePackageProgress(
+  handler: (payload: PackageProgressPayload, event: Event<PackageProgressPayload>) => void
+): Promise<UnlistenFn> {
+  return listen<PackageProgressPayload>("packager://progress", (event) =>
+    handler(event.payload, event)
+  );
+}
+
+/** Resolves to the id of the new watch session */
+export function startWatcher(paths: string[], options?: WatchOptions): Promise<string> {
+  return invoke("start_watcher", { paths, options: options ?? null });
+}
+
+export function stopWatcher(sessionId: string): Promise<void> {
+  return invoke("stop_watcher", { session_id: sessionId });
+}
+
+export function listWatcherSessions(): Promise<string[]> {
+  return invoke("list_watcher_sessions");
+}
+
+export function subscribeFsBatches(
+  handler: (batch: FsEventBatch, event: Event<FsEventBatch>) => void
+): Promise<UnlistenFn> {
+  return listen<FsEventBatch>("watcher://fs-batch", (event) => handler(event.payload, event));
 }
 
+export function subscribeWatcherStarted(
+  handler: (payload: WatcherStartedPayload) => void
+): Promise<UnlistenFn> {
+  return listen<WatcherStartedPayload>("watcher://started", (event) => handler(event.payload));
+}
+
+/** Per-file view of `subscribeFsBatches` */
 export function subscribeFsEvents(
-  handler: (payload: FsEventPayload, event: Event<FsEventPayload>) => void
+  handler: (payload: FsEventPayload, batch: FsEventBatch) => void
 ): Promise<UnlistenFn> {
-  return listen<FsEventPayload>("watcher://fs-event", (event) => handler(event.payload, event));
+  return subscribeFsBatches((batch) => {
+    for (const entry of batch.events) {
+      handler({ session_id: batch.session_id, path: entry.path, kind: entry.event_type }, batch);
+    }
+  });
 }
 
 export function packageSave(
@@ -87,6 +414,16 @@ export function packageSave(
   });
 }
 
+export function getStartupState(): Promise<SubsystemState[]> {
+  return invoke("get_startup_state");
+}
+
+export function subscribeStartupState(
+  handler: (payload: SubsystemState, event: Event<SubsystemState>) => void
+): Promise<UnlistenFn> {
+  return listen<SubsystemState>("startup://state", (event) => handler(event.payload, event));
+}
+
 export function listGamesFromHistory(): Promise<string[]> {
   return invoke("list_games_from_history");
 }
@@ -95,6 +432,13 @@ export function listProfiles(): Promise<EmulatorProfile[]> {
   return invoke("list_profiles");
 }
 
+export type EmulatorActivity = "not_running" | "idle" | "busy";
+
+/** Packaging while the emulator is "busy" can capture a half-written save */
+export function getEmulatorActivity(emulatorId: string): Promise<EmulatorActivity> {
+  return invoke("get_emulator_activity", { emulatorId });
+}
+
 export function saveProfile(profile: EmulatorProfile): Promise<EmulatorProfile> {
   return invoke("save_profile", { profile });
 }
@@ -103,6 +447,49 @@ export function deleteProfile(emulatorId: string): Promise<void> {
   return invoke("delete_profile", { emulatorId });
 }
 
+export function discoverSteamProfiles(): Promise<EmulatorProfile[]> {
+  return invoke("discover_steam_profiles");
+}
+
+export interface RetroArchGame {
+  game_id: string;
+  label: string;
+  content_path: string;
+  core_name: string | null;
+  save_dir: string;
+  save_name: string;
+}
+
+export interface RetroArchImport {
+  config_path: string;
+  playlists: number;
+  games: RetroArchGame[];
+  skipped: number;
+}
+
+/** Map RetroArch playlist entries to game IDs and per-core save folders in the RetroArch profile */
+export function importRetroArchLibrary(configPath?: string): Promise<RetroArchImport> {
+  return invoke("import_retroarch_library", { configPath: configPath ?? null });
+}
+
+export function listSuggestedProfiles(): Promise<EmulatorProfile[]> {
+  return invoke("list_suggested_profiles");
+}
+
+export function exportProfile(emulatorId: string, destination: string): Promise<void> {
+  return invoke("export_profile", { emulatorId, destination });
+}
+
+/** Fails with "... already exists" for a taken id unless `replace` is set */
+export function importProfile(path: string, replace = false): Promise<EmulatorProfile> {
+  return invoke("import_profile", { path, replace });
+}
+
+/** Fails with "... already exists" for a taken id unless `replace` is set */
+export function installProfileFromUrl(url: string, replace = false): Promise<EmulatorProfile> {
+  return invoke("install_profile_from_url", { url, replace });
+}
+
 export function validatePaths(paths: string[]): Promise<string[]> {
   return invoke("validate_paths", { paths });
 }
@@ -111,18 +498,290 @@ export function listHistory(gameId: string): Promise<HistoryEntry[]> {
   return invoke("list_history", { game_id: gameId });
 }
 
+export interface HistoryQuery {
+  emulator_id?: string;
+  source?: "local" | "manual" | "cloud";
+  /** Inclusive Unix-second bounds */
+  from_timestamp?: number;
+  to_timestamp?: number;
+  /** Matches game id, note and tags */
+  search?: string;
+  sort?: "newest_first" | "oldest_first" | "game_id";
+  offset?: number;
+  limit?: number;
+}
+
+export interface HistoryPage {
+  entries: HistoryEntry[];
+  total: number;
+  offset: number;
+  limit: number;
+  /** False while startup indexing is still running */
+  indexed: boolean;
+}
+
+export function listAllHistory(query?: HistoryQuery): Promise<HistoryPage> {
+  return invoke("list_all_history", { query: query ?? null });
+}
+
 export function getHistoryItem(gameId: string, versionId: string): Promise<HistoryEntry> {
   return invoke("get_history_item", { game_id: gameId, version_id: versionId });
 }
 
-export function rollbackVersion(gameId: string, versionId: string): Promise<PackagedSave> {
-  return invoke("rollback_version", { game_id: gameId, version_id: versionId });
+export interface ArchiveEntry {
+  path: string;
+  size_bytes: number;
+  compressed_bytes: number;
+  modified: string | null;
+  crc32: number;
+}
+
+export interface ArchivePreview {
+  game_id: string;
+  version_id: string;
+  source: "history" | "cloud_download";
+  total_bytes: number;
+  entries: ArchiveEntry[];
+}
+
+export function previewArchive(gameId: string, versionId: string): Promise<ArchivePreview> {
+  return invoke("preview_archive", { game_id: gameId, version_id: versionId });
+}
+
+export function downloadCloudFile(
+  gameId: string,
+  versionId: string,
+  path: string
+): Promise<string> {
+  return invoke("download_cloud_file", { game_id: gameId, version_id: versionId, path });
+}
+
+export interface PlannedFile {
+  path: string;
+  size_bytes: number;
+  action: "create" | "overwrite" | "unchanged";
+}
+
+export interface RollbackReport {
+  game_id: string;
+  version_id: string;
+  target_dir: string;
+  files: PlannedFile[];
+  dry_run: boolean;
+  /** Version holding the saves that were on disk before the restore */
+  backup: HistoryEntry | null;
+}
+
+/** Restore a version into the save folder; `dryRun` only lists the changes */
+export function rollbackVersion(
+  gameId: string,
+  versionId: string,
+  dryRun = false
+): Promise<RollbackReport> {
+  return invoke("rollback_version", { game_id: gameId, version_id: versionId, dry_run: dryRun });
+}
+
+export interface RollbackProgressPayload {
+  game_id: string;
+  version_id: string;
+  stage: "backing_up" | "restoring" | "recording" | "completed" | "failed";
+  message?: string;
+}
+
+export function subscribeRollbackProgress(
+  handler: (payload: RollbackProgressPayload, event: Event<RollbackProgressPayload>) => void
+): Promise<UnlistenFn> {
+  return listen<RollbackProgressPayload>("rollback://progress", (event) =>
+    handler(event.payload, event)
+  );
+}
+
+export type DeepLinkRestoreSource = "local" | "cloud";
+
+export interface DeepLinkRestoreRequest {
+  game_id: string;
+  version_id: string;
+}
+
+/** State to put in a browser sign-in URL; it comes back in the login callback link */
+export function beginLinkLogin(): Promise<string> {
+  return invoke("begin_link_login");
+}
+
+export function confirmDeepLinkRestore(
+  gameId: string,
+  versionId: string
+): Promise<DeepLinkRestoreSource> {
+  return invoke("confirm_deep_link_restore", { game_id: gameId, version_id: versionId });
 }
 
 export function deleteHistoryItem(gameId: string, versionId: string): Promise<void> {
   return invoke("delete_history_item", { game_id: gameId, version_id: versionId });
 }
 
+export function updateHistoryNote(
+  gameId: string,
+  versionId: string,
+  note: string | null,
+  tags: string[]
+): Promise<HistoryEntry> {
+  return invoke("update_history_note", {
+    game_id: gameId,
+    version_id: versionId,
+    note,
+    tags,
+  });
+}
+
+export function pinHistoryItem(gameId: string, versionId: string): Promise<HistoryEntry> {
+  return invoke("pin_history_item", { game_id: gameId, version_id: versionId });
+}
+
+export function unpinHistoryItem(gameId: string, versionId: string): Promise<HistoryEntry> {
+  return invoke("unpin_history_item", { game_id: gameId, version_id: versionId });
+}
+
+export interface CompactionReport {
+  games_scanned: number;
+  duplicates_linked: number;
+  reclaimed_bytes: number;
+}
+
+export function compactHistory(): Promise<CompactionReport> {
+  return invoke("compact_history");
+}
+
+export interface ExportVersionRequest {
+  game_id: string;
+  version_id: string;
+  target_dir: string;
+  /** Export the cloud copy even when local history has the version */
+  from_cloud?: boolean;
+}
+
+export interface ExportResult {
+  folder: string;
+  provenance_path: string;
+  file_count: number;
+}
+
+/** Extract a version read-only into a new folder under target_dir */
+export function exportVersionToFolder(request: ExportVersionRequest): Promise<ExportResult> {
+  return invoke("export_version_to_folder", { request });
+}
+
+/** Save formats the built-in converters translate between */
+export type SaveFormat = "desmume" | "drastic" | "retroarch" | "raw";
+
+export interface ConvertedSave {
+  path: string;
+  size_bytes: number;
+}
+
+/** Convert a save file for another emulator; the result is written next to it */
+export function convertSave(
+  sourcePath: string,
+  sourceFormat: SaveFormat,
+  targetFormat: SaveFormat
+): Promise<ConvertedSave> {
+  return invoke("convert_save", { sourcePath, sourceFormat, targetFormat });
+}
+
+export interface GameInfo {
+  /** Display title without region or revision tags */
+  title: string;
+  /** Short platform id, e.g. `snes` or `psx` */
+  platform?: string | null;
+  cover_sha256?: string | null;
+}
+
+/** Title, platform and cover art for a game id; null when unknown */
+export function getGameInfo(gameId: string): Promise<GameInfo | null> {
+  return invoke("get_game_info", { gameId });
+}
+
+export function attachVersionThumbnail(
+  gameId: string,
+  versionId: string,
+  imagePath: string
+): Promise<HistoryEntry> {
+  return invoke("attach_version_thumbnail", {
+    game_id: gameId,
+    version_id: versionId,
+    image_path: imagePath,
+  });
+}
+
+export type PruneLocation = "local" | "cloud";
+
+export interface PruneSuggestion {
+  game_id: string;
+  version_id: string;
+  /** Newer version that stays and covers the removed one */
+  kept_version_id: string;
+  similarity: number;
+  location: PruneLocation;
+  reclaim_bytes: number;
+}
+
+export interface PruneReport {
+  local: PruneSuggestion[];
+  cloud: PruneSuggestion[];
+  reclaim_bytes: number;
+}
+
+export interface PruneOutcome {
+  suggestion: PruneSuggestion;
+  deleted: boolean;
+  error?: string | null;
+}
+
+export function suggestPruning(
+  gameId: string | null,
+  includeCloud: boolean
+): Promise<PruneReport> {
+  return invoke("suggest_pruning", { game_id: gameId, include_cloud: includeCloud });
+}
+
+export function applyPruning(suggestions: PruneSuggestion[]): Promise<PruneOutcome[]> {
+  return invoke("apply_pruning", { suggestions });
+}
+
+/** Cloud versions a deletion covers; a preview carries the confirmation token */
+export interface CloudDeletion {
+  game_id: string;
+  versions: CloudVersion[];
+  reclaim_bytes: number;
+  confirmation_token: string | null;
+  outcomes: { version_id: string; deleted: boolean; error: string | null }[];
+}
+
+/** Without a token this only previews; call again with the preview's token to delete */
+export function deleteCloudVersion(
+  gameId: string,
+  versionId: string,
+  confirmationToken?: string
+): Promise<CloudDeletion> {
+  return invoke("delete_cloud_version", {
+    game_id: gameId,
+    version_id: versionId,
+    confirmation_token: confirmationToken ?? null,
+  });
+}
+
+/** Delete cloud versions past the newest `keepN`; pinned versions stay */
+export function pruneCloudVersions(
+  gameId: string,
+  keepN: number,
+  confirmationToken?: string
+): Promise<CloudDeletion> {
+  return invoke("prune_cloud_versions", {
+    game_id: gameId,
+    keep_n: keepN,
+    confirmation_token: confirmationToken ?? null,
+  });
+}
+
 export function getAppSettings(): Promise<AppSettings> {
   return invoke("get_app_settings");
 }
@@ -131,6 +790,38 @@ export function updateAppSettings(settings: AppSettings): Promise<AppSettings> {
   return invoke("update_app_settings", { settings });
 }
 
+export function updateRetention(retention: RetentionSettings): Promise<AppSettings> {
+  return invoke("update_retention", { retention });
+}
+
+export function updateCloudCredentials(credentials: CloudCredentials): Promise<AppSettings> {
+  return invoke("update_cloud_credentials", { credentials });
+}
+
+/** Every value saving these settings would reject; empty when all are valid */
+export function validateAppSettings(settings: AppSettings): Promise<SettingsIssue[]> {
+  return invoke("validate_app_settings", { settings });
+}
+
+/** Settings after settings.json was edited outside the app */
+export function subscribeSettingsChanged(
+  handler: (settings: AppSettings) => void
+): Promise<UnlistenFn> {
+  return listen<AppSettings>("settings://changed", (event) => handler(event.payload));
+}
+
+/** Whether tokens and access keys sit in settings.json in plain text */
+export function secretsInPlaintext(): Promise<boolean> {
+  return invoke("secrets_in_plaintext");
+}
+
+/** Secrets moved into settings.json in plain text (true) or back out (false) */
+export function subscribeSecretsPlaintext(
+  handler: (plaintext: boolean) => void
+): Promise<UnlistenFn> {
+  return listen<boolean>("settings://secrets-plaintext", (event) => handler(event.payload));
+}
+
 export function getStorageInfo(): Promise<StorageInfo> {
   return invoke("get_storage_info");
 }
@@ -138,25 +829,264 @@ export function getStorageInfo(): Promise<StorageInfo> {
 export function clearHistoryCache(): Promise<void> {
   return invoke("clear_history_cache");
 }
+
+/** Move the history to an empty folder, e.g. on a bigger drive */
+export function relocateHistory(newPath: string): Promise<AppSettings> {
+  return invoke("relocate_history", { new_path: newPath });
+}
+
+/** Resolves to the bytes freed */
+export function clearDownloadsCache(): Promise<number> {
+  return invoke("clear_downloads_cache");
+}
+
+export type ConflictSide = "local" | "cloud";
+
+export interface ConflictRecord {
+  game_id: string;
+  kept: ConflictSide;
+  local_device_id: string;
+  cloud_device_id: string;
+  local_timestamp: number;
+  cloud_timestamp: number;
+  time_gap_secs: number;
+  resolved_at: number;
+  /** Applied by a rule rather than chosen by the user */
+  automatic: boolean;
+}
+
+export interface ConflictRule {
+  game_id: string;
+  /** Keep whichever side was saved on this device */
+  device_id?: string | null;
+  /** Side to keep when `device_id` is unset or matches neither side */
+  keep: ConflictSide;
+  created_at?: number;
+}
+
+export interface RuleSuggestion {
+  rule: ConflictRule;
+  occurrences: number;
+}
+
+export function listConflictHistory(gameId?: string): Promise<ConflictRecord[]> {
+  return invoke("list_conflict_history", { game_id: gameId ?? null });
+}
+
+export function listConflictSuggestions(): Promise<RuleSuggestion[]> {
+  return invoke("list_conflict_suggestions");
+}
+
+export function acceptConflictSuggestion(gameId: string): Promise<ConflictRule> {
+  return invoke("accept_conflict_suggestion", { game_id: gameId });
+}
+
+export function dismissConflictSuggestion(gameId: string): Promise<void> {
+  return invoke("dismiss_conflict_suggestion", { game_id: gameId });
+}
+
+export function listConflictRules(): Promise<ConflictRule[]> {
+  return invoke("list_conflict_rules");
+}
+
+export function setConflictRule(rule: ConflictRule): Promise<void> {
+  return invoke("set_conflict_rule", { rule });
+}
+
+export function deleteConflictRule(gameId: string): Promise<void> {
+  return invoke("delete_conflict_rule", { game_id: gameId });
+}
+
+export function subscribeConflictRuleSuggestions(
+  handler: (payload: RuleSuggestion, event: Event<RuleSuggestion>) => void
+): Promise<UnlistenFn> {
+  return listen<RuleSuggestion>("sync://conflict-rule-suggested", (event) =>
+    handler(event.payload, event)
+  );
+}
+
+export interface SyncEvent {
+  version_id: string;
+  /** ISO 8601 */
+  at: string;
+}
+
+export interface GameSyncStatus {
+  game_id: string;
+  last_sync: string | null;
+  last_upload: SyncEvent | null;
+  last_download: SyncEvent | null;
+  last_conflict_at: string | null;
+  /** Most recent resolution of a conflict for this game */
+  last_resolution: ConflictRecord | null;
+}
+
+export function getGameSyncStatus(gameId: string): Promise<GameSyncStatus> {
+  return invoke("get_game_sync_status", { game_id: gameId });
+}
+
+export type HoldReason = "metered" | "battery_saver" | "device_idle";
+
+export interface DeviceConditions {
+  metered: boolean;
+  charging: boolean;
+  battery_saver: boolean;
+  /** Android doze */
+  idle: boolean;
+  uploads_held: HoldReason | null;
+  downloads_held: HoldReason | null;
+}
+
+export function getDeviceConditions(): Promise<DeviceConditions> {
+  return invoke("get_device_conditions");
+}
+
+export function subscribeDeviceConditions(
+  handler: (payload: DeviceConditions, event: Event<DeviceConditions>) => void
+): Promise<UnlistenFn> {
+  return listen<DeviceConditions>("sync://conditions", (event) => handler(event.payload, event));
+}
+
+/** Raised when the signed-in account is not the one the local history was synced with */
+export interface AccountSwitch {
+  previous: string;
+  current: string;
+  /** History kept apart for `current` during an earlier switch */
+  has_separate_history: boolean;
+}
+
+/** `separate` sets the history aside for the previous account; `merge` hands it to the new one */
+export type AccountHistoryChoice = "separate" | "merge";
+
+export function getAccountSwitch(): Promise<AccountSwitch | null> {
+  return invoke("get_account_switch");
+}
+
+export function resolveAccountSwitch(choice: AccountHistoryChoice): Promise<void> {
+  return invoke("resolve_account_switch", { choice });
+}
+
+export function subscribeAccountSwitch(
+  handler: (payload: AccountSwitch, event: Event<AccountSwitch>) => void
+): Promise<UnlistenFn> {
+  return listen<AccountSwitch>("cloud://account-changed", (event) =>
+    handler(event.payload, event)
+  );
+}
+
+export type ReconcileState = "local_only" | "cloud_only" | "differs" | "in_sync";
+
+/** `auto` leaves the game to the background sync rules */
+export type ReconcileDirection = "upload" | "download" | "auto";
+
+export interface VersionBrief {
+  version_id: string;
+  timestamp: number;
+  size_bytes: number | null;
+  device_id: string | null;
+}
+
+export interface GameReconciliation {
+  game_id: string;
+  state: ReconcileState;
+  local: VersionBrief | null;
+  cloud: VersionBrief | null;
+  suggested: ReconcileDirection;
+}
+
+export interface ReconciliationReport {
+  games: GameReconciliation[];
+  generated_at: number;
+}
+
+export interface ReconcileChoice {
+  game_id: string;
+  direction: ReconcileDirection;
+}
+
+export interface ReconcileOutcome {
+  uploads_queued: number;
+  downloads_queued: number;
+  left_to_sync: number;
+}
+
+export function getReconciliationReport(): Promise<ReconciliationReport> {
+  return invoke("get_reconciliation_report");
+}
+
+export function applyReconciliation(choices: ReconcileChoice[]): Promise<ReconcileOutcome> {
+  return invoke("apply_reconciliation", { choices });
+}
+
+/** Raised when the first sync of an account waits for `applyReconciliation` */
+export function subscribeReconcileRequired(handler: () => void): Promise<UnlistenFn> {
+  return listen("sync://reconcile-required", () => handler());
+}
 export interface ScannedFile {
   path: string;
   name: string;
   size: number;
   modified: number;
+  encrypted: boolean;
+}
+
+export interface ScanOptions {
+  /** Add overall and per-directory totals */
+  summarize?: boolean;
+}
+
+/** Totals for one directory, or for the whole scan when `path` is null */
+export interface ScanSummary {
+  path: string | null;
+  file_count: number;
+  total_size: number;
+  /** Milliseconds since the epoch */
+  newest_modified: number | null;
+  /** Files matching each of the profile's file patterns */
+  pattern_matches: Record<string, number>;
+}
+
+export interface ScanResponse {
+  files: ScannedFile[];
+  summary: ScanSummary | null;
+  /** Newest first */
+  directories: ScanSummary[];
 }
 
-export function scanSaveFiles(emulatorId: string): Promise<ScannedFile[]> {
-  return invoke("scan_save_files", { emulatorId });
+export function scanSaveFiles(
+  emulatorId: string,
+  gameId?: string,
+  scanOptions?: ScanOptions
+): Promise<ScanResponse> {
+  return invoke("scan_save_files", { emulatorId, gameId, scanOptions: scanOptions ?? null });
 }
 
-export interface PathStatus {
+/** Codes that packaging and restore errors start with, e.g. `path_not_writable: /saves` */
+export type PathIssue =
+  | "path_missing"
+  | "path_not_directory"
+  | "path_not_readable"
+  | "path_not_writable"
+  | "path_low_space";
+
+export interface PathReport {
   path: string;
   exists: boolean;
   is_dir: boolean;
-  error?: string;
+  readable: boolean;
+  writable: boolean;
+  /** SMB, NFS and similar shares, which are watched by polling */
+  is_network_fs: boolean;
+  free_space_bytes: number | null;
+  /** Null when it couldn't be probed, e.g. in a read-only folder */
+  case_sensitive: boolean | null;
+  issues: PathIssue[];
+  error?: string | null;
+  /** OneDrive or iCloud files under the path that are only online */
+  placeholders: string[];
 }
 
-export function checkPathStatus(emulatorId: string): Promise<PathStatus[]> {
+export function checkPathStatus(emulatorId: string): Promise<PathReport[]> {
   return invoke("check_path_status", { emulatorId });
 }
 
@@ -168,3 +1098,124 @@ export function selectDirectory(): Promise<string | null> {
   return invoke("select_directory");
 }
 
+
+/** An official server the client can point at; tokens stay in the backend */
+export interface CloudEndpointSummary {
+  name: string;
+  base_url: string;
+  signed_in: boolean;
+}
+
+export interface CloudEndpoints {
+  active: string;
+  endpoints: CloudEndpointSummary[];
+}
+
+export function listCloudEndpoints(): Promise<CloudEndpoints> {
+  return invoke("list_cloud_endpoints");
+}
+
+export function saveCloudEndpoint(name: string, baseUrl: string): Promise<CloudEndpoints> {
+  return invoke("save_cloud_endpoint", { name, base_url: baseUrl });
+}
+
+export function removeCloudEndpoint(name: string): Promise<CloudEndpoints> {
+  return invoke("remove_cloud_endpoint", { name });
+}
+
+/** Switch official endpoints, keeping each one's session */
+export function selectCloudEndpoint(name: string): Promise<CloudEndpoints> {
+  return invoke("select_cloud_endpoint", { name });
+}
+
+/** A newer client release offered to this device */
+export interface UpdateInfo {
+  current_version: string;
+  version: string;
+  notes: string;
+  pub_date: string | null;
+  url: string;
+  /** `installUpdate` can install it; otherwise send users to `url` */
+  installable: boolean;
+}
+
+export function checkForUpdates(): Promise<UpdateInfo | null> {
+  return invoke("check_for_updates");
+}
+
+/** Install the offered release and restart into it */
+export function installUpdate(): Promise<void> {
+  return invoke("install_update");
+}
+
+export function subscribeUpdateAvailable(
+  handler: (payload: UpdateInfo, event: Event<UpdateInfo>) => void
+): Promise<UnlistenFn> {
+  return listen<UpdateInfo>("update://available", (event) => handler(event.payload, event));
+}
+
+/** A panic caught by the crash reporter */
+export interface CrashReport {
+  id: string;
+  created_at: string;
+  app_version: string;
+  message: string;
+  location: string | null;
+  thread: string | null;
+  backtrace: string;
+  log_tail: string[];
+  submitted: boolean;
+}
+
+export function listCrashReports(): Promise<CrashReport[]> {
+  return invoke("list_crash_reports");
+}
+
+export function submitCrashReport(reportId: string): Promise<void> {
+  return invoke("submit_crash_report", { report_id: reportId });
+}
+
+export function deleteCrashReport(reportId: string): Promise<void> {
+  return invoke("delete_crash_report", { report_id: reportId });
+}
+
+export type LogLevel = "ERROR" | "WARN" | "INFO" | "DEBUG" | "TRACE";
+
+/** One line of the rotated log files */
+export interface LogLine {
+  level: LogLevel;
+  text: string;
+}
+
+/** The newest log lines at `level` (default info) or more severe, oldest first */
+export function getRecentLogs(level?: LogLevel, limit?: number): Promise<LogLine[]> {
+  return invoke("get_recent_logs", { level, limit });
+}
+
+/** Zip logs, redacted settings and the sync journal; returns the zip's path */
+export function exportDiagnostics(targetDir: string): Promise<string> {
+  return invoke("export_diagnostics", { target_dir: targetDir });
+}
+
+/** One timed step of the local performance check */
+export interface PerfMeasurement {
+  name: string;
+  millis: number;
+  budget_ms: number;
+  within_budget: boolean;
+  throughput_mb_s: number | null;
+}
+
+export interface PerfReport {
+  fixture_files: number;
+  fixture_bytes: number;
+  history_versions: number;
+  measurements: PerfMeasurement[];
+  within_budget: boolean;
+  generated_at: number;
+}
+
+/** Time packaging, hashing, extraction and history indexing on this machine */
+export function getPerfReport(): Promise<PerfReport> {
+  return invoke("get_perf_report");
+}
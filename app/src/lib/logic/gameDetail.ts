@@ -3,7 +3,7 @@ import {
   listHistory,
   listProfiles,
   packageGame,
-  subscribeFsEvents,
+  subscribeFsBatches,
   type FsEventPayload,
   type HistoryEntry,
 } from "../api";
@@ -94,8 +94,8 @@ export function createGameDetailLogic(gameId: string) {
     console.log(`[PACKAGE] Starting package for game: ${actualGameId}, emulator: ${emulatorId}`);
     packaging.set(true);
     try {
-      await packageGame(emulatorId, actualGameId);
-      pushInfo("Packaging completed");
+      const { skipped } = await packageGame(emulatorId, actualGameId);
+      pushInfo(skipped ? "No changes since the last version" : "Packaging completed");
       console.log("[PACKAGE] Packaging completed successfully");
       await loadHistory();
       changesDetected.set(false);
@@ -192,11 +192,21 @@ export function createGameDetailLogic(gameId: string) {
 
   async function startWatcherFeed() {
     try {
-      unlistenWatcher = await subscribeFsEvents((payload) => {
-        appendWatcherEvent(payload);
+      // Tracked changes seen during a burst are packaged once it settles
+      let pendingChanges = false;
+      unlistenWatcher = await subscribeFsBatches((batch) => {
+        for (const entry of batch.events) {
+          appendWatcherEvent({
+            session_id: batch.session_id,
+            path: entry.path,
+            kind: entry.event_type,
+          });
+          pendingChanges ||= pathMatches(entry.path, get(trackedPatterns));
+        }
 
-        // Handle auto-package here
-        if (pathMatches(payload.path, get(trackedPatterns))) {
+        // Handle auto-package here, once the emulator has gone quiet
+        if (pendingChanges && !batch.burst) {
+          pendingChanges = false;
           if (get(autoPackageEnabled) && !get(packaging)) {
             const emuId = get(emulatorIdStore);
             if (emuId) {
@@ -1,6 +1,7 @@
 import { derived, get, writable } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { AccountSwitch } from '$lib/api';
 
 interface AuthState {
     isLoggedIn: boolean;
@@ -13,6 +14,8 @@ interface AuthState {
 export interface SyncStatus {
     queue_length: number;
     active_job: any | null;
+    download_queue_length: number;
+    active_download: any | null;
     last_sync: string | null;
     is_syncing: boolean;
 }
@@ -24,6 +27,13 @@ export interface CloudVersion {
     device_id: string;
     sha256: string;
     file_list: string[];
+    thumbnail_url?: string | null;
+    note?: string | null;
+    tags?: string[];
+    pinned?: boolean;
+    parent_version_id?: string | null;
+    /** The emulator encrypted these saves */
+    encrypted?: boolean;
 }
 
 export interface CloudDevice {
@@ -33,13 +43,93 @@ export interface CloudDevice {
     last_seen: number;
 }
 
+/** Duplicate entries of one machine folded into `kept` */
+export interface MergedDevices {
+    kept: string;
+    removed: string[];
+}
+
+export interface DeviceMerge {
+    merged: MergedDevices[];
+    /** Older entries that only match by platform and name; pass their ids as `confirm` to merge them */
+    suggested: MergedDevices[];
+    /** The device list after merging, or as it would be on a dry run */
+    devices: CloudDevice[];
+}
+
+/** One entry of the server's audit log for this account */
+export interface AccountActivity {
+    action:
+        | 'signup'
+        | 'login'
+        | 'login_failed'
+        | 'device_added'
+        | 'device_removed'
+        | 'version_uploaded'
+        | 'version_deleted'
+        | 'email_verified'
+        | 'password_reset_requested'
+        | 'password_reset'
+        | 'session_revoked'
+        | 'share_granted'
+        | 'share_revoked';
+    timestamp: number;
+    device_id: string | null;
+    ip: string | null;
+    user_agent: string | null;
+    game_id: string | null;
+    version_id: string | null;
+    session_id: string | null;
+    other_user_id: string | null;
+}
+
+/** A login on the account whose token the server still accepts */
+export interface CloudSession {
+    session_id: string;
+    device_id: string | null;
+    created_at: number;
+    expires_at: number;
+    ip: string | null;
+    user_agent: string | null;
+    /** The session this app is using */
+    current: boolean;
+}
+
+export type ShareAccess = 'read' | 'write';
+
+/** One game's saves shared by their owner with another account */
+export interface SharedGame {
+    owner_user_id: string;
+    owner_email: string;
+    grantee_user_id: string;
+    grantee_email: string;
+    game_id: string;
+    access: ShareAccess;
+    created_at: number;
+}
+
+export interface GameShares {
+    /** Games this account shares with others */
+    granted: SharedGame[];
+    /** Games others share with this account */
+    received: SharedGame[];
+    /** Accounts one game can be shared with; sharing past it grants nothing */
+    max_grantees_per_game?: number | null;
+}
+
 export interface ConnectionStatus {
     connected: boolean;
     last_success?: number; // timestamp in seconds
     last_error?: string;
+    circuit?: {
+        state: 'closed' | 'open' | 'half_open';
+        consecutive_failures: number;
+        retry_in_secs: number | null;
+    };
 }
 
-export type CloudMode = 'official' | 'self_host' | 'off';
+// 'demo' is the in-memory cloud; it is never offered in the settings page
+export type CloudMode = 'official' | 'self_host' | 'off' | 'demo';
 export type CloudAuthMode = 'NONE' | 'ACCESS_KEY' | 'USERPASS';
 
 export interface SelfHostSettings {
@@ -75,6 +165,7 @@ interface LoginResult {
     token: string;
     device_id: string;
     user_id?: string;
+    account_switch?: AccountSwitch;
 }
 
 interface SignupSuccessPayload {
@@ -88,6 +179,7 @@ interface SignupErrorPayload {
 interface AppSettingsSnapshot {
     retention_limit: number;
     auto_delete: boolean;
+    max_history_size_mb?: number | null;
     cloud: CloudConfig;
     cloud_mode: CloudMode;
     self_host: SelfHostSettings;
@@ -108,6 +200,12 @@ interface CloudValidationPayload {
     message: string;
 }
 
+export interface StorageFullState {
+    scope: 'local' | 'cloud';
+    message: string;
+    hint: string;
+}
+
 const authState = writable<AuthState>({
     isLoggedIn: false,
     email: null,
@@ -119,6 +217,8 @@ const authState = writable<AuthState>({
 const syncStatus = writable<SyncStatus>({
     queue_length: 0,
     active_job: null,
+    download_queue_length: 0,
+    active_download: null,
     last_sync: null,
     is_syncing: false
 });
@@ -141,6 +241,7 @@ const devices = writable<CloudDevice[]>([]);
 const cloudMode = writable<CloudMode>('off');
 const cloudConfig = writable<CloudConfig | null>(null);
 const validationResult = writable<CloudValidationResult>({ status: 'idle', message: '' });
+const storageFull = writable<StorageFullState | null>(null);
 
 // Game ID cache - now fetched from API instead of localStorage
 const gameIdCache = writable<string[]>([]);
@@ -191,6 +292,9 @@ function bindEvents() {
         listen<{ gameId: string; message: string }>('sync://cloud-list-error', (event) => {
             console.error('Cloud list error:', event.payload?.message ?? event.payload);
         }),
+        listen<StorageFullState>('sync://storage-full', (event) => {
+            storageFull.set(event.payload);
+        }),
         listen('sync://online', () => onlineStatus.set('online')),
         listen('sync://offline', () => onlineStatus.set('offline')),
         listen<ConnectionStatus>('connection-status', (event) => {
@@ -230,9 +334,6 @@ function bindEvents() {
         listen<CloudValidationPayload>('cloud://config-invalid', (event) => {
             validationResult.set({ status: 'invalid', message: event.payload?.message ?? 'Configuration invalid' });
         }),
-        listen<string>('sync://device-missing', (event) => {
-            console.warn('Device missing from cloud:', event.payload);
-        }),
         listen<string>('sync://device-error', (event) => {
             console.error('Device error:', event.payload);
         }),
@@ -301,6 +402,7 @@ export const cloudStore = {
     cloudConfig: { subscribe: cloudConfig.subscribe },
     validation: { subscribe: validationResult.subscribe },
     gameIdCache: { subscribe: gameIdCache.subscribe },
+    storageFull: { subscribe: storageFull.subscribe },
 
     async initialize(): Promise<void> {
         bindEvents();
@@ -434,11 +536,58 @@ export const cloudStore = {
         await this.listDevices();
     },
 
+    async getAccountActivity(limit?: number): Promise<AccountActivity[]> {
+        return invoke<AccountActivity[]>('get_account_activity', { limit });
+    },
+
+    async listSessions(): Promise<CloudSession[]> {
+        return invoke<CloudSession[]>('list_cloud_sessions');
+    },
+
+    /** Signs out one session, or every other one when no ID is given */
+    async revokeSession(sessionId?: string): Promise<number> {
+        return invoke<number>('revoke_cloud_session', { session_id: sessionId ?? null });
+    },
+
+    async shareGame(gameId: string, email: string, access: ShareAccess): Promise<void> {
+        await invoke('share_game', { game_id: gameId, email, access });
+    },
+
+    async listSharedGames(): Promise<GameShares> {
+        return invoke<GameShares>('list_shared_games');
+    },
+
+    /** Stops sharing with one account, or leaves a game shared with this one */
+    async unshareGame(gameId: string, granteeUserId?: string): Promise<void> {
+        await invoke('unshare_game', { game_id: gameId, grantee_user_id: granteeUserId ?? null });
+    },
+
+    async requestPasswordReset(email: string): Promise<void> {
+        await invoke('request_password_reset', { email });
+    },
+
+    async completePasswordReset(token: string, newPassword: string): Promise<void> {
+        await invoke('complete_password_reset', { token, new_password: newPassword });
+    },
+
     async removeDevice(deviceId: string): Promise<void> {
         await invoke('remove_cloud_device', { device_id: deviceId });
         await this.listDevices();
     },
 
+    /** Folds duplicate entries of one machine into one; `dryRun` only reports them */
+    async mergeDuplicateDevices(dryRun = false, confirm: string[] = []): Promise<DeviceMerge> {
+        bindEvents();
+        const result = await invoke<DeviceMerge>('merge_duplicate_devices', {
+            dry_run: dryRun,
+            confirm
+        });
+        if (!dryRun) {
+            devices.set(result.devices);
+        }
+        return result;
+    },
+
     async forceSyncNow(): Promise<void> {
         await invoke('force_sync_now');
     },
@@ -454,6 +603,11 @@ export const cloudStore = {
         await invoke('clear_sync_queue');
     },
 
+    async resumeSync(): Promise<void> {
+        await invoke('resume_sync');
+        storageFull.set(null);
+    },
+
     async listCloudVersions(gameId: string): Promise<CloudVersion[]> {
         bindEvents();
         const versions = await invoke<CloudVersion[]>('list_cloud_versions', {
@@ -541,13 +695,8 @@ export const cloudStore = {
 
     async updateSelfHostSettings(settings: SelfHostSettings): Promise<SelfHostSettings> {
         bindEvents();
-        const snapshot = await invoke<AppSettingsSnapshot>('get_app_settings');
-        const updatedSettings = await invoke<AppSettingsSnapshot>('update_app_settings', {
-            settings: {
-                ...snapshot,
-                self_host: settings,
-                cloud_mode: snapshot.cloud_mode ?? get(cloudMode)
-            }
+        const updatedSettings = await invoke<AppSettingsSnapshot>('update_cloud_credentials', {
+            credentials: { mode: 'self_host', ...settings }
         });
 
         const mergedConfig = { ...get(cloudConfig), self_host: updatedSettings.self_host, mode: 'self_host' as CloudMode };
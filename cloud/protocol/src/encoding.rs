@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// Compression applied to a whole archive on top of the zip, negotiated
+/// through the capabilities `/health` reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveEncoding {
+    Zstd,
+}
+
+impl ArchiveEncoding {
+    /// Content type the archive is stored with
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ArchiveEncoding::Zstd => "application/zstd",
+        }
+    }
+}
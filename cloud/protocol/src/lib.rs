@@ -0,0 +1,9 @@
+//! Formats the self-host server (`cloud/server`) shares with the official
+//! Cloudflare worker (`cloud/worker`). The worker can't link Rust, so it
+//! checks the vectors in this crate's root against its TypeScript port.
+
+pub mod encoding;
+pub mod worker_token;
+
+pub use encoding::ArchiveEncoding;
+pub use worker_token::{WorkerTokenClaims, WorkerTokenError};
@@ -0,0 +1,111 @@
+//! Worker tokens, issued by `POST /save/upload-url` and sent back in
+//! `POST /save/notify-upload`. `cloud/worker/src/workerToken.ts` is the
+//! worker's copy of this module.
+//!
+//! A token is `base64url(payload_json) + "." + base64url(HMAC-SHA256(key, encoded_payload))`,
+//! both parts unpadded. The signature covers the encoded payload string, so the
+//! JSON field order only matters when signing.
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::ArchiveEncoding;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Error)]
+pub enum WorkerTokenError {
+    #[error("malformed worker token")]
+    Malformed,
+    #[error("invalid worker token signature")]
+    Signature,
+    #[error("invalid worker token encoding")]
+    Encoding,
+    #[error("invalid worker token payload: {0}")]
+    Payload(#[from] serde_json::Error),
+    #[error("incomplete worker token payload")]
+    Incomplete,
+    #[error("worker token expired")]
+    Expired,
+}
+
+/// Worker token claims (for upload proxy)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerTokenClaims {
+    pub user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    pub r2_key: String,
+    pub version_id: String,
+    pub exp: i64,
+    /// Unique token ID, consumed by the first notify-upload that uses it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Encoding the upload URL was signed for; notify-upload must match it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ArchiveEncoding>,
+}
+
+fn mac(key: &str) -> HmacSha256 {
+    // HMAC takes keys of any length
+    HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length")
+}
+
+/// Sign `claims` with `key`
+pub fn sign(claims: &WorkerTokenClaims, key: &str) -> Result<String, WorkerTokenError> {
+    let payload = serde_json::to_vec(claims)?;
+    let encoded_payload = Base64UrlUnpadded::encode_string(&payload);
+    let mut mac = mac(key);
+    mac.update(encoded_payload.as_bytes());
+    let signature = Base64UrlUnpadded::encode_string(&mac.finalize().into_bytes());
+
+    Ok(format!("{encoded_payload}.{signature}"))
+}
+
+/// Verify `token` against the main key and, during a rotation, the
+/// previous one, as of Unix time `now`
+pub fn verify(
+    token: &str,
+    main_key: &str,
+    rotated_key: Option<&str>,
+    now: i64,
+) -> Result<WorkerTokenClaims, WorkerTokenError> {
+    let mut parts = token.split('.');
+    let (encoded_payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(payload), Some(signature), None) => (payload, signature),
+        _ => return Err(WorkerTokenError::Malformed),
+    };
+    let signature =
+        Base64UrlUnpadded::decode_vec(signature).map_err(|_| WorkerTokenError::Signature)?;
+
+    let signed_by = |key: &str| {
+        let mut mac = mac(key);
+        mac.update(encoded_payload.as_bytes());
+        mac.verify_slice(&signature).is_ok()
+    };
+
+    if !signed_by(main_key) && !rotated_key.is_some_and(signed_by) {
+        return Err(WorkerTokenError::Signature);
+    }
+
+    let payload =
+        Base64UrlUnpadded::decode_vec(encoded_payload).map_err(|_| WorkerTokenError::Encoding)?;
+    let claims: WorkerTokenClaims = serde_json::from_slice(&payload)?;
+
+    if claims.exp <= 0
+        || claims.user_id.is_empty()
+        || claims.r2_key.is_empty()
+        || claims.version_id.is_empty()
+    {
+        return Err(WorkerTokenError::Incomplete);
+    }
+
+    if claims.exp < now {
+        return Err(WorkerTokenError::Expired);
+    }
+
+    Ok(claims)
+}
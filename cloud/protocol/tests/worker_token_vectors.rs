@@ -0,0 +1,95 @@
+//! Checks the worker token format against the vectors the official
+//! worker's tests (`cloud/worker/tests/workerToken.test.ts`) use as well.
+
+use crosssave_protocol::{worker_token, WorkerTokenClaims};
+use serde_json::Value;
+
+const VECTORS: &str = include_str!("../worker_token_vectors.json");
+// Earlier than every non-expired vector, later than the expired one
+const NOW: i64 = 1_800_000_000;
+
+fn vectors() -> Value {
+    serde_json::from_str(VECTORS).expect("Invalid worker token vectors")
+}
+
+fn key_for(vectors: &Value, case: &Value) -> String {
+    let field = match case["key"].as_str() {
+        Some("rotated") => "rotated_signing_key",
+        _ => "signing_key",
+    };
+    vectors[field].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_signing_matches_vectors() {
+    let vectors = vectors();
+    for case in vectors["valid"].as_array().unwrap() {
+        let claims: WorkerTokenClaims = serde_json::from_value(case["payload"].clone()).unwrap();
+        let token = worker_token::sign(&claims, &key_for(&vectors, case)).unwrap();
+        assert_eq!(
+            token,
+            case["token"].as_str().unwrap(),
+            "case {}",
+            case["name"]
+        );
+    }
+}
+
+#[test]
+fn test_valid_vectors_verify() {
+    let vectors = vectors();
+    let main = vectors["signing_key"].as_str().unwrap();
+    let rotated = vectors["rotated_signing_key"].as_str();
+
+    for case in vectors["valid"].as_array().unwrap() {
+        let claims = worker_token::verify(case["token"].as_str().unwrap(), main, rotated, NOW)
+            .unwrap_or_else(|err| panic!("case {} failed: {}", case["name"], err));
+        assert_eq!(serde_json::to_value(&claims).unwrap(), case["payload"]);
+    }
+}
+
+#[test]
+fn test_invalid_vectors_rejected() {
+    let vectors = vectors();
+    let main = vectors["signing_key"].as_str().unwrap();
+    let rotated = vectors["rotated_signing_key"].as_str();
+
+    for case in vectors["invalid"].as_array().unwrap() {
+        let result = worker_token::verify(case["token"].as_str().unwrap(), main, rotated, NOW);
+        assert!(result.is_err(), "case {} should be rejected", case["name"]);
+    }
+}
+
+#[test]
+fn test_rotated_key_requires_configuration() {
+    let vectors = vectors();
+    let main = vectors["signing_key"].as_str().unwrap();
+    let rotated_case = vectors["valid"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|case| case["key"] == "rotated")
+        .unwrap();
+
+    let result = worker_token::verify(rotated_case["token"].as_str().unwrap(), main, None, NOW);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_jti_round_trips() {
+    let vectors = vectors();
+    let key = vectors["signing_key"].as_str().unwrap();
+    let claims = WorkerTokenClaims {
+        user_id: "user-123".to_string(),
+        device_id: None,
+        r2_key: "users/user-123/saves/zelda_alttp/v3_abcdef.zip".to_string(),
+        version_id: "v3_abcdef".to_string(),
+        exp: NOW + 60,
+        jti: Some("token-1".to_string()),
+        encoding: None,
+    };
+
+    let token = worker_token::sign(&claims, key).unwrap();
+    let verified = worker_token::verify(&token, key, None, NOW).unwrap();
+    assert_eq!(verified.jti.as_deref(), Some("token-1"));
+}
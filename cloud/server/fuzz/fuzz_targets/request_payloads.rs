@@ -0,0 +1,53 @@
+//! Request bodies are deserialized and validated before any storage call,
+//! so arbitrary JSON must end in a parsed value or an `AppError`, never a
+//! panic. The first byte picks the request type. Run with
+//! `cargo fuzz run request_payloads` from `cloud/server`.
+
+#![no_main]
+
+use crosssave_selfhost_server::routes::auth::{LoginRequest, SignupRequest};
+use crosssave_selfhost_server::routes::save::{DeleteSaveRequest, NotifyUploadRequest};
+use crosssave_selfhost_server::services::save::SaveService;
+use crosssave_selfhost_server::types::{DownloadPayload, UploadPayload};
+use crosssave_selfhost_server::validation::{
+    validate_device_id, validate_email, validate_game_id, validate_version_id,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((kind, body)) = data.split_first() else {
+        return;
+    };
+
+    match kind % 5 {
+        0 => {
+            if let Ok(payload) = serde_json::from_slice::<UploadPayload>(body) {
+                let _ = SaveService::validate_upload_payload(&payload);
+            }
+        }
+        1 => {
+            if let Ok(req) = serde_json::from_slice::<NotifyUploadRequest>(body) {
+                let _ = SaveService::validate_notify_request(&req);
+            }
+        }
+        2 => {
+            if let Ok(payload) = serde_json::from_slice::<DownloadPayload>(body) {
+                let _ =
+                    validate_game_id(&payload.game_id) && validate_version_id(&payload.version_id);
+            }
+        }
+        3 => {
+            if let Ok(req) = serde_json::from_slice::<DeleteSaveRequest>(body) {
+                let _ = validate_game_id(&req.game_id) && validate_version_id(&req.version_id);
+            }
+        }
+        _ => {
+            if let Ok(req) = serde_json::from_slice::<SignupRequest>(body) {
+                let _ = validate_email(&req.email) && validate_device_id(&req.device_id);
+            }
+            if let Ok(req) = serde_json::from_slice::<LoginRequest>(body) {
+                let _ = validate_email(&req.email) && validate_device_id(&req.device_id);
+            }
+        }
+    }
+});
@@ -1,20 +1,27 @@
-use crate::config::ServerConfig;
-use crate::types::Claims;
+use crate::config::{self, ServerConfig};
+use crate::types::{Claims, PasswordAlgorithm};
 use anyhow::{anyhow, Result};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use std::sync::OnceLock;
-
-static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2, Params, Version,
+};
+use jsonwebtoken::{
+    crypto, decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use std::sync::Arc;
 
 pub fn init_jwt(config: ServerConfig) {
-    CONFIG.set(config).expect("JWT config already initialized");
+    config::install(config);
+}
+
+/// Server config registered through `init_jwt`, as last reloaded
+pub(crate) fn config() -> Result<Arc<ServerConfig>> {
+    config::current().ok_or_else(|| anyhow!("JWT config not initialized"))
 }
 
 /// Sign a JWT token
 pub fn sign_jwt(claims: &Claims) -> Result<String> {
-    let config = CONFIG
-        .get()
-        .ok_or_else(|| anyhow!("JWT config not initialized"))?;
+    let config = config()?;
 
     let token = encode(
         &Header::default(),
@@ -27,9 +34,7 @@ pub fn sign_jwt(claims: &Claims) -> Result<String> {
 
 /// Verify and decode a JWT token
 pub fn verify_jwt(token: &str) -> Result<Claims> {
-    let config = CONFIG
-        .get()
-        .ok_or_else(|| anyhow!("JWT config not initialized"))?;
+    let config = config()?;
 
     let token_data = decode::<Claims>(
         token,
@@ -40,14 +45,93 @@ pub fn verify_jwt(token: &str) -> Result<Claims> {
     Ok(token_data.claims)
 }
 
-/// Hash a password using bcrypt
+/// Argon2id cost for new hashes: 19 MiB, 2 passes, 1 lane (the OWASP
+/// baseline). Raising it re-hashes each password at its next login.
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+
+fn argon2_hasher() -> Result<Argon2<'static>> {
+    let params = Params::new(
+        ARGON2_MEMORY_KIB,
+        ARGON2_ITERATIONS,
+        ARGON2_PARALLELISM,
+        None,
+    )
+    .map_err(|e| anyhow!("invalid Argon2 parameters: {e}"))?;
+    Ok(Argon2::new(
+        argon2::Algorithm::Argon2id,
+        Version::V0x13,
+        params,
+    ))
+}
+
+/// Scheme a stored hash was made with
+pub fn password_algorithm(hash: &str) -> Option<PasswordAlgorithm> {
+    if hash.starts_with("$argon2id$") {
+        Some(PasswordAlgorithm::Argon2id)
+    } else if hash.starts_with("$2") {
+        Some(PasswordAlgorithm::Bcrypt)
+    } else {
+        None
+    }
+}
+
+/// Hash a password using Argon2id with a fresh salt
 pub fn hash_password(password: &str) -> Result<String> {
-    Ok(bcrypt::hash(password, bcrypt::DEFAULT_COST)?)
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2_hasher()?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!("password hashing failed: {e}"))?;
+    Ok(hash.to_string())
 }
 
-/// Verify a password against a hash
+/// Verify a password against an Argon2id or legacy bcrypt hash
 pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
-    Ok(bcrypt::verify(password, hash)?)
+    match password_algorithm(hash) {
+        Some(PasswordAlgorithm::Argon2id) => {
+            let parsed =
+                PasswordHash::new(hash).map_err(|e| anyhow!("invalid Argon2 hash: {e}"))?;
+            // Verifies with the parameters stored in the hash, not the current ones
+            Ok(Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok())
+        }
+        Some(PasswordAlgorithm::Bcrypt) => Ok(bcrypt::verify(password, hash)?),
+        None => Err(anyhow!("unknown password hash format")),
+    }
+}
+
+/// Whether a hash that just verified should be replaced: it's bcrypt, or
+/// Argon2id with parameters other than the current ones
+pub fn password_needs_rehash(hash: &str) -> bool {
+    if password_algorithm(hash) != Some(PasswordAlgorithm::Argon2id) {
+        return true;
+    }
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return true;
+    };
+    let Ok(params) = Params::try_from(&parsed) else {
+        return true;
+    };
+    (params.m_cost(), params.t_cost(), params.p_cost())
+        != (ARGON2_MEMORY_KIB, ARGON2_ITERATIONS, ARGON2_PARALLELISM)
+}
+
+/// New single-use token for verification and password reset emails
+pub fn new_account_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// Name an emailed account token is stored under. Keyed with the JWT secret
+/// so a leaked bucket listing can't be turned back into usable tokens.
+pub fn hash_account_token(token: &str) -> Result<String> {
+    let config = config()?;
+    Ok(crypto::sign(
+        token.trim().as_bytes(),
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+        Algorithm::HS256,
+    )?)
 }
 
 #[cfg(test)]
@@ -62,4 +146,36 @@ mod tests {
         assert!(verify_password(password, &hash).unwrap());
         assert!(!verify_password("wrong_password", &hash).unwrap());
     }
+
+    #[test]
+    fn test_new_hashes_use_argon2id() {
+        let hash = hash_password("test_password_123").unwrap();
+
+        assert_eq!(password_algorithm(&hash), Some(PasswordAlgorithm::Argon2id));
+        assert!(!password_needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_bcrypt_hashes_verify_and_need_rehash() {
+        let hash = bcrypt::hash("test_password_123", 4).unwrap();
+
+        assert_eq!(password_algorithm(&hash), Some(PasswordAlgorithm::Bcrypt));
+        assert!(verify_password("test_password_123", &hash).unwrap());
+        assert!(!verify_password("wrong_password", &hash).unwrap());
+        assert!(password_needs_rehash(&hash));
+    }
+
+    #[test]
+    fn test_weaker_argon2_parameters_need_rehash() {
+        let params = Params::new(8 * 1024, 1, 1, None).unwrap();
+        let weak = Argon2::new(argon2::Algorithm::Argon2id, Version::V0x13, params);
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = weak
+            .hash_password(b"test_password_123", &salt)
+            .unwrap()
+            .to_string();
+
+        assert!(verify_password("test_password_123", &hash).unwrap());
+        assert!(password_needs_rehash(&hash));
+    }
 }
@@ -1,9 +1,10 @@
 pub mod jwt;
+pub mod worker_token;
 
-use crate::types::Claims;
+use crate::{services::session::SessionService, storage::S3Client, types::Scope};
 use axum::{
     async_trait,
-    extract::FromRequestParts,
+    extract::{FromRef, FromRequestParts},
     http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
     Json, RequestPartsExt,
@@ -13,14 +14,28 @@ use axum_extra::{
     TypedHeader,
 };
 use serde_json::json;
+use std::{marker::PhantomData, ops::Deref};
 
-pub use jwt::{hash_password, sign_jwt, verify_jwt, verify_password};
+pub use jwt::{
+    hash_account_token, hash_password, new_account_token, password_needs_rehash, sign_jwt,
+    verify_jwt, verify_password,
+};
 
 /// Authentication context extracted from request
 #[derive(Debug, Clone)]
 pub struct AuthContext {
     pub user_id: String,
     pub device_id: Option<String>,
+    pub scopes: Vec<Scope>,
+    /// Session of the token, for tokens issued since sessions exist
+    pub session_id: Option<String>,
+}
+
+impl AuthContext {
+    /// Check whether the token grants `scope` (admin implies every scope)
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
 }
 
 /// Error response for authentication failures
@@ -40,10 +55,11 @@ impl IntoResponse for AuthError {
 impl<S> FromRequestParts<S> for AuthContext
 where
     S: Send + Sync,
+    S3Client: FromRef<S>,
 {
     type Rejection = AuthError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         // Extract Authorization header
         let TypedHeader(Authorization(bearer)) = parts
             .extract::<TypedHeader<Authorization<Bearer>>>()
@@ -53,9 +69,189 @@ where
         // Verify JWT token
         let claims = verify_jwt(bearer.token()).map_err(|_| AuthError)?;
 
+        // Revoked sessions fail like bad tokens. If the session list can't
+        // be read the request fails too, rather than letting a revoked
+        // token through.
+        let client = S3Client::from_ref(state);
+        match SessionService::is_active(&client, &claims).await {
+            Ok(true) => {}
+            Ok(false) => return Err(AuthError),
+            Err(err) => {
+                tracing::warn!("Failed to check session of {}: {:#}", claims.user_id, err);
+                return Err(AuthError);
+            }
+        }
+        crate::telemetry::record_active_user(&claims.user_id);
+
         Ok(AuthContext {
             user_id: claims.user_id,
             device_id: claims.device_id,
+            scopes: claims.scopes,
+            session_id: claims.jti,
+        })
+    }
+}
+
+/// Error response for tokens missing the scope a route requires
+pub struct ScopeError(pub Scope);
+
+impl IntoResponse for ScopeError {
+    fn into_response(self) -> Response {
+        let body = Json(json!({
+            "error": "insufficient_scope",
+            "required": self.0
+        }));
+        (StatusCode::FORBIDDEN, body).into_response()
+    }
+}
+
+/// Marker for the scope a route requires
+pub trait ScopeRequirement {
+    const SCOPE: Scope;
+}
+
+pub mod scopes {
+    use super::ScopeRequirement;
+    use crate::types::Scope;
+
+    pub struct Read;
+    pub struct Write;
+    pub struct DeviceManage;
+    pub struct Admin;
+
+    impl ScopeRequirement for Read {
+        const SCOPE: Scope = Scope::Read;
+    }
+
+    impl ScopeRequirement for Write {
+        const SCOPE: Scope = Scope::Write;
+    }
+
+    impl ScopeRequirement for DeviceManage {
+        const SCOPE: Scope = Scope::DeviceManage;
+    }
+
+    impl ScopeRequirement for Admin {
+        const SCOPE: Scope = Scope::Admin;
+    }
+}
+
+/// Authentication context that additionally requires scope `R`
+pub struct Scoped<R: ScopeRequirement> {
+    pub auth: AuthContext,
+    _scope: PhantomData<fn() -> R>,
+}
+
+impl<R: ScopeRequirement> Deref for Scoped<R> {
+    type Target = AuthContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.auth
+    }
+}
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for Scoped<R>
+where
+    S: Send + Sync,
+    S3Client: FromRef<S>,
+    R: ScopeRequirement + 'static,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let auth = AuthContext::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if !auth.has_scope(R::SCOPE) {
+            tracing::warn!("Rejected request missing scope {:?}", R::SCOPE);
+            return Err(ScopeError(R::SCOPE).into_response());
+        }
+
+        Ok(Scoped {
+            auth,
+            _scope: PhantomData,
         })
     }
 }
+
+/// Caller allowed on `/admin` routes: a user token with the admin scope, or
+/// the static `ADMIN_TOKEN` for operators without an account
+pub enum AdminAuth {
+    User(AuthContext),
+    Operator,
+}
+
+/// Compare secrets without stopping at the first differing byte
+fn secrets_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminAuth
+where
+    S: Send + Sync,
+    S3Client: FromRef<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let admin_token = crate::config::current().and_then(|config| config.admin_token.clone());
+        if let Some(expected) = admin_token {
+            let given = parts
+                .headers
+                .get(axum::http::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+            if given.is_some_and(|token| secrets_match(token.trim(), &expected)) {
+                return Ok(AdminAuth::Operator);
+            }
+        }
+
+        let Scoped { auth, .. } = Scoped::<scopes::Admin>::from_request_parts(parts, state).await?;
+        Ok(AdminAuth::User(auth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Claims;
+
+    #[test]
+    fn test_admin_implies_all_scopes() {
+        let auth = AuthContext {
+            user_id: "user".to_string(),
+            device_id: None,
+            scopes: vec![Scope::Admin],
+            session_id: None,
+        };
+
+        assert!(auth.has_scope(Scope::Read));
+        assert!(auth.has_scope(Scope::DeviceManage));
+    }
+
+    #[test]
+    fn test_secrets_match() {
+        assert!(secrets_match("operator-token", "operator-token"));
+        assert!(!secrets_match("operator-tokem", "operator-token"));
+        assert!(!secrets_match("operator", "operator-token"));
+    }
+
+    #[test]
+    fn test_legacy_claims_get_user_scopes() {
+        let claims: Claims =
+            serde_json::from_str(r#"{"user_id":"user","exp":0}"#).unwrap();
+
+        assert!(claims.scopes.contains(&Scope::Write));
+        assert!(!claims.scopes.contains(&Scope::Admin));
+        // Tokens from before sessions aren't tied to one
+        assert!(claims.jti.is_none());
+    }
+}
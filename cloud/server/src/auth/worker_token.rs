@@ -0,0 +1,25 @@
+//! Worker tokens signed with the configured keys. The format lives in
+//! `crosssave_protocol::worker_token`, which the official Cloudflare worker
+//! (`cloud/worker/src/workerToken.ts`) checks against the same vectors.
+
+use super::jwt;
+use crate::types::WorkerTokenClaims;
+use anyhow::Result;
+use crosssave_protocol::worker_token;
+
+/// Sign a worker token with the configured main key
+pub fn sign_worker_token(claims: &WorkerTokenClaims) -> Result<String> {
+    let config = jwt::config()?;
+    Ok(worker_token::sign(claims, &config.worker_signing_key)?)
+}
+
+/// Verify a worker token against the configured main and rotated keys
+pub fn verify_worker_token(token: &str) -> Result<WorkerTokenClaims> {
+    let config = jwt::config()?;
+    Ok(worker_token::verify(
+        token,
+        &config.worker_signing_key,
+        config.worker_signing_key_rotated.as_deref(),
+        chrono::Utc::now().timestamp(),
+    )?)
+}
@@ -1,32 +1,291 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::env;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::storage::S3Options;
+
+const REDACTED: &str = "<redacted>";
+
+/// Requests per minute and burst for each rate limit policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub auth_per_minute: u32,
+    pub auth_burst: u32,
+    pub listing_per_minute: u32,
+    pub listing_burst: u32,
+    pub default_per_minute: u32,
+    pub default_burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            auth_per_minute: 10,
+            auth_burst: 5,
+            listing_per_minute: 600,
+            listing_burst: 60,
+            default_per_minute: 120,
+            default_burst: 30,
+        }
+    }
+}
+
+/// Settings `reload` applies to the running server. Everything else in
+/// `ServerConfig` is fixed at startup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tunables {
+    pub rate_limits: RateLimitConfig,
+    /// Lifetime of login and signup sessions
+    pub session_ttl_secs: i64,
+    /// Lifetime of presigned upload and download URLs
+    pub presign_ttl_secs: u64,
+    pub worker_token_ttl_secs: i64,
+}
+
+impl Default for Tunables {
+    fn default() -> Self {
+        Self {
+            rate_limits: RateLimitConfig::default(),
+            session_ttl_secs: 60 * 60 * 24 * 7, // 7 days
+            presign_ttl_secs: 300,              // 5 minutes
+            worker_token_ttl_secs: 60,          // 1 minute
+        }
+    }
+}
+
+impl Tunables {
+    fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut tunables = Self::default();
+        let limits = &mut tunables.rate_limits;
+        for (name, field) in [
+            ("RATE_LIMIT_AUTH_PER_MINUTE", &mut limits.auth_per_minute),
+            ("RATE_LIMIT_AUTH_BURST", &mut limits.auth_burst),
+            (
+                "RATE_LIMIT_LISTING_PER_MINUTE",
+                &mut limits.listing_per_minute,
+            ),
+            ("RATE_LIMIT_LISTING_BURST", &mut limits.listing_burst),
+            (
+                "RATE_LIMIT_DEFAULT_PER_MINUTE",
+                &mut limits.default_per_minute,
+            ),
+            ("RATE_LIMIT_DEFAULT_BURST", &mut limits.default_burst),
+        ] {
+            if let Ok(value) = env::var(name) {
+                *field = value.trim().parse()?;
+            }
+        }
+        if let Ok(secs) = env::var("SESSION_TTL_SECS") {
+            tunables.session_ttl_secs = secs.trim().parse()?;
+        }
+        if let Ok(secs) = env::var("PRESIGN_TTL_SECS") {
+            tunables.presign_ttl_secs = secs.trim().parse()?;
+        }
+        if let Ok(secs) = env::var("WORKER_TOKEN_TTL_SECS") {
+            tunables.worker_token_ttl_secs = secs.trim().parse()?;
+        }
+        Ok(tunables)
+    }
+}
+
+/// Outgoing mail for verification and password reset emails
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// `From` address, e.g. `CrossSave <noreply@example.com>`
+    pub from: String,
+    /// Connect with implicit TLS instead of STARTTLS
+    pub implicit_tls: bool,
+}
+
+impl SmtpConfig {
+    /// `None` unless `SMTP_HOST` is set
+    fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Some(host) = env::var("SMTP_HOST")
+            .ok()
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+        else {
+            return Ok(None);
+        };
+        let implicit_tls = env::var("SMTP_TLS")
+            .map(|mode| mode.trim().eq_ignore_ascii_case("implicit"))
+            .unwrap_or(false);
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port.trim().parse()?,
+            Err(_) if implicit_tls => 465,
+            Err(_) => 587,
+        };
+        let optional = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        let from = optional("SMTP_FROM").ok_or("SMTP_FROM must be set with SMTP_HOST")?;
+
+        Ok(Some(Self {
+            host,
+            port,
+            username: optional("SMTP_USERNAME"),
+            password: optional("SMTP_PASSWORD"),
+            from,
+            implicit_tls,
+        }))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub jwt_secret: String,
+    pub worker_signing_key: String,
+    pub worker_signing_key_rotated: Option<String>,
     pub s3_endpoint: String,
     pub s3_bucket: String,
     pub s3_access_key: String,
     pub s3_secret_key: String,
     pub s3_region: String,
+    pub s3_options: S3Options,
+    /// Serve Prometheus metrics on `/metrics`
+    pub metrics_enabled: bool,
+    /// How long to keep serving after a shutdown signal while `/readyz`
+    /// already fails, so load balancers stop sending traffic first
+    pub shutdown_drain: Duration,
+    /// How long in-flight requests, then background tasks, may take to
+    /// finish before the server exits anyway
+    pub shutdown_timeout: Duration,
+    /// Static bearer token accepted on `/admin` routes
+    pub admin_token: Option<String>,
+    /// `KEY=value` file read at startup and again on every reload
+    pub config_file: Option<PathBuf>,
+    /// Verification and password reset emails are off without it
+    pub smtp: Option<SmtpConfig>,
+    pub tunables: Tunables,
 }
 
 impl ServerConfig {
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let config_file = env::var("CONFIG_FILE")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        if let Some(path) = &config_file {
+            // Values in the file win over the process environment, so edits
+            // to it take effect on reload
+            dotenvy::from_path_override(path)?;
+        }
+
+        let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        // Same lookup order as the official worker; fall back to the JWT secret
+        let worker_signing_key = env::var("WORKER_SIGNING_KEY_MAIN")
+            .or_else(|_| env::var("WORKER_SIGNING_KEY"))
+            .map(|key| key.trim().to_string())
+            .ok()
+            .filter(|key| !key.is_empty())
+            .unwrap_or_else(|| jwt_secret.clone());
+        let worker_signing_key_rotated = env::var("WORKER_SIGNING_KEY_ROTATED")
+            .ok()
+            .map(|key| key.trim().to_string())
+            .filter(|key| !key.is_empty());
+
+        let mut shutdown_drain = Duration::from_secs(5);
+        if let Ok(secs) = env::var("SHUTDOWN_DRAIN_SECS") {
+            shutdown_drain = Duration::from_secs(secs.parse()?);
+        }
+        let mut shutdown_timeout = Duration::from_secs(30);
+        if let Ok(secs) = env::var("SHUTDOWN_TIMEOUT_SECS") {
+            shutdown_timeout = Duration::from_secs(secs.parse()?);
+        }
+
+        let mut s3_options = S3Options::default();
+        if let Ok(secs) = env::var("S3_TIMEOUT_SECS") {
+            s3_options.operation_timeout = Duration::from_secs(secs.parse()?);
+        }
+        if let Ok(attempts) = env::var("S3_MAX_ATTEMPTS") {
+            s3_options.max_attempts = attempts.parse()?;
+        }
+        if let Ok(mb) = env::var("S3_MULTIPART_THRESHOLD_MB") {
+            s3_options.multipart_threshold = mb.parse::<usize>()? * 1024 * 1024;
+        }
+
         Ok(Self {
             host: env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
             port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| "7373".to_string())
                 .parse()?,
-            jwt_secret: env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
+            jwt_secret,
+            worker_signing_key,
+            worker_signing_key_rotated,
             s3_endpoint: env::var("S3_ENDPOINT")
                 .unwrap_or_else(|_| "http://localhost:9373".to_string()),
             s3_bucket: env::var("S3_BUCKET").unwrap_or_else(|_| "crosssave".to_string()),
             s3_access_key: env::var("S3_ACCESS_KEY").unwrap_or_else(|_| "minioadmin".to_string()),
             s3_secret_key: env::var("S3_SECRET_KEY").unwrap_or_else(|_| "minioadmin".to_string()),
             s3_region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_options,
+            metrics_enabled: env::var("METRICS_ENABLED")
+                .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            shutdown_drain,
+            shutdown_timeout,
+            admin_token: env::var("ADMIN_TOKEN")
+                .ok()
+                .map(|token| token.trim().to_string())
+                .filter(|token| !token.is_empty()),
+            config_file,
+            smtp: SmtpConfig::from_env()?,
+            tunables: Tunables::from_env()?,
+        })
+    }
+
+    /// Effective configuration with keys and secrets replaced
+    pub fn redacted(&self) -> Value {
+        let secret = |value: &str| {
+            if value.is_empty() {
+                Value::Null
+            } else {
+                Value::String(REDACTED.to_string())
+            }
+        };
+        json!({
+            "host": self.host,
+            "port": self.port,
+            "jwt_secret": secret(&self.jwt_secret),
+            "worker_signing_key": secret(&self.worker_signing_key),
+            "worker_signing_key_rotated": self
+                .worker_signing_key_rotated
+                .as_deref()
+                .map(secret),
+            "s3_endpoint": self.s3_endpoint,
+            "s3_bucket": self.s3_bucket,
+            "s3_access_key": secret(&self.s3_access_key),
+            "s3_secret_key": secret(&self.s3_secret_key),
+            "s3_region": self.s3_region,
+            "s3_options": {
+                "operation_timeout_secs": self.s3_options.operation_timeout.as_secs(),
+                "attempt_timeout_secs": self.s3_options.attempt_timeout.as_secs(),
+                "connect_timeout_secs": self.s3_options.connect_timeout.as_secs(),
+                "max_attempts": self.s3_options.max_attempts,
+                "multipart_threshold": self.s3_options.multipart_threshold,
+                "part_size": self.s3_options.part_size,
+            },
+            "metrics_enabled": self.metrics_enabled,
+            "shutdown_drain_secs": self.shutdown_drain.as_secs(),
+            "shutdown_timeout_secs": self.shutdown_timeout.as_secs(),
+            "admin_token": self.admin_token.as_deref().map(secret),
+            "config_file": self.config_file,
+            "smtp": self.smtp.as_ref().map(|smtp| json!({
+                "host": smtp.host,
+                "port": smtp.port,
+                "username": smtp.username,
+                "password": smtp.password.as_deref().map(secret),
+                "from": smtp.from,
+                "implicit_tls": smtp.implicit_tls,
+            })),
+            "tunables": self.tunables,
         })
     }
 
@@ -34,3 +293,60 @@ impl ServerConfig {
         format!("{}:{}", self.host, self.port)
     }
 }
+
+static CURRENT: OnceLock<RwLock<Arc<ServerConfig>>> = OnceLock::new();
+/// Keeps a SIGHUP and an admin request from reloading at the same time
+static RELOAD: Mutex<()> = Mutex::new(());
+
+/// Make `config` the one `current` returns
+pub fn install(config: ServerConfig) {
+    let config = Arc::new(config);
+    let slot = CURRENT.get_or_init(|| RwLock::new(config.clone()));
+    *slot.write().unwrap_or_else(|p| p.into_inner()) = config;
+}
+
+/// The configuration in effect, once `install` ran
+pub fn current() -> Option<Arc<ServerConfig>> {
+    CURRENT
+        .get()
+        .map(|slot| slot.read().unwrap_or_else(|p| p.into_inner()).clone())
+}
+
+/// Current tunables, or the defaults before a config is installed
+pub fn tunables() -> Tunables {
+    current()
+        .map(|config| config.tunables.clone())
+        .unwrap_or_default()
+}
+
+/// Re-read the config file and environment and apply the tunables. Other
+/// settings keep their startup values until a restart.
+pub fn reload() -> Result<Arc<ServerConfig>, Box<dyn std::error::Error>> {
+    let _guard = RELOAD.lock().unwrap_or_else(|p| p.into_inner());
+    let fresh = ServerConfig::from_env()?;
+    let Some(running) = current() else {
+        install(fresh);
+        return Ok(current().expect("config installed"));
+    };
+
+    let mut next = (*running).clone();
+    next.tunables = fresh.tunables.clone();
+    next.admin_token = fresh.admin_token.clone();
+
+    let mut restart_only = fresh;
+    restart_only.tunables = next.tunables.clone();
+    restart_only.admin_token = next.admin_token.clone();
+    if restart_only.redacted() != next.redacted()
+        || restart_only.jwt_secret != next.jwt_secret
+        || restart_only.worker_signing_key != next.worker_signing_key
+        || restart_only.s3_secret_key != next.s3_secret_key
+    {
+        tracing::warn!("Config reload ignored settings that need a restart");
+    }
+    if next.tunables != running.tunables {
+        tracing::info!("Config reloaded: {:?}", next.tunables);
+    }
+
+    install(next);
+    Ok(current().expect("config installed"))
+}
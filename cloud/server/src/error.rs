@@ -10,6 +10,8 @@ pub enum AppError {
     AuthError(String),
     InvalidInput(String),
     NotFound(String),
+    /// Signed in, but not allowed to touch this resource
+    Forbidden(String),
     DatabaseError(String),
     InternalError(anyhow::Error),
     Conflict(String),
@@ -21,6 +23,7 @@ impl IntoResponse for AppError {
             AppError::AuthError(msg) => (StatusCode::UNAUTHORIZED, msg),
             AppError::InvalidInput(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             AppError::DatabaseError(msg) => {
                 tracing::error!("Database error: {}", msg);
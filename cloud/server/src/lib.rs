@@ -1,9 +1,12 @@
 pub mod auth;
 pub mod config;
 pub mod error;
+pub mod lifecycle;
+pub mod rate_limit;
 pub mod routes;
 pub mod services;
 pub mod storage;
+pub mod telemetry;
 pub mod types;
 pub mod validation;
 
@@ -32,19 +35,13 @@ pub async fn create_router() -> Router {
     Router::new()
 }
 
+use axum::middleware;
 use std::sync::Arc;
-use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
 
 // Better approach: Expose a function that sets up the app with a given client
 pub fn create_app(client: storage::S3Client) -> Router {
-    // Rate limit configuration: 5 requests per second, burst 10
-    let governor_conf = Arc::new(
-        GovernorConfigBuilder::default()
-            .per_second(5)
-            .burst_size(10)
-            .finish()
-            .unwrap(),
-    );
+    // Per-route limits, keyed by user when signed in (see rate_limit.rs)
+    let rate_limits = Arc::new(rate_limit::RateLimits::default());
 
     routes::create_router(client)
         .layer(
@@ -54,9 +51,10 @@ pub fn create_app(client: storage::S3Client) -> Router {
                 .allow_headers(Any),
         )
         .layer(TraceLayer::new_for_http())
-        .layer(GovernorLayer {
-            config: governor_conf,
-        })
+        .layer(middleware::from_fn_with_state(
+            rate_limits,
+            rate_limit::enforce,
+        ))
 }
 
 // Helper for integration tests
@@ -0,0 +1,115 @@
+//! Readiness and shutdown. The server turns ready once storage is reachable,
+//! stops being ready as soon as a shutdown signal arrives so load balancers
+//! move traffic away, then drains in-flight requests and the background
+//! tasks started through `spawn_background`.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+use tokio::{sync::Notify, task::JoinSet};
+
+static READY: AtomicBool = AtomicBool::new(false);
+static DRAINING: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN: OnceLock<Notify> = OnceLock::new();
+static BACKGROUND: OnceLock<Mutex<JoinSet<()>>> = OnceLock::new();
+
+fn shutdown_notify() -> &'static Notify {
+    SHUTDOWN.get_or_init(Notify::new)
+}
+
+fn background() -> &'static Mutex<JoinSet<()>> {
+    BACKGROUND.get_or_init(|| Mutex::new(JoinSet::new()))
+}
+
+/// Storage is set up; `/readyz` may report ready
+pub fn mark_ready() {
+    READY.store(true, Ordering::SeqCst);
+}
+
+/// Ready and not shutting down
+pub fn is_ready() -> bool {
+    READY.load(Ordering::SeqCst) && !is_draining()
+}
+
+pub fn is_draining() -> bool {
+    DRAINING.load(Ordering::SeqCst)
+}
+
+/// Stop reporting ready and wake everything waiting in `shutdown_started`
+pub fn begin_shutdown() {
+    if !DRAINING.swap(true, Ordering::SeqCst) {
+        tracing::info!("Shutdown started, no longer ready");
+    }
+    shutdown_notify().notify_waiters();
+}
+
+/// Resolves once `begin_shutdown` was called
+pub async fn shutdown_started() {
+    let notified = shutdown_notify().notified();
+    if is_draining() {
+        return;
+    }
+    notified.await;
+}
+
+/// Run work that must not hold up the response but must finish before the
+/// process exits, such as audit writes
+pub fn spawn_background<F>(task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut tasks = background().lock().unwrap_or_else(|p| p.into_inner());
+    // Reap finished tasks so the set doesn't grow with every request
+    while tasks.try_join_next().is_some() {}
+    tasks.spawn(task);
+}
+
+/// Wait for background tasks, giving up after `timeout`. Returns how many
+/// were still running when it gave up.
+pub async fn drain_background(timeout: Duration) -> usize {
+    let mut tasks = std::mem::take(&mut *background().lock().unwrap_or_else(|p| p.into_inner()));
+    if tasks.is_empty() {
+        return 0;
+    }
+    tracing::info!("Waiting for {} background tasks", tasks.len());
+
+    let drained = tokio::time::timeout(timeout, async {
+        while let Some(result) = tasks.join_next().await {
+            if let Err(err) = result {
+                tracing::warn!("Background task failed: {}", err);
+            }
+        }
+    })
+    .await;
+    if drained.is_err() {
+        tracing::warn!(
+            "Gave up on {} background tasks after {}s",
+            tasks.len(),
+            timeout.as_secs()
+        );
+    }
+    tasks.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn background_tasks_are_drained() {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        spawn_background(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let _ = sender.send(());
+        });
+
+        assert_eq!(drain_background(Duration::from_secs(5)).await, 0);
+        assert!(receiver.await.is_ok());
+    }
+}
@@ -1,7 +1,7 @@
 use crosssave_selfhost_server::{
-    auth, config, routes, storage, create_app
+    auth, config, lifecycle, routes, storage, telemetry, create_app
 };
-use std::net::SocketAddr;
+use std::{future::IntoFuture, net::SocketAddr, time::Duration};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -27,20 +27,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize JWT auth
     auth::jwt::init_jwt(config.clone());
 
+    if config.metrics_enabled {
+        telemetry::install()?;
+        tracing::info!("Prometheus metrics enabled on /metrics");
+    }
+
     // Initialize S3 client
     tracing::info!("Connecting to S3 endpoint: {}", config.s3_endpoint);
-    let s3_client = storage::S3Client::new(
+    let s3_client = storage::S3Client::with_options(
         &config.s3_endpoint,
         &config.s3_region,
         &config.s3_access_key,
         &config.s3_secret_key,
         &config.s3_bucket,
+        config.s3_options.clone(),
     )
     .await?;
 
     // Ensure bucket exists
     s3_client.ensure_bucket().await?;
     tracing::info!("S3 bucket '{}' ready", config.s3_bucket);
+    lifecycle::mark_ready();
+
+    #[cfg(unix)]
+    tokio::spawn(reload_on_hangup());
 
     // Create router with CORS and tracing
     let app = create_app(s3_client);
@@ -50,14 +60,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("Server listening on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(config.shutdown_drain));
 
+    // Once the listener closes, in-flight requests get `shutdown_timeout`
+    let deadline = async {
+        lifecycle::shutdown_started().await;
+        tokio::time::sleep(config.shutdown_drain + config.shutdown_timeout).await;
+    };
+    tokio::select! {
+        result = server.into_future() => result?,
+        _ = deadline => tracing::warn!(
+            "Requests still in flight after {}s, exiting anyway",
+            config.shutdown_timeout.as_secs()
+        ),
+    }
+
+    lifecycle::drain_background(config.shutdown_timeout).await;
+    tracing::info!("Shutdown complete");
     Ok(())
 }
 
-async fn shutdown_signal() {
+/// Reload the tunables whenever the process gets SIGHUP
+#[cfg(unix)]
+async fn reload_on_hangup() {
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(signal) => signal,
+        Err(err) => {
+            tracing::warn!("Failed to install SIGHUP handler: {}", err);
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        tracing::info!("SIGHUP received, reloading configuration");
+        if let Err(err) = config::reload() {
+            tracing::error!(
+                "Config reload failed, keeping the current settings: {}",
+                err
+            );
+        }
+    }
+}
+
+async fn shutdown_signal(drain: Duration) {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
             .await
@@ -81,4 +129,14 @@ async fn shutdown_signal() {
     }
 
     tracing::info!("Signal received, starting graceful shutdown");
+    lifecycle::begin_shutdown();
+
+    // Keep accepting while load balancers notice `/readyz` failing
+    if !drain.is_zero() {
+        tracing::info!(
+            "Draining for {}s before closing the listener",
+            drain.as_secs()
+        );
+        tokio::time::sleep(drain).await;
+    }
 }
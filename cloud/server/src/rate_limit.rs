@@ -0,0 +1,283 @@
+//! Request limits per route group, counted per signed-in user, or per
+//! client address for anonymous requests. Devices sharing one NAT each get
+//! their own budget once logged in, while `/login` and `/signup` stay
+//! strict per address to slow down credential stuffing.
+
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use governor::{
+    clock::{Clock, DefaultClock},
+    DefaultKeyedRateLimiter, Quota, RateLimiter,
+};
+use serde_json::json;
+
+use crate::{
+    auth::verify_jwt,
+    config::{self, RateLimitConfig},
+    routes::account::client_ip,
+};
+
+/// Keys tracked per limiter before idle ones are dropped
+const MAX_TRACKED_KEYS: usize = 10_000;
+/// Orchestrator probes, which must never be refused
+const UNLIMITED_PATHS: &[&str] = &["/healthz", "/readyz"];
+
+/// Requests allowed per minute for a group of routes, and how many of
+/// them may come at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    pub name: &'static str,
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl Policy {
+    /// Login, signup and the emailed account codes, per client address
+    pub fn auth(limits: &RateLimitConfig) -> Self {
+        Self {
+            name: "auth",
+            per_minute: limits.auth_per_minute,
+            burst: limits.auth_burst,
+        }
+    }
+
+    /// Listings and checks clients poll during sync
+    pub fn listing(limits: &RateLimitConfig) -> Self {
+        Self {
+            name: "listing",
+            per_minute: limits.listing_per_minute,
+            burst: limits.listing_burst,
+        }
+    }
+
+    /// Everything else
+    pub fn standard(limits: &RateLimitConfig) -> Self {
+        Self {
+            name: "default",
+            per_minute: limits.default_per_minute,
+            burst: limits.default_burst,
+        }
+    }
+}
+
+/// Policy for a request path
+pub fn policy_for(path: &str, limits: &RateLimitConfig) -> Policy {
+    match path {
+        "/login"
+        | "/signup"
+        | "/account/verify-email"
+        | "/account/password-reset/request"
+        | "/account/password-reset/confirm" => Policy::auth(limits),
+        "/health" | "/save/list" | "/save/latest-batch" | "/save/games" | "/save/exists"
+        | "/device/check" | "/device/list" | "/share/list" => Policy::listing(limits),
+        _ => Policy::standard(limits),
+    }
+}
+
+fn quota(policy: Policy) -> Quota {
+    let per_minute = NonZeroU32::new(policy.per_minute.max(1)).expect("nonzero");
+    let burst = NonZeroU32::new(policy.burst.max(1)).expect("nonzero");
+    Quota::per_minute(per_minute).allow_burst(burst)
+}
+
+struct Limiters {
+    config: RateLimitConfig,
+    auth: DefaultKeyedRateLimiter<String>,
+    listing: DefaultKeyedRateLimiter<String>,
+    default: DefaultKeyedRateLimiter<String>,
+}
+
+impl Limiters {
+    fn new(config: RateLimitConfig) -> Self {
+        Self {
+            auth: RateLimiter::keyed(quota(Policy::auth(&config))),
+            listing: RateLimiter::keyed(quota(Policy::listing(&config))),
+            default: RateLimiter::keyed(quota(Policy::standard(&config))),
+            config,
+        }
+    }
+}
+
+pub struct RateLimits {
+    limiters: RwLock<Limiters>,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimits {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            limiters: RwLock::new(Limiters::new(config)),
+        }
+    }
+
+    /// Switch to `config` if it changed since the limiters were built.
+    /// Budgets start over when that happens.
+    pub fn apply(&self, config: RateLimitConfig) {
+        let current = self
+            .limiters
+            .read()
+            .unwrap_or_else(|p| p.into_inner())
+            .config;
+        if current == config {
+            return;
+        }
+        let mut limiters = self.limiters.write().unwrap_or_else(|p| p.into_inner());
+        if limiters.config != config {
+            tracing::info!("Rate limits changed to {:?}", config);
+            *limiters = Limiters::new(config);
+        }
+    }
+
+    /// Take one request from `key`'s budget under `policy`. On refusal,
+    /// returns how long until the next request would be allowed.
+    pub fn check(&self, policy: Policy, key: &str) -> Result<(), Duration> {
+        let limiters = self.limiters.read().unwrap_or_else(|p| p.into_inner());
+        let limiter = match policy.name {
+            "auth" => &limiters.auth,
+            "listing" => &limiters.listing,
+            _ => &limiters.default,
+        };
+        if limiter.len() > MAX_TRACKED_KEYS {
+            limiter.retain_recent();
+        }
+        limiter
+            .check_key(&key.to_string())
+            .map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
+    }
+}
+
+/// Whose budget a request spends: the user of a valid token, else the
+/// client address
+fn request_key(request: &Request, policy: Policy) -> String {
+    if policy.name != "auth" {
+        let user_id = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| verify_jwt(token.trim()).ok())
+            .map(|claims| claims.user_id);
+        if let Some(user_id) = user_id {
+            return format!("user:{user_id}");
+        }
+    }
+    match client_ip(request.headers(), request.extensions()) {
+        Some(ip) => format!("ip:{ip}"),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// Middleware refusing requests over their budget with `429` and a
+/// `Retry-After` in whole seconds
+pub async fn enforce(
+    State(limits): State<Arc<RateLimits>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if UNLIMITED_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+    // Picks up limits changed by a config reload
+    let config = config::tunables().rate_limits;
+    limits.apply(config);
+    let policy = policy_for(request.uri().path(), &config);
+    let key = request_key(&request, policy);
+
+    match limits.check(policy, &key) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::debug!(
+                "Rate limited {} on {} policy, retry in {}s",
+                key,
+                policy.name,
+                retry_after
+            );
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({
+                    "ok": false,
+                    "error": "rate_limited",
+                    "retry_after": retry_after
+                })),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_map_to_policies() {
+        let limits = RateLimitConfig::default();
+        assert_eq!(policy_for("/login", &limits).name, "auth");
+        assert_eq!(policy_for("/signup", &limits).name, "auth");
+        assert_eq!(
+            policy_for("/account/password-reset/confirm", &limits).name,
+            "auth"
+        );
+        assert_eq!(policy_for("/save/list", &limits).name, "listing");
+        assert_eq!(policy_for("/save/upload-url", &limits).name, "default");
+    }
+
+    #[test]
+    fn keys_have_separate_budgets() {
+        let config = RateLimitConfig::default();
+        let auth = Policy::auth(&config);
+        let limits = RateLimits::new(config);
+        for _ in 0..auth.burst {
+            assert!(limits.check(auth, "ip:192.0.2.1").is_ok());
+        }
+        let wait = limits
+            .check(auth, "ip:192.0.2.1")
+            .expect_err("burst exhausted");
+        assert!(wait > Duration::ZERO);
+        assert!(wait <= Duration::from_secs(60));
+
+        assert!(limits.check(auth, "ip:192.0.2.2").is_ok());
+        // Other policies keep their own counts for the same key
+        assert!(limits
+            .check(Policy::standard(&config), "ip:192.0.2.1")
+            .is_ok());
+    }
+
+    #[test]
+    fn changed_limits_rebuild_the_budgets() {
+        let mut config = RateLimitConfig {
+            auth_burst: 1,
+            ..RateLimitConfig::default()
+        };
+        let limits = RateLimits::new(config);
+        assert!(limits.check(Policy::auth(&config), "ip:192.0.2.1").is_ok());
+        assert!(limits.check(Policy::auth(&config), "ip:192.0.2.1").is_err());
+
+        config.auth_burst = 3;
+        limits.apply(config);
+        for _ in 0..3 {
+            assert!(limits.check(Policy::auth(&config), "ip:192.0.2.1").is_ok());
+        }
+    }
+}
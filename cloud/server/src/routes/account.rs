@@ -0,0 +1,142 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, State},
+    http::{header, request::Parts, Extensions, HeaderMap},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    auth::{scopes, Scoped},
+    error::AppError,
+    services::{account::AccountService, audit::AuditService},
+    storage::S3Client,
+    types::AuditEvent,
+};
+
+/// Where a request came from, for the audit log
+#[derive(Debug, Clone, Default)]
+pub struct RequestMeta {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestMeta
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ip = client_ip(&parts.headers, &parts.extensions);
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.chars().take(200).collect());
+
+        Ok(RequestMeta { ip, user_agent })
+    }
+}
+
+/// The client's address. Behind the reverse proxy the peer is the proxy
+/// itself, so `X-Forwarded-For` wins when present. Only its last entry is
+/// used: that one was added by the proxy, earlier ones by the client.
+pub fn client_ip(headers: &HeaderMap, extensions: &Extensions) -> Option<String> {
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty());
+    forwarded.or_else(|| {
+        extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    })
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ActivityRequest {
+    /// Newest events to return, 50 by default
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivityResponse {
+    pub ok: bool,
+    /// Newest first
+    pub events: Vec<AuditEvent>,
+}
+
+/// Handle recent account activity
+pub async fn handle_activity(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+    Json(req): Json<ActivityRequest>,
+) -> Result<Json<ActivityResponse>, AppError> {
+    let response = AuditService::recent_activity(&client, &auth, req).await?;
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    /// Code from the verification email
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmPasswordResetRequest {
+    /// Code from the reset email
+    pub token: String,
+    pub new_password: String,
+}
+
+/// Handle email verification
+pub async fn handle_verify_email(
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = AccountService::verify_email(&client, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle sending another verification email
+pub async fn handle_resend_verification(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+) -> Result<Json<Value>, AppError> {
+    let response = AccountService::resend_verification(&client, &auth).await?;
+    Ok(Json(response))
+}
+
+/// Handle a forgotten password
+pub async fn handle_password_reset_request(
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<PasswordResetRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = AccountService::request_password_reset(&client, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle choosing a new password with a reset code
+pub async fn handle_password_reset_confirm(
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<ConfirmPasswordResetRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = AccountService::confirm_password_reset(&client, &meta, req).await?;
+    Ok(Json(response))
+}
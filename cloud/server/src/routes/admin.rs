@@ -0,0 +1,36 @@
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{auth::AdminAuth, config, error::AppError};
+
+#[derive(Debug, Serialize)]
+pub struct ConfigResponse {
+    pub ok: bool,
+    /// Effective configuration with keys and secrets redacted
+    pub config: Value,
+}
+
+fn effective_config() -> Result<ConfigResponse, AppError> {
+    let current = config::current()
+        .ok_or_else(|| AppError::InternalError(anyhow::anyhow!("config not initialized")))?;
+    Ok(ConfigResponse {
+        ok: true,
+        config: current.redacted(),
+    })
+}
+
+/// Handle reporting the configuration the server runs with
+pub async fn handle_config(_admin: AdminAuth) -> Result<Json<ConfigResponse>, AppError> {
+    Ok(Json(effective_config()?))
+}
+
+/// Handle reloading the tunables from `CONFIG_FILE` and the environment
+pub async fn handle_reload(admin: AdminAuth) -> Result<Json<ConfigResponse>, AppError> {
+    match &admin {
+        AdminAuth::User(auth) => tracing::info!("Config reload requested by {}", auth.user_id),
+        AdminAuth::Operator => tracing::info!("Config reload requested with the admin token"),
+    }
+    config::reload().map_err(|err| AppError::InvalidInput(format!("invalid_config: {err}")))?;
+    Ok(Json(effective_config()?))
+}
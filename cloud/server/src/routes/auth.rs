@@ -3,8 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     error::AppError,
+    routes::account::RequestMeta,
     services::auth::AuthService,
     storage::S3Client,
+    types::Scope,
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -17,6 +19,8 @@ pub struct SignupRequest {
     pub device_name: Option<String>,
     #[serde(default)]
     pub platform: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -29,6 +33,13 @@ pub struct LoginRequest {
     pub device_name: Option<String>,
     #[serde(default)]
     pub platform: Option<String>,
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub new_install: bool,
+    /// Narrower scopes for the token than a full session, e.g. `["read"]`
+    #[serde(default)]
+    pub scopes: Option<Vec<Scope>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -40,23 +51,28 @@ pub struct AuthResponse {
     pub email: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
+    pub email_verified: bool,
+    /// What the token may do
+    pub scopes: Vec<Scope>,
 }
 
 /// Handle signup
 pub async fn handle_signup(
     State(client): State<S3Client>,
+    meta: RequestMeta,
     Json(req): Json<SignupRequest>,
 ) -> Result<Json<AuthResponse>, AppError> {
-    let response = AuthService::signup(&client, req).await?;
+    let response = AuthService::signup(&client, &meta, req).await?;
     Ok(Json(response))
 }
 
 /// Handle login
 pub async fn handle_login(
     State(client): State<S3Client>,
+    meta: RequestMeta,
     Json(req): Json<LoginRequest>,
 ) -> Result<Json<AuthResponse>, AppError> {
-    let response = AuthService::login(&client, req).await?;
+    let response = AuthService::login(&client, &meta, req).await?;
     Ok(Json(response))
 }
 
@@ -0,0 +1,20 @@
+use axum::{extract::State, Json};
+use serde_json::Value;
+
+use crate::{
+    error::AppError,
+    storage::{get_client_release_key, read_json, S3Client},
+};
+
+/// Release metadata for the clients' update check, uploaded by the operator
+/// to `client/release.json` in the bucket. It is served as stored; clients
+/// verify its signature themselves.
+pub async fn handle_client_release(
+    State(client): State<S3Client>,
+) -> Result<Json<Value>, AppError> {
+    read_json::<Value>(&client, &get_client_release_key())
+        .await
+        .map_err(AppError::InternalError)?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("no_release".to_string()))
+}
@@ -3,11 +3,12 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
-    auth::AuthContext,
+    auth::{scopes, Scoped},
     error::AppError,
+    routes::account::RequestMeta,
     services::device::DeviceService,
     storage::S3Client,
-    types::Device,
+    types::{Device, MergedDevices},
 };
 
 #[derive(Debug, Deserialize)]
@@ -17,6 +18,18 @@ pub struct RegisterDeviceRequest {
     pub device_name: Option<String>,
     #[serde(default)]
     pub platform: Option<String>,
+    /// Lets a new id for a known machine replace its old entry
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    /// Set by a client that just made its install salt; only then may it
+    /// take over an entry with the same fingerprint
+    #[serde(default)]
+    pub new_install: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckDeviceRequest {
+    pub device_id: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,31 +37,73 @@ pub struct RemoveDeviceRequest {
     pub device_id: String,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct MergeDevicesRequest {
+    /// Report what would be merged without changing the list
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Device to keep from its group when the token names none, e.g. for
+    /// access-key sessions
+    #[serde(default)]
+    pub keep: Option<String>,
+    /// Ids of entries without a fingerprint the user agreed to merge by
+    /// platform and name, from an earlier response's `suggested`
+    #[serde(default)]
+    pub confirm: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DeviceResponse {
     pub ok: bool,
     pub device: Device,
 }
 
+#[derive(Debug, Serialize)]
+pub struct CheckDeviceResponse {
+    pub ok: bool,
+    pub registered: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DeviceListResponse {
     pub ok: bool,
     pub devices: Vec<Device>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct MergeDevicesResponse {
+    pub ok: bool,
+    pub merged: Vec<MergedDevices>,
+    /// Matches by platform and name that wait for the user's confirmation
+    pub suggested: Vec<MergedDevices>,
+    /// The list after merging, or as it would be on a dry run
+    pub devices: Vec<Device>,
+}
+
 /// Handle device registration
 pub async fn handle_register_device(
-    auth: AuthContext,
+    auth: Scoped<scopes::DeviceManage>,
     State(client): State<S3Client>,
+    meta: RequestMeta,
     Json(req): Json<RegisterDeviceRequest>,
 ) -> Result<Json<DeviceResponse>, AppError> {
-    let response = DeviceService::register_device(&client, &auth, req).await?;
+    let response = DeviceService::register_device(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle device check, a read-only lookup clients use before registering
+pub async fn handle_check_device(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+    Json(req): Json<CheckDeviceRequest>,
+) -> Result<Json<CheckDeviceResponse>, AppError> {
+    let response = DeviceService::check_device(&client, &auth, req).await?;
     Ok(Json(response))
 }
 
 /// Handle device list
 pub async fn handle_list_devices(
-    auth: AuthContext,
+    auth: Scoped<scopes::Read>,
     State(client): State<S3Client>,
 ) -> Result<Json<DeviceListResponse>, AppError> {
     let response = DeviceService::list_devices(&client, &auth).await?;
@@ -57,10 +112,22 @@ pub async fn handle_list_devices(
 
 /// Handle device removal
 pub async fn handle_remove_device(
-    auth: AuthContext,
+    auth: Scoped<scopes::DeviceManage>,
     State(client): State<S3Client>,
+    meta: RequestMeta,
     Json(req): Json<RemoveDeviceRequest>,
 ) -> Result<Json<Value>, AppError> {
-    let response = DeviceService::remove_device(&client, &auth, req).await?;
+    let response = DeviceService::remove_device(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle merging duplicate entries for the same machine
+pub async fn handle_merge_devices(
+    auth: Scoped<scopes::DeviceManage>,
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<MergeDevicesRequest>,
+) -> Result<Json<MergeDevicesResponse>, AppError> {
+    let response = DeviceService::merge_devices(&client, &auth, &meta, req).await?;
     Ok(Json(response))
 }
@@ -1,14 +1,22 @@
-use axum::{http::StatusCode, Json};
+use axum::{extract::State, http::StatusCode, Json};
 use serde::Serialize;
 use serde_json::{json, Value};
 
+use crate::{lifecycle, storage::S3Client};
+
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
     pub uptime_seconds: u64,
+    /// Optional features clients may use with this server
+    pub capabilities: Vec<String>,
 }
 
+/// Reported in `capabilities`; `zstd` means archives may be uploaded
+/// compressed with zstd
+pub const CAPABILITIES: &[&str] = &["zstd"];
+
 // Global start time to calculate uptime
 // In a real app, this might be stored in a shared state, but lazy_static or once_cell is fine for simple stats
 use std::sync::OnceLock;
@@ -27,7 +35,36 @@ pub async fn handle_health_check() -> (StatusCode, Json<Value>) {
         status: "ok".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         uptime_seconds: uptime,
+        capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
     };
 
     (StatusCode::OK, Json(json!(response)))
 }
+
+/// Liveness: the process is up and serving, even while draining
+pub async fn handle_liveness() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::OK,
+        Json(json!({ "ok": true, "status": "alive" })),
+    )
+}
+
+/// Readiness: storage is reachable and no shutdown has started
+pub async fn handle_readiness(State(client): State<S3Client>) -> (StatusCode, Json<Value>) {
+    let status = if lifecycle::is_draining() {
+        "draining"
+    } else if !lifecycle::is_ready() {
+        "starting"
+    } else if !client.bucket_reachable().await {
+        "storage_unreachable"
+    } else {
+        return (
+            StatusCode::OK,
+            Json(json!({ "ok": true, "status": "ready" })),
+        );
+    };
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "ok": false, "status": status })),
+    )
+}
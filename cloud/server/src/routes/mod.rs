@@ -1,10 +1,17 @@
+pub mod account;
+pub mod admin;
 pub mod auth;
+pub mod client;
 pub mod device;
 pub mod health;
 pub mod save;
+pub mod session;
+pub mod share;
+pub mod storage;
 
-use crate::storage::S3Client;
+use crate::{storage::S3Client, telemetry};
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -14,23 +21,72 @@ pub fn create_router(client: S3Client) -> Router {
     // Initialize health check start time
     health::init_health_check();
 
-    Router::new()
+    let router = Router::new()
         // Health check
         .route("/health", get(health::handle_health_check))
+        .route("/healthz", get(health::handle_liveness))
+        .route("/readyz", get(health::handle_readiness))
+        // Client release metadata for update checks (no authentication)
+        .route("/client/release", get(client::handle_client_release))
         // Auth routes (no authentication required)
         .route("/signup", post(auth::handle_signup))
         .route("/login", post(auth::handle_login))
+        .route("/account/verify-email", post(account::handle_verify_email))
+        .route(
+            "/account/password-reset/request",
+            post(account::handle_password_reset_request),
+        )
+        .route(
+            "/account/password-reset/confirm",
+            post(account::handle_password_reset_confirm),
+        )
+        // Account routes (authentication required)
+        .route("/account/activity", post(account::handle_activity))
+        .route(
+            "/account/verify-email/resend",
+            post(account::handle_resend_verification),
+        )
         // Device routes (authentication required)
         .route("/device/register", post(device::handle_register_device))
+        .route("/device/check", post(device::handle_check_device))
         .route("/device/list", get(device::handle_list_devices))
         .route("/device/remove", post(device::handle_remove_device))
+        .route("/device/merge", post(device::handle_merge_devices))
+        // Session routes (authentication required)
+        .route("/session/list", get(session::handle_list_sessions))
+        .route("/session/revoke", post(session::handle_revoke_session))
+        // Share routes (authentication required)
+        .route("/share/grant", post(share::handle_grant_share))
+        .route("/share/revoke", post(share::handle_revoke_share))
+        .route("/share/list", get(share::handle_list_shares))
         // Save routes (authentication required)
         .route("/save/upload-url", post(save::handle_upload_url))
         .route("/save/upload-content", post(save::handle_upload_content))
         .route("/save/notify-upload", post(save::handle_notify_upload))
+        .route("/save/exists", get(save::handle_save_exists))
+        .route("/save/link", post(save::handle_link_version))
         .route("/save/download-url", post(save::handle_download_url))
+        .route("/save/download-file", post(save::handle_download_file))
         .route("/save/list", post(save::handle_list_saves))
+        .route("/save/latest-batch", post(save::handle_latest_batch))
+        .route("/save/delete", post(save::handle_delete_save))
+        .route("/save/pin", post(save::handle_pin_save))
         .route("/save/games", post(save::handle_list_games))
-        // Add S3 client to state
-        .with_state(client)
+        // Storage maintenance (authentication required)
+        .route("/storage/objects", post(storage::handle_list_objects))
+        // Operator routes (admin scope or ADMIN_TOKEN)
+        .route("/admin/config", get(admin::handle_config))
+        .route("/admin/config/reload", post(admin::handle_reload))
+        // After routing, so requests are labelled with their route pattern
+        .route_layer(middleware::from_fn(telemetry::track_requests));
+
+    // Added after the tracking layer so scrapes don't count themselves
+    let router = if telemetry::is_enabled() {
+        router.route("/metrics", get(telemetry::handle_metrics))
+    } else {
+        router
+    };
+
+    // Add S3 client to state
+    router.with_state(client)
 }
@@ -1,13 +1,23 @@
-use axum::{extract::State, http::StatusCode, Json};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::{
-    auth::AuthContext,
+    auth::{scopes, Scoped},
+    config,
     error::AppError,
+    routes::account::RequestMeta,
     services::save::SaveService,
     storage::S3Client,
-    types::{DownloadPayload, UploadPayload},
+    types::{ArchiveEncoding, DownloadFilePayload, DownloadPayload, UploadPayload},
 };
 
 #[derive(Debug, Serialize)]
@@ -17,6 +27,8 @@ pub struct UploadUrlResponse {
     pub r2_key: String,
     pub version_id: String,
     pub worker_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_upload_url: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,6 +44,19 @@ pub struct DownloadUrlResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub emulator_id: Option<String>,
     pub timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_version_id: Option<String>,
+    pub encrypted: bool,
+    /// The downloaded bytes must be decoded before they are a zip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ArchiveEncoding>,
 }
 
 #[derive(Debug, Serialize)]
@@ -51,12 +76,32 @@ pub struct SaveVersionDto {
     pub device_id: Option<String>,
     pub sha256: String,
     pub file_list: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_version_id: Option<String>,
+    pub encrypted: bool,
+}
+
+/// Latest version of each game, ordered by game ID
+#[derive(Debug, Serialize)]
+pub struct LatestBatchResponse {
+    pub ok: bool,
+    pub versions: Vec<SaveVersionDto>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ListGamesResponse {
     pub ok: bool,
     pub games: Vec<String>,
+    /// Those of `games` other accounts share with the caller
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub shared: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -64,6 +109,63 @@ pub struct ListSavesRequest {
     pub game_id: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LatestBatchRequest {
+    /// Games to report; every game with a version when omitted
+    #[serde(default)]
+    pub game_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveExistsQuery {
+    pub game_id: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaveExistsResponse {
+    pub ok: bool,
+    pub exists: bool,
+    /// Newest version of the game with that archive hash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+}
+
+/// A new version whose archive is identical to an existing one's
+#[derive(Debug, Deserialize)]
+pub struct LinkVersionRequest {
+    pub game_id: String,
+    pub version_id: String,
+    /// Version already holding the archive
+    pub source_version_id: String,
+    pub sha256: String,
+    #[serde(default)]
+    pub emulator_id: Option<String>,
+    #[serde(default)]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub parent_version_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteSaveRequest {
+    pub game_id: String,
+    pub version_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinSaveRequest {
+    pub game_id: String,
+    pub version_id: String,
+    pub pinned: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct NotifyUploadRequest {
     pub game_id: String,
@@ -76,11 +178,26 @@ pub struct NotifyUploadRequest {
     #[serde(default)]
     pub device_id: Option<String>,
     pub worker_token: String,
+    #[serde(default)]
+    pub thumbnail_sha256: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub parent_version_id: Option<String>,
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Must match what the upload URL was requested with
+    #[serde(default)]
+    pub encoding: Option<ArchiveEncoding>,
 }
 
 /// Handle upload URL generation
 pub async fn handle_upload_url(
-    auth: AuthContext,
+    auth: Scoped<scopes::Write>,
     State(client): State<S3Client>,
     Json(payload): Json<UploadPayload>,
 ) -> Result<Json<UploadUrlResponse>, AppError> {
@@ -102,17 +219,39 @@ pub async fn handle_upload_content(
 
 /// Handle notify upload
 pub async fn handle_notify_upload(
-    auth: AuthContext,
+    auth: Scoped<scopes::Write>,
     State(client): State<S3Client>,
+    meta: RequestMeta,
     Json(req): Json<NotifyUploadRequest>,
 ) -> Result<Json<Value>, AppError> {
-    let response = SaveService::notify_upload(&client, &auth, req).await?;
+    let response = SaveService::notify_upload(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle checking for an archive the game already has
+pub async fn handle_save_exists(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+    Query(query): Query<SaveExistsQuery>,
+) -> Result<Json<SaveExistsResponse>, AppError> {
+    let response = SaveService::find_by_hash(&client, &auth, query).await?;
+    Ok(Json(response))
+}
+
+/// Handle recording a version that reuses an existing archive
+pub async fn handle_link_version(
+    auth: Scoped<scopes::Write>,
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<LinkVersionRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = SaveService::link_version(&client, &auth, &meta, req).await?;
     Ok(Json(response))
 }
 
 /// Handle download URL generation
 pub async fn handle_download_url(
-    auth: AuthContext,
+    auth: Scoped<scopes::Read>,
     State(client): State<S3Client>,
     Json(payload): Json<DownloadPayload>,
 ) -> Result<Json<DownloadUrlResponse>, AppError> {
@@ -120,21 +259,123 @@ pub async fn handle_download_url(
     Ok(Json(response))
 }
 
+/// Handle single-file download; the body is the file itself
+pub async fn handle_download_file(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+    Json(payload): Json<DownloadFilePayload>,
+) -> Result<Response, AppError> {
+    let file = SaveService::download_file(&client, &auth, payload).await?;
+    let headers = [
+        (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+        (
+            header::HeaderName::from_static("x-crc32"),
+            format!("{:08x}", file.crc32),
+        ),
+    ];
+    Ok((headers, file.data).into_response())
+}
+
 /// Handle list saves
 pub async fn handle_list_saves(
-    auth: AuthContext,
+    auth: Scoped<scopes::Read>,
     State(client): State<S3Client>,
+    headers: HeaderMap,
     Json(req): Json<ListSavesRequest>,
-) -> Result<Json<ListSavesResponse>, AppError> {
+) -> Result<Response, AppError> {
     let response = SaveService::list_saves(&client, &auth, req).await?;
+    conditional_json(&headers, &response)
+}
+
+/// Handle latest version per game
+pub async fn handle_latest_batch(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+    headers: HeaderMap,
+    Json(req): Json<LatestBatchRequest>,
+) -> Result<Response, AppError> {
+    let response = SaveService::latest_versions(&client, &auth, req).await?;
+    conditional_json(&headers, &response)
+}
+
+/// Handle delete save version
+pub async fn handle_delete_save(
+    auth: Scoped<scopes::Write>,
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<DeleteSaveRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = SaveService::delete_version(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle pin or unpin save version
+pub async fn handle_pin_save(
+    auth: Scoped<scopes::Write>,
+    State(client): State<S3Client>,
+    Json(req): Json<PinSaveRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = SaveService::set_pinned(&client, &auth, req).await?;
     Ok(Json(response))
 }
 
 /// Handle list games
 pub async fn handle_list_games(
-    auth: AuthContext,
+    auth: Scoped<scopes::Read>,
     State(client): State<S3Client>,
-) -> Result<Json<ListGamesResponse>, AppError> {
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
     let response = SaveService::list_games(&client, &auth).await?;
-    Ok(Json(response))
+    conditional_json(&headers, &response)
+}
+
+/// Listing with an ETag, or a bare 304 when `If-None-Match` already names it
+fn conditional_json<T: Serialize>(headers: &HeaderMap, body: &T) -> Result<Response, AppError> {
+    let body = serde_json::to_value(body).map_err(|e| AppError::InternalError(e.into()))?;
+    let etag = listing_etag(&body);
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        });
+
+    let cache_headers = [
+        (header::ETAG, etag),
+        (header::CACHE_CONTROL, "private, no-cache".to_string()),
+    ];
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+    Ok((cache_headers, Json(body)).into_response())
+}
+
+/// Presigned thumbnail URLs change on every request, so only whether a
+/// version has one goes into the tag. Listings with one also hash the half
+/// of the presign lifetime they were made in, so a client revalidating with
+/// an old tag gets fresh URLs before the ones it kept expire.
+fn listing_etag(body: &Value) -> String {
+    let mut stable = body.clone();
+    let mut presigned = false;
+    if let Some(versions) = stable.get_mut("versions").and_then(Value::as_array_mut) {
+        for version in versions.iter_mut().filter_map(Value::as_object_mut) {
+            if let Some(url) = version
+                .get_mut("thumbnail_url")
+                .filter(|url| !url.is_null())
+            {
+                *url = Value::Bool(true);
+                presigned = true;
+            }
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    stable.to_string().hash(&mut hasher);
+    if presigned {
+        let window = (config::tunables().presign_ttl_secs / 2).max(1);
+        (chrono::Utc::now().timestamp().max(0) as u64 / window).hash(&mut hasher);
+    }
+    format!("\"{:016x}\"", hasher.finish())
 }
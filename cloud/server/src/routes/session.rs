@@ -0,0 +1,57 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    auth::{scopes, Scoped},
+    error::AppError,
+    routes::account::RequestMeta,
+    services::session::SessionService,
+    storage::S3Client,
+    types::Session,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeSessionRequest {
+    /// Session to revoke
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Revoke every session except the caller's instead
+    #[serde(default)]
+    pub others: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionDto {
+    #[serde(flatten)]
+    pub session: Session,
+    /// The session making this request
+    pub current: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionListResponse {
+    pub ok: bool,
+    /// Newest first
+    pub sessions: Vec<SessionDto>,
+}
+
+/// Handle listing active sessions
+pub async fn handle_list_sessions(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+) -> Result<Json<SessionListResponse>, AppError> {
+    let response = SessionService::list_sessions(&client, &auth).await?;
+    Ok(Json(response))
+}
+
+/// Handle session revocation
+pub async fn handle_revoke_session(
+    auth: Scoped<scopes::DeviceManage>,
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<RevokeSessionRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = SessionService::revoke_session(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
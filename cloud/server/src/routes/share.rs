@@ -0,0 +1,72 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    auth::{scopes, Scoped},
+    error::AppError,
+    routes::account::RequestMeta,
+    services::share::ShareService,
+    storage::S3Client,
+    types::{ShareAccess, ShareGrant},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct GrantShareRequest {
+    pub game_id: String,
+    /// Account to share with
+    pub email: String,
+    pub access: ShareAccess,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeShareRequest {
+    pub game_id: String,
+    /// Account the caller shared the game with; omitted to leave a game
+    /// shared with the caller
+    #[serde(default)]
+    pub grantee_user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareListResponse {
+    pub ok: bool,
+    /// Games the caller shares with others
+    pub granted: Vec<ShareGrant>,
+    /// Games others share with the caller
+    pub received: Vec<ShareGrant>,
+    /// Accounts one game can be shared with. `/share/grant` doesn't report
+    /// going over it, so clients check it here.
+    pub max_grantees_per_game: usize,
+}
+
+/// Handle sharing a game
+pub async fn handle_grant_share(
+    auth: Scoped<scopes::Write>,
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<GrantShareRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = ShareService::grant(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle ending a share
+pub async fn handle_revoke_share(
+    auth: Scoped<scopes::Write>,
+    State(client): State<S3Client>,
+    meta: RequestMeta,
+    Json(req): Json<RevokeShareRequest>,
+) -> Result<Json<Value>, AppError> {
+    let response = ShareService::revoke(&client, &auth, &meta, req).await?;
+    Ok(Json(response))
+}
+
+/// Handle listing shares
+pub async fn handle_list_shares(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+) -> Result<Json<ShareListResponse>, AppError> {
+    let response = ShareService::list(&client, &auth).await?;
+    Ok(Json(response))
+}
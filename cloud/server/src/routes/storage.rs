@@ -0,0 +1,48 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{scopes, Scoped},
+    error::AppError,
+    services::storage::StorageService,
+    storage::S3Client,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ListObjectsRequest {
+    /// Relative to the user's storage root, e.g. `saves/` or `saves/<game_id>/`
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// `next_cursor` from the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoredObjectDto {
+    /// Relative to the user's storage root
+    pub key: String,
+    pub size_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListObjectsResponse {
+    pub ok: bool,
+    pub objects: Vec<StoredObjectDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Handle listing the caller's stored objects, one page at a time
+pub async fn handle_list_objects(
+    auth: Scoped<scopes::Read>,
+    State(client): State<S3Client>,
+    Json(req): Json<ListObjectsRequest>,
+) -> Result<Json<ListObjectsResponse>, AppError> {
+    let response = StorageService::list_objects(&client, &auth, req).await?;
+    Ok(Json(response))
+}
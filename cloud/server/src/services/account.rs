@@ -0,0 +1,202 @@
+use serde_json::json;
+
+use crate::{
+    auth::{hash_account_token, hash_password, new_account_token, AuthContext},
+    error::AppError,
+    routes::account::{
+        ConfirmPasswordResetRequest, PasswordResetRequest, RequestMeta, VerifyEmailRequest,
+    },
+    services::{
+        audit::AuditService, auth::AuthService, mail::MailService, session::SessionService,
+    },
+    storage::{
+        get_account_token_key, load_user_metadata, read_json, save_user_metadata, write_json,
+        S3Client,
+    },
+    types::{AccountToken, AccountTokenPurpose, AuditAction, PasswordAlgorithm, UserMetadata},
+};
+
+const VERIFY_EMAIL_TTL_SECS: i64 = 60 * 60 * 48; // 48 hours
+const PASSWORD_RESET_TTL_SECS: i64 = 60 * 60; // 1 hour
+const MIN_PASSWORD_LEN: usize = 8;
+
+pub struct AccountService;
+
+impl AccountService {
+    /// Store a new token for `user` and return it
+    async fn issue_token(
+        client: &S3Client,
+        purpose: AccountTokenPurpose,
+        user: &UserMetadata,
+    ) -> Result<String, AppError> {
+        let ttl = match purpose {
+            AccountTokenPurpose::VerifyEmail => VERIFY_EMAIL_TTL_SECS,
+            AccountTokenPurpose::PasswordReset => PASSWORD_RESET_TTL_SECS,
+        };
+        let token = new_account_token();
+        let record = AccountToken {
+            purpose,
+            user_id: user.user_id.clone(),
+            email: user.email.clone(),
+            expires_at: chrono::Utc::now().timestamp() + ttl,
+        };
+
+        let key = get_account_token_key(&hash_account_token(&token)?);
+        write_json(client, &key, &record)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        Ok(token)
+    }
+
+    /// Consume a token and return the account it belongs to. Tokens work
+    /// once, and only while the account still has the address they were
+    /// sent to.
+    async fn redeem_token(
+        client: &S3Client,
+        purpose: AccountTokenPurpose,
+        token: &str,
+    ) -> Result<UserMetadata, AppError> {
+        let invalid = || AppError::InvalidInput("invalid_token".to_string());
+        if token.trim().is_empty() {
+            return Err(invalid());
+        }
+
+        let key = get_account_token_key(&hash_account_token(token)?);
+        let record: AccountToken = read_json(client, &key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?
+            .ok_or_else(invalid)?;
+        if record.purpose != purpose {
+            return Err(invalid());
+        }
+
+        client
+            .delete_object(&key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        if record.expires_at < chrono::Utc::now().timestamp() {
+            return Err(AppError::InvalidInput("token_expired".to_string()));
+        }
+
+        let user = load_user_metadata(client, &record.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?
+            .filter(|user| user.email == record.email)
+            .ok_or_else(invalid)?;
+        Ok(user)
+    }
+
+    /// Email `user` a verification code
+    pub async fn send_verification(client: &S3Client, user: &UserMetadata) -> Result<(), AppError> {
+        let token = Self::issue_token(client, AccountTokenPurpose::VerifyEmail, user).await?;
+        let body = format!(
+            "Enter this code in CrossSave to verify your email address:\n\n\
+             {token}\n\n\
+             The code expires in 48 hours. If you didn't create a CrossSave \
+             account, you can ignore this email.\n"
+        );
+        MailService::send_later(user.email.clone(), "Verify your CrossSave email", body);
+        Ok(())
+    }
+
+    /// Send the signed-in user a new verification code
+    pub async fn resend_verification(
+        client: &S3Client,
+        auth: &AuthContext,
+    ) -> Result<serde_json::Value, AppError> {
+        if !MailService::is_configured() {
+            return Err(AppError::NotFound("email_unavailable".to_string()));
+        }
+
+        let user = load_user_metadata(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?
+            .ok_or_else(|| AppError::NotFound("user_not_found".to_string()))?;
+        if !user.email_verified {
+            Self::send_verification(client, &user).await?;
+        }
+
+        Ok(json!({ "ok": true, "email_verified": user.email_verified }))
+    }
+
+    pub async fn verify_email(
+        client: &S3Client,
+        meta: &RequestMeta,
+        req: VerifyEmailRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        let mut user =
+            Self::redeem_token(client, AccountTokenPurpose::VerifyEmail, &req.token).await?;
+
+        if !user.email_verified {
+            user.email_verified = true;
+            user.updated_at = chrono::Utc::now().timestamp();
+            save_user_metadata(client, &user)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?;
+            let event = AuditService::event(AuditAction::EmailVerified, meta);
+            AuditService::record(client, &user.user_id, event);
+        }
+
+        Ok(json!({ "ok": true, "email_verified": true }))
+    }
+
+    /// Email a reset code if the address has an account. The response is the
+    /// same either way, so it can't be used to probe for accounts.
+    pub async fn request_password_reset(
+        client: &S3Client,
+        meta: &RequestMeta,
+        req: PasswordResetRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        if !MailService::is_configured() {
+            return Err(AppError::NotFound("email_unavailable".to_string()));
+        }
+
+        let email = AuthService::normalize_email(&req.email);
+        if let Some(user) = AuthService::get_user_by_email(client, &email).await? {
+            let token =
+                Self::issue_token(client, AccountTokenPurpose::PasswordReset, &user).await?;
+            let body = format!(
+                "Someone asked to reset the password of your CrossSave account. \
+                 Enter this code in CrossSave to choose a new password:\n\n\
+                 {token}\n\n\
+                 The code expires in 1 hour. If this wasn't you, you can ignore \
+                 this email; your password stays the same.\n"
+            );
+            MailService::send_later(user.email.clone(), "Reset your CrossSave password", body);
+
+            let event = AuditService::event(AuditAction::PasswordResetRequested, meta);
+            AuditService::record(client, &user.user_id, event);
+        }
+
+        Ok(json!({ "ok": true }))
+    }
+
+    pub async fn confirm_password_reset(
+        client: &S3Client,
+        meta: &RequestMeta,
+        req: ConfirmPasswordResetRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        if req.new_password.len() < MIN_PASSWORD_LEN {
+            return Err(AppError::InvalidInput("weak_password".to_string()));
+        }
+
+        let mut user =
+            Self::redeem_token(client, AccountTokenPurpose::PasswordReset, &req.token).await?;
+        user.password_hash = hash_password(&req.new_password).map_err(AppError::InternalError)?;
+        user.password_algorithm = PasswordAlgorithm::Argon2id;
+        // The code arrived by email, which proves the address as well
+        user.email_verified = true;
+        user.updated_at = chrono::Utc::now().timestamp();
+        save_user_metadata(client, &user)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        // Whoever knew the old password may still hold a token
+        SessionService::revoke_all(client, &user.user_id).await?;
+
+        let event = AuditService::event(AuditAction::PasswordReset, meta);
+        AuditService::record(client, &user.user_id, event);
+
+        Ok(json!({ "ok": true }))
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::OnceLock;
+
+use tokio::sync::Mutex;
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    lifecycle,
+    routes::account::{ActivityRequest, ActivityResponse, RequestMeta},
+    storage::{get_audit_log_key, get_audit_log_prefix, S3Client},
+    types::{AuditAction, AuditEvent},
+};
+
+/// Newest events returned by `/account/activity` when no limit is given
+const DEFAULT_ACTIVITY_LIMIT: usize = 50;
+const MAX_ACTIVITY_LIMIT: usize = 500;
+/// Days of logs read to fill one activity page
+const MAX_ACTIVITY_DAYS: usize = 31;
+
+/// S3 objects can't be appended to, so each append rewrites the day's
+/// object; this keeps two requests on this server from losing a line
+static APPEND_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Build an event stamped with the current time and request origin
+    pub fn event(action: AuditAction, meta: &RequestMeta) -> AuditEvent {
+        AuditEvent {
+            action,
+            timestamp: chrono::Utc::now().timestamp(),
+            device_id: None,
+            ip: meta.ip.clone(),
+            user_agent: meta.user_agent.clone(),
+            game_id: None,
+            version_id: None,
+            session_id: None,
+            other_user_id: None,
+        }
+    }
+
+    /// Append `event` to the user's log for today in the background, so the
+    /// request doesn't wait on it; shutdown waits for it instead. A failed
+    /// write is logged and never fails the request being audited.
+    pub fn record(client: &S3Client, user_id: &str, event: AuditEvent) {
+        let client = client.clone();
+        let user_id = user_id.to_string();
+        lifecycle::spawn_background(async move {
+            if let Err(err) = Self::append(&client, &user_id, &event).await {
+                tracing::warn!(
+                    "Failed to write audit event {:?} for {}: {:#}",
+                    event.action,
+                    user_id,
+                    err
+                );
+            }
+        });
+    }
+
+    async fn append(client: &S3Client, user_id: &str, event: &AuditEvent) -> anyhow::Result<()> {
+        let day = chrono::DateTime::from_timestamp(event.timestamp, 0)
+            .unwrap_or_else(chrono::Utc::now)
+            .format("%Y-%m-%d")
+            .to_string();
+        let key = get_audit_log_key(user_id, &day);
+
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        let _guard = APPEND_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+        let mut log = client.get_object(&key).await?.unwrap_or_default();
+        log.extend_from_slice(&line);
+        client.put_object(&key, log).await
+    }
+
+    /// The user's newest events, newest first
+    pub async fn recent_activity(
+        client: &S3Client,
+        auth: &AuthContext,
+        req: ActivityRequest,
+    ) -> Result<ActivityResponse, AppError> {
+        let limit = req
+            .limit
+            .unwrap_or(DEFAULT_ACTIVITY_LIMIT)
+            .clamp(1, MAX_ACTIVITY_LIMIT);
+
+        // Day keys sort by date, so the last ones listed are the newest
+        let prefix = get_audit_log_prefix(&auth.user_id);
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let page = client
+                .list_objects(&prefix, token.as_deref(), 1000)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?;
+            keys.extend(page.objects.into_iter().map(|object| object.key));
+            token = page.next_token;
+            if token.is_none() {
+                break;
+            }
+        }
+
+        let mut events = Vec::new();
+        for key in keys.iter().rev().take(MAX_ACTIVITY_DAYS) {
+            let Some(data) = client
+                .get_object(key)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?
+            else {
+                continue;
+            };
+            let mut day: Vec<AuditEvent> = data
+                .split(|byte| *byte == b'\n')
+                .filter(|line| !line.is_empty())
+                .filter_map(|line| serde_json::from_slice(line).ok())
+                .collect();
+            day.reverse();
+            events.extend(day);
+            if events.len() >= limit {
+                break;
+            }
+        }
+        events.truncate(limit);
+
+        Ok(ActivityResponse { ok: true, events })
+    }
+}
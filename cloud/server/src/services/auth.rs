@@ -1,22 +1,28 @@
 use crate::{
-    auth::{hash_password, sign_jwt, verify_password},
+    auth::{hash_password, password_needs_rehash, sign_jwt, verify_password},
+    config,
     error::AppError,
+    routes::account::RequestMeta,
     routes::auth::{AuthResponse, LoginRequest, SignupRequest},
+    services::audit::AuditService,
+    services::{account::AccountService, mail::MailService, session::SessionService},
     storage::{
-        load_user_devices, save_user_devices, save_user_metadata, S3Client,
+        load_user_devices, load_user_metadata, save_user_devices, save_user_metadata, S3Client,
     },
-    types::{Claims, Device, UserDevices, UserMetadata},
-    validation::{validate_device_id, validate_email},
+    types::{
+        default_user_scopes, requested_user_scopes, AuditAction, Claims, Device, DeviceUpsert,
+        PasswordAlgorithm, UserDevices, UserMetadata,
+    },
+    validation::{validate_device_id, validate_email, validate_fingerprint},
 };
+use serde::Deserialize;
 use serde_json::json;
 
-const SESSION_TTL_SECONDS: i64 = 60 * 60 * 24 * 7; // 7 days
-
 pub struct AuthService;
 
 impl AuthService {
     /// Normalize email (lowercase, trim)
-    fn normalize_email(email: &str) -> String {
+    pub(crate) fn normalize_email(email: &str) -> String {
         email.trim().to_lowercase()
     }
 
@@ -37,12 +43,23 @@ impl AuthService {
     }
 
     /// GET user by email
-    async fn get_user_by_email(
+    pub(crate) async fn get_user_by_email(
         client: &S3Client,
         email: &str,
     ) -> Result<Option<UserMetadata>, AppError> {
+        #[derive(Deserialize)]
+        struct EmailLookup {
+            user_id: String,
+        }
+
         let lookup_key = format!("email_lookup/{}.json", email);
-        crate::storage::read_json::<UserMetadata>(client, &lookup_key)
+        let lookup = crate::storage::read_json::<EmailLookup>(client, &lookup_key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        let Some(lookup) = lookup else {
+            return Ok(None);
+        };
+        load_user_metadata(client, &lookup.user_id)
             .await
             .map_err(|e| AppError::InternalError(e.into()))
     }
@@ -60,7 +77,43 @@ impl AuthService {
             .map_err(|e| AppError::InternalError(e.into()))
     }
 
-    pub async fn signup(client: &S3Client, req: SignupRequest) -> Result<AuthResponse, AppError> {
+    /// Replace `user`'s stored hash with a current Argon2id one. Failures are
+    /// logged; the old hash keeps working.
+    async fn rehash_password(client: &S3Client, user: &UserMetadata, password: &str, now: i64) {
+        let password_hash = match hash_password(password) {
+            Ok(hash) => hash,
+            Err(err) => {
+                tracing::warn!("Failed to rehash password for {}: {:#}", user.user_id, err);
+                return;
+            }
+        };
+        let updated = UserMetadata {
+            password_hash,
+            password_algorithm: PasswordAlgorithm::Argon2id,
+            updated_at: now,
+            ..user.clone()
+        };
+        match save_user_metadata(client, &updated).await {
+            Ok(()) => tracing::info!(
+                "Migrated password hash for {} from {:?}",
+                user.user_id,
+                user.password_algorithm
+            ),
+            Err(err) => {
+                tracing::warn!(
+                    "Failed to save rehashed password for {}: {:#}",
+                    user.user_id,
+                    err
+                )
+            }
+        }
+    }
+
+    pub async fn signup(
+        client: &S3Client,
+        meta: &RequestMeta,
+        req: SignupRequest,
+    ) -> Result<AuthResponse, AppError> {
         // Validate inputs
         let email = Self::normalize_email(&req.email);
         if !validate_email(&email) {
@@ -75,6 +128,10 @@ impl AuthService {
             return Err(AppError::InvalidInput("invalid_device_id".to_string()));
         }
 
+        if !validate_fingerprint(&req.fingerprint) {
+            return Err(AppError::InvalidInput("invalid_fingerprint".to_string()));
+        }
+
         // Check if user exists
         if Self::get_user_by_email(client, &email).await?.is_some() {
             return Err(AppError::InvalidInput("email_already_registered".to_string()));
@@ -92,6 +149,8 @@ impl AuthService {
             created_at: now,
             updated_at: now,
             devices: if req.device_id.is_some() { 1 } else { 0 },
+            password_algorithm: PasswordAlgorithm::Argon2id,
+            email_verified: false,
         };
 
         // Save user metadata
@@ -102,6 +161,14 @@ impl AuthService {
         // Save email lookup
         Self::save_email_lookup(client, &email, &user_id).await?;
 
+        // A failed verification email shouldn't fail the signup; the user
+        // can ask for another one
+        if MailService::is_configured() {
+            if let Err(err) = AccountService::send_verification(client, &metadata).await {
+                tracing::warn!("Failed to start email verification for {}: {:?}", user_id, err);
+            }
+        }
+
         // Register device if provided
         if let Some(device_id) = &req.device_id {
             let device = Device {
@@ -109,6 +176,7 @@ impl AuthService {
                 platform: Self::normalize_platform(req.platform.as_deref()),
                 device_name: Self::normalize_device_name(req.device_name.as_deref()),
                 last_seen: now,
+                fingerprint: req.fingerprint.clone(),
             };
 
             let devices = UserDevices {
@@ -121,15 +189,23 @@ impl AuthService {
         }
 
         // Generate JWT
-        let exp = now + SESSION_TTL_SECONDS;
+        let exp = now + config::tunables().session_ttl_secs;
+        let session_id =
+            SessionService::create(client, &user_id, req.device_id.clone(), exp, meta).await?;
         let claims = Claims {
             user_id: user_id.clone(),
             device_id: req.device_id.clone(),
             exp,
+            scopes: default_user_scopes(),
+            jti: Some(session_id),
         };
 
         let token = sign_jwt(&claims).map_err(|e| AppError::InternalError(e))?;
 
+        let mut event = AuditService::event(AuditAction::Signup, meta);
+        event.device_id = req.device_id.clone();
+        AuditService::record(client, &user_id, event);
+
         Ok(AuthResponse {
             ok: true,
             user_id,
@@ -137,10 +213,16 @@ impl AuthService {
             exp,
             email,
             device_id: req.device_id,
+            email_verified: false,
+            scopes: claims.scopes,
         })
     }
 
-    pub async fn login(client: &S3Client, req: LoginRequest) -> Result<AuthResponse, AppError> {
+    pub async fn login(
+        client: &S3Client,
+        meta: &RequestMeta,
+        req: LoginRequest,
+    ) -> Result<AuthResponse, AppError> {
         // Validate inputs
         let email = Self::normalize_email(&req.email);
         if !validate_email(&email) || req.password.is_empty() {
@@ -151,6 +233,16 @@ impl AuthService {
             return Err(AppError::InvalidInput("invalid_device_id".to_string()));
         }
 
+        if !validate_fingerprint(&req.fingerprint) {
+            return Err(AppError::InvalidInput("invalid_fingerprint".to_string()));
+        }
+
+        let scopes = match &req.scopes {
+            Some(requested) => requested_user_scopes(requested)
+                .ok_or_else(|| AppError::InvalidInput("invalid_scopes".to_string()))?,
+            None => default_user_scopes(),
+        };
+
         // Get user
         let user = Self::get_user_by_email(client, &email)
             .await?
@@ -161,41 +253,61 @@ impl AuthService {
             .map_err(|e| AppError::InternalError(e))?;
 
         if !valid {
+            let mut event = AuditService::event(AuditAction::LoginFailed, meta);
+            event.device_id = req.device_id.clone();
+            AuditService::record(client, &user.user_id, event);
             return Err(AppError::AuthError("invalid_credentials".to_string()));
         }
 
-        // Update device if provided
+        // Move old bcrypt hashes to Argon2id while the password is at hand
         let now = chrono::Utc::now().timestamp();
+        if password_needs_rehash(&user.password_hash) {
+            Self::rehash_password(client, &user, &req.password, now).await;
+        }
+
+        // Update device if provided
+        let mut added_device = false;
         if let Some(device_id) = &req.device_id {
             let mut devices = load_user_devices(client, &user.user_id)
                 .await
                 .map_err(|e| AppError::InternalError(e.into()))?;
 
-            // Update or add device
-            if let Some(existing) = devices.devices.iter_mut().find(|d| d.device_id == *device_id) {
-                existing.last_seen = now;
-                existing.platform = Self::normalize_platform(req.platform.as_deref());
-                existing.device_name = Self::normalize_device_name(req.device_name.as_deref());
-            } else {
-                devices.devices.push(Device {
+            // Update or add device, replacing an older id for the same machine
+            let upsert = devices.upsert(
+                Device {
                     device_id: device_id.clone(),
                     platform: Self::normalize_platform(req.platform.as_deref()),
                     device_name: Self::normalize_device_name(req.device_name.as_deref()),
                     last_seen: now,
-                });
-            }
+                    fingerprint: req.fingerprint.clone(),
+                },
+                req.new_install,
+            );
+            added_device = upsert != DeviceUpsert::Updated;
 
             save_user_devices(client, &user.user_id, &devices)
                 .await
                 .map_err(|e| AppError::InternalError(e.into()))?;
         }
 
+        let mut event = AuditService::event(AuditAction::Login, meta);
+        event.device_id = req.device_id.clone();
+        AuditService::record(client, &user.user_id, event.clone());
+        if added_device {
+            event.action = AuditAction::DeviceAdded;
+            AuditService::record(client, &user.user_id, event);
+        }
+
         // Generate JWT
-        let exp = now + SESSION_TTL_SECONDS;
+        let exp = now + config::tunables().session_ttl_secs;
+        let session_id =
+            SessionService::create(client, &user.user_id, req.device_id.clone(), exp, meta).await?;
         let claims = Claims {
             user_id: user.user_id.clone(),
             device_id: req.device_id.clone(),
             exp,
+            scopes: scopes.clone(),
+            jti: Some(session_id),
         };
 
         let token = sign_jwt(&claims).map_err(|e| AppError::InternalError(e))?;
@@ -207,6 +319,8 @@ impl AuthService {
             exp,
             email: user.email,
             device_id: req.device_id,
+            email_verified: user.email_verified,
+            scopes,
         })
     }
 }
@@ -1,10 +1,15 @@
 use crate::{
     auth::AuthContext,
     error::AppError,
-    routes::device::{DeviceListResponse, DeviceResponse, RegisterDeviceRequest, RemoveDeviceRequest},
+    routes::account::RequestMeta,
+    routes::device::{
+        CheckDeviceRequest, CheckDeviceResponse, DeviceListResponse, DeviceResponse,
+        MergeDevicesRequest, MergeDevicesResponse, RegisterDeviceRequest, RemoveDeviceRequest,
+    },
+    services::audit::AuditService,
     storage::{load_user_devices, save_user_devices, S3Client},
-    types::Device,
-    validation::validate_device_id,
+    types::{AuditAction, Device, DeviceUpsert},
+    validation::{validate_device_id, validate_fingerprint},
 };
 use serde_json::json;
 
@@ -30,6 +35,7 @@ impl DeviceService {
     pub async fn register_device(
         client: &S3Client,
         auth: &AuthContext,
+        meta: &RequestMeta,
         req: RegisterDeviceRequest,
     ) -> Result<DeviceResponse, AppError> {
         let device_id = req.device_id.trim().to_string();
@@ -38,25 +44,27 @@ impl DeviceService {
             return Err(AppError::InvalidInput("invalid_device_id".to_string()));
         }
 
+        if !validate_fingerprint(&req.fingerprint) {
+            return Err(AppError::InvalidInput("invalid_fingerprint".to_string()));
+        }
+
         let mut devices = load_user_devices(client, &auth.user_id)
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
         let now = chrono::Utc::now().timestamp();
 
-        // Update or add device
-        if let Some(existing) = devices.devices.iter_mut().find(|d| d.device_id == device_id) {
-            existing.last_seen = now;
-            existing.platform = Self::normalize_platform(req.platform.as_deref());
-            existing.device_name = Self::normalize_device_name(req.device_name.as_deref());
-        } else {
-            devices.devices.push(Device {
+        // Update or add device, replacing an older id for the same machine
+        let upsert = devices.upsert(
+            Device {
                 device_id: device_id.clone(),
                 platform: Self::normalize_platform(req.platform.as_deref()),
                 device_name: Self::normalize_device_name(req.device_name.as_deref()),
                 last_seen: now,
-            });
-        }
+                fingerprint: req.fingerprint,
+            },
+            req.new_install,
+        );
 
         save_user_devices(client, &auth.user_id, &devices)
             .await
@@ -69,9 +77,47 @@ impl DeviceService {
             .unwrap()
             .clone();
 
+        if let DeviceUpsert::Replaced(previous) = &upsert {
+            tracing::info!(
+                "Device {} of user {} replaced {}",
+                device_id,
+                auth.user_id,
+                previous
+            );
+            let mut event = AuditService::event(AuditAction::DeviceRemoved, meta);
+            event.device_id = Some(previous.clone());
+            AuditService::record(client, &auth.user_id, event);
+        }
+        if upsert != DeviceUpsert::Updated {
+            let mut event = AuditService::event(AuditAction::DeviceAdded, meta);
+            event.device_id = Some(device_id);
+            AuditService::record(client, &auth.user_id, event);
+        }
+
         Ok(DeviceResponse { ok: true, device })
     }
 
+    pub async fn check_device(
+        client: &S3Client,
+        auth: &AuthContext,
+        req: CheckDeviceRequest,
+    ) -> Result<CheckDeviceResponse, AppError> {
+        let device_id = req.device_id.trim();
+
+        if !validate_device_id(&Some(device_id.to_string())) {
+            return Err(AppError::InvalidInput("invalid_device_id".to_string()));
+        }
+
+        let devices = load_user_devices(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        Ok(CheckDeviceResponse {
+            ok: true,
+            registered: devices.devices.iter().any(|d| d.device_id == device_id),
+        })
+    }
+
     pub async fn list_devices(
         client: &S3Client,
         auth: &AuthContext,
@@ -89,6 +135,7 @@ impl DeviceService {
     pub async fn remove_device(
         client: &S3Client,
         auth: &AuthContext,
+        meta: &RequestMeta,
         req: RemoveDeviceRequest,
     ) -> Result<serde_json::Value, AppError> {
         let device_id = req.device_id.trim();
@@ -119,6 +166,46 @@ impl DeviceService {
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
+        let mut event = AuditService::event(AuditAction::DeviceRemoved, meta);
+        event.device_id = Some(device_id.to_string());
+        AuditService::record(client, &auth.user_id, event);
+
         Ok(json!({ "ok": true }))
     }
+
+    /// Fold entries for the same machine into one. The calling device is
+    /// always the one kept from its group; entries matched only by name
+    /// need the user's confirmation.
+    pub async fn merge_devices(
+        client: &S3Client,
+        auth: &AuthContext,
+        meta: &RequestMeta,
+        req: MergeDevicesRequest,
+    ) -> Result<MergeDevicesResponse, AppError> {
+        let mut devices = load_user_devices(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let keep = auth.device_id.as_deref().or(req.keep.as_deref());
+        let outcome = devices.merge_duplicates(keep, &req.confirm);
+
+        if !req.dry_run && !outcome.merged.is_empty() {
+            save_user_devices(client, &auth.user_id, &devices)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?;
+
+            for device_id in outcome.merged.iter().flat_map(|group| &group.removed) {
+                let mut event = AuditService::event(AuditAction::DeviceRemoved, meta);
+                event.device_id = Some(device_id.clone());
+                AuditService::record(client, &auth.user_id, event);
+            }
+        }
+
+        Ok(MergeDevicesResponse {
+            ok: true,
+            merged: outcome.merged,
+            suggested: outcome.suggested,
+            devices: devices.devices,
+        })
+    }
 }
@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{config, lifecycle};
+
+pub struct MailService;
+
+impl MailService {
+    /// Whether `SMTP_HOST` is configured
+    pub fn is_configured() -> bool {
+        config::current().is_some_and(|config| config.smtp.is_some())
+    }
+
+    /// Send a plain text email
+    pub async fn send(to: &str, subject: &str, body: String) -> Result<()> {
+        let config = config::current().ok_or_else(|| anyhow!("config not initialized"))?;
+        let smtp = config
+            .smtp
+            .as_ref()
+            .ok_or_else(|| anyhow!("SMTP is not configured"))?;
+
+        let message = Message::builder()
+            .from(smtp.from.parse()?)
+            .to(to.parse()?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)?;
+
+        let mut transport = if smtp.implicit_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?
+        }
+        .port(smtp.port);
+        if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        transport.build().send(message).await?;
+        Ok(())
+    }
+
+    /// Send in the background so the response time doesn't depend on the
+    /// mail server, or reveal whether an address has an account
+    pub fn send_later(to: String, subject: &'static str, body: String) {
+        lifecycle::spawn_background(async move {
+            if let Err(err) = Self::send(&to, subject, body).await {
+                tracing::warn!("Failed to send \"{}\" email: {:#}", subject, err);
+            }
+        });
+    }
+}
@@ -1,3 +1,9 @@
+pub mod account;
+pub mod audit;
 pub mod auth;
 pub mod device;
+pub mod mail;
 pub mod save;
+pub mod session;
+pub mod share;
+pub mod storage;
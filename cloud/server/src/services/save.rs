@@ -1,52 +1,124 @@
 use crate::{
-    auth::{jwt::{sign_jwt, verify_jwt}, AuthContext},
+    auth::{worker_token, AuthContext},
+    config,
     error::AppError,
+    routes::account::RequestMeta,
     routes::save::{
-        DownloadUrlResponse, ListGamesResponse, ListSavesRequest, ListSavesResponse,
-        NotifyUploadRequest, SaveVersionDto, UploadUrlResponse,
+        DeleteSaveRequest, DownloadUrlResponse, LatestBatchRequest, LatestBatchResponse,
+        LinkVersionRequest, ListGamesResponse, ListSavesRequest, ListSavesResponse,
+        NotifyUploadRequest, PinSaveRequest, SaveExistsQuery, SaveExistsResponse, SaveVersionDto,
+        UploadUrlResponse,
+    },
+    services::{audit::AuditService, share::ShareService},
+    storage::{
+        get_plain_save_object_key, get_save_object_key, get_thumbnail_object_key,
+        load_consumed_worker_tokens, load_save_metadata, save_consumed_worker_tokens,
+        save_save_metadata, S3Client,
+    },
+    telemetry::{record_downloaded, record_uploaded},
+    types::{
+        ArchiveEncoding, AuditAction, ConsumedWorkerTokens, DownloadFilePayload, DownloadPayload,
+        SaveVersion, UploadPayload, WorkerTokenClaims,
     },
-    storage::{get_save_object_key, load_save_metadata, save_save_metadata, S3Client},
-    types::{Claims, DownloadPayload, SaveVersion, UploadPayload, WorkerTokenClaims},
     validation::{
-        validate_file_list, validate_game_id, validate_sha256, validate_size_bytes,
+        validate_archive_path, validate_file_list, validate_game_id, validate_note,
+        validate_parent_version_id, validate_sha256, validate_size_bytes, validate_tags,
         validate_version_id,
     },
 };
 use serde_json::json;
-
-const PRESIGN_TTL_SECONDS: u64 = 300; // 5 minutes
-const WORKER_TOKEN_TTL_SECONDS: i64 = 60; // 1 minute
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::sync::{Arc, OnceLock, PoisonError};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Serialize notify-uploads between reading and saving the consumed
+/// worker token list and the save metadata, which are one object per user,
+/// so they only wait on uploads touching the same user
+static USER_LOCKS: OnceLock<std::sync::Mutex<HashMap<String, Arc<Mutex<()>>>>> = OnceLock::new();
+
+const ARCHIVE_CONTENT_TYPE: &str = "application/zip";
+const THUMBNAIL_CONTENT_TYPE: &str = "image/png";
+/// Most game IDs one latest-batch request may name
+const MAX_BATCH_GAMES: usize = 10_000;
+/// Largest single file `download_file` inflates, whatever its header claims
+const MAX_FILE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// One file read out of a stored archive
+pub struct ArchiveFile {
+    pub data: Vec<u8>,
+    pub crc32: u32,
+}
 
 pub struct SaveService;
 
 impl SaveService {
     /// Sign worker token
     fn sign_worker_token(claims: &WorkerTokenClaims) -> Result<String, AppError> {
-        // Reuse JWT infrastructure with worker token payload
-        let jwt_claims = Claims {
-            user_id: serde_json::to_string(claims).map_err(|e| AppError::InternalError(e.into()))?,
-            device_id: None,
-            exp: claims.exp,
-        };
-
-        sign_jwt(&jwt_claims).map_err(|e| AppError::InternalError(e))
+        worker_token::sign_worker_token(claims).map_err(AppError::InternalError)
     }
 
     /// Verify worker token
     fn verify_worker_token(token: &str) -> Result<WorkerTokenClaims, AppError> {
-        let claims = verify_jwt(token).map_err(|_| AppError::AuthError("invalid_worker_token".to_string()))?;
-        let worker_claims: WorkerTokenClaims = serde_json::from_str(&claims.user_id)
-            .map_err(|_| AppError::AuthError("invalid_worker_token".to_string()))?;
+        worker_token::verify_worker_token(token).map_err(|err| {
+            tracing::warn!("Rejected worker token: {}", err);
+            AppError::AuthError("invalid_worker_token".to_string())
+        })
+    }
 
-        Ok(worker_claims)
+    /// Lock `user_ids`, always in the same order so two uploads naming the
+    /// same users can't each hold one lock the other waits for
+    async fn lock_users(user_ids: &[&str]) -> Vec<OwnedMutexGuard<()>> {
+        let mut user_ids = user_ids.to_vec();
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let locks: Vec<Arc<Mutex<()>>> = {
+            let mut locks = USER_LOCKS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            // Forget locks nobody holds or waits on
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            user_ids
+                .iter()
+                .map(|user_id| locks.entry(user_id.to_string()).or_default().clone())
+                .collect()
+        };
+
+        let mut guards = Vec::with_capacity(locks.len());
+        for lock in locks {
+            guards.push(lock.lock_owned().await);
+        }
+        guards
     }
 
-    pub async fn get_upload_url(
+    /// The user's consumed token IDs with this token's added, for the
+    /// caller to save once the upload is recorded. Fails if the token was
+    /// used before. Callers hold `lock_users` until the list is saved.
+    async fn claim_worker_token(
         client: &S3Client,
-        auth: &AuthContext,
-        payload: UploadPayload,
-    ) -> Result<UploadUrlResponse, AppError> {
-        // Validate payload
+        user_id: &str,
+        claims: &WorkerTokenClaims,
+    ) -> Result<ConsumedWorkerTokens, AppError> {
+        let Some(jti) = claims.jti.as_deref().filter(|jti| !jti.is_empty()) else {
+            tracing::warn!("Rejected worker token without jti");
+            return Err(AppError::AuthError("invalid_worker_token".to_string()));
+        };
+
+        let mut consumed = load_consumed_worker_tokens(client, user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        if !consumed.consume(jti, claims.exp, chrono::Utc::now().timestamp()) {
+            tracing::warn!("Rejected replayed worker token for user {}", user_id);
+            return Err(AppError::AuthError("worker_token_used".to_string()));
+        }
+        Ok(consumed)
+    }
+
+    /// Check an upload-url request before anything is presigned
+    pub fn validate_upload_payload(payload: &UploadPayload) -> Result<(), AppError> {
         if !validate_game_id(&payload.game_id) || !validate_version_id(&payload.version_id) {
             return Err(AppError::InvalidInput("invalid_payload".to_string()));
         }
@@ -59,22 +131,121 @@ impl SaveService {
             return Err(AppError::InvalidInput("invalid_file_list".to_string()));
         }
 
-        let object_key = get_save_object_key(&auth.user_id, &payload.game_id, &payload.version_id);
+        if let Some(thumbnail_sha256) = &payload.thumbnail_sha256 {
+            if !validate_sha256(thumbnail_sha256) {
+                return Err(AppError::InvalidInput("invalid_payload".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check a notify-upload request before its worker token is verified
+    pub fn validate_notify_request(req: &NotifyUploadRequest) -> Result<(), AppError> {
+        if !validate_game_id(&req.game_id) || !validate_version_id(&req.version_id) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        if !validate_sha256(&req.sha256) || !validate_size_bytes(req.size_bytes) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        if !validate_note(&req.note) || !validate_tags(&req.tags) {
+            return Err(AppError::InvalidInput("invalid_label".to_string()));
+        }
+
+        if !validate_parent_version_id(&req.parent_version_id, &req.version_id) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Newest version of each game among `versions`, keyed by game ID
+    fn latest_per_game<'a>(
+        versions: impl Iterator<Item = &'a SaveVersion>,
+    ) -> BTreeMap<&'a str, &'a SaveVersion> {
+        let mut latest: BTreeMap<&str, &SaveVersion> = BTreeMap::new();
+        for v in versions {
+            let current = latest.entry(v.game_id.as_str()).or_insert(v);
+            if v.timestamp > current.timestamp {
+                *current = v;
+            }
+        }
+        latest
+    }
+
+    /// Presigned GET URL for a version's thumbnail, if it has one
+    async fn thumbnail_url(
+        client: &S3Client,
+        user_id: &str,
+        version: &SaveVersion,
+    ) -> Option<String> {
+        if version.thumbnail_sha256.is_none() {
+            return None;
+        }
+
+        let key = get_thumbnail_object_key(user_id, &version.game_id, &version.version_id);
+        let ttl = config::tunables().presign_ttl_secs;
+        match client.presign_get(&key, ttl).await {
+            Ok(url) => Some(url),
+            Err(err) => {
+                tracing::warn!("Failed to presign thumbnail {}: {}", key, err);
+                None
+            }
+        }
+    }
+
+    pub async fn get_upload_url(
+        client: &S3Client,
+        auth: &AuthContext,
+        payload: UploadPayload,
+    ) -> Result<UploadUrlResponse, AppError> {
+        Self::validate_upload_payload(&payload)?;
+
+        let owner_id = ShareService::slot_owner(client, auth, &payload.game_id, true).await?;
+        let object_key = get_save_object_key(&owner_id, &payload.game_id, &payload.version_id);
+        let tunables = config::tunables();
 
         // Generate presigned URL
+        let content_type = payload
+            .encoding
+            .map_or(ARCHIVE_CONTENT_TYPE, ArchiveEncoding::content_type);
         let upload_url = client
-            .presign_put(&object_key, PRESIGN_TTL_SECONDS)
+            .presign_put(&object_key, content_type, tunables.presign_ttl_secs)
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
-        // Generate worker token for notify-upload verification
+        // Thumbnails go next to the archive under the same version ID
+        let thumbnail_upload_url = match payload.thumbnail_sha256 {
+            Some(_) => {
+                let thumbnail_key =
+                    get_thumbnail_object_key(&owner_id, &payload.game_id, &payload.version_id);
+                Some(
+                    client
+                        .presign_put(
+                            &thumbnail_key,
+                            THUMBNAIL_CONTENT_TYPE,
+                            tunables.presign_ttl_secs,
+                        )
+                        .await
+                        .map_err(|e| AppError::InternalError(e.into()))?,
+                )
+            }
+            None => None,
+        };
+
+        // Generate worker token for notify-upload verification. It stays
+        // tied to the uploader; only the key points into a shared slot.
         let now = chrono::Utc::now().timestamp();
         let worker_claims = WorkerTokenClaims {
             user_id: auth.user_id.clone(),
             device_id: auth.device_id.clone(),
             r2_key: object_key.clone(),
             version_id: payload.version_id.clone(),
-            exp: now + WORKER_TOKEN_TTL_SECONDS,
+            exp: now + tunables.worker_token_ttl_secs,
+            jti: Some(uuid::Uuid::new_v4().to_string()),
+            encoding: payload.encoding,
         };
 
         let worker_token = Self::sign_worker_token(&worker_claims)?;
@@ -85,32 +256,29 @@ impl SaveService {
             r2_key: object_key,
             version_id: payload.version_id,
             worker_token,
+            thumbnail_upload_url,
         })
     }
 
     pub async fn notify_upload(
         client: &S3Client,
         auth: &AuthContext,
+        meta: &RequestMeta,
         req: NotifyUploadRequest,
     ) -> Result<serde_json::Value, AppError> {
-        // Validate payload
-        if !validate_game_id(&req.game_id) || !validate_version_id(&req.version_id) {
-            return Err(AppError::InvalidInput("invalid_payload".to_string()));
-        }
-
-        if !validate_sha256(&req.sha256) || !validate_size_bytes(req.size_bytes) {
-            return Err(AppError::InvalidInput("invalid_payload".to_string()));
-        }
+        Self::validate_notify_request(&req)?;
 
         // Verify worker token
         let worker_claims = Self::verify_worker_token(&req.worker_token)?;
 
-        let object_key = get_save_object_key(&auth.user_id, &req.game_id, &req.version_id);
+        let owner_id = ShareService::slot_owner(client, auth, &req.game_id, true).await?;
+        let object_key = get_save_object_key(&owner_id, &req.game_id, &req.version_id);
 
         // Verify token matches request
         if worker_claims.user_id != auth.user_id
             || worker_claims.version_id != req.version_id
             || worker_claims.r2_key != object_key
+            || worker_claims.encoding != req.encoding
         {
             return Err(AppError::AuthError("invalid_worker_token".to_string()));
         }
@@ -125,13 +293,39 @@ impl SaveService {
             return Err(AppError::NotFound("upload_missing".to_string()));
         }
 
+        // A missing thumbnail never fails the upload; the version is stored without one
+        let thumbnail_sha256 = match req.thumbnail_sha256 {
+            Some(sha256) if validate_sha256(&sha256) => {
+                let thumbnail_key =
+                    get_thumbnail_object_key(&owner_id, &req.game_id, &req.version_id);
+                let uploaded = client
+                    .head_object(&thumbnail_key)
+                    .await
+                    .map_err(|e| AppError::InternalError(e.into()))?;
+                if uploaded {
+                    Some(sha256)
+                } else {
+                    tracing::warn!("Thumbnail missing for version {}", req.version_id);
+                    None
+                }
+            }
+            Some(_) => return Err(AppError::InvalidInput("invalid_payload".to_string())),
+            None => None,
+        };
+
+        // Held until the token is recorded, so a replay racing this notify
+        // waits and then finds the token used
+        let token_guard = Self::lock_users(&[auth.user_id.as_str(), owner_id.as_str()]).await;
+        let consumed = Self::claim_worker_token(client, &auth.user_id, &worker_claims).await?;
+
         // Load current metadata
-        let mut metadata = load_save_metadata(client, &auth.user_id)
+        let mut metadata = load_save_metadata(client, &owner_id)
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
         // Create new version entry
         let now = chrono::Utc::now().timestamp();
+        let device_id = req.device_id.or(auth.device_id.clone());
         let entry = SaveVersion {
             version_id: req.version_id.clone(),
             game_id: req.game_id.clone(),
@@ -139,8 +333,15 @@ impl SaveService {
             sha256: req.sha256,
             file_list: req.file_list,
             emulator_id: req.emulator_id,
-            device_id: req.device_id.or(auth.device_id.clone()),
+            device_id: device_id.clone(),
             timestamp: now,
+            thumbnail_sha256,
+            note: req.note.filter(|note| !note.trim().is_empty()),
+            tags: req.tags,
+            pinned: req.pinned,
+            parent_version_id: req.parent_version_id,
+            encrypted: req.encrypted,
+            encoding: req.encoding,
         };
 
         // Remove existing version with same ID and prepend new one
@@ -148,10 +349,146 @@ impl SaveService {
         metadata.versions.insert(0, entry);
 
         // Save metadata
-        save_save_metadata(client, &auth.user_id, &metadata)
+        save_save_metadata(client, &owner_id, &metadata)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        // Recorded only once the version is saved, so a notify that fails
+        // before this point can still be retried with the same token
+        save_consumed_worker_tokens(client, &auth.user_id, &consumed)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        drop(token_guard);
+
+        // Uploads into a shared slot land in the owner's log, naming the uploader
+        record_uploaded(req.size_bytes);
+        let mut event = AuditService::event(AuditAction::VersionUploaded, meta);
+        event.device_id = device_id;
+        event.game_id = Some(req.game_id);
+        event.version_id = Some(req.version_id);
+        if owner_id != auth.user_id {
+            event.other_user_id = Some(auth.user_id.clone());
+        }
+        AuditService::record(client, &owner_id, event);
+
+        Ok(json!({ "ok": true }))
+    }
+
+    /// Newest version of a game whose archive has `sha256`, so a client can
+    /// link a new version to it instead of uploading the same bytes again
+    pub async fn find_by_hash(
+        client: &S3Client,
+        auth: &AuthContext,
+        query: SaveExistsQuery,
+    ) -> Result<SaveExistsResponse, AppError> {
+        if !validate_game_id(&query.game_id) || !validate_sha256(&query.sha256) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        let owner_id = ShareService::slot_owner(client, auth, &query.game_id, false).await?;
+        let metadata = load_save_metadata(client, &owner_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let version_id = metadata
+            .versions
+            .iter()
+            .filter(|v| v.game_id == query.game_id && v.sha256.eq_ignore_ascii_case(&query.sha256))
+            .max_by_key(|v| v.timestamp)
+            .map(|v| v.version_id.clone());
+
+        Ok(SaveExistsResponse {
+            ok: true,
+            exists: version_id.is_some(),
+            version_id,
+        })
+    }
+
+    /// Record a new version whose archive is identical to an existing one's.
+    /// The archive is copied inside the bucket so the two versions stay
+    /// independent for deletion and retention. Thumbnails aren't carried
+    /// over; they belong to the version they were taken for.
+    pub async fn link_version(
+        client: &S3Client,
+        auth: &AuthContext,
+        meta: &RequestMeta,
+        req: LinkVersionRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        if !validate_game_id(&req.game_id)
+            || !validate_version_id(&req.version_id)
+            || !validate_version_id(&req.source_version_id)
+            || !validate_sha256(&req.sha256)
+            || !validate_parent_version_id(&req.parent_version_id, &req.version_id)
+        {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+        if !validate_note(&req.note) || !validate_tags(&req.tags) {
+            return Err(AppError::InvalidInput("invalid_label".to_string()));
+        }
+
+        let owner_id = ShareService::slot_owner(client, auth, &req.game_id, true).await?;
+        let mut metadata = load_save_metadata(client, &owner_id)
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
+        let source = metadata
+            .versions
+            .iter()
+            .find(|v| {
+                v.game_id == req.game_id
+                    && v.version_id == req.source_version_id
+                    && v.sha256.eq_ignore_ascii_case(&req.sha256)
+            })
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("version_not_found".to_string()))?;
+
+        // A retried link whose first attempt went through
+        if source.version_id == req.version_id {
+            return Ok(json!({ "ok": true }));
+        }
+
+        let source_key = get_save_object_key(&owner_id, &req.game_id, &source.version_id);
+        let object_key = get_save_object_key(&owner_id, &req.game_id, &req.version_id);
+        client
+            .copy_object(&source_key, &object_key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let device_id = req.device_id.or(auth.device_id.clone());
+        let entry = SaveVersion {
+            version_id: req.version_id.clone(),
+            game_id: req.game_id.clone(),
+            size_bytes: source.size_bytes,
+            sha256: source.sha256,
+            file_list: source.file_list,
+            emulator_id: req.emulator_id,
+            device_id: device_id.clone(),
+            timestamp: chrono::Utc::now().timestamp(),
+            thumbnail_sha256: None,
+            note: req.note.filter(|note| !note.trim().is_empty()),
+            tags: req.tags,
+            pinned: req.pinned,
+            parent_version_id: req.parent_version_id,
+            // Same archive, so the same saves
+            encrypted: source.encrypted,
+            encoding: source.encoding,
+        };
+
+        metadata.versions.retain(|v| v.version_id != req.version_id);
+        metadata.versions.insert(0, entry);
+        save_save_metadata(client, &owner_id, &metadata)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let mut event = AuditService::event(AuditAction::VersionUploaded, meta);
+        event.device_id = device_id;
+        event.game_id = Some(req.game_id);
+        event.version_id = Some(req.version_id);
+        if owner_id != auth.user_id {
+            event.other_user_id = Some(auth.user_id.clone());
+        }
+        AuditService::record(client, &owner_id, event);
+
         Ok(json!({ "ok": true }))
     }
 
@@ -166,7 +503,8 @@ impl SaveService {
         }
 
         // Load metadata
-        let metadata = load_save_metadata(client, &auth.user_id)
+        let owner_id = ShareService::slot_owner(client, auth, &payload.game_id, false).await?;
+        let metadata = load_save_metadata(client, &owner_id)
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
@@ -177,7 +515,7 @@ impl SaveService {
             .find(|v| v.version_id == payload.version_id && v.game_id == payload.game_id)
             .ok_or_else(|| AppError::NotFound("version_not_found".to_string()))?;
 
-        let object_key = get_save_object_key(&auth.user_id, &payload.game_id, &payload.version_id);
+        let object_key = get_save_object_key(&owner_id, &payload.game_id, &payload.version_id);
 
         // Verify object exists
         let exists = client
@@ -189,12 +527,27 @@ impl SaveService {
             return Err(AppError::NotFound("object_missing".to_string()));
         }
 
+        // Clients that can't decode the stored encoding get a plain zip
+        let encoding = version
+            .encoding
+            .filter(|encoding| payload.accept_encoding.contains(encoding));
+        let download_key = match version.encoding {
+            Some(ArchiveEncoding::Zstd) if encoding.is_none() => {
+                Self::plain_archive(client, &owner_id, version, &object_key).await?
+            }
+            _ => object_key.clone(),
+        };
+
         // Generate presigned URL
         let download_url = client
-            .presign_get(&object_key, PRESIGN_TTL_SECONDS)
+            .presign_get(&download_key, config::tunables().presign_ttl_secs)
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
+        let thumbnail_url = Self::thumbnail_url(client, &owner_id, version).await;
+
+        // Presigned downloads bypass the server, so the archive size stands in
+        record_downloaded(version.size_bytes);
         Ok(DownloadUrlResponse {
             ok: true,
             download_url,
@@ -206,9 +559,128 @@ impl SaveService {
             file_list: version.file_list.clone(),
             emulator_id: version.emulator_id.clone(),
             timestamp: version.timestamp,
+            thumbnail_url,
+            note: version.note.clone(),
+            tags: version.tags.clone(),
+            pinned: version.pinned,
+            parent_version_id: version.parent_version_id.clone(),
+            encrypted: version.encrypted,
+            encoding,
         })
     }
 
+    /// Key of a plain zip copy of a zstd-encoded version, decoded and
+    /// stored on first use so later downloads by the same clients reuse it
+    async fn plain_archive(
+        client: &S3Client,
+        owner_id: &str,
+        version: &SaveVersion,
+        object_key: &str,
+    ) -> Result<String, AppError> {
+        let plain_key = get_plain_save_object_key(owner_id, &version.game_id, &version.version_id);
+        let exists = client
+            .head_object(&plain_key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        if exists {
+            return Ok(plain_key);
+        }
+
+        let archive = client
+            .get_object(object_key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?
+            .ok_or_else(|| AppError::NotFound("object_missing".to_string()))?;
+        let archive = Self::decode_zstd(archive, version.size_bytes).await?;
+        client
+            .put_object(&plain_key, archive)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        Ok(plain_key)
+    }
+
+    /// Read one file out of a version's archive, so clients can fetch a
+    /// single save without downloading the rest
+    pub async fn download_file(
+        client: &S3Client,
+        auth: &AuthContext,
+        payload: DownloadFilePayload,
+    ) -> Result<ArchiveFile, AppError> {
+        if !validate_game_id(&payload.game_id)
+            || !validate_version_id(&payload.version_id)
+            || !validate_archive_path(&payload.path)
+        {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        let owner_id = ShareService::slot_owner(client, auth, &payload.game_id, false).await?;
+        let metadata = load_save_metadata(client, &owner_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        let version = metadata
+            .versions
+            .iter()
+            .find(|v| v.version_id == payload.version_id && v.game_id == payload.game_id)
+            .ok_or_else(|| AppError::NotFound("version_not_found".to_string()))?;
+        if !version.file_list.iter().any(|file| file == &payload.path) {
+            return Err(AppError::NotFound("file_not_found".to_string()));
+        }
+
+        let object_key = get_save_object_key(&owner_id, &payload.game_id, &payload.version_id);
+        let archive = client
+            .get_object(&object_key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?
+            .ok_or_else(|| AppError::NotFound("object_missing".to_string()))?;
+
+        let archive = match version.encoding {
+            Some(ArchiveEncoding::Zstd) => Self::decode_zstd(archive, version.size_bytes).await?,
+            None => archive,
+        };
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(archive))
+            .map_err(|_| AppError::InvalidInput("invalid_archive".to_string()))?;
+        let mut entry = archive
+            .by_name(&payload.path)
+            .map_err(|_| AppError::NotFound("file_not_found".to_string()))?;
+        if entry.is_dir() {
+            return Err(AppError::NotFound("file_not_found".to_string()));
+        }
+
+        let crc32 = entry.crc32();
+        let mut data = Vec::new();
+        (&mut entry)
+            .take(MAX_FILE_BYTES + 1)
+            .read_to_end(&mut data)
+            .map_err(|_| AppError::InvalidInput("invalid_archive".to_string()))?;
+        if data.len() as u64 > MAX_FILE_BYTES {
+            return Err(AppError::InvalidInput("file_too_large".to_string()));
+        }
+
+        record_downloaded(data.len() as u64);
+        Ok(ArchiveFile { data, crc32 })
+    }
+
+    /// Undo the upload's zstd layer, refusing to inflate past the zip size
+    /// the uploader declared
+    async fn decode_zstd(data: Vec<u8>, size_bytes: u64) -> Result<Vec<u8>, AppError> {
+        tokio::task::spawn_blocking(move || {
+            let decoder = zstd::stream::read::Decoder::new(data.as_slice())
+                .map_err(|_| AppError::InvalidInput("invalid_archive".to_string()))?;
+            let mut archive = Vec::new();
+            decoder
+                .take(size_bytes + 1)
+                .read_to_end(&mut archive)
+                .map_err(|_| AppError::InvalidInput("invalid_archive".to_string()))?;
+            if archive.len() as u64 != size_bytes {
+                return Err(AppError::InvalidInput("invalid_archive".to_string()));
+            }
+            Ok(archive)
+        })
+        .await
+        .map_err(|e| AppError::InternalError(e.into()))?
+    }
+
     pub async fn list_saves(
         client: &S3Client,
         auth: &AuthContext,
@@ -226,22 +698,28 @@ impl SaveService {
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
-        // Filter by partial game ID match (case-insensitive)
+        // Filter by partial game ID match (case-insensitive). Games shared
+        // with the caller come from the owner's slot instead.
+        let shared = ShareService::received(client, &auth.user_id).await?;
         let search_lower = game_id.to_lowercase();
-        let mut versions: Vec<SaveVersionDto> = metadata
-            .versions
+        let mut versions: Vec<SaveVersionDto> = Vec::new();
+        for v in metadata.versions.iter().filter(|v| {
+            v.game_id.to_lowercase().contains(&search_lower)
+                && !shared.iter().any(|grant| grant.game_id == v.game_id)
+        }) {
+            versions.push(Self::version_dto(client, &auth.user_id, v).await);
+        }
+        for grant in shared
             .iter()
-            .filter(|v| v.game_id.to_lowercase().contains(&search_lower))
-            .map(|v| SaveVersionDto {
-                version_id: v.version_id.clone(),
-                game_id: v.game_id.clone(),
-                size_bytes: v.size_bytes,
-                timestamp: v.timestamp,
-                device_id: v.device_id.clone(),
-                sha256: v.sha256.clone(),
-                file_list: v.file_list.clone(),
-            })
-            .collect();
+            .filter(|grant| grant.game_id.to_lowercase().contains(&search_lower))
+        {
+            let owned = load_save_metadata(client, &grant.owner_user_id)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?;
+            for v in owned.versions.iter().filter(|v| v.game_id == grant.game_id) {
+                versions.push(Self::version_dto(client, &grant.owner_user_id, v).await);
+            }
+        }
 
         // Sort by timestamp descending
         versions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
@@ -253,6 +731,190 @@ impl SaveService {
         })
     }
 
+    /// Latest version of each requested game, or of every game, in one
+    /// response; games without a version are left out
+    pub async fn latest_versions(
+        client: &S3Client,
+        auth: &AuthContext,
+        req: LatestBatchRequest,
+    ) -> Result<LatestBatchResponse, AppError> {
+        let wanted: Option<HashSet<String>> = match req.game_ids {
+            Some(game_ids) => {
+                if game_ids.len() > MAX_BATCH_GAMES {
+                    return Err(AppError::InvalidInput("invalid_payload".to_string()));
+                }
+                // IDs no upload could have used simply match nothing
+                Some(game_ids.into_iter().collect())
+            }
+            None => None,
+        };
+
+        // Load metadata
+        let metadata = load_save_metadata(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let is_wanted = |game_id: &String| {
+            wanted
+                .as_ref()
+                .map_or(true, |wanted| wanted.contains(game_id))
+        };
+        let shared = ShareService::received(client, &auth.user_id).await?;
+        let latest = Self::latest_per_game(metadata.versions.iter().filter(|v| {
+            is_wanted(&v.game_id) && !shared.iter().any(|grant| grant.game_id == v.game_id)
+        }));
+
+        let mut versions = BTreeMap::new();
+        for v in latest.into_values() {
+            let dto = Self::version_dto(client, &auth.user_id, v).await;
+            versions.insert(v.game_id.clone(), dto);
+        }
+        // Shared games report the owner's latest version
+        for grant in shared.iter().filter(|grant| is_wanted(&grant.game_id)) {
+            let owned = load_save_metadata(client, &grant.owner_user_id)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?;
+            let latest =
+                Self::latest_per_game(owned.versions.iter().filter(|v| v.game_id == grant.game_id));
+            if let Some(v) = latest.into_values().next() {
+                let dto = Self::version_dto(client, &grant.owner_user_id, v).await;
+                versions.insert(v.game_id.clone(), dto);
+            };
+        }
+
+        Ok(LatestBatchResponse {
+            ok: true,
+            versions: versions.into_values().collect(),
+        })
+    }
+
+    async fn version_dto(client: &S3Client, user_id: &str, v: &SaveVersion) -> SaveVersionDto {
+        SaveVersionDto {
+            version_id: v.version_id.clone(),
+            game_id: v.game_id.clone(),
+            size_bytes: v.size_bytes,
+            timestamp: v.timestamp,
+            device_id: v.device_id.clone(),
+            sha256: v.sha256.clone(),
+            file_list: v.file_list.clone(),
+            thumbnail_url: Self::thumbnail_url(client, user_id, v).await,
+            note: v.note.clone(),
+            tags: v.tags.clone(),
+            pinned: v.pinned,
+            parent_version_id: v.parent_version_id.clone(),
+            encrypted: v.encrypted,
+        }
+    }
+
+    pub async fn delete_version(
+        client: &S3Client,
+        auth: &AuthContext,
+        meta: &RequestMeta,
+        req: DeleteSaveRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        // Validate payload
+        if !validate_game_id(&req.game_id) || !validate_version_id(&req.version_id) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        // Only the owner removes versions from a shared slot
+        let owner_id = ShareService::slot_owner(client, auth, &req.game_id, true).await?;
+        if owner_id != auth.user_id {
+            return Err(AppError::Forbidden("shared_slot".to_string()));
+        }
+
+        // Load metadata
+        let mut metadata = load_save_metadata(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let version = metadata
+            .versions
+            .iter()
+            .find(|v| v.version_id == req.version_id && v.game_id == req.game_id)
+            .ok_or_else(|| AppError::NotFound("version_not_found".to_string()))?;
+
+        // Pinned versions must be unpinned before they can be deleted
+        if version.pinned {
+            return Err(AppError::Conflict("version_pinned".to_string()));
+        }
+
+        let object_key = get_save_object_key(&auth.user_id, &req.game_id, &req.version_id);
+        client
+            .delete_object(&object_key)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        if version.thumbnail_sha256.is_some() {
+            let thumbnail_key =
+                get_thumbnail_object_key(&auth.user_id, &req.game_id, &req.version_id);
+            if let Err(err) = client.delete_object(&thumbnail_key).await {
+                tracing::warn!("Failed to delete thumbnail {}: {}", thumbnail_key, err);
+            }
+        }
+
+        if version.encoding.is_some() {
+            let plain_key = get_plain_save_object_key(&auth.user_id, &req.game_id, &req.version_id);
+            if let Err(err) = client.delete_object(&plain_key).await {
+                tracing::warn!("Failed to delete plain archive {}: {}", plain_key, err);
+            }
+        }
+
+        metadata
+            .versions
+            .retain(|v| !(v.version_id == req.version_id && v.game_id == req.game_id));
+
+        // Save metadata
+        save_save_metadata(client, &auth.user_id, &metadata)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let mut event = AuditService::event(AuditAction::VersionDeleted, meta);
+        event.device_id = auth.device_id.clone();
+        event.game_id = Some(req.game_id);
+        event.version_id = Some(req.version_id);
+        AuditService::record(client, &auth.user_id, event);
+
+        Ok(json!({ "ok": true }))
+    }
+
+    /// Mirror a client's pin, which keeps the version from being deleted
+    pub async fn set_pinned(
+        client: &S3Client,
+        auth: &AuthContext,
+        req: PinSaveRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        // Validate payload
+        if !validate_game_id(&req.game_id) || !validate_version_id(&req.version_id) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        // Pins guard deletes, which only the owner of a shared slot makes
+        let owner_id = ShareService::slot_owner(client, auth, &req.game_id, true).await?;
+        if owner_id != auth.user_id {
+            return Err(AppError::Forbidden("shared_slot".to_string()));
+        }
+
+        let mut metadata = load_save_metadata(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let version = metadata
+            .versions
+            .iter_mut()
+            .find(|v| v.version_id == req.version_id && v.game_id == req.game_id)
+            .ok_or_else(|| AppError::NotFound("version_not_found".to_string()))?;
+
+        if version.pinned != req.pinned {
+            version.pinned = req.pinned;
+            save_save_metadata(client, &auth.user_id, &metadata)
+                .await
+                .map_err(|e| AppError::InternalError(e.into()))?;
+        }
+
+        Ok(json!({ "ok": true }))
+    }
+
     pub async fn list_games(
         client: &S3Client,
         auth: &AuthContext,
@@ -262,11 +924,17 @@ impl SaveService {
             .await
             .map_err(|e| AppError::InternalError(e.into()))?;
 
-        // Extract unique game IDs
+        // Extract unique game IDs, along with games shared with the caller
+        let shared: Vec<String> = ShareService::received(client, &auth.user_id)
+            .await?
+            .into_iter()
+            .map(|grant| grant.game_id)
+            .collect();
         let mut game_ids: Vec<String> = metadata
             .versions
             .iter()
             .map(|v| v.game_id.clone())
+            .chain(shared.iter().cloned())
             .collect::<std::collections::HashSet<_>>()
             .into_iter()
             .collect();
@@ -276,6 +944,7 @@ impl SaveService {
         Ok(ListGamesResponse {
             ok: true,
             games: game_ids,
+            shared,
         })
     }
 }
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as StdMutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    routes::{
+        account::RequestMeta,
+        session::{RevokeSessionRequest, SessionDto, SessionListResponse},
+    },
+    services::audit::AuditService,
+    storage::{load_user_sessions, save_user_sessions, S3Client},
+    types::{AuditAction, Claims, Session, UserSessions},
+};
+
+/// How long a session list is trusted before it's read again. Revocations
+/// made on this server apply at once; ones made elsewhere within this time.
+const SESSION_CACHE_TTL: Duration = Duration::from_secs(30);
+/// Oldest sessions are dropped past this many per user
+const MAX_SESSIONS: usize = 100;
+
+/// Serializes read-modify-write of session lists on this server, so two
+/// logins at once can't drop each other's session
+static WRITE_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+static CACHE: OnceLock<StdMutex<HashMap<String, (Instant, Arc<UserSessions>)>>> = OnceLock::new();
+
+fn cache() -> &'static StdMutex<HashMap<String, (Instant, Arc<UserSessions>)>> {
+    CACHE.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+pub struct SessionService;
+
+impl SessionService {
+    async fn load(client: &S3Client, user_id: &str) -> anyhow::Result<Arc<UserSessions>> {
+        if let Some((loaded, sessions)) = cache()
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .get(user_id)
+        {
+            if loaded.elapsed() < SESSION_CACHE_TTL {
+                return Ok(sessions.clone());
+            }
+        }
+
+        let sessions = Arc::new(load_user_sessions(client, user_id).await?);
+        cache()
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .insert(user_id.to_string(), (Instant::now(), sessions.clone()));
+        Ok(sessions)
+    }
+
+    /// Apply `change` to the stored list, dropping expired sessions
+    async fn update<F>(client: &S3Client, user_id: &str, change: F) -> Result<(), AppError>
+    where
+        F: FnOnce(&mut UserSessions),
+    {
+        let _guard = WRITE_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+        let mut sessions = load_user_sessions(client, user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let now = chrono::Utc::now().timestamp();
+        sessions.sessions.retain(|session| session.expires_at > now);
+        change(&mut sessions);
+        if sessions.sessions.len() > MAX_SESSIONS {
+            sessions.sessions.sort_by_key(|session| session.created_at);
+            let excess = sessions.sessions.len() - MAX_SESSIONS;
+            sessions.sessions.drain(..excess);
+        }
+
+        save_user_sessions(client, user_id, &sessions)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        cache()
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .insert(user_id.to_string(), (Instant::now(), Arc::new(sessions)));
+        Ok(())
+    }
+
+    /// Record a new session for a token about to be issued and return its ID
+    pub async fn create(
+        client: &S3Client,
+        user_id: &str,
+        device_id: Option<String>,
+        expires_at: i64,
+        meta: &RequestMeta,
+    ) -> Result<String, AppError> {
+        let session = Session {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            device_id,
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at,
+            ip: meta.ip.clone(),
+            user_agent: meta.user_agent.clone(),
+        };
+        let session_id = session.session_id.clone();
+        Self::update(client, user_id, |sessions| sessions.sessions.push(session)).await?;
+        Ok(session_id)
+    }
+
+    /// Whether the token's session wasn't revoked
+    pub async fn is_active(client: &S3Client, claims: &Claims) -> anyhow::Result<bool> {
+        let Some(jti) = &claims.jti else {
+            return Ok(true);
+        };
+        let sessions = Self::load(client, &claims.user_id).await?;
+        Ok(sessions
+            .sessions
+            .iter()
+            .any(|session| session.session_id == *jti))
+    }
+
+    /// Revoke every session, e.g. after a password change
+    pub async fn revoke_all(client: &S3Client, user_id: &str) -> Result<(), AppError> {
+        Self::update(client, user_id, |sessions| sessions.sessions.clear()).await
+    }
+
+    pub async fn list_sessions(
+        client: &S3Client,
+        auth: &AuthContext,
+    ) -> Result<SessionListResponse, AppError> {
+        let now = chrono::Utc::now().timestamp();
+        let sessions = Self::load(client, &auth.user_id)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        let mut sessions: Vec<SessionDto> = sessions
+            .sessions
+            .iter()
+            .filter(|session| session.expires_at > now)
+            .map(|session| SessionDto {
+                current: auth.session_id.as_deref() == Some(session.session_id.as_str()),
+                session: session.clone(),
+            })
+            .collect();
+        sessions.sort_by(|a, b| b.session.created_at.cmp(&a.session.created_at));
+
+        Ok(SessionListResponse { ok: true, sessions })
+    }
+
+    /// Revoke one session, or with `others` every session but the caller's
+    pub async fn revoke_session(
+        client: &S3Client,
+        auth: &AuthContext,
+        meta: &RequestMeta,
+        req: RevokeSessionRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        let current = auth.session_id.clone();
+        let mut revoked = Vec::new();
+        match (req.session_id, req.others) {
+            (Some(session_id), false) => {
+                let mut found = false;
+                Self::update(client, &auth.user_id, |sessions| {
+                    sessions.sessions.retain(|session| {
+                        let matches = session.session_id == session_id;
+                        found |= matches;
+                        !matches
+                    })
+                })
+                .await?;
+                if !found {
+                    return Err(AppError::NotFound("session_not_found".to_string()));
+                }
+                revoked.push(session_id);
+            }
+            (None, true) => {
+                Self::update(client, &auth.user_id, |sessions| {
+                    sessions.sessions.retain(|session| {
+                        let keep = current.as_deref() == Some(session.session_id.as_str());
+                        if !keep {
+                            revoked.push(session.session_id.clone());
+                        }
+                        keep
+                    })
+                })
+                .await?;
+            }
+            _ => return Err(AppError::InvalidInput("invalid_payload".to_string())),
+        }
+
+        for session_id in &revoked {
+            let mut event = AuditService::event(AuditAction::SessionRevoked, meta);
+            event.session_id = Some(session_id.clone());
+            AuditService::record(client, &auth.user_id, event);
+        }
+
+        Ok(json!({ "ok": true, "revoked": revoked.len() }))
+    }
+}
@@ -0,0 +1,279 @@
+use std::sync::OnceLock;
+
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    routes::{
+        account::RequestMeta,
+        share::{GrantShareRequest, RevokeShareRequest, ShareListResponse},
+    },
+    services::{audit::AuditService, auth::AuthService},
+    storage::{load_user_metadata, load_user_shares, save_user_shares, S3Client},
+    types::{AuditAction, ShareAccess, ShareGrant},
+    validation::validate_game_id,
+};
+
+/// Most accounts one game can be shared with
+const MAX_GRANTEES_PER_GAME: usize = 10;
+
+/// A grant is written to both accounts' share lists; this keeps two changes
+/// on this server from interleaving between the two writes
+static WRITE_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+pub struct ShareService;
+
+impl ShareService {
+    /// Games shared with `user_id` by other accounts
+    pub async fn received(client: &S3Client, user_id: &str) -> Result<Vec<ShareGrant>, AppError> {
+        let shares = load_user_shares(client, user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        Ok(shares.received)
+    }
+
+    /// Account whose save slot the caller reads or writes for `game_id`:
+    /// the owner when the game was shared with the caller, else the caller.
+    /// Writing into a shared slot needs write access.
+    pub async fn slot_owner(
+        client: &S3Client,
+        auth: &AuthContext,
+        game_id: &str,
+        write: bool,
+    ) -> Result<String, AppError> {
+        let received = Self::received(client, &auth.user_id).await?;
+        match received.into_iter().find(|grant| grant.game_id == game_id) {
+            Some(grant) if write && grant.access != ShareAccess::Write => {
+                Err(AppError::Forbidden("share_read_only".to_string()))
+            }
+            Some(grant) => Ok(grant.owner_user_id),
+            None => Ok(auth.user_id.clone()),
+        }
+    }
+
+    /// Share one of the caller's games with the account registered to
+    /// `email`. Sharing again with the same account changes its access. An
+    /// email without an account gets the same answer as one with, so the
+    /// endpoint can't be used to find out who has an account.
+    pub async fn grant(
+        client: &S3Client,
+        auth: &AuthContext,
+        meta: &RequestMeta,
+        req: GrantShareRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        if !validate_game_id(&req.game_id) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        let owner = load_user_metadata(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?
+            .ok_or_else(|| AppError::NotFound("user_not_found".to_string()))?;
+        let email = AuthService::normalize_email(&req.email);
+        if email == owner.email {
+            return Err(AppError::InvalidInput("cannot_share_with_self".to_string()));
+        }
+
+        let _guard = WRITE_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+        let mut owner_shares = load_user_shares(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        // Slots are shared by their owner only. Checked before looking the
+        // grantee up so the answer doesn't depend on the account existing.
+        if owner_shares
+            .received
+            .iter()
+            .any(|grant| grant.game_id == req.game_id)
+        {
+            return Err(AppError::Conflict("game_shared_with_you".to_string()));
+        }
+
+        let Some(grantee) = AuthService::get_user_by_email(client, &email).await? else {
+            tracing::info!(
+                "Share of {} by {} names no account; nothing granted",
+                req.game_id,
+                auth.user_id
+            );
+            return Ok(json!({ "ok": true }));
+        };
+        if grantee.user_id == auth.user_id {
+            return Err(AppError::InvalidInput("cannot_share_with_self".to_string()));
+        }
+        let mut grantee_shares = load_user_shares(client, &grantee.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        // A grantee follows one owner per game. Like an unknown email, a
+        // grant that can't be made answers `ok` so the answer doesn't tell
+        // whether the account exists; it just stays out of `/share/list`.
+        if grantee_shares
+            .received
+            .iter()
+            .any(|grant| grant.game_id == req.game_id && grant.owner_user_id != auth.user_id)
+        {
+            tracing::info!(
+                "Share of {} by {} names an account that follows another owner; nothing granted",
+                req.game_id,
+                auth.user_id
+            );
+            return Ok(json!({ "ok": true }));
+        }
+        let existing = owner_shares
+            .granted
+            .iter()
+            .filter(|grant| {
+                grant.game_id == req.game_id && grant.grantee_user_id != grantee.user_id
+            })
+            .count();
+        if existing >= MAX_GRANTEES_PER_GAME {
+            tracing::info!(
+                "Share of {} by {} is over the grantee limit; nothing granted",
+                req.game_id,
+                auth.user_id
+            );
+            return Ok(json!({ "ok": true }));
+        }
+
+        let grant = ShareGrant {
+            owner_user_id: auth.user_id.clone(),
+            owner_email: owner.email,
+            grantee_user_id: grantee.user_id.clone(),
+            grantee_email: grantee.email,
+            game_id: req.game_id.clone(),
+            access: req.access,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        let same = |other: &ShareGrant| {
+            other.game_id == grant.game_id && other.grantee_user_id == grant.grantee_user_id
+        };
+        owner_shares.granted.retain(|other| !same(other));
+        owner_shares.granted.push(grant.clone());
+        grantee_shares.received.retain(|other| !same(other));
+        grantee_shares.received.push(grant);
+
+        save_user_shares(client, &grantee.user_id, &grantee_shares)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        save_user_shares(client, &auth.user_id, &owner_shares)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        Self::record(
+            client,
+            AuditAction::ShareGranted,
+            meta,
+            &auth.user_id,
+            &grantee.user_id,
+            &req.game_id,
+        );
+
+        Ok(json!({ "ok": true }))
+    }
+
+    /// Stop sharing a game. The owner names the account to remove; a
+    /// grantee leaves by omitting it.
+    pub async fn revoke(
+        client: &S3Client,
+        auth: &AuthContext,
+        meta: &RequestMeta,
+        req: RevokeShareRequest,
+    ) -> Result<serde_json::Value, AppError> {
+        if !validate_game_id(&req.game_id) {
+            return Err(AppError::InvalidInput("invalid_payload".to_string()));
+        }
+
+        let _guard = WRITE_LOCK.get_or_init(|| Mutex::new(())).lock().await;
+        let mut caller_shares = load_user_shares(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let grant = match &req.grantee_user_id {
+            Some(grantee_user_id) => caller_shares.granted.iter().find(|grant| {
+                grant.game_id == req.game_id && grant.grantee_user_id == *grantee_user_id
+            }),
+            None => caller_shares
+                .received
+                .iter()
+                .find(|grant| grant.game_id == req.game_id),
+        }
+        .cloned()
+        .ok_or_else(|| AppError::NotFound("share_not_found".to_string()))?;
+
+        let other_user_id = if grant.owner_user_id == auth.user_id {
+            &grant.grantee_user_id
+        } else {
+            &grant.owner_user_id
+        };
+        let mut other_shares = load_user_shares(client, other_user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        let same = |other: &ShareGrant| {
+            other.game_id == grant.game_id
+                && other.owner_user_id == grant.owner_user_id
+                && other.grantee_user_id == grant.grantee_user_id
+        };
+        for shares in [&mut caller_shares, &mut other_shares] {
+            shares.granted.retain(|other| !same(other));
+            shares.received.retain(|other| !same(other));
+        }
+
+        save_user_shares(client, &auth.user_id, &caller_shares)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+        save_user_shares(client, other_user_id, &other_shares)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        Self::record(
+            client,
+            AuditAction::ShareRevoked,
+            meta,
+            &auth.user_id,
+            other_user_id,
+            &grant.game_id,
+        );
+
+        Ok(json!({ "ok": true }))
+    }
+
+    pub async fn list(
+        client: &S3Client,
+        auth: &AuthContext,
+    ) -> Result<ShareListResponse, AppError> {
+        let shares = load_user_shares(client, &auth.user_id)
+            .await
+            .map_err(|e| AppError::InternalError(e.into()))?;
+
+        Ok(ShareListResponse {
+            ok: true,
+            granted: shares.granted,
+            received: shares.received,
+            max_grantees_per_game: MAX_GRANTEES_PER_GAME,
+        })
+    }
+
+    /// Both accounts see a share change in their activity; the other one
+    /// without the caller's address
+    fn record(
+        client: &S3Client,
+        action: AuditAction,
+        meta: &RequestMeta,
+        user_id: &str,
+        other_user_id: &str,
+        game_id: &str,
+    ) {
+        let mut event = AuditService::event(action, meta);
+        event.game_id = Some(game_id.to_string());
+        event.other_user_id = Some(other_user_id.to_string());
+        AuditService::record(client, user_id, event);
+
+        let mut event = AuditService::event(action, &RequestMeta::default());
+        event.game_id = Some(game_id.to_string());
+        event.other_user_id = Some(user_id.to_string());
+        AuditService::record(client, other_user_id, event);
+    }
+}
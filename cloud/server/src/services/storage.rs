@@ -0,0 +1,59 @@
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    routes::storage::{ListObjectsRequest, ListObjectsResponse, StoredObjectDto},
+    storage::{get_user_base_key, S3Client},
+    validation::validate_object_prefix,
+};
+
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 1000;
+
+pub struct StorageService;
+
+impl StorageService {
+    /// Page through the objects stored under the caller's own prefix
+    pub async fn list_objects(
+        client: &S3Client,
+        auth: &AuthContext,
+        req: ListObjectsRequest,
+    ) -> Result<ListObjectsResponse, AppError> {
+        let relative_prefix = req.prefix.unwrap_or_default();
+        if !validate_object_prefix(&relative_prefix) {
+            return Err(AppError::InvalidInput("invalid_prefix".to_string()));
+        }
+
+        let limit = req
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+
+        let base_key = get_user_base_key(&auth.user_id);
+        let prefix = format!("{}{}", base_key, relative_prefix);
+        let cursor = req.cursor.filter(|cursor| !cursor.is_empty());
+
+        let page = client
+            .list_objects(&prefix, cursor.as_deref(), limit as i32)
+            .await
+            .map_err(AppError::InternalError)?;
+
+        let objects = page
+            .objects
+            .into_iter()
+            .filter_map(|object| {
+                let key = object.key.strip_prefix(&base_key)?.to_string();
+                Some(StoredObjectDto {
+                    key,
+                    size_bytes: object.size_bytes,
+                    last_modified: object.last_modified,
+                })
+            })
+            .collect();
+
+        Ok(ListObjectsResponse {
+            ok: true,
+            objects,
+            next_cursor: page.next_token,
+        })
+    }
+}
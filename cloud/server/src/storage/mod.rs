@@ -1,8 +1,10 @@
 pub mod s3_client;
 
-use crate::types::{UserDevices, UserMetadata, UserSaveMetadata};
+use crate::types::{
+    ConsumedWorkerTokens, UserDevices, UserMetadata, UserSaveMetadata, UserSessions, UserShares,
+};
 use anyhow::Result;
-pub use s3_client::S3Client;
+pub use s3_client::{ObjectInfo, ObjectPage, S3Client, S3Options};
 use serde::{de::DeserializeOwned, Serialize};
 
 /// Storage key utilities matching official worker format
@@ -23,18 +25,66 @@ pub fn get_save_metadata_key(user_id: &str) -> String {
     format!("{}save_metadata.json", get_user_base_key(user_id))
 }
 
+pub fn get_consumed_worker_tokens_key(user_id: &str) -> String {
+    format!("{}worker_tokens.json", get_user_base_key(user_id))
+}
+
+pub fn get_user_sessions_key(user_id: &str) -> String {
+    format!("{}sessions.json", get_user_base_key(user_id))
+}
+
+pub fn get_user_shares_key(user_id: &str) -> String {
+    format!("{}shares.json", get_user_base_key(user_id))
+}
+
 pub fn get_save_object_key(user_id: &str, game_id: &str, version_id: &str) -> String {
     format!("{}saves/{}/{}.zip", get_user_base_key(user_id), game_id, version_id)
 }
 
-/// Read JSON object from S3
+/// Plain zip of a zstd-encoded archive, made for clients that can't
+/// decode it
+pub fn get_plain_save_object_key(user_id: &str, game_id: &str, version_id: &str) -> String {
+    format!(
+        "{}saves/{}/{}.plain.zip",
+        get_user_base_key(user_id),
+        game_id,
+        version_id
+    )
+}
+
+/// Thumbnail stored as a sibling of the save archive
+pub fn get_thumbnail_object_key(user_id: &str, game_id: &str, version_id: &str) -> String {
+    format!("{}saves/{}/{}.png", get_user_base_key(user_id), game_id, version_id)
+}
+
+/// One JSONL object per user and UTC day, e.g. `audit/2026-10-15.jsonl`
+pub fn get_audit_log_prefix(user_id: &str) -> String {
+    format!("{}audit/", get_user_base_key(user_id))
+}
+
+pub fn get_audit_log_key(user_id: &str, day: &str) -> String {
+    format!("{}{}.jsonl", get_audit_log_prefix(user_id), day)
+}
+
+/// Pending verification or reset token, keyed by `hash_account_token`
+pub fn get_account_token_key(token_hash: &str) -> String {
+    format!("account_tokens/{}.json", token_hash)
+}
+
+/// Signed client release metadata, published by the operator
+pub fn get_client_release_key() -> String {
+    "client/release.json".to_string()
+}
+
+/// Read JSON object from S3. `None` only when the object doesn't exist;
+/// storage and parse failures are errors.
 pub async fn read_json<T: DeserializeOwned>(client: &S3Client, key: &str) -> Result<Option<T>> {
-    match client.get_object(key).await {
-        Ok(data) => {
+    match client.get_object(key).await? {
+        Some(data) => {
             let parsed: T = serde_json::from_slice(&data)?;
             Ok(Some(parsed))
         }
-        Err(_) => Ok(None), // Object doesn't exist
+        None => Ok(None),
     }
 }
 
@@ -69,6 +119,30 @@ pub async fn save_user_devices(client: &S3Client, user_id: &str, devices: &UserD
     write_json(client, &key, devices).await
 }
 
+/// Load user sessions or return default
+pub async fn load_user_sessions(client: &S3Client, user_id: &str) -> Result<UserSessions> {
+    let key = get_user_sessions_key(user_id);
+    Ok(read_json(client, &key).await?.unwrap_or_default())
+}
+
+/// Save user sessions
+pub async fn save_user_sessions(client: &S3Client, user_id: &str, sessions: &UserSessions) -> Result<()> {
+    let key = get_user_sessions_key(user_id);
+    write_json(client, &key, sessions).await
+}
+
+/// Load user shares or return default
+pub async fn load_user_shares(client: &S3Client, user_id: &str) -> Result<UserShares> {
+    let key = get_user_shares_key(user_id);
+    Ok(read_json(client, &key).await?.unwrap_or_default())
+}
+
+/// Save user shares
+pub async fn save_user_shares(client: &S3Client, user_id: &str, shares: &UserShares) -> Result<()> {
+    let key = get_user_shares_key(user_id);
+    write_json(client, &key, shares).await
+}
+
 /// Load save metadata or return default
 pub async fn load_save_metadata(client: &S3Client, user_id: &str) -> Result<UserSaveMetadata> {
     let key = get_save_metadata_key(user_id);
@@ -80,3 +154,15 @@ pub async fn save_save_metadata(client: &S3Client, user_id: &str, metadata: &Use
     let key = get_save_metadata_key(user_id);
     write_json(client, &key, metadata).await
 }
+
+/// Load consumed worker token IDs or return default
+pub async fn load_consumed_worker_tokens(client: &S3Client, user_id: &str) -> Result<ConsumedWorkerTokens> {
+    let key = get_consumed_worker_tokens_key(user_id);
+    Ok(read_json(client, &key).await?.unwrap_or_default())
+}
+
+/// Save consumed worker token IDs
+pub async fn save_consumed_worker_tokens(client: &S3Client, user_id: &str, tokens: &ConsumedWorkerTokens) -> Result<()> {
+    let key = get_consumed_worker_tokens_key(user_id);
+    write_json(client, &key, tokens).await
+}
@@ -1,18 +1,81 @@
 use anyhow::{anyhow, Result};
-use aws_config::BehaviorVersion;
+use aws_config::{retry::RetryConfig, timeout::TimeoutConfig, BehaviorVersion};
 use aws_credential_types::Credentials;
 use aws_sdk_s3::{
     config::Region,
+    error::SdkError,
     presigning::PresigningConfig,
     primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
     Client,
 };
+use aws_smithy_runtime_api::client::orchestrator::HttpResponse;
 use std::time::Duration;
 
+use crate::telemetry::record_s3_error;
+
+/// S3 rejects multipart parts smaller than this, except the last one
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Timeouts, retries and multipart settings for `S3Client`
+#[derive(Debug, Clone)]
+pub struct S3Options {
+    /// Whole operation, including retries
+    pub operation_timeout: Duration,
+    /// A single attempt of an operation
+    pub attempt_timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Attempts per operation, including the first one
+    pub max_attempts: u32,
+    /// First retry delay; later retries back off exponentially with jitter
+    pub initial_backoff: Duration,
+    /// `put_object` bodies larger than this use a multipart upload
+    pub multipart_threshold: usize,
+    pub part_size: usize,
+}
+
+impl Default for S3Options {
+    fn default() -> Self {
+        Self {
+            operation_timeout: Duration::from_secs(60),
+            attempt_timeout: Duration::from_secs(20),
+            connect_timeout: Duration::from_secs(5),
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            multipart_threshold: 16 * 1024 * 1024,
+            part_size: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl S3Options {
+    fn part_size(&self) -> usize {
+        self.part_size.max(MIN_PART_SIZE)
+    }
+}
+
+/// One stored object as returned by `list_objects`
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size_bytes: u64,
+    /// Unix seconds
+    pub last_modified: Option<i64>,
+}
+
+/// A page of `list_objects` results
+#[derive(Debug, Clone)]
+pub struct ObjectPage {
+    pub objects: Vec<ObjectInfo>,
+    /// Pass back to `list_objects` for the next page; `None` on the last one
+    pub next_token: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct S3Client {
     client: Client,
     bucket: String,
+    options: S3Options,
 }
 
 impl S3Client {
@@ -22,6 +85,25 @@ impl S3Client {
         access_key: &str,
         secret_key: &str,
         bucket: &str,
+    ) -> Result<Self> {
+        Self::with_options(
+            endpoint,
+            region,
+            access_key,
+            secret_key,
+            bucket,
+            S3Options::default(),
+        )
+        .await
+    }
+
+    pub async fn with_options(
+        endpoint: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+        bucket: &str,
+        options: S3Options,
     ) -> Result<Self> {
         let credentials = Credentials::new(
             access_key,
@@ -31,10 +113,24 @@ impl S3Client {
             "static",
         );
 
+        let timeouts = TimeoutConfig::builder()
+            .operation_timeout(options.operation_timeout)
+            .operation_attempt_timeout(options.attempt_timeout)
+            .connect_timeout(options.connect_timeout)
+            .build();
+
+        // Standard mode only retries transient failures: throttling, 5xx,
+        // timeouts and dropped connections
+        let retries = RetryConfig::standard()
+            .with_max_attempts(options.max_attempts.max(1))
+            .with_initial_backoff(options.initial_backoff);
+
         let config = aws_config::defaults(BehaviorVersion::latest())
             .endpoint_url(endpoint)
             .region(Region::new(region.to_string()))
             .credentials_provider(credentials)
+            .timeout_config(timeouts)
+            .retry_config(retries)
             .load()
             .await;
 
@@ -47,11 +143,16 @@ impl S3Client {
         Ok(Self {
             client,
             bucket: bucket.to_string(),
+            options,
         })
     }
 
-    /// Put object to S3
+    /// Put object to S3, in parts when it is over the multipart threshold
     pub async fn put_object(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        if data.len() > self.options.multipart_threshold {
+            return self.put_object_multipart(key, data).await;
+        }
+
         self.client
             .put_object()
             .bucket(&self.bucket)
@@ -59,25 +160,152 @@ impl S3Client {
             .body(ByteStream::from(data))
             .content_type("application/json")
             .send()
-            .await?;
+            .await
+            .inspect_err(|_| record_s3_error("put_object"))?;
 
         Ok(())
     }
 
-    /// Get object from S3
-    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
-        let response = self.client
+    async fn put_object_multipart(&self, key: &str, data: Vec<u8>) -> Result<()> {
+        let upload = self.client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type("application/json")
+            .send()
+            .await
+            .inspect_err(|_| record_s3_error("put_object"))?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| anyhow!("multipart upload for {} returned no upload id", key))?
+            .to_string();
+
+        let parts = match self.upload_parts(key, &upload_id, &data).await {
+            Ok(parts) => parts,
+            Err(err) => {
+                // Parts of an abandoned upload are billed until aborted
+                if let Err(abort_err) = self.client
+                    .abort_multipart_upload()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .send()
+                    .await
+                {
+                    tracing::warn!("Failed to abort multipart upload of {}: {}", key, abort_err);
+                }
+                record_s3_error("put_object");
+                return Err(err);
+            }
+        };
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .upload_id(&upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .inspect_err(|_| record_s3_error("put_object"))?;
+
+        Ok(())
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        data: &[u8],
+    ) -> Result<Vec<CompletedPart>> {
+        let mut parts = Vec::new();
+        for (index, chunk) in data.chunks(self.options.part_size()).enumerate() {
+            let part_number = index as i32 + 1;
+            let part = self.client
+                .upload_part()
+                .bucket(&self.bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(ByteStream::from(chunk.to_vec()))
+                .send()
+                .await?;
+
+            parts.push(
+                CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(part.e_tag().map(str::to_string))
+                    .build(),
+            );
+        }
+
+        Ok(parts)
+    }
+
+    /// Get object from S3, or `None` if it does not exist
+    pub async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = match self.client
             .get_object()
             .bucket(&self.bucket)
             .key(key)
             .send()
-            .await?;
+            .await
+        {
+            Ok(response) => response,
+            Err(err)
+                if is_not_found(&err)
+                    || err.as_service_error().is_some_and(|e| e.is_no_such_key()) =>
+            {
+                return Ok(None);
+            }
+            Err(err) => {
+                record_s3_error("get_object");
+                return Err(err.into());
+            }
+        };
 
-        let data = response.body.collect().await?;
-        Ok(data.into_bytes().to_vec())
+        let data = response
+            .body
+            .collect()
+            .await
+            .inspect_err(|_| record_s3_error("get_object"))?;
+        Ok(Some(data.into_bytes().to_vec()))
     }
 
-    /// Check if object exists
+    /// Delete object from S3 (succeeds if it is already gone)
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .inspect_err(|_| record_s3_error("delete_object"))?;
+
+        Ok(())
+    }
+
+    /// Copy an object within the bucket; the bytes never pass through
+    /// this server
+    pub async fn copy_object(&self, source_key: &str, key: &str) -> Result<()> {
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .copy_source(format!("{}/{}", self.bucket, source_key))
+            .key(key)
+            .send()
+            .await
+            .inspect_err(|_| record_s3_error("copy_object"))?;
+
+        Ok(())
+    }
+
+    /// Check if object exists. Errors other than a missing object are
+    /// returned rather than reported as `false`.
     pub async fn head_object(&self, key: &str) -> Result<bool> {
         match self.client
             .head_object()
@@ -87,12 +315,62 @@ impl S3Client {
             .await
         {
             Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+            Err(err) if is_not_found(&err) => Ok(false),
+            Err(err) => {
+                record_s3_error("head_object");
+                Err(err.into())
+            }
         }
     }
 
+    /// List up to `max_keys` objects under `prefix`, in key order
+    pub async fn list_objects(
+        &self,
+        prefix: &str,
+        continuation_token: Option<&str>,
+        max_keys: i32,
+    ) -> Result<ObjectPage> {
+        let response = self.client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .set_continuation_token(continuation_token.map(str::to_string))
+            .max_keys(max_keys)
+            .send()
+            .await
+            .inspect_err(|_| record_s3_error("list_objects"))?;
+
+        let objects = response
+            .contents()
+            .iter()
+            .filter_map(|object| {
+                Some(ObjectInfo {
+                    key: object.key()?.to_string(),
+                    size_bytes: object.size().unwrap_or_default().max(0) as u64,
+                    last_modified: object.last_modified().map(|time| time.secs()),
+                })
+            })
+            .collect();
+
+        let next_token = if response.is_truncated().unwrap_or(false) {
+            response.next_continuation_token().map(str::to_string)
+        } else {
+            None
+        };
+
+        Ok(ObjectPage {
+            objects,
+            next_token,
+        })
+    }
+
     /// Generate presigned PUT URL
-    pub async fn presign_put(&self, key: &str, ttl_seconds: u64) -> Result<String> {
+    pub async fn presign_put(
+        &self,
+        key: &str,
+        content_type: &str,
+        ttl_seconds: u64,
+    ) -> Result<String> {
         let presigning_config = PresigningConfig::builder()
             .expires_in(Duration::from_secs(ttl_seconds))
             .build()?;
@@ -101,7 +379,7 @@ impl S3Client {
             .put_object()
             .bucket(&self.bucket)
             .key(key)
-            .content_type("application/zip")
+            .content_type(content_type)
             .presigned(presigning_config)
             .await?;
 
@@ -124,6 +402,17 @@ impl S3Client {
         Ok(presigned.uri().to_string())
     }
 
+    /// Whether the bucket answers, for the readiness probe
+    pub async fn bucket_reachable(&self) -> bool {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .inspect_err(|_| record_s3_error("head_bucket"))
+            .is_ok()
+    }
+
     /// Ensure bucket exists (create if not)
     pub async fn ensure_bucket(&self) -> Result<()> {
         match self.client
@@ -148,3 +437,26 @@ impl S3Client {
         }
     }
 }
+
+/// HEAD responses carry no error body, so a 404 status is the only signal
+fn is_not_found<E>(err: &SdkError<E, HttpResponse>) -> bool {
+    err.raw_response()
+        .is_some_and(|response| response.status().as_u16() == 404)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_size_respects_s3_minimum() {
+        let options = S3Options {
+            part_size: 1024,
+            ..S3Options::default()
+        };
+        assert_eq!(options.part_size(), MIN_PART_SIZE);
+
+        let options = S3Options::default();
+        assert_eq!(options.part_size(), 8 * 1024 * 1024);
+    }
+}
@@ -0,0 +1,123 @@
+//! Prometheus metrics served on `/metrics` when `METRICS_ENABLED` is set.
+//! Until `install` runs the recording calls below are no-ops, so handlers
+//! and the S3 client can record unconditionally.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+/// Users who made an authenticated request this recently count as active
+pub const ACTIVE_USER_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+static ACTIVE_USERS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+
+/// Install the global recorder. Safe to call more than once.
+pub fn install() -> anyhow::Result<()> {
+    if HANDLE.get().is_some() {
+        return Ok(());
+    }
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full("http_request_duration_seconds".to_string()),
+            LATENCY_BUCKETS,
+        )?
+        .install_recorder()?;
+    let _ = HANDLE.set(handle);
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    HANDLE.get().is_some()
+}
+
+/// Count every request and time it, labelled with the route pattern
+/// rather than the raw path
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let status = response.status().as_u16().to_string();
+    counter!(
+        "http_requests_total",
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status
+    )
+    .increment(1);
+    histogram!("http_request_duration_seconds", "method" => method, "route" => route)
+        .record(elapsed);
+    response
+}
+
+/// Note a user seen on an authenticated request
+pub fn record_active_user(user_id: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let users = ACTIVE_USERS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Ok(mut users) = users.lock() {
+        users.insert(user_id.to_string(), Instant::now());
+    }
+}
+
+/// A failed S3 call, after the client's own retries
+pub fn record_s3_error(operation: &'static str) {
+    counter!("s3_errors_total", "operation" => operation).increment(1);
+}
+
+/// Archive bytes a client finished uploading
+pub fn record_uploaded(bytes: u64) {
+    counter!("bytes_uploaded_total").increment(bytes);
+}
+
+/// Archive bytes handed to a client, directly or through a download URL
+pub fn record_downloaded(bytes: u64) {
+    counter!("bytes_downloaded_total").increment(bytes);
+}
+
+fn refresh_active_users() {
+    let Some(users) = ACTIVE_USERS.get() else {
+        return;
+    };
+    if let Ok(mut users) = users.lock() {
+        users.retain(|_, seen| seen.elapsed() < ACTIVE_USER_WINDOW);
+        gauge!("active_users").set(users.len() as f64);
+    }
+}
+
+/// `/metrics` in the Prometheus text format
+pub async fn handle_metrics() -> Response {
+    let Some(handle) = HANDLE.get() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    refresh_active_users();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+        .into_response()
+}
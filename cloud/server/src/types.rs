@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+/// Shared with the official worker through `cloud/protocol`
+pub use crosssave_protocol::{ArchiveEncoding, WorkerTokenClaims};
+
 /// User account metadata stored in S3
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserMetadata {
@@ -9,6 +12,23 @@ pub struct UserMetadata {
     pub created_at: i64,
     pub updated_at: i64,
     pub devices: u32,
+    /// Scheme of `password_hash`; accounts from before Argon2 use bcrypt
+    #[serde(default)]
+    pub password_algorithm: PasswordAlgorithm,
+    /// Set once the user follows a verification email. Accounts from before
+    /// verification existed, or on servers without SMTP, stay unverified.
+    #[serde(default)]
+    pub email_verified: bool,
+}
+
+/// Password hashing scheme. The hash itself carries the per-user cost
+/// parameters; bcrypt hashes are replaced with Argon2id at the next login.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordAlgorithm {
+    #[default]
+    Bcrypt,
+    Argon2id,
 }
 
 /// Device information
@@ -18,6 +38,10 @@ pub struct Device {
     pub platform: String,
     pub device_name: String,
     pub last_seen: i64,
+    /// SHA-256 of the machine the client runs on, the same for every
+    /// install there. Devices registered before clients sent one have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
 }
 
 /// List of devices for a user
@@ -34,6 +58,168 @@ impl Default for UserDevices {
     }
 }
 
+/// What registering a device did to the list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceUpsert {
+    Added,
+    /// The device was known and its details were refreshed
+    Updated,
+    /// Another id with the same fingerprint was replaced, e.g. after the
+    /// client lost its settings; holds the id it had
+    Replaced(String),
+}
+
+/// Devices folded into one by `UserDevices::merge_duplicates`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergedDevices {
+    pub kept: String,
+    pub removed: Vec<String>,
+}
+
+/// What `UserDevices::merge_duplicates` did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceMergeOutcome {
+    pub merged: Vec<MergedDevices>,
+    /// Entries without a fingerprint that only look like the same machine
+    /// by platform and name. They are left alone until the user confirms.
+    pub suggested: Vec<MergedDevices>,
+}
+
+impl UserDevices {
+    /// Add `device` or refresh the entry with its id. When the client says
+    /// it is a `new_install`, a new id whose fingerprint is already listed
+    /// takes over that entry instead of showing up as a second device.
+    /// Other installs on the same machine share the fingerprint, so an
+    /// established one is always added next to them.
+    pub fn upsert(&mut self, device: Device, new_install: bool) -> DeviceUpsert {
+        if let Some(existing) = self
+            .devices
+            .iter_mut()
+            .find(|d| d.device_id == device.device_id)
+        {
+            existing.platform = device.platform;
+            existing.device_name = device.device_name;
+            existing.last_seen = device.last_seen;
+            if device.fingerprint.is_some() {
+                existing.fingerprint = device.fingerprint;
+            }
+            return DeviceUpsert::Updated;
+        }
+
+        if let (true, Some(fingerprint)) = (new_install, &device.fingerprint) {
+            if let Some(existing) = self
+                .devices
+                .iter_mut()
+                .find(|d| d.fingerprint.as_ref() == Some(fingerprint))
+            {
+                let previous = std::mem::replace(existing, device);
+                return DeviceUpsert::Replaced(previous.device_id);
+            }
+        }
+
+        self.devices.push(device);
+        DeviceUpsert::Added
+    }
+
+    /// Fold entries for the same machine into one. Entries sharing a
+    /// fingerprint are merged right away; entries without one that have
+    /// the same platform and name as another are only merged when their id
+    /// is in `confirmed`, and are suggested otherwise. `keep` survives its
+    /// group, otherwise the most recently seen entry does.
+    pub fn merge_duplicates(
+        &mut self,
+        keep: Option<&str>,
+        confirmed: &[String],
+    ) -> DeviceMergeOutcome {
+        let (fingerprinted, legacy): (Vec<Device>, Vec<Device>) = self
+            .devices
+            .drain(..)
+            .partition(|d| d.fingerprint.is_some());
+
+        let mut groups: Vec<Vec<Device>> = Vec::new();
+        for device in fingerprinted {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].fingerprint == device.fingerprint)
+            {
+                Some(group) => group.push(device),
+                None => groups.push(vec![device]),
+            }
+        }
+        for device in legacy {
+            match groups.iter_mut().find(|group| {
+                group
+                    .iter()
+                    .any(|d| d.platform == device.platform && d.device_name == device.device_name)
+            }) {
+                Some(group) => group.push(device),
+                None => groups.push(vec![device]),
+            }
+        }
+
+        let mut outcome = DeviceMergeOutcome::default();
+        for group in groups {
+            let (mut certain, unconfirmed): (Vec<Device>, Vec<Device>) = group
+                .into_iter()
+                .partition(|d| d.fingerprint.is_some() || confirmed.contains(&d.device_id));
+
+            let kept_id = if certain.is_empty() {
+                unconfirmed[kept_index(&unconfirmed, keep)]
+                    .device_id
+                    .clone()
+            } else {
+                let mut kept = certain.swap_remove(kept_index(&certain, keep));
+                if !certain.is_empty() {
+                    kept.last_seen = certain
+                        .iter()
+                        .map(|d| d.last_seen)
+                        .fold(kept.last_seen, i64::max);
+                    if kept.fingerprint.is_none() {
+                        kept.fingerprint = certain.iter().find_map(|d| d.fingerprint.clone());
+                    }
+                    outcome.merged.push(MergedDevices {
+                        kept: kept.device_id.clone(),
+                        removed: certain.into_iter().map(|d| d.device_id).collect(),
+                    });
+                }
+                let id = kept.device_id.clone();
+                self.devices.push(kept);
+                id
+            };
+
+            let suggested: Vec<String> = unconfirmed
+                .iter()
+                .map(|d| d.device_id.clone())
+                .filter(|id| *id != kept_id)
+                .collect();
+            if !suggested.is_empty() {
+                outcome.suggested.push(MergedDevices {
+                    kept: kept_id,
+                    removed: suggested,
+                });
+            }
+            self.devices.extend(unconfirmed);
+        }
+        outcome
+    }
+}
+
+/// Index of the entry to keep from a group: `keep` when it is there,
+/// otherwise the most recently seen
+fn kept_index(group: &[Device], keep: Option<&str>) -> usize {
+    group
+        .iter()
+        .position(|d| Some(d.device_id.as_str()) == keep)
+        .or_else(|| {
+            group
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, d)| d.last_seen)
+                .map(|(index, _)| index)
+        })
+        .unwrap_or(0)
+}
+
 /// Save version entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SaveVersion {
@@ -47,6 +233,27 @@ pub struct SaveVersion {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
     pub timestamp: i64,
+    /// Set when a thumbnail was uploaded next to the archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_sha256: Option<String>,
+    /// User label, e.g. "before final boss"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Pinned versions must never be removed by retention
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Version the client made this one from; lets clients tell a
+    /// fast-forward from two devices diverging
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_version_id: Option<String>,
+    /// The emulator encrypted the saves, so clients treat them as opaque
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
+    /// How the stored archive is encoded; none for a plain zip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<ArchiveEncoding>,
 }
 
 /// User's save metadata (list of all versions)
@@ -63,6 +270,33 @@ impl Default for UserSaveMetadata {
     }
 }
 
+/// Permission scope granted to a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    Read,
+    Write,
+    DeviceManage,
+    Admin,
+}
+
+/// Scopes issued to regular user sessions (signup/login)
+pub fn default_user_scopes() -> Vec<Scope> {
+    vec![Scope::Read, Scope::Write, Scope::DeviceManage]
+}
+
+/// Scopes for a login that asked for `requested`, e.g. a read-only token
+/// for a device that only restores. `None` when it asked for nothing a
+/// user session may hold.
+pub fn requested_user_scopes(requested: &[Scope]) -> Option<Vec<Scope>> {
+    let scopes: Vec<Scope> = default_user_scopes()
+        .into_iter()
+        .filter(|scope| requested.contains(scope))
+        .collect();
+    let allowed = requested.iter().all(|scope| scopes.contains(scope));
+    (allowed && !scopes.is_empty()).then_some(scopes)
+}
+
 /// JWT claims
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
@@ -70,6 +304,13 @@ pub struct Claims {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
     pub exp: i64,
+    /// Tokens issued before scopes existed are treated as full user sessions
+    #[serde(default = "default_user_scopes")]
+    pub scopes: Vec<Scope>,
+    /// Session the token belongs to. Tokens from before sessions existed
+    /// have none and stay valid until they expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
 }
 
 /// Upload request payload
@@ -84,6 +325,12 @@ pub struct UploadPayload {
     pub emulator_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
+    /// Request a second presigned URL for a PNG thumbnail
+    #[serde(default)]
+    pub thumbnail_sha256: Option<String>,
+    /// Set when the archive will be uploaded compressed
+    #[serde(default)]
+    pub encoding: Option<ArchiveEncoding>,
 }
 
 /// Download request payload
@@ -91,15 +338,166 @@ pub struct UploadPayload {
 pub struct DownloadPayload {
     pub game_id: String,
     pub version_id: String,
+    /// Encodings the client can undo. Older clients send none and are
+    /// handed a plain zip.
+    #[serde(default)]
+    pub accept_encoding: Vec<ArchiveEncoding>,
+}
+
+/// Request for one file out of a version's archive
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadFilePayload {
+    pub game_id: String,
+    pub version_id: String,
+    /// Path inside the archive, as listed in the version's `file_list`
+    pub path: String,
 }
 
-/// Worker token claims (for upload proxy)
+/// A worker token ID that has already been used
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkerTokenClaims {
+pub struct ConsumedWorkerToken {
+    pub jti: String,
+    pub exp: i64,
+}
+
+/// Worker token IDs a user has consumed and that have not expired yet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsumedWorkerTokens {
+    pub tokens: Vec<ConsumedWorkerToken>,
+}
+
+impl ConsumedWorkerTokens {
+    /// Record `jti` as used. Returns false if it was already consumed.
+    /// Expired entries are dropped first; an expired token fails verification
+    /// anyway, so they never need to be remembered past `exp`.
+    pub fn consume(&mut self, jti: &str, exp: i64, now: i64) -> bool {
+        self.tokens.retain(|token| token.exp >= now);
+        if self.tokens.iter().any(|token| token.jti == jti) {
+            return false;
+        }
+        self.tokens.push(ConsumedWorkerToken {
+            jti: jti.to_string(),
+            exp,
+        });
+        true
+    }
+}
+
+/// Security-relevant account events kept in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Signup,
+    Login,
+    /// Wrong password for an existing account
+    LoginFailed,
+    DeviceAdded,
+    DeviceRemoved,
+    VersionUploaded,
+    VersionDeleted,
+    EmailVerified,
+    PasswordResetRequested,
+    PasswordReset,
+    SessionRevoked,
+    ShareGranted,
+    ShareRevoked,
+}
+
+/// One line of a user's audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+    /// Unix seconds
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Game and version for upload and delete events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+    /// Session revoked by a `session_revoked` event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Other account in a share event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_user_id: Option<String>,
+}
+
+/// What a single-use account token emailed to the user allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountTokenPurpose {
+    VerifyEmail,
+    PasswordReset,
+}
+
+/// Pending account token, stored under a hash of the token itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountToken {
+    pub purpose: AccountTokenPurpose,
     pub user_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Address the token was sent to; a changed address invalidates it
+    pub email: String,
+    /// Unix seconds
+    pub expires_at: i64,
+}
+
+/// A login or signup whose token is still accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// `jti` of the session's token
+    pub session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub device_id: Option<String>,
-    pub r2_key: String,
-    pub version_id: String,
-    pub exp: i64,
+    /// Unix seconds
+    pub created_at: i64,
+    pub expires_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+/// A user's active sessions; a token whose session isn't listed is revoked
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserSessions {
+    pub sessions: Vec<Session>,
+}
+
+/// What a share lets the other account do with the owner's save slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareAccess {
+    /// Download versions only
+    Read,
+    /// Also upload new versions into the slot
+    Write,
+}
+
+/// One game's saves shared by their owner with another account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareGrant {
+    pub owner_user_id: String,
+    pub owner_email: String,
+    pub grantee_user_id: String,
+    pub grantee_email: String,
+    pub game_id: String,
+    pub access: ShareAccess,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+/// Shares a user made and received. Each grant is stored on both sides, so
+/// either account can list it without scanning the other's data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserShares {
+    #[serde(default)]
+    pub granted: Vec<ShareGrant>,
+    #[serde(default)]
+    pub received: Vec<ShareGrant>,
 }
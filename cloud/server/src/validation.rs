@@ -16,6 +16,14 @@ pub fn validate_device_id(device_id: &Option<String>) -> bool {
     }
 }
 
+/// Validate a device fingerprint, a SHA-256 hex digest when present
+pub fn validate_fingerprint(fingerprint: &Option<String>) -> bool {
+    match fingerprint {
+        Some(fingerprint) => validate_sha256(fingerprint),
+        None => true,
+    }
+}
+
 /// Validate game ID
 pub fn validate_game_id(game_id: &str) -> bool {
     game_id.len() >= 3 && game_id.len() <= 256
@@ -26,6 +34,14 @@ pub fn validate_version_id(version_id: &str) -> bool {
     !version_id.is_empty() && version_id.len() <= 256
 }
 
+/// Validate the parent of an uploaded version; a version cannot be its own parent
+pub fn validate_parent_version_id(parent: &Option<String>, version_id: &str) -> bool {
+    match parent {
+        Some(id) => validate_version_id(id) && id != version_id,
+        None => true, // Versions from older clients have no parent
+    }
+}
+
 /// Validate SHA256 hash
 pub fn validate_sha256(hash: &str) -> bool {
     hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
@@ -45,6 +61,41 @@ pub fn validate_file_list(files: &[String]) -> bool {
     files.iter().all(|f| !f.is_empty() && f.len() <= 512)
 }
 
+/// Validate a path inside a save archive, as named in a file list
+pub fn validate_archive_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.len() <= 512
+        && !path.starts_with('/')
+        && !path.contains('\\')
+        && !path.split('/').any(|segment| segment == "..")
+}
+
+/// Validate a version note
+pub fn validate_note(note: &Option<String>) -> bool {
+    match note {
+        Some(text) => text.chars().count() <= 500,
+        None => true, // Optional field
+    }
+}
+
+/// Validate version tags
+pub fn validate_tags(tags: &[String]) -> bool {
+    if tags.len() > 16 {
+        return false;
+    }
+
+    tags.iter()
+        .all(|tag| !tag.trim().is_empty() && tag.chars().count() <= 32)
+}
+
+/// Validate an object key prefix relative to a user's storage root
+pub fn validate_object_prefix(prefix: &str) -> bool {
+    prefix.len() <= 512
+        && !prefix.starts_with('/')
+        && !prefix.contains('\\')
+        && !prefix.split('/').any(|segment| segment == "..")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,84 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use crosssave_selfhost_server::{routes, storage::S3Client};
+use tower::ServiceExt; // for oneshot
+
+async fn test_app() -> Router {
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+    routes::create_router(client)
+}
+
+async fn post(app: Router, uri: &str, body: &str) -> StatusCode {
+    app.oneshot(
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+    .status()
+}
+
+#[tokio::test]
+async fn test_password_reset_unavailable_without_smtp() {
+    let status = post(
+        test_app().await,
+        "/account/password-reset/request",
+        r#"{"email":"player@example.com"}"#,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_password_reset_rejects_weak_password() {
+    let status = post(
+        test_app().await,
+        "/account/password-reset/confirm",
+        r#"{"token":"0123456789abcdef","new_password":"short"}"#,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_resend_verification_requires_auth() {
+    let status = post(test_app().await, "/account/verify-email/resend", "{}").await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_session_revoke_requires_auth() {
+    let status = post(test_app().await, "/session/revoke", r#"{"others":true}"#).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_share_grant_requires_auth() {
+    let status = post(
+        test_app().await,
+        "/share/grant",
+        r#"{"game_id":"psx-crash","email":"family@example.com","access":"read"}"#,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
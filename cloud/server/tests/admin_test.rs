@@ -0,0 +1,82 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use crosssave_selfhost_server::{
+    auth::{jwt, sign_jwt},
+    config::{self, ServerConfig},
+    routes,
+    storage::S3Client,
+    types::{default_user_scopes, Claims},
+};
+use serde_json::Value;
+use tower::ServiceExt; // for oneshot
+
+const ADMIN_TOKEN: &str = "operator-secret-token";
+
+async fn send(app: &Router, method: &str, uri: &str, token: Option<&str>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {token}"));
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+// Config lives in process-wide state, so this is a single test
+#[tokio::test]
+async fn test_admin_config_is_redacted_and_reloads() {
+    std::env::set_var("JWT_SECRET", "test-jwt-secret");
+    std::env::set_var("S3_SECRET_KEY", "s3-secret-value");
+    std::env::set_var("ADMIN_TOKEN", ADMIN_TOKEN);
+    std::env::set_var("RATE_LIMIT_AUTH_BURST", "7");
+    jwt::init_jwt(ServerConfig::from_env().unwrap());
+
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+    let app = routes::create_router(client);
+
+    let (status, _) = send(&app, "GET", "/admin/config", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A regular session lacks the admin scope
+    let user_token = sign_jwt(&Claims {
+        user_id: "user".to_string(),
+        device_id: None,
+        exp: chrono::Utc::now().timestamp() + 60,
+        scopes: default_user_scopes(),
+        jti: None,
+    })
+    .unwrap();
+    let (status, _) = send(&app, "GET", "/admin/config", Some(&user_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(&app, "GET", "/admin/config", Some(ADMIN_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    let text = body.to_string();
+    assert!(!text.contains("test-jwt-secret"));
+    assert!(!text.contains("s3-secret-value"));
+    assert!(!text.contains(ADMIN_TOKEN));
+    assert_eq!(body["config"]["s3_secret_key"], "<redacted>");
+    assert_eq!(body["config"]["tunables"]["rate_limits"]["auth_burst"], 7);
+
+    std::env::set_var("SESSION_TTL_SECS", "3600");
+    let (status, body) = send(&app, "POST", "/admin/config/reload", Some(ADMIN_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["config"]["tunables"]["session_ttl_secs"], 3600);
+    assert_eq!(config::tunables().session_ttl_secs, 3600);
+}
@@ -0,0 +1,69 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    routing::get,
+    Router,
+};
+use crosssave_selfhost_server::{
+    routes::{self, account::RequestMeta},
+    storage::S3Client,
+};
+use tower::ServiceExt; // for oneshot
+
+async fn echo_meta(meta: RequestMeta) -> String {
+    format!(
+        "{}|{}",
+        meta.ip.unwrap_or_default(),
+        meta.user_agent.unwrap_or_default()
+    )
+}
+
+#[tokio::test]
+async fn test_request_meta_uses_address_added_by_proxy() {
+    let app = Router::new().route("/", get(echo_meta));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                // The client sent the first entry; the proxy appended the second
+                .header("X-Forwarded-For", "198.51.100.9, 203.0.113.7")
+                .header("User-Agent", "CrossSave/1.0")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"203.0.113.7|CrossSave/1.0");
+}
+
+#[tokio::test]
+async fn test_activity_requires_auth() {
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+    let app = routes::create_router(client);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/account/activity")
+                .header("Content-Type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -5,6 +5,7 @@ use axum::{
 use crosssave_selfhost_server::{
     routes::auth::{AuthResponse, LoginRequest, SignupRequest},
     storage::S3Client,
+    types::Scope,
 };
 use tower::ServiceExt; // for oneshot
 
@@ -24,6 +25,7 @@ async fn test_signup_validation() {
         device_id: None,
         device_name: None,
         platform: None,
+        fingerprint: None,
     };
 
     let response = app
@@ -52,6 +54,9 @@ async fn test_login_validation() {
         device_id: None,
         device_name: None,
         platform: None,
+        fingerprint: None,
+        new_install: false,
+        scopes: None,
     };
 
     let response = app
@@ -69,3 +74,34 @@ async fn test_login_validation() {
     // Should fail validation
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
+
+#[tokio::test]
+async fn test_login_rejects_scopes_beyond_a_session() {
+    let app = create_app().await;
+
+    let payload = LoginRequest {
+        email: "test@example.com".to_string(),
+        password: "password123".to_string(),
+        device_id: None,
+        device_name: None,
+        platform: None,
+        fingerprint: None,
+        new_install: false,
+        scopes: Some(vec![Scope::Read, Scope::Admin]),
+    };
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/login")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&payload).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Checked before the account is looked up
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,23 @@
+//! The token format itself is tested in `cloud/protocol`; this covers the
+//! single-use bookkeeping the self-host server keeps on top of it.
+
+use crosssave_selfhost_server::types::ConsumedWorkerTokens;
+
+const NOW: i64 = 1_800_000_000;
+
+#[test]
+fn test_consumed_tokens_reject_reuse() {
+    let mut consumed = ConsumedWorkerTokens::default();
+    assert!(consumed.consume("token-1", NOW + 60, NOW));
+    assert!(!consumed.consume("token-1", NOW + 60, NOW + 30));
+    assert!(consumed.consume("token-2", NOW + 60, NOW + 30));
+}
+
+#[test]
+fn test_consumed_tokens_forget_expired_entries() {
+    let mut consumed = ConsumedWorkerTokens::default();
+    assert!(consumed.consume("token-1", NOW + 60, NOW));
+    assert!(consumed.consume("token-2", NOW + 120, NOW + 61));
+    assert_eq!(consumed.tokens.len(), 1);
+    assert_eq!(consumed.tokens[0].jti, "token-2");
+}
@@ -0,0 +1,147 @@
+use crosssave_selfhost_server::types::{
+    Device, DeviceMergeOutcome, DeviceUpsert, MergedDevices, UserDevices,
+};
+
+fn device(id: &str, name: &str, last_seen: i64, fingerprint: Option<&str>) -> Device {
+    Device {
+        device_id: id.to_string(),
+        platform: "windows".to_string(),
+        device_name: name.to_string(),
+        last_seen,
+        fingerprint: fingerprint.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_new_install_for_known_machine_replaces_its_entry() {
+    let mut devices = UserDevices {
+        devices: vec![device("old", "Windows PC", 10, Some("aa"))],
+    };
+
+    assert_eq!(
+        devices.upsert(device("old", "Gaming PC", 20, None), false),
+        DeviceUpsert::Updated
+    );
+    assert_eq!(devices.devices[0].device_name, "Gaming PC");
+    assert_eq!(devices.devices[0].fingerprint.as_deref(), Some("aa"));
+
+    assert_eq!(
+        devices.upsert(device("new", "Gaming PC", 30, Some("aa")), true),
+        DeviceUpsert::Replaced("old".to_string())
+    );
+    assert_eq!(devices.devices.len(), 1);
+    assert_eq!(devices.devices[0].device_id, "new");
+
+    assert_eq!(
+        devices.upsert(device("laptop", "Laptop", 40, Some("bb")), true),
+        DeviceUpsert::Added
+    );
+    assert_eq!(devices.devices.len(), 2);
+}
+
+#[test]
+fn test_installs_sharing_a_machine_keep_their_entries() {
+    let mut devices = UserDevices {
+        devices: vec![device("first", "Windows PC", 10, Some("aa"))],
+    };
+
+    // A second, established install on the same machine
+    assert_eq!(
+        devices.upsert(device("second", "Windows PC", 20, Some("aa")), false),
+        DeviceUpsert::Added
+    );
+    assert_eq!(
+        devices.upsert(device("first", "Windows PC", 30, Some("aa")), false),
+        DeviceUpsert::Updated
+    );
+    assert_eq!(
+        devices.upsert(device("second", "Windows PC", 40, Some("aa")), false),
+        DeviceUpsert::Updated
+    );
+    assert_eq!(devices.devices.len(), 2);
+}
+
+#[test]
+fn test_merge_folds_fingerprints_and_suggests_legacy_names() {
+    let mut devices = UserDevices {
+        devices: vec![
+            device("legacy", "Windows PC", 5, None),
+            device("first", "Windows PC", 10, Some("aa")),
+            device("second", "Windows PC", 30, Some("aa")),
+            device("laptop", "Laptop", 20, Some("bb")),
+        ],
+    };
+
+    let outcome = devices.merge_duplicates(None, &[]);
+    assert_eq!(
+        outcome,
+        DeviceMergeOutcome {
+            merged: vec![MergedDevices {
+                kept: "second".to_string(),
+                removed: vec!["first".to_string()],
+            }],
+            suggested: vec![MergedDevices {
+                kept: "second".to_string(),
+                removed: vec!["legacy".to_string()],
+            }],
+        }
+    );
+    let mut ids: Vec<_> = devices
+        .devices
+        .iter()
+        .map(|d| d.device_id.as_str())
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec!["laptop", "legacy", "second"]);
+
+    // Once confirmed the legacy entry is merged too
+    let outcome = devices.merge_duplicates(None, &["legacy".to_string()]);
+    assert_eq!(
+        outcome.merged,
+        vec![MergedDevices {
+            kept: "second".to_string(),
+            removed: vec!["legacy".to_string()],
+        }]
+    );
+    assert!(outcome.suggested.is_empty());
+    assert_eq!(devices.devices.len(), 2);
+
+    // Two legacy entries with the same name stay apart until confirmed
+    let mut devices = UserDevices {
+        devices: vec![
+            device("one", "Windows PC", 10, None),
+            device("two", "Windows PC", 20, None),
+        ],
+    };
+    let outcome = devices.merge_duplicates(None, &[]);
+    assert!(outcome.merged.is_empty());
+    assert_eq!(
+        outcome.suggested,
+        vec![MergedDevices {
+            kept: "two".to_string(),
+            removed: vec!["one".to_string()],
+        }]
+    );
+    assert_eq!(devices.devices.len(), 2);
+}
+
+#[test]
+fn test_merge_keeps_the_calling_device() {
+    let mut devices = UserDevices {
+        devices: vec![
+            device("active", "Windows PC", 10, None),
+            device("stale", "Windows PC", 50, Some("aa")),
+        ],
+    };
+    assert_eq!(
+        devices
+            .merge_duplicates(Some("active"), &["active".to_string()])
+            .merged,
+        vec![MergedDevices {
+            kept: "active".to_string(),
+            removed: vec!["stale".to_string()],
+        }]
+    );
+    assert_eq!(devices.devices[0].last_seen, 50);
+    assert_eq!(devices.devices[0].fingerprint.as_deref(), Some("aa"));
+}
@@ -0,0 +1,60 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use crosssave_selfhost_server::{lifecycle, routes, storage::S3Client};
+use tower::ServiceExt; // for oneshot
+
+async fn create_app() -> axum::Router {
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+
+    routes::create_router(client)
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_liveness_and_readiness_split() {
+    let app = create_app().await;
+
+    let response = app.clone().oneshot(get("/healthz")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Not marked ready yet, so readiness fails without touching storage
+    let response = app.clone().oneshot(get("/readyz")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // Draining keeps the process alive but not ready
+    lifecycle::mark_ready();
+    lifecycle::begin_shutdown();
+    assert!(!lifecycle::is_ready());
+    let response = app.clone().oneshot(get("/readyz")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let response = app.oneshot(get("/healthz")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_health_reports_capabilities() {
+    let app = create_app().await;
+
+    let response = app.oneshot(get("/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["capabilities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|capability| capability == "zstd"));
+}
@@ -0,0 +1,43 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use crosssave_selfhost_server::{routes, storage::S3Client, telemetry};
+use tower::ServiceExt; // for oneshot
+
+async fn create_app() -> axum::Router {
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+
+    routes::create_router(client)
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_count_requests_by_route() {
+    telemetry::install().expect("install recorder");
+    let app = create_app().await;
+
+    let response = app.clone().oneshot(get("/health")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(get("/metrics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    assert!(body.contains("http_requests_total"));
+    assert!(body.contains("route=\"/health\""));
+    assert!(body.contains("http_request_duration_seconds_bucket"));
+    assert!(!body.contains("route=\"/metrics\""));
+}
@@ -0,0 +1,57 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use crosssave_selfhost_server::{routes, storage::S3Client};
+use tower::ServiceExt; // for oneshot
+
+async fn test_app() -> Router {
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+    routes::create_router(client)
+}
+
+#[tokio::test]
+async fn test_save_exists_requires_auth() {
+    let sha256 = "a".repeat(64);
+    let response = test_app()
+        .await
+        .oneshot(
+            Request::builder()
+                .uri(format!("/save/exists?game_id=psx-crash&sha256={sha256}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_link_version_requires_auth() {
+    let response = test_app()
+        .await
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/save/link")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    r#"{"game_id":"psx-crash","version_id":"v2","source_version_id":"v1","sha256":"00"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
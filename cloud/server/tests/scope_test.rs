@@ -0,0 +1,93 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use crosssave_selfhost_server::{
+    auth::{jwt, sign_jwt},
+    config::ServerConfig,
+    routes,
+    storage::S3Client,
+    types::{requested_user_scopes, Claims, Scope},
+};
+use serde_json::Value;
+use tower::ServiceExt; // for oneshot
+
+async fn post(app: &Router, uri: &str, token: &str, body: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("Authorization", format!("Bearer {token}"))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_requested_scopes_narrow_a_session() {
+    assert_eq!(
+        requested_user_scopes(&[Scope::Read]),
+        Some(vec![Scope::Read])
+    );
+    assert_eq!(
+        requested_user_scopes(&[Scope::Write, Scope::Read]),
+        Some(vec![Scope::Read, Scope::Write])
+    );
+    // Nothing a session may hold, or more than it may
+    assert_eq!(requested_user_scopes(&[]), None);
+    assert_eq!(requested_user_scopes(&[Scope::Admin]), None);
+    assert_eq!(requested_user_scopes(&[Scope::Read, Scope::Admin]), None);
+}
+
+// Config lives in process-wide state, so the routes are checked in one test
+#[tokio::test]
+async fn test_read_only_token_is_rejected_on_write_routes() {
+    std::env::set_var("JWT_SECRET", "test-jwt-secret");
+    jwt::init_jwt(ServerConfig::from_env().unwrap());
+
+    let client = S3Client::new(
+        "http://localhost:9000",
+        "us-east-1",
+        "minioadmin",
+        "minioadmin",
+        "test-bucket",
+    )
+    .await
+    .expect("Failed to create test client");
+    let app = routes::create_router(client);
+
+    let token = sign_jwt(&Claims {
+        user_id: "user".to_string(),
+        device_id: None,
+        exp: chrono::Utc::now().timestamp() + 60,
+        scopes: requested_user_scopes(&[Scope::Read]).unwrap(),
+        jti: None,
+    })
+    .unwrap();
+
+    let save = r#"{"game_id":"psx-crash","version_id":"v1_abcdef","pinned":true}"#;
+    for uri in [
+        "/save/delete",
+        "/save/pin",
+        "/save/notify-upload",
+        "/share/grant",
+    ] {
+        let (status, body) = post(&app, uri, &token, save).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+        assert_eq!(body["error"], "insufficient_scope", "{uri}");
+        assert_eq!(body["required"], "write", "{uri}");
+    }
+
+    let (status, body) = post(&app, "/device/remove", &token, r#"{"device_id":"d"}"#).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["required"], "device-manage");
+}
@@ -1,5 +1,7 @@
 use crosssave_selfhost_server::validation::{
-    validate_device_id, validate_email, validate_game_id, validate_version_id,
+    validate_archive_path, validate_device_id, validate_email, validate_fingerprint,
+    validate_game_id, validate_note, validate_object_prefix, validate_parent_version_id,
+    validate_tags, validate_version_id,
 };
 
 #[test]
@@ -22,6 +24,13 @@ fn test_validate_device_id() {
     assert!(validate_device_id(&None)); // Optional is valid
 }
 
+#[test]
+fn test_validate_fingerprint() {
+    assert!(validate_fingerprint(&Some("ab".repeat(32))));
+    assert!(!validate_fingerprint(&Some("not-a-digest".to_string())));
+    assert!(validate_fingerprint(&None));
+}
+
 #[test]
 fn test_validate_game_id() {
     assert!(validate_game_id("game123"));
@@ -39,3 +48,49 @@ fn test_validate_version_id() {
     assert!(!validate_version_id("a".repeat(257).as_str())); // Too long (>256)
                                                              // assert!(!validate_version_id("invalid/char")); // Regex check not implemented yet
 }
+
+#[test]
+fn test_validate_parent_version_id() {
+    assert!(validate_parent_version_id(&None, "v2"));
+    assert!(validate_parent_version_id(&Some("v1".to_string()), "v2"));
+    assert!(!validate_parent_version_id(&Some("v2".to_string()), "v2")); // Own parent
+    assert!(!validate_parent_version_id(&Some("".to_string()), "v2"));
+}
+
+#[test]
+fn test_validate_note() {
+    assert!(validate_note(&None));
+    assert!(validate_note(&Some("before final boss".to_string())));
+    assert!(!validate_note(&Some("a".repeat(501)))); // Too long (>500)
+}
+
+#[test]
+fn test_validate_tags() {
+    assert!(validate_tags(&[]));
+    assert!(validate_tags(&["boss".to_string(), "100%".to_string()]));
+    assert!(!validate_tags(&[" ".to_string()])); // Blank tag
+    assert!(!validate_tags(&["a".repeat(33)])); // Too long (>32)
+    assert!(!validate_tags(&vec!["tag".to_string(); 17])); // Too many (>16)
+}
+
+#[test]
+fn test_validate_object_prefix() {
+    assert!(validate_object_prefix(""));
+    assert!(validate_object_prefix("saves/"));
+    assert!(validate_object_prefix("saves/my-game/"));
+    assert!(!validate_object_prefix("/saves/")); // Absolute
+    assert!(!validate_object_prefix("saves/../../other-user/")); // Parent segment
+    assert!(!validate_object_prefix("saves\\game")); // Backslash
+    assert!(!validate_object_prefix(&"a".repeat(513))); // Too long (>512)
+}
+
+#[test]
+fn test_validate_archive_path() {
+    assert!(validate_archive_path("save.srm"));
+    assert!(validate_archive_path("GC/USA/Card A/save.gci"));
+    assert!(!validate_archive_path(""));
+    assert!(!validate_archive_path("/etc/passwd"));
+    assert!(!validate_archive_path("saves/../../evil.srm"));
+    assert!(!validate_archive_path("saves\\save.srm"));
+    assert!(!validate_archive_path("a".repeat(513).as_str()));
+}
@@ -9,6 +9,11 @@ export interface VersionMetadataEntry {
   emulator_id?: string;
   device_id?: string;
   timestamp: number;
+  parent_version_id?: string;
+  /** The emulator encrypted the saves; clients treat them as opaque */
+  encrypted?: boolean;
+  /** Refused by `/save/delete` until unpinned through `/save/pin` */
+  pinned?: boolean;
 }
 
 export interface UserSaveMetadata {
@@ -14,6 +14,22 @@ export function getSaveObjectKey(userId: string, gameId: string, versionId: stri
   return `${getUserBaseKey(userId)}saves/${gameId}/${versionId}.zip`;
 }
 
+/** Signed client release metadata, published by the operator */
+export function getClientReleaseKey(): string {
+  return "client/release.json";
+}
+
+export function getConsumedWorkerTokensKey(userId: string): string {
+  return `${getUserBaseKey(userId)}worker_tokens.json`;
+}
+
+interface ConsumedWorkerTokens {
+  tokens: Array<{ jti: string; exp: number }>;
+}
+
+/** Attempts at recording a token before giving up on a list that keeps changing */
+const CONSUME_ATTEMPTS = 5;
+
 export async function readJson<T>(bucket: R2Bucket, key: string): Promise<T | null> {
   const object = await bucket.get(key);
   if (!object) {
@@ -60,3 +76,45 @@ export async function ensureUserScaffold(bucket: R2Bucket, userId: string): Prom
     await writeJson(bucket, devicesKey, devices);
   }
 }
+
+/** Whether a worker token ID is already in the user's consumed list */
+export async function workerTokenConsumed(bucket: R2Bucket, userId: string, jti: string): Promise<boolean> {
+  const consumed = await readJson<ConsumedWorkerTokens>(bucket, getConsumedWorkerTokensKey(userId));
+  return (consumed?.tokens ?? []).some((token) => token.jti === jti);
+}
+
+/**
+ * Record a worker token ID as used, dropping expired entries. Returns false
+ * if it was already consumed. The write only lands if the list is unchanged
+ * since it was read, so concurrent notifies can't drop each other's entries.
+ */
+export async function consumeWorkerToken(
+  bucket: R2Bucket,
+  userId: string,
+  jti: string,
+  exp: number
+): Promise<boolean> {
+  const key = getConsumedWorkerTokensKey(userId);
+  for (let attempt = 0; attempt < CONSUME_ATTEMPTS; attempt++) {
+    const object = await bucket.get(key);
+    const consumed = object ? await object.json<ConsumedWorkerTokens>() : { tokens: [] };
+    const now = Math.floor(Date.now() / 1000);
+    const tokens = (consumed.tokens ?? []).filter((token) => token.exp >= now);
+    if (tokens.some((token) => token.jti === jti)) {
+      return false;
+    }
+    tokens.push({ jti, exp });
+
+    // The first write has no etag to compare against
+    const written = await bucket.put(key, JSON.stringify({ tokens }), {
+      httpMetadata: {
+        contentType: "application/json"
+      },
+      onlyIf: object ? { etagMatches: object.etag } : undefined
+    });
+    if (written) {
+      return true;
+    }
+  }
+  throw new Error("consumed worker token list kept changing");
+}
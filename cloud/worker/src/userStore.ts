@@ -14,12 +14,20 @@ export interface DeviceInfo {
   platform: string;
   last_seen: number;
   device_name: string;
+  /** SHA-256 of the machine, the same for every install on it */
+  fingerprint?: string;
 }
 
 export interface DevicesEntry {
   devices: DeviceInfo[];
 }
 
+/** Devices folded into one by `mergeDuplicateDevices` */
+export interface MergedDevices {
+  kept: string;
+  removed: string[];
+}
+
 interface EmailIndex {
   by_email: Record<string, string>;
 }
@@ -75,6 +83,7 @@ export async function loadUserDevices(env: { CROSSSAVE_R2: R2Bucket }, userId: s
       platform: device.platform || "unknown",
       last_seen: device.last_seen,
       device_name: device.device_name?.trim() || "Unknown device",
+      ...(device.fingerprint ? { fingerprint: device.fingerprint } : {}),
     }));
     const needsSave = existing.devices.some((device, index) => {
       const normalized = normalizedDevices[index];
@@ -117,3 +126,106 @@ export async function updateLastSeen(
     await saveUserDevices(env, userId, devices);
   }
 }
+
+/**
+ * Add `device` or refresh the entry with its id. When the client says it is a
+ * `newInstall`, a new id whose fingerprint is already listed takes over that
+ * entry and the id it had is returned. Other installs on the same machine
+ * share the fingerprint, so an established one is added next to them.
+ */
+export function upsertDevice(
+  entry: DevicesEntry,
+  device: DeviceInfo,
+  newInstall: boolean
+): { added: boolean; replaced?: string } {
+  const existing = entry.devices.find((d) => d.device_id === device.device_id);
+  if (existing) {
+    existing.platform = device.platform;
+    existing.last_seen = device.last_seen;
+    existing.device_name = device.device_name;
+    if (device.fingerprint) {
+      existing.fingerprint = device.fingerprint;
+    }
+    return { added: false };
+  }
+
+  const index =
+    newInstall && device.fingerprint
+      ? entry.devices.findIndex((d) => d.fingerprint === device.fingerprint)
+      : -1;
+  if (index >= 0) {
+    const replaced = entry.devices[index].device_id;
+    entry.devices[index] = device;
+    return { added: true, replaced };
+  }
+
+  entry.devices.push(device);
+  return { added: true };
+}
+
+/** Entry to keep from a group: `keep` when it is there, otherwise the most recently seen */
+function keptDevice(group: DeviceInfo[], keep?: string): DeviceInfo {
+  return (
+    group.find((d) => d.device_id === keep) ??
+    group.reduce((a, b) => (b.last_seen > a.last_seen ? b : a))
+  );
+}
+
+/**
+ * Fold entries for the same machine into one. Entries sharing a fingerprint
+ * are merged right away; entries without one that have the same platform and
+ * name as another are only merged when their id is in `confirmed`, and are
+ * suggested otherwise. `keep` survives its group, otherwise the most recently
+ * seen entry does.
+ */
+export function mergeDuplicateDevices(
+  entry: DevicesEntry,
+  keep: string | undefined,
+  confirmed: string[]
+): { merged: MergedDevices[]; suggested: MergedDevices[] } {
+  const groups: DeviceInfo[][] = [];
+  for (const device of entry.devices.filter((d) => d.fingerprint)) {
+    const group = groups.find((g) => g[0].fingerprint === device.fingerprint);
+    if (group) {
+      group.push(device);
+    } else {
+      groups.push([device]);
+    }
+  }
+  for (const device of entry.devices.filter((d) => !d.fingerprint)) {
+    const group = groups.find((g) =>
+      g.some((d) => d.platform === device.platform && d.device_name === device.device_name)
+    );
+    if (group) {
+      group.push(device);
+    } else {
+      groups.push([device]);
+    }
+  }
+
+  const merged: MergedDevices[] = [];
+  const suggested: MergedDevices[] = [];
+  entry.devices = groups.flatMap((group) => {
+    const certain = group.filter((d) => d.fingerprint || confirmed.includes(d.device_id));
+    const unconfirmed = group.filter((d) => !certain.includes(d));
+
+    let kept: DeviceInfo | undefined;
+    if (certain.length > 0) {
+      kept = keptDevice(certain, keep);
+      const removed = certain.filter((d) => d !== kept);
+      if (removed.length > 0) {
+        kept.last_seen = Math.max(...certain.map((d) => d.last_seen));
+        kept.fingerprint ??= removed.find((d) => d.fingerprint)?.fingerprint;
+        merged.push({ kept: kept.device_id, removed: removed.map((d) => d.device_id) });
+      }
+    }
+
+    const keptId = (kept ?? keptDevice(unconfirmed, keep)).device_id;
+    const maybe = unconfirmed.filter((d) => d.device_id !== keptId);
+    if (maybe.length > 0) {
+      suggested.push({ kept: keptId, removed: maybe.map((d) => d.device_id) });
+    }
+    return kept ? [kept, ...unconfirmed] : unconfirmed;
+  });
+  return { merged, suggested };
+}
@@ -7,6 +7,30 @@ export function jsonResponse(data: unknown, init: ResponseInit = {}): Response {
   return new Response(JSON.stringify(data), { ...init, headers });
 }
 
+/**
+ * JSON response tagged with a hash of its body, for listings the client
+ * polls. A request whose `If-None-Match` names the tag gets an empty 304.
+ */
+export async function etagJsonResponse(request: Request, data: unknown): Promise<Response> {
+  const body = JSON.stringify(data);
+  const etag = `"${(await computeSha256(body)).slice(0, 32)}"`;
+  const headers = { etag, "cache-control": "private, no-cache" };
+  if (matchesEtag(request.headers.get("if-none-match"), etag)) {
+    return new Response(null, { status: 304, headers });
+  }
+  return new Response(body, { headers: { ...headers, "content-type": "application/json" } });
+}
+
+function matchesEtag(ifNoneMatch: string | null, etag: string): boolean {
+  if (!ifNoneMatch) {
+    return false;
+  }
+  return ifNoneMatch
+    .split(",")
+    .map((tag) => tag.trim().replace(/^W\//, ""))
+    .some((tag) => tag === "*" || tag === etag);
+}
+
 export function errorResponse(status: number, message: string): Response {
   return jsonResponse({ error: message }, { status });
 }
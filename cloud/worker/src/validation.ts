@@ -1,5 +1,6 @@
 const MAX_FILE_COUNT = 200;
 const MAX_SIZE_BYTES = 2 * 1024 * 1024 * 1024; // 2 GB
+const MAX_ARCHIVE_PATH_LENGTH = 512;
 
 export function validateEmail(email: string): boolean {
   return /^[^\s@]+@[^\s@]+\.[^\s@]+$/.test(email.trim());
@@ -23,6 +24,10 @@ export function validateSha256(hash: string): boolean {
   return /^[a-fA-F0-9]{64}$/.test(hash.trim());
 }
 
+export function validateFingerprint(fingerprint: string | undefined): boolean {
+  return fingerprint === undefined || validateSha256(fingerprint);
+}
+
 export function validateFileList(list: unknown): string[] | null {
   if (!Array.isArray(list)) {
     return null;
@@ -47,3 +52,10 @@ export function validateFileList(list: unknown): string[] | null {
 export function validateSizeBytes(sizeBytes: number): boolean {
   return Number.isFinite(sizeBytes) && sizeBytes > 0 && sizeBytes <= MAX_SIZE_BYTES;
 }
+
+// A path inside a save archive, relative and with forward slashes only
+export function validateArchivePath(path: string): boolean {
+  if (!path || path.length > MAX_ARCHIVE_PATH_LENGTH) return false;
+  if (path.startsWith("/") || path.includes("\\")) return false;
+  return path.split("/").every((segment) => segment.length > 0 && segment !== "..");
+}
@@ -1,19 +1,25 @@
-import { errorResponse, jsonResponse } from "./utils";
+import { errorResponse, etagJsonResponse, jsonResponse } from "./utils";
 import {
+  consumeWorkerToken,
   ensureUserScaffold,
+  getClientReleaseKey,
   getSaveObjectKey,
   getUserBaseKey,
   getUserMetadataKey,
   readJson,
+  workerTokenConsumed,
   writeJson,
 } from "./storage";
 import { hashPassword, verifyPassword } from "./security";
 import {
+  DeviceInfo,
   getUserByEmail,
   loadUserDevices,
+  mergeDuplicateDevices,
   saveUserDevices,
   saveUserMetadata as saveAccountMetadata,
-  updateLastSeen
+  updateLastSeen,
+  upsertDevice
 } from "./userStore";
 import { signJwt } from "./jwt";
 import { AuthContext, parseAuth } from "./auth";
@@ -22,19 +28,23 @@ import {
   generatePresignedGet,
   loadUserMetadata,
   saveUserMetadata,
-  UserSaveMetadata
+  UserSaveMetadata,
+  VersionMetadataEntry
 } from "./saveMetadata";
 import { requireAccess, requireTurnstile, applySoftRateLimit } from "./middleware";
 import {
+  validateArchivePath,
   validateDeviceId,
   validateEmail,
   validateFileList,
+  validateFingerprint,
   validateGameId,
   validateSha256,
   validateSizeBytes,
   validateVersionId
 } from "./validation";
 import { signWorkerToken, verifyWorkerToken } from "./workerToken";
+import { readZipEntry, ZipEntryError } from "./zipEntry";
 
 interface Env {
   CROSSSAVE_R2: R2Bucket;
@@ -58,6 +68,10 @@ interface UploadPayload {
   file_list: string[];
   emulator_id?: string;
   device_id?: string;
+  /** Version the client made this one from */
+  parent_version_id?: string;
+  encrypted?: boolean;
+  pinned?: boolean;
 }
 
 const SESSION_TTL_SECONDS = 60 * 60 * 24 * 7;
@@ -68,6 +82,9 @@ const ROUTE_RATE_LIMITS: Record<string, { limit: number; window: number }> = {
   "/signup:POST": { limit: 2, window: 10 },
   "/save/upload-url:POST": { limit: 10, window: 10 },
   "/save/notify-upload:POST": { limit: 20, window: 10 },
+  "/save/delete:POST": { limit: 20, window: 10 },
+  "/save/pin:POST": { limit: 20, window: 10 },
+  "/save/latest-batch:POST": { limit: 10, window: 10 },
   "/device/register:POST": { limit: 5, window: 5 },
 };
 
@@ -126,11 +143,23 @@ function parseUploadPayload(body: Record<string, unknown>): UploadPayload | null
       ? body.emulator_id.trim()
       : undefined;
   const deviceId = typeof body.device_id === "string" ? body.device_id.trim() : undefined;
+  const parentVersionId =
+    typeof body.parent_version_id === "string" ? body.parent_version_id.trim() : undefined;
+  const encrypted = body.encrypted === true ? true : undefined;
+  const pinned = body.pinned === true ? true : undefined;
 
   if (!validateGameId(gameIdRaw) || !validateVersionId(versionIdRaw)) {
     return null;
   }
 
+  // A version cannot be its own parent
+  if (
+    parentVersionId !== undefined &&
+    (!validateVersionId(parentVersionId) || parentVersionId === versionIdRaw)
+  ) {
+    return null;
+  }
+
   if (!validateSha256(sha256Raw) || !validateSizeBytes(sizeBytes)) {
     return null;
   }
@@ -153,6 +182,9 @@ function parseUploadPayload(body: Record<string, unknown>): UploadPayload | null
     file_list: sortedFiles,
     emulator_id: emulatorId,
     device_id: deviceId,
+    parent_version_id: parentVersionId,
+    encrypted,
+    pinned,
   };
 }
 
@@ -176,6 +208,7 @@ async function handleSignup(request: Request, env: Env): Promise<Response> {
   const platform = normalizePlatform(typeof body.platform === "string" ? body.platform : undefined);
   const deviceNameInput = typeof body.device_name === "string" ? body.device_name : undefined;
   const deviceName = normalizeDeviceName(deviceNameInput);
+  const fingerprint = typeof body.fingerprint === "string" ? body.fingerprint.trim() : undefined;
 
   if (!email || !validateEmail(email)) {
     return errorResponse(400, "invalid_email");
@@ -188,6 +221,10 @@ async function handleSignup(request: Request, env: Env): Promise<Response> {
     return errorResponse(400, "invalid_device_id");
   }
 
+  if (!validateFingerprint(fingerprint)) {
+    return errorResponse(400, "invalid_fingerprint");
+  }
+
   const existing = await getUserByEmail(env, email);
   if (existing) {
     return errorResponse(400, "email_already_registered");
@@ -207,7 +244,7 @@ async function handleSignup(request: Request, env: Env): Promise<Response> {
   });
 
   const devices = {
-    devices: [] as Array<{ device_id: string; platform: string; last_seen: number; device_name: string }>,
+    devices: [] as DeviceInfo[],
   };
   if (deviceId) {
     devices.devices.push({
@@ -215,6 +252,7 @@ async function handleSignup(request: Request, env: Env): Promise<Response> {
       platform,
       last_seen: now,
       device_name: deviceName,
+      ...(fingerprint ? { fingerprint } : {}),
     });
   }
   await saveUserDevices(env, userId, devices);
@@ -245,6 +283,8 @@ async function handleLogin(request: Request, env: Env): Promise<Response> {
   const platform = normalizePlatform(typeof body.platform === "string" ? body.platform : undefined);
   const deviceNameInput = typeof body.device_name === "string" ? body.device_name : undefined;
   const deviceName = normalizeDeviceName(deviceNameInput);
+  const fingerprint = typeof body.fingerprint === "string" ? body.fingerprint.trim() : undefined;
+  const newInstall = body.new_install === true;
 
   if (!email || !validateEmail(email) || !password) {
     return errorResponse(401, "invalid_credentials");
@@ -254,6 +294,10 @@ async function handleLogin(request: Request, env: Env): Promise<Response> {
     return errorResponse(400, "invalid_device_id");
   }
 
+  if (!validateFingerprint(fingerprint)) {
+    return errorResponse(400, "invalid_fingerprint");
+  }
+
   const user = await getUserByEmail(env, email);
   if (!user) {
     return errorResponse(401, "invalid_credentials");
@@ -267,19 +311,17 @@ async function handleLogin(request: Request, env: Env): Promise<Response> {
   const now = Math.floor(Date.now() / 1000);
   if (deviceId) {
     const devices = await loadUserDevices(env, user.user_id);
-    const existing = devices.devices.find((d) => d.device_id === deviceId);
-    if (existing) {
-      existing.last_seen = now;
-      existing.platform = platform;
-      existing.device_name = deviceName;
-    } else {
-      devices.devices.push({
+    upsertDevice(
+      devices,
+      {
         device_id: deviceId,
         platform,
         last_seen: now,
         device_name: deviceName,
-      });
-    }
+        ...(fingerprint ? { fingerprint } : {}),
+      },
+      newInstall
+    );
     await saveUserDevices(env, user.user_id, devices);
   }
 
@@ -340,6 +382,7 @@ async function handleUploadUrl(
         r2_key: objectKey,
         version_id: payload.version_id,
         exp: now + WORKER_TOKEN_TTL_SECONDS,
+        jti: crypto.randomUUID(),
       },
       env
     );
@@ -429,6 +472,19 @@ async function handleNotifyUpload(
     return errorResponse(401, "invalid_worker_token");
   }
 
+  if (!verified.jti) {
+    return errorResponse(401, "invalid_worker_token");
+  }
+
+  try {
+    if (await workerTokenConsumed(env.CROSSSAVE_R2, auth.user_id, verified.jti)) {
+      return errorResponse(401, "worker_token_used");
+    }
+  } catch (error) {
+    console.error("[worker] failed to load consumed worker tokens", error);
+    return errorResponse(500, "metadata_load_failed");
+  }
+
   const head = await env.CROSSSAVE_R2.head(objectKey);
   if (!head) {
     return errorResponse(404, "upload_missing");
@@ -456,6 +512,9 @@ async function handleNotifyUpload(
     emulator_id: payload.emulator_id,
     device_id: payload.device_id || verified.device_id || auth.device_id,
     timestamp: now,
+    parent_version_id: payload.parent_version_id,
+    encrypted: payload.encrypted,
+    pinned: payload.pinned,
   };
 
   const filtered = metadata.versions.filter((v) => v.version_id !== payload.version_id);
@@ -468,6 +527,17 @@ async function handleNotifyUpload(
     return errorResponse(500, "metadata_save_failed");
   }
 
+  // Recorded only once the version is saved, so a notify that fails before
+  // then can be retried with the same token
+  try {
+    if (!(await consumeWorkerToken(env.CROSSSAVE_R2, auth.user_id, verified.jti, verified.exp))) {
+      return errorResponse(401, "worker_token_used");
+    }
+  } catch (error) {
+    console.error("[worker] failed to record worker token", error);
+    return errorResponse(500, "metadata_save_failed");
+  }
+
   return jsonResponse({ ok: true });
 }
 
@@ -528,6 +598,8 @@ async function handleDownloadUrl(
       file_list: version.file_list,
       emulator_id: version.emulator_id,
       timestamp: version.timestamp,
+      parent_version_id: version.parent_version_id,
+      encrypted: version.encrypted === true,
     };
 
     try {
@@ -551,6 +623,59 @@ async function handleDownloadUrl(
   }
 }
 
+async function handleDownloadFile(
+  request: Request,
+  env: Env,
+  auth: AuthContext
+): Promise<Response> {
+  const body = await parseJsonBody(request);
+  if (!body) {
+    return errorResponse(400, "invalid_json");
+  }
+
+  const payload = parseDownloadPayload(body);
+  const path = typeof body.path === "string" ? body.path : "";
+  if (!payload || !validateArchivePath(path)) {
+    return errorResponse(400, "invalid_payload");
+  }
+
+  let metadata: UserSaveMetadata;
+  try {
+    metadata = await loadUserMetadata(env.CROSSSAVE_R2, auth.user_id);
+  } catch (error) {
+    console.error("[worker] failed to load metadata", error);
+    return errorResponse(500, "metadata_load_failed");
+  }
+
+  const version = metadata.versions.find(
+    (entry) => entry.version_id === payload.version_id && entry.game_id === payload.game_id
+  );
+  if (!version) {
+    return errorResponse(404, "version_not_found");
+  }
+  if (!version.file_list.includes(path)) {
+    return errorResponse(404, "file_not_found");
+  }
+
+  const objectKey = getSaveObjectKey(auth.user_id, payload.game_id, payload.version_id);
+  try {
+    const entry = await readZipEntry(env.CROSSSAVE_R2, objectKey, path);
+    return new Response(entry.data, {
+      headers: {
+        "content-type": "application/octet-stream",
+        "x-crc32": entry.crc32.toString(16).padStart(8, "0"),
+      },
+    });
+  } catch (error) {
+    if (error instanceof ZipEntryError) {
+      const status = error.code === "object_missing" || error.code === "file_not_found" ? 404 : 400;
+      return errorResponse(status, error.code);
+    }
+    console.error("[worker] failed to read archive entry", error);
+    return errorResponse(500, "read_failed");
+  }
+}
+
 async function handleListSaves(
   request: Request,
   env: Env,
@@ -594,13 +719,187 @@ async function handleListSaves(
       device_id: entry.device_id,
       sha256: entry.sha256,
       file_list: Array.isArray(entry.file_list) ? entry.file_list : [],
+      parent_version_id: entry.parent_version_id,
+      encrypted: entry.encrypted === true,
+      pinned: entry.pinned === true,
     }))
     .sort((a, b) => b.timestamp - a.timestamp);
 
-  return jsonResponse({ ok: true, game_id: gameId, versions });
+  return etagJsonResponse(request, { ok: true, game_id: gameId, versions });
+}
+
+/** Most game IDs one batch request may name */
+const MAX_BATCH_GAMES = 10000;
+
+/**
+ * Latest version of each game in one response, so the client's sync loop
+ * does not list every game on each cycle. `game_ids` is optional; without
+ * it every game is returned.
+ */
+async function handleLatestBatch(
+  request: Request,
+  env: Env,
+  auth: AuthContext
+): Promise<Response> {
+  const body = await parseJsonBody(request);
+  if (!body) {
+    return errorResponse(400, "invalid_json");
+  }
+
+  let wanted: Set<string> | null = null;
+  if (body.game_ids !== undefined) {
+    if (
+      !Array.isArray(body.game_ids) ||
+      body.game_ids.length > MAX_BATCH_GAMES ||
+      !body.game_ids.every((id: unknown) => typeof id === "string")
+    ) {
+      return errorResponse(400, "invalid_payload");
+    }
+    wanted = new Set(body.game_ids as string[]);
+  }
+
+  const metadataKey = getUserMetadataKey(auth.user_id);
+  const head = await env.CROSSSAVE_R2.head(metadataKey);
+  if (!head) {
+    return jsonResponse({ ok: true, versions: [] });
+  }
+
+  let metadata: UserSaveMetadata;
+  try {
+    metadata = await loadUserMetadata(env.CROSSSAVE_R2, auth.user_id);
+  } catch (error) {
+    console.error("[worker] failed to parse metadata", error);
+    return errorResponse(500, "metadata_corrupted");
+  }
+
+  const latest = new Map<string, VersionMetadataEntry>();
+  for (const entry of metadata.versions) {
+    if (!entry.game_id || (wanted && !wanted.has(entry.game_id))) {
+      continue;
+    }
+    const current = latest.get(entry.game_id);
+    if (!current || entry.timestamp > current.timestamp) {
+      latest.set(entry.game_id, entry);
+    }
+  }
+
+  const versions = Array.from(latest.values())
+    .map((entry) => ({
+      version_id: entry.version_id,
+      game_id: entry.game_id,
+      size_bytes: entry.size_bytes,
+      timestamp: entry.timestamp,
+      device_id: entry.device_id,
+      sha256: entry.sha256,
+      file_list: Array.isArray(entry.file_list) ? entry.file_list : [],
+      parent_version_id: entry.parent_version_id,
+      encrypted: entry.encrypted === true,
+      pinned: entry.pinned === true,
+    }))
+    .sort((a, b) => a.game_id.localeCompare(b.game_id));
+
+  return etagJsonResponse(request, { ok: true, versions });
+}
+
+async function handleDeleteSave(
+  request: Request,
+  env: Env,
+  auth: AuthContext
+): Promise<Response> {
+  const body = await parseJsonBody(request);
+  if (!body) {
+    return errorResponse(400, "invalid_json");
+  }
+
+  const payload = parseDownloadPayload(body);
+  if (!payload) {
+    return errorResponse(400, "invalid_payload");
+  }
+
+  let metadata: UserSaveMetadata;
+  try {
+    metadata = await loadUserMetadata(env.CROSSSAVE_R2, auth.user_id);
+  } catch (error) {
+    console.error("[worker] failed to load metadata", error);
+    return errorResponse(500, "metadata_load_failed");
+  }
+
+  const matches = (entry: { version_id: string; game_id: string }) =>
+    entry.version_id === payload.version_id && entry.game_id === payload.game_id;
+  const version = metadata.versions.find(matches);
+  if (!version) {
+    return errorResponse(404, "version_not_found");
+  }
+
+  // Pinned versions must be unpinned before they can be deleted
+  if (version.pinned) {
+    return errorResponse(409, "version_pinned");
+  }
+
+  const objectKey = getSaveObjectKey(auth.user_id, payload.game_id, payload.version_id);
+  try {
+    await env.CROSSSAVE_R2.delete(objectKey);
+  } catch (error) {
+    console.error("[worker] failed to delete save object", error);
+    return errorResponse(500, "delete_failed");
+  }
+
+  metadata.versions = metadata.versions.filter((entry) => !matches(entry));
+  try {
+    await saveUserMetadata(env.CROSSSAVE_R2, auth.user_id, metadata);
+  } catch (error) {
+    console.error("[worker] failed to save metadata", error);
+    return errorResponse(500, "metadata_save_failed");
+  }
+
+  return jsonResponse({ ok: true });
+}
+
+async function handlePinSave(
+  request: Request,
+  env: Env,
+  auth: AuthContext
+): Promise<Response> {
+  const body = await parseJsonBody(request);
+  if (!body) {
+    return errorResponse(400, "invalid_json");
+  }
+
+  const payload = parseDownloadPayload(body);
+  if (!payload || typeof body.pinned !== "boolean") {
+    return errorResponse(400, "invalid_payload");
+  }
+
+  let metadata: UserSaveMetadata;
+  try {
+    metadata = await loadUserMetadata(env.CROSSSAVE_R2, auth.user_id);
+  } catch (error) {
+    console.error("[worker] failed to load metadata", error);
+    return errorResponse(500, "metadata_load_failed");
+  }
+
+  const version = metadata.versions.find(
+    (entry) => entry.version_id === payload.version_id && entry.game_id === payload.game_id
+  );
+  if (!version) {
+    return errorResponse(404, "version_not_found");
+  }
+
+  if ((version.pinned === true) !== body.pinned) {
+    version.pinned = body.pinned || undefined;
+    try {
+      await saveUserMetadata(env.CROSSSAVE_R2, auth.user_id, metadata);
+    } catch (error) {
+      console.error("[worker] failed to save metadata", error);
+      return errorResponse(500, "metadata_save_failed");
+    }
+  }
+
+  return jsonResponse({ ok: true });
 }
 
 async function handleListGames(
+  request: Request,
   env: Env,
   auth: AuthContext
 ): Promise<Response> {
@@ -627,7 +926,7 @@ async function handleListGames(
   }
 
   const games = Array.from(gameIds).sort();
-  return jsonResponse({ ok: true, games });
+  return etagJsonResponse(request, { ok: true, games });
 }
 
 async function handleRegisterDevice(request: Request, env: Env, auth: AuthContext): Promise<Response> {
@@ -640,32 +939,73 @@ async function handleRegisterDevice(request: Request, env: Env, auth: AuthContex
   const platform = normalizePlatform(typeof body.platform === "string" ? body.platform : undefined);
   const deviceNameInput = typeof body.device_name === "string" ? body.device_name : undefined;
   const deviceName = normalizeDeviceName(deviceNameInput);
+  const fingerprint = typeof body.fingerprint === "string" ? body.fingerprint.trim() : undefined;
+  const newInstall = body.new_install === true;
 
   if (!deviceId || !validateDeviceId(deviceId)) {
     return errorResponse(400, "invalid_device_id");
   }
 
+  if (!validateFingerprint(fingerprint)) {
+    return errorResponse(400, "invalid_fingerprint");
+  }
+
   const devices = await loadUserDevices(env, auth.user_id);
   const now = Math.floor(Date.now() / 1000);
-  const existing = devices.devices.find((d) => d.device_id === deviceId);
-  if (existing) {
-    existing.platform = platform;
-    existing.last_seen = now;
-    existing.device_name = deviceName;
-  } else {
-    devices.devices.push({ device_id: deviceId, platform, last_seen: now, device_name: deviceName });
-  }
+  upsertDevice(
+    devices,
+    {
+      device_id: deviceId,
+      platform,
+      last_seen: now,
+      device_name: deviceName,
+      ...(fingerprint ? { fingerprint } : {}),
+    },
+    newInstall
+  );
 
   await saveUserDevices(env, auth.user_id, devices);
   const device = devices.devices.find((d) => d.device_id === deviceId)!;
   return jsonResponse({ ok: true, device });
 }
 
+async function handleCheckDevice(request: Request, env: Env, auth: AuthContext): Promise<Response> {
+  const body = await parseJsonBody(request);
+  if (!body) {
+    return errorResponse(400, "invalid_json");
+  }
+
+  const deviceId = typeof body.device_id === "string" ? body.device_id.trim() : "";
+  if (!deviceId || !validateDeviceId(deviceId)) {
+    return errorResponse(400, "invalid_device_id");
+  }
+
+  const devices = await loadUserDevices(env, auth.user_id);
+  const registered = devices.devices.some((d) => d.device_id === deviceId);
+  return jsonResponse({ ok: true, registered });
+}
+
 async function handleListDevices(env: Env, auth: AuthContext): Promise<Response> {
   const devices = await loadUserDevices(env, auth.user_id);
   return jsonResponse({ ok: true, devices: devices.devices });
 }
 
+async function handleMergeDevices(request: Request, env: Env, auth: AuthContext): Promise<Response> {
+  const body = (await parseJsonBody(request)) ?? {};
+  const dryRun = body.dry_run === true;
+  const keep = auth.device_id ?? (typeof body.keep === "string" ? body.keep.trim() : undefined);
+  const confirm = Array.isArray(body.confirm)
+    ? body.confirm.filter((id: unknown): id is string => typeof id === "string")
+    : [];
+
+  const devices = await loadUserDevices(env, auth.user_id);
+  const { merged, suggested } = mergeDuplicateDevices(devices, keep, confirm);
+  if (!dryRun && merged.length > 0) {
+    await saveUserDevices(env, auth.user_id, devices);
+  }
+  return jsonResponse({ ok: true, merged, suggested, devices: devices.devices });
+}
+
 async function handleRemoveDevice(request: Request, env: Env, auth: AuthContext): Promise<Response> {
   const body = await parseJsonBody(request);
   if (!body) {
@@ -691,6 +1031,18 @@ async function handleRemoveDevice(request: Request, env: Env, auth: AuthContext)
   return jsonResponse({ ok: true });
 }
 
+/**
+ * Release metadata for the clients' update check. It is served as stored;
+ * clients verify its signature themselves.
+ */
+async function handleClientRelease(env: Env): Promise<Response> {
+  const release = await readJson<unknown>(env.CROSSSAVE_R2, getClientReleaseKey());
+  if (!release) {
+    return errorResponse(404, "no_release");
+  }
+  return jsonResponse(release);
+}
+
 export default {
   async fetch(request: Request, env: Env, _ctx: ExecutionContext): Promise<Response> {
     const url = new URL(request.url);
@@ -709,6 +1061,10 @@ export default {
       }
     }
 
+    if (path === "/client/release" && request.method === "GET") {
+      return handleClientRelease(env);
+    }
+
     const accessCheck = await requireAccess(request, env);
     if (accessCheck) {
       return accessCheck;
@@ -739,6 +1095,14 @@ export default {
       return handleRegisterDevice(request, env, auth);
     }
 
+    if (path === "/device/check" && request.method === "POST") {
+      const auth = await requireAuth(env, request);
+      if (!auth) {
+        return errorResponse(401, "unauthorized");
+      }
+      return handleCheckDevice(request, env, auth);
+    }
+
     if (path === "/save/upload-url" && request.method === "POST") {
       const auth = await requireAuth(env, request);
       if (!auth) {
@@ -767,6 +1131,14 @@ export default {
       return handleDownloadUrl(request, env, auth);
     }
 
+    if (path === "/save/download-file" && request.method === "POST") {
+      const auth = await requireAuth(env, request);
+      if (!auth) {
+        return errorResponse(401, "unauthorized");
+      }
+      return handleDownloadFile(request, env, auth);
+    }
+
     if (path === "/save/list" && request.method === "POST") {
       const auth = await requireAuth(env, request);
       if (!auth) {
@@ -775,6 +1147,30 @@ export default {
       return handleListSaves(request, env, auth);
     }
 
+    if (path === "/save/latest-batch" && request.method === "POST") {
+      const auth = await requireAuth(env, request);
+      if (!auth) {
+        return errorResponse(401, "unauthorized");
+      }
+      return handleLatestBatch(request, env, auth);
+    }
+
+    if (path === "/save/delete" && request.method === "POST") {
+      const auth = await requireAuth(env, request);
+      if (!auth) {
+        return errorResponse(401, "unauthorized");
+      }
+      return handleDeleteSave(request, env, auth);
+    }
+
+    if (path === "/save/pin" && request.method === "POST") {
+      const auth = await requireAuth(env, request);
+      if (!auth) {
+        return errorResponse(401, "unauthorized");
+      }
+      return handlePinSave(request, env, auth);
+    }
+
     if (path === "/device/list" && request.method === "GET") {
       const auth = await requireAuth(env, request);
       if (!auth) {
@@ -791,12 +1187,20 @@ export default {
       return handleRemoveDevice(request, env, auth);
     }
 
+    if (path === "/device/merge" && request.method === "POST") {
+      const auth = await requireAuth(env, request);
+      if (!auth) {
+        return errorResponse(401, "unauthorized");
+      }
+      return handleMergeDevices(request, env, auth);
+    }
+
     if (path === "/save/games" && request.method === "POST") {
       const auth = await requireAuth(env, request);
       if (!auth) {
         return errorResponse(401, "unauthorized");
       }
-      return handleListGames(env, auth);
+      return handleListGames(request, env, auth);
     }
 
     return errorResponse(404, "Not implemented");
@@ -6,6 +6,10 @@ export interface WorkerTokenPayload {
   r2_key: string;
   version_id: string;
   exp: number;
+  /** Unique token ID, consumed by the first notify-upload that uses it */
+  jti?: string;
+  /** Encoding the upload URL was signed for; notify-upload must match it */
+  encoding?: "zstd";
 }
 
 interface WorkerTokenEnv {
@@ -77,6 +81,13 @@ export async function verifyWorkerToken(token: string, env: WorkerTokenEnv): Pro
     if (!payload.exp || !payload.user_id || !payload.r2_key || !payload.version_id) {
       return null;
     }
+    // Rejected like the Rust types reject them (cloud/protocol)
+    if (payload.jti !== undefined && typeof payload.jti !== "string") {
+      return null;
+    }
+    if (payload.encoding !== undefined && payload.encoding !== "zstd") {
+      return null;
+    }
 
     const now = Math.floor(Date.now() / 1000);
     if (payload.exp < now) {
@@ -0,0 +1,113 @@
+// Read a single file out of a zip archive in R2 using ranged reads, so the
+// rest of the archive is never downloaded.
+
+const EOCD_SIGNATURE = 0x06054b50;
+const CENTRAL_SIGNATURE = 0x02014b50;
+const LOCAL_SIGNATURE = 0x04034b50;
+const EOCD_MIN_BYTES = 22;
+const MAX_COMMENT_BYTES = 0xffff;
+const MAX_ENTRY_BYTES = 256 * 1024 * 1024;
+
+export interface ZipEntryData {
+  data: Uint8Array;
+  crc32: number;
+}
+
+export class ZipEntryError extends Error {
+  constructor(public readonly code: "object_missing" | "file_not_found" | "invalid_archive" | "file_too_large") {
+    super(code);
+  }
+}
+
+async function readRange(bucket: R2Bucket, key: string, range: R2Range): Promise<DataView> {
+  const object = await bucket.get(key, { range });
+  if (!object) {
+    throw new ZipEntryError("object_missing");
+  }
+  return new DataView(await object.arrayBuffer());
+}
+
+export async function readZipEntry(bucket: R2Bucket, key: string, path: string): Promise<ZipEntryData> {
+  const head = await bucket.head(key);
+  if (!head) {
+    throw new ZipEntryError("object_missing");
+  }
+  if (head.size < EOCD_MIN_BYTES) {
+    throw new ZipEntryError("invalid_archive");
+  }
+
+  const tail = await readRange(bucket, key, {
+    suffix: Math.min(head.size, EOCD_MIN_BYTES + MAX_COMMENT_BYTES),
+  });
+  let eocd = -1;
+  for (let i = tail.byteLength - EOCD_MIN_BYTES; i >= 0; i--) {
+    if (tail.getUint32(i, true) === EOCD_SIGNATURE) {
+      eocd = i;
+      break;
+    }
+  }
+  if (eocd < 0) {
+    throw new ZipEntryError("invalid_archive");
+  }
+
+  const directorySize = tail.getUint32(eocd + 12, true);
+  const directoryOffset = tail.getUint32(eocd + 16, true);
+  // Zip64 archives mark these as 0xffffffff; save archives never get that big
+  if (directoryOffset === 0xffffffff || directoryOffset + directorySize > head.size) {
+    throw new ZipEntryError("invalid_archive");
+  }
+
+  const directory = await readRange(bucket, key, { offset: directoryOffset, length: directorySize });
+  const decoder = new TextDecoder();
+  let cursor = 0;
+  while (cursor + 46 <= directory.byteLength) {
+    if (directory.getUint32(cursor, true) !== CENTRAL_SIGNATURE) {
+      throw new ZipEntryError("invalid_archive");
+    }
+    const method = directory.getUint16(cursor + 10, true);
+    const crc32 = directory.getUint32(cursor + 16, true);
+    const compressedSize = directory.getUint32(cursor + 20, true);
+    const size = directory.getUint32(cursor + 24, true);
+    const nameLength = directory.getUint16(cursor + 28, true);
+    const extraLength = directory.getUint16(cursor + 30, true);
+    const commentLength = directory.getUint16(cursor + 32, true);
+    const localOffset = directory.getUint32(cursor + 42, true);
+    const name = decoder.decode(
+      new Uint8Array(directory.buffer, directory.byteOffset + cursor + 46, nameLength)
+    );
+    cursor += 46 + nameLength + extraLength + commentLength;
+
+    if (name !== path) {
+      continue;
+    }
+    if (size > MAX_ENTRY_BYTES || compressedSize > MAX_ENTRY_BYTES) {
+      throw new ZipEntryError("file_too_large");
+    }
+
+    const local = await readRange(bucket, key, { offset: localOffset, length: 30 });
+    if (local.getUint32(0, true) !== LOCAL_SIGNATURE) {
+      throw new ZipEntryError("invalid_archive");
+    }
+    const dataOffset = localOffset + 30 + local.getUint16(26, true) + local.getUint16(28, true);
+    if (compressedSize === 0) {
+      return { data: new Uint8Array(0), crc32 };
+    }
+    const raw = await readRange(bucket, key, { offset: dataOffset, length: compressedSize });
+    const bytes = new Uint8Array(raw.buffer, raw.byteOffset, raw.byteLength);
+
+    if (method === 0) {
+      return { data: bytes, crc32 };
+    }
+    if (method === 8) {
+      const inflated = new Blob([bytes]).stream().pipeThrough(new DecompressionStream("deflate-raw"));
+      const data = new Uint8Array(await new Response(inflated).arrayBuffer());
+      if (data.byteLength !== size) {
+        throw new ZipEntryError("invalid_archive");
+      }
+      return { data, crc32 };
+    }
+    throw new ZipEntryError("invalid_archive");
+  }
+
+  throw new ZipEntryError("file_not_found");
+}
@@ -0,0 +1,48 @@
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { test } from "node:test";
+
+import { signWorkerToken, verifyWorkerToken, type WorkerTokenPayload } from "../src/workerToken";
+
+// Shared with the Rust implementation (cloud/protocol/tests/worker_token_vectors.rs)
+const vectors = JSON.parse(
+  readFileSync(new URL("../../protocol/worker_token_vectors.json", import.meta.url), "utf8")
+) as {
+  signing_key: string;
+  rotated_signing_key: string;
+  valid: { name: string; key: "main" | "rotated"; payload: WorkerTokenPayload; token: string }[];
+  invalid: { name: string; token: string }[];
+};
+
+const env = {
+  WORKER_SIGNING_KEY_MAIN: vectors.signing_key,
+  WORKER_SIGNING_KEY_ROTATED: vectors.rotated_signing_key,
+};
+
+test("signing matches shared vectors", async () => {
+  for (const vector of vectors.valid) {
+    const key = vector.key === "rotated" ? vectors.rotated_signing_key : vectors.signing_key;
+    const token = await signWorkerToken(vector.payload, { WORKER_SIGNING_KEY_MAIN: key });
+    assert.equal(token, vector.token, vector.name);
+  }
+});
+
+test("valid shared vectors verify", async () => {
+  for (const vector of vectors.valid) {
+    const payload = await verifyWorkerToken(vector.token, env);
+    assert.deepEqual(payload, vector.payload, vector.name);
+  }
+});
+
+test("invalid shared vectors are rejected", async () => {
+  for (const vector of vectors.invalid) {
+    assert.equal(await verifyWorkerToken(vector.token, env), null, vector.name);
+  }
+});
+
+test("rotated key requires configuration", async () => {
+  const rotated = vectors.valid.find((vector) => vector.key === "rotated");
+  assert.ok(rotated);
+  const payload = await verifyWorkerToken(rotated.token, { WORKER_SIGNING_KEY_MAIN: vectors.signing_key });
+  assert.equal(payload, null);
+});